@@ -9,7 +9,9 @@
 
 const logical_level = 1;
 const relational_level = logical_level + 1;
-const not_level = relational_level + 1;
+const additive_level = relational_level + 1;
+const multiplicative_level = additive_level + 1;
+const not_level = multiplicative_level + 1;
 
 module.exports = grammar({
   name: 'grammar',
@@ -44,7 +46,8 @@ module.exports = grammar({
     module_field: $ => choice(
       $.type,
       $.module_method,
-      $.interceptor
+      $.interceptor,
+      $.job
     ),
     module_method: $ => seq(
       repeat(field("annotation", $.annotation)),
@@ -64,6 +67,14 @@ module.exports = grammar({
       optional(commaSep(field("args", $.argument))),
       ")",
     ),
+    job: $ => seq(
+      repeat(field("annotation", $.annotation)),
+      "job",
+      field("name", $.term),
+      "(",
+      optional(commaSep(field("args", $.argument))),
+      ")",
+    ),
     context: $ => seq(
       repeat(field("annotation", $.annotation)),
       "context",
@@ -88,10 +99,17 @@ module.exports = grammar({
     ),
     annotation_params: $ => choice(
       $.annotation_multiple_params,
-      $.annotation_map_params
+      $.annotation_map_params,
+      $.annotation_mixed_params
     ),
     annotation_multiple_params: $ => commaSep(field("exprs", $.expression)),
     annotation_map_params: $ => commaSep(field("param", $.annotation_map_param)),
+    // A single positional parameter followed by named parameters such as `@deno("x.ts", allowNet=["api.stripe.com"])`
+    annotation_mixed_params: $ => seq(
+      field("expr", $.expression),
+      ",",
+      commaSep(field("param", $.annotation_map_param))
+    ),
     annotation_map_param: $ => seq(field("name", $.term), "=", field("expr", $.expression)),
     argument: $ => seq(
       repeat(field("annotation", $.annotation)),
@@ -112,12 +130,13 @@ module.exports = grammar({
     ),
     field_default_value: $ => choice(
       field("default_value_concrete", $.expression),
-      seq(
+      // Prefer this over a `function_call` expression (such as `now()`)
+      prec(1, seq(
         field("default_value_fn", $.term),
         "(",
         optional(commaSep(field("default_value_fn_args", $.expression))),
         ")"
-      )
+      ))
     ),
     field_type: $ => choice(
       $.optional_field_type,
@@ -128,12 +147,22 @@ module.exports = grammar({
       $.parenthetical,
       prec(1, $.logical_op),
       prec(3, $.relational_op),
+      prec(3, $.arithmetic_op),
       $.selection,
       $.literal_number,
       $.literal_str,
-      $.literal_boolean
+      $.literal_boolean,
+      $.literal_list,
+      $.function_call
     ),
     parenthetical: $ => seq("(", field("expression", $.expression), ")"),
+    // Call to a built-in function such as `env("WEBHOOK_URL")`
+    function_call: $ => seq(
+      field("name", $.term),
+      "(",
+      optional(commaSep(field("args", $.expression))),
+      ")"
+    ),
     selection: $ => choice(
       $.selection_select,
       $.term
@@ -146,6 +175,13 @@ module.exports = grammar({
     selection_element: $ => choice(
       $.term,
       $.hof_call,
+      $.method_call,
+    ),
+    // Call to a method without arguments such as `count()`
+    method_call: $ => seq(
+      field("name", $.term),
+      "(",
+      ")"
     ),
     // High-order function call of the `name(param_name, expr)` such as: 
     // - `some((du) => du.userId == AuthContext.id && du.read)`
@@ -206,12 +242,27 @@ module.exports = grammar({
     relational_in: $ => prec.left(relational_level, seq(
       field("left", $.expression), "in", field("right", $.expression)
     )),
+    arithmetic_op: $ => choice(
+      $.arithmetic_add,
+      $.arithmetic_sub,
+      $.arithmetic_mul,
+    ),
+    arithmetic_add: $ => prec.left(additive_level, seq(
+      field("left", $.expression), "+", field("right", $.expression)
+    )),
+    arithmetic_sub: $ => prec.left(additive_level, seq(
+      field("left", $.expression), "-", field("right", $.expression)
+    )),
+    arithmetic_mul: $ => prec.left(multiplicative_level, seq(
+      field("left", $.expression), "*", field("right", $.expression)
+    )),
     term: $ => /[a-zA-Z_][a-zA-Z0-9_]*/,
     str: $ => /(?:[^"\\]|\\.)*/, // string with escaped quotes
     number: $ => /\d+/,
     literal_str: $ => seq("\"", field("value", $.str), "\""),
     literal_boolean: $ => choice("true", "false"),
     literal_number: $ => field("value", $.number),
+    literal_list: $ => seq("[", commaSep(field("value", $.expression)), "]"),
     comment: $ => token(choice(
       seq('//', /.*/),
       seq(
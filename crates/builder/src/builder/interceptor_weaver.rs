@@ -89,6 +89,12 @@ fn matches(expr: &AstExpr<Typed>, operation_name: &str, operation_kind: Operatio
         AstExpr::StringList(_, _) => {
             panic!("List not supported in interceptor expression")
         }
+        AstExpr::FunctionCall(_) => {
+            panic!("Function call not supported in interceptor expression")
+        }
+        AstExpr::ArithmeticOp(_) => {
+            panic!("Arithmetic not supported in interceptor expression")
+        }
     }
 }
 
@@ -21,9 +21,10 @@ use tree_sitter_c2rust::{Node, Tree, TreeCursor};
 
 use super::{sitter_ffi, span_from_node};
 use crate::ast::ast_types::{
-    AstAnnotation, AstAnnotationParams, AstArgument, AstExpr, AstField, AstFieldDefault,
-    AstFieldDefaultKind, AstFieldType, AstInterceptor, AstMethod, AstModel, AstModelKind,
-    AstModule, AstSystem, FieldSelection, LogicalOp, RelationalOp, Untyped,
+    ArithmeticOp, AstAnnotation, AstAnnotationParams, AstArgument, AstExpr, AstField,
+    AstFieldDefault, AstFieldDefaultKind, AstFieldType, AstInterceptor, AstJob, AstMethod,
+    AstModel, AstModelKind, AstModule, AstSystem, FieldSelection, FunctionCall, LogicalOp,
+    RelationalOp, Untyped,
 };
 use crate::error::ParserError;
 
@@ -245,6 +246,9 @@ fn convert_module(
         interceptors: matching_nodes(node, &mut node.walk(), "interceptor")
             .map(|n| convert_interceptor(n, source, source_span))
             .collect(),
+        jobs: matching_nodes(node, &mut node.walk(), "job")
+            .map(|n| convert_job(n, source, source_span))
+            .collect(),
         annotations,
         base_exofile: filepath.into(),
         span: span_from_node(source_span, node),
@@ -297,6 +301,23 @@ fn convert_interceptor(node: Node, source: &[u8], source_span: Span) -> AstInter
     }
 }
 
+fn convert_job(node: Node, source: &[u8], source_span: Span) -> AstJob<Untyped> {
+    let mut cursor = node.walk();
+
+    AstJob {
+        name: text_child(node, source, "name"),
+        arguments: node
+            .children_by_field_name("args", &mut cursor)
+            .map(|c| convert_argument(c, source, source_span))
+            .collect(),
+        annotations: node
+            .children_by_field_name("annotation", &mut cursor)
+            .map(|c| convert_annotation(c, source, source_span))
+            .collect(),
+        span: span_from_node(source_span, node),
+    }
+}
+
 fn convert_fields(node: Node, source: &[u8], source_span: Span) -> Vec<AstField<Untyped>> {
     let mut cursor = node.walk();
     node.children_by_field_name("field", &mut cursor)
@@ -440,13 +461,7 @@ fn convert_annotation_params(
                 AstAnnotationParams::Single(exprs[0].clone(), first_child_span)
             } else {
                 // try as a string list
-                let string_list = exprs
-                    .iter()
-                    .map(|expr| match expr {
-                        AstExpr::StringLiteral(string, _) => string.clone(),
-                        _ => panic!("Only string literals are allowed in a list currently"),
-                    })
-                    .collect();
+                let string_list = exprs.iter().map(|expr| list_element(expr).0).collect();
 
                 AstAnnotationParams::Single(
                     AstExpr::StringList(string_list, spans),
@@ -455,43 +470,63 @@ fn convert_annotation_params(
             }
         }
         "annotation_map_params" => {
-            let params = first_child
-                .children_by_field_name("param", &mut cursor)
-                .map(|p| (text_child(p, source, "name"), p))
-                .collect::<Vec<_>>();
-
-            let exprs = params
-                .iter()
-                .map(|(name, p)| {
-                    (
-                        name.clone(),
-                        convert_expression(
-                            p.child_by_field_name("expr").unwrap(),
-                            source,
-                            source_span,
-                        ),
-                    )
-                })
-                .collect();
+            let (exprs, spans) = convert_annotation_map_params(first_child, source, source_span);
 
-            let mut spans: HashMap<String, Vec<Span>> = HashMap::new();
+            AstAnnotationParams::Map(exprs, spans)
+        }
+        "annotation_mixed_params" => {
+            let expr_node = first_child.child_by_field_name("expr").unwrap();
+            let expr = convert_expression(expr_node, source, source_span);
+            let expr_span = span_from_node(source_span, expr_node);
 
-            for (name, node) in &params {
-                let span = span_from_node(source_span, *node);
-                match spans.get_mut(name) {
-                    Some(spans) => spans.push(span),
-                    None => {
-                        spans.insert(name.clone(), vec![span]);
-                    }
-                }
-            }
+            let (exprs, spans) = convert_annotation_map_params(first_child, source, source_span);
 
-            AstAnnotationParams::Map(exprs, spans)
+            AstAnnotationParams::Mixed(expr, expr_span, exprs, spans)
         }
         o => panic!("unsupported annotation params kind: {o}"),
     }
 }
 
+fn convert_annotation_map_params(
+    node: Node,
+    source: &[u8],
+    source_span: Span,
+) -> (
+    HashMap<String, AstExpr<Untyped>>,
+    HashMap<String, Vec<Span>>,
+) {
+    let mut cursor = node.walk();
+
+    let params = node
+        .children_by_field_name("param", &mut cursor)
+        .map(|p| (text_child(p, source, "name"), p))
+        .collect::<Vec<_>>();
+
+    let exprs = params
+        .iter()
+        .map(|(name, p)| {
+            (
+                name.clone(),
+                convert_expression(p.child_by_field_name("expr").unwrap(), source, source_span),
+            )
+        })
+        .collect();
+
+    let mut spans: HashMap<String, Vec<Span>> = HashMap::new();
+
+    for (name, node) in &params {
+        let span = span_from_node(source_span, *node);
+        match spans.get_mut(name) {
+            Some(spans) => spans.push(span),
+            None => {
+                spans.insert(name.clone(), vec![span]);
+            }
+        }
+    }
+
+    (exprs, spans)
+}
+
 fn convert_expression(node: Node, source: &[u8], source_span: Span) -> AstExpr<Untyped> {
     assert_eq!(node.kind(), "expression");
     let first_child = node.child(0).unwrap();
@@ -520,10 +555,39 @@ fn convert_expression(node: Node, source: &[u8], source_span: Span) -> AstExpr<U
             let value = first_child.child(0).unwrap().utf8_text(source).unwrap();
             AstExpr::BooleanLiteral(value == "true", source_span)
         }
+        "literal_list" => {
+            let mut cursor = first_child.walk();
+            let (strings, spans) = first_child
+                .children_by_field_name("value", &mut cursor)
+                .map(|node| list_element(&convert_expression(node, source, source_span)))
+                .unzip();
+
+            AstExpr::StringList(strings, spans)
+        }
+        "function_call" => {
+            let name_field = first_child.child_by_field_name("name").unwrap();
+            let name = name_field.utf8_text(source).unwrap().to_string();
+
+            let mut cursor = first_child.walk();
+            let args = first_child
+                .children_by_field_name("args", &mut cursor)
+                .map(|node| convert_expression(node, source, source_span))
+                .collect();
+
+            AstExpr::FunctionCall(FunctionCall {
+                name: Identifier(name, span_from_node(source_span, name_field)),
+                args,
+                span: span_from_node(source_span, first_child),
+                typ: (),
+            })
+        }
         "logical_op" => AstExpr::LogicalOp(convert_logical_op(first_child, source, source_span)),
         "relational_op" => {
             AstExpr::RelationalOp(convert_relational_op(first_child, source, source_span))
         }
+        "arithmetic_op" => {
+            AstExpr::ArithmeticOp(convert_arithmetic_op(first_child, source, source_span))
+        }
         "selection" => AstExpr::FieldSelection(convert_selection(first_child, source, source_span)),
         "parenthetical" => {
             let expression = first_child.child_by_field_name("expression").unwrap();
@@ -533,6 +597,19 @@ fn convert_expression(node: Node, source: &[u8], source_span: Span) -> AstExpr<U
     }
 }
 
+// A list element may be a string literal or a bare identifier (for example, `on=[create, update]`),
+// which we treat as a string
+fn list_element(expr: &AstExpr<Untyped>) -> (String, Span) {
+    match expr {
+        AstExpr::StringLiteral(string, span) => (string.clone(), *span),
+        AstExpr::FieldSelection(FieldSelection::Single(
+            FieldSelectionElement::Identifier(name, span, _),
+            _,
+        )) => (name.clone(), *span),
+        _ => panic!("Only string literals and identifiers are allowed in a list currently"),
+    }
+}
+
 fn convert_logical_op(node: Node, source: &[u8], source_span: Span) -> LogicalOp<Untyped> {
     assert_eq!(node.kind(), "logical_op");
     let first_child = node.child(0).unwrap();
@@ -606,6 +683,29 @@ fn convert_relational_op(node: Node, source: &[u8], source_span: Span) -> Relati
     }
 }
 
+fn convert_arithmetic_op(node: Node, source: &[u8], source_span: Span) -> ArithmeticOp<Untyped> {
+    assert_eq!(node.kind(), "arithmetic_op");
+    let first_child = node.child(0).unwrap();
+
+    let left_expr = Box::new(convert_expression(
+        first_child.child_by_field_name("left").unwrap(),
+        source,
+        source_span,
+    ));
+    let right_expr = Box::new(convert_expression(
+        first_child.child_by_field_name("right").unwrap(),
+        source,
+        source_span,
+    ));
+
+    match first_child.kind() {
+        "arithmetic_add" => ArithmeticOp::Add(left_expr, right_expr, ()),
+        "arithmetic_sub" => ArithmeticOp::Sub(left_expr, right_expr, ()),
+        "arithmetic_mul" => ArithmeticOp::Mul(left_expr, right_expr, ()),
+        o => panic!("unsupported arithmetic op kind: {o}"),
+    }
+}
+
 fn convert_selection(node: Node, source: &[u8], source_span: Span) -> FieldSelection<Untyped> {
     assert_eq!(node.kind(), "selection");
     let first_child = node.child(0).unwrap();
@@ -670,6 +770,16 @@ fn convert_selection_elem(
                 typ: (),
             }
         }
+        "method_call" => {
+            let name_field = first_child.child_by_field_name("name").unwrap();
+            let name = name_field.utf8_text(source).unwrap().to_string();
+
+            FieldSelectionElement::MethodCall {
+                span: span_from_node(source_span, first_child),
+                name: Identifier(name, span_from_node(source_span, name_field)),
+                typ: (),
+            }
+        }
         o => panic!("unsupported selection element kind: {o}"),
     }
 }
@@ -837,4 +947,44 @@ mod tests {
             "access_control_function_with_paren"
         );
     }
+
+    #[multiplatform_test]
+    fn annotation_mixed_params() {
+        let src = r#"
+            @deno("payments.ts", allowNet=["api.stripe.com", "api.example.com"], allowEnv="STRIPE_KEY")
+            module PaymentModule {
+            }
+        "#;
+
+        let mut codemap = CodeMap::new();
+        let file_span = codemap
+            .add_file("input.exo".to_string(), src.to_string())
+            .span;
+        let parsed = parse(src).unwrap();
+        let system = convert_root(
+            parsed.root_node(),
+            src.as_bytes(),
+            file_span,
+            Path::new("input.exo"),
+        )
+        .unwrap();
+
+        let annotation = &system.modules[0].annotations[0];
+        assert_eq!(annotation.name, "deno");
+
+        let AstAnnotationParams::Mixed(expr, _, params, spans) = &annotation.params else {
+            panic!("Expected mixed parameters, got {:?}", annotation.params);
+        };
+        assert!(matches!(expr, AstExpr::StringLiteral(value, _) if value == "payments.ts"));
+        assert!(matches!(
+            &params["allowNet"],
+            AstExpr::StringList(values, _) if values == &["api.stripe.com", "api.example.com"]
+        ));
+        assert!(matches!(
+            &params["allowEnv"],
+            AstExpr::StringLiteral(value, _) if value == "STRIPE_KEY"
+        ));
+        assert_eq!(params.len(), 2);
+        assert_eq!(spans["allowNet"].len(), 1);
+    }
 }
@@ -9,10 +9,14 @@
 
 use std::collections::{HashMap, HashSet};
 
-use crate::ast::ast_types::{AstAnnotation, AstAnnotationParams, Untyped};
+use crate::ast::ast_types::{AstAnnotation, AstAnnotationParams, AstExpr, Untyped};
+use codemap::Span;
 use codemap_diagnostic::{Diagnostic, Level, SpanLabel, SpanStyle};
 use core_model::mapped_arena::MappedArena;
-use core_model_builder::typechecker::{annotation::AnnotationSpec, Typed};
+use core_model_builder::typechecker::{
+    annotation::{AnnotationSpec, MappedAnnotationParamSpec},
+    Typed,
+};
 
 use super::{Scope, Type, TypecheckFrom};
 use crate::util;
@@ -101,61 +105,22 @@ impl TypecheckFrom<AstAnnotation<Untyped>> for AstAnnotation<Typed> {
 
                 // Check given parameters are correct
                 Some(param_specs) => {
-                    let mut span_labels = Vec::new();
-                    let mut missing_param = false;
-
-                    // Check for any duplicate parameters
-                    for (name, spans) in spans.iter() {
-                        if spans.len() > 1 {
-                            let mut span_labels = vec![SpanLabel {
-                                span: spans[0],
-                                label: Some("previously defined here".to_owned()),
-                                style: SpanStyle::Secondary,
-                            }];
-
-                            for span in &spans[1..] {
-                                span_labels.push(SpanLabel {
-                                    span: *span,
-                                    label: Some("redefined here".to_owned()),
-                                    style: SpanStyle::Primary,
-                                });
-                            }
-
-                            errors.push(Diagnostic {
-                                level: Level::Error,
-                                message: format!("Duplicate definitions of parameter `{name}`"),
-                                code: Some("A000".to_string()),
-                                spans: span_labels,
-                            });
-                        }
-                    }
-
-                    // Keep track of extra unused parameters
-                    let mut unexpected_params = params.keys().cloned().collect::<HashSet<_>>();
-
-                    // For each field, check if it is given or if it's optional
-                    for param_spec in param_specs {
-                        if params.contains_key(param_spec.name) {
-                            unexpected_params.remove(param_spec.name);
-                        } else if !param_spec.optional {
-                            missing_param = true;
-                        }
-                    }
-
-                    // For any unexpected parameters, push an error
-                    for unexpected in unexpected_params {
-                        span_labels.push(SpanLabel {
-                            span: *spans[&unexpected].first().unwrap(),
-                            label: Some("unexpected parameter".to_owned()),
-                            style: SpanStyle::Secondary,
-                        });
-                    }
-
-                    if !span_labels.is_empty() || missing_param {
-                        let mut diagnostic = base_diagnostic;
-                        diagnostic.spans.append(&mut span_labels);
-                        errors.push(diagnostic);
-                    }
+                    check_mapped_params(params, spans, param_specs, base_diagnostic, errors)
+                }
+            },
+            AstAnnotationParams::Mixed(_, span, params, spans) => match spec.mapped_params {
+                // Given a single parameter along with mapped parameters, so expect both
+                Some(param_specs) if spec.single_params => {
+                    check_mapped_params(params, spans, param_specs, base_diagnostic, errors)
+                }
+                _ => {
+                    let mut diagnostic = base_diagnostic;
+                    diagnostic.spans.push(SpanLabel {
+                        span: *span,
+                        label: Some("unexpected parameter".to_owned()),
+                        style: SpanStyle::Secondary,
+                    });
+                    errors.push(diagnostic);
                 }
             },
         }
@@ -163,3 +128,67 @@ impl TypecheckFrom<AstAnnotation<Untyped>> for AstAnnotation<Typed> {
         self.params.pass(type_env, annotation_env, scope, errors)
     }
 }
+
+fn check_mapped_params(
+    params: &HashMap<String, AstExpr<Typed>>,
+    spans: &HashMap<String, Vec<Span>>,
+    param_specs: &[MappedAnnotationParamSpec],
+    base_diagnostic: Diagnostic,
+    errors: &mut Vec<Diagnostic>,
+) {
+    let mut span_labels = Vec::new();
+    let mut missing_param = false;
+
+    // Check for any duplicate parameters
+    for (name, spans) in spans.iter() {
+        if spans.len() > 1 {
+            let mut span_labels = vec![SpanLabel {
+                span: spans[0],
+                label: Some("previously defined here".to_owned()),
+                style: SpanStyle::Secondary,
+            }];
+
+            for span in &spans[1..] {
+                span_labels.push(SpanLabel {
+                    span: *span,
+                    label: Some("redefined here".to_owned()),
+                    style: SpanStyle::Primary,
+                });
+            }
+
+            errors.push(Diagnostic {
+                level: Level::Error,
+                message: format!("Duplicate definitions of parameter `{name}`"),
+                code: Some("A000".to_string()),
+                spans: span_labels,
+            });
+        }
+    }
+
+    // Keep track of extra unused parameters
+    let mut unexpected_params = params.keys().cloned().collect::<HashSet<_>>();
+
+    // For each field, check if it is given or if it's optional
+    for param_spec in param_specs {
+        if params.contains_key(param_spec.name) {
+            unexpected_params.remove(param_spec.name);
+        } else if !param_spec.optional {
+            missing_param = true;
+        }
+    }
+
+    // For any unexpected parameters, push an error
+    for unexpected in unexpected_params {
+        span_labels.push(SpanLabel {
+            span: *spans[&unexpected].first().unwrap(),
+            label: Some("unexpected parameter".to_owned()),
+            style: SpanStyle::Secondary,
+        });
+    }
+
+    if !span_labels.is_empty() || missing_param {
+        let mut diagnostic = base_diagnostic;
+        diagnostic.spans.append(&mut span_labels);
+        errors.push(diagnostic);
+    }
+}
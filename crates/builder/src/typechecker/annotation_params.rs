@@ -31,6 +31,15 @@ impl TypecheckFrom<AstAnnotationParams<Untyped>> for AstAnnotationParams<Typed>
                     .collect(),
                 spans.clone(),
             ),
+            AstAnnotationParams::Mixed(expr, span, params, spans) => AstAnnotationParams::Mixed(
+                AstExpr::shallow(expr),
+                *span,
+                params
+                    .iter()
+                    .map(|(name, expr)| (name.clone(), AstExpr::shallow(expr)))
+                    .collect(),
+                spans.clone(),
+            ),
         }
     }
 
@@ -54,6 +63,17 @@ impl TypecheckFrom<AstAnnotationParams<Untyped>> for AstAnnotationParams<Typed>
                     .count()
                     > 0
             }
+            AstAnnotationParams::Mixed(expr, _, params, _) => {
+                let expr_changed = expr.pass(type_env, annotation_env, scope, errors);
+                let params_changed = params
+                    .values_mut()
+                    .map(|param| param.pass(type_env, annotation_env, scope, errors))
+                    .filter(|b| *b)
+                    .count()
+                    > 0;
+
+                expr_changed || params_changed
+            }
         }
     }
 }
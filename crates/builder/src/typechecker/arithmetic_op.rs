@@ -0,0 +1,97 @@
+// Copyright Exograph, Inc. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file at the root of this repository.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::collections::HashMap;
+
+use codemap_diagnostic::{Diagnostic, Level, SpanLabel, SpanStyle};
+use core_model::mapped_arena::MappedArena;
+use core_model_builder::typechecker::{annotation::AnnotationSpec, Typed};
+
+use crate::ast::ast_types::{ArithmeticOp, AstExpr, Untyped};
+
+use super::{PrimitiveType, Scope, Type, TypecheckFrom};
+
+impl TypecheckFrom<ArithmeticOp<Untyped>> for ArithmeticOp<Typed> {
+    fn shallow(untyped: &ArithmeticOp<Untyped>) -> ArithmeticOp<Typed> {
+        let (left, right) = untyped.sides();
+
+        let combiner = match untyped {
+            ArithmeticOp::Add(..) => ArithmeticOp::Add,
+            ArithmeticOp::Sub(..) => ArithmeticOp::Sub,
+            ArithmeticOp::Mul(..) => ArithmeticOp::Mul,
+        };
+
+        let left = Box::new(AstExpr::shallow(left));
+        let right = Box::new(AstExpr::shallow(right));
+
+        combiner(left, right, Type::Defer)
+    }
+
+    fn pass(
+        &mut self,
+        type_env: &MappedArena<Type>,
+        annotation_env: &HashMap<String, AnnotationSpec>,
+        scope: &Scope,
+        errors: &mut Vec<Diagnostic>,
+    ) -> bool {
+        let (ArithmeticOp::Add(left, right, o_typ)
+        | ArithmeticOp::Sub(left, right, o_typ)
+        | ArithmeticOp::Mul(left, right, o_typ)) = self;
+
+        let in_updated = left.pass(type_env, annotation_env, scope, errors)
+            || right.pass(type_env, annotation_env, scope, errors);
+
+        if !o_typ.is_incomplete() {
+            return in_updated;
+        }
+
+        // Only integer arithmetic is supported (optional operands are allowed, since a rule may
+        // compute with a nullable field)
+        fn is_int(typ: &Type) -> bool {
+            match typ {
+                Type::Optional(inner) => is_int(inner),
+                Type::Primitive(PrimitiveType::Int) => true,
+                _ => false,
+            }
+        }
+
+        let left_typ = left.typ().deref(type_env);
+        let right_typ = right.typ().deref(type_env);
+
+        if is_int(&left_typ) && is_int(&right_typ) {
+            *o_typ = Type::Primitive(PrimitiveType::Int);
+            true
+        } else {
+            *o_typ = Type::Error;
+
+            if left_typ.is_complete() && right_typ.is_complete() {
+                let spans = [(left, &left_typ), (right, &right_typ)]
+                    .into_iter()
+                    .filter(|(_, typ)| !is_int(typ))
+                    .map(|(expr, typ)| SpanLabel {
+                        span: expr.span(),
+                        style: SpanStyle::Primary,
+                        label: Some(format!("expected Int, got {typ}")),
+                    })
+                    .collect();
+
+                errors.push(Diagnostic {
+                    level: Level::Error,
+                    message: format!(
+                        "Arithmetic is supported only on Int values, got {left_typ} and {right_typ}"
+                    ),
+                    code: Some("C000".to_string()),
+                    spans,
+                });
+            }
+
+            in_updated
+        }
+    }
+}
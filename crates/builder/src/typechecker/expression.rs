@@ -13,7 +13,9 @@ use codemap_diagnostic::Diagnostic;
 use core_model::mapped_arena::MappedArena;
 use core_model_builder::typechecker::{annotation::AnnotationSpec, Typed};
 
-use crate::ast::ast_types::{AstExpr, FieldSelection, LogicalOp, RelationalOp, Untyped};
+use crate::ast::ast_types::{
+    ArithmeticOp, AstExpr, FieldSelection, FunctionCall, LogicalOp, RelationalOp, Untyped,
+};
 
 use super::{Scope, Type, TypecheckFrom};
 
@@ -27,10 +29,14 @@ impl TypecheckFrom<AstExpr<Untyped>> for AstExpr<Typed> {
             AstExpr::RelationalOp(relation) => {
                 AstExpr::RelationalOp(RelationalOp::shallow(relation))
             }
+            AstExpr::ArithmeticOp(arithmetic) => {
+                AstExpr::ArithmeticOp(ArithmeticOp::shallow(arithmetic))
+            }
             AstExpr::StringLiteral(v, s) => AstExpr::StringLiteral(v.clone(), *s),
             AstExpr::BooleanLiteral(v, s) => AstExpr::BooleanLiteral(*v, *s),
             AstExpr::NumberLiteral(v, s) => AstExpr::NumberLiteral(*v, *s),
             AstExpr::StringList(v, s) => AstExpr::StringList(v.clone(), s.clone()),
+            AstExpr::FunctionCall(call) => AstExpr::FunctionCall(FunctionCall::shallow(call)),
         }
     }
 
@@ -47,6 +53,10 @@ impl TypecheckFrom<AstExpr<Untyped>> for AstExpr<Typed> {
             AstExpr::RelationalOp(relation) => {
                 relation.pass(type_env, annotation_env, scope, errors)
             }
+            AstExpr::ArithmeticOp(arithmetic) => {
+                arithmetic.pass(type_env, annotation_env, scope, errors)
+            }
+            AstExpr::FunctionCall(call) => call.pass(type_env, annotation_env, scope, errors),
             AstExpr::StringList(_, _)
             | AstExpr::StringLiteral(_, _)
             | AstExpr::BooleanLiteral(_, _)
@@ -0,0 +1,133 @@
+// Copyright Exograph, Inc. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file at the root of this repository.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::collections::HashMap;
+
+use codemap_diagnostic::{Diagnostic, Level, SpanLabel, SpanStyle};
+use core_model::mapped_arena::MappedArena;
+use core_model_builder::typechecker::{annotation::AnnotationSpec, Typed};
+
+use crate::ast::ast_types::{AstExpr, FunctionCall, Untyped};
+
+use super::{PrimitiveType, Scope, Type, TypecheckFrom};
+
+impl TypecheckFrom<FunctionCall<Untyped>> for FunctionCall<Typed> {
+    fn shallow(untyped: &FunctionCall<Untyped>) -> FunctionCall<Typed> {
+        FunctionCall {
+            name: untyped.name.clone(),
+            args: untyped.args.iter().map(AstExpr::shallow).collect(),
+            span: untyped.span,
+            typ: Type::Defer,
+        }
+    }
+
+    fn pass(
+        &mut self,
+        type_env: &MappedArena<Type>,
+        annotation_env: &HashMap<String, AnnotationSpec>,
+        scope: &Scope,
+        errors: &mut Vec<Diagnostic>,
+    ) -> bool {
+        let args_updated = self
+            .args
+            .iter_mut()
+            .map(|arg| arg.pass(type_env, annotation_env, scope, errors))
+            .filter(|updated| *updated)
+            .count()
+            > 0;
+
+        if !self.typ.is_incomplete() {
+            return args_updated;
+        }
+
+        let return_type = match self.name.0.as_str() {
+            // `env("NAME")`: the value of an environment variable (resolved when the server starts)
+            "env" => match self.args.as_slice() {
+                [AstExpr::StringLiteral(_, _)] => Ok(Type::Primitive(PrimitiveType::String)),
+                _ => Err("env() takes a single string literal argument".to_string()),
+            },
+            // `now()`: the current time (resolved when evaluating an access rule)
+            "now" => match self.args.as_slice() {
+                [] => Ok(Type::Primitive(PrimitiveType::Instant)),
+                _ => Err("now() takes no arguments".to_string()),
+            },
+            // `contains(haystack, needle)`: whether a string contains a substring or a list contains
+            // an element
+            "contains" => match self.args.as_slice() {
+                [haystack, needle] => {
+                    let haystack_typ = haystack.typ().deref(type_env);
+                    let needle_typ = needle.typ().deref(type_env);
+
+                    if haystack_typ.is_incomplete() || needle_typ.is_incomplete() {
+                        // Try again in the next pass (the arguments report their own errors)
+                        self.typ = Type::Error;
+                        return args_updated;
+                    }
+
+                    if contains_match(&haystack_typ, &needle_typ) {
+                        Ok(Type::Primitive(PrimitiveType::Boolean))
+                    } else {
+                        Err(format!(
+                            "contains() can't look for {needle_typ} in {haystack_typ} (expected a string in a string or an element in a list)"
+                        ))
+                    }
+                }
+                _ => Err("contains() takes two arguments".to_string()),
+            },
+            // `check("canRead", AuthContext.id, ...)`: the result of a module function (resolved
+            // when evaluating an access rule)
+            "check" => match self.args.as_slice() {
+                [AstExpr::StringLiteral(_, _), ..] => Ok(Type::Primitive(PrimitiveType::Boolean)),
+                _ => Err(
+                    "check() takes a function name (string literal) and its arguments".to_string(),
+                ),
+            },
+            name => Err(format!("Unknown function {name}")),
+        };
+
+        match return_type {
+            Ok(typ) => {
+                self.typ = typ;
+                true
+            }
+            Err(message) => {
+                self.typ = Type::Error;
+                errors.push(Diagnostic {
+                    level: Level::Error,
+                    message: message.clone(),
+                    code: Some("C000".to_string()),
+                    spans: vec![SpanLabel {
+                        span: self.span,
+                        style: SpanStyle::Primary,
+                        label: Some(message),
+                    }],
+                });
+                false
+            }
+        }
+    }
+}
+
+/// Can `contains` look for `needle` in `haystack`? Either both are strings or `haystack` is a list
+/// of `needle`s.
+fn contains_match(haystack: &Type, needle: &Type) -> bool {
+    // Allow optional values (for example, an optional field)
+    fn non_optional(typ: &Type) -> &Type {
+        match typ {
+            Type::Optional(underlying) => underlying.as_ref(),
+            typ => typ,
+        }
+    }
+
+    match (non_optional(haystack), non_optional(needle)) {
+        (Type::Primitive(PrimitiveType::String), Type::Primitive(PrimitiveType::String)) => true,
+        (Type::Array(element) | Type::Set(element), needle) => non_optional(element) == needle,
+        _ => false,
+    }
+}
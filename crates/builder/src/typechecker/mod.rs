@@ -7,13 +7,16 @@
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use codemap::Span;
 use codemap_diagnostic::{Diagnostic, Level, SpanLabel, SpanStyle};
 use core_model::{mapped_arena::MappedArena, primitive_type::PrimitiveType};
 use core_model_builder::{
-    ast::ast_types::{AstModel, AstModelKind, AstModule, AstSystem, Untyped},
+    ast::ast_types::{
+        AstAnnotationParams, AstExpr, AstMethodType, AstModel, AstModelKind, AstModule, AstSystem,
+        FieldSelection, FieldSelectionElement, FunctionCall, LogicalOp, Untyped,
+    },
     typechecker::{
         annotation::{AnnotationSpec, AnnotationTarget, MappedAnnotationParamSpec},
         typ::{Module, Type, TypecheckedSystem},
@@ -27,10 +30,12 @@ use crate::error::ParserError;
 mod annotation;
 pub mod annotation_map;
 mod annotation_params;
+mod arithmetic_op;
 mod expression;
 mod field;
 mod field_default_value;
 mod field_type;
+mod function_call;
 mod logical_op;
 mod model;
 mod module;
@@ -130,6 +135,15 @@ fn populate_annotation_env(
                 ]),
             },
         ),
+        (
+            "apiKey",
+            AnnotationSpec {
+                targets: &[AnnotationTarget::Field],
+                no_params: true,
+                single_params: true,
+                mapped_params: None,
+            },
+        ),
         (
             "cookie",
             AnnotationSpec {
@@ -185,6 +199,45 @@ fn populate_annotation_env(
                 mapped_params: None,
             },
         ),
+        (
+            "cron",
+            AnnotationSpec {
+                targets: &[AnnotationTarget::Job],
+                no_params: false,
+                single_params: true,
+                mapped_params: None,
+            },
+        ),
+        (
+            "transaction",
+            AnnotationSpec {
+                targets: &[AnnotationTarget::Type, AnnotationTarget::Method],
+                no_params: false,
+                single_params: false,
+                mapped_params: Some(&[
+                    MappedAnnotationParamSpec {
+                        name: "isolation",
+                        optional: true,
+                    },
+                    MappedAnnotationParamSpec {
+                        name: "timeout",
+                        optional: true,
+                    },
+                ]),
+            },
+        ),
+        (
+            "cache",
+            AnnotationSpec {
+                targets: &[AnnotationTarget::Type],
+                no_params: false,
+                single_params: false,
+                mapped_params: Some(&[MappedAnnotationParamSpec {
+                    name: "ttl",
+                    optional: false,
+                }]),
+            },
+        ),
         (
             "header",
             AnnotationSpec {
@@ -261,6 +314,17 @@ pub fn build(
         validate_module(module)?;
     }
 
+    // Jobs are enqueued and run by name (without the module name), so the name must identify a
+    // job across all modules
+    let ast_jobs: Vec<_> = ast_system
+        .modules
+        .iter()
+        .flat_map(|module| module.jobs.iter())
+        .collect();
+    validate_no_duplicates(&ast_jobs, |job| &job.name, |job| job.span, "job")?;
+
+    validate_access_checks(&ast_system)?;
+
     let ast_types_iter = ast_system.types.iter().chain(ast_module_types.iter());
     let ast_root_types = &ast_system.types;
 
@@ -373,6 +437,180 @@ fn validate_module(module: &AstModule<Untyped>) -> Result<(), ParserError> {
     )
 }
 
+/// Validate that each `check(...)` in an access rule calls a module query and passes an argument
+/// for each of its non-injected parameters. Since contexts are supplied through the injected
+/// parameters, arguments referring to an entire context (such as `AuthContext`) don't count.
+fn validate_access_checks(ast_system: &AstSystem<Untyped>) -> Result<(), ParserError> {
+    fn collect_checks<'a>(expr: &'a AstExpr<Untyped>, checks: &mut Vec<&'a FunctionCall<Untyped>>) {
+        match expr {
+            AstExpr::FunctionCall(call) => {
+                if call.name.0 == "check" {
+                    checks.push(call);
+                }
+                call.args.iter().for_each(|arg| collect_checks(arg, checks));
+            }
+            AstExpr::FieldSelection(selection) => collect_selection_checks(selection, checks),
+            AstExpr::LogicalOp(op) => match op {
+                LogicalOp::Not(expr, _, _) => collect_checks(expr, checks),
+                LogicalOp::And(left, right, _, _) | LogicalOp::Or(left, right, _, _) => {
+                    collect_checks(left, checks);
+                    collect_checks(right, checks);
+                }
+            },
+            AstExpr::RelationalOp(op) => {
+                let (left, right) = op.sides();
+                collect_checks(left, checks);
+                collect_checks(right, checks);
+            }
+            AstExpr::ArithmeticOp(op) => {
+                let (left, right) = op.sides();
+                collect_checks(left, checks);
+                collect_checks(right, checks);
+            }
+            AstExpr::StringLiteral(..)
+            | AstExpr::BooleanLiteral(..)
+            | AstExpr::NumberLiteral(..)
+            | AstExpr::StringList(..) => {}
+        }
+    }
+
+    fn collect_selection_checks<'a>(
+        selection: &'a FieldSelection<Untyped>,
+        checks: &mut Vec<&'a FunctionCall<Untyped>>,
+    ) {
+        let element = match selection {
+            FieldSelection::Single(element, _) => element,
+            FieldSelection::Select(prefix, element, _, _) => {
+                collect_selection_checks(prefix, checks);
+                element
+            }
+        };
+
+        if let FieldSelectionElement::HofCall { expr, .. } = element {
+            collect_checks(expr, checks);
+        }
+    }
+
+    let query_arities: HashMap<&str, usize> = ast_system
+        .modules
+        .iter()
+        .flat_map(|module| module.methods.iter())
+        .filter(|method| method.typ == AstMethodType::Query)
+        .map(|method| {
+            let arity = method
+                .arguments
+                .iter()
+                .filter(|argument| {
+                    !argument
+                        .annotations
+                        .iter()
+                        .any(|annotation| annotation.name == "inject")
+                })
+                .count();
+            (method.name.as_str(), arity)
+        })
+        .collect();
+
+    let ast_types = || {
+        ast_system.types.iter().chain(
+            ast_system
+                .modules
+                .iter()
+                .flat_map(|module| module.types.iter()),
+        )
+    };
+
+    let context_names: HashSet<&str> = ast_types()
+        .filter(|ast_type| ast_type.kind == AstModelKind::Context)
+        .map(|ast_type| ast_type.name.as_str())
+        .collect();
+
+    let annotations = ast_types()
+        .flat_map(|ast_type| {
+            ast_type.annotations.iter().chain(
+                ast_type
+                    .fields
+                    .iter()
+                    .flat_map(|field| field.annotations.iter()),
+            )
+        })
+        .chain(
+            ast_system
+                .modules
+                .iter()
+                .flat_map(|module| module.methods.iter())
+                .flat_map(|method| method.annotations.iter()),
+        );
+
+    let mut checks = vec![];
+    for annotation in annotations {
+        match &annotation.params {
+            AstAnnotationParams::None => {}
+            AstAnnotationParams::Single(expr, _) => collect_checks(expr, &mut checks),
+            AstAnnotationParams::Map(params, _) => params
+                .values()
+                .for_each(|expr| collect_checks(expr, &mut checks)),
+            AstAnnotationParams::Mixed(expr, _, params, _) => {
+                collect_checks(expr, &mut checks);
+                params
+                    .values()
+                    .for_each(|expr| collect_checks(expr, &mut checks));
+            }
+        }
+    }
+
+    let is_context_reference = |expr: &AstExpr<Untyped>| {
+        matches!(
+            expr,
+            AstExpr::FieldSelection(FieldSelection::Single(
+                FieldSelectionElement::Identifier(name, _, _),
+                _,
+            )) if context_names.contains(name.as_str())
+        )
+    };
+
+    // The function-call typechecker reports a `check()` without a function name
+    let diagnostics: Vec<_> = checks
+        .into_iter()
+        .filter_map(|call| match call.args.as_slice() {
+            [AstExpr::StringLiteral(function_name, _), args @ ..] => {
+                let message = match query_arities.get(function_name.as_str()) {
+                    None => format!(
+                        "check() must call a module query, but there is no query named {function_name}"
+                    ),
+                    Some(arity) => {
+                        let arg_count = args.iter().filter(|arg| !is_context_reference(arg)).count();
+                        if arg_count == *arity {
+                            return None;
+                        }
+                        format!(
+                            "{function_name} expects {arity} argument(s), but check() passes {arg_count}"
+                        )
+                    }
+                };
+
+                Some(Diagnostic {
+                    level: Level::Error,
+                    message: message.clone(),
+                    code: Some("C000".to_string()),
+                    spans: vec![SpanLabel {
+                        span: call.span,
+                        style: SpanStyle::Primary,
+                        label: Some(message),
+                    }],
+                })
+            }
+            _ => None,
+        })
+        .collect();
+
+    if diagnostics.is_empty() {
+        Ok(())
+    } else {
+        Err(ParserError::Diagnosis(diagnostics))
+    }
+}
+
 fn validate_no_duplicates<T>(
     items: &[T],
     get_name: impl Fn(&T) -> &str,
@@ -654,6 +892,23 @@ mod tests {
         assert_err(src);
     }
 
+    #[multiplatform_test]
+    fn duplicate_job_across_modules() {
+        let src = r#"
+        @deno("users.ts")
+        module UserModule {
+            job sendEmail(userId: Int)
+        }
+
+        @deno("orders.ts")
+        module OrderModule {
+            job sendEmail(orderId: Int)
+        }
+        "#;
+
+        assert_err(src);
+    }
+
     #[multiplatform_test]
     fn duplicate_plugin_annotations() {
         let src = r#"
@@ -767,6 +1022,61 @@ mod tests {
         assert_err(src);
     }
 
+    #[multiplatform_test]
+    fn annotation_mixed_params() {
+        let src = r#"
+        @postgres
+        module UserModule {
+            @table("users", schema="auth")
+            type User {
+                @pk id: Int = autoIncrement()
+            }
+        }
+        "#;
+
+        assert!(build(src).is_ok());
+    }
+
+    #[multiplatform_test]
+    fn invalid_annotation_mixed_params() {
+        // An unknown named parameter
+        let unknown_param = r#"
+        @postgres
+        module UserModule {
+            @table("users", schemaa="auth")
+            type User {
+                @pk id: Int = autoIncrement()
+            }
+        }
+        "#;
+
+        // A duplicate named parameter
+        let duplicate_param = r#"
+        @postgres
+        module UserModule {
+            @table("users", schema="auth", schema="public")
+            type User {
+                @pk id: Int = autoIncrement()
+            }
+        }
+        "#;
+
+        // An annotation that doesn't take named parameters
+        let no_mapped_params = r#"
+        @postgres
+        module UserModule {
+            type User {
+                @pk id: Int = autoIncrement()
+                @unique("email", name="email") email: String
+            }
+        }
+        "#;
+
+        assert_err(unknown_param);
+        assert_err(duplicate_param);
+        assert_err(no_mapped_params);
+    }
+
     #[multiplatform_test]
     fn invalid_annotation_target() {
         let model = r#"
@@ -899,6 +1209,71 @@ mod tests {
         assert_err(model);
     }
 
+    #[multiplatform_test]
+    fn contains_arguments() {
+        let model = |access: &str| {
+            format!(
+                r#"
+                @postgres
+                module DocumentModule {{
+                    @access({access})
+                    type Document {{
+                        @pk id: Int = autoIncrement()
+                        title: String
+                        tags: Array<String>
+                    }}
+                }}
+                "#
+            )
+        };
+
+        assert!(build(&model(r#"contains(self.title, "draft")"#)).is_ok());
+        assert!(build(&model(r#"contains(self.tags, "public")"#)).is_ok());
+
+        assert_err(&model(r#"contains(self.title, 1)"#));
+        assert_err(&model(r#"contains(self.tags, 1)"#));
+        assert_err(&model(r#"contains(self.id, self.id)"#));
+    }
+
+    #[multiplatform_test]
+    fn check_calls() {
+        let model = |access: &str| {
+            format!(
+                r#"
+                context AuthContext {{
+                    @jwt id: Int
+                }}
+
+                @deno("access.ts")
+                module AccessModule {{
+                    query canRead(@inject authContext: AuthContext, documentId: Int): Boolean
+                    mutation grantRead(documentId: Int): Boolean
+                }}
+
+                @postgres
+                module DocumentModule {{
+                    @access({access})
+                    type Document {{
+                        @pk id: Int = autoIncrement()
+                        title: String
+                    }}
+                }}
+                "#
+            )
+        };
+
+        assert!(build(&model(r#"check("canRead", AuthContext.id)"#)).is_ok());
+        // A context argument goes to the injected parameter
+        assert!(build(&model(r#"check("canRead", AuthContext, AuthContext.id)"#)).is_ok());
+        assert!(build(&model(r#"self.title == "public" || check("canRead", 1)"#)).is_ok());
+
+        assert_err(&model(r#"check("canWrite", AuthContext.id)"#));
+        // Only queries may decide access
+        assert_err(&model(r#"check("grantRead", AuthContext.id)"#));
+        assert_err(&model(r#"check("canRead")"#));
+        assert_err(&model(r#"check("canRead", AuthContext.id, 1)"#));
+    }
+
     fn assert_err(src: &str) {
         assert!(build(src).is_err());
     }
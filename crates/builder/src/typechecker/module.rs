@@ -18,7 +18,7 @@ use core_model_builder::typechecker::{
 };
 
 use crate::ast::ast_types::{
-    AstArgument, AstFieldType, AstInterceptor, AstMethod, AstModule, Untyped,
+    AstArgument, AstFieldType, AstInterceptor, AstJob, AstMethod, AstModule, Untyped,
 };
 
 use super::{annotation_map::AnnotationMapImpl, Scope, Type, TypecheckFrom};
@@ -36,6 +36,7 @@ impl TypecheckFrom<AstModule<Untyped>> for AstModule<Typed> {
             types: typed(&untyped.types),
             methods: typed(&untyped.methods),
             interceptors: typed(&untyped.interceptors),
+            jobs: typed(&untyped.jobs),
             annotations: annotation_map,
             base_exofile: untyped.base_exofile.clone(),
             span: untyped.span,
@@ -77,6 +78,14 @@ impl TypecheckFrom<AstModule<Untyped>> for AstModule<Typed> {
             .count()
             > 0;
 
+        let job_changed = self
+            .jobs
+            .iter_mut()
+            .map(|m| m.pass(type_env, annotation_env, scope, errors))
+            .filter(|v| *v)
+            .count()
+            > 0;
+
         let annot_changed = self.annotations.pass(
             AnnotationTarget::Module,
             type_env,
@@ -109,7 +118,7 @@ impl TypecheckFrom<AstModule<Untyped>> for AstModule<Typed> {
             })
         }
 
-        types_changed || methods_changed || interceptor_changed || annot_changed
+        types_changed || methods_changed || interceptor_changed || job_changed || annot_changed
     }
 }
 
@@ -214,6 +223,45 @@ impl TypecheckFrom<AstInterceptor<Untyped>> for AstInterceptor<Typed> {
     }
 }
 
+impl TypecheckFrom<AstJob<Untyped>> for AstJob<Typed> {
+    fn shallow(untyped: &AstJob<Untyped>) -> AstJob<Typed> {
+        let annotation_map = AnnotationMap::new(&untyped.annotations);
+
+        AstJob {
+            name: untyped.name.clone(),
+            arguments: typed(&untyped.arguments),
+            annotations: annotation_map,
+            span: untyped.span,
+        }
+    }
+
+    fn pass(
+        &mut self,
+        type_env: &MappedArena<Type>,
+        annotation_env: &HashMap<String, AnnotationSpec>,
+        scope: &super::Scope,
+        errors: &mut Vec<codemap_diagnostic::Diagnostic>,
+    ) -> bool {
+        let arguments_changes = self
+            .arguments
+            .iter_mut()
+            .map(|a| a.pass(type_env, annotation_env, scope, errors))
+            .filter(|v| *v)
+            .count()
+            > 0;
+
+        let annot_changed = self.annotations.pass(
+            AnnotationTarget::Job,
+            type_env,
+            annotation_env,
+            scope,
+            errors,
+        );
+
+        arguments_changes || annot_changed
+    }
+}
+
 impl TypecheckFrom<AstArgument<Untyped>> for AstArgument<Typed> {
     fn shallow(untyped: &AstArgument<Untyped>) -> AstArgument<Typed> {
         let annotation_map = AnnotationMap::new(&untyped.annotations);
@@ -97,13 +97,28 @@ impl TypecheckFrom<RelationalOp<Untyped>> for RelationalOp<Typed> {
         };
 
         fn identical_match(left: &Type, right: &Type) -> bool {
-            // Allow optional types to be compared to non-optional types
-            match (left, right) {
-                (Type::Optional(left), Type::Optional(right)) => left == right,
-                (Type::Optional(left), right) => left.as_ref() == right,
-                (left, Type::Optional(right)) => left == right.as_ref(),
-                _ => left == right,
+            // Allow date-time values to be compared with each other (for example, a `LocalDate`
+            // field with `now()`)
+            fn is_date_time(typ: &Type) -> bool {
+                matches!(
+                    typ,
+                    Type::Primitive(
+                        PrimitiveType::Instant
+                            | PrimitiveType::LocalDateTime
+                            | PrimitiveType::LocalDate
+                    )
+                )
             }
+
+            // Allow optional types to be compared to non-optional types
+            let (left, right) = match (left, right) {
+                (Type::Optional(left), Type::Optional(right)) => (left.as_ref(), right.as_ref()),
+                (Type::Optional(left), right) => (left.as_ref(), right),
+                (left, Type::Optional(right)) => (left, right.as_ref()),
+                _ => (left, right),
+            };
+
+            left == right || (is_date_time(left) && is_date_time(right))
         }
 
         fn in_relation_match(left: &Type, right: &Type) -> bool {
@@ -18,7 +18,7 @@ use core_model_builder::{
 
 use crate::ast::ast_types::{AstModelKind, FieldSelection, Untyped};
 
-use super::{Scope, Type, TypecheckFrom};
+use super::{PrimitiveType, Scope, Type, TypecheckFrom};
 
 pub trait TypecheckHofCallFrom<T>
 where
@@ -54,6 +54,13 @@ impl TypecheckHofCallFrom<FieldSelectionElement<Untyped>> for FieldSelectionElem
                 expr: Box::new(AstExpr::shallow(expr)),
                 typ: Type::Defer,
             },
+            FieldSelectionElement::MethodCall { span, name, .. } => {
+                FieldSelectionElement::MethodCall {
+                    span: *span,
+                    name: name.clone(),
+                    typ: Type::Defer,
+                }
+            }
         }
     }
 
@@ -141,6 +148,36 @@ impl TypecheckHofCallFrom<FieldSelectionElement<Untyped>> for FieldSelectionElem
                 *typ = expr.typ().clone();
                 updated
             }
+            FieldSelectionElement::MethodCall { span, name, typ } => {
+                if !typ.is_incomplete() {
+                    return false;
+                }
+
+                // The only method is `count()` on a set (the target type is passed as `elem_type`)
+                match (name.0.as_str(), elem_type) {
+                    ("count", Some(Type::Set(_))) => {
+                        *typ = Type::Primitive(PrimitiveType::Int);
+                        true
+                    }
+                    _ => {
+                        *typ = Type::Error;
+                        errors.push(Diagnostic {
+                            level: Level::Error,
+                            message: format!(
+                                "Unsupported method {} (only `count()` on a set is supported)",
+                                name.0
+                            ),
+                            code: Some("C000".to_string()),
+                            spans: vec![SpanLabel {
+                                span: *span,
+                                style: SpanStyle::Primary,
+                                label: Some("unsupported method".to_string()),
+                            }],
+                        });
+                        false
+                    }
+                }
+            }
         }
     }
 }
@@ -174,7 +211,8 @@ impl TypecheckFrom<FieldSelection<Untyped>> for FieldSelection<Typed> {
                     FieldSelectionElement::Identifier(_, _, resolved_typ) => {
                         *typ = resolved_typ.clone();
                     }
-                    FieldSelectionElement::HofCall { name, span, .. } => {
+                    FieldSelectionElement::HofCall { name, span, .. }
+                    | FieldSelectionElement::MethodCall { name, span, .. } => {
                         *typ = Type::Error;
                         errors.push(Diagnostic {
                             level: Level::Error,
@@ -217,7 +255,8 @@ impl TypecheckFrom<FieldSelection<Untyped>> for FieldSelection<Typed> {
                         Type::Composite(c) => {
                             let elem = match elem {
                                 FieldSelectionElement::Identifier(value, s, _) => (value, *s),
-                                FieldSelectionElement::HofCall { span, name, .. } => {
+                                FieldSelectionElement::HofCall { span, name, .. }
+                                | FieldSelectionElement::MethodCall { span, name, .. } => {
                                     *typ = Type::Error;
                                     errors.push(Diagnostic {
                                         level: Level::Error,
@@ -290,13 +329,25 @@ impl TypecheckFrom<FieldSelection<Untyped>> for FieldSelection<Typed> {
                                 *typ = hof_call.typ().clone();
                                 updated
                             }
+                            method_call @ FieldSelectionElement::MethodCall { .. } => {
+                                let updated = method_call.pass(
+                                    type_env,
+                                    annotation_env,
+                                    scope,
+                                    Some(&Type::Set(elem_type.clone())),
+                                    errors,
+                                );
+                                *typ = method_call.typ().clone();
+                                updated
+                            }
                         },
                         _ => {
                             *typ = Type::Error;
 
                             let field_name = match elem {
                                 FieldSelectionElement::Identifier(value, _, _) => value,
-                                FieldSelectionElement::HofCall { name, .. } => &name.0,
+                                FieldSelectionElement::HofCall { name, .. }
+                                | FieldSelectionElement::MethodCall { name, .. } => &name.0,
                             };
 
                             if !prefix.typ().is_error() {
@@ -74,11 +74,11 @@ impl CommandDefinition for DevCommandDefinition {
 
             loop {
                 let postgres_subsystem = util::create_postgres_system(&model, None).await?;
-                let verification_result = Migration::verify(&db_client, &postgres_subsystem).await;
+                let verification_result = Migration::verify(&db_client, &postgres_subsystem, None).await;
 
                 match verification_result {
                     Err(e @ VerificationErrors::ModelNotCompatible(_)) => {
-                        let migrations = Migration::from_db_and_model(&db_client, &postgres_subsystem).await?;
+                        let migrations = Migration::from_db_and_model(&db_client, &postgres_subsystem, None).await?;
 
                         // If migrations are safe to apply, let's go ahead with those
                         if !migrations.has_destructive_changes() {
@@ -11,7 +11,7 @@ use anyhow::Result;
 use async_trait::async_trait;
 use clap::Command;
 use postgres_model::migration::Migration;
-use std::{io::Write, path::PathBuf};
+use std::{collections::HashMap, io::Write, path::PathBuf};
 
 use exo_sql::schema::database_spec::DatabaseSpec;
 
@@ -45,12 +45,20 @@ impl CommandDefinition for CreateCommandDefinition {
 
         let mut buffer: Box<dyn Write> = open_file_for_output(output.as_deref())?;
 
-        // Creating the schema from the model is the same as migrating from an empty database.
-        let migrations = Migration::from_schemas(
-            &DatabaseSpec::new(vec![]),
-            &DatabaseSpec::from_database(&postgres_subsystem.database),
-        );
-        migrations.write(&mut buffer, true)?;
+        let url_envs = postgres_subsystem.database_url_envs();
+        for url_env in url_envs.iter() {
+            if url_envs.len() > 1 {
+                writeln!(buffer, "-- {}\n", util::database_label(*url_env))?;
+            }
+
+            // Creating the schema from the model is the same as migrating from an empty database.
+            let mut migrations = Migration::from_schemas(
+                &DatabaseSpec::new(vec![]),
+                &postgres_subsystem.database_spec(*url_env),
+            );
+            migrations.add_webhook_triggers(&postgres_subsystem, *url_env, &HashMap::new());
+            migrations.write(&mut buffer, true)?;
+        }
 
         Ok(())
     }
@@ -7,7 +7,10 @@
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
 
-use std::{io, path::PathBuf};
+use std::{
+    io::{self, Write},
+    path::PathBuf,
+};
 
 use anyhow::anyhow;
 use exo_sql::{database_error::DatabaseError, DatabaseClientManager};
@@ -37,7 +40,7 @@ impl CommandDefinition for MigrateCommandDefinition {
         .arg(output_arg())
         .arg(
             Arg::new("apply-to-database")
-                .help("Apply non-destructive migration to the database specified by the --database flag or the environment variable EXO_POSTGRES_URL")
+                .help("Apply non-destructive migration to the database specified by the --database flag or the environment variable EXO_POSTGRES_URL (and the databases of modules with their own URL)")
                 .long("apply-to-database")
                 .required(false)
                 .num_args(0)
@@ -49,6 +52,13 @@ impl CommandDefinition for MigrateCommandDefinition {
                 .required(false)
                 .num_args(0),
         )
+        .arg(
+            Arg::new("rls")
+                .help("Also generate Postgres row-level security policies from the access rules (the server must run with EXO_POSTGRES_RLS=true to set the context values they use)")
+                .long("rls")
+                .required(false)
+                .num_args(0),
+        )
     }
 
     /// Perform a database migration for a exograph model
@@ -60,6 +70,7 @@ impl CommandDefinition for MigrateCommandDefinition {
         let output: Option<PathBuf> = get(matches, "output");
         let apply_to_database: bool = matches.get_flag("apply-to-database");
         let allow_destructive_changes: bool = matches.get_flag("allow-destructive-changes");
+        let rls: bool = matches.get_flag("rls");
 
         if output.is_some() && apply_to_database {
             return Err(anyhow!(
@@ -68,19 +79,43 @@ impl CommandDefinition for MigrateCommandDefinition {
         }
 
         let postgres_subsystem = util::create_postgres_system(&model, None).await?;
-        let db_client = open_database(database.as_deref()).await?;
-        let migrations = Migration::from_db_and_model(&db_client, &postgres_subsystem).await?;
+        let url_envs = postgres_subsystem.database_url_envs();
+
+        // Migrate each database (with modules using `@postgres(url=env("..."))`, there may be
+        // more than one)
+        let mut database_migrations = vec![];
+        for url_env in url_envs.iter() {
+            let db_client = open_model_database(*url_env, database.as_deref()).await?;
+            let mut migrations =
+                Migration::from_db_and_model(&db_client, &postgres_subsystem, *url_env).await?;
+
+            if rls {
+                migrations.add_rls_policies(&postgres_subsystem, *url_env);
+            }
+
+            database_migrations.push((*url_env, db_client, migrations));
+        }
 
         if apply_to_database {
-            if migrations.has_destructive_changes() {
+            if database_migrations
+                .iter()
+                .any(|(_, _, migrations)| migrations.has_destructive_changes())
+            {
                 Err(anyhow!("Migration contains destructive changes"))
             } else {
-                migrations.apply(&db_client, false).await?;
+                for (_, db_client, migrations) in database_migrations.iter() {
+                    migrations.apply(db_client, false).await?;
+                }
                 Ok(())
             }
         } else {
             let mut buffer: Box<dyn io::Write> = open_file_for_output(output.as_deref())?;
-            migrations.write(&mut buffer, allow_destructive_changes)?;
+            for (url_env, _, migrations) in database_migrations.iter() {
+                if url_envs.len() > 1 {
+                    writeln!(buffer, "-- {}\n", util::database_label(*url_env))?;
+                }
+                migrations.write(&mut buffer, allow_destructive_changes)?;
+            }
             Ok(())
         }
     }
@@ -93,3 +128,19 @@ pub async fn open_database(database: Option<&str>) -> Result<DatabaseClientManag
         Ok(util::database_manager_from_env().await?)
     }
 }
+
+/// Open a database of the model: the default database (which `database` overrides) or one
+/// specified through `@postgres(url=env("..."))`
+pub async fn open_model_database(
+    url_env: Option<&str>,
+    database: Option<&str>,
+) -> Result<DatabaseClientManager, DatabaseError> {
+    match url_env {
+        None => open_database(database).await,
+        Some(url_env) => {
+            let url = std::env::var(url_env)
+                .map_err(|_| DatabaseError::Config(format!("{url_env} not set")))?;
+            DatabaseClientManager::from_url(&url, true, None).await
+        }
+    }
+}
@@ -52,6 +52,12 @@ fn deserialize_postgres_subsystem(
         })
 }
 
+/// A label for a database of the model (identified by the env var with its URL, `None` for the
+/// default database) to use in the output of commands that deal with multiple databases
+pub(crate) fn database_label(url_env: Option<&str>) -> String {
+    format!("Database at {}", url_env.unwrap_or(EXO_POSTGRES_URL))
+}
+
 pub(crate) async fn database_manager_from_env() -> Result<DatabaseClientManager, DatabaseError> {
     let url = std::env::var(EXO_POSTGRES_URL)
         .or(std::env::var(DATABASE_URL))
@@ -17,7 +17,7 @@ use crate::commands::command::{
     database_arg, default_model_file, ensure_exo_project_dir, get, CommandDefinition,
 };
 
-use super::{migrate::open_database, util};
+use super::{migrate::open_model_database, util};
 
 pub(super) struct VerifyCommandDefinition {}
 
@@ -37,20 +37,36 @@ impl CommandDefinition for VerifyCommandDefinition {
         let model: PathBuf = default_model_file();
         let database: Option<String> = get(matches, "database");
 
-        let db_client = open_database(database.as_deref()).await?;
         let postgres_subsystem = util::create_postgres_system(&model, None).await?;
-        let verification_result = Migration::verify(&db_client, &postgres_subsystem).await;
-
-        match &verification_result {
-            Ok(()) => eprintln!("This model is compatible with the database schema!"),
-            Err(e @ VerificationErrors::ModelNotCompatible(_)) => {
-                eprintln!("This model is not compatible with the current database schema. You may need to update your model to match, or perform a migration to update it.");
-                eprintln!("The following issues should be corrected:");
-                eprintln!("{e}")
+        let url_envs = postgres_subsystem.database_url_envs();
+
+        let mut compatible = true;
+        for url_env in url_envs.iter() {
+            if url_envs.len() > 1 {
+                eprintln!("{}:", util::database_label(*url_env));
+            }
+
+            let db_client = open_model_database(*url_env, database.as_deref()).await?;
+            let verification_result =
+                Migration::verify(&db_client, &postgres_subsystem, *url_env).await;
+
+            match &verification_result {
+                Ok(()) => eprintln!("This model is compatible with the database schema!"),
+                Err(e @ VerificationErrors::ModelNotCompatible(_)) => {
+                    eprintln!("This model is not compatible with the current database schema. You may need to update your model to match, or perform a migration to update it.");
+                    eprintln!("The following issues should be corrected:");
+                    eprintln!("{e}")
+                }
+                Err(e) => eprintln!("Error: {e}"),
             }
-            Err(e) => eprintln!("Error: {e}"),
+
+            compatible &= verification_result.is_ok();
         }
 
-        verification_result.map_err(|_| anyhow!("Incompatible model."))
+        if compatible {
+            Ok(())
+        } else {
+            Err(anyhow!("Incompatible model."))
+        }
     }
 }
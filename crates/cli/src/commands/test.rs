@@ -13,6 +13,7 @@ use super::command::{get, get_required, CommandDefinition};
 use anyhow::{anyhow, Result};
 use async_trait::async_trait;
 use clap::{Arg, ArgMatches, Command};
+use testing::{CoverageFormat, CoverageOptions, ReporterFormat, ReporterOptions};
 
 const EXO_RUN_INTROSPECTION_TESTS: &str = "EXO_RUN_INTROSPECTION_TESTS";
 
@@ -37,11 +38,66 @@ impl CommandDefinition for TestCommandDefinition {
                     .required(false)
                     .index(2),
             )
+            .arg(
+                Arg::new("update")
+                    .help("Update the expected responses of the tests with the actual responses.")
+                    .long("update")
+                    .action(clap::ArgAction::SetTrue)
+                    .required(false),
+            )
+            .arg(
+                Arg::new("reporter")
+                    .help("Write the test results in the given format (for CI systems).")
+                    .long("reporter")
+                    .value_parser(["junit", "json"])
+                    .required(false),
+            )
+            .arg(
+                Arg::new("output")
+                    .help("The file to write the test results to (with --reporter).")
+                    .long("output")
+                    .value_parser(clap::value_parser!(PathBuf))
+                    .requires("reporter")
+                    .required(false),
+            )
+            .arg(
+                Arg::new("coverage")
+                    .help("Report the access rules exercised by the tests (in the given format).")
+                    .long("coverage")
+                    .value_parser(["text", "lcov", "json"])
+                    .num_args(0..=1)
+                    .default_missing_value("text")
+                    .required(false),
+            )
+            .arg(
+                Arg::new("coverage-output")
+                    .help("The file to write the lcov or JSON coverage report to.")
+                    .long("coverage-output")
+                    .value_parser(clap::value_parser!(PathBuf))
+                    .requires("coverage")
+                    .required(false),
+            )
     }
 
     async fn execute(&self, matches: &ArgMatches) -> Result<()> {
         let dir: PathBuf = get_required(matches, "dir")?;
         let pattern: Option<String> = get(matches, "pattern"); // glob pattern indicating tests to be executed
+        let update = matches.get_flag("update");
+        let reporter = get::<String>(matches, "reporter").map(|format| ReporterOptions {
+            format: match format.as_str() {
+                "json" => ReporterFormat::Json,
+                _ => ReporterFormat::Junit,
+            },
+            output: get(matches, "output"),
+        });
+        let coverage = get::<String>(matches, "coverage").map(|format| CoverageOptions {
+            format: match format.as_str() {
+                "lcov" => CoverageFormat::Lcov,
+                "json" => CoverageFormat::Json,
+                _ => CoverageFormat::Text,
+            },
+            output: get(matches, "coverage-output"),
+        });
 
         let run_introspection_tests: bool = match std::env::var(EXO_RUN_INTROSPECTION_TESTS) {
             Ok(e) => match e.to_lowercase().as_str() {
@@ -61,6 +117,13 @@ impl CommandDefinition for TestCommandDefinition {
             }
         }
 
-        testing::run(&dir, &pattern, run_introspection_tests)
+        testing::run(
+            &dir,
+            &pattern,
+            run_introspection_tests,
+            coverage,
+            reporter,
+            update,
+        )
     }
 }
@@ -82,16 +82,13 @@ async fn run(root_path: &Path, port: Option<u32>) -> Result<()> {
     let jwt_secret = std::env::var(EXO_JWT_SECRET).ok();
     let oidc_url = std::env::var(EXO_OIDC_URL).ok();
 
+    // If both are set, the server trusts tokens from either (the OIDC URL is inherited from the
+    // environment)
     let jwt_secret = match (jwt_secret, oidc_url) {
-        (Some(_), Some(_)) => Err(anyhow::anyhow!(
-            "Both {} and {} are set. Please unset one of them.",
-            EXO_JWT_SECRET,
-            EXO_OIDC_URL
-        )),
-        (Some(s), None) => Ok(JWTSecret::EnvSecret(s)),
-        (None, Some(s)) => Ok(JWTSecret::EnvOidc(s)),
-        (None, None) => Ok(JWTSecret::Generated(super::util::generate_random_string())),
-    }?;
+        (Some(s), _) => JWTSecret::EnvSecret(s),
+        (None, Some(s)) => JWTSecret::EnvOidc(s),
+        (None, None) => JWTSecret::Generated(super::util::generate_random_string()),
+    };
 
     let prestart_callback = || run_server(&model, &jwt_secret, db.as_ref()).boxed();
 
@@ -143,7 +140,7 @@ async fn run_server(
 
     // generate migrations for current database
     let postgres_subystem = util::create_postgres_system(model, None).await?;
-    let migrations = Migration::from_db_and_model(&db_client, &postgres_subystem).await?;
+    let migrations = Migration::from_db_and_model(&db_client, &postgres_subystem, None).await?;
 
     // execute migration
     println!("Applying migrations...");
@@ -6,14 +6,48 @@ pub const EXO_INTROSPECTION_LIVE_UPDATE: &str = "EXO_INTROSPECTION_LIVE_UPDATE";
 pub const EXO_CORS_DOMAINS: &str = "EXO_CORS_DOMAINS";
 
 pub const EXO_JWT_SECRET: &str = "EXO_JWT_SECRET";
+pub const EXO_JWT_PUBLIC_KEY_FILE: &str = "EXO_JWT_PUBLIC_KEY_FILE";
+pub const EXO_JWT_JWKS_FILE: &str = "EXO_JWT_JWKS_FILE";
+pub const EXO_JWT_ISSUER: &str = "EXO_JWT_ISSUER";
+pub const EXO_JWT_AUDIENCE: &str = "EXO_JWT_AUDIENCE";
+pub const EXO_JWT_LEEWAY: &str = "EXO_JWT_LEEWAY";
+pub const EXO_JWT_SOURCES: &str = "EXO_JWT_SOURCES";
 pub const EXO_OIDC_URL: &str = "EXO_OIDC_URL";
 
+pub const EXO_API_KEY_HEADER: &str = "EXO_API_KEY_HEADER";
+pub const EXO_API_KEYS_FILE: &str = "EXO_API_KEYS_FILE";
+pub const EXO_API_KEYS_TABLE: &str = "EXO_API_KEYS_TABLE";
+
 pub const EXO_POSTGRES_URL: &str = "EXO_POSTGRES_URL";
 pub const DATABASE_URL: &str = "DATABASE_URL";
+pub const EXO_POSTGRES_READ_URL: &str = "EXO_POSTGRES_READ_URL";
 pub const EXO_CONNECTION_POOL_SIZE: &str = "EXO_CONNECTION_POOL_SIZE";
 pub const EXO_CHECK_CONNECTION_ON_STARTUP: &str = "EXO_CHECK_CONNECTION_ON_STARTUP";
+pub const EXO_POSTGRES_RLS: &str = "EXO_POSTGRES_RLS";
+pub const EXO_POSTGRES_STATEMENT_TIMEOUT: &str = "EXO_POSTGRES_STATEMENT_TIMEOUT";
+pub const EXO_POSTGRES_STATEMENT_CACHE_SIZE: &str = "EXO_POSTGRES_STATEMENT_CACHE_SIZE";
+pub const EXO_SLOW_QUERY_THRESHOLD: &str = "EXO_SLOW_QUERY_THRESHOLD";
+pub const EXO_POSTGRES_SQL_COMMENTS: &str = "EXO_POSTGRES_SQL_COMMENTS";
+
+pub const EXO_DENO_MAX_ACTORS_PER_MODULE: &str = "EXO_DENO_MAX_ACTORS_PER_MODULE";
+pub const EXO_DENO_QUEUE_TIMEOUT: &str = "EXO_DENO_QUEUE_TIMEOUT";
+pub const EXO_DENO_EXECUTION_TIMEOUT: &str = "EXO_DENO_EXECUTION_TIMEOUT";
+pub const EXO_DENO_MAX_HEAP_SIZE: &str = "EXO_DENO_MAX_HEAP_SIZE";
+pub const EXO_DENO_WARM_ACTORS_PER_MODULE: &str = "EXO_DENO_WARM_ACTORS_PER_MODULE";
+
+pub const EXO_JOBS: &str = "EXO_JOBS";
+pub const EXO_JOB_POLL_INTERVAL: &str = "EXO_JOB_POLL_INTERVAL";
+pub const EXO_JOB_VISIBILITY_TIMEOUT: &str = "EXO_JOB_VISIBILITY_TIMEOUT";
+pub const EXO_JOB_CONCURRENCY: &str = "EXO_JOB_CONCURRENCY";
+
+pub const EXO_RESPONSE_CACHE: &str = "EXO_RESPONSE_CACHE";
+
+pub const EXO_ACCESS_AUDIT_LOG: &str = "EXO_ACCESS_AUDIT_LOG";
+pub const EXO_ACCESS_AUDIT_LOG_CONTEXT: &str = "EXO_ACCESS_AUDIT_LOG_CONTEXT";
 
 pub const EXO_SERVER_PORT: &str = "EXO_SERVER_PORT";
+pub const EXO_METRICS_HTTP_PATH: &str = "EXO_METRICS_HTTP_PATH";
+pub const EXO_METRICS_PORT: &str = "EXO_METRICS_PORT";
 
 pub const _EXO_DEPLOYMENT_MODE: &str = "_EXO_DEPLOYMENT_MODE"; // "yolo", "dev", "playground" or "prod" (default)
 pub const _EXO_ENFORCE_TRUSTED_DOCUMENTS: &str = "_EXO_ENFORCE_TRUSTED_DOCUMENTS";
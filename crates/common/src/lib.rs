@@ -10,6 +10,7 @@
 use thiserror::Error;
 
 pub mod env_const;
+pub mod trace_context;
 
 #[derive(Error, Debug)]
 pub enum EnvError {
@@ -19,6 +20,12 @@ pub enum EnvError {
         env_value: String,
         message: String,
     },
+    #[error("Invalid env value {env_value} for {env_key}: {message}")]
+    InvalidValue {
+        env_key: &'static str,
+        env_value: String,
+        message: String,
+    },
 }
 
 #[cfg(feature = "opentelemetry")]
@@ -29,21 +29,35 @@
 //!
 //! At least one `OTEL_` prefixed variable must be set to enable OpenTelemetry.
 //!
+//! Metrics are exported to the same endpoint along with traces, unless `OTEL_METRICS_EXPORTER` is
+//! set to `none`.
+//!
 //! To use Jaeger, a local server can be started using docker:
 //!
 //! ```shell
 //! $ docker run -d --name jaeger -e COLLECTOR_OTLP_ENABLED=true -p 16686:16686 -p 4317:4317 -p 4318:4318 jaegertracing/all-in-one:latest
 //! ```
 //!
-use std::str::FromStr;
+use std::{
+    str::FromStr,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use opentelemetry_otlp::{
+    HttpExporterBuilder, MetricsExporterBuilder, SpanExporterBuilder, TonicExporterBuilder,
+};
 use tracing_subscriber::{filter::LevelFilter, prelude::*, EnvFilter};
 
 const EXO_LOG: &str = "EXO_LOG";
+
+static OTLP_METRICS_ENABLED: AtomicBool = AtomicBool::new(false);
+
 /// Initialize the tracing subscriber.
 ///
 /// Creates a `tracing_subscriber::fmt` layer by default and adds a `tracing_opentelemetry`
 /// layer if OpenTelemetry, exporting traces with `opentelemetry_otlp` if any OpenTelemetry
-/// environment variables are set.
+/// environment variables are set. In that case, also installs a global meter provider that
+/// exports metrics.
 pub fn init() {
     let fmt_layer = tracing_subscriber::fmt::layer().compact();
     let telemetry_layer =
@@ -58,18 +72,73 @@ pub fn init() {
         .with(fmt_layer)
         .with(telemetry_layer)
         .init();
+
+    create_otlp_meter_provider();
+}
+
+/// Whether metrics are exported with OpenTelemetry (through the global meter provider)
+pub fn otlp_metrics_enabled() -> bool {
+    OTLP_METRICS_ENABLED.load(Ordering::Relaxed)
+}
+
+fn otlp_enabled() -> bool {
+    std::env::vars().any(|(name, _)| name.starts_with("OTEL_"))
 }
 
 fn create_otlp_tracer() -> Option<opentelemetry_sdk::trace::Tracer> {
-    if !std::env::vars().any(|(name, _)| name.starts_with("OTEL_")) {
+    if !otlp_enabled() {
         return None;
     }
-    let protocol = std::env::var("OTEL_EXPORTER_OTLP_PROTOCOL").unwrap_or("grpc".to_string());
 
-    let base_tracer = opentelemetry_otlp::new_pipeline().tracing();
+    let tracer = opentelemetry_otlp::new_pipeline()
+        .tracing()
+        .with_exporter(create_otlp_exporter::<SpanExporterBuilder>());
+
+    // Use the simple exporter if running the integration tests and using
+    // opentelemetry. Otherwise the test server will be killed before the batched
+    // spans are exported.
+    // Some(tracer.install_simple().unwrap())
+    Some(
+        tracer
+            .install_batch(opentelemetry_sdk::runtime::Tokio)
+            .unwrap(),
+    )
+}
+
+fn create_otlp_meter_provider() {
+    if !otlp_enabled() || std::env::var("OTEL_METRICS_EXPORTER").as_deref() == Ok("none") {
+        return;
+    }
+
+    let meter_provider = opentelemetry_otlp::new_pipeline()
+        .metrics(opentelemetry_sdk::runtime::Tokio)
+        .with_exporter(create_otlp_exporter::<MetricsExporterBuilder>())
+        .build();
+
+    // Failing to export metrics shouldn't keep the server from starting (traces may still be
+    // exported)
+    let meter_provider = match meter_provider {
+        Ok(meter_provider) => meter_provider,
+        Err(e) => {
+            tracing::error!("Unable to export metrics with OpenTelemetry: {e}");
+            return;
+        }
+    };
+
+    opentelemetry::global::set_meter_provider(meter_provider);
+    OTLP_METRICS_ENABLED.store(true, Ordering::Relaxed);
+}
+
+/// Create an exporter (of traces or metrics) based on the `OTEL_EXPORTER_OTLP_*` environment
+/// variables
+fn create_otlp_exporter<B>() -> B
+where
+    B: From<TonicExporterBuilder> + From<HttpExporterBuilder>,
+{
+    let protocol = std::env::var("OTEL_EXPORTER_OTLP_PROTOCOL").unwrap_or("grpc".to_string());
     let headers = parse_otlp_headers_from_env();
 
-    let tracer = match protocol.as_str() {
+    match protocol.as_str() {
         "grpc" => {
             let mut exporter = opentelemetry_otlp::new_exporter()
                 .tonic()
@@ -81,26 +150,14 @@ fn create_otlp_tracer() -> Option<opentelemetry_sdk::trace::Tracer> {
                     exporter = exporter.with_tls_config(Default::default());
                 }
             }
-            base_tracer.with_exporter(exporter)
-        }
-        "http/protobuf" => {
-            let exporter = opentelemetry_otlp::new_exporter()
-                .http()
-                .with_headers(headers.into_iter().collect());
-            base_tracer.with_exporter(exporter)
+            exporter.into()
         }
+        "http/protobuf" => opentelemetry_otlp::new_exporter()
+            .http()
+            .with_headers(headers.into_iter().collect())
+            .into(),
         p => panic!("Unsupported protocol {}", p),
-    };
-
-    // Use the simple exporter if running the integration tests and using
-    // opentelemetry. Otherwise the test server will be killed before the batched
-    // spans are exported.
-    // Some(tracer.install_simple().unwrap())
-    Some(
-        tracer
-            .install_batch(opentelemetry_sdk::runtime::Tokio)
-            .unwrap(),
-    )
+    }
 }
 
 fn metadata_from_headers(headers: Vec<(String, String)>) -> tonic::metadata::MetadataMap {
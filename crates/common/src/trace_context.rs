@@ -0,0 +1,166 @@
+// Copyright Exograph, Inc. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file at the root of this repository.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! [W3C trace context](https://www.w3.org/TR/trace-context/) support, so that handling a request
+//! joins the trace of its caller, and the services (and the database) we call join ours.
+//!
+//! Spans belong to a trace only if OpenTelemetry is enabled (see `logging_tracing`), so without
+//! it, there is no trace context to propagate and setting a parent has no effect.
+
+use std::str::FromStr;
+
+use opentelemetry::{
+    trace::{SpanContext, SpanId, TraceContextExt, TraceFlags, TraceId, TraceState},
+    Context,
+};
+use tracing::Span;
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+pub const TRACEPARENT_HEADER: &str = "traceparent";
+pub const TRACESTATE_HEADER: &str = "tracestate";
+
+/// The trace context of a span as the values of the `traceparent` and `tracestate` headers
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceContext {
+    pub traceparent: String,
+    pub tracestate: Option<String>,
+}
+
+impl TraceContext {
+    /// The trace context of the current span (`None` if it doesn't belong to a trace)
+    pub fn current() -> Option<Self> {
+        let context = Span::current().context();
+        let span_context = context.span().span_context().clone();
+
+        span_context
+            .is_valid()
+            .then(|| Self::from_span_context(&span_context))
+    }
+
+    fn from_span_context(span_context: &SpanContext) -> Self {
+        let tracestate = span_context.trace_state().header();
+
+        Self {
+            traceparent: format!(
+                "00-{}-{}-{:02x}",
+                span_context.trace_id(),
+                span_context.span_id(),
+                span_context.trace_flags().to_u8()
+            ),
+            tracestate: (!tracestate.is_empty()).then_some(tracestate),
+        }
+    }
+
+    /// The span context described by the header values (`None` if the `traceparent` is invalid;
+    /// an invalid `tracestate` is ignored)
+    fn span_context(traceparent: &str, tracestate: Option<&str>) -> Option<SpanContext> {
+        let parts: Vec<_> = traceparent.trim().split('-').collect();
+
+        let [version, trace_id, span_id, flags, rest @ ..] = &parts[..] else {
+            return None;
+        };
+
+        // Later versions may add fields, but must keep these
+        let valid_version = is_lower_hex(version, 2) && *version != "ff";
+        if !valid_version || (*version == "00" && !rest.is_empty()) {
+            return None;
+        }
+        if !is_lower_hex(trace_id, 32) || !is_lower_hex(span_id, 16) || !is_lower_hex(flags, 2) {
+            return None;
+        }
+
+        let trace_id = TraceId::from_hex(trace_id).ok()?;
+        let span_id = SpanId::from_hex(span_id).ok()?;
+        let flags = TraceFlags::new(u8::from_str_radix(flags, 16).ok()?);
+        let trace_state = tracestate
+            .and_then(|tracestate| TraceState::from_str(tracestate).ok())
+            .unwrap_or_default();
+
+        let span_context = SpanContext::new(trace_id, span_id, flags, true, trace_state);
+        span_context.is_valid().then_some(span_context)
+    }
+}
+
+/// Make the span a child of the caller's span (if the request carries a valid trace context)
+pub fn set_remote_parent(span: &Span, traceparent: Option<&str>, tracestate: Option<&str>) {
+    if let Some(span_context) =
+        traceparent.and_then(|traceparent| TraceContext::span_context(traceparent, tracestate))
+    {
+        span.set_parent(Context::new().with_remote_span_context(span_context));
+    }
+}
+
+fn is_lower_hex(value: &str, len: usize) -> bool {
+    value.len() == len
+        && value
+            .chars()
+            .all(|c| c.is_ascii_digit() || ('a'..='f').contains(&c))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TRACEPARENT: &str = "00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01";
+
+    #[test]
+    fn valid_trace_context() {
+        let span_context = TraceContext::span_context(
+            TRACEPARENT,
+            Some("congo=t61rcWkgMzE,rojo=00f067aa0ba902b7"),
+        )
+        .unwrap();
+
+        assert_eq!(
+            span_context.trace_id(),
+            TraceId::from_hex("0af7651916cd43dd8448eb211c80319c").unwrap()
+        );
+        assert_eq!(
+            span_context.span_id(),
+            SpanId::from_hex("b7ad6b7169203331").unwrap()
+        );
+        assert!(span_context.is_sampled());
+        assert!(span_context.is_remote());
+
+        assert_eq!(
+            TraceContext::from_span_context(&span_context),
+            TraceContext {
+                traceparent: TRACEPARENT.to_string(),
+                tracestate: Some("congo=t61rcWkgMzE,rojo=00f067aa0ba902b7".to_string()),
+            }
+        );
+
+        // A later version may add fields
+        assert!(TraceContext::span_context(
+            "01-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-00-extra",
+            None
+        )
+        .is_some());
+    }
+
+    #[test]
+    fn invalid_trace_context() {
+        for traceparent in [
+            "",
+            "00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331",
+            "00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01-extra",
+            "ff-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01",
+            "00-0AF7651916CD43DD8448EB211C80319C-b7ad6b7169203331-01",
+            "00-00000000000000000000000000000000-b7ad6b7169203331-01",
+            "00-0af7651916cd43dd8448eb211c80319c-0000000000000000-01",
+            "00-0af7651916cd43dd8448eb211c8031-b7ad6b7169203331-01",
+        ] {
+            assert_eq!(
+                TraceContext::span_context(traceparent, None),
+                None,
+                "{traceparent}"
+            );
+        }
+    }
+}
@@ -83,6 +83,7 @@ pub struct AstModule<T: NodeTypedness> {
     pub types: Vec<AstModel<T>>,
     pub methods: Vec<AstMethod<T>>,
     pub interceptors: Vec<AstInterceptor<T>>,
+    pub jobs: Vec<AstJob<T>>,
     pub base_exofile: PathBuf, // The exo file in which this module is defined. Used to resolve relative imports and js/ts/wasm sources
     #[serde(skip_serializing)]
     #[serde(skip_deserializing)]
@@ -132,6 +133,18 @@ pub struct AstInterceptor<T: NodeTypedness> {
     pub span: Span,
 }
 
+/// A module function that runs in the background (either on a `@cron` schedule or when enqueued)
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct AstJob<T: NodeTypedness> {
+    pub name: String,
+    pub arguments: Vec<AstArgument<T>>,
+    pub annotations: T::Annotations,
+    #[serde(skip_serializing)]
+    #[serde(skip_deserializing)]
+    #[serde(default = "default_span")]
+    pub span: Span,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 pub enum AstModelKind {
     Type,    // a type in a module (with semantics assigned by each module plugin)
@@ -232,19 +245,31 @@ pub enum AstAnnotationParams<T: NodeTypedness> {
         #[serde(skip_deserializing)]
         HashMap<String, Vec<Span>>, // store as Vec to check for duplicates later on
     ),
+    /// A single parameter followed by named parameters (e.g. `@deno("x.ts", allowNet=["api.stripe.com"])`)
+    Mixed(
+        AstExpr<T>,
+        #[serde(skip_serializing)]
+        #[serde(skip_deserializing)]
+        #[serde(default = "default_span")]
+        Span,
+        HashMap<String, AstExpr<T>>,
+        #[serde(skip_serializing)]
+        #[serde(skip_deserializing)]
+        HashMap<String, Vec<Span>>,
+    ),
 }
 
 impl AstAnnotationParams<Typed> {
     pub fn as_single(&self) -> &AstExpr<Typed> {
         match self {
-            Self::Single(expr, _) => expr,
+            Self::Single(expr, _) | Self::Mixed(expr, _, _, _) => expr,
             _ => panic!(),
         }
     }
 
     pub fn as_map(&self) -> &HashMap<String, AstExpr<Typed>> {
         match self {
-            Self::Map(map, _) => map,
+            Self::Map(map, _) | Self::Mixed(_, _, map, _) => map,
             _ => panic!(),
         }
     }
@@ -255,6 +280,7 @@ pub enum AstExpr<T: NodeTypedness> {
     FieldSelection(FieldSelection<T>),
     LogicalOp(LogicalOp<T>),
     RelationalOp(RelationalOp<T>),
+    ArithmeticOp(ArithmeticOp<T>),
     StringLiteral(
         String,
         #[serde(skip_serializing)]
@@ -282,6 +308,7 @@ pub enum AstExpr<T: NodeTypedness> {
         #[serde(skip_deserializing)]
         Vec<Span>,
     ),
+    FunctionCall(FunctionCall<T>),
 }
 
 impl<T: NodeTypedness> AstExpr<T> {
@@ -295,6 +322,7 @@ impl<T: NodeTypedness> AstExpr<T> {
                 LogicalOp::Or(_, _, s, _) => *s,
             },
             AstExpr::RelationalOp(r) => r.span(),
+            AstExpr::ArithmeticOp(a) => a.span(),
             AstExpr::BooleanLiteral(_, s) => *s,
             AstExpr::NumberLiteral(_, s) => *s,
             AstExpr::StringList(_, s) => {
@@ -304,10 +332,23 @@ impl<T: NodeTypedness> AstExpr<T> {
                 }
                 span
             }
+            AstExpr::FunctionCall(call) => call.span,
         }
     }
 }
 
+/// A call to a built-in function such as `env("WEBHOOK_URL")`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct FunctionCall<T: NodeTypedness> {
+    pub name: Identifier,
+    pub args: Vec<AstExpr<T>>,
+    #[serde(skip_serializing)]
+    #[serde(skip_deserializing)]
+    #[serde(default = "default_span")]
+    pub span: Span,
+    pub typ: T::Expr,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub enum FieldSelection<T: NodeTypedness> {
     Single(FieldSelectionElement<T>, T::FieldSelection),
@@ -345,7 +386,8 @@ impl FieldSelection<Typed> {
             fn process_selection_elem(elem: &FieldSelectionElement<Typed>, acc: &mut Vec<String>) {
                 match elem {
                     FieldSelectionElement::Identifier(name, _, _) => acc.push(name.clone()),
-                    FieldSelectionElement::HofCall { .. } => {
+                    FieldSelectionElement::HofCall { .. }
+                    | FieldSelectionElement::MethodCall { .. } => {
                         unimplemented!("Context path doesn't support function calls yet")
                     }
                 }
@@ -396,6 +438,15 @@ pub enum FieldSelectionElement<T: NodeTypedness> {
         expr: Box<AstExpr<T>>, // expression passed to the function such as "du.userId == AuthContext.id && du.read"
         typ: T::FieldSelection,
     },
+    /// Call to a method without arguments such as `count()`
+    MethodCall {
+        #[serde(skip_serializing)]
+        #[serde(skip_deserializing)]
+        #[serde(default = "default_span")]
+        span: Span,
+        name: Identifier, // name of the method such as "count"
+        typ: T::FieldSelection,
+    },
 }
 
 impl<T: NodeTypedness> FieldSelectionElement<T> {
@@ -403,6 +454,7 @@ impl<T: NodeTypedness> FieldSelectionElement<T> {
         match &self {
             FieldSelectionElement::Identifier(_, span, _) => span,
             FieldSelectionElement::HofCall { span, .. } => span,
+            FieldSelectionElement::MethodCall { span, .. } => span,
         }
     }
 
@@ -410,6 +462,7 @@ impl<T: NodeTypedness> FieldSelectionElement<T> {
         match &self {
             FieldSelectionElement::Identifier(_, _, typ) => typ,
             FieldSelectionElement::HofCall { typ, .. } => typ,
+            FieldSelectionElement::MethodCall { typ, .. } => typ,
         }
     }
 }
@@ -484,6 +537,39 @@ impl<T: NodeTypedness> RelationalOp<T> {
     }
 }
 
+/// Integer arithmetic such as `self.capacity - 10`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum ArithmeticOp<T: NodeTypedness> {
+    Add(Box<AstExpr<T>>, Box<AstExpr<T>>, T::Expr),
+    Sub(Box<AstExpr<T>>, Box<AstExpr<T>>, T::Expr),
+    Mul(Box<AstExpr<T>>, Box<AstExpr<T>>, T::Expr),
+}
+
+impl<T: NodeTypedness> ArithmeticOp<T> {
+    pub fn typ(&self) -> &T::Expr {
+        let (_, _, typ) = self.to_tuple();
+        typ
+    }
+
+    pub fn sides(&self) -> (&AstExpr<T>, &AstExpr<T>) {
+        let (l, r, _) = self.to_tuple();
+        (l, r)
+    }
+
+    fn to_tuple(&self) -> (&AstExpr<T>, &AstExpr<T>, &T::Expr) {
+        match self {
+            ArithmeticOp::Add(l, r, typ) => (l, r, typ),
+            ArithmeticOp::Sub(l, r, typ) => (l, r, typ),
+            ArithmeticOp::Mul(l, r, typ) => (l, r, typ),
+        }
+    }
+
+    fn span(&self) -> Span {
+        let (l, r) = self.sides();
+        l.span().merge(r.span())
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 pub struct Identifier(
     pub String,
@@ -7,8 +7,15 @@
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
 
+use std::{collections::HashMap, str::FromStr};
+
 use codemap_diagnostic::{Diagnostic, Level, SpanLabel, SpanStyle};
-use core_model::{mapped_arena::MappedArena, primitive_type::PrimitiveType, types::FieldType};
+use core_model::{
+    mapped_arena::MappedArena,
+    primitive_type::PrimitiveType,
+    transaction::{parse_duration, IsolationLevel, TransactionOptions},
+    types::FieldType,
+};
 use serde::{Deserialize, Serialize};
 
 use crate::{
@@ -47,6 +54,53 @@ impl AstAnnotationHelper for AstAnnotation<Typed> {
     }
 }
 
+/// Build the transaction options from `@transaction(isolation="...", timeout="...")` (the
+/// default options, if the annotation is absent)
+pub fn build_transaction_options(
+    annotation_params: Option<&AstAnnotationParams<Typed>>,
+    errors: &mut Vec<Diagnostic>,
+) -> TransactionOptions {
+    let params = match annotation_params {
+        Some(AstAnnotationParams::Map(params, _)) => params,
+        _ => return TransactionOptions::default(), // The typechecker ensures that the parameters are named
+    };
+
+    fn string_param<T>(
+        params: &HashMap<String, AstExpr<Typed>>,
+        param_name: &str,
+        parse: impl Fn(&str) -> Result<T, String>,
+        errors: &mut Vec<Diagnostic>,
+    ) -> Option<T> {
+        let (message, span) = match params.get(param_name)? {
+            AstExpr::StringLiteral(value, span) => match parse(value) {
+                Ok(value) => return Some(value),
+                Err(message) => (message, *span),
+            },
+            expr => (
+                format!("The `{param_name}` parameter of `@transaction` must be a string"),
+                expr.span(),
+            ),
+        };
+
+        errors.push(Diagnostic {
+            level: Level::Error,
+            message,
+            code: Some("C000".to_string()),
+            spans: vec![SpanLabel {
+                span,
+                style: SpanStyle::Primary,
+                label: None,
+            }],
+        });
+        None
+    }
+
+    TransactionOptions {
+        isolation_level: string_param(params, "isolation", IsolationLevel::from_str, errors),
+        timeout: string_param(params, "timeout", parse_duration, errors),
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct ResolvedContext {
     pub name: String,
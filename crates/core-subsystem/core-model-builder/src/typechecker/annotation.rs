@@ -28,6 +28,7 @@ pub enum AnnotationTarget {
     Module,
     Method,
     Interceptor,
+    Job,
 }
 
 /// Specification for a mapped parameter of an annotation.
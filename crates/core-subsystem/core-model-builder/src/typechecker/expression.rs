@@ -19,12 +19,14 @@ impl AstExpr<Typed> {
             AstExpr::FieldSelection(select) => select.typ().clone(),
             AstExpr::LogicalOp(logic) => logic.typ().clone(),
             AstExpr::RelationalOp(relation) => relation.typ().clone(),
+            AstExpr::ArithmeticOp(arithmetic) => arithmetic.typ().clone(),
             AstExpr::StringLiteral(_, _) => Type::Primitive(PrimitiveType::String),
             AstExpr::BooleanLiteral(_, _) => Type::Primitive(PrimitiveType::Boolean),
             AstExpr::NumberLiteral(_, _) => Type::Primitive(PrimitiveType::Int),
             AstExpr::StringList(_, _) => {
                 Type::Array(Box::new(Type::Primitive(PrimitiveType::String)))
             }
+            AstExpr::FunctionCall(call) => call.typ.clone(),
         }
     }
 
@@ -76,6 +76,9 @@ where
     Gt(Box<PrimExpr>, Box<PrimExpr>),
     Gte(Box<PrimExpr>, Box<PrimExpr>),
     In(Box<PrimExpr>, Box<PrimExpr>),
+    /// `contains(left, right)`: the left string contains the right string or the left list
+    /// contains the right value
+    Contains(Box<PrimExpr>, Box<PrimExpr>),
 }
 
 impl<PrimExpr> AccessRelationalOp<PrimExpr>
@@ -93,6 +96,7 @@ where
             AccessRelationalOp::Gt(left, right) => (left, right),
             AccessRelationalOp::Gte(left, right) => (left, right),
             AccessRelationalOp::In(left, right) => (left, right),
+            AccessRelationalOp::Contains(left, right) => (left, right),
         }
     }
 
@@ -105,6 +109,7 @@ where
             AccessRelationalOp::Gt(left, right) => (left, right),
             AccessRelationalOp::Gte(left, right) => (left, right),
             AccessRelationalOp::In(left, right) => (left, right),
+            AccessRelationalOp::Contains(left, right) => (left, right),
         }
     }
 
@@ -119,6 +124,7 @@ where
             AccessRelationalOp::Gt(_, _) => AccessRelationalOp::Gt,
             AccessRelationalOp::Gte(_, _) => AccessRelationalOp::Gte,
             AccessRelationalOp::In(_, _) => AccessRelationalOp::In,
+            AccessRelationalOp::Contains(_, _) => AccessRelationalOp::Contains,
         }
     }
 }
@@ -129,6 +135,26 @@ pub enum CommonAccessPrimitiveExpression {
     StringLiteral(String),              // for example, "ADMIN"
     BooleanLiteral(bool),               // for example, true
     NumberLiteral(i64),                 // for example, integer (-13, 0, 300, etc.)
+    Now,                                // now()
+}
+
+/// Arithmetic operators such as `+` in `self.members.count() + 1`
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArithmeticOp {
+    Add,
+    Sub,
+    Mul,
+}
+
+impl ArithmeticOp {
+    /// Apply the operator to two integers (returns `None` on overflow)
+    pub fn apply(&self, left: i64, right: i64) -> Option<i64> {
+        match self {
+            ArithmeticOp::Add => left.checked_add(right),
+            ArithmeticOp::Sub => left.checked_sub(right),
+            ArithmeticOp::Mul => left.checked_mul(right),
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -136,7 +162,20 @@ pub struct FunctionCall<PrimExpr>
 where
     PrimExpr: Send + Sync,
 {
-    pub name: String,                              // "some", "all", etc
+    pub name: String,                              // "some", "every", or "none"
     pub parameter_name: String,                    // "du"
     pub expr: AccessPredicateExpression<PrimExpr>, // "du.id == AuthContext.id && du.read"
 }
+
+/// A call to a module function that decides access, such as `check("canRead", AuthContext.id)`.
+///
+/// The function returns a boolean or, for subsystems that support it, a residual predicate (such
+/// as a `where` filter for Postgres).
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CheckCall<PrimExpr>
+where
+    PrimExpr: Send + Sync,
+{
+    pub function_name: String, // "canRead"
+    pub args: Vec<PrimExpr>,   // Mapped (in order) to the function's non-injected parameters
+}
@@ -11,6 +11,7 @@ pub mod access;
 pub mod context_type;
 pub mod mapped_arena;
 pub mod primitive_type;
+pub mod transaction;
 
 pub mod type_normalization;
 pub mod types;
@@ -0,0 +1,121 @@
+// Copyright Exograph, Inc. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file at the root of this repository.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! Options for the transaction that executes an operation, declared through the `@transaction`
+//! annotation on a type (for its mutations) or on a module method:
+//! ```no_rust
+//! @postgres
+//! module InventoryDatabase {
+//!   @transaction(isolation="serializable", timeout="5s")
+//!   type Product {
+//!     ...
+//!   }
+//! }
+//!
+//! @deno("reports.ts")
+//! module Reports {
+//!   @transaction(isolation="repeatable read", timeout="2min")
+//!   query salesReport(@inject exograph: Exograph): String
+//! }
+//! ```
+
+use std::{str::FromStr, time::Duration};
+
+use serde::{Deserialize, Serialize};
+
+/// Transaction isolation levels (ordered from the least to the most strict)
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum IsolationLevel {
+    ReadCommitted,
+    RepeatableRead,
+    Serializable,
+}
+
+impl FromStr for IsolationLevel {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "read committed" => Ok(IsolationLevel::ReadCommitted),
+            "repeatable read" => Ok(IsolationLevel::RepeatableRead),
+            "serializable" => Ok(IsolationLevel::Serializable),
+            _ => Err(format!(
+                "Invalid isolation level `{s}`. Must be one of `read committed`, `repeatable read`, or `serializable`"
+            )),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct TransactionOptions {
+    /// The isolation level (the database's default, if not specified)
+    pub isolation_level: Option<IsolationLevel>,
+    /// The maximum duration of each statement (no limit, if not specified)
+    pub timeout: Option<Duration>,
+}
+
+/// Parse a duration such as `500ms`, `5s`, `2min`, or `1h` (the units Postgres accepts for
+/// `statement_timeout`). A number without a unit is in milliseconds.
+pub fn parse_duration(s: &str) -> Result<Duration, String> {
+    let s = s.trim();
+    let unit_start = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    let (value, unit) = s.split_at(unit_start);
+
+    let value: u64 = value
+        .parse()
+        .map_err(|_| format!("Invalid duration `{s}`"))?;
+
+    let millis_per_unit = match unit.trim() {
+        "" | "ms" => 1,
+        "s" => 1000,
+        "min" => 60 * 1000,
+        "h" => 60 * 60 * 1000,
+        _ => return Err(format!(
+            "Invalid duration `{s}`. Must be a number followed by one of `ms`, `s`, `min`, or `h`"
+        )),
+    };
+
+    Ok(Duration::from_millis(value * millis_per_unit))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use multiplatform_test::multiplatform_test;
+
+    #[multiplatform_test]
+    fn isolation_levels() {
+        assert_eq!(
+            "serializable".parse::<IsolationLevel>(),
+            Ok(IsolationLevel::Serializable)
+        );
+        assert_eq!(
+            "repeatable read".parse::<IsolationLevel>(),
+            Ok(IsolationLevel::RepeatableRead)
+        );
+        assert!("snapshot".parse::<IsolationLevel>().is_err());
+
+        assert!(IsolationLevel::ReadCommitted < IsolationLevel::RepeatableRead);
+        assert!(IsolationLevel::RepeatableRead < IsolationLevel::Serializable);
+    }
+
+    #[multiplatform_test]
+    fn durations() {
+        assert_eq!(parse_duration("250"), Ok(Duration::from_millis(250)));
+        assert_eq!(parse_duration("250ms"), Ok(Duration::from_millis(250)));
+        assert_eq!(parse_duration("5s"), Ok(Duration::from_secs(5)));
+        assert_eq!(parse_duration("2min"), Ok(Duration::from_secs(120)));
+        assert_eq!(parse_duration("1h"), Ok(Duration::from_secs(3600)));
+
+        assert!(parse_duration("").is_err());
+        assert!(parse_duration("s").is_err());
+        assert!(parse_duration("5 days").is_err());
+        assert!(parse_duration("-5s").is_err());
+    }
+}
@@ -12,7 +12,7 @@ pub enum TrustedDocuments {
     MatchingOnly(HashMap<String, String>),
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 pub enum TrustedDocumentEnforcement {
     Enforce,
     DoNotEnforce,
@@ -8,8 +8,9 @@
 // by the Apache License, Version 2.0.
 
 use async_trait::async_trait;
+use chrono::{DateTime, SecondsFormat, Utc};
 use core_model::access::{
-    AccessLogicalExpression, AccessPredicateExpression, AccessRelationalOp,
+    AccessLogicalExpression, AccessPredicateExpression, AccessRelationalOp, ArithmeticOp,
     CommonAccessPrimitiveExpression,
 };
 use thiserror::Error;
@@ -18,6 +19,7 @@ use crate::{
     context::{ContextExtractionError, RequestContext},
     context_extractor::ContextExtractor,
     number_cmp::NumberWrapper,
+    system_resolver::SystemResolutionError,
     value::Val,
 };
 
@@ -34,6 +36,9 @@ pub enum AccessSolverError {
     #[error("{0}")]
     ContextExtraction(#[from] ContextExtractionError),
 
+    #[error("{0}")]
+    AccessCheck(#[from] SystemResolutionError),
+
     #[error("{0}")]
     Generic(Box<dyn std::error::Error + Send + Sync>),
 }
@@ -149,6 +154,9 @@ pub async fn reduce_common_primitive_expression<'a>(
         CommonAccessPrimitiveExpression::StringLiteral(value) => Some(Val::String(value.clone())),
         CommonAccessPrimitiveExpression::BooleanLiteral(value) => Some(Val::Bool(*value)),
         CommonAccessPrimitiveExpression::NumberLiteral(value) => Some(Val::Number((*value).into())),
+        CommonAccessPrimitiveExpression::Now => Some(Val::String(
+            Utc::now().to_rfc3339_opts(SecondsFormat::Micros, true),
+        )),
     })
 }
 
@@ -175,12 +183,21 @@ pub fn in_values(left_value: &Val, right_value: &Val) -> bool {
     }
 }
 
+pub fn contains_values(left_value: &Val, right_value: &Val) -> bool {
+    match (left_value, right_value) {
+        (Val::String(left), Val::String(right)) => left.contains(right.as_str()),
+        (Val::List(values), value) => values.iter().any(|v| eq_values(v, value)),
+        _ => false,
+    }
+}
+
 pub fn lt_values(left_value: &Val, right_value: &Val) -> bool {
     match (left_value, right_value) {
         (Val::Number(left_number), Val::Number(right_number)) => {
             NumberWrapper(left_number.clone()) < NumberWrapper(right_number.clone())
         }
-        _ => unreachable!("The operands of `<` operator must be numbers"),
+        (Val::String(left), Val::String(right)) => compare_strings(left, right).is_lt(),
+        _ => unreachable!("The operands of `<` operator must be numbers or strings"),
     }
 }
 
@@ -189,7 +206,31 @@ pub fn lte_values(left_value: &Val, right_value: &Val) -> bool {
         (Val::Number(left_number), Val::Number(right_number)) => {
             NumberWrapper(left_number.clone()) <= NumberWrapper(right_number.clone())
         }
-        _ => unreachable!("The operands of `<=` operator must be numbers"),
+        (Val::String(left), Val::String(right)) => compare_strings(left, right).is_le(),
+        _ => unreachable!("The operands of `<=` operator must be numbers or strings"),
+    }
+}
+
+/// Compare strings, treating them as timestamps if possible (so that, for example, comparing to
+/// `now()` doesn't depend on the timezone offset or the precision of the timestamps)
+fn compare_strings(left: &str, right: &str) -> std::cmp::Ordering {
+    match (
+        DateTime::parse_from_rfc3339(left),
+        DateTime::parse_from_rfc3339(right),
+    ) {
+        (Ok(left), Ok(right)) => left.cmp(&right),
+        _ => left.cmp(right),
+    }
+}
+
+/// Apply an arithmetic operator to two integer values. Returns `None` if either value isn't an
+/// integer or the result overflows.
+pub fn arithmetic_values(op: ArithmeticOp, left_value: &Val, right_value: &Val) -> Option<Val> {
+    match (left_value, right_value) {
+        (Val::Number(left), Val::Number(right)) => op
+            .apply(left.as_i64()?, right.as_i64()?)
+            .map(|result| Val::Number(result.into())),
+        _ => None,
     }
 }
 
@@ -200,3 +241,65 @@ pub fn gt_values(left_value: &Val, right_value: &Val) -> bool {
 pub fn gte_values(left_value: &Val, right_value: &Val) -> bool {
     !lt_values(left_value, right_value)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn contains() {
+        let string = |s: &str| Val::String(s.to_string());
+
+        assert!(contains_values(&string("draft: intro"), &string("draft")));
+        assert!(!contains_values(&string("intro"), &string("draft")));
+
+        let roles = Val::List(vec![string("admin"), string("editor")]);
+        assert!(contains_values(&roles, &string("editor")));
+        assert!(!contains_values(&roles, &string("viewer")));
+    }
+
+    #[test]
+    fn timestamp_comparison() {
+        let string = |s: &str| Val::String(s.to_string());
+
+        // Same instant in different offsets and precisions
+        assert!(lte_values(
+            &string("2024-01-01T10:00:00Z"),
+            &string("2024-01-01T12:00:00.000+02:00")
+        ));
+        assert!(!lt_values(
+            &string("2024-01-01T10:00:00Z"),
+            &string("2024-01-01T12:00:00.000+02:00")
+        ));
+        assert!(lt_values(
+            &string("2024-01-01T10:00:00Z"),
+            &string("2024-01-01T10:00:00.5Z")
+        ));
+    }
+
+    #[test]
+    fn arithmetic() {
+        let number = |n: i64| Val::Number(n.into());
+
+        assert_eq!(
+            arithmetic_values(ArithmeticOp::Add, &number(2), &number(3)),
+            Some(number(5))
+        );
+        assert_eq!(
+            arithmetic_values(ArithmeticOp::Sub, &number(2), &number(3)),
+            Some(number(-1))
+        );
+        assert_eq!(
+            arithmetic_values(ArithmeticOp::Mul, &number(2), &number(3)),
+            Some(number(6))
+        );
+        assert_eq!(
+            arithmetic_values(ArithmeticOp::Mul, &number(i64::MAX), &number(2)),
+            None
+        );
+        assert_eq!(
+            arithmetic_values(ArithmeticOp::Add, &Val::String("2".to_string()), &number(3)),
+            None
+        );
+    }
+}
@@ -0,0 +1,236 @@
+// Copyright Exograph, Inc. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file at the root of this repository.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! Records of access control decisions made while resolving a request.
+//!
+//! Subsystem resolvers record each access rule they evaluate (through
+//! [`RequestContext::record_access`](crate::context::RequestContext::record_access)). The
+//! records are reported:
+//! - in the `accessTrace` extension of the response, if the request asks for it (by setting
+//!   `extensions.accessTrace` to `true`) and the server isn't running in production mode
+//! - as audit log events (with the `exograph::audit` target), if `EXO_ACCESS_AUDIT_LOG` is set
+//!   to `true` (with the values of only the context keys listed in `EXO_ACCESS_AUDIT_LOG_CONTEXT`)
+//!
+//! Subsystems also declare the rules they may record (through
+//! [`SubsystemResolver::access_rules`](crate::plugin::SubsystemResolver::access_rules)), so that
+//! `exo test --coverage` can report the rules that tests don't exercise.
+
+use std::{
+    collections::HashSet,
+    sync::{Arc, Mutex},
+};
+
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+/// An evaluated access rule
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccessTraceEntry {
+    /// The type, field (such as `Document.content`), or operation whose rule was evaluated
+    pub rule: String,
+    /// The kind of access such as `query`, `create`, `update`, `delete`, or `mutation`
+    pub operation: String,
+    pub decision: AccessDecision,
+    /// The residual predicate that filters the result (for [`AccessDecision::Filtered`])
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub residual: Option<String>,
+}
+
+impl AccessTraceEntry {
+    pub fn access_rule(&self) -> AccessRule {
+        AccessRule {
+            rule: self.rule.clone(),
+            operation: self.operation.clone(),
+        }
+    }
+}
+
+/// An access rule that a subsystem may evaluate (identified the same way as in
+/// [`AccessTraceEntry`])
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+pub struct AccessRule {
+    pub rule: String,
+    pub operation: String,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum AccessDecision {
+    Allowed,
+    Denied,
+    /// Allowed, but only for the objects that match a residual predicate
+    Filtered,
+}
+
+/// The trace of access decisions for a request (shared so that the response can include it after
+/// the request context is done)
+#[derive(Clone, Default)]
+pub struct AccessTrace(Arc<Mutex<Option<Vec<AccessTraceEntry>>>>);
+
+impl AccessTrace {
+    /// Start recording entries (by default, the trace doesn't record anything)
+    pub fn enable(&self) {
+        let mut entries = self.0.lock().unwrap();
+        if entries.is_none() {
+            *entries = Some(vec![]);
+        }
+    }
+
+    pub fn record(&self, entry: AccessTraceEntry) {
+        if let Some(entries) = self.0.lock().unwrap().as_mut() {
+            entries.push(entry);
+        }
+    }
+
+    /// Discard the recorded entries (for example, of a request attempt that will be retried),
+    /// while continuing to record if enabled
+    pub fn clear(&self) {
+        if let Some(entries) = self.0.lock().unwrap().as_mut() {
+            entries.clear();
+        }
+    }
+
+    /// Take the recorded entries (`None` if the trace isn't enabled)
+    pub fn take(&self) -> Option<Vec<AccessTraceEntry>> {
+        self.0.lock().unwrap().take()
+    }
+}
+
+/// The context values used by a request (keyed by "annotation.key", for example "jwt.sub") to
+/// include in audit log events. Only the values of the logged keys appear as is; the rest are
+/// redacted, since contexts may carry secrets or personal data (such as cookies or emails).
+pub(crate) struct AuditContext {
+    logged_keys: HashSet<String>,
+    values: Mutex<Map<String, Value>>,
+}
+
+impl AuditContext {
+    /// Create an audit context that logs the values of the keys in a comma-separated list
+    pub fn new(logged_keys: Option<&str>) -> Self {
+        Self {
+            logged_keys: logged_keys
+                .into_iter()
+                .flat_map(|keys| keys.split(','))
+                .map(|key| key.trim().to_string())
+                .filter(|key| !key.is_empty())
+                .collect(),
+            values: Mutex::new(Map::new()),
+        }
+    }
+
+    pub fn record(&self, key: String, value: Value) {
+        let value = if self.logged_keys.contains(&key) {
+            value
+        } else {
+            Value::String("<redacted>".to_string())
+        };
+
+        self.values.lock().unwrap().insert(key, value);
+    }
+
+    pub fn to_value(&self) -> Value {
+        Value::Object(self.values.lock().unwrap().clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(rule: &str) -> AccessTraceEntry {
+        AccessTraceEntry {
+            rule: rule.to_string(),
+            operation: "query".to_string(),
+            decision: AccessDecision::Allowed,
+            residual: None,
+        }
+    }
+
+    #[test]
+    fn records_only_when_enabled() {
+        let trace = AccessTrace::default();
+        trace.record(entry("Concert"));
+        assert!(trace.take().is_none());
+
+        trace.enable();
+        trace.clone().record(entry("Venue"));
+
+        let entries = trace.take().unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].rule, "Venue");
+        assert!(trace.take().is_none());
+    }
+
+    #[test]
+    fn clear_keeps_recording() {
+        let trace = AccessTrace::default();
+        trace.clear();
+        assert!(trace.take().is_none());
+
+        trace.enable();
+        trace.record(entry("Concert"));
+        trace.clear();
+        trace.record(entry("Venue"));
+
+        let entries = trace.take().unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].rule, "Venue");
+    }
+
+    #[test]
+    fn serialize() {
+        let filtered = AccessTraceEntry {
+            rule: "Document".to_string(),
+            operation: "query".to_string(),
+            decision: AccessDecision::Filtered,
+            residual: Some("documents.owner_id = 42".to_string()),
+        };
+
+        assert_eq!(
+            serde_json::to_value([entry("Concert"), filtered]).unwrap(),
+            serde_json::json!([
+                {"rule": "Concert", "operation": "query", "decision": "allowed"},
+                {
+                    "rule": "Document",
+                    "operation": "query",
+                    "decision": "filtered",
+                    "residual": "documents.owner_id = 42"
+                }
+            ])
+        );
+    }
+
+    #[test]
+    fn audit_context_redacts_unlisted_keys() {
+        let context = AuditContext::new(Some("jwt.sub, jwt.role"));
+        context.record("jwt.sub".to_string(), serde_json::json!("42"));
+        context.record(
+            "jwt.email".to_string(),
+            serde_json::json!("jane@example.com"),
+        );
+        context.record("cookie.session".to_string(), serde_json::json!("secret"));
+
+        assert_eq!(
+            context.to_value(),
+            serde_json::json!({
+                "jwt.sub": "42",
+                "jwt.email": "<redacted>",
+                "cookie.session": "<redacted>"
+            })
+        );
+
+        let context = AuditContext::new(None);
+        context.record("jwt.sub".to_string(), serde_json::json!("42"));
+        assert_eq!(
+            context.to_value(),
+            serde_json::json!({"jwt.sub": "<redacted>"})
+        );
+    }
+}
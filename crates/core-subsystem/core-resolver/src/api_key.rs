@@ -0,0 +1,180 @@
+// Copyright Exograph, Inc. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file at the root of this repository.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! Support for API key authentication (the `@apiKey` context annotation).
+//!
+//! We never store keys themselves, only their SHA-256 hashes (hex-encoded), along with
+//! attributes such as the owner and scopes, which become available as context fields. Keys are
+//! stored in either a JSON file ([ApiKeyFile]) or a database table offered by a subsystem (see
+//! `SubsystemResolver::api_key_store`).
+//!
+//! Since a key may have an expiration time, rotating a key amounts to adding a new key and setting
+//! the expiration of the old key to allow clients some time to switch over.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+
+/// The attribute holding the hash of the key
+pub const KEY_HASH_ATTRIBUTE: &str = "key_hash";
+/// The (optional) attribute holding the expiration time of the key
+pub const EXPIRES_AT_ATTRIBUTE: &str = "expires_at";
+
+#[async_trait]
+pub trait ApiKeyStore: Send + Sync {
+    /// Find an unexpired key with the given hash. Returns the attributes of the key (which become
+    /// `@apiKey` context fields).
+    async fn lookup(&self, key_hash: &str) -> Result<Option<Map<String, Value>>, ApiKeyError>;
+}
+
+/// The hash under which a key is stored
+pub fn hash_api_key(key: &str) -> String {
+    hex::encode(Sha256::digest(key.as_bytes()))
+}
+
+/// Keys loaded from a JSON file with an array of objects such as:
+///
+/// ```json
+/// [{"key_hash": "9f86d0...", "owner": "billing-service", "scopes": ["invoices:read"], "expires_at": "2025-01-01T00:00:00Z"}]
+/// ```
+pub struct ApiKeyFile {
+    keys: Vec<Map<String, Value>>,
+}
+
+impl ApiKeyFile {
+    pub fn new(content: &str) -> Result<Self, ApiKeyError> {
+        let keys: Vec<Map<String, Value>> = serde_json::from_str(content)
+            .map_err(|e| ApiKeyError::Config(format!("Invalid API key file: {e}")))?;
+
+        for key in &keys {
+            if !matches!(key.get(KEY_HASH_ATTRIBUTE), Some(Value::String(_))) {
+                return Err(ApiKeyError::Config(format!(
+                    "Each API key must have a `{KEY_HASH_ATTRIBUTE}` string"
+                )));
+            }
+
+            expires_at(key)?;
+        }
+
+        Ok(Self { keys })
+    }
+
+    pub fn load(path: &str) -> Result<Self, ApiKeyError> {
+        let content = std::fs::read_to_string(path).map_err(|e| {
+            ApiKeyError::Config(format!("Unable to read the API key file {path}: {e}"))
+        })?;
+
+        Self::new(&content)
+    }
+}
+
+#[async_trait]
+impl ApiKeyStore for ApiKeyFile {
+    async fn lookup(&self, key_hash: &str) -> Result<Option<Map<String, Value>>, ApiKeyError> {
+        let now = Utc::now();
+
+        Ok(self
+            .keys
+            .iter()
+            .find(|key| {
+                // We validated expiration times when loading the file
+                key.get(KEY_HASH_ATTRIBUTE).and_then(Value::as_str) == Some(key_hash)
+                    && expires_at(key)
+                        .ok()
+                        .flatten()
+                        .map_or(true, |expires_at| expires_at > now)
+            })
+            .cloned())
+    }
+}
+
+fn expires_at(key: &Map<String, Value>) -> Result<Option<DateTime<Utc>>, ApiKeyError> {
+    match key.get(EXPIRES_AT_ATTRIBUTE) {
+        None | Some(Value::Null) => Ok(None),
+        Some(Value::String(value)) => DateTime::parse_from_rfc3339(value)
+            .map(|value| Some(value.with_timezone(&Utc)))
+            .map_err(|_| invalid_expiration(value)),
+        Some(value) => Err(invalid_expiration(&value.to_string())),
+    }
+}
+
+fn invalid_expiration(value: &str) -> ApiKeyError {
+    ApiKeyError::Config(format!(
+        "Invalid `{EXPIRES_AT_ATTRIBUTE}` value {value} for an API key (expected an RFC 3339 timestamp)"
+    ))
+}
+
+#[derive(Error, Debug)]
+pub enum ApiKeyError {
+    #[error("{0}")]
+    Config(String),
+
+    #[error("{0}")]
+    Delegate(#[source] Box<dyn std::error::Error + Send + Sync + 'static>),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hash() {
+        assert_eq!(
+            hash_api_key("test"),
+            "9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08"
+        );
+    }
+
+    #[tokio::test]
+    async fn file_lookup() {
+        let file = ApiKeyFile::new(&format!(
+            r#"[
+                {{"key_hash": "{}", "owner": "billing", "scopes": ["invoices:read"]}},
+                {{"key_hash": "{}", "owner": "reporting", "expires_at": "2000-01-01T00:00:00Z"}},
+                {{"key_hash": "{}", "owner": "reporting", "expires_at": "2100-01-01T00:00:00Z"}}
+            ]"#,
+            hash_api_key("current"),
+            hash_api_key("expired"),
+            hash_api_key("rotated")
+        ))
+        .unwrap();
+
+        let billing = file
+            .lookup(&hash_api_key("current"))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(billing["owner"], "billing");
+        assert_eq!(billing["scopes"], serde_json::json!(["invoices:read"]));
+
+        assert!(file
+            .lookup(&hash_api_key("expired"))
+            .await
+            .unwrap()
+            .is_none());
+        assert!(file
+            .lookup(&hash_api_key("rotated"))
+            .await
+            .unwrap()
+            .is_some());
+        assert!(file
+            .lookup(&hash_api_key("unknown"))
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn invalid_file() {
+        assert!(ApiKeyFile::new(r#"[{"owner": "billing"}]"#).is_err());
+        assert!(ApiKeyFile::new(r#"[{"key_hash": "abcd", "expires_at": "tomorrow"}]"#).is_err());
+    }
+}
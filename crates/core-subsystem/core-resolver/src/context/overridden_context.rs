@@ -7,7 +7,9 @@
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
 
+use core_model::transaction::TransactionOptions;
 use elsa::sync::FrozenMap;
+use exo_sql::database_error::DatabaseError;
 use serde_json::Value;
 
 use crate::value::Val;
@@ -74,4 +76,11 @@ impl<'a> OverriddenContext<'a> {
     pub async fn ensure_transaction(&self) {
         self.base_context.ensure_transaction().await;
     }
+
+    pub async fn set_transaction_options(
+        &self,
+        options: &TransactionOptions,
+    ) -> Result<(), DatabaseError> {
+        self.base_context.set_transaction_options(options).await
+    }
 }
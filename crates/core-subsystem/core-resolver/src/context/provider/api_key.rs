@@ -0,0 +1,94 @@
+// Copyright Exograph, Inc. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file at the root of this repository.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use async_trait::async_trait;
+use common::env_const::{EXO_API_KEYS_FILE, EXO_API_KEYS_TABLE, EXO_API_KEY_HEADER};
+use serde_json::{Map, Value};
+use tokio::sync::OnceCell;
+use tracing::{error, warn};
+
+use crate::api_key::hash_api_key;
+use crate::context::context_extractor::ContextExtractor;
+use crate::context::error::ContextExtractionError;
+use crate::context::request::Request;
+use crate::context::RequestContext;
+use crate::system_resolver::SystemResolver;
+
+const DEFAULT_API_KEY_HEADER: &str = "X-API-Key";
+
+pub struct ApiKeyExtractor<'a> {
+    system_resolver: &'a SystemResolver,
+    // Use OnceCell to look up the key only once per request (and not per `@apiKey` field)
+    extracted_key: OnceCell<Option<Map<String, Value>>>,
+}
+
+impl<'a> ApiKeyExtractor<'a> {
+    pub fn new(system_resolver: &'a SystemResolver) -> Self {
+        Self {
+            system_resolver,
+            extracted_key: OnceCell::new(),
+        }
+    }
+
+    /// Look up the key passed in the request. Returns `None` if the request has no key (which
+    /// indicates an anonymous client), but fails if the key is unknown or expired.
+    async fn extract_key(
+        &self,
+        request: &(dyn Request + Send + Sync),
+    ) -> Result<Option<Map<String, Value>>, ContextExtractionError> {
+        let header = self
+            .system_resolver
+            .env
+            .get(EXO_API_KEY_HEADER)
+            .unwrap_or_else(|| DEFAULT_API_KEY_HEADER.to_string());
+
+        let Some(key) = request.get_header(&header) else {
+            return Ok(None);
+        };
+
+        let Some(store) = self.system_resolver.api_key_store() else {
+            warn!(
+                "Neither {} nor {} is set, not validating API keys",
+                EXO_API_KEYS_FILE, EXO_API_KEYS_TABLE
+            );
+            return Ok(None);
+        };
+
+        match store.lookup(&hash_api_key(&key)).await {
+            Ok(Some(attributes)) => Ok(Some(attributes)),
+            Ok(None) => Err(ContextExtractionError::Unauthorized),
+            Err(err) => {
+                error!("Error validating API key: {}", err);
+                Err(ContextExtractionError::Unauthorized)
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl ContextExtractor for ApiKeyExtractor<'_> {
+    fn annotation_name(&self) -> &str {
+        "apiKey"
+    }
+
+    async fn extract_context_field(
+        &self,
+        key: &str,
+        _request_context: &RequestContext,
+        request: &(dyn Request + Send + Sync),
+    ) -> Result<Option<Value>, ContextExtractionError> {
+        Ok(self
+            .extracted_key
+            .get_or_try_init(|| self.extract_key(request))
+            .await?
+            .as_ref()
+            .and_then(|attributes| attributes.get(key))
+            .cloned())
+    }
+}
@@ -36,12 +36,15 @@ impl CookieExtractor {
     ) -> Result<HashMap<String, Value>, ContextExtractionError> {
         let cookie_headers = request.get_headers("cookie");
 
+        // A header may hold multiple cookies (for example, `theme=dark; session=abcd`)
         let cookie_strings = cookie_headers
-            .into_iter()
-            .map(|header| header.split(';').collect());
+            .iter()
+            .flat_map(|header| header.split(';'))
+            .map(str::trim)
+            .filter(|cookie_string| !cookie_string.is_empty());
 
         let cookies = cookie_strings
-            .map(|cookie_string: String| {
+            .map(|cookie_string| {
                 Cookie::parse(cookie_string)
                     .map(|cookie| (cookie.name().to_owned(), cookie.value().to_owned().into()))
                     .map_err(|_| ContextExtractionError::Malformed)
@@ -1,6 +1,9 @@
-use common::env_const::EXO_JWT_SECRET;
 #[cfg(feature = "oidc")]
 use common::env_const::EXO_OIDC_URL;
+use common::env_const::{
+    EXO_JWT_AUDIENCE, EXO_JWT_ISSUER, EXO_JWT_JWKS_FILE, EXO_JWT_LEEWAY, EXO_JWT_PUBLIC_KEY_FILE,
+    EXO_JWT_SECRET, EXO_JWT_SOURCES,
+};
 use exo_env::Environment;
 use jsonwebtoken::{decode, DecodingKey, Validation};
 use serde_json::Value;
@@ -13,11 +16,25 @@ use crate::context::request::Request;
 
 #[cfg(feature = "oidc")]
 use super::oidc::Oidc;
+use super::static_key::StaticKeys;
+use super::token_source::{extract_token, TokenSource};
+
+/// Clock skew (in seconds) to allow when validating the `exp` and `nbf` claims
+const DEFAULT_LEEWAY: u64 = 60;
 
 /// Authenticator with information about how to validate JWT tokens
-/// It can be either a secret or a OIDC url
-pub enum JwtAuthenticator {
-    Secret(String),
+///
+/// It may trust multiple issuers at once (for example, an internal identity provider and an OIDC
+/// provider while migrating from one to the other). We validate a token only against verifiers
+/// that accept its issuer.
+pub struct JwtAuthenticator {
+    verifiers: Vec<JwtVerifier>,
+    sources: Vec<TokenSource>,
+}
+
+enum JwtVerifier {
+    /// A secret or public keys known upfront
+    Static(StaticKeys),
     #[cfg(feature = "oidc")]
     Oidc(Oidc),
 }
@@ -48,25 +65,55 @@ impl JwtAuthenticator {
     pub async fn new_from_env(
         env: &dyn Environment,
     ) -> Result<Option<Self>, JwtConfigurationError> {
-        let secret = env.get(EXO_JWT_SECRET);
+        let issuers = list_env(env, EXO_JWT_ISSUER);
+        let audiences = list_env(env, EXO_JWT_AUDIENCE);
+        let leeway = match env.get(EXO_JWT_LEEWAY) {
+            Some(leeway) => leeway.parse().map_err(|_| {
+                JwtConfigurationError::InvalidSetup(format!(
+                    "{EXO_JWT_LEEWAY} must be a non-negative number of seconds"
+                ))
+            })?,
+            None => DEFAULT_LEEWAY,
+        };
+
+        let mut verifiers = vec![];
+
+        let mut static_keys = StaticKeys::new(issuers.clone(), audiences.as_deref(), leeway);
+
+        if let Some(secret) = env.get(EXO_JWT_SECRET) {
+            static_keys.add_secret(&secret);
+        }
+        for path in list_env(env, EXO_JWT_PUBLIC_KEY_FILE).unwrap_or_default() {
+            static_keys.add_public_key(read_key_file(&path)?.as_bytes())?;
+        }
+        for path in list_env(env, EXO_JWT_JWKS_FILE).unwrap_or_default() {
+            static_keys.add_jwks(&read_key_file(&path)?)?;
+        }
+
+        if !static_keys.is_empty() {
+            verifiers.push(JwtVerifier::Static(static_keys));
+        } else if issuers.is_some() {
+            return Err(JwtConfigurationError::InvalidSetup(format!(
+                "{EXO_JWT_ISSUER} is set, but none of {EXO_JWT_SECRET}, {EXO_JWT_PUBLIC_KEY_FILE}, or {EXO_JWT_JWKS_FILE} is set"
+            )));
+        }
 
         #[cfg(feature = "oidc")]
-        let oidc_url = env.get(EXO_OIDC_URL);
+        for oidc_url in list_env(env, EXO_OIDC_URL).unwrap_or_default() {
+            verifiers.push(JwtVerifier::Oidc(
+                Oidc::new(oidc_url, audiences.as_deref(), leeway).await?,
+            ));
+        }
 
-        #[cfg(not(feature = "oidc"))]
-        let oidc_url: Option<String> = None;
+        let sources = match env.get(EXO_JWT_SOURCES) {
+            Some(sources) => TokenSource::parse_list(&sources)?,
+            None => TokenSource::default_sources(),
+        };
 
-        match (secret, oidc_url) {
-            (Some(secret), None) => Ok(Some(JwtAuthenticator::Secret(secret))),
-            #[cfg(feature = "oidc")]
-            (None, Some(oidc_url)) => Ok(Some(JwtAuthenticator::Oidc(Oidc::new(oidc_url).await?))),
-            #[cfg(feature = "oidc")]
-            (Some(_), Some(_)) => {
-                Err(JwtConfigurationError::InvalidSetup(format!("Both {EXO_JWT_SECRET} and {EXO_OIDC_URL} are set. Only one of them can be set at a time")))
-            }
-            (None, None) => Ok(None),
-            #[cfg(not(feature = "oidc"))]
-            (_, Some(_)) => unreachable!()
+        if verifiers.is_empty() {
+            Ok(None)
+        } else {
+            Ok(Some(JwtAuthenticator { verifiers, sources }))
         }
     }
 
@@ -80,35 +127,53 @@ impl JwtAuthenticator {
             }
         }
 
-        match self {
-            JwtAuthenticator::Secret(secret) => Ok(decode::<Value>(
-                token,
-                &DecodingKey::from_secret(secret.as_ref()),
-                &Validation::default(),
-            )
-            .map_err(map_jwt_error)?
-            .claims),
-            #[cfg(feature = "oidc")]
-            JwtAuthenticator::Oidc(oidc) => oidc.validate(token).await.map_err(|err| match err {
-                oidc_jwt_validator::ValidationError::ValidationFailed(err) => map_jwt_error(err),
-                err => {
-                    error!("Error validating JWT: {}", err);
-                    JwtAuthenticationError::Invalid
+        let issuer = unverified_issuer(token);
+
+        let mut result = Err(JwtAuthenticationError::Invalid);
+
+        for verifier in self
+            .verifiers
+            .iter()
+            .filter(|verifier| verifier.accepts_issuer(issuer.as_deref()))
+        {
+            let verifier_result = match verifier {
+                JwtVerifier::Static(keys) => keys.validate(token).map_err(map_jwt_error),
+                #[cfg(feature = "oidc")]
+                JwtVerifier::Oidc(oidc) => oidc.validate(token).await.map_err(|err| match err {
+                    oidc_jwt_validator::ValidationError::ValidationFailed(err) => {
+                        map_jwt_error(err)
+                    }
+                    err => {
+                        error!("Error validating JWT: {}", err);
+                        JwtAuthenticationError::Invalid
+                    }
+                }),
+            };
+
+            match verifier_result {
+                Ok(claims) => return Ok(claims),
+                // Report the token as expired if any of the verifiers found it so (the client
+                // may then refresh the token)
+                Err(err) => {
+                    if !matches!(result, Err(JwtAuthenticationError::Expired)) {
+                        result = Err(err)
+                    }
                 }
-            }),
+            }
         }
+
+        result
     }
 
-    /// Extract authentication form the "Authorization" header with a bearer token
+    /// Extract authentication from the first configured source (by default, the "Authorization"
+    /// header with a bearer token) that has a token.
     /// The claim is deserialized into an opaque json `Value`, which will be eventually be mapped to
     /// the declared user context model
     pub(super) async fn extract_authentication(
         &self,
         request: &(dyn Request + Send + Sync),
     ) -> Result<Value, ContextExtractionError> {
-        let jwt_token = request
-            .get_header("Authorization")
-            .and_then(|auth_token| auth_token.strip_prefix("Bearer ").map(|t| t.to_owned()));
+        let jwt_token = extract_token(&self.sources, request)?;
 
         match jwt_token {
             Some(jwt_token) => self
@@ -125,11 +190,57 @@ impl JwtAuthenticator {
                     }
                 }),
             None => {
-                // Either the "Authorization" header was absent or the next token wasn't "Bearer"
-                // It is not an error to have no authorization header, since that indicates an anonymous user
+                // None of the sources had a token (for example, the "Authorization" header was
+                // absent or the next token wasn't "Bearer").
+                // It is not an error to have no token, since that indicates an anonymous user
                 // and there may be queries allowed for such users.
                 Ok(serde_json::Value::Null)
             }
         }
     }
 }
+
+impl JwtVerifier {
+    fn accepts_issuer(&self, issuer: Option<&str>) -> bool {
+        match self {
+            JwtVerifier::Static(keys) => keys.accepts_issuer(issuer),
+            #[cfg(feature = "oidc")]
+            JwtVerifier::Oidc(oidc) => oidc.accepts_issuer(issuer),
+        }
+    }
+}
+
+/// The `iss` claim of the token (without validating the token), so that we can pick the verifiers
+/// to validate it with
+fn unverified_issuer(token: &str) -> Option<String> {
+    let mut validation = Validation::default();
+    validation.insecure_disable_signature_validation();
+    validation.validate_exp = false;
+    validation.validate_aud = false;
+    validation.required_spec_claims.clear();
+
+    decode::<Value>(token, &DecodingKey::from_secret(&[]), &validation)
+        .ok()?
+        .claims
+        .get("iss")?
+        .as_str()
+        .map(|issuer| issuer.to_owned())
+}
+
+/// A comma-separated list from an env variable
+fn list_env(env: &dyn Environment, key: &str) -> Option<Vec<String>> {
+    env.get(key).map(|value| {
+        value
+            .split(',')
+            .map(|item| item.trim().to_owned())
+            .filter(|item| !item.is_empty())
+            .collect()
+    })
+}
+
+fn read_key_file(path: &str) -> Result<String, JwtConfigurationError> {
+    std::fs::read_to_string(path).map_err(|e| JwtConfigurationError::Configuration {
+        message: format!("Unable to read the key file {path}"),
+        source: e.into(),
+    })
+}
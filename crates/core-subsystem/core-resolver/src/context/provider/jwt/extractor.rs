@@ -2,7 +2,7 @@ use std::sync::Arc;
 
 use async_trait::async_trait;
 #[cfg(not(target_family = "wasm"))]
-use common::env_const::{EXO_JWT_SECRET, EXO_OIDC_URL};
+use common::env_const::{EXO_JWT_JWKS_FILE, EXO_JWT_PUBLIC_KEY_FILE, EXO_JWT_SECRET, EXO_OIDC_URL};
 use serde_json::Value;
 use tokio::sync::OnceCell;
 use tracing::warn;
@@ -35,12 +35,12 @@ impl JwtExtractor {
             jwt_authenticator.extract_authentication(request).await
         } else {
             #[cfg(target_family = "wasm")]
-            warn!("JWT secret or OIDC URL is not set, not parsing JWT tokens");
+            warn!("JWT secret, public keys, or OIDC URL are not set, not parsing JWT tokens");
 
             #[cfg(not(target_family = "wasm"))]
             warn!(
-                "{} or {} is not set, not parsing JWT tokens",
-                EXO_JWT_SECRET, EXO_OIDC_URL
+                "None of {}, {}, {}, or {} is set, not parsing JWT tokens",
+                EXO_JWT_SECRET, EXO_JWT_PUBLIC_KEY_FILE, EXO_JWT_JWKS_FILE, EXO_OIDC_URL
             );
             Ok(serde_json::Value::Null)
         }
@@ -11,6 +11,8 @@ mod authenticator;
 mod extractor;
 #[cfg(feature = "oidc")]
 mod oidc;
+mod static_key;
+mod token_source;
 
 pub use authenticator::JwtAuthenticator;
 pub use extractor::JwtExtractor;
@@ -5,10 +5,15 @@ use super::authenticator::JwtConfigurationError;
 
 pub struct Oidc {
     validator: Validator,
+    issuers: [String; 2],
 }
 
 impl Oidc {
-    pub(super) async fn new(url: String) -> Result<Self, JwtConfigurationError> {
+    pub(super) async fn new(
+        url: String,
+        audiences: Option<&[String]>,
+        leeway: u64,
+    ) -> Result<Self, JwtConfigurationError> {
         let client = reqwest::ClientBuilder::new().build().map_err(|e| {
             JwtConfigurationError::Configuration {
                 message: "Unable to create HTTP client".to_owned(),
@@ -20,7 +25,13 @@ impl Oidc {
         // The issuer can be either the base URL (for example, Clerk) or the base URL with a trailing slash (for example, Auth0)
         // so we add both to the list of issuers to check.
         let url = url.trim_end_matches('/').to_owned();
-        settings.set_issuer(&[&url, &format!("{url}/")]);
+        let issuers = [url.clone(), format!("{url}/")];
+        settings.set_issuer(&issuers);
+
+        if let Some(audiences) = audiences {
+            settings.set_audience(audiences);
+        }
+        settings.leeway = leeway;
 
         let validator = Validator::new(url, client, Strategy::Automatic, settings)
             .await
@@ -29,7 +40,11 @@ impl Oidc {
                 source: e.into(),
             })?;
 
-        Ok(Self { validator })
+        Ok(Self { validator, issuers })
+    }
+
+    pub(super) fn accepts_issuer(&self, issuer: Option<&str>) -> bool {
+        issuer.is_some_and(|issuer| self.issuers.iter().any(|i| i == issuer))
     }
 
     pub(super) async fn validate(&self, token: &str) -> Result<Value, ValidationError> {
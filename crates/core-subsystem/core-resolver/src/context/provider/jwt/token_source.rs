@@ -0,0 +1,201 @@
+// Copyright Exograph, Inc. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file at the root of this repository.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use crate::context::{
+    error::ContextExtractionError, provider::cookie::CookieExtractor, request::Request,
+};
+
+use super::authenticator::JwtConfigurationError;
+
+/// Where in a request to look for the JWT token
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) enum TokenSource {
+    /// A header such as `Authorization: Bearer <token>` or `X-Session-Token: <token>`
+    Header(String),
+    /// A cookie (typically an HttpOnly cookie set by the application's login endpoint)
+    Cookie(String),
+    /// A query parameter (for clients such as browser WebSockets that cannot set headers)
+    Query(String),
+}
+
+impl TokenSource {
+    pub(super) fn default_sources() -> Vec<TokenSource> {
+        vec![TokenSource::Header("Authorization".to_string())]
+    }
+
+    /// Parse a comma-separated list of sources such as `header:Authorization, cookie:session`
+    pub(super) fn parse_list(list: &str) -> Result<Vec<TokenSource>, JwtConfigurationError> {
+        list.split(',')
+            .map(str::trim)
+            .filter(|source| !source.is_empty())
+            .map(|source| {
+                let (kind, name) = source
+                    .split_once(':')
+                    .map(|(kind, name)| (kind.trim(), name.trim()))
+                    .filter(|(_, name)| !name.is_empty())
+                    .ok_or_else(|| invalid_source(source))?;
+
+                match kind {
+                    "header" => Ok(TokenSource::Header(name.to_string())),
+                    "cookie" => Ok(TokenSource::Cookie(name.to_string())),
+                    "query" => Ok(TokenSource::Query(name.to_string())),
+                    _ => Err(invalid_source(source)),
+                }
+            })
+            .collect()
+    }
+
+    fn extract(
+        &self,
+        request: &(dyn Request + Send + Sync),
+    ) -> Result<Option<String>, ContextExtractionError> {
+        Ok(match self {
+            TokenSource::Header(name) => request.get_header(name).and_then(|value| {
+                match value.strip_prefix("Bearer ") {
+                    Some(token) => Some(token.to_owned()),
+                    // The "Authorization" header may use other schemes (such as "Basic"), which
+                    // aren't JWT tokens. Other headers may hold the token without a scheme.
+                    None if name.eq_ignore_ascii_case("authorization") => None,
+                    None => Some(value),
+                }
+            }),
+            TokenSource::Cookie(name) => CookieExtractor::extract_cookies(request)?
+                .get(name)
+                .and_then(|value| value.as_str())
+                .map(|value| value.to_owned()),
+            TokenSource::Query(name) => request.get_query_param(name),
+        })
+    }
+}
+
+/// The token from the first source that has one
+pub(super) fn extract_token(
+    sources: &[TokenSource],
+    request: &(dyn Request + Send + Sync),
+) -> Result<Option<String>, ContextExtractionError> {
+    for source in sources {
+        if let Some(token) = source.extract(request)? {
+            return Ok(Some(token));
+        }
+    }
+
+    Ok(None)
+}
+
+fn invalid_source(source: &str) -> JwtConfigurationError {
+    JwtConfigurationError::InvalidSetup(format!(
+        "Invalid JWT token source `{source}`. Must be one of `header:<name>`, `cookie:<name>`, or `query:<name>`"
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    struct TestRequest {
+        headers: HashMap<String, String>,
+        query: HashMap<String, String>,
+    }
+
+    impl Request for TestRequest {
+        fn get_headers(&self, key: &str) -> Vec<String> {
+            self.headers.get(key).cloned().into_iter().collect()
+        }
+
+        fn get_query_param(&self, key: &str) -> Option<String> {
+            self.query.get(key).cloned()
+        }
+
+        fn get_ip(&self) -> Option<std::net::IpAddr> {
+            None
+        }
+    }
+
+    fn request(headers: &[(&str, &str)], query: &[(&str, &str)]) -> TestRequest {
+        let to_map = |pairs: &[(&str, &str)]| {
+            pairs
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect()
+        };
+
+        TestRequest {
+            headers: to_map(headers),
+            query: to_map(query),
+        }
+    }
+
+    #[test]
+    fn parse_sources() {
+        assert_eq!(
+            TokenSource::parse_list("header:Authorization, cookie:session,query:token").unwrap(),
+            vec![
+                TokenSource::Header("Authorization".to_string()),
+                TokenSource::Cookie("session".to_string()),
+                TokenSource::Query("token".to_string()),
+            ]
+        );
+
+        assert!(TokenSource::parse_list("session").is_err());
+        assert!(TokenSource::parse_list("body:token").is_err());
+        assert!(TokenSource::parse_list("cookie:").is_err());
+    }
+
+    #[test]
+    fn first_matching_source() {
+        let sources =
+            TokenSource::parse_list("header:Authorization,cookie:session,query:token").unwrap();
+
+        let req = request(
+            &[
+                ("authorization", "Bearer from-header"),
+                ("cookie", "theme=dark; session=from-cookie"),
+            ],
+            &[("token", "from-query")],
+        );
+        assert_eq!(
+            extract_token(&sources, &req).unwrap().as_deref(),
+            Some("from-header")
+        );
+
+        let req = request(
+            &[
+                ("authorization", "Basic dXNlcjpwYXNz"),
+                ("cookie", "theme=dark; session=from-cookie"),
+            ],
+            &[("token", "from-query")],
+        );
+        assert_eq!(
+            extract_token(&sources, &req).unwrap().as_deref(),
+            Some("from-cookie")
+        );
+
+        let req = request(&[], &[("token", "from-query")]);
+        assert_eq!(
+            extract_token(&sources, &req).unwrap().as_deref(),
+            Some("from-query")
+        );
+
+        let req = request(&[], &[]);
+        assert_eq!(extract_token(&sources, &req).unwrap(), None);
+    }
+
+    #[test]
+    fn custom_header() {
+        let sources = TokenSource::parse_list("header:X-Session-Token").unwrap();
+
+        let req = request(&[("x-session-token", "raw-token")], &[]);
+        assert_eq!(
+            extract_token(&sources, &req).unwrap().as_deref(),
+            Some("raw-token")
+        );
+    }
+}
@@ -7,6 +7,7 @@
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
 
+pub(crate) mod api_key;
 pub(crate) mod cookie;
 pub(crate) mod environment;
 pub(crate) mod header;
@@ -48,6 +48,8 @@ impl ContextExtractor for QueryExtractor<'_> {
                     query: Some(query),
                     variables: None,
                     query_hash: None,
+                    access_trace: false,
+                    sql: None,
                 },
                 request_context,
                 TrustedDocumentEnforcement::DoNotEnforce,
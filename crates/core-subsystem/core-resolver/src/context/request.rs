@@ -17,6 +17,12 @@ pub trait Request {
         self.get_headers(&key.to_lowercase()).first().cloned()
     }
 
+    // return the value of a query parameter in the request URL (requests without a URL, such as
+    // those for running jobs, have none)
+    fn get_query_param(&self, _key: &str) -> Option<String> {
+        None
+    }
+
     // return the IP address used to make the request
     fn get_ip(&self) -> Option<std::net::IpAddr>;
 }
@@ -8,8 +8,14 @@
 // by the Apache License, Version 2.0.
 
 use async_recursion::async_recursion;
+use core_model::transaction::TransactionOptions;
+use exo_sql::database_error::DatabaseError;
 
-use crate::{system_resolver::SystemResolver, value::Val};
+use crate::{
+    access_trace::AccessTraceEntry,
+    system_resolver::{SystemResolutionError, SystemResolver},
+    value::Val,
+};
 
 use super::{
     context_extractor::BoxedContextExtractor, error::ContextExtractionError,
@@ -87,6 +93,40 @@ impl<'a> RequestContext<'a> {
         }
     }
 
+    /// Invoke the module function backing a `check(...)` access rule. The result is memoised for
+    /// the rest of the request.
+    pub async fn invoke_access_check(
+        &self,
+        function_name: &str,
+        arguments: &[Val],
+    ) -> Result<Val, SystemResolutionError> {
+        self.get_base_context()
+            .invoke_access_check(function_name, arguments, self)
+            .await
+    }
+
+    /// Record the decision of an access rule (see [`crate::access_trace`])
+    pub fn record_access(&self, entry: AccessTraceEntry) {
+        self.get_base_context().record_access(entry)
+    }
+
+    /// Record that the request mutated the entity types (whose cached responses must be
+    /// invalidated once the request's transaction commits)
+    pub fn record_mutation(&self, entity_types: impl IntoIterator<Item = String>) {
+        self.get_base_context().record_mutation(entity_types)
+    }
+
+    /// Whether the request has mutated any entity types (in which case its queries may see
+    /// uncommitted changes)
+    pub fn has_mutations(&self) -> bool {
+        self.get_base_context().has_mutations()
+    }
+
+    /// The entity types mutated by the request (clearing the record)
+    pub fn take_mutated_entity_types(&self) -> Vec<String> {
+        self.get_base_context().take_mutated_entity_types()
+    }
+
     #[async_recursion]
     pub async fn ensure_transaction(&self) {
         match self {
@@ -98,4 +138,21 @@ impl<'a> RequestContext<'a> {
             }
         }
     }
+
+    /// Set the options for the request's transaction (see
+    /// [`exo_sql::TransactionHolder::set_transaction_options`])
+    #[async_recursion]
+    pub async fn set_transaction_options(
+        &self,
+        options: &TransactionOptions,
+    ) -> Result<(), DatabaseError> {
+        match self {
+            RequestContext::User(user_request_context) => {
+                user_request_context.set_transaction_options(options).await
+            }
+            RequestContext::Overridden(overridden_context) => {
+                overridden_context.set_transaction_options(options).await
+            }
+        }
+    }
 }
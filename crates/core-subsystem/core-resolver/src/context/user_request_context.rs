@@ -10,15 +10,21 @@
 use std::{collections::HashMap, sync::Arc};
 use tokio::sync::Mutex;
 
+use common::env_const::{EXO_ACCESS_AUDIT_LOG, EXO_ACCESS_AUDIT_LOG_CONTEXT};
+use core_model::transaction::{IsolationLevel, TransactionOptions};
 use elsa::sync::FrozenMap;
-use exo_sql::TransactionHolder;
+use exo_sql::{database_error::DatabaseError, TransactionHolder};
 
-use crate::{system_resolver::SystemResolver, value::Val};
+use crate::{
+    access_trace::{AccessTrace, AccessTraceEntry, AuditContext},
+    system_resolver::{SystemResolutionError, SystemResolver},
+    value::Val,
+};
 
 use super::provider::jwt::JwtExtractor;
 use super::provider::{
-    cookie::CookieExtractor, environment::EnvironmentContextExtractor, header::HeaderExtractor,
-    ip::IpExtractor, query::QueryExtractor,
+    api_key::ApiKeyExtractor, cookie::CookieExtractor, environment::EnvironmentContextExtractor,
+    header::HeaderExtractor, ip::IpExtractor, query::QueryExtractor,
 };
 use super::{
     context_extractor::BoxedContextExtractor, error::ContextExtractionError, request::Request,
@@ -33,6 +39,16 @@ pub struct UserRequestContext<'a> {
     request: &'a (dyn Request + Send + Sync),
     // cache of context values so that we compute them only once per request
     context_cache: FrozenMap<(String, String), Box<Option<Val>>>,
+    system_resolver: &'a SystemResolver,
+    // cache of `check(...)` results so that we invoke each function (for the same arguments) only
+    // once per request
+    access_check_cache: FrozenMap<(String, String), Box<Val>>,
+    pub access_trace: AccessTrace,
+    // context values extracted so far to include in audit log events (recorded only if the audit
+    // log is enabled)
+    audit_context: Option<AuditContext>,
+    // entity types mutated by the request (to invalidate the cached responses that depend on them)
+    mutated_entity_types: std::sync::Mutex<Vec<String>>,
 }
 
 impl<'a> UserRequestContext<'a> {
@@ -52,6 +68,7 @@ impl<'a> UserRequestContext<'a> {
             Box::new(IpExtractor),
             Box::new(CookieExtractor::new()),
             Box::new(JwtExtractor::new(system_resolver.jwt_authenticator.clone())),
+            Box::new(ApiKeyExtractor::new(system_resolver)),
         ];
 
         Ok(UserRequestContext {
@@ -63,6 +80,20 @@ impl<'a> UserRequestContext<'a> {
             transaction_holder: Arc::new(Mutex::new(TransactionHolder::default())),
             request,
             context_cache: FrozenMap::new(),
+            system_resolver,
+            access_check_cache: FrozenMap::new(),
+            access_trace: AccessTrace::default(),
+            audit_context: (system_resolver.env.get(EXO_ACCESS_AUDIT_LOG).as_deref()
+                == Some("true"))
+            .then(|| {
+                AuditContext::new(
+                    system_resolver
+                        .env
+                        .get(EXO_ACCESS_AUDIT_LOG_CONTEXT)
+                        .as_deref(),
+                )
+            }),
+            mutated_entity_types: std::sync::Mutex::new(vec![]),
         })
     }
 
@@ -93,6 +124,12 @@ impl<'a> UserRequestContext<'a> {
                 let coerced_value =
                     raw_field_value.and_then(|value| value.map(coerce_value).transpose())?;
 
+                if let (Some(audit_context), Some(value)) = (&self.audit_context, &coerced_value) {
+                    if let Ok(value) = value.clone().into_json() {
+                        audit_context.record(format!("{annotation}.{key}"), value);
+                    }
+                }
+
                 self.context_cache
                     .insert(cache_key, Box::new(coerced_value))
             }
@@ -120,6 +157,74 @@ impl<'a> UserRequestContext<'a> {
             .map(Val::from))
     }
 
+    pub async fn invoke_access_check(
+        &self,
+        function_name: &str,
+        arguments: &[Val],
+        request_context: &RequestContext<'_>,
+    ) -> Result<Val, SystemResolutionError> {
+        // (function name, serialized arguments), for example ("canRead", "[1]")
+        let cache_key = serde_json::to_string(arguments)
+            .ok()
+            .map(|arguments| (function_name.to_owned(), arguments));
+
+        if let Some(value) = cache_key
+            .as_ref()
+            .and_then(|cache_key| self.access_check_cache.get(cache_key))
+        {
+            return Ok(value.clone());
+        }
+
+        let value = self
+            .system_resolver
+            .invoke_access_check(function_name, arguments, request_context)
+            .await?;
+
+        if let Some(cache_key) = cache_key {
+            self.access_check_cache
+                .insert(cache_key, Box::new(value.clone()));
+        }
+
+        Ok(value)
+    }
+
+    /// Record an access decision in the trace and, if enabled, the audit log
+    pub fn record_access(&self, entry: AccessTraceEntry) {
+        if let Some(audit_context) = &self.audit_context {
+            let context = audit_context.to_value();
+
+            tracing::info!(
+                target: "exograph::audit",
+                rule = %entry.rule,
+                operation = %entry.operation,
+                decision = ?entry.decision,
+                residual = entry.residual.as_deref(),
+                context = %context,
+                "Access decision"
+            );
+        }
+
+        self.access_trace.record(entry);
+    }
+
+    pub fn record_mutation(&self, entity_types: impl IntoIterator<Item = String>) {
+        let mut mutated_entity_types = self.mutated_entity_types.lock().unwrap();
+
+        for entity_type in entity_types {
+            if !mutated_entity_types.contains(&entity_type) {
+                mutated_entity_types.push(entity_type);
+            }
+        }
+    }
+
+    pub fn has_mutations(&self) -> bool {
+        !self.mutated_entity_types.lock().unwrap().is_empty()
+    }
+
+    pub fn take_mutated_entity_types(&self) -> Vec<String> {
+        std::mem::take(&mut *self.mutated_entity_types.lock().unwrap())
+    }
+
     pub async fn ensure_transaction(&self) {
         self.transaction_holder
             .as_ref()
@@ -127,4 +232,24 @@ impl<'a> UserRequestContext<'a> {
             .await
             .ensure_transaction();
     }
+
+    pub async fn set_transaction_options(
+        &self,
+        options: &TransactionOptions,
+    ) -> Result<(), DatabaseError> {
+        let options = exo_sql::TransactionOptions {
+            isolation_level: options.isolation_level.map(|level| match level {
+                IsolationLevel::ReadCommitted => exo_sql::IsolationLevel::ReadCommitted,
+                IsolationLevel::RepeatableRead => exo_sql::IsolationLevel::RepeatableRead,
+                IsolationLevel::Serializable => exo_sql::IsolationLevel::Serializable,
+            }),
+            statement_timeout: options.timeout,
+        };
+
+        self.transaction_holder
+            .as_ref()
+            .lock()
+            .await
+            .set_transaction_options(&options)
+    }
 }
@@ -0,0 +1,115 @@
+// Copyright Exograph, Inc. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file at the root of this repository.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! Support for background jobs.
+//!
+//! Module subsystems declare jobs (`job cleanup()`, optionally with a `@cron` schedule) and know how
+//! to run them. A subsystem backed by a database (currently Postgres) offers a [JobQueue] to
+//! persist enqueued jobs, so that they survive restarts and may be picked up by any server
+//! instance. The runner (see the `resolver` crate) ties the two together.
+
+use std::time::Duration;
+
+use async_trait::async_trait;
+use serde_json::Value;
+use thiserror::Error;
+
+use crate::context::Request;
+
+/// A job declared by a subsystem
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JobDefinition {
+    pub name: String,
+    /// The cron expression (from `@cron`), if the job runs periodically
+    pub schedule: Option<String>,
+    /// Whether code may enqueue the job. Jobs recorded by other means (such as webhook events,
+    /// which the database records) may not be enqueued, so that their arguments can be trusted.
+    pub enqueueable: bool,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct EnqueueOptions {
+    /// Run the job no earlier than after this delay
+    pub delay: Option<Duration>,
+    /// Total number of attempts (including the first one) before giving up. Defaults to the
+    /// queue's default.
+    pub max_attempts: Option<u32>,
+    /// If set, a job with the same key will not be enqueued again
+    pub unique_key: Option<String>,
+}
+
+/// A job claimed by a worker. No other worker will claim it until the visibility timeout elapses
+/// (or the job is marked as failed with a retry).
+#[derive(Debug, Clone)]
+pub struct ClaimedJob {
+    pub id: i64,
+    pub name: String,
+    pub arguments: Value,
+    /// The number of attempts so far (including the current one)
+    pub attempts: u32,
+    pub max_attempts: u32,
+}
+
+#[async_trait]
+pub trait JobQueue: Send + Sync {
+    /// Enqueue a job. Returns the id of the job or `None` if a job with the same unique key
+    /// already exists.
+    async fn enqueue(
+        &self,
+        name: &str,
+        arguments: &Value,
+        options: &EnqueueOptions,
+    ) -> Result<Option<i64>, JobError>;
+
+    /// Claim the next due job (if any) for the given duration
+    async fn claim(&self, visibility_timeout: Duration) -> Result<Option<ClaimedJob>, JobError>;
+
+    /// Keep a claimed job locked for another `visibility_timeout` (while it is still running)
+    async fn extend_lock(&self, id: i64, visibility_timeout: Duration) -> Result<(), JobError>;
+
+    /// Mark the job as successfully completed
+    async fn complete(&self, id: i64) -> Result<(), JobError>;
+
+    /// Record a failed attempt. If `retry_after` is `None`, the job will not be retried.
+    async fn fail(
+        &self,
+        id: i64,
+        error: &str,
+        retry_after: Option<Duration>,
+    ) -> Result<(), JobError>;
+}
+
+/// The [Request] used to run a job. Since there is no HTTP request associated with a job, it has no
+/// headers (and thus no authenticated user) or IP address.
+pub struct JobRequest;
+
+impl Request for JobRequest {
+    fn get_headers(&self, _key: &str) -> Vec<String> {
+        vec![]
+    }
+
+    fn get_ip(&self) -> Option<std::net::IpAddr> {
+        None
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum JobError {
+    #[error("No job named `{0}`")]
+    NotFound(String),
+
+    #[error("The job `{0}` can't be enqueued")]
+    NotEnqueueable(String),
+
+    #[error("Jobs require a subsystem with a job queue (such as Postgres)")]
+    NoQueue,
+
+    #[error("{0}")]
+    Delegate(#[source] Box<dyn std::error::Error + Send + Sync + 'static>),
+}
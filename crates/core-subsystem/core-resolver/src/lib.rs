@@ -8,12 +8,17 @@
 // by the Apache License, Version 2.0.
 
 pub mod access_solver;
+pub mod access_trace;
+pub mod api_key;
 pub mod context;
 pub mod context_extractor;
 pub mod introspection;
+pub mod job;
+pub mod metrics;
 pub mod number_cmp;
 pub mod operation_resolver;
 pub mod plugin;
+pub mod response_cache;
 pub mod system_resolver;
 pub mod validation;
 pub mod value;
@@ -0,0 +1,508 @@
+// Copyright Exograph, Inc. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file at the root of this repository.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! Metrics in the Prometheus text exposition format (served by `server-actix` when
+//! `EXO_METRICS_HTTP_PATH` is set, on `EXO_METRICS_PORT` if that is set).
+//!
+//! Metrics about events (requests, errors, and WASM executions) are recorded here as they happen
+//! (across the process). Metrics about the current state of a subsystem (such as the usage of a
+//! connection pool) are collected from each subsystem when rendering (see
+//! [`crate::plugin::SubsystemResolver::metrics`]).
+//!
+//! The same metrics are also recorded with the global OpenTelemetry meter provider, which exports
+//! them over OTLP if OpenTelemetry is enabled (and does nothing otherwise). Since that export is
+//! periodic, the subsystem metrics are pushed to it using [`update_subsystem_metrics`].
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Write,
+    sync::{Mutex, OnceLock},
+    time::{Duration, Instant},
+};
+
+use opentelemetry::{
+    global,
+    metrics::{AsyncInstrument, Counter, Histogram as OtelHistogram, Meter, Unit},
+    KeyValue,
+};
+
+use crate::{plugin::SubsystemResolutionError, system_resolver::SystemResolutionError};
+
+/// Upper bounds (in seconds) of the duration histogram buckets (the Prometheus client libraries'
+/// defaults)
+const DURATION_BUCKETS: [f64; 11] = [
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+/// The maximum number of operation names to report. Clients choose operation names, so to bound
+/// the number of time series, we report any further names as [`OTHER_OPERATION`].
+const MAX_OPERATION_NAMES: usize = 1000;
+const OTHER_OPERATION: &str = "<other>";
+
+static REQUESTS: Mutex<BTreeMap<String, RequestMetrics>> = Mutex::new(BTreeMap::new());
+static REQUEST_ERRORS: Mutex<BTreeMap<&'static str, u64>> = Mutex::new(BTreeMap::new());
+static WASM_EXECUTIONS: Mutex<BTreeMap<String, Histogram>> = Mutex::new(BTreeMap::new());
+
+static OTEL_INSTRUMENTS: OnceLock<OtelInstruments> = OnceLock::new();
+/// The latest subsystem metrics (observed by the OpenTelemetry instruments registered for them)
+static SUBSYSTEM_SAMPLES: Mutex<Vec<MetricSample>> = Mutex::new(Vec::new());
+static OBSERVED_SUBSYSTEM_METRICS: Mutex<BTreeSet<&'static str>> = Mutex::new(BTreeSet::new());
+
+/// A metric about the current state of a subsystem
+#[derive(Debug, Clone, PartialEq)]
+pub struct MetricSample {
+    pub name: &'static str,
+    pub help: &'static str,
+    pub kind: MetricKind,
+    pub labels: Vec<(&'static str, String)>,
+    pub value: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MetricKind {
+    Counter,
+    Gauge,
+}
+
+impl MetricKind {
+    fn as_str(&self) -> &'static str {
+        match self {
+            MetricKind::Counter => "counter",
+            MetricKind::Gauge => "gauge",
+        }
+    }
+}
+
+/// Measures the duration of an event (where the time is available: `Instant::now` panics in
+/// WebAssembly)
+pub struct Timer(Option<Instant>);
+
+impl Timer {
+    pub fn start() -> Self {
+        #[cfg(not(target_family = "wasm"))]
+        {
+            Self(Some(Instant::now()))
+        }
+
+        #[cfg(target_family = "wasm")]
+        {
+            Self(None)
+        }
+    }
+
+    pub fn elapsed(&self) -> Option<Duration> {
+        self.0.map(|start| start.elapsed())
+    }
+}
+
+struct OtelInstruments {
+    requests: Counter<u64>,
+    request_duration: OtelHistogram<f64>,
+    request_errors: Counter<u64>,
+    wasm_execution_duration: OtelHistogram<f64>,
+}
+
+impl OtelInstruments {
+    fn get() -> &'static Self {
+        OTEL_INSTRUMENTS.get_or_init(|| {
+            let meter = meter();
+
+            Self {
+                requests: meter
+                    .u64_counter("exograph.requests")
+                    .with_description("Requests by operation name")
+                    .init(),
+                request_duration: meter
+                    .f64_histogram("exograph.request.duration")
+                    .with_description("Duration of requests by operation name")
+                    .with_unit(Unit::new("s"))
+                    .init(),
+                request_errors: meter
+                    .u64_counter("exograph.request.errors")
+                    .with_description("Failed requests by the kind of error")
+                    .init(),
+                wasm_execution_duration: meter
+                    .f64_histogram("exograph.wasm.execution.duration")
+                    .with_description("Duration of WASM function executions by function name")
+                    .with_unit(Unit::new("s"))
+                    .init(),
+            }
+        })
+    }
+}
+
+fn meter() -> Meter {
+    global::meter("exograph")
+}
+
+#[derive(Default)]
+struct RequestMetrics {
+    count: u64,
+    duration: Histogram,
+}
+
+#[derive(Default)]
+struct Histogram {
+    /// The number of observations in each bucket (cumulative, so each includes the previous ones)
+    bucket_counts: [u64; DURATION_BUCKETS.len()],
+    count: u64,
+    sum: f64,
+}
+
+impl Histogram {
+    fn observe(&mut self, duration: Duration) {
+        let seconds = duration.as_secs_f64();
+
+        for (bound, bucket_count) in DURATION_BUCKETS.iter().zip(self.bucket_counts.iter_mut()) {
+            if seconds <= *bound {
+                *bucket_count += 1;
+            }
+        }
+        self.count += 1;
+        self.sum += seconds;
+    }
+}
+
+/// Record a (GraphQL) request with its operation name (`None` for an anonymous operation)
+pub fn record_request(
+    operation_name: Option<&str>,
+    duration: Option<Duration>,
+    error: Option<&SystemResolutionError>,
+) {
+    let otel_instruments = OtelInstruments::get();
+
+    {
+        let mut requests = REQUESTS.lock().unwrap();
+
+        let operation_name = operation_name.unwrap_or_default();
+        let operation_name =
+            if requests.len() < MAX_OPERATION_NAMES || requests.contains_key(operation_name) {
+                operation_name
+            } else {
+                OTHER_OPERATION
+            };
+        let attributes = [KeyValue::new("operation", operation_name.to_string())];
+
+        let request_metrics = requests.entry(operation_name.to_string()).or_default();
+        request_metrics.count += 1;
+        otel_instruments.requests.add(1, &attributes);
+        if let Some(duration) = duration {
+            request_metrics.duration.observe(duration);
+            otel_instruments
+                .request_duration
+                .record(duration.as_secs_f64(), &attributes);
+        }
+    }
+
+    if let Some(error) = error {
+        let kind = error_kind(error);
+
+        *REQUEST_ERRORS.lock().unwrap().entry(kind).or_default() += 1;
+        otel_instruments
+            .request_errors
+            .add(1, &[KeyValue::new("kind", kind)]);
+    }
+}
+
+/// Record the execution of a WASM function
+pub fn record_wasm_execution(function_name: &str, duration: Duration) {
+    WASM_EXECUTIONS
+        .lock()
+        .unwrap()
+        .entry(function_name.to_string())
+        .or_default()
+        .observe(duration);
+    OtelInstruments::get().wasm_execution_duration.record(
+        duration.as_secs_f64(),
+        &[KeyValue::new("function", function_name.to_string())],
+    );
+}
+
+/// Update the subsystem metrics reported to OpenTelemetry (registering an instrument for each
+/// metric not seen before)
+pub fn update_subsystem_metrics(samples: Vec<MetricSample>) {
+    let mut observed_metrics = OBSERVED_SUBSYSTEM_METRICS.lock().unwrap();
+
+    for sample in samples.iter() {
+        if !observed_metrics.insert(sample.name) {
+            continue;
+        }
+
+        let name = sample.name;
+        let meter = meter();
+        // The instruments stay registered with the meter provider (along with their callbacks)
+        match sample.kind {
+            MetricKind::Counter => {
+                meter
+                    .f64_observable_counter(name)
+                    .with_description(sample.help)
+                    .with_callback(move |observer| observe_subsystem_metric(name, observer))
+                    .init();
+            }
+            MetricKind::Gauge => {
+                meter
+                    .f64_observable_gauge(name)
+                    .with_description(sample.help)
+                    .with_callback(move |observer| observe_subsystem_metric(name, observer))
+                    .init();
+            }
+        }
+    }
+
+    *SUBSYSTEM_SAMPLES.lock().unwrap() = samples;
+}
+
+fn observe_subsystem_metric(name: &str, observer: &dyn AsyncInstrument<f64>) {
+    for sample in SUBSYSTEM_SAMPLES.lock().unwrap().iter() {
+        if sample.name == name {
+            let attributes = sample
+                .labels
+                .iter()
+                .map(|(name, value)| KeyValue::new(*name, value.clone()))
+                .collect::<Vec<_>>();
+            observer.observe(sample.value, &attributes);
+        }
+    }
+}
+
+/// Render the recorded metrics along with the subsystem metrics
+pub fn render(mut samples: Vec<MetricSample>) -> String {
+    let mut output = String::new();
+
+    {
+        let requests = REQUESTS.lock().unwrap();
+
+        write_header(
+            &mut output,
+            "exograph_requests_total",
+            "Requests by operation name",
+            "counter",
+        );
+        for (operation_name, request_metrics) in requests.iter() {
+            write_sample(
+                &mut output,
+                "exograph_requests_total",
+                &[("operation", operation_name)],
+                request_metrics.count as f64,
+            );
+        }
+
+        write_header(
+            &mut output,
+            "exograph_request_duration_seconds",
+            "Duration of requests by operation name",
+            "histogram",
+        );
+        for (operation_name, request_metrics) in requests.iter() {
+            write_histogram(
+                &mut output,
+                "exograph_request_duration_seconds",
+                ("operation", operation_name),
+                &request_metrics.duration,
+            );
+        }
+    }
+
+    write_header(
+        &mut output,
+        "exograph_request_errors_total",
+        "Failed requests by the kind of error",
+        "counter",
+    );
+    for (kind, count) in REQUEST_ERRORS.lock().unwrap().iter() {
+        write_sample(
+            &mut output,
+            "exograph_request_errors_total",
+            &[("kind", kind)],
+            *count as f64,
+        );
+    }
+
+    write_header(
+        &mut output,
+        "exograph_wasm_execution_duration_seconds",
+        "Duration of WASM function executions by function name",
+        "histogram",
+    );
+    for (function_name, histogram) in WASM_EXECUTIONS.lock().unwrap().iter() {
+        write_histogram(
+            &mut output,
+            "exograph_wasm_execution_duration_seconds",
+            ("function", function_name),
+            histogram,
+        );
+    }
+
+    // Group the samples of each metric (keeping the order in which subsystems reported them)
+    samples.sort_by_key(|sample| sample.name);
+
+    let mut previous_name = None;
+    for sample in samples {
+        if previous_name != Some(sample.name) {
+            write_header(&mut output, sample.name, sample.help, sample.kind.as_str());
+            previous_name = Some(sample.name);
+        }
+
+        let labels = sample
+            .labels
+            .iter()
+            .map(|(name, value)| (*name, value.as_str()))
+            .collect::<Vec<_>>();
+        write_sample(&mut output, sample.name, &labels, sample.value);
+    }
+
+    output
+}
+
+fn error_kind(error: &SystemResolutionError) -> &'static str {
+    match error {
+        SystemResolutionError::Validation(_) => "validation",
+        SystemResolutionError::SubsystemResolutionError(
+            SubsystemResolutionError::Authorization,
+        ) => "authorization",
+        SystemResolutionError::SubsystemResolutionError(_) => "subsystem",
+        SystemResolutionError::TrustedDocumentResolution(_) => "trusted_document",
+        SystemResolutionError::Delegate(_)
+        | SystemResolutionError::NoResolverFound
+        | SystemResolutionError::Generic(_)
+        | SystemResolutionError::AroundInterceptorReturnedNoResponse
+        | SystemResolutionError::NoInterceptionTree => "internal",
+    }
+}
+
+fn write_header(output: &mut String, name: &str, help: &str, kind: &str) {
+    writeln!(output, "# HELP {name} {help}").unwrap();
+    writeln!(output, "# TYPE {name} {kind}").unwrap();
+}
+
+fn write_histogram(output: &mut String, name: &str, label: (&str, &str), histogram: &Histogram) {
+    let bucket_name = format!("{name}_bucket");
+
+    for (bound, bucket_count) in DURATION_BUCKETS.iter().zip(histogram.bucket_counts.iter()) {
+        write_sample(
+            output,
+            &bucket_name,
+            &[label, ("le", &bound.to_string())],
+            *bucket_count as f64,
+        );
+    }
+    write_sample(
+        output,
+        &bucket_name,
+        &[label, ("le", "+Inf")],
+        histogram.count as f64,
+    );
+    write_sample(output, &format!("{name}_sum"), &[label], histogram.sum);
+    write_sample(
+        output,
+        &format!("{name}_count"),
+        &[label],
+        histogram.count as f64,
+    );
+}
+
+fn write_sample(output: &mut String, name: &str, labels: &[(&str, &str)], value: f64) {
+    output.push_str(name);
+
+    if !labels.is_empty() {
+        let labels = labels
+            .iter()
+            .map(|(name, value)| format!("{name}=\"{}\"", escape_label_value(value)))
+            .collect::<Vec<_>>()
+            .join(",");
+        write!(output, "{{{labels}}}").unwrap();
+    }
+
+    writeln!(output, " {value}").unwrap();
+}
+
+fn escape_label_value(value: &str) -> String {
+    value
+        .replace('\\', r"\\")
+        .replace('"', r#"\""#)
+        .replace('\n', r"\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use multiplatform_test::multiplatform_test;
+
+    #[multiplatform_test]
+    fn requests_and_errors() {
+        record_request(
+            Some("metricsTestQuery"),
+            Some(Duration::from_millis(20)),
+            None,
+        );
+        record_request(
+            Some("metricsTestQuery"),
+            Some(Duration::from_millis(300)),
+            Some(&SystemResolutionError::SubsystemResolutionError(
+                SubsystemResolutionError::Authorization,
+            )),
+        );
+
+        let output = render(vec![]);
+
+        assert!(output.contains("exograph_requests_total{operation=\"metricsTestQuery\"} 2\n"));
+        assert!(output.contains(
+            "exograph_request_duration_seconds_bucket{operation=\"metricsTestQuery\",le=\"0.01\"} 0\n"
+        ));
+        assert!(output.contains(
+            "exograph_request_duration_seconds_bucket{operation=\"metricsTestQuery\",le=\"0.025\"} 1\n"
+        ));
+        assert!(output.contains(
+            "exograph_request_duration_seconds_bucket{operation=\"metricsTestQuery\",le=\"0.5\"} 2\n"
+        ));
+        assert!(output.contains(
+            "exograph_request_duration_seconds_bucket{operation=\"metricsTestQuery\",le=\"+Inf\"} 2\n"
+        ));
+        assert!(output.contains(
+            "exograph_request_duration_seconds_count{operation=\"metricsTestQuery\"} 2\n"
+        ));
+        assert!(output.contains("exograph_request_errors_total{kind=\"authorization\"}"));
+    }
+
+    #[multiplatform_test]
+    fn subsystem_samples() {
+        let sample = |database: &str, value| MetricSample {
+            name: "exograph_test_connections",
+            help: "Connections",
+            kind: MetricKind::Gauge,
+            labels: vec![("database", database.to_string())],
+            value,
+        };
+
+        let output = render(vec![
+            sample("primary", 3.0),
+            MetricSample {
+                name: "exograph_test_hits_total",
+                help: "Hits",
+                kind: MetricKind::Counter,
+                labels: vec![],
+                value: 10.0,
+            },
+            sample("a \"quoted\" name", 1.5),
+        ]);
+
+        let subsystem_output = &output[output.find("# HELP exograph_test").unwrap()..];
+        assert_eq!(
+            subsystem_output,
+            "# HELP exograph_test_connections Connections\n\
+             # TYPE exograph_test_connections gauge\n\
+             exograph_test_connections{database=\"primary\"} 3\n\
+             exograph_test_connections{database=\"a \\\"quoted\\\" name\"} 1.5\n\
+             # HELP exograph_test_hits_total Hits\n\
+             # TYPE exograph_test_hits_total counter\n\
+             exograph_test_hits_total 10\n"
+        );
+    }
+}
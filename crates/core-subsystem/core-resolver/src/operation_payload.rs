@@ -7,15 +7,21 @@
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
 
+use exo_sql::StatementCapture;
 use serde::Deserialize;
 use serde_json::{Map, Value};
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct OperationsPayload {
     pub operation_name: Option<String>,
     pub query: Option<String>,
     pub variables: Option<Map<String, Value>>,
     pub query_hash: Option<String>,
+    /// Whether the request asks for the access decisions (`extensions.accessTrace`) in the response
+    pub access_trace: bool,
+    /// The statements the request asks for in the response (`extensions.sql`: `true` for the
+    /// statements, `"explain"` to also include their query plans)
+    pub sql: Option<StatementCapture>,
 }
 
 impl OperationsPayload {
@@ -42,11 +48,32 @@ impl OperationsPayload {
                     })
             });
 
+            let access_trace = raw_payload
+                .extensions
+                .as_ref()
+                .and_then(|extensions| extensions.get("accessTrace"))
+                .and_then(Value::as_bool)
+                .unwrap_or(false);
+
+            let sql = raw_payload
+                .extensions
+                .as_ref()
+                .and_then(|extensions| extensions.get("sql"))
+                .and_then(|sql| match sql {
+                    Value::Bool(true) => Some(StatementCapture::Statements),
+                    Value::String(s) if s == "explain" => {
+                        Some(StatementCapture::StatementsWithPlans)
+                    }
+                    _ => None,
+                });
+
             OperationsPayload {
                 operation_name: raw_payload.operation_name,
                 query: raw_payload.query,
                 variables: raw_payload.variables,
                 query_hash,
+                access_trace,
+                sql,
             }
         })
     }
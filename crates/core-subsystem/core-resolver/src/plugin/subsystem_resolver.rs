@@ -8,12 +8,21 @@
 // by the Apache License, Version 2.0.
 
 use crate::{
-    context::RequestContext, system_resolver::SystemResolver, validation::field::ValidatedField,
+    access_trace::AccessRule,
+    api_key::ApiKeyStore,
+    context::RequestContext,
+    job::{JobDefinition, JobQueue},
+    metrics::MetricSample,
+    response_cache::CachePolicy,
+    system_resolver::SystemResolver,
+    validation::field::ValidatedField,
+    value::Val,
     InterceptedOperation, QueryResponse,
 };
 use async_graphql_parser::types::{FieldDefinition, OperationType, TypeDefinition};
 use async_trait::async_trait;
 use core_plugin_shared::interception::InterceptorIndex;
+use serde_json::Value;
 use thiserror::Error;
 use tokio::runtime::Handle;
 
@@ -80,6 +89,21 @@ pub trait SubsystemResolver: Sync {
         system_resolver: &'a SystemResolver,
     ) -> Result<Option<QueryResponse>, SubsystemResolutionError>;
 
+    /// Resolve several queries together (typically issued concurrently by module code through
+    /// `executeQuery`). The queries are for the same field with the same selection (but may have
+    /// different arguments), so a subsystem may resolve them with a single statement.
+    ///
+    /// Returns `None` if the subsystem can't resolve the queries together (they are then resolved
+    /// individually), otherwise a response for each query (in order)
+    async fn resolve_batch<'a>(
+        &'a self,
+        _operations: &'a [&'a ValidatedField],
+        _request_context: &'a RequestContext<'a>,
+        _system_resolver: &'a SystemResolver,
+    ) -> Result<Option<Vec<QueryResponse>>, SubsystemResolutionError> {
+        Ok(None)
+    }
+
     /// Involves an interceptor
     ///
     /// Returns `None` for `QueryResponse` if the interceptor is of before/after type (which is not
@@ -92,6 +116,74 @@ pub trait SubsystemResolver: Sync {
         system_resolver: &'a SystemResolver,
     ) -> Result<Option<QueryResponse>, SubsystemResolutionError>;
 
+    /// Jobs declared by this subsystem
+    fn jobs(&self) -> Vec<JobDefinition> {
+        vec![]
+    }
+
+    /// Run a job with the given arguments
+    ///
+    /// Returns `None` if the job is not handled by this subsystem
+    async fn invoke_job<'a>(
+        &'a self,
+        _name: &'a str,
+        _arguments: &'a Value,
+        _request_context: &'a RequestContext<'a>,
+        _system_resolver: &'a SystemResolver,
+    ) -> Result<Option<()>, SubsystemResolutionError> {
+        Ok(None)
+    }
+
+    /// Invoke a module function that decides access (through `check(...)` in an access rule) with
+    /// the given arguments (mapped, in order, to the function's non-injected parameters)
+    ///
+    /// Returns `None` if the function is not handled by this subsystem
+    async fn invoke_access_check<'a>(
+        &'a self,
+        _function_name: &'a str,
+        _arguments: &'a [Val],
+        _request_context: &'a RequestContext<'a>,
+        _system_resolver: &'a SystemResolver,
+    ) -> Result<Option<Val>, SubsystemResolutionError> {
+        Ok(None)
+    }
+
+    /// How the response of a query may be cached (see [`crate::response_cache`])
+    ///
+    /// Returns `None` if the operation is not handled by this subsystem or its response must not
+    /// be cached
+    async fn cache_policy<'a>(
+        &'a self,
+        _operation: &'a ValidatedField,
+        _operation_type: OperationType,
+        _request_context: &'a RequestContext<'a>,
+    ) -> Result<Option<CachePolicy>, SubsystemResolutionError> {
+        Ok(None)
+    }
+
+    /// Access rules that this subsystem may record while resolving operations (see
+    /// [`crate::access_trace`])
+    fn access_rules(&self) -> Vec<AccessRule> {
+        vec![]
+    }
+
+    /// Metrics about the current state of this subsystem (such as the usage of a connection pool)
+    /// to report along with the process-wide ones (see [`crate::metrics`])
+    async fn metrics(&self) -> Vec<MetricSample> {
+        vec![]
+    }
+
+    /// The queue to persist enqueued jobs, if this subsystem offers one
+    fn job_queue(&self) -> Option<&dyn JobQueue> {
+        None
+    }
+
+    /// The store to validate API keys against, if this subsystem offers one (and is configured to
+    /// do so)
+    fn api_key_store(&self) -> Option<&dyn ApiKeyStore> {
+        None
+    }
+
     // Support for schema creation (and in turn, validation)
 
     /// Queries supported by this subsystem
@@ -7,7 +7,7 @@
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
 
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
 
 use async_graphql_parser::{
     types::{ExecutableDocument, OperationType},
@@ -32,16 +32,31 @@ use exo_env::Environment;
 use crate::context::provider::jwt::JwtAuthenticator;
 
 use crate::{
+    access_trace::AccessRule,
+    api_key::{ApiKeyFile, ApiKeyStore},
     context::RequestContext,
     introspection::definition::schema::Schema,
+    job::{EnqueueOptions, JobDefinition, JobError, JobQueue},
+    metrics::MetricSample,
     plugin::{subsystem_resolver::SubsystemResolver, SubsystemResolutionError},
+    response_cache::{CachePolicy, ResponseCache},
     validation::{
         document_validator::DocumentValidator, field::ValidatedField,
         operation::ValidatedOperation, validation_error::ValidationError,
     },
-    FieldResolver, InterceptedOperation, OperationsPayload, QueryResponse,
+    value::Val,
+    FieldResolver, InterceptedOperation, OperationsPayload, QueryResponse, QueryResponseBody,
 };
 
+pub type ExographExecuteQueriesFn<'a> = dyn Fn(
+        Vec<(String, Option<serde_json::Map<String, Value>>)>,
+        TrustedDocumentEnforcement,
+        Value,
+    ) -> BoxFuture<'a, Vec<Result<QueryResponse, SystemResolutionError>>>
+    + 'a
+    + Send
+    + Sync;
+
 pub type ExographExecuteQueryFn<'a> = dyn Fn(
         String,
         Option<serde_json::Map<String, Value>>,
@@ -52,6 +67,16 @@ pub type ExographExecuteQueryFn<'a> = dyn Fn(
     + Send
     + Sync;
 
+/// The response cache, the key, and the time to live to cache an operation's response with
+type CacheEntry<'r> = (&'r ResponseCache, String, Duration);
+
+/// The result of looking up an operation's response in the cache
+enum CacheLookup<'r> {
+    Hit(Vec<(String, QueryResponse)>),
+    /// Not cached (along with where to cache the response, if it can be)
+    Miss(Option<CacheEntry<'r>>),
+}
+
 /// The top-level system resolver.
 ///
 /// Delegates to subsystem resolvers to resolve individual operations.
@@ -62,9 +87,11 @@ pub struct SystemResolver {
     trusted_documents: TrustedDocuments,
     schema: Schema,
     pub jwt_authenticator: Arc<Option<JwtAuthenticator>>,
+    api_key_file: Option<ApiKeyFile>,
     pub env: Box<dyn Environment>,
     normal_query_depth_limit: usize,
     introspection_query_depth_limit: usize,
+    response_cache: Option<ResponseCache>,
 }
 
 impl SystemResolver {
@@ -76,9 +103,11 @@ impl SystemResolver {
         trusted_documents: TrustedDocuments,
         schema: Schema,
         jwt_authenticator: Arc<Option<JwtAuthenticator>>,
+        api_key_file: Option<ApiKeyFile>,
         env: Box<dyn Environment>,
         normal_query_depth_limit: usize,
         introspection_query_depth_limit: usize,
+        response_cache: Option<ResponseCache>,
     ) -> Self {
         #[cfg(not(target_family = "wasm"))]
         let trusted_documents = if is_production() || get_enforce_trusted_documents() {
@@ -98,9 +127,11 @@ impl SystemResolver {
             trusted_documents,
             schema,
             jwt_authenticator,
+            api_key_file,
             env,
             normal_query_depth_limit,
             introspection_query_depth_limit,
+            response_cache,
         }
     }
 
@@ -136,18 +167,154 @@ impl SystemResolver {
         request_context: &RequestContext<'a>,
         trusted_document_enforcement: TrustedDocumentEnforcement,
     ) -> Result<Vec<(String, QueryResponse)>, SystemResolutionError> {
+        // A cached response wouldn't include the access decisions or the statements
+        let use_response_cache =
+            !operations_payload.access_trace && operations_payload.sql.is_none();
+
+        let operation = self.validate_payload(operations_payload, trusted_document_enforcement)?;
+
+        let cache_entry = if use_response_cache {
+            match self.cache_lookup(&operation, request_context).await? {
+                CacheLookup::Hit(responses) => return Ok(responses),
+                CacheLookup::Miss(cache_entry) => cache_entry,
+            }
+        } else {
+            None
+        };
+
+        self.resolve_validated_operation(&operation, cache_entry, request_context)
+            .await
+    }
+
+    /// Resolve several independent operations (typically issued concurrently by module code
+    /// through `executeQuery`), returning the result for each (in order).
+    ///
+    /// Operations consisting of a single query (that isn't intercepted) for the same field and
+    /// selection are offered to the subsystems to resolve together (see
+    /// [`SubsystemResolver::resolve_batch`]). This way, a module looking up an entity for each item
+    /// in a list doesn't execute a statement per item. All other operations are resolved
+    /// individually (in order, with a group of queries resolved at the position of its first
+    /// query). To not reorder queries around mutations, we group only queries between mutations.
+    #[instrument(
+        name = "SystemResolver::resolve_operations_batch"
+        skip_all
+        fields(count = operations_payloads.len())
+        )]
+    pub async fn resolve_operations_batch<'a>(
+        &self,
+        operations_payloads: Vec<OperationsPayload>,
+        request_context: &RequestContext<'a>,
+        trusted_document_enforcement: TrustedDocumentEnforcement,
+    ) -> Vec<Result<Vec<(String, QueryResponse)>, SystemResolutionError>> {
+        let mut results: Vec<_> = operations_payloads.iter().map(|_| None).collect();
+
+        // Groups of (index, operation, cache entry) in the order to resolve them
+        let mut groups: Vec<Vec<(usize, ValidatedOperation, Option<CacheEntry<'_>>)>> = vec![];
+        // The groups (from `groups`) that later queries may join
+        let mut open_groups_start = 0;
+
+        for (index, operations_payload) in operations_payloads.into_iter().enumerate() {
+            let use_response_cache =
+                !operations_payload.access_trace && operations_payload.sql.is_none();
+
+            let operation =
+                match self.validate_payload(operations_payload, trusted_document_enforcement) {
+                    Ok(operation) => operation,
+                    Err(e) => {
+                        results[index] = Some(Err(e));
+                        continue;
+                    }
+                };
+
+            let cache_entry = if use_response_cache {
+                match self.cache_lookup(&operation, request_context).await {
+                    Ok(CacheLookup::Hit(responses)) => {
+                        results[index] = Some(Ok(responses));
+                        continue;
+                    }
+                    Ok(CacheLookup::Miss(cache_entry)) => cache_entry,
+                    Err(e) => {
+                        results[index] = Some(Err(e));
+                        continue;
+                    }
+                }
+            } else {
+                None
+            };
+
+            if !self.is_batchable(&operation) {
+                if operation.typ != OperationType::Query {
+                    open_groups_start = groups.len() + 1;
+                }
+                groups.push(vec![(index, operation, cache_entry)]);
+                continue;
+            }
+
+            let group = groups[open_groups_start..].iter_mut().find(|group| {
+                let (_, first, _) = &group[0];
+                self.is_batchable(first)
+                    && first.fields[0].name == operation.fields[0].name
+                    && first.fields[0].subfields == operation.fields[0].subfields
+            });
+
+            match group {
+                Some(group) => group.push((index, operation, cache_entry)),
+                None => groups.push(vec![(index, operation, cache_entry)]),
+            }
+        }
+
+        for group in groups {
+            if group.len() > 1 {
+                let fields: Vec<_> = group
+                    .iter()
+                    .map(|(_, operation, _)| &operation.fields[0])
+                    .collect();
+
+                if let Some(responses) = self.resolve_batch(&fields, request_context).await {
+                    for ((index, operation, cache_entry), response) in
+                        group.into_iter().zip(responses)
+                    {
+                        let responses = vec![(operation.fields[0].output_name(), response)];
+                        self.cache_responses(cache_entry, &responses, request_context)
+                            .await;
+                        results[index] = Some(Ok(responses));
+                    }
+                    continue;
+                }
+            }
+
+            for (index, operation, cache_entry) in group {
+                results[index] = Some(
+                    self.resolve_validated_operation(&operation, cache_entry, request_context)
+                        .await,
+                );
+            }
+        }
+
+        results
+            .into_iter()
+            .map(|result| result.expect("Every operation should have been resolved"))
+            .collect()
+    }
+
+    /// Validate the operation in the payload (after resolving it against the trusted documents)
+    fn validate_payload(
+        &self,
+        operations_payload: OperationsPayload,
+        trusted_document_enforcement: TrustedDocumentEnforcement,
+    ) -> Result<ValidatedOperation, SystemResolutionError> {
         let query = self.trusted_documents.resolve(
             operations_payload.query.as_deref(),
             operations_payload.query_hash.as_deref(),
             trusted_document_enforcement,
         );
 
-        let operation = match query {
-            Ok(query) => self.validate_operation(
+        match query {
+            Ok(query) => Ok(self.validate_operation(
                 query,
                 operations_payload.operation_name,
                 operations_payload.variables,
-            )?,
+            )?),
             // Special handing on introspection queries made by tools to be implicitly trusted
             // Introspection queries made by the playground and tools such as graphql-codegen send queries as a string
             // and have top-level field `__schema` (but we also allow `__type` and `__typename` to be more widely useful).
@@ -175,20 +342,181 @@ impl SystemResolver {
                     .into());
                 }
 
-                operation
-            }
-            Err(e) => {
-                return Err(e.into());
+                Ok(operation)
             }
-        };
+            Err(e) => Err(e.into()),
+        }
+    }
 
+    async fn resolve_validated_operation<'a>(
+        &self,
+        operation: &ValidatedOperation,
+        cache_entry: Option<CacheEntry<'_>>,
+        request_context: &RequestContext<'a>,
+    ) -> Result<Vec<(String, QueryResponse)>, SystemResolutionError> {
         // If multiple operations are present, we need to ensure that we have a transaction
         if operation.fields.len() > 1 {
             request_context.ensure_transaction().await;
         }
-        operation
+        let responses = operation
             .resolve_fields(&operation.fields, self, request_context)
-            .await
+            .await?;
+
+        self.cache_responses(cache_entry, &responses, request_context)
+            .await;
+
+        Ok(responses)
+    }
+
+    /// Can the operation be resolved along with other operations (see
+    /// [`Self::resolve_operations_batch`])?
+    fn is_batchable(&self, operation: &ValidatedOperation) -> bool {
+        operation.typ == OperationType::Query
+            && operation.fields.len() == 1
+            && self
+                .applicable_interception_tree(&operation.fields[0].name, operation.typ)
+                .is_none()
+    }
+
+    /// Resolve the queries together through the first subsystem that can do so. Returns `None` if
+    /// no subsystem could (including if it failed, so that each query gets its own error).
+    async fn resolve_batch<'a>(
+        &self,
+        fields: &[&ValidatedField],
+        request_context: &RequestContext<'a>,
+    ) -> Option<Vec<QueryResponse>> {
+        for subsystem_resolver in self.subsystem_resolvers.iter() {
+            match subsystem_resolver
+                .resolve_batch(fields, request_context, self)
+                .await
+            {
+                Ok(Some(responses)) if responses.len() == fields.len() => return Some(responses),
+                Ok(Some(_)) => {
+                    error!("Subsystem returned a different number of responses for a batch");
+                    return None;
+                }
+                Ok(None) => {}
+                Err(e) => {
+                    warn!("Unable to resolve a batch of queries together: {e}");
+                    return None;
+                }
+            }
+        }
+
+        None
+    }
+
+    /// Look up the cached responses for the operation
+    async fn cache_lookup<'a>(
+        &self,
+        operation: &ValidatedOperation,
+        request_context: &RequestContext<'a>,
+    ) -> Result<CacheLookup<'_>, SystemResolutionError> {
+        let cache_entry = self
+            .response_cache_entry(operation, request_context)
+            .await?;
+
+        if let Some((response_cache, key, _)) = &cache_entry {
+            match response_cache.get(key).await {
+                Ok(Some(responses)) => return Ok(CacheLookup::Hit(responses)),
+                Ok(None) => {}
+                Err(e) => warn!("Unable to get a cached response: {e}"),
+            }
+        }
+
+        Ok(CacheLookup::Miss(cache_entry))
+    }
+
+    /// Cache the responses (if the operation can be cached)
+    async fn cache_responses<'a>(
+        &self,
+        cache_entry: Option<CacheEntry<'_>>,
+        responses: &[(String, QueryResponse)],
+        request_context: &RequestContext<'a>,
+    ) {
+        if let Some((response_cache, key, ttl)) = cache_entry {
+            // Don't cache responses that set headers (such as cookies) or that may include the
+            // request's own (not yet committed) changes
+            if responses
+                .iter()
+                .all(|(_, response)| response.headers.is_empty())
+                && !request_context.has_mutations()
+            {
+                if let Err(e) = response_cache.set(&key, responses, ttl).await {
+                    warn!("Unable to cache the response: {e}");
+                }
+            }
+        }
+    }
+
+    /// The response cache, the key, and the time to live for the operation's response (if the
+    /// cache is enabled and all subsystems resolving the operation allow caching it)
+    async fn response_cache_entry<'a>(
+        &self,
+        operation: &ValidatedOperation,
+        request_context: &RequestContext<'a>,
+    ) -> Result<Option<CacheEntry<'_>>, SystemResolutionError> {
+        let Some(response_cache) = &self.response_cache else {
+            return Ok(None);
+        };
+
+        // Queries of a request that has mutated data may see its (not yet committed) changes
+        if operation.typ != OperationType::Query || request_context.has_mutations() {
+            return Ok(None);
+        }
+
+        let mut policy: Option<CachePolicy> = None;
+
+        for field in &operation.fields {
+            // Interceptors may have side effects (or produce a different response each time)
+            if !matches!(
+                self.applicable_interception_tree(&field.name, operation.typ),
+                None | Some(InterceptionTree::Operation)
+            ) {
+                return Ok(None);
+            }
+
+            let mut field_policy = None;
+            for subsystem_resolver in self.subsystem_resolvers.iter() {
+                field_policy = subsystem_resolver
+                    .cache_policy(field, operation.typ, request_context)
+                    .await?;
+                if field_policy.is_some() {
+                    break;
+                }
+            }
+
+            let Some(field_policy) = field_policy else {
+                return Ok(None);
+            };
+
+            policy = Some(match policy {
+                Some(policy) => policy.merge(field_policy),
+                None => field_policy,
+            });
+        }
+
+        let Some(policy) = policy else {
+            return Ok(None);
+        };
+
+        match response_cache.key(&operation.fields, &policy).await {
+            Ok(key) => Ok(Some((response_cache, key, policy.ttl))),
+            Err(e) => {
+                warn!("Unable to use the response cache: {e}");
+                Ok(None)
+            }
+        }
+    }
+
+    /// Invalidate the cached responses that depend on the entity types (typically after a
+    /// mutation of those types commits)
+    pub async fn invalidate_cached_responses(&self, entity_types: &[String]) {
+        if let Some(response_cache) = &self.response_cache {
+            if let Err(e) = response_cache.invalidate(entity_types).await {
+                error!("Unable to invalidate the cached responses: {e}");
+            }
+        }
     }
 
     /// Should we allow introspection queries?
@@ -276,6 +604,119 @@ impl SystemResolver {
             .map_err(|e| e.into())
     }
 
+    /// Jobs declared across all subsystems
+    pub fn jobs(&self) -> Vec<JobDefinition> {
+        self.subsystem_resolvers
+            .iter()
+            .flat_map(|subsystem_resolver| subsystem_resolver.jobs())
+            .collect()
+    }
+
+    /// Access rules declared across all subsystems
+    pub fn access_rules(&self) -> Vec<AccessRule> {
+        self.subsystem_resolvers
+            .iter()
+            .flat_map(|subsystem_resolver| subsystem_resolver.access_rules())
+            .collect()
+    }
+
+    /// Metrics reported by all subsystems (see [`crate::metrics`])
+    pub async fn metrics(&self) -> Vec<MetricSample> {
+        let mut samples = vec![];
+        for subsystem_resolver in self.subsystem_resolvers.iter() {
+            samples.extend(subsystem_resolver.metrics().await);
+        }
+        samples
+    }
+
+    /// The queue to persist enqueued jobs (the first one offered by a subsystem)
+    pub fn job_queue(&self) -> Option<&dyn JobQueue> {
+        self.subsystem_resolvers
+            .iter()
+            .find_map(|subsystem_resolver| subsystem_resolver.job_queue())
+    }
+
+    /// The store to validate API keys against: the key file (if configured) or the first store
+    /// offered by a subsystem
+    pub fn api_key_store(&self) -> Option<&dyn ApiKeyStore> {
+        match &self.api_key_file {
+            Some(api_key_file) => Some(api_key_file),
+            None => self
+                .subsystem_resolvers
+                .iter()
+                .find_map(|subsystem_resolver| subsystem_resolver.api_key_store()),
+        }
+    }
+
+    /// Enqueue a job to be run by the job runner.
+    ///
+    /// Returns the id of the job or `None` if a job with the same unique key already exists.
+    pub async fn enqueue_job(
+        &self,
+        name: &str,
+        arguments: &Value,
+        options: &EnqueueOptions,
+    ) -> Result<Option<i64>, JobError> {
+        match self.jobs().into_iter().find(|job| job.name == name) {
+            None => return Err(JobError::NotFound(name.to_string())),
+            Some(job) if !job.enqueueable => {
+                return Err(JobError::NotEnqueueable(name.to_string()))
+            }
+            Some(_) => {}
+        }
+
+        self.job_queue()
+            .ok_or(JobError::NoQueue)?
+            .enqueue(name, arguments, options)
+            .await
+    }
+
+    /// Run a job (typically invoked by the job runner after claiming an enqueued job)
+    #[instrument(
+        name = "SystemResolver::invoke_job",
+        skip(self, arguments, request_context)
+    )]
+    pub async fn invoke_job<'a>(
+        &self,
+        name: &str,
+        arguments: &Value,
+        request_context: &RequestContext<'a>,
+    ) -> Result<(), SystemResolutionError> {
+        for subsystem_resolver in self.subsystem_resolvers.iter() {
+            if let Some(()) = subsystem_resolver
+                .invoke_job(name, arguments, request_context, self)
+                .await?
+            {
+                return Ok(());
+            }
+        }
+
+        Err(SystemResolutionError::NoResolverFound)
+    }
+
+    /// Invoke the module function backing a `check(...)` access rule
+    #[instrument(
+        name = "SystemResolver::invoke_access_check",
+        skip(self, arguments, request_context)
+    )]
+    pub async fn invoke_access_check<'a>(
+        &self,
+        function_name: &str,
+        arguments: &[Val],
+        request_context: &RequestContext<'a>,
+    ) -> Result<Val, SystemResolutionError> {
+        for subsystem_resolver in self.subsystem_resolvers.iter() {
+            if let Some(result) = subsystem_resolver
+                .invoke_access_check(function_name, arguments, request_context, self)
+                .await?
+            {
+                return Ok(result);
+            }
+        }
+
+        Err(SystemResolutionError::NoResolverFound)
+    }
+
     #[instrument(skip_all)]
     fn validate_operation(
         &self,
@@ -304,7 +745,9 @@ macro_rules! exograph_execute_query {
                variables: Option<serde_json::Map<String, serde_json::Value>>,
                enforce_trusted_documents: TrustedDocumentEnforcement,
                context_override: serde_json::Value| {
-            use core_plugin_interface::core_resolver::{OperationsPayload, QueryResponseBody};
+            use core_plugin_interface::core_resolver::{
+                system_resolver::collate_query_responses, OperationsPayload,
+            };
             use futures::FutureExt;
 
             let new_request_context = $request_context.with_override(context_override);
@@ -317,37 +760,86 @@ macro_rules! exograph_execute_query {
                             query: Some(query_string),
                             variables,
                             query_hash: None,
+                            access_trace: false,
+                            sql: None,
                         },
                         &new_request_context,
                         enforce_trusted_documents,
                     )
                     .await?;
 
-                // collate result into a single QueryResponse
+                Ok(collate_query_responses(result))
+            }
+            .boxed()
+        }
+    };
+}
 
-                // since query execution results in a Vec<(String, QueryResponse)>, we want to
-                // extract and collect all HTTP headers generated in QueryResponses
-                let headers = result
-                    .iter()
-                    .flat_map(|(_, response)| response.headers.clone())
-                    .collect::<Vec<_>>();
+/// Like [`exograph_execute_query`], but executes several queries (with the same context override)
+/// through [`SystemResolver::resolve_operations_batch`]
+#[macro_export]
+macro_rules! exograph_execute_queries {
+    ($system_resolver:expr, $request_context:expr) => {
+        &move |queries: Vec<(String, Option<serde_json::Map<String, serde_json::Value>>)>,
+               enforce_trusted_documents: TrustedDocumentEnforcement,
+               context_override: serde_json::Value| {
+            use core_plugin_interface::core_resolver::{
+                system_resolver::collate_query_responses, OperationsPayload,
+            };
+            use futures::FutureExt;
 
-                // generate the body
-                let body = result
+            let new_request_context = $request_context.with_override(context_override);
+            async move {
+                let operations_payloads = queries
                     .into_iter()
-                    .map(|(name, response)| (name, response.body.to_json().unwrap()))
-                    .collect::<serde_json::Map<_, _>>();
+                    .map(|(query_string, variables)| OperationsPayload {
+                        operation_name: None,
+                        query: Some(query_string),
+                        variables,
+                        query_hash: None,
+                        access_trace: false,
+                        sql: None,
+                    })
+                    .collect();
 
-                Ok(QueryResponse {
-                    body: QueryResponseBody::Json(serde_json::Value::Object(body)),
-                    headers,
-                })
+                $system_resolver
+                    .resolve_operations_batch(
+                        operations_payloads,
+                        &new_request_context,
+                        enforce_trusted_documents,
+                    )
+                    .await
+                    .into_iter()
+                    .map(|result| result.map(collate_query_responses))
+                    .collect::<Vec<_>>()
             }
             .boxed()
         }
     };
 }
 
+/// Collate the responses of an operation's fields into a single response (as returned to module
+/// code through `executeQuery`)
+pub fn collate_query_responses(responses: Vec<(String, QueryResponse)>) -> QueryResponse {
+    // since query execution results in a Vec<(String, QueryResponse)>, we want to
+    // extract and collect all HTTP headers generated in QueryResponses
+    let headers = responses
+        .iter()
+        .flat_map(|(_, response)| response.headers.clone())
+        .collect::<Vec<_>>();
+
+    // generate the body
+    let body = responses
+        .into_iter()
+        .map(|(name, response)| (name, response.body.to_json().unwrap()))
+        .collect::<serde_json::Map<_, _>>();
+
+    QueryResponse {
+        body: QueryResponseBody::Json(Value::Object(body)),
+        headers,
+    }
+}
+
 #[instrument(name = "system_resolver::parse_query")]
 fn parse_query(query: &str) -> Result<ExecutableDocument, ValidationError> {
     async_graphql_parser::parse_query(query).map_err(|error| {
@@ -453,3 +945,399 @@ impl SystemResolutionError {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::HashMap,
+        sync::{Arc, Mutex},
+    };
+
+    use async_graphql_parser::{
+        parse_schema,
+        types::{FieldDefinition, TypeDefinition, TypeKind, TypeSystemDefinition},
+    };
+    use async_trait::async_trait;
+    use core_plugin_shared::interception::InterceptorIndex;
+    use exo_env::MapEnvironment;
+    use serde_json::json;
+
+    use super::*;
+    use crate::context::Request;
+
+    const SCHEMA: &str = r#"
+        scalar Int
+        scalar String
+
+        type Concert {
+            id: Int!
+            title: String!
+        }
+
+        type Venue {
+            id: Int!
+            name: String!
+        }
+
+        type Query {
+            concert(id: Int!): Concert
+            venue(id: Int!): Venue
+        }
+
+        type Mutation {
+            createConcert(title: String!): Concert
+        }
+    "#;
+
+    /// How the test subsystem handles a batch of queries
+    #[derive(Clone, Copy)]
+    enum Batch {
+        Resolve,
+        Decline,
+        Fail,
+    }
+
+    /// A subsystem with concerts and venues with ids 1 and 2 that records the operations it
+    /// resolves
+    struct TestSubsystemResolver {
+        batch: Batch,
+        calls: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl TestSubsystemResolver {
+        fn response(field: &ValidatedField) -> QueryResponse {
+            let id = match field.arguments.get("id") {
+                Some(Val::Number(id)) => id.as_i64().unwrap(),
+                _ => 3,
+            };
+            let entity = match field.name.as_str() {
+                "concert" | "createConcert" => json!({"id": id, "title": format!("C{id}")}),
+                _ => json!({"id": id, "name": format!("V{id}")}),
+            };
+
+            let body = (id <= 2 || field.name == "createConcert").then(|| {
+                let selection: Map<_, _> = field
+                    .subfields
+                    .iter()
+                    .map(|subfield| {
+                        (
+                            subfield.output_name(),
+                            entity[subfield.name.as_str()].clone(),
+                        )
+                    })
+                    .collect();
+                Value::Object(selection).to_string()
+            });
+
+            QueryResponse {
+                body: QueryResponseBody::Raw(body),
+                headers: vec![],
+            }
+        }
+
+        fn record(&self, call: String) {
+            self.calls.lock().unwrap().push(call);
+        }
+    }
+
+    fn call_name(field: &ValidatedField) -> String {
+        let arguments: Vec<_> = field
+            .arguments
+            .values()
+            .map(|argument| argument.clone().into_json().unwrap().to_string())
+            .collect();
+        format!("{}({})", field.name, arguments.join(", "))
+    }
+
+    #[async_trait]
+    impl SubsystemResolver for TestSubsystemResolver {
+        fn id(&self) -> &'static str {
+            "test"
+        }
+
+        async fn resolve<'a>(
+            &'a self,
+            operation: &'a ValidatedField,
+            _operation_type: OperationType,
+            _request_context: &'a RequestContext,
+            _system_resolver: &'a SystemResolver,
+        ) -> Result<Option<QueryResponse>, SubsystemResolutionError> {
+            self.record(call_name(operation));
+            Ok(Some(Self::response(operation)))
+        }
+
+        async fn resolve_batch<'a>(
+            &'a self,
+            operations: &'a [&'a ValidatedField],
+            _request_context: &'a RequestContext<'a>,
+            _system_resolver: &'a SystemResolver,
+        ) -> Result<Option<Vec<QueryResponse>>, SubsystemResolutionError> {
+            let calls: Vec<_> = operations.iter().map(|field| call_name(field)).collect();
+            self.record(format!("batch[{}]", calls.join(", ")));
+
+            match self.batch {
+                Batch::Resolve => Ok(Some(
+                    operations
+                        .iter()
+                        .map(|field| Self::response(field))
+                        .collect(),
+                )),
+                Batch::Decline => Ok(None),
+                Batch::Fail => Err(SubsystemResolutionError::UserDisplayError(
+                    "Batch failed".to_string(),
+                )),
+            }
+        }
+
+        async fn invoke_interceptor<'a>(
+            &'a self,
+            _interceptor_index: InterceptorIndex,
+            _intercepted_operation: &'a InterceptedOperation,
+            _request_context: &'a RequestContext<'a>,
+            _system_resolver: &'a SystemResolver,
+        ) -> Result<Option<QueryResponse>, SubsystemResolutionError> {
+            Err(SubsystemResolutionError::NoInterceptorFound)
+        }
+
+        fn schema_queries(&self) -> Vec<FieldDefinition> {
+            root_fields("Query")
+        }
+
+        fn schema_mutations(&self) -> Vec<FieldDefinition> {
+            root_fields("Mutation")
+        }
+
+        fn schema_types(&self) -> Vec<TypeDefinition> {
+            type_definitions()
+                .into_iter()
+                .filter(|td| td.name.node != "Query" && td.name.node != "Mutation")
+                .collect()
+        }
+    }
+
+    fn type_definitions() -> Vec<TypeDefinition> {
+        parse_schema(SCHEMA)
+            .unwrap()
+            .definitions
+            .into_iter()
+            .filter_map(|definition| match definition {
+                TypeSystemDefinition::Type(td) => Some(td.node),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn root_fields(type_name: &str) -> Vec<FieldDefinition> {
+        type_definitions()
+            .into_iter()
+            .find(|td| td.name.node == type_name)
+            .map(|td| match td.kind {
+                TypeKind::Object(object_type) => {
+                    object_type.fields.into_iter().map(|f| f.node).collect()
+                }
+                _ => vec![],
+            })
+            .unwrap()
+    }
+
+    struct TestRequest {}
+
+    impl Request for TestRequest {
+        fn get_headers(&self, _key: &str) -> Vec<String> {
+            vec![]
+        }
+
+        fn get_ip(&self) -> Option<std::net::IpAddr> {
+            None
+        }
+    }
+
+    /// Resolve the operations as a batch, returning the result of each (as JSON, with errors as
+    /// `{"error": ...}`) and the calls made to the subsystem
+    async fn resolve_batch(batch: Batch, queries: &[&str]) -> (Vec<Value>, Vec<String>) {
+        let calls = Arc::new(Mutex::new(vec![]));
+        let subsystem_resolvers: Vec<Box<dyn SubsystemResolver + Send + Sync>> =
+            vec![Box::new(TestSubsystemResolver {
+                batch,
+                calls: calls.clone(),
+            })];
+        let schema = Schema::new_from_resolvers(&subsystem_resolvers);
+
+        let system_resolver = SystemResolver::new(
+            subsystem_resolvers,
+            InterceptionMap {
+                map: HashMap::new(),
+            },
+            InterceptionMap {
+                map: HashMap::new(),
+            },
+            TrustedDocuments::all(),
+            schema,
+            None.into(),
+            None,
+            Box::new(MapEnvironment::from(HashMap::new())),
+            10,
+            10,
+            None,
+        );
+
+        let request = TestRequest {};
+        let request_context = RequestContext::new(&request, vec![], &system_resolver).unwrap();
+
+        let operations_payloads = queries
+            .iter()
+            .map(|query| OperationsPayload {
+                operation_name: None,
+                query: Some(query.to_string()),
+                variables: None,
+                query_hash: None,
+                access_trace: false,
+                sql: None,
+            })
+            .collect();
+
+        let results = system_resolver
+            .resolve_operations_batch(
+                operations_payloads,
+                &request_context,
+                TrustedDocumentEnforcement::DoNotEnforce,
+            )
+            .await
+            .into_iter()
+            .map(|result| match result {
+                Ok(responses) => Value::Object(
+                    responses
+                        .into_iter()
+                        .map(|(name, response)| (name, response.body.to_json().unwrap()))
+                        .collect(),
+                ),
+                Err(e) => json!({"error": e.to_string()}),
+            })
+            .collect();
+
+        let calls = calls.lock().unwrap().clone();
+        (results, calls)
+    }
+
+    #[cfg_attr(not(target_family = "wasm"), tokio::test)]
+    #[cfg_attr(target_family = "wasm", wasm_bindgen_test::wasm_bindgen_test)]
+    async fn queries_grouped_by_field_and_selection() {
+        let (results, calls) = resolve_batch(
+            Batch::Resolve,
+            &[
+                "{ concert(id: 1) { id title } }",
+                "{ venue(id: 1) { id name } }",
+                "{ concert(id: 2) { id title } }",
+                "{ concert(id: 1) { title } }",
+                "{ venue(id: 2) { id name } }",
+                "{ c: concert(id: 2) { id title } }",
+            ],
+        )
+        .await;
+
+        assert_eq!(
+            calls,
+            vec![
+                "batch[concert(1), concert(2), concert(2)]",
+                "batch[venue(1), venue(2)]",
+                // A different selection isn't resolved with the other concert queries
+                "concert(1)",
+            ]
+        );
+        assert_eq!(
+            results,
+            vec![
+                json!({"concert": {"id": 1, "title": "C1"}}),
+                json!({"venue": {"id": 1, "name": "V1"}}),
+                json!({"concert": {"id": 2, "title": "C2"}}),
+                json!({"concert": {"title": "C1"}}),
+                json!({"venue": {"id": 2, "name": "V2"}}),
+                json!({"c": {"id": 2, "title": "C2"}}),
+            ]
+        );
+    }
+
+    #[cfg_attr(not(target_family = "wasm"), tokio::test)]
+    #[cfg_attr(target_family = "wasm", wasm_bindgen_test::wasm_bindgen_test)]
+    async fn mutations_end_groups() {
+        let (results, calls) = resolve_batch(
+            Batch::Resolve,
+            &[
+                "{ concert(id: 1) { id title } }",
+                r#"mutation { createConcert(title: "C3") { id title } }"#,
+                "{ concert(id: 2) { id title } }",
+                "{ concert(id: 3) { id title } }",
+            ],
+        )
+        .await;
+
+        // The query after the mutation isn't resolved (before it) with the one before
+        assert_eq!(
+            calls,
+            vec![
+                "concert(1)",
+                r#"createConcert("C3")"#,
+                "batch[concert(2), concert(3)]",
+            ]
+        );
+        assert_eq!(
+            results,
+            vec![
+                json!({"concert": {"id": 1, "title": "C1"}}),
+                json!({"createConcert": {"id": 3, "title": "C3"}}),
+                json!({"concert": {"id": 2, "title": "C2"}}),
+                json!({"concert": null}),
+            ]
+        );
+    }
+
+    #[cfg_attr(not(target_family = "wasm"), tokio::test)]
+    #[cfg_attr(target_family = "wasm", wasm_bindgen_test::wasm_bindgen_test)]
+    async fn batch_responses_per_query() {
+        let (results, calls) = resolve_batch(
+            Batch::Resolve,
+            &[
+                "{ concert(id: 1) { id title } }",
+                "{ concert(id: 5) { id title } }",
+                "{ concert(id: 2) { id title } }",
+                "{ concert(id: 2) { id rating } }",
+            ],
+        )
+        .await;
+
+        assert_eq!(calls, vec!["batch[concert(1), concert(5), concert(2)]"]);
+        // Each query gets its own response (including `null` if there is no matching entity) or
+        // error
+        assert_eq!(results[0], json!({"concert": {"id": 1, "title": "C1"}}));
+        assert_eq!(results[1], json!({"concert": null}));
+        assert_eq!(results[2], json!({"concert": {"id": 2, "title": "C2"}}));
+        assert!(results[3].get("error").is_some());
+    }
+
+    #[cfg_attr(not(target_family = "wasm"), tokio::test)]
+    #[cfg_attr(target_family = "wasm", wasm_bindgen_test::wasm_bindgen_test)]
+    async fn unresolved_batches_resolved_individually() {
+        for batch in [Batch::Decline, Batch::Fail] {
+            let (results, calls) = resolve_batch(
+                batch,
+                &[
+                    "{ concert(id: 1) { id title } }",
+                    "{ concert(id: 5) { id title } }",
+                ],
+            )
+            .await;
+
+            assert_eq!(
+                calls,
+                vec!["batch[concert(1), concert(5)]", "concert(1)", "concert(5)",]
+            );
+            assert_eq!(
+                results,
+                vec![
+                    json!({"concert": {"id": 1, "title": "C1"}}),
+                    json!({"concert": null}),
+                ]
+            );
+        }
+    }
+}
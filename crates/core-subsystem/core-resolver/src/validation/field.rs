@@ -12,7 +12,7 @@ use serde::Serialize;
 
 use crate::value::val::Val;
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, PartialEq)]
 pub struct ValidatedField {
     pub alias: Option<Name>,
     /// The name of the field.
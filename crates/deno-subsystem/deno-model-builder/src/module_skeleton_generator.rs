@@ -139,6 +139,10 @@ pub fn generate_module_skeleton(
         )?;
     }
 
+    for job in module.jobs.iter() {
+        generate_method_skeleton(&job.name, &job.arguments, None, &mut file, is_typescript)?;
+    }
+
     Ok(())
 }
 
@@ -246,7 +250,11 @@ fn import_types(
             .iter()
             .flat_map(|interceptor| interceptor.arguments.iter());
 
-        method_arguments.chain(interceptor_arguments)
+        let job_arguments = module.jobs.iter().flat_map(|job| job.arguments.iter());
+
+        method_arguments
+            .chain(interceptor_arguments)
+            .chain(job_arguments)
     }
 
     let mut types_used = arguments(module)
@@ -15,7 +15,7 @@ use core_plugin_interface::{
         error::ModelBuildingError,
         plugin::{Interception, SubsystemBuild},
         typechecker::{
-            annotation::{AnnotationSpec, AnnotationTarget},
+            annotation::{AnnotationSpec, AnnotationTarget, MappedAnnotationParamSpec},
             typ::TypecheckedSystem,
         },
     },
@@ -41,7 +41,29 @@ impl SubsystemBuilder for DenoSubsystemBuilder {
                 targets: &[AnnotationTarget::Module],
                 no_params: false,
                 single_params: true,
-                mapped_params: None,
+                // Permissions granted to the module (in addition to the module path as the first parameter)
+                mapped_params: Some(&[
+                    MappedAnnotationParamSpec {
+                        name: "allowNet",
+                        optional: true,
+                    },
+                    MappedAnnotationParamSpec {
+                        name: "allowEnv",
+                        optional: true,
+                    },
+                    MappedAnnotationParamSpec {
+                        name: "allowRead",
+                        optional: true,
+                    },
+                    MappedAnnotationParamSpec {
+                        name: "allowWrite",
+                        optional: true,
+                    },
+                    MappedAnnotationParamSpec {
+                        name: "allowRun",
+                        optional: true,
+                    },
+                ]),
             },
         )]
     }
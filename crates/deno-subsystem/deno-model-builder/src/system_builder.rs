@@ -10,9 +10,10 @@
 use std::{collections::HashMap, path::Path, sync::Arc};
 
 use core_plugin_interface::{
+    codemap_diagnostic::{Diagnostic, Level, SpanLabel, SpanStyle},
     core_model::mapped_arena::{MappedArena, SerializableSlabIndex},
     core_model_builder::{
-        ast::ast_types::{AstExpr, AstModule},
+        ast::ast_types::{AstAnnotationParams, AstExpr, AstModule},
         builder::{resolved_builder::AnnotationMapHelper, system_builder::BaseModelSystem},
         error::ModelBuildingError,
         typechecker::{typ::TypecheckedSystem, Typed},
@@ -43,7 +44,10 @@ use deno_runtime::{
     permissions::PermissionsContainer,
 };
 use deno_virtual_fs::virtual_fs::{VfsBuilder, VirtualDirectory};
-use exo_deno::deno_executor_pool::{DenoScriptDefn, ResolvedModule};
+use exo_deno::{
+    deno_executor_pool::{DenoScriptDefn, ResolvedModule},
+    DenoPermissions,
+};
 use url::Url;
 
 use crate::module_skeleton_generator;
@@ -74,6 +78,7 @@ pub async fn build(
     if underlying_module_system.queries.is_empty()
         && underlying_module_system.mutations.is_empty()
         && underlying_module_system.interceptors.is_empty()
+        && underlying_module_system.jobs.is_empty()
     {
         return Ok(None);
     }
@@ -97,6 +102,7 @@ pub async fn build(
             methods: underlying_module_system.methods,
             scripts: underlying_module_system.scripts,
             interceptors: underlying_module_system.interceptors,
+            jobs: underlying_module_system.jobs,
         },
         interceptors: module_system.interceptors,
     }))
@@ -109,6 +115,8 @@ fn process_script(
 ) -> Result<(String, Vec<u8>), ModelBuildingError> {
     module_skeleton_generator::generate_module_skeleton(module, base_system, module_fs_path)?;
 
+    let permissions = extract_permissions(module.annotations.get("deno"))?;
+
     fn run_local<F, R>(future: F) -> R
     where
         F: std::future::Future<Output = R>,
@@ -225,6 +233,7 @@ fn process_script(
                     vfs.0,
                     vfs.1,
                 )),
+                permissions,
             })
         };
         run_local(future)
@@ -235,6 +244,46 @@ fn process_script(
     Ok((root.to_string(), serde_json::to_vec(&script_defn).unwrap()))
 }
 
+/// Extract the permissions declared through the `@deno` annotation. For example,
+/// `@deno("payments.ts", allowNet=["api.stripe.com"], allowEnv=["STRIPE_KEY"])`.
+///
+/// Returns `None` if the module doesn't declare any permissions (`@deno("payments.ts")`).
+fn extract_permissions(
+    annotation_params: Option<&AstAnnotationParams<Typed>>,
+) -> Result<Option<DenoPermissions>, ModelBuildingError> {
+    let params = match annotation_params {
+        Some(AstAnnotationParams::Mixed(_, _, params, _)) => params,
+        _ => return Ok(None),
+    };
+
+    let allow_list = |param_name: &str| match params.get(param_name) {
+        None => Ok(vec![]),
+        Some(AstExpr::StringLiteral(value, _)) => Ok(vec![value.clone()]),
+        Some(AstExpr::StringList(values, _)) => Ok(values.clone()),
+        Some(expr) => Err(ModelBuildingError::Diagnosis(vec![Diagnostic {
+            level: Level::Error,
+            message: format!(
+                "The `{param_name}` parameter of `@deno` must be a string or a list of strings"
+            ),
+            code: Some("C000".to_string()),
+            spans: vec![SpanLabel {
+                span: expr.span(),
+                style: SpanStyle::Primary,
+                label: None,
+            }],
+        }])),
+    };
+
+    Ok(Some(DenoPermissions {
+        allow_all: false,
+        allow_net: allow_list("allowNet")?,
+        allow_env: allow_list("allowEnv")?,
+        allow_read: allow_list("allowRead")?,
+        allow_write: allow_list("allowWrite")?,
+        allow_run: allow_list("allowRun")?,
+    }))
+}
+
 #[allow(clippy::too_many_arguments)]
 fn walk_node_resolutions(
     root: NodeResolution,
@@ -545,3 +594,93 @@ fn walk_module_graph(
     }
     Ok(modules)
 }
+
+#[cfg(test)]
+mod tests {
+    use core_plugin_interface::core_model_builder::ast::ast_types::default_span;
+
+    use super::*;
+
+    fn deno_annotation(params: Vec<(&str, AstExpr<Typed>)>) -> AstAnnotationParams<Typed> {
+        let spans = params
+            .iter()
+            .map(|(name, _)| (name.to_string(), vec![default_span()]))
+            .collect();
+        let params = params
+            .into_iter()
+            .map(|(name, expr)| (name.to_string(), expr))
+            .collect();
+
+        AstAnnotationParams::Mixed(
+            AstExpr::StringLiteral("payments.ts".to_string(), default_span()),
+            default_span(),
+            params,
+            spans,
+        )
+    }
+
+    fn string_list(values: &[&str]) -> AstExpr<Typed> {
+        AstExpr::StringList(
+            values.iter().map(|value| value.to_string()).collect(),
+            values.iter().map(|_| default_span()).collect(),
+        )
+    }
+
+    #[test]
+    fn declared_permissions() {
+        let params = deno_annotation(vec![
+            (
+                "allowNet",
+                string_list(&["api.stripe.com", "api.example.com"]),
+            ),
+            (
+                "allowEnv",
+                AstExpr::StringLiteral("STRIPE_KEY".to_string(), default_span()),
+            ),
+            ("allowRead", string_list(&[])),
+        ]);
+
+        assert_eq!(
+            extract_permissions(Some(&params)).unwrap(),
+            Some(DenoPermissions {
+                allow_all: false,
+                allow_net: vec!["api.stripe.com".to_string(), "api.example.com".to_string()],
+                allow_env: vec!["STRIPE_KEY".to_string()],
+                allow_read: vec![],
+                allow_write: vec![],
+                allow_run: vec![],
+            })
+        );
+    }
+
+    #[test]
+    fn undeclared_permissions() {
+        let params = AstAnnotationParams::Single(
+            AstExpr::StringLiteral("payments.ts".to_string(), default_span()),
+            default_span(),
+        );
+
+        assert_eq!(extract_permissions(Some(&params)).unwrap(), None);
+        assert_eq!(extract_permissions(None).unwrap(), None);
+
+        // Naming only some permissions denies the rest
+        let params = deno_annotation(vec![]);
+        assert_eq!(
+            extract_permissions(Some(&params)).unwrap(),
+            Some(DenoPermissions::default())
+        );
+    }
+
+    #[test]
+    fn invalid_permissions() {
+        let params = deno_annotation(vec![(
+            "allowRun",
+            AstExpr::NumberLiteral(1, default_span()),
+        )]);
+
+        assert!(matches!(
+            extract_permissions(Some(&params)),
+            Err(ModelBuildingError::Diagnosis(_))
+        ));
+    }
+}
@@ -24,6 +24,7 @@ use serde::{Deserialize, Serialize};
 use super::module::Script;
 use crate::{
     interceptor::Interceptor,
+    job::Job,
     module::ModuleMethod,
     operation::{DenoMutation, DenoQuery},
     types::ModuleType,
@@ -44,6 +45,7 @@ pub struct DenoSubsystem {
     pub methods: SerializableSlab<ModuleMethod>,
     pub scripts: SerializableSlab<Script>,
     pub interceptors: SerializableSlab<Interceptor>,
+    pub jobs: SerializableSlab<Job>,
 }
 
 impl DenoSubsystem {
@@ -9,6 +9,8 @@
 const {
     op_exograph_execute_query,
     op_exograph_execute_query_priv,
+    op_exograph_execute_queries,
+    op_exograph_enqueue_job,
     op_exograph_add_header,
     op_exograph_version,
     op_operation_name,
@@ -20,12 +22,65 @@ export function exograph_version() {
     return op_exograph_version();
 }
 
+// Queries issued in the same tick (for example, one per item through `Promise.all`) are sent
+// together, so that lookups of the same kind may be resolved with a single statement. Each entry
+// holds the query, a function to execute it alone, and the functions to settle its promise.
+let pendingQueries = [];
+
+function enqueueQuery(query, executeAlone) {
+    return new Promise((resolve, reject) => {
+        pendingQueries.push({ query, executeAlone, resolve, reject });
+
+        if (pendingQueries.length === 1) {
+            queueMicrotask(executePendingQueries);
+        }
+    });
+}
+
+async function executePendingQueries() {
+    const queries = pendingQueries;
+    pendingQueries = [];
+
+    if (queries.length === 1) {
+        const { executeAlone, resolve, reject } = queries[0];
+        try {
+            resolve(await executeAlone());
+        } catch (e) {
+            reject(e);
+        }
+        return;
+    }
+
+    let results;
+    try {
+        results = await op_exograph_execute_queries(queries.map(({ query }) => query));
+    } catch (e) {
+        queries.forEach(({ reject }) => reject(e));
+        return;
+    }
+
+    queries.forEach(({ resolve, reject }, index) => {
+        const result = results[index];
+
+        if ("data" in result) {
+            resolve(result.data);
+        } else if (result.explicit && typeof ExographError === "function") {
+            reject(new ExographError(result.error));
+        } else {
+            reject(new Error(result.error));
+        }
+    });
+}
+
 // TODO: There's a lot of duplication between the shim code and the extension.
 // Ideally we'd get rid of the shim code and just expose the code directly from the extension.
 //
 globalThis.ExographExtension = ({
     executeQuery: async function (query_string, variables) {
-        const result = await op_exograph_execute_query(query_string, variables);
+        const result = await enqueueQuery(
+            { query: query_string, variables, contextOverride: null },
+            () => op_exograph_execute_query(query_string, variables)
+        );
         return result;
     },
 
@@ -34,9 +89,16 @@ globalThis.ExographExtension = ({
     },
 
     executeQueryPriv: async function (query_string, variables, context_override) {
-        const result = await op_exograph_execute_query_priv(query_string, variables, context_override);
+        const result = await enqueueQuery(
+            { query: query_string, variables, contextOverride: context_override ?? null },
+            () => op_exograph_execute_query_priv(query_string, variables, context_override)
+        );
         return result;
     },
+
+    enqueueJob: async function (name, args, options) {
+        return await op_exograph_enqueue_job(name, args, options);
+    },
 })
 
 globalThis.ExographOperation = ({
@@ -9,14 +9,16 @@
 
 //! [`AccessSolver`] for the Deno subsystem.
 
+use async_recursion::async_recursion;
 use async_trait::async_trait;
 
 use core_plugin_interface::{
     core_model::access::AccessRelationalOp,
     core_resolver::{
         access_solver::{
-            eq_values, gt_values, gte_values, in_values, lt_values, lte_values, neq_values,
-            reduce_common_primitive_expression, AccessPredicate, AccessSolver, AccessSolverError,
+            arithmetic_values, contains_values, eq_values, gt_values, gte_values, in_values,
+            lt_values, lte_values, neq_values, reduce_common_primitive_expression, AccessPredicate,
+            AccessSolver, AccessSolverError,
         },
         context::RequestContext,
         value::Val,
@@ -65,6 +67,7 @@ impl<'a> AccessSolver<'a, ModuleAccessPrimitiveExpression, ModuleAccessPredicate
         _input_context: Option<&'a Val>,
         op: &AccessRelationalOp<ModuleAccessPrimitiveExpression>,
     ) -> Result<Option<ModuleAccessPredicateWrapper>, AccessSolverError> {
+        #[async_recursion]
         async fn reduce_primitive_expression<'a>(
             solver: &DenoSubsystem,
             request_context: &'a RequestContext<'a>,
@@ -74,6 +77,30 @@ impl<'a> AccessSolver<'a, ModuleAccessPrimitiveExpression, ModuleAccessPredicate
                 ModuleAccessPrimitiveExpression::Common(common_expr) => {
                     reduce_common_primitive_expression(solver, request_context, common_expr).await?
                 }
+                ModuleAccessPrimitiveExpression::Arithmetic(left, op, right) => {
+                    let left = reduce_primitive_expression(solver, request_context, left).await?;
+                    let right = reduce_primitive_expression(solver, request_context, right).await?;
+
+                    match (left, right) {
+                        (Some(left), Some(right)) => arithmetic_values(*op, &left, &right),
+                        _ => None,
+                    }
+                }
+                ModuleAccessPrimitiveExpression::Check(call) => {
+                    let mut args = Vec::with_capacity(call.args.len());
+                    for arg in call.args.iter() {
+                        // Pass an unavailable argument (such as a missing context) as `null`, so
+                        // that the function decides how to handle it
+                        let arg = reduce_primitive_expression(solver, request_context, arg).await?;
+                        args.push(arg.unwrap_or(Val::Null));
+                    }
+
+                    Some(
+                        request_context
+                            .invoke_access_check(&call.function_name, &args)
+                            .await?,
+                    )
+                }
             })
         }
 
@@ -92,6 +119,7 @@ impl<'a> AccessSolver<'a, ModuleAccessPrimitiveExpression, ModuleAccessPredicate
                     AccessRelationalOp::Gt(_, _) => gt_values(left_value, right_value),
                     AccessRelationalOp::Gte(_, _) => gte_values(left_value, right_value),
                     AccessRelationalOp::In(..) => in_values(left_value, right_value),
+                    AccessRelationalOp::Contains(..) => contains_values(left_value, right_value),
                 }
                 .into(),
             )),
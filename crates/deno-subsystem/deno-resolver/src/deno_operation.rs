@@ -12,11 +12,11 @@ use indexmap::IndexMap;
 use core_plugin_interface::{
     core_resolver::{
         access_solver::{AccessSolver, AccessSolverError},
+        access_trace::{AccessDecision, AccessTraceEntry},
         context::RequestContext,
         context_extractor::ContextExtractor,
-        exograph_execute_query,
-        system_resolver::{ExographExecuteQueryFn, SystemResolver},
-        validation::field::ValidatedField,
+        exograph_execute_queries, exograph_execute_query,
+        system_resolver::{ExographExecuteQueriesFn, ExographExecuteQueryFn, SystemResolver},
         value::Val,
         QueryResponse, QueryResponseBody,
     },
@@ -24,7 +24,7 @@ use core_plugin_interface::{
 };
 
 use deno_model::{
-    module::{Argument, ModuleMethod},
+    module::{Argument, ModuleMethod, ModuleMethodType},
     subsystem::DenoSubsystem,
     types::{ModuleCompositeType, ModuleTypeKind},
 };
@@ -41,7 +41,7 @@ use std::collections::HashMap;
 
 pub struct DenoOperation<'a> {
     pub method: &'a ModuleMethod,
-    pub field: &'a ValidatedField,
+    pub arguments: &'a IndexMap<String, Val>,
     pub request_context: &'a RequestContext<'a>,
     pub subsystem_resolver: &'a DenoSubsystemResolver,
     pub system_resolver: &'a SystemResolver,
@@ -51,13 +51,48 @@ impl<'a> DenoOperation<'a> {
     pub async fn execute(&self) -> Result<QueryResponse, DenoExecutionError> {
         let access_predicate = self.compute_module_access_predicate().await?;
 
+        self.request_context.record_access(AccessTraceEntry {
+            rule: self.method.name.clone(),
+            operation: match self.method.operation_kind {
+                ModuleMethodType::Query(_) => "query",
+                ModuleMethodType::Mutation(_) => "mutation",
+            }
+            .to_string(),
+            decision: if access_predicate {
+                AccessDecision::Allowed
+            } else {
+                AccessDecision::Denied
+            },
+            residual: None,
+        });
+
         if !access_predicate {
             return Err(DenoExecutionError::Authorization);
         }
 
+        // Set the options before executing the code, so that its queries run in the transaction
+        // with these options
+        self.request_context
+            .set_transaction_options(&self.method.transaction)
+            .await
+            .map_err(|e| DenoExecutionError::Generic(e.to_string()))?;
+
         self.resolve_deno().await
     }
 
+    /// Execute the method to decide access for a `check(...)` in an access rule. As for an
+    /// operation, the method's access rules must allow the call (otherwise, the check fails).
+    pub async fn execute_check(&self) -> Result<Val, DenoExecutionError> {
+        if !self.compute_module_access_predicate().await? {
+            return Ok(Val::Bool(false));
+        }
+
+        match self.resolve_deno().await?.body {
+            QueryResponseBody::Json(result) => Ok(result.into()),
+            QueryResponseBody::Raw(_) => unreachable!("Deno methods produce JSON results"),
+        }
+    }
+
     async fn compute_module_access_predicate(&self) -> Result<bool, AccessSolverError> {
         let subsystem = &self.subsystem();
         let return_type = self.method.return_type.typ(&subsystem.module_types);
@@ -87,12 +122,16 @@ impl<'a> DenoOperation<'a> {
 
         let exograph_execute_query: &ExographExecuteQueryFn =
             exograph_execute_query!(self.system_resolver, self.request_context);
+        let exograph_execute_queries: &ExographExecuteQueriesFn =
+            exograph_execute_queries!(self.system_resolver, self.request_context);
 
         let arg_sequence: Vec<Arg> = self.construct_arg_sequence().await?;
 
         let callback_processor = ExoCallbackProcessor {
             exograph_execute_query,
+            exograph_execute_queries,
             exograph_proceed: None,
+            system_resolver: self.system_resolver,
         };
 
         let deserialized: DenoScriptDefn = serde_json::from_slice(&script.script).unwrap();
@@ -119,7 +158,7 @@ impl<'a> DenoOperation<'a> {
 
     pub async fn construct_arg_sequence(&self) -> Result<Vec<Arg>, DenoExecutionError> {
         construct_arg_sequence(
-            &self.field.arguments,
+            self.arguments,
             &self.method.arguments,
             self.subsystem(),
             self.request_context,
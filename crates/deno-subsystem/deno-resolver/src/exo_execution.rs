@@ -14,9 +14,13 @@ use async_trait::async_trait;
 use futures::future::BoxFuture;
 use serde_json::Value;
 
+use common::trace_context::TraceContext;
 use core_plugin_interface::{
     core_resolver::{
-        system_resolver::{ExographExecuteQueryFn, SystemResolutionError},
+        job::{EnqueueOptions, JobError},
+        system_resolver::{
+            ExographExecuteQueriesFn, ExographExecuteQueryFn, SystemResolutionError, SystemResolver,
+        },
         QueryResponse,
     },
     trusted_documents::TrustedDocumentEnforcement,
@@ -45,11 +49,37 @@ pub enum RequestFromDenoMessage {
         context_override: Value,
         response_sender: oneshot::Sender<ResponseForDenoMessage>,
     },
+    /// Queries issued concurrently (in the same tick) by module code, so that they may be resolved
+    /// together
+    ExographExecuteBatch {
+        queries: Vec<BatchedQuery>,
+        response_sender: oneshot::Sender<ResponseForDenoMessage>,
+    },
+    EnqueueJob {
+        name: String,
+        arguments: Value,
+        options: EnqueueOptions,
+        response_sender: oneshot::Sender<ResponseForDenoMessage>,
+    },
+    /// The trace context of the operation (to propagate it to the services module code calls)
+    TraceContext {
+        response_sender: oneshot::Sender<ResponseForDenoMessage>,
+    },
 }
 
 pub enum ResponseForDenoMessage {
     InterceptedOperationProceed(Result<QueryResponse, SystemResolutionError>),
     ExographExecute(Result<QueryResponse, SystemResolutionError>),
+    ExographExecuteBatch(Vec<Result<QueryResponse, SystemResolutionError>>),
+    EnqueueJob(Result<Option<i64>, JobError>),
+    TraceContext(Option<TraceContext>),
+}
+
+/// A query sent as part of [`RequestFromDenoMessage::ExographExecuteBatch`]
+pub struct BatchedQuery {
+    pub query_string: String,
+    pub variables: Option<serde_json::Map<String, Value>>,
+    pub context_override: Value,
 }
 
 pub type FnExographInterceptorProceed<'a> =
@@ -57,7 +87,9 @@ pub type FnExographInterceptorProceed<'a> =
 
 pub struct ExoCallbackProcessor<'a, 'b> {
     pub exograph_execute_query: &'a ExographExecuteQueryFn<'a>,
+    pub exograph_execute_queries: &'a ExographExecuteQueriesFn<'a>,
     pub exograph_proceed: Option<&'b FnExographInterceptorProceed<'a>>,
+    pub system_resolver: &'a SystemResolver,
 }
 
 #[async_trait]
@@ -91,6 +123,50 @@ impl<'a> CallbackProcessor<RequestFromDenoMessage> for ExoCallbackProcessor<'a,
                     .ok()
                     .unwrap();
             }
+            RequestFromDenoMessage::ExographExecuteBatch {
+                queries,
+                response_sender,
+            } => {
+                let mut query_results = Vec::with_capacity(queries.len());
+
+                for (context_override, group) in group_by_context_override(queries) {
+                    query_results.extend(
+                        (self.exograph_execute_queries)(
+                            group,
+                            TrustedDocumentEnforcement::DoNotEnforce,
+                            context_override,
+                        )
+                        .await,
+                    );
+                }
+
+                response_sender
+                    .send(ResponseForDenoMessage::ExographExecuteBatch(query_results))
+                    .ok()
+                    .unwrap();
+            }
+            RequestFromDenoMessage::EnqueueJob {
+                name,
+                arguments,
+                options,
+                response_sender,
+            } => {
+                let enqueue_result = self
+                    .system_resolver
+                    .enqueue_job(&name, &arguments, &options)
+                    .await;
+                response_sender
+                    .send(ResponseForDenoMessage::EnqueueJob(enqueue_result))
+                    .ok()
+                    .unwrap();
+            }
+            RequestFromDenoMessage::TraceContext { response_sender } => {
+                // Callbacks are processed in the task (and the span) resolving the operation
+                response_sender
+                    .send(ResponseForDenoMessage::TraceContext(TraceContext::current()))
+                    .ok()
+                    .unwrap();
+            }
         }
     }
 }
@@ -109,7 +185,10 @@ const SHIMS: [(&str, &[&str]); 3] = {
 };
 
 const USER_AGENT: &str = "Exograph";
-const ADDITIONAL_CODE: &[&str] = &[include_str!("./exograph_error.js")];
+const ADDITIONAL_CODE: &[&str] = &[
+    include_str!("./exograph_error.js"),
+    include_str!("./trace_context.js"),
+];
 const EXPLICIT_ERROR_CLASS_NAME: Option<&'static str> = Some("ExographError");
 
 pub fn process_call_context(
@@ -127,8 +206,11 @@ deno_core::extension!(
     ops = [
         super::exograph_ops::op_exograph_execute_query,
         super::exograph_ops::op_exograph_execute_query_priv,
+        super::exograph_ops::op_exograph_execute_queries,
+        super::exograph_ops::op_exograph_enqueue_job,
         super::exograph_ops::op_exograph_add_header,
         super::exograph_ops::op_exograph_version,
+        super::exograph_ops::op_exograph_trace_context,
         super::exograph_ops::op_operation_name,
         super::exograph_ops::op_operation_query,
         super::exograph_ops::op_operation_proceed,
@@ -157,13 +239,39 @@ pub fn exo_config() -> DenoExecutorConfig<Option<InterceptedOperationInfo>> {
     )
 }
 
+/// Group consecutive queries with the same context override (each override makes a different
+/// request context, so only queries with the same one may be resolved together)
+#[allow(clippy::type_complexity)]
+fn group_by_context_override(
+    queries: Vec<BatchedQuery>,
+) -> Vec<(Value, Vec<(String, Option<serde_json::Map<String, Value>>)>)> {
+    let mut groups = vec![];
+
+    let mut queries = queries.into_iter().peekable();
+    while let Some(query) = queries.next() {
+        let context_override = query.context_override;
+        let mut group = vec![(query.query_string, query.variables)];
+        while let Some(query) = queries.next_if(|query| query.context_override == context_override)
+        {
+            group.push((query.query_string, query.variables));
+        }
+        groups.push((context_override, group));
+    }
+
+    groups
+}
+
 #[cfg(test)]
 mod tests {
     use std::path::Path;
     use test_log::test;
 
-    use super::exograph;
-    use exo_deno::{DenoModule, DenoModuleSharedState, UserCode};
+    use super::{
+        exograph, group_by_context_override, BatchedQuery, RequestFromDenoMessage,
+        ResponseForDenoMessage,
+    };
+    use common::trace_context::TraceContext;
+    use exo_deno::{Arg, DenoModule, DenoModuleSharedState, UserCode};
 
     #[test]
     #[allow(deprecated)]
@@ -180,6 +288,45 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn batched_queries_grouped_by_context_override() {
+        let query = |query_string: &str, context_override: serde_json::Value| BatchedQuery {
+            query_string: query_string.to_string(),
+            variables: None,
+            context_override,
+        };
+        let admin = serde_json::json!({"AuthContext": {"role": "admin"}});
+
+        let groups = group_by_context_override(vec![
+            query("q1", serde_json::Value::Null),
+            query("q2", serde_json::Value::Null),
+            query("q3", admin.clone()),
+            query("q4", admin.clone()),
+            query("q5", serde_json::Value::Null),
+        ]);
+
+        let groups: Vec<_> = groups
+            .into_iter()
+            .map(|(context_override, group)| {
+                let query_strings: Vec<_> = group.into_iter().map(|(query, _)| query).collect();
+                (context_override, query_strings)
+            })
+            .collect();
+
+        // Only consecutive queries are grouped (so that the results stay in order)
+        assert_eq!(
+            groups,
+            vec![
+                (
+                    serde_json::Value::Null,
+                    vec!["q1".to_string(), "q2".to_string()]
+                ),
+                (admin, vec!["q3".to_string(), "q4".to_string()]),
+                (serde_json::Value::Null, vec!["q5".to_string()]),
+            ]
+        );
+    }
+
     #[test(tokio::test)]
     async fn test_call_version_op() {
         let mut deno_module = DenoModule::new(
@@ -197,6 +344,7 @@ mod tests {
             None,
             None,
             None,
+            None,
         )
         .await
         .unwrap();
@@ -210,4 +358,94 @@ mod tests {
             serde_json::Value::String(env!("CARGO_PKG_VERSION").into())
         );
     }
+
+    /// A `fetch` that responds with the headers of the request (instead of making it)
+    const RESPONDING_FETCH: &str = r#"
+        globalThis.fetch = async function fetch(input, init) {
+            const request = new Request(input, init);
+            return new Response(JSON.stringify(Object.fromEntries(request.headers)));
+        };
+    "#;
+
+    #[test(tokio::test)]
+    async fn trace_context_propagated_to_fetch() {
+        const TRACEPARENT: &str = "00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01";
+
+        let mut deno_module = DenoModule::new(
+            UserCode::LoadFromFs(
+                Path::new("src")
+                    .join("test_js")
+                    .join("test_trace_context.js")
+                    .to_owned(),
+            ),
+            "deno_module",
+            vec![],
+            vec![RESPONDING_FETCH, include_str!("./trace_context.js")],
+            vec![exograph::init_ops_and_esm()],
+            DenoModuleSharedState::default(),
+            None,
+            None,
+            None,
+            None,
+        )
+        .await
+        .unwrap();
+
+        // Respond with a trace context (as `ExoCallbackProcessor` does while resolving an
+        // operation)
+        let (sender, mut receiver) = tokio::sync::mpsc::channel(1);
+        deno_module.put(sender).unwrap();
+        tokio::spawn(async move {
+            while let Some(RequestFromDenoMessage::TraceContext { response_sender }) =
+                receiver.recv().await
+            {
+                let trace_context = TraceContext {
+                    traceparent: TRACEPARENT.to_string(),
+                    tracestate: Some("congo=t61rcWkgMzE".to_string()),
+                };
+                response_sender
+                    .send(ResponseForDenoMessage::TraceContext(Some(trace_context)))
+                    .ok();
+            }
+        });
+
+        for function_name in ["fetchHeaders", "fetchRequestHeaders"] {
+            let headers = deno_module
+                .execute_function(
+                    function_name,
+                    vec![Arg::Serde(serde_json::json!({"x-request-id": "1"}))],
+                )
+                .await
+                .unwrap();
+            assert_eq!(
+                headers,
+                serde_json::json!({
+                    "traceparent": TRACEPARENT,
+                    "tracestate": "congo=t61rcWkgMzE",
+                    "x-request-id": "1"
+                }),
+                "{function_name}"
+            );
+
+            // Headers set by the module are kept
+            let module_traceparent = "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01";
+            let headers = deno_module
+                .execute_function(
+                    function_name,
+                    vec![Arg::Serde(
+                        serde_json::json!({"traceparent": module_traceparent}),
+                    )],
+                )
+                .await
+                .unwrap();
+            assert_eq!(
+                headers,
+                serde_json::json!({
+                    "traceparent": module_traceparent,
+                    "tracestate": "congo=t61rcWkgMzE"
+                }),
+                "{function_name}"
+            );
+        }
+    }
 }
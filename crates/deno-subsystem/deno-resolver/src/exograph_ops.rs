@@ -10,13 +10,16 @@
 use anyhow::{anyhow, bail, Result};
 use exo_deno::deno_core::{error::AnyError, op2, OpState};
 
-use core_plugin_interface::core_resolver::system_resolver::SystemResolutionError;
+use core_plugin_interface::core_resolver::{
+    job::EnqueueOptions, system_resolver::SystemResolutionError,
+};
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use std::{cell::RefCell, rc::Rc};
+use std::{cell::RefCell, rc::Rc, time::Duration};
 use tokio::sync::mpsc::Sender;
 
 use super::exo_execution::{
-    ExographMethodResponse, RequestFromDenoMessage, ResponseForDenoMessage,
+    BatchedQuery, ExographMethodResponse, RequestFromDenoMessage, ResponseForDenoMessage,
 };
 
 #[derive(Debug)]
@@ -96,12 +99,225 @@ pub async fn op_exograph_execute_query_priv(
     op_exograph_execute_query_helper(state, query_string, variables, context_override).await
 }
 
+/// A query issued through `executeQuery` or `executeQueryPriv` (see `extension/exograph.js`)
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QueryRequest {
+    query: String,
+    variables: Option<serde_json::Map<String, Value>>,
+    #[serde(default)]
+    context_override: Value,
+}
+
+/// The result of a query in a batch. Since one query failing shouldn't fail the others, we return
+/// an error as a value (which `extension/exograph.js` throws for the caller of that query).
+#[derive(Serialize)]
+#[serde(untagged)]
+pub enum QueryResult {
+    Data {
+        data: Value,
+    },
+    Error {
+        error: String,
+        /// Whether the message is meant for the user (and so must be thrown as an
+        /// `ExographError`), see `process_execution_error`
+        explicit: bool,
+    },
+}
+
+/// Execute queries issued concurrently (in the same tick) by module code together, so that
+/// lookups (such as one per item in a list) may be resolved with a single statement
+#[op2(async)]
+#[serde]
+pub async fn op_exograph_execute_queries(
+    state: Rc<RefCell<OpState>>,
+    #[serde] queries: Vec<QueryRequest>,
+) -> Result<Vec<QueryResult>, AnyError> {
+    let (response_sender, response_receiver) = tokio::sync::oneshot::channel();
+
+    let sender = {
+        let state = state.borrow();
+        state.borrow::<Sender<RequestFromDenoMessage>>().to_owned()
+    };
+
+    sender
+        .send(RequestFromDenoMessage::ExographExecuteBatch {
+            queries: queries
+                .into_iter()
+                .map(|query| BatchedQuery {
+                    query_string: query.query,
+                    variables: query.variables,
+                    context_override: query.context_override,
+                })
+                .collect(),
+            response_sender,
+        })
+        .await
+        .map_err(|err| {
+            anyhow!(
+                "Could not send request from op_exograph_execute_queries ({})",
+                err
+            )
+        })?;
+
+    if let ResponseForDenoMessage::ExographExecuteBatch(results) =
+        response_receiver.await.map_err(|err| {
+            anyhow!(
+                "Could not receive result in op_exograph_execute_queries ({})",
+                err
+            )
+        })?
+    {
+        let mut query_results = Vec::with_capacity(results.len());
+
+        for result in results {
+            query_results.push(match result {
+                Ok(result) => {
+                    for (header, value) in result.headers.into_iter() {
+                        let mut state = state.borrow_mut();
+
+                        add_header(&mut state, header, value)?
+                    }
+
+                    QueryResult::Data {
+                        data: result.body.to_json()?,
+                    }
+                }
+                Err(err) => match err.explicit_message() {
+                    Some(msg) => QueryResult::Error {
+                        error: msg,
+                        explicit: true,
+                    },
+                    None => QueryResult::Error {
+                        error: err.to_string(),
+                        explicit: false,
+                    },
+                },
+            });
+        }
+
+        Ok(query_results)
+    } else {
+        bail!("Wrong response type for op_exograph_execute_queries")
+    }
+}
+
+/// Options accepted by `ExographPriv.enqueueJob` (the delay is in seconds)
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct JobOptions {
+    delay: Option<f64>,
+    max_attempts: Option<u32>,
+    unique_key: Option<String>,
+}
+
+#[op2(async)]
+#[serde]
+pub async fn op_exograph_enqueue_job(
+    state: Rc<RefCell<OpState>>,
+    #[string] name: String,
+    #[serde] arguments: Option<serde_json::Value>,
+    #[serde] options: Option<JobOptions>,
+) -> Result<Option<i64>, AnyError> {
+    let (response_sender, response_receiver) = tokio::sync::oneshot::channel();
+
+    let sender = {
+        let state = state.borrow();
+        state.borrow::<Sender<RequestFromDenoMessage>>().to_owned()
+    };
+
+    let options = options.unwrap_or_default();
+    let delay = options
+        .delay
+        .map(|delay| {
+            Duration::try_from_secs_f64(delay)
+                .map_err(|_| anyhow!("Invalid job delay {delay} (must be non-negative seconds)"))
+        })
+        .transpose()?;
+
+    sender
+        .send(RequestFromDenoMessage::EnqueueJob {
+            name,
+            arguments: arguments.unwrap_or_else(|| Value::Object(Default::default())),
+            options: EnqueueOptions {
+                delay,
+                max_attempts: options.max_attempts,
+                unique_key: options.unique_key,
+            },
+            response_sender,
+        })
+        .await
+        .map_err(|err| {
+            anyhow!(
+                "Could not send request from op_exograph_enqueue_job ({})",
+                err
+            )
+        })?;
+
+    if let ResponseForDenoMessage::EnqueueJob(result) = response_receiver.await.map_err(|err| {
+        anyhow!(
+            "Could not receive result in op_exograph_enqueue_job ({})",
+            err
+        )
+    })? {
+        Ok(result?)
+    } else {
+        bail!("Wrong response type for op_exograph_enqueue_job")
+    }
+}
+
 #[op2]
 #[string]
 pub fn op_exograph_version() -> &'static str {
     env!("CARGO_PKG_VERSION")
 }
 
+#[derive(Serialize)]
+pub struct TraceContextHeaders {
+    traceparent: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tracestate: Option<String>,
+}
+
+#[op2(async)]
+#[serde]
+pub async fn op_exograph_trace_context(
+    state: Rc<RefCell<OpState>>,
+) -> Result<Option<TraceContextHeaders>, AnyError> {
+    let (response_sender, response_receiver) = tokio::sync::oneshot::channel();
+
+    let sender = {
+        let state = state.borrow();
+        state.borrow::<Sender<RequestFromDenoMessage>>().to_owned()
+    };
+
+    sender
+        .send(RequestFromDenoMessage::TraceContext { response_sender })
+        .await
+        .map_err(|err| {
+            anyhow!(
+                "Could not send request from op_exograph_trace_context ({})",
+                err
+            )
+        })?;
+
+    if let ResponseForDenoMessage::TraceContext(trace_context) =
+        response_receiver.await.map_err(|err| {
+            anyhow!(
+                "Could not receive result in op_exograph_trace_context ({})",
+                err
+            )
+        })?
+    {
+        Ok(trace_context.map(|trace_context| TraceContextHeaders {
+            traceparent: trace_context.traceparent,
+            tracestate: trace_context.tracestate,
+        }))
+    } else {
+        bail!("Wrong response type for op_exograph_trace_context")
+    }
+}
+
 #[op2]
 #[string]
 pub fn op_operation_name(state: &mut OpState) -> Result<String, AnyError> {
@@ -12,4 +12,8 @@
         const result = await ExographExtension.executeQueryPriv(query_string, variables, context_override);
         return result;
     },
+
+    enqueueJob: async function (name, args, options) {
+        return await ExographExtension.enqueueJob(name, args, options);
+    },
 })
@@ -8,7 +8,9 @@
 // by the Apache License, Version 2.0.
 
 use core_plugin_interface::core_resolver::{
-    context::RequestContext, system_resolver::ExographExecuteQueryFn, InterceptedOperation,
+    context::RequestContext,
+    system_resolver::{ExographExecuteQueriesFn, ExographExecuteQueryFn, SystemResolver},
+    InterceptedOperation,
 };
 use deno_model::interceptor::Interceptor;
 use exo_deno::{deno_executor_pool::DenoScriptDefn, Arg};
@@ -28,7 +30,9 @@ pub async fn execute_interceptor<'a>(
     subsystem_resolver: &'a DenoSubsystemResolver,
     request_context: &'a RequestContext<'a>,
     exograph_execute_query: &'a ExographExecuteQueryFn<'a>,
+    exograph_execute_queries: &'a ExographExecuteQueriesFn<'a>,
     intercepted_operation: &'a InterceptedOperation<'a>,
+    system_resolver: &'a SystemResolver,
 ) -> Result<(Value, Option<ExographMethodResponse>), DenoExecutionError> {
     let script = &subsystem_resolver.subsystem.scripts[interceptor.script];
 
@@ -44,7 +48,9 @@ pub async fn execute_interceptor<'a>(
 
     let callback_processor = ExoCallbackProcessor {
         exograph_execute_query,
+        exograph_execute_queries,
         exograph_proceed: Some(&intercepted_operation_resolver),
+        system_resolver,
     };
 
     let deserialized: DenoScriptDefn = serde_json::from_slice(&script.script).unwrap();
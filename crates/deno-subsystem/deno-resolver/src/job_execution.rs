@@ -0,0 +1,80 @@
+// Copyright Exograph, Inc. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file at the root of this repository.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use core_plugin_interface::core_resolver::{
+    context::RequestContext,
+    system_resolver::{ExographExecuteQueriesFn, ExographExecuteQueryFn, SystemResolver},
+    value::Val,
+};
+use deno_model::job::Job;
+use exo_deno::{deno_executor_pool::DenoScriptDefn, Arg};
+use indexmap::IndexMap;
+use serde_json::Value;
+
+use crate::{deno_operation::construct_arg_sequence, plugin::DenoSubsystemResolver};
+
+use super::{deno_execution_error::DenoExecutionError, exo_execution::ExoCallbackProcessor};
+
+pub async fn execute_job<'a>(
+    job: &Job,
+    arguments: &Value,
+    subsystem_resolver: &'a DenoSubsystemResolver,
+    request_context: &'a RequestContext<'a>,
+    exograph_execute_query: &'a ExographExecuteQueryFn<'a>,
+    exograph_execute_queries: &'a ExographExecuteQueriesFn<'a>,
+    system_resolver: &'a SystemResolver,
+) -> Result<(), DenoExecutionError> {
+    let script = &subsystem_resolver.subsystem.scripts[job.script];
+
+    let field_args: IndexMap<String, Val> = match arguments {
+        Value::Object(arguments) => arguments
+            .iter()
+            .map(|(name, value)| (name.clone(), value.clone().into()))
+            .collect(),
+        Value::Null => IndexMap::new(),
+        _ => {
+            return Err(DenoExecutionError::Generic(format!(
+                "Arguments to job `{}` must be an object",
+                job.method_name
+            )))
+        }
+    };
+
+    let arg_sequence: Vec<Arg> = construct_arg_sequence(
+        &field_args,
+        &job.arguments,
+        &subsystem_resolver.subsystem,
+        request_context,
+    )
+    .await?;
+
+    let callback_processor = ExoCallbackProcessor {
+        exograph_execute_query,
+        exograph_execute_queries,
+        exograph_proceed: None,
+        system_resolver,
+    };
+
+    let deserialized: DenoScriptDefn = serde_json::from_slice(&script.script).unwrap();
+
+    subsystem_resolver
+        .executor
+        .execute_and_get_r(
+            &script.path,
+            deserialized,
+            &job.method_name,
+            arg_sequence,
+            None,
+            callback_processor,
+        )
+        .await
+        .map_err(DenoExecutionError::Deno)?;
+
+    Ok(())
+}
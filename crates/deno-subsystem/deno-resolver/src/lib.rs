@@ -15,5 +15,6 @@ mod deno_operation;
 mod exo_execution;
 mod exograph_ops;
 mod interceptor_execution;
+mod job_execution;
 mod module_access_predicate;
 mod plugin;
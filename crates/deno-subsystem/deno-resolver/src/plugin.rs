@@ -7,17 +7,23 @@
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
 
+use std::time::Duration;
+
 use async_graphql_parser::types::{FieldDefinition, OperationType, TypeDefinition};
 use async_trait::async_trait;
 
 use core_plugin_interface::{
     core_model::mapped_arena::SerializableSlabIndex,
     core_resolver::{
+        access_trace::AccessRule,
         context::RequestContext,
-        exograph_execute_query,
+        exograph_execute_queries, exograph_execute_query,
+        job::JobDefinition,
+        metrics::{MetricKind, MetricSample},
         plugin::{SubsystemResolutionError, SubsystemResolver},
         system_resolver::SystemResolver,
         validation::field::ValidatedField,
+        value::Val,
         InterceptedOperation, QueryResponse, QueryResponseBody,
     },
     interception::InterceptorIndex,
@@ -26,9 +32,14 @@ use core_plugin_interface::{
     trusted_documents::TrustedDocumentEnforcement,
 };
 
+use common::env_const::{
+    is_production, EXO_DENO_EXECUTION_TIMEOUT, EXO_DENO_MAX_ACTORS_PER_MODULE,
+    EXO_DENO_MAX_HEAP_SIZE, EXO_DENO_QUEUE_TIMEOUT, EXO_DENO_WARM_ACTORS_PER_MODULE,
+};
 use deno_model::{module::ModuleMethod, subsystem::DenoSubsystem};
-use exo_deno::DenoExecutorPool;
+use exo_deno::{deno_executor_pool::DenoPoolConfig, DenoExecutorPool, DenoPermissions};
 use exo_env::Environment;
+use indexmap::IndexMap;
 
 use super::{
     deno_execution_error::DenoExecutionError,
@@ -54,12 +65,37 @@ impl SubsystemLoader for DenoSubsystemLoader {
     async fn init(
         &mut self,
         serialized_subsystem: Vec<u8>,
-        _env: &dyn Environment,
+        env: &dyn Environment,
     ) -> Result<Box<dyn SubsystemResolver + Send + Sync>, SubsystemLoadingError> {
         deno_core::JsRuntime::init_platform(None);
         let subsystem = DenoSubsystem::deserialize(serialized_subsystem)?;
 
-        let executor = DenoExecutorPool::new_from_config(exo_config());
+        // Modules that don't declare their permissions (for example, `@deno("x.ts")`) are denied
+        // everything in production, but may do anything in development to keep iterating easy
+        let default_permissions = if is_production() {
+            DenoPermissions::default()
+        } else {
+            DenoPermissions::allow_all()
+        };
+
+        let executor = DenoExecutorPool::new_from_config(
+            exo_config()
+                .with_default_permissions(default_permissions)
+                .with_pool_config(pool_config(env)?),
+        );
+
+        // Keep a warm pool of actors that have loaded their module, so that the first requests
+        // don't pay for loading it (useful to reduce cold-start latency)
+        if let Some(warm_actors) = parse_positive(env, EXO_DENO_WARM_ACTORS_PER_MODULE)? {
+            for (_, script) in subsystem.scripts.iter() {
+                executor
+                    .warm_up(&script.path, warm_actors as usize, || {
+                        serde_json::from_slice(&script.script).unwrap()
+                    })
+                    .await
+                    .map_err(|e| SubsystemLoadingError::BoxedError(Box::new(e)))?;
+            }
+        }
 
         Ok(Box::new(DenoSubsystemResolver {
             id: self.id(),
@@ -69,6 +105,32 @@ impl SubsystemLoader for DenoSubsystemLoader {
     }
 }
 
+fn parse_positive(env: &dyn Environment, key: &str) -> Result<Option<u64>, SubsystemLoadingError> {
+    env.get(key)
+        .map(|value| match value.parse::<u64>() {
+            Ok(value) if value > 0 => Ok(value),
+            _ => Err(SubsystemLoadingError::Config(format!(
+                "Env {key} must be a positive integer (found '{value}')"
+            ))),
+        })
+        .transpose()
+}
+
+fn pool_config(env: &dyn Environment) -> Result<DenoPoolConfig, SubsystemLoadingError> {
+    let default_config = DenoPoolConfig::default();
+
+    Ok(DenoPoolConfig {
+        max_actors_per_module: parse_positive(env, EXO_DENO_MAX_ACTORS_PER_MODULE)?
+            .map(|max_actors| max_actors as usize)
+            .unwrap_or(default_config.max_actors_per_module),
+        queue_timeout: parse_positive(env, EXO_DENO_QUEUE_TIMEOUT)?.map(Duration::from_secs),
+        execution_timeout: parse_positive(env, EXO_DENO_EXECUTION_TIMEOUT)?
+            .map(Duration::from_secs),
+        max_heap_size: parse_positive(env, EXO_DENO_MAX_HEAP_SIZE)?
+            .map(|megabytes| megabytes as usize * 1024 * 1024),
+    })
+}
+
 pub struct DenoSubsystemResolver {
     pub id: &'static str,
     pub subsystem: DenoSubsystem,
@@ -101,7 +163,7 @@ impl SubsystemResolver for DenoSubsystemResolver {
                     create_deno_operation(
                         &self.subsystem,
                         &query.method_id,
-                        operation,
+                        &operation.arguments,
                         request_context,
                         self,
                         system_resolver,
@@ -114,7 +176,7 @@ impl SubsystemResolver for DenoSubsystemResolver {
                     create_deno_operation(
                         &self.subsystem,
                         &mutation.method_id,
-                        operation,
+                        &operation.arguments,
                         request_context,
                         self,
                         system_resolver,
@@ -144,12 +206,15 @@ impl SubsystemResolver for DenoSubsystemResolver {
             &self.subsystem.interceptors[SerializableSlabIndex::from_idx(interceptor_index.0)];
 
         let exograph_execute_query = exograph_execute_query!(system_resolver, request_context);
+        let exograph_execute_queries = exograph_execute_queries!(system_resolver, request_context);
         let (result, response) = super::interceptor_execution::execute_interceptor(
             interceptor,
             self,
             request_context,
             &exograph_execute_query,
+            &exograph_execute_queries,
             intercepted_operation,
+            system_resolver,
         )
         .await?;
 
@@ -164,6 +229,156 @@ impl SubsystemResolver for DenoSubsystemResolver {
         }))
     }
 
+    fn jobs(&self) -> Vec<JobDefinition> {
+        self.subsystem
+            .jobs
+            .iter()
+            .map(|(_, job)| JobDefinition {
+                name: job.method_name.clone(),
+                schedule: job.schedule.clone(),
+                enqueueable: true,
+            })
+            .collect()
+    }
+
+    fn access_rules(&self) -> Vec<AccessRule> {
+        let operation_rules = |method_id: Option<SerializableSlabIndex<ModuleMethod>>,
+                               operation: &str| {
+            method_id.map(|method_id| AccessRule {
+                rule: self.subsystem.methods[method_id].name.clone(),
+                operation: operation.to_string(),
+            })
+        };
+
+        self.subsystem
+            .queries
+            .iter()
+            .filter_map(|(_, query)| operation_rules(query.method_id, "query"))
+            .chain(
+                self.subsystem
+                    .mutations
+                    .iter()
+                    .filter_map(|(_, mutation)| operation_rules(mutation.method_id, "mutation")),
+            )
+            .collect()
+    }
+
+    async fn invoke_job<'a>(
+        &'a self,
+        name: &'a str,
+        arguments: &'a serde_json::Value,
+        request_context: &'a RequestContext<'a>,
+        system_resolver: &'a SystemResolver,
+    ) -> Result<Option<()>, SubsystemResolutionError> {
+        let Some((_, job)) = self
+            .subsystem
+            .jobs
+            .iter()
+            .find(|(_, job)| job.method_name == name)
+        else {
+            return Ok(None);
+        };
+
+        // Similar to a top-level operation, we can't be sure what the JS code will do
+        request_context.ensure_transaction().await;
+
+        let exograph_execute_query = exograph_execute_query!(system_resolver, request_context);
+        let exograph_execute_queries = exograph_execute_queries!(system_resolver, request_context);
+        super::job_execution::execute_job(
+            job,
+            arguments,
+            self,
+            request_context,
+            &exograph_execute_query,
+            &exograph_execute_queries,
+            system_resolver,
+        )
+        .await
+        // Job errors are never shown to API users, so keep the details to help with debugging
+        .map_err(|e| SubsystemResolutionError::UserDisplayError(e.to_string()))?;
+
+        Ok(Some(()))
+    }
+
+    async fn invoke_access_check<'a>(
+        &'a self,
+        function_name: &'a str,
+        arguments: &'a [Val],
+        request_context: &'a RequestContext<'a>,
+        system_resolver: &'a SystemResolver,
+    ) -> Result<Option<Val>, SubsystemResolutionError> {
+        // Only queries may decide access (mutations may have side effects)
+        let Some(query) = self.subsystem.queries.get_by_key(function_name) else {
+            return Ok(None);
+        };
+        let method = &self.subsystem.methods[query.method_id.unwrap()];
+
+        // The typechecker ensures that the access rule passes an argument for each non-injected
+        // parameter
+        let arguments: IndexMap<String, Val> = method
+            .arguments
+            .iter()
+            .filter(|argument| !argument.is_injected)
+            .zip(arguments)
+            .map(|(parameter, value)| (parameter.name.clone(), value.clone()))
+            .collect();
+
+        let operation = create_deno_operation(
+            &self.subsystem,
+            &query.method_id,
+            &arguments,
+            request_context,
+            self,
+            system_resolver,
+        )?;
+
+        Ok(Some(operation.execute_check().await?))
+    }
+
+    async fn metrics(&self) -> Vec<MetricSample> {
+        self.executor
+            .metrics()
+            .await
+            .into_iter()
+            .flat_map(|(module, pool_metrics)| {
+                let sample = |name, help, kind, value: f64| MetricSample {
+                    name,
+                    help,
+                    kind,
+                    labels: vec![("module", module.clone())],
+                    value,
+                };
+
+                [
+                    sample(
+                        "exograph_deno_actors",
+                        "Live Deno actors of a module",
+                        MetricKind::Gauge,
+                        pool_metrics.actors as f64,
+                    ),
+                    sample(
+                        "exograph_deno_busy_actors",
+                        "Deno actors of a module currently executing a call",
+                        MetricKind::Gauge,
+                        pool_metrics.busy_actors as f64,
+                    ),
+                    sample(
+                        "exograph_deno_waiting_calls",
+                        "Calls waiting for a Deno actor of a module",
+                        MetricKind::Gauge,
+                        pool_metrics.waiting_calls as f64,
+                    ),
+                    sample(
+                        "exograph_deno_timed_out_calls_total",
+                        "Calls to a module that exceeded the queue or execution timeout",
+                        MetricKind::Counter,
+                        pool_metrics.timed_out_calls as f64,
+                    ),
+                ]
+            })
+            .collect()
+    }
+
     fn schema_queries(&self) -> Vec<FieldDefinition> {
         self.subsystem.schema_queries()
     }
@@ -180,7 +395,7 @@ impl SubsystemResolver for DenoSubsystemResolver {
 pub(crate) fn create_deno_operation<'a>(
     system: &'a DenoSubsystem,
     method_id: &Option<SerializableSlabIndex<ModuleMethod>>,
-    field: &'a ValidatedField,
+    arguments: &'a IndexMap<String, Val>,
     request_context: &'a RequestContext<'a>,
     subsystem_resolver: &'a DenoSubsystemResolver,
     system_resolver: &'a SystemResolver,
@@ -190,7 +405,7 @@ pub(crate) fn create_deno_operation<'a>(
 
     Ok(DenoOperation {
         method,
-        field,
+        arguments,
         request_context,
         subsystem_resolver,
         system_resolver,
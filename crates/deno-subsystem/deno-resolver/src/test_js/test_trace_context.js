@@ -0,0 +1,21 @@
+// Copyright Exograph, Inc. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file at the root of this repository.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+// Return the headers of the request that `fetch` received (the test replaces `fetch` with one that
+// responds with them)
+
+export async function fetchHeaders(headers) {
+    const response = await fetch("https://example.com", { headers });
+    return await response.json();
+}
+
+export async function fetchRequestHeaders(headers) {
+    const response = await fetch(new Request("https://example.com", { headers }));
+    return await response.json();
+}
@@ -0,0 +1,37 @@
+// Copyright Exograph, Inc. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file at the root of this repository.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+// Propagate the trace context of the operation to the services called using `fetch` (unless the
+// module sets the headers itself), so that their spans join the operation's trace
+(function () {
+    const originalFetch = globalThis.fetch;
+    const { op_exograph_trace_context } = Deno[Deno.internal].core.ops;
+
+    globalThis.fetch = async function fetch(input, init) {
+        let traceContext;
+        try {
+            traceContext = await op_exograph_trace_context();
+        } catch (_e) {
+            // Not called from an operation (for example, while loading the module)
+        }
+
+        if (!traceContext) {
+            return originalFetch(input, init);
+        }
+
+        const request = new Request(input, init);
+        for (const [name, value] of Object.entries(traceContext)) {
+            if (!request.headers.has(name)) {
+                request.headers.set(name, value);
+            }
+        }
+
+        return originalFetch(request);
+    };
+})();
@@ -12,7 +12,7 @@ use std::collections::HashMap;
 use core_plugin_interface::{
     core_model::access::FunctionCall,
     core_model_builder::{
-        ast::ast_types::{AstExpr, FieldSelection, LogicalOp, RelationalOp},
+        ast::ast_types::{self, AstExpr, FieldSelection, LogicalOp, RelationalOp},
         error::ModelBuildingError,
         typechecker::Typed,
     },
@@ -20,8 +20,8 @@ use core_plugin_interface::{
 use core_plugin_interface::{
     core_model::{
         access::{
-            AccessLogicalExpression, AccessPredicateExpression, AccessRelationalOp,
-            CommonAccessPrimitiveExpression,
+            AccessLogicalExpression, AccessPredicateExpression, AccessRelationalOp, ArithmeticOp,
+            CheckCall, CommonAccessPrimitiveExpression,
         },
         context_type::{get_context, ContextFieldType, ContextSelection},
         mapped_arena::MappedArena,
@@ -52,6 +52,7 @@ enum DatabasePathSelection<'a> {
         PhysicalColumnPath,
         FunctionCall<DatabaseAccessPrimitiveExpression>,
     ), // Function, for example self.documentUser.some(du => du.id == AuthContext.id && du.read)
+    Count(PhysicalColumnPath), // Count of a relation, for example self.members.count()
     Context(ContextSelection, &'a ContextFieldType),
 }
 
@@ -62,6 +63,7 @@ enum JsonPathSelection<'a> {
         Option<String>, // Parameter name (such as "du", default: "self")
     ),
     Function(Vec<String>, FunctionCall<InputAccessPrimitiveExpression>), // Function, for example self.documentUser.some(du => du.id == AuthContext.id && du.read)
+    Count(Vec<String>), // Count of a relation, for example self.members.count()
     Context(ContextSelection, &'a ContextFieldType),
 }
 
@@ -131,12 +133,19 @@ pub fn compute_input_predicate_expression(
                     }
                 }
                 JsonPathSelection::Function(lead_path, function_call) => {
-                    compute_input_function_expr(
-                        lead_path,
-                        function_call.parameter_name,
-                        function_call.expr,
-                    )
+                    match function_call.name.as_str() {
+                        "some" => compute_input_function_expr(
+                            lead_path,
+                            function_call.parameter_name,
+                            function_call.expr,
+                        ),
+                        "every" | "none" => compute_input_quantifier_expr(lead_path, function_call),
+                        _ => Err(unsupported_function_error()),
+                    }
                 }
+                JsonPathSelection::Count(_) => Err(ModelBuildingError::Generic(
+                    "Top-level expression cannot be a count".to_string(),
+                )),
             })
         }
         AstExpr::LogicalOp(op) => {
@@ -173,6 +182,32 @@ pub fn compute_input_predicate_expression(
         AstExpr::StringList(_, _) => Err(ModelBuildingError::Generic(
             "Top-level expression cannot be a list literal".to_string(),
         )),
+        AstExpr::ArithmeticOp(_) => Err(ModelBuildingError::Generic(
+            "Top-level expression cannot be an arithmetic expression".to_string(),
+        )),
+        AstExpr::FunctionCall(call) => {
+            let primitive_expr = |expr: &AstExpr<Typed>| {
+                compute_primitive_json_expr(
+                    expr,
+                    scope.clone(),
+                    resolved_env,
+                    subsystem_primitive_types,
+                    subsystem_entity_types,
+                )
+            };
+
+            if call.name.0 == "check" {
+                let check_call = compute_check_call(call, resolved_env, primitive_expr)?;
+                Ok(check_predicate(
+                    InputAccessPrimitiveExpression::Check(check_call),
+                    InputAccessPrimitiveExpression::Common(
+                        CommonAccessPrimitiveExpression::BooleanLiteral(true),
+                    ),
+                ))
+            } else {
+                compute_function_predicate(call, primitive_expr)
+            }
+        }
     }
 }
 
@@ -226,18 +261,19 @@ pub fn compute_predicate_expression(
                     }
                 }
                 DatabasePathSelection::Function(column_path, function_call) => {
-                    if function_call.name != "some" {
-                        Err(ModelBuildingError::Generic(
-                            "Only `some` function is supported".to_string(),
-                        ))
-                    } else {
-                        compute_function_expr(
+                    match function_call.name.as_str() {
+                        "some" => compute_function_expr(
                             column_path,
                             function_call.parameter_name,
                             function_call.expr,
-                        )
+                        ),
+                        "every" | "none" => compute_quantifier_expr(column_path, function_call),
+                        _ => Err(unsupported_function_error()),
                     }
                 }
+                DatabasePathSelection::Count(_) => Err(ModelBuildingError::Generic(
+                    "Field selection must be a boolean".to_string(),
+                )),
                 DatabasePathSelection::Context(context_selection, field_type) => {
                     if field_type.innermost() == &PrimitiveType::Boolean {
                         // Treat boolean context expressions in the same way as an "eq" relational expression
@@ -290,6 +326,49 @@ pub fn compute_predicate_expression(
             };
             compute_relational_op(op, predicate_expr)
         }
+        AstExpr::FunctionCall(call) => {
+            let primitive_expr = |expr: &AstExpr<Typed>| {
+                compute_primitive_db_expr(
+                    expr,
+                    self_type_info,
+                    resolved_env,
+                    function_context.clone(),
+                    subsystem_primitive_types,
+                    subsystem_entity_types,
+                    database,
+                )
+            };
+
+            if call.name.0 == "check" {
+                let check_call = compute_check_call(call, resolved_env, primitive_expr)?;
+
+                // The database rows aren't available when we invoke the function, so the function
+                // must instead return a filter (such as `{id: {in: [1, 2]}}`)
+                if check_call.args.iter().any(uses_columns) {
+                    return Err(ModelBuildingError::Generic(
+                        "`check` cannot pass fields of `self` to the function in query, update, or delete rules (return a filter from the function instead)".to_string(),
+                    ));
+                }
+
+                let entity_id = subsystem_entity_types
+                    .get_id(&self_type_info.name)
+                    .ok_or_else(|| {
+                        ModelBuildingError::Generic(format!(
+                            "Type {} not found",
+                            self_type_info.name
+                        ))
+                    })?;
+
+                Ok(check_predicate(
+                    DatabaseAccessPrimitiveExpression::Check(check_call, entity_id),
+                    DatabaseAccessPrimitiveExpression::Common(
+                        CommonAccessPrimitiveExpression::BooleanLiteral(true),
+                    ),
+                ))
+            } else {
+                compute_function_predicate(call, primitive_expr)
+            }
+        }
         AstExpr::BooleanLiteral(value, _) => Ok(AccessPredicateExpression::BooleanLiteral(*value)),
 
         _ => Err(ModelBuildingError::Generic(
@@ -324,9 +403,20 @@ fn compute_function_expr(
                     parameter_name,
                 ))
             }
-            DatabaseAccessPrimitiveExpression::Function(_, _) => Err(ModelBuildingError::Generic(
-                "Cannot have a function call inside another function call".to_string(),
-            )),
+            DatabaseAccessPrimitiveExpression::Arithmetic(left, op, right) => {
+                Ok(DatabaseAccessPrimitiveExpression::Arithmetic(
+                    Box::new(function_elem_path(
+                        lead_path.clone(),
+                        function_param_name.clone(),
+                        *left,
+                    )?),
+                    op,
+                    Box::new(function_elem_path(lead_path, function_param_name, *right)?),
+                ))
+            }
+            DatabaseAccessPrimitiveExpression::Function(_, _)
+            | DatabaseAccessPrimitiveExpression::Count(_, _)
+            | DatabaseAccessPrimitiveExpression::Check(_, _) => Err(nested_function_error()),
             expr => Ok(expr),
         }
     }
@@ -398,9 +488,20 @@ fn compute_input_function_expr(
                     parameter_name,
                 ))
             }
-            InputAccessPrimitiveExpression::Function(_, _) => Err(ModelBuildingError::Generic(
-                "Cannot have a function call inside another function call".to_string(),
-            )),
+            InputAccessPrimitiveExpression::Arithmetic(left, op, right) => {
+                Ok(InputAccessPrimitiveExpression::Arithmetic(
+                    Box::new(function_elem_path(
+                        lead_path.clone(),
+                        function_param_name.clone(),
+                        *left,
+                    )?),
+                    op,
+                    Box::new(function_elem_path(lead_path, function_param_name, *right)?),
+                ))
+            }
+            InputAccessPrimitiveExpression::Function(_, _)
+            | InputAccessPrimitiveExpression::Count(_, _)
+            | InputAccessPrimitiveExpression::Check(_) => Err(nested_function_error()),
             expr => Ok(expr),
         }
     }
@@ -477,16 +578,17 @@ fn compute_primitive_db_expr(
             subsystem_entity_types,
             database,
         )
-        .map(|selection| match selection {
-            DatabasePathSelection::Column(column_path, _, parameter_name) => {
-                DatabaseAccessPrimitiveExpression::Column(column_path, parameter_name)
-            }
-            DatabasePathSelection::Function(column_path, function_call) => {
-                DatabaseAccessPrimitiveExpression::Function(column_path, function_call)
-            }
-            DatabasePathSelection::Context(c, _) => DatabaseAccessPrimitiveExpression::Common(
-                CommonAccessPrimitiveExpression::ContextSelection(c),
+        .and_then(|selection| match selection {
+            DatabasePathSelection::Column(column_path, _, parameter_name) => Ok(
+                DatabaseAccessPrimitiveExpression::Column(column_path, parameter_name),
+            ),
+            DatabasePathSelection::Function(column_path, function_call) => Ok(
+                DatabaseAccessPrimitiveExpression::Function(column_path, function_call),
             ),
+            DatabasePathSelection::Count(column_path) => compute_count_expr(column_path, None),
+            DatabasePathSelection::Context(c, _) => Ok(DatabaseAccessPrimitiveExpression::Common(
+                CommonAccessPrimitiveExpression::ContextSelection(c),
+            )),
         }),
         AstExpr::StringLiteral(value, _) => Ok(DatabaseAccessPrimitiveExpression::Common(
             CommonAccessPrimitiveExpression::StringLiteral(value.clone()),
@@ -500,6 +602,29 @@ fn compute_primitive_db_expr(
         AstExpr::StringList(_, _) => Err(ModelBuildingError::Generic(
             "Access expressions do not support lists yet".to_string(),
         )),
+        AstExpr::FunctionCall(call) => {
+            compute_primitive_function(call).map(DatabaseAccessPrimitiveExpression::Common)
+        }
+        AstExpr::ArithmeticOp(op) => {
+            let (left, right) = op.sides();
+            let primitive_expr = |expr: &AstExpr<Typed>| {
+                compute_primitive_db_expr(
+                    expr,
+                    self_type_info,
+                    resolved_env,
+                    function_context.clone(),
+                    subsystem_primitive_types,
+                    subsystem_entity_types,
+                    database,
+                )
+            };
+
+            Ok(DatabaseAccessPrimitiveExpression::Arithmetic(
+                Box::new(primitive_expr(left)?),
+                arithmetic_op(op),
+                Box::new(primitive_expr(right)?),
+            ))
+        }
         AstExpr::LogicalOp(_) => unreachable!(), // Parser ensures that the two sides are primitive expressions
         AstExpr::RelationalOp(_) => unreachable!(), // Parser ensures that the two sides are primitive expressions
     }
@@ -530,6 +655,7 @@ fn compute_primitive_json_expr(
             JsonPathSelection::Function(path, function_call) => {
                 InputAccessPrimitiveExpression::Function(path, function_call)
             }
+            JsonPathSelection::Count(path) => InputAccessPrimitiveExpression::Count(path, None),
         }),
         AstExpr::StringLiteral(value, _) => Ok(InputAccessPrimitiveExpression::Common(
             CommonAccessPrimitiveExpression::StringLiteral(value.clone()),
@@ -543,6 +669,27 @@ fn compute_primitive_json_expr(
         AstExpr::StringList(_, _) => Err(ModelBuildingError::Generic(
             "Access expressions do not support lists yet".to_string(),
         )),
+        AstExpr::FunctionCall(call) => {
+            compute_primitive_function(call).map(InputAccessPrimitiveExpression::Common)
+        }
+        AstExpr::ArithmeticOp(op) => {
+            let (left, right) = op.sides();
+            let primitive_expr = |expr: &AstExpr<Typed>| {
+                compute_primitive_json_expr(
+                    expr,
+                    scope.clone(),
+                    resolved_env,
+                    subsystem_primitive_types,
+                    subsystem_entity_types,
+                )
+            };
+
+            Ok(InputAccessPrimitiveExpression::Arithmetic(
+                Box::new(primitive_expr(left)?),
+                arithmetic_op(op),
+                Box::new(primitive_expr(right)?),
+            ))
+        }
         AstExpr::LogicalOp(_) => unreachable!(), // Parser has already ensures that the two sides are primitive expressions
         AstExpr::RelationalOp(_) => unreachable!(), // Parser has already ensures that the two sides are primitive expressions
     }
@@ -595,6 +742,222 @@ fn compute_relational_op<PrimExpr: Send + Sync>(
     )))
 }
 
+/// Compute `every` and `none` by counting elements: `self.members.none(m => m.banned)` holds if
+/// no member is banned and `self.members.every(m => m.active)` holds if no member fails to be
+/// active. The resolver counts the elements for which the filter of `every` isn't true (`NOT
+/// COALESCE(filter, false)`), so that an element for which the filter is unknown (such as a member
+/// with a null `active`) doesn't satisfy `every`.
+fn compute_quantifier_expr(
+    lead_path: PhysicalColumnPath,
+    function_call: FunctionCall<DatabaseAccessPrimitiveExpression>,
+) -> Result<AccessPredicateExpression<DatabaseAccessPrimitiveExpression>, ModelBuildingError> {
+    check_no_nested_function(&function_call.expr, &is_function)?;
+
+    Ok(AccessPredicateExpression::RelationalOp(
+        AccessRelationalOp::Eq(
+            Box::new(compute_count_expr(lead_path, Some(function_call))?),
+            Box::new(DatabaseAccessPrimitiveExpression::Common(
+                CommonAccessPrimitiveExpression::NumberLiteral(0),
+            )),
+        ),
+    ))
+}
+
+fn compute_input_quantifier_expr(
+    lead_path: Vec<String>,
+    function_call: FunctionCall<InputAccessPrimitiveExpression>,
+) -> Result<AccessPredicateExpression<InputAccessPrimitiveExpression>, ModelBuildingError> {
+    check_no_nested_function(&function_call.expr, &is_input_function)?;
+
+    Ok(AccessPredicateExpression::RelationalOp(
+        AccessRelationalOp::Eq(
+            Box::new(InputAccessPrimitiveExpression::Count(
+                lead_path,
+                Some(function_call),
+            )),
+            Box::new(InputAccessPrimitiveExpression::Common(
+                CommonAccessPrimitiveExpression::NumberLiteral(0),
+            )),
+        ),
+    ))
+}
+
+/// We count rows through a subquery on the related table, so the path must be a single
+/// one-to-many relation such as `self.members`.
+fn compute_count_expr(
+    lead_path: PhysicalColumnPath,
+    filter: Option<FunctionCall<DatabaseAccessPrimitiveExpression>>,
+) -> Result<DatabaseAccessPrimitiveExpression, ModelBuildingError> {
+    match lead_path.split_head() {
+        (ColumnPathLink::Relation(link), None) => {
+            Ok(DatabaseAccessPrimitiveExpression::Count(link, filter))
+        }
+        _ => Err(ModelBuildingError::Generic(
+            "`count`, `every`, and `none` are supported only on relations of `self` (such as `self.members`)"
+                .to_string(),
+        )),
+    }
+}
+
+fn is_function(expr: &DatabaseAccessPrimitiveExpression) -> bool {
+    match expr {
+        DatabaseAccessPrimitiveExpression::Function(..)
+        | DatabaseAccessPrimitiveExpression::Count(..)
+        | DatabaseAccessPrimitiveExpression::Check(..) => true,
+        DatabaseAccessPrimitiveExpression::Arithmetic(left, _, right) => {
+            is_function(left) || is_function(right)
+        }
+        DatabaseAccessPrimitiveExpression::Column(..)
+        | DatabaseAccessPrimitiveExpression::Common(_) => false,
+    }
+}
+
+fn is_input_function(expr: &InputAccessPrimitiveExpression) -> bool {
+    match expr {
+        InputAccessPrimitiveExpression::Function(..)
+        | InputAccessPrimitiveExpression::Count(..)
+        | InputAccessPrimitiveExpression::Check(..) => true,
+        InputAccessPrimitiveExpression::Arithmetic(left, _, right) => {
+            is_input_function(left) || is_input_function(right)
+        }
+        InputAccessPrimitiveExpression::Path(..) | InputAccessPrimitiveExpression::Common(_) => {
+            false
+        }
+    }
+}
+
+fn check_no_nested_function<PrimExpr: Send + Sync>(
+    expr: &AccessPredicateExpression<PrimExpr>,
+    is_call: &impl Fn(&PrimExpr) -> bool,
+) -> Result<(), ModelBuildingError> {
+    match expr {
+        AccessPredicateExpression::LogicalOp(op) => match op {
+            AccessLogicalExpression::Not(p) => check_no_nested_function(p, is_call),
+            AccessLogicalExpression::And(left, right)
+            | AccessLogicalExpression::Or(left, right) => {
+                check_no_nested_function(left, is_call)?;
+                check_no_nested_function(right, is_call)
+            }
+        },
+        AccessPredicateExpression::RelationalOp(op) => {
+            let (left, right) = op.sides();
+            if is_call(left) || is_call(right) {
+                Err(nested_function_error())
+            } else {
+                Ok(())
+            }
+        }
+        AccessPredicateExpression::BooleanLiteral(_) => Ok(()),
+    }
+}
+
+/// Function calls that evaluate to a boolean such as `contains(self.tags, "featured")`
+fn compute_function_predicate<PrimExpr: Send + Sync>(
+    call: &ast_types::FunctionCall<Typed>,
+    primitive_expr: impl Fn(&AstExpr<Typed>) -> Result<PrimExpr, ModelBuildingError>,
+) -> Result<AccessPredicateExpression<PrimExpr>, ModelBuildingError> {
+    match (call.name.0.as_str(), call.args.as_slice()) {
+        ("contains", [haystack, needle]) => Ok(AccessPredicateExpression::RelationalOp(
+            AccessRelationalOp::Contains(
+                Box::new(primitive_expr(haystack)?),
+                Box::new(primitive_expr(needle)?),
+            ),
+        )),
+        (name, _) => Err(ModelBuildingError::Generic(format!(
+            "Function {name} cannot be used as a predicate in access expressions"
+        ))),
+    }
+}
+
+/// Compute the call for `check("canRead", AuthContext.id, ...)`
+fn compute_check_call<PrimExpr: Send + Sync>(
+    call: &ast_types::FunctionCall<Typed>,
+    resolved_env: &ResolvedTypeEnv,
+    primitive_expr: impl Fn(&AstExpr<Typed>) -> Result<PrimExpr, ModelBuildingError>,
+) -> Result<CheckCall<PrimExpr>, ModelBuildingError> {
+    match call.args.as_slice() {
+        [AstExpr::StringLiteral(function_name, _), args @ ..] => Ok(CheckCall {
+            function_name: function_name.clone(),
+            args: args
+                .iter()
+                // Contexts are supplied to the function through its injected parameters
+                .filter(|arg| !is_context_reference(arg, resolved_env))
+                .map(primitive_expr)
+                .collect::<Result<_, _>>()?,
+        }),
+        _ => Err(ModelBuildingError::Generic(
+            "`check` requires the name of a module function as its first argument".to_string(),
+        )),
+    }
+}
+
+/// Treat `check(...)` the same way as `check(...) == true`
+fn check_predicate<PrimExpr: Send + Sync>(
+    check: PrimExpr,
+    true_expr: PrimExpr,
+) -> AccessPredicateExpression<PrimExpr> {
+    AccessPredicateExpression::RelationalOp(AccessRelationalOp::Eq(
+        Box::new(check),
+        Box::new(true_expr),
+    ))
+}
+
+/// Is the expression a reference to an entire context (such as `AuthContext`)?
+fn is_context_reference(expr: &AstExpr<Typed>, resolved_env: &ResolvedTypeEnv) -> bool {
+    match expr {
+        AstExpr::FieldSelection(selection) => match selection.context_path().as_slice() {
+            [name] => resolved_env.contexts.get_by_key(name).is_some(),
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+fn uses_columns(expr: &DatabaseAccessPrimitiveExpression) -> bool {
+    match expr {
+        DatabaseAccessPrimitiveExpression::Column(..)
+        | DatabaseAccessPrimitiveExpression::Function(..)
+        | DatabaseAccessPrimitiveExpression::Count(..) => true,
+        DatabaseAccessPrimitiveExpression::Arithmetic(left, _, right) => {
+            uses_columns(left) || uses_columns(right)
+        }
+        DatabaseAccessPrimitiveExpression::Check(call, _) => call.args.iter().any(uses_columns),
+        DatabaseAccessPrimitiveExpression::Common(_) => false,
+    }
+}
+
+/// Function calls that evaluate to a value such as `now()`
+fn compute_primitive_function(
+    call: &ast_types::FunctionCall<Typed>,
+) -> Result<CommonAccessPrimitiveExpression, ModelBuildingError> {
+    match call.name.0.as_str() {
+        "now" => Ok(CommonAccessPrimitiveExpression::Now),
+        name => Err(ModelBuildingError::Generic(format!(
+            "Function {name} is not supported in access expressions"
+        ))),
+    }
+}
+
+fn arithmetic_op(op: &ast_types::ArithmeticOp<Typed>) -> ArithmeticOp {
+    match op {
+        ast_types::ArithmeticOp::Add(..) => ArithmeticOp::Add,
+        ast_types::ArithmeticOp::Sub(..) => ArithmeticOp::Sub,
+        ast_types::ArithmeticOp::Mul(..) => ArithmeticOp::Mul,
+    }
+}
+
+fn unsupported_function_error() -> ModelBuildingError {
+    ModelBuildingError::Generic(
+        "Only `some`, `every`, and `none` functions are supported".to_string(),
+    )
+}
+
+fn nested_function_error() -> ModelBuildingError {
+    ModelBuildingError::Generic(
+        "Cannot have a function call inside another function call".to_string(),
+    )
+}
+
 fn compute_column_selection<'a>(
     selection: &FieldSelection<Typed>,
     self_type_info: &'a EntityType,
@@ -661,7 +1024,8 @@ fn compute_column_selection<'a>(
                             Some(field_type),
                         )
                     }
-                    FieldSelectionElement::HofCall { .. } => unreachable!(),
+                    FieldSelectionElement::HofCall { .. }
+                    | FieldSelectionElement::MethodCall { .. } => unreachable!(),
                 }
             },
         )
@@ -723,6 +1087,17 @@ fn compute_column_selection<'a>(
                             },
                         ))
                     }
+                    FieldSelectionElement::MethodCall { .. } => {
+                        // The typechecker ensures that this is `count()` on a set (but the set
+                        // may be missing, as in `self.count()`)
+                        let (_, column_path, _) = compute_column_path(lead_type, tail_init);
+                        let column_path = column_path.ok_or_else(|| {
+                            ModelBuildingError::Generic(format!(
+                                "`count()` must be called on a relation (such as `{value}.members.count()`)"
+                            ))
+                        })?;
+                        Ok(DatabasePathSelection::Count(column_path))
+                    }
                 }
             } else {
                 let path_elements = selection.context_path();
@@ -734,9 +1109,11 @@ fn compute_column_selection<'a>(
                 ))
             }
         }
-        FieldSelectionElement::HofCall { .. } => Err(ModelBuildingError::Generic(
-            "Function selection at the top level is not supported".to_string(),
-        )),
+        FieldSelectionElement::HofCall { .. } | FieldSelectionElement::MethodCall { .. } => {
+            Err(ModelBuildingError::Generic(
+                "Function selection at the top level is not supported".to_string(),
+            ))
+        }
     }
 }
 
@@ -792,7 +1169,8 @@ fn compute_json_selection<'a>(
 
                         (field_composite_type, json_path, Some(field_type))
                     }
-                    FieldSelectionElement::HofCall { .. } => unreachable!(),
+                    FieldSelectionElement::HofCall { .. }
+                    | FieldSelectionElement::MethodCall { .. } => unreachable!(),
                 }
             },
         )
@@ -854,6 +1232,10 @@ fn compute_json_selection<'a>(
                                 },
                             ))
                         }
+                        FieldSelectionElement::MethodCall { .. } => {
+                            let (_, path, _) = compute_json_path(scope_type, tail_init);
+                            Ok(JsonPathSelection::Count(path))
+                        }
                     }
                 }
                 None => {
@@ -867,9 +1249,11 @@ fn compute_json_selection<'a>(
                 }
             }
         }
-        FieldSelectionElement::HofCall { .. } => Err(ModelBuildingError::Generic(
-            "Function selection at the top level is not supported".to_string(),
-        )),
+        FieldSelectionElement::HofCall { .. } | FieldSelectionElement::MethodCall { .. } => {
+            Err(ModelBuildingError::Generic(
+                "Function selection at the top level is not supported".to_string(),
+            ))
+        }
     }
 }
 
@@ -882,6 +1266,16 @@ enum NestedPredicatePart<T> {
     Common(T),
 }
 
+impl<T> NestedPredicatePart<T> {
+    fn into_inner(self) -> T {
+        match self {
+            NestedPredicatePart::Parent(t)
+            | NestedPredicatePart::Nested(t)
+            | NestedPredicatePart::Common(t) => t,
+        }
+    }
+}
+
 /// Compute the predicate that should be applied to the parent entity
 ///
 /// This works in conjunction with `TransactionStep::Filter` step to narrows down parent elements
@@ -1087,6 +1481,29 @@ fn reduce_nested_primitive_expr(
                 _ => NestedPredicatePart::Nested(expr),
             }
         }
+        DatabaseAccessPrimitiveExpression::Arithmetic(left, op, right) => {
+            let reduced_left = reduce_nested_primitive_expr(*left, parent_entity);
+            let reduced_right = reduce_nested_primitive_expr(*right, parent_entity);
+
+            let arithmetic =
+                |l, r| DatabaseAccessPrimitiveExpression::Arithmetic(Box::new(l), op, Box::new(r));
+
+            match (reduced_left, reduced_right) {
+                (NestedPredicatePart::Common(l), NestedPredicatePart::Common(r)) => {
+                    NestedPredicatePart::Common(arithmetic(l, r))
+                }
+                (NestedPredicatePart::Parent(l), NestedPredicatePart::Parent(r))
+                | (NestedPredicatePart::Parent(l), NestedPredicatePart::Common(r))
+                | (NestedPredicatePart::Common(l), NestedPredicatePart::Parent(r)) => {
+                    NestedPredicatePart::Parent(arithmetic(l, r))
+                }
+                (l, r) => NestedPredicatePart::Nested(arithmetic(l.into_inner(), r.into_inner())),
+            }
+        }
+        // Counting rows related to the nested entity cannot be expressed for the parent
+        DatabaseAccessPrimitiveExpression::Count(_, _) => NestedPredicatePart::Nested(expr),
+        // The function may return a filter for the nested entity
+        DatabaseAccessPrimitiveExpression::Check(_, _) => NestedPredicatePart::Nested(expr),
         DatabaseAccessPrimitiveExpression::Common(_) => NestedPredicatePart::Common(expr),
     }
 }
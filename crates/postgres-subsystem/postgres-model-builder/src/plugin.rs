@@ -38,7 +38,10 @@ impl SubsystemBuilder for PostgresSubsystemBuilder {
                     targets: &[AnnotationTarget::Module],
                     no_params: true,
                     single_params: false,
-                    mapped_params: None,
+                    mapped_params: Some(&[MappedAnnotationParamSpec {
+                        name: "url",
+                        optional: true,
+                    }]),
                 },
             ),
             (
@@ -221,6 +224,28 @@ impl SubsystemBuilder for PostgresSubsystemBuilder {
                     mapped_params: None,
                 },
             ),
+            (
+                "webhook",
+                AnnotationSpec {
+                    targets: &[AnnotationTarget::Type],
+                    no_params: false,
+                    single_params: false,
+                    mapped_params: Some(&[
+                        MappedAnnotationParamSpec {
+                            name: "url",
+                            optional: false,
+                        },
+                        MappedAnnotationParamSpec {
+                            name: "on",
+                            optional: true,
+                        },
+                        MappedAnnotationParamSpec {
+                            name: "secret",
+                            optional: true,
+                        },
+                    ]),
+                },
+            ),
         ]
     }
 
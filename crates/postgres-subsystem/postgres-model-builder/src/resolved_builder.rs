@@ -13,7 +13,7 @@
 //! column name, here that information is encoded into an attribute of `ResolvedType`.
 //! If no @column is provided, the encoded information is set to an appropriate default value.
 
-use std::collections::HashMap;
+use std::{collections::HashMap, time::Duration};
 
 use codemap::Span;
 use codemap_diagnostic::{Diagnostic, Level, SpanLabel, SpanStyle};
@@ -22,6 +22,7 @@ use core_plugin_interface::{
     core_model::{
         mapped_arena::MappedArena,
         primitive_type::PrimitiveType,
+        transaction::{parse_duration, TransactionOptions},
         types::{FieldType, Named},
     },
     core_model_builder::{
@@ -29,7 +30,7 @@ use core_plugin_interface::{
             default_span, AstAnnotationParams, AstExpr, AstField, AstFieldDefault,
             AstFieldDefaultKind, AstFieldType, AstModel, AstModelKind,
         },
-        builder::resolved_builder::AnnotationMapHelper,
+        builder::resolved_builder::{build_transaction_options, AnnotationMapHelper},
         error::ModelBuildingError,
         typechecker::{
             typ::{Module, Type, TypecheckedSystem},
@@ -38,6 +39,7 @@ use core_plugin_interface::{
     },
 };
 use exo_sql::{PhysicalTableName, VectorDistanceFunction};
+use postgres_model::webhook::{Webhook, WebhookEvent};
 
 use super::{
     access_builder::{build_access, ResolvedAccess},
@@ -91,6 +93,14 @@ pub struct ResolvedCompositeType {
     pub fields: Vec<ResolvedField>,
     pub table_name: PhysicalTableName,
     pub access: ResolvedAccess,
+    pub webhook: Option<Webhook>,
+    /// The env var with the URL of the database the type is stored in (specified through
+    /// `@postgres(url=env("..."))` on the module), or `None` for the default database
+    pub database_url_env: Option<String>,
+    /// Options for the transaction the type's mutations run in (from `@transaction`)
+    pub transaction: TransactionOptions,
+    /// How long to cache the results of queries on the type (from `@cache`)
+    pub cache_ttl: Option<Duration>,
     #[serde(skip_serializing)]
     #[serde(skip_deserializing)]
     #[serde(default = "default_span")]
@@ -276,7 +286,9 @@ fn resolve(
 
     for (_, Module(module)) in typechecked_system.modules.iter() {
         // Process each persistent type to create a PostgresType
-        if module.annotations.get("postgres").is_some() {
+        if let Some(postgres_annotation) = module.annotations.get("postgres") {
+            let database_url_env = build_database_url_env(postgres_annotation, errors);
+
             for typ in module.types.iter() {
                 if let Some(Type::Composite(ct)) = typechecked_system.types.get_by_key(&typ.name) {
                     if ct.kind == AstModelKind::Type {
@@ -295,6 +307,27 @@ fn resolve(
                         );
 
                         let access = build_access(ct.annotations.get("access"));
+                        let webhook = build_webhook(ct.annotations.get("webhook"), errors);
+                        let transaction =
+                            build_transaction_options(ct.annotations.get("transaction"), errors);
+                        let cache_ttl = build_cache_ttl(ct.annotations.get("cache"), errors);
+                        // Webhook events are queued in the jobs table (in the default database)
+                        // by database triggers, so the table must be in the same database
+                        if webhook.is_some() && database_url_env.is_some() {
+                            errors.push(Diagnostic {
+                                level: Level::Error,
+                                message: format!(
+                                    "Type `{}` has a webhook, but webhooks are supported only for types in the default database",
+                                    ct.name
+                                ),
+                                code: Some("C000".to_string()),
+                                spans: vec![SpanLabel {
+                                    span: ct.span,
+                                    style: SpanStyle::Primary,
+                                    label: None,
+                                }],
+                            });
+                        }
                         let name = ct.name.clone();
                         let plural_name =
                             plural_annotation_value.unwrap_or_else(|| ct.name.to_plural()); // fallback to automatically pluralizing name
@@ -361,6 +394,10 @@ fn resolve(
                                     schema: schema_name,
                                 },
                                 access: access.clone(),
+                                webhook,
+                                database_url_env: database_url_env.clone(),
+                                transaction,
+                                cache_ttl,
                                 span: ct.span,
                             }),
                         );
@@ -370,9 +407,217 @@ fn resolve(
         }
     }
 
+    check_cross_database_relations(&resolved_postgres_types, errors);
+
     Ok(resolved_postgres_types)
 }
 
+/// Get the database URL's env var from `@postgres(url=env("..."))` (`None` for a plain `@postgres`)
+fn build_database_url_env(
+    annotation_params: &AstAnnotationParams<Typed>,
+    errors: &mut Vec<Diagnostic>,
+) -> Option<String> {
+    match annotation_params {
+        AstAnnotationParams::Map(params, _) => env_param("postgres", params, "url", errors),
+        _ => None,
+    }
+}
+
+/// Report relations between types stored in different databases (since we can't join or
+/// otherwise query across databases)
+fn check_cross_database_relations(
+    resolved_postgres_types: &MappedArena<ResolvedType>,
+    errors: &mut Vec<Diagnostic>,
+) {
+    let database_name = |url_env: &Option<String>| match url_env {
+        Some(url_env) => format!("the database at `{url_env}`"),
+        None => "the default database".to_string(),
+    };
+
+    for (_, typ) in resolved_postgres_types.iter() {
+        let ResolvedType::Composite(composite) = typ else {
+            continue;
+        };
+
+        for field in composite.fields.iter() {
+            let Some(ResolvedType::Composite(field_type)) =
+                field.typ.deref_subsystem_type(resolved_postgres_types)
+            else {
+                continue;
+            };
+
+            if field_type.database_url_env != composite.database_url_env {
+                errors.push(Diagnostic {
+                    level: Level::Error,
+                    message: format!(
+                        "Field `{}` of `{}` refers to `{}`, but `{}` is stored in {} and `{}` in {}. Relations across databases are not supported",
+                        field.name,
+                        composite.name,
+                        field_type.name,
+                        composite.name,
+                        database_name(&composite.database_url_env),
+                        field_type.name,
+                        database_name(&field_type.database_url_env),
+                    ),
+                    code: Some("C000".to_string()),
+                    spans: vec![SpanLabel {
+                        span: field.span,
+                        style: SpanStyle::Primary,
+                        label: None,
+                    }],
+                });
+            }
+        }
+    }
+}
+
+/// Get the name of the env var from a parameter of the form `env("NAME")`. Since URLs and secrets
+/// differ between deployments (and secrets shouldn't be checked in), we require them to come from
+/// the environment.
+fn env_param(
+    annotation_name: &str,
+    params: &HashMap<String, AstExpr<Typed>>,
+    param_name: &str,
+    errors: &mut Vec<Diagnostic>,
+) -> Option<String> {
+    match params.get(param_name) {
+        Some(AstExpr::FunctionCall(call)) if call.name.0 == "env" => match call.args.as_slice() {
+            [AstExpr::StringLiteral(name, _)] => Some(name.clone()),
+            _ => None, // The typechecker ensures that `env` takes a single string literal
+        },
+        Some(expr) => {
+            errors.push(Diagnostic {
+                level: Level::Error,
+                message: format!(
+                    "The `{param_name}` parameter of `@{annotation_name}` must be of the form env(\"NAME\")"
+                ),
+                code: Some("C000".to_string()),
+                spans: vec![SpanLabel {
+                    span: expr.span(),
+                    style: SpanStyle::Primary,
+                    label: None,
+                }],
+            });
+            None
+        }
+        None => None,
+    }
+}
+
+/// Build the time to cache query results from `@cache(ttl="...")`
+fn build_cache_ttl(
+    annotation_params: Option<&AstAnnotationParams<Typed>>,
+    errors: &mut Vec<Diagnostic>,
+) -> Option<Duration> {
+    let params = match annotation_params? {
+        AstAnnotationParams::Map(params, _) => params,
+        _ => return None, // The typechecker ensures that the parameters are named
+    };
+
+    let (message, span) = match params.get("ttl")? {
+        AstExpr::StringLiteral(value, span) => match parse_duration(value) {
+            Ok(ttl) if ttl.is_zero() => {
+                ("The `ttl` of `@cache` must be positive".to_string(), *span)
+            }
+            Ok(ttl) => return Some(ttl),
+            Err(message) => (message, *span),
+        },
+        expr => (
+            "The `ttl` parameter of `@cache` must be a string".to_string(),
+            expr.span(),
+        ),
+    };
+
+    errors.push(Diagnostic {
+        level: Level::Error,
+        message,
+        code: Some("C000".to_string()),
+        spans: vec![SpanLabel {
+            span,
+            style: SpanStyle::Primary,
+            label: None,
+        }],
+    });
+    None
+}
+
+/// Build a webhook from `@webhook(url=env("..."), on=[create, update, delete], secret=env("..."))`.
+///
+/// If `on` is not specified, the webhook receives all events.
+fn build_webhook(
+    annotation_params: Option<&AstAnnotationParams<Typed>>,
+    errors: &mut Vec<Diagnostic>,
+) -> Option<Webhook> {
+    let params = match annotation_params? {
+        AstAnnotationParams::Map(params, _) => params,
+        _ => return None, // The typechecker ensures that the parameters are named
+    };
+
+    let url_env = env_param("webhook", params, "url", errors);
+    let secret_env = env_param("webhook", params, "secret", errors);
+
+    let mut report = |message: String, span: Span| {
+        errors.push(Diagnostic {
+            level: Level::Error,
+            message,
+            code: Some("C000".to_string()),
+            spans: vec![SpanLabel {
+                span,
+                style: SpanStyle::Primary,
+                label: None,
+            }],
+        });
+    };
+
+    let events = match params.get("on") {
+        Some(expr @ AstExpr::StringList(names, _)) if names.is_empty() => {
+            report(
+                "The `on` parameter of `@webhook` must list at least one event".to_string(),
+                expr.span(),
+            );
+            vec![]
+        }
+        Some(AstExpr::StringList(names, spans)) => names
+            .iter()
+            .zip(spans)
+            .filter_map(|(name, span)| {
+                let event = WebhookEvent::from_name(name);
+                if event.is_none() {
+                    report(
+                        format!("Unknown webhook event `{name}`. Must be one of `create`, `update`, or `delete`"),
+                        *span,
+                    );
+                }
+                event
+            })
+            .collect(),
+        Some(AstExpr::StringLiteral(name, span)) => match WebhookEvent::from_name(name) {
+            Some(event) => vec![event],
+            None => {
+                report(
+                    format!("Unknown webhook event `{name}`. Must be one of `create`, `update`, or `delete`"),
+                    *span,
+                );
+                vec![]
+            }
+        },
+        Some(expr) => {
+            report(
+                "The `on` parameter of `@webhook` must be a list of events".to_string(),
+                expr.span(),
+            );
+            vec![]
+        }
+        None => WebhookEvent::ALL.to_vec(),
+    };
+
+    Some(Webhook {
+        url_env: url_env?,
+        secret_env,
+        events,
+    })
+}
+
 fn resolve_field_default_type(
     default_value: &AstFieldDefault<Typed>,
     field_type: &FieldType<ResolvedFieldType>,
@@ -796,7 +1041,9 @@ fn compute_column_info(
                     _ => panic!("Not a string nor a string list when specifying unique"),
                 },
                 AstAnnotationParams::None => vec![field.name.clone()],
-                AstAnnotationParams::Map(_, _) => panic!(),
+                AstAnnotationParams::Map(_, _) | AstAnnotationParams::Mixed(_, _, _, _) => {
+                    panic!()
+                }
             })
             .unwrap_or_default();
 
@@ -812,7 +1059,9 @@ fn compute_column_info(
                     _ => panic!("Not a string nor a string list when specifying index"),
                 },
                 AstAnnotationParams::None => vec![index_computed_name.clone()],
-                AstAnnotationParams::Map(_, _) => panic!(),
+                AstAnnotationParams::Map(_, _) | AstAnnotationParams::Mixed(_, _, _, _) => {
+                    panic!()
+                }
             })
             .unwrap_or_default();
 
@@ -1159,7 +1408,8 @@ mod tests {
 
     use super::*;
     use builder::{load_subsystem_builders, parser, typechecker};
-    use std::fs::File;
+    use core_plugin_interface::core_model::transaction::IsolationLevel;
+    use std::{fs::File, time::Duration};
 
     fn create_resolved_system(src: &str) -> Result<MappedArena<ResolvedType>, ModelBuildingError> {
         let mut codemap = CodeMap::new();
@@ -1241,6 +1491,85 @@ mod tests {
         );
     }
 
+    #[multiplatform_test]
+    fn with_webhook() {
+        let resolved = create_resolved_system(
+            r#"
+        @postgres
+        module ConcertModule {
+            @webhook(url=env("CONCERT_WEBHOOK_URL"), on=[create, delete])
+            type Concert {
+              @pk id: Int = autoIncrement()
+              title: String
+            }
+
+            @webhook(url=env("VENUE_WEBHOOK_URL"), secret=env("VENUE_WEBHOOK_SECRET"))
+            type Venue {
+              @pk id: Int = autoIncrement()
+              name: String
+            }
+        }
+        "#,
+        )
+        .unwrap();
+
+        let webhook = |name: &str| match resolved.get_by_key(name).unwrap() {
+            ResolvedType::Composite(ct) => ct.webhook.clone(),
+            _ => panic!("Expected a composite type"),
+        };
+
+        assert_eq!(
+            webhook("Concert"),
+            Some(Webhook {
+                url_env: "CONCERT_WEBHOOK_URL".to_string(),
+                secret_env: None,
+                events: vec![WebhookEvent::Create, WebhookEvent::Delete],
+            })
+        );
+        assert_eq!(
+            webhook("Venue"),
+            Some(Webhook {
+                url_env: "VENUE_WEBHOOK_URL".to_string(),
+                secret_env: Some("VENUE_WEBHOOK_SECRET".to_string()),
+                events: WebhookEvent::ALL.to_vec(),
+            })
+        );
+    }
+
+    #[multiplatform_test]
+    fn webhook_with_literal_url() {
+        let resolved = create_resolved_system(
+            r#"
+        @postgres
+        module ConcertModule {
+            @webhook(url="https://example.com/hooks")
+            type Concert {
+              @pk id: Int = autoIncrement()
+            }
+        }
+        "#,
+        );
+
+        assert!(resolved.is_err());
+    }
+
+    #[multiplatform_test]
+    fn webhook_without_events() {
+        let resolved = create_resolved_system(
+            r#"
+        @postgres
+        module ConcertModule {
+            @webhook(url=env("CONCERT_WEBHOOK_URL"), on=[])
+            type Concert {
+              @pk id: Int = autoIncrement()
+            }
+        }
+        "#,
+        );
+
+        assert!(resolved.is_err());
+    }
+
     #[multiplatform_test]
     fn with_defaults() {
         // Note the swapped order between @pk and @dbtype to assert that our parsing logic permits any order
@@ -1495,4 +1824,198 @@ mod tests {
             "non_public_schema"
         );
     }
+
+    #[multiplatform_test]
+    fn multiple_databases() {
+        let src = r#"
+        @postgres
+        module CatalogModule {
+            type Product {
+                @pk id: Int = autoIncrement()
+                name: String
+            }
+        }
+
+        @postgres(url=env("BILLING_DB_URL"))
+        module BillingModule {
+            type Invoice {
+                @pk id: Int = autoIncrement()
+                lines: Set<InvoiceLine>
+            }
+
+            type InvoiceLine {
+                @pk id: Int = autoIncrement()
+                invoice: Invoice
+            }
+        }
+        "#;
+
+        let resolved = create_resolved_system(src).unwrap();
+
+        let database_url_env = |name: &str| {
+            resolved
+                .get_by_key(name)
+                .unwrap()
+                .as_composite()
+                .database_url_env
+                .clone()
+        };
+
+        assert_eq!(database_url_env("Product"), None);
+        assert_eq!(
+            database_url_env("Invoice"),
+            Some("BILLING_DB_URL".to_string())
+        );
+        assert_eq!(
+            database_url_env("InvoiceLine"),
+            Some("BILLING_DB_URL".to_string())
+        );
+    }
+
+    #[multiplatform_test]
+    fn cross_database_relation() {
+        let src = r#"
+        @postgres
+        module CatalogModule {
+            type Product {
+                @pk id: Int = autoIncrement()
+                invoiceLines: Set<InvoiceLine>
+            }
+        }
+
+        @postgres(url=env("BILLING_DB_URL"))
+        module BillingModule {
+            type InvoiceLine {
+                @pk id: Int = autoIncrement()
+                product: Product
+            }
+        }
+        "#;
+
+        let Err(ModelBuildingError::Diagnosis(errors)) = create_resolved_system(src) else {
+            panic!("Expected a cross-database relation error");
+        };
+
+        assert!(errors.iter().all(|error| error
+            .message
+            .contains("Relations across databases are not supported")));
+        assert_eq!(errors.len(), 2); // one for each side of the relation
+    }
+
+    #[multiplatform_test]
+    fn transaction_options() {
+        let src = r#"
+        @postgres
+        module InventoryModule {
+            @transaction(isolation="serializable", timeout="5s")
+            type Product {
+                @pk id: Int = autoIncrement()
+                stock: Int
+            }
+
+            type Warehouse {
+                @pk id: Int = autoIncrement()
+                name: String
+            }
+        }
+        "#;
+
+        let resolved = create_resolved_system(src).unwrap();
+
+        let transaction = |name: &str| {
+            resolved
+                .get_by_key(name)
+                .unwrap()
+                .as_composite()
+                .transaction
+                .clone()
+        };
+
+        assert_eq!(
+            transaction("Product"),
+            TransactionOptions {
+                isolation_level: Some(IsolationLevel::Serializable),
+                timeout: Some(Duration::from_secs(5)),
+            }
+        );
+        assert_eq!(transaction("Warehouse"), TransactionOptions::default());
+    }
+
+    #[multiplatform_test]
+    fn invalid_transaction_options() {
+        let src = r#"
+        @postgres
+        module InventoryModule {
+            @transaction(isolation="snapshot", timeout="5 days")
+            type Product {
+                @pk id: Int = autoIncrement()
+                stock: Int
+            }
+        }
+        "#;
+
+        let Err(ModelBuildingError::Diagnosis(errors)) = create_resolved_system(src) else {
+            panic!("Expected invalid transaction options errors");
+        };
+
+        assert_eq!(errors.len(), 2);
+        assert!(errors[0]
+            .message
+            .contains("Invalid isolation level `snapshot`"));
+        assert!(errors[1].message.contains("Invalid duration `5 days`"));
+    }
+
+    #[multiplatform_test]
+    fn cache_ttl() {
+        let src = r#"
+        @postgres
+        module ConcertModule {
+            @cache(ttl="30s")
+            type Concert {
+                @pk id: Int = autoIncrement()
+                title: String
+            }
+
+            type Venue {
+                @pk id: Int = autoIncrement()
+                name: String
+            }
+        }
+        "#;
+
+        let resolved = create_resolved_system(src).unwrap();
+
+        let cache_ttl = |name: &str| resolved.get_by_key(name).unwrap().as_composite().cache_ttl;
+
+        assert_eq!(cache_ttl("Concert"), Some(Duration::from_secs(30)));
+        assert_eq!(cache_ttl("Venue"), None);
+    }
+
+    #[multiplatform_test]
+    fn invalid_cache_ttl() {
+        let src = r#"
+        @postgres
+        module ConcertModule {
+            @cache(ttl="0s")
+            type Concert {
+                @pk id: Int = autoIncrement()
+                title: String
+            }
+
+            @cache(ttl="soon")
+            type Venue {
+                @pk id: Int = autoIncrement()
+                name: String
+            }
+        }
+        "#;
+
+        let Err(ModelBuildingError::Diagnosis(errors)) = create_resolved_system(src) else {
+            panic!("Expected invalid cache TTL errors");
+        };
+
+        assert_eq!(errors.len(), 2);
+        assert!(errors[0].message.contains("must be positive"));
+        assert!(errors[1].message.contains("Invalid duration `soon`"));
+    }
 }
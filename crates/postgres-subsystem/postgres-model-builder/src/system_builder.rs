@@ -15,8 +15,9 @@ use core_plugin_interface::{
         mapped_arena::{MappedArena, SerializableSlab, SerializableSlabIndex},
     },
     core_model_builder::{
-        builder::system_builder::BaseModelSystem, error::ModelBuildingError,
-        typechecker::typ::TypecheckedSystem,
+        builder::system_builder::BaseModelSystem,
+        error::ModelBuildingError,
+        typechecker::typ::{Module, TypecheckedSystem},
     },
 };
 
@@ -57,6 +58,16 @@ pub fn build(
         build_shallow(&resolved_env, &mut building);
         build_expanded(&resolved_env, &mut building)?;
 
+        // Jobs (of any module) and webhook events are queued in the default database
+        let declares_jobs = typechecked_system
+            .modules
+            .iter()
+            .any(|(_, Module(module))| !module.jobs.is_empty());
+        let has_webhooks = building
+            .entity_types
+            .iter()
+            .any(|(_, entity)| entity.webhook.is_some());
+
         PostgresSubsystem {
             contexts: base_system.contexts.clone(),
             primitive_types: building.primitive_types.values(),
@@ -75,6 +86,8 @@ pub fn build(
 
             input_access_expressions: building.input_access_expressions.into_inner().elems,
             database_access_expressions: building.database_access_expressions.into_inner().elems,
+
+            needs_job_table: declares_jobs || has_webhooks,
         }
     };
 
@@ -23,6 +23,7 @@ use core_plugin_interface::{
         context_type::{get_context, ContextType},
         mapped_arena::{MappedArena, SerializableSlabIndex},
         primitive_type::PrimitiveType,
+        transaction::TransactionOptions,
         types::{FieldType, Named},
     },
     core_model_builder::{ast::ast_types::AstExpr, error::ModelBuildingError, typechecker::Typed},
@@ -173,6 +174,10 @@ fn create_shallow_type(
                 collection_query: SerializableSlabIndex::shallow(),
                 aggregate_query: SerializableSlabIndex::shallow(),
                 access: restrictive_access(),
+                webhook: None,
+                database_url_env: None,
+                transaction: TransactionOptions::default(),
+                cache_ttl: None,
             };
 
             building.entity_types.add(&resolved_type.name(), typ);
@@ -269,6 +274,10 @@ fn expand_type_no_fields(
     existing_type.pk_query = pk_query;
     existing_type.collection_query = collection_query;
     existing_type.aggregate_query = aggregate_query;
+    existing_type.webhook = resolved_type.webhook.clone();
+    existing_type.database_url_env = resolved_type.database_url_env.clone();
+    existing_type.transaction = resolved_type.transaction.clone();
+    existing_type.cache_ttl = resolved_type.cache_ttl;
 }
 
 fn expand_type_relations(
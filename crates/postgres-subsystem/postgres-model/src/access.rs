@@ -8,12 +8,18 @@
 // by the Apache License, Version 2.0.
 
 use core_plugin_interface::core_model::{
-    access::{AccessPredicateExpression, CommonAccessPrimitiveExpression, FunctionCall},
+    access::{
+        AccessLogicalExpression, AccessPredicateExpression, ArithmeticOp, CheckCall,
+        CommonAccessPrimitiveExpression, FunctionCall,
+    },
+    context_type::ContextSelection,
     mapped_arena::SerializableSlabIndex,
 };
-use exo_sql::PhysicalColumnPath;
+use exo_sql::{PhysicalColumnPath, RelationLink};
 use serde::{Deserialize, Serialize};
 
+use crate::types::EntityType;
+
 /// Access specification for a model
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Access {
@@ -37,7 +43,10 @@ pub struct UpdateAccessExpression {
 pub enum DatabaseAccessPrimitiveExpression {
     Column(PhysicalColumnPath, Option<String>), // Column path, for example self.user.id and parameter name (such as "du", default: "self")
     Function(PhysicalColumnPath, FunctionCall<Self>), // Function, for example self.documentUser.some(du => du.id == AuthContext.id && du.read)
-    Common(CommonAccessPrimitiveExpression),          // expression shared by all access expressions
+    Count(RelationLink, Option<FunctionCall<Self>>), // Number of related rows (that match the function's expression, if any), for example self.members.count()
+    Arithmetic(Box<Self>, ArithmeticOp, Box<Self>),  // for example, self.capacity - 10
+    Check(CheckCall<Self>, SerializableSlabIndex<EntityType>), // Module function deciding access (or returning a `where` filter for the entity), for example check("canRead", AuthContext.id)
+    Common(CommonAccessPrimitiveExpression), // expression shared by all access expressions
 }
 
 /// Primitive expressions that can express data input access control rules.
@@ -45,5 +54,70 @@ pub enum DatabaseAccessPrimitiveExpression {
 pub enum InputAccessPrimitiveExpression {
     Path(Vec<String>, Option<String>), // JSON path, for example self.user.id and parameter name (such as "du", default: "self")
     Function(Vec<String>, FunctionCall<Self>), // Function, for example self.documentUser.some(du => du.id == AuthContext.id && du.read)
-    Common(CommonAccessPrimitiveExpression),   // expression shared by all access expressions
+    Count(Vec<String>, Option<FunctionCall<Self>>), // Number of elements (that match the function's expression, if any), for example self.members.count()
+    Arithmetic(Box<Self>, ArithmeticOp, Box<Self>), // for example, self.capacity - 10
+    Check(CheckCall<Self>), // Module function deciding access, for example check("canCreate", self.ownerId)
+    Common(CommonAccessPrimitiveExpression), // expression shared by all access expressions
+}
+
+/// The context selections a database access expression uses, or `None` if it calls a module
+/// function (through `check(...)`), whose result may depend on anything
+pub fn context_selections(
+    expr: &AccessPredicateExpression<DatabaseAccessPrimitiveExpression>,
+) -> Option<Vec<ContextSelection>> {
+    fn predicate_selections(
+        expr: &AccessPredicateExpression<DatabaseAccessPrimitiveExpression>,
+        selections: &mut Vec<ContextSelection>,
+    ) -> Option<()> {
+        match expr {
+            AccessPredicateExpression::LogicalOp(AccessLogicalExpression::Not(expr)) => {
+                predicate_selections(expr, selections)
+            }
+            AccessPredicateExpression::LogicalOp(
+                AccessLogicalExpression::And(left, right)
+                | AccessLogicalExpression::Or(left, right),
+            ) => {
+                predicate_selections(left, selections)?;
+                predicate_selections(right, selections)
+            }
+            AccessPredicateExpression::RelationalOp(op) => {
+                let (left, right) = op.sides();
+                primitive_selections(left, selections)?;
+                primitive_selections(right, selections)
+            }
+            AccessPredicateExpression::BooleanLiteral(_) => Some(()),
+        }
+    }
+
+    fn primitive_selections(
+        expr: &DatabaseAccessPrimitiveExpression,
+        selections: &mut Vec<ContextSelection>,
+    ) -> Option<()> {
+        match expr {
+            DatabaseAccessPrimitiveExpression::Column(..) => Some(()),
+            DatabaseAccessPrimitiveExpression::Function(_, function_call) => {
+                predicate_selections(&function_call.expr, selections)
+            }
+            DatabaseAccessPrimitiveExpression::Count(_, function_call) => match function_call {
+                Some(function_call) => predicate_selections(&function_call.expr, selections),
+                None => Some(()),
+            },
+            DatabaseAccessPrimitiveExpression::Arithmetic(left, _, right) => {
+                primitive_selections(left, selections)?;
+                primitive_selections(right, selections)
+            }
+            DatabaseAccessPrimitiveExpression::Check(..) => None,
+            DatabaseAccessPrimitiveExpression::Common(
+                CommonAccessPrimitiveExpression::ContextSelection(selection),
+            ) => {
+                selections.push(selection.clone());
+                Some(())
+            }
+            DatabaseAccessPrimitiveExpression::Common(_) => Some(()),
+        }
+    }
+
+    let mut selections = vec![];
+    predicate_selections(expr, &mut selections)?;
+    Some(selections)
 }
@@ -0,0 +1,92 @@
+// Copyright Exograph, Inc. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file at the root of this repository.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use exo_sql::{
+    schema::{
+        column_spec::{ColumnSpec, ColumnTypeSpec},
+        index_spec::{IndexKind, IndexSpec},
+        table_spec::TableSpec,
+    },
+    IntBits, PhysicalTableName,
+};
+
+/// The table that stores jobs (declared in modules) and webhook events
+pub const JOB_TABLE_NAME: &str = "exograph_jobs";
+
+/// The number of attempts (including the first one) to run a job, unless specified otherwise
+pub const DEFAULT_MAX_ATTEMPTS: i32 = 5;
+
+/// The specification of the job table in the default database.
+///
+/// The default values are spelled the way Postgres reports them, so that `exo schema verify`
+/// doesn't consider a migrated table different from the specification.
+pub fn job_table_spec() -> TableSpec {
+    let text = || ColumnTypeSpec::String { max_length: None };
+    let int = || ColumnTypeSpec::Int { bits: IntBits::_32 };
+    let timestamp = || ColumnTypeSpec::Timestamp {
+        timezone: true,
+        precision: None,
+    };
+
+    let column =
+        |name: &str, typ: ColumnTypeSpec, is_nullable: bool, default: Option<&str>| ColumnSpec {
+            name: name.to_string(),
+            typ,
+            is_pk: false,
+            is_auto_increment: false,
+            is_nullable,
+            unique_constraints: vec![],
+            default_value: default.map(|default| default.to_string()),
+        };
+
+    let columns = vec![
+        ColumnSpec {
+            is_pk: true,
+            is_auto_increment: true,
+            ..column(
+                "id",
+                ColumnTypeSpec::Int { bits: IntBits::_64 },
+                false,
+                None,
+            )
+        },
+        column("name", text(), false, None),
+        column(
+            "arguments",
+            ColumnTypeSpec::Json,
+            false,
+            Some("'{}'::jsonb"),
+        ),
+        ColumnSpec {
+            unique_constraints: vec![format!("{JOB_TABLE_NAME}_unique_key_key")],
+            ..column("unique_key", text(), true, None)
+        },
+        column("status", text(), false, Some("'pending'::text")),
+        column("attempts", int(), false, Some("0")),
+        column("max_attempts", int(), false, None),
+        column("run_at", timestamp(), false, Some("now()")),
+        column("locked_until", timestamp(), true, None),
+        column("last_error", text(), true, None),
+        column("created_at", timestamp(), false, Some("now()")),
+        column("updated_at", timestamp(), false, Some("now()")),
+    ];
+
+    // Workers look for due jobs by `run_at`
+    let indices = vec![IndexSpec::new(
+        format!("{JOB_TABLE_NAME}_run_at_idx"),
+        ["run_at".to_string()].into_iter().collect(),
+        IndexKind::DatabaseDefault,
+    )];
+
+    TableSpec::new(
+        PhysicalTableName::new(JOB_TABLE_NAME, None),
+        columns,
+        indices,
+    )
+}
@@ -9,6 +9,7 @@
 
 pub mod access;
 pub mod aggregate;
+pub mod job_queue;
 pub mod limit_offset;
 pub mod migration;
 pub mod mutation;
@@ -17,6 +18,8 @@ pub mod order;
 pub mod predicate;
 pub mod query;
 pub mod relation;
+pub mod rls;
 pub mod subsystem;
 pub mod types;
 pub mod vector_distance;
+pub mod webhook;
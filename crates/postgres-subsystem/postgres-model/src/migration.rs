@@ -7,13 +7,17 @@
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
 
-use std::fmt::Display;
+use std::{collections::HashMap, fmt::Display};
 
-use super::subsystem::PostgresSubsystem;
+use super::{
+    rls::RlsPolicies,
+    subsystem::PostgresSubsystem,
+    webhook::{self, InstalledTrigger},
+};
 use exo_sql::{
     database_error::DatabaseError,
     schema::{database_spec::DatabaseSpec, issue::WithIssues, op::SchemaOp, spec::diff},
-    DatabaseClientManager,
+    DatabaseClientManager, PhysicalTableName,
 };
 use serde::Serialize;
 
@@ -105,9 +109,12 @@ impl Migration {
         }
     }
 
+    /// Migrate a database (identified by the env var with its URL, `None` for the default
+    /// database) to the tables the model stores in it
     pub async fn from_db_and_model(
         database: &DatabaseClientManager,
         postgres_subsystem: &PostgresSubsystem,
+        url_env: Option<&str>,
     ) -> Result<Self, DatabaseError> {
         let old_schema = extract_db_schema(database).await?;
 
@@ -115,9 +122,51 @@ impl Migration {
             eprintln!("{issue}");
         }
 
-        let database_spec = DatabaseSpec::from_database(&postgres_subsystem.database);
+        let database_spec = postgres_subsystem.database_spec(url_env);
+        let installed_triggers = installed_webhook_triggers(database).await?;
+
+        let mut migration = Migration::from_schemas(&old_schema.value, &database_spec);
+        migration.add_webhook_triggers(postgres_subsystem, url_env, &installed_triggers);
+
+        Ok(migration)
+    }
+
+    /// Append statements that install the webhook triggers for the model (and remove those of
+    /// entities that no longer have a webhook). See [`webhook::trigger_statements`].
+    pub fn add_webhook_triggers(
+        &mut self,
+        postgres_subsystem: &PostgresSubsystem,
+        url_env: Option<&str>,
+        installed_triggers: &HashMap<PhysicalTableName, InstalledTrigger>,
+    ) {
+        // Triggers don't hold any data, so we don't consider replacing them destructive
+        self.statements.extend(
+            webhook::trigger_statements(postgres_subsystem, url_env, installed_triggers)
+                .into_iter()
+                .map(|statement| MigrationStatement::new(statement, false)),
+        );
+    }
 
-        Ok(Migration::from_schemas(&old_schema.value, &database_spec))
+    /// Append statements that (re)create row-level security policies mirroring the access rules
+    /// (see [`RlsPolicies`])
+    pub fn add_rls_policies(
+        &mut self,
+        postgres_subsystem: &PostgresSubsystem,
+        url_env: Option<&str>,
+    ) {
+        let policies = RlsPolicies::for_database(postgres_subsystem, url_env);
+
+        for issue in &policies.issues {
+            eprintln!("{issue}");
+        }
+
+        // Replacing policies doesn't lose any data, so we don't consider it destructive
+        self.statements.extend(
+            policies
+                .statements
+                .into_iter()
+                .map(|statement| MigrationStatement::new(statement, false)),
+        );
     }
 
     pub fn has_destructive_changes(&self) -> bool {
@@ -129,6 +178,7 @@ impl Migration {
     pub async fn verify(
         database: &DatabaseClientManager,
         postgres_subsystem: &PostgresSubsystem,
+        url_env: Option<&str>,
     ) -> Result<(), VerificationErrors> {
         let old_schema = extract_db_schema(database).await?;
 
@@ -136,11 +186,17 @@ impl Migration {
             eprintln!("{issue}");
         }
 
-        let new_schema = DatabaseSpec::from_database(&postgres_subsystem.database);
+        let new_schema = postgres_subsystem.database_spec(url_env);
 
         let diff = diff(&old_schema.value, &new_schema);
 
-        let errors: Vec<_> = diff.iter().flat_map(|op| op.error_string()).collect();
+        let mut errors: Vec<_> = diff.iter().flat_map(|op| op.error_string()).collect();
+
+        let installed_triggers = installed_webhook_triggers(database).await?;
+        if !webhook::trigger_statements(postgres_subsystem, url_env, &installed_triggers).is_empty()
+        {
+            errors.push("The webhook triggers in the database don't match the model.".to_string());
+        }
 
         if !errors.is_empty() {
             Err(VerificationErrors::ModelNotCompatible(errors))
@@ -209,6 +265,48 @@ async fn extract_db_schema(
     DatabaseSpec::from_live_database(&client).await
 }
 
+/// The webhook triggers installed in the database (keyed by their table)
+async fn installed_webhook_triggers(
+    database: &DatabaseClientManager,
+) -> Result<HashMap<PhysicalTableName, InstalledTrigger>, DatabaseError> {
+    const TRIGGERS_QUERY: &str = r#"
+        SELECT table_ns.nspname AS table_schema, tbl.relname AS table_name,
+            function_ns.nspname AS function_schema, proc.proname AS function_name,
+            proc.prosrc AS function_source
+        FROM pg_trigger trg
+            JOIN pg_class tbl ON tbl.oid = trg.tgrelid
+            JOIN pg_namespace table_ns ON table_ns.oid = tbl.relnamespace
+            JOIN pg_proc proc ON proc.oid = trg.tgfoid
+            JOIN pg_namespace function_ns ON function_ns.oid = proc.pronamespace
+        WHERE trg.tgname = $1 AND NOT trg.tgisinternal"#;
+
+    // Tables and functions in the public schema are referred to without a schema
+    let name = |schema: String, name: String| {
+        PhysicalTableName::new(
+            name,
+            Some(schema.as_str()).filter(|schema| *schema != "public"),
+        )
+    };
+
+    let client = database.get_client().await?;
+
+    Ok(client
+        .query(TRIGGERS_QUERY, &[&webhook::TRIGGER_NAME])
+        .await?
+        .into_iter()
+        .map(|row| {
+            let table_name = name(row.get("table_schema"), row.get("table_name"));
+            let trigger = InstalledTrigger {
+                function_name: name(row.get("function_schema"), row.get("function_name"))
+                    .sql_name(),
+                function_source: row.get("function_source"),
+            };
+
+            (table_name, trigger)
+        })
+        .collect())
+}
+
 pub async fn wipe_database(database: &DatabaseClientManager) -> Result<(), DatabaseError> {
     let client = database.get_client().await?;
 
@@ -230,6 +328,7 @@ pub async fn wipe_database(database: &DatabaseClientManager) -> Result<(), Datab
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::job_queue::{job_table_spec, JOB_TABLE_NAME};
     use core_plugin_interface::{
         error::ModelSerializationError, serializable_system::SerializableSystem,
     };
@@ -1605,6 +1704,82 @@ mod tests {
         .await
     }
 
+    #[cfg_attr(not(target_family = "wasm"), tokio::test)]
+    #[cfg_attr(target_family = "wasm", wasm_bindgen_test::wasm_bindgen_test)]
+    async fn job_table() {
+        let without_webhook = r#"
+            @postgres
+            module ConcertModule {
+                type Concert {
+                    @pk id: Int = autoIncrement()
+                    title: String
+                }
+            }
+        "#;
+
+        let with_webhook = r#"
+            @postgres
+            module ConcertModule {
+                @webhook(url=env("CONCERT_WEBHOOK_URL"))
+                type Concert {
+                    @pk id: Int = autoIncrement()
+                    title: String
+                }
+            }
+        "#;
+
+        let has_job_table = |spec: &DatabaseSpec| {
+            spec.tables
+                .iter()
+                .any(|table| table.name.name == JOB_TABLE_NAME)
+        };
+
+        let subsystem = create_postgres_system_from_str(without_webhook, "test.exo".to_string())
+            .await
+            .unwrap();
+        assert!(!has_job_table(&subsystem.database_spec(None)));
+
+        let subsystem = create_postgres_system_from_str(with_webhook, "test.exo".to_string())
+            .await
+            .unwrap();
+        let spec = subsystem.database_spec(None);
+        assert!(has_job_table(&spec));
+        assert_change(&spec, &spec, vec![], "Idempotent with the job table");
+
+        assert_change(
+            &DatabaseSpec::new(vec![]),
+            &DatabaseSpec::new(vec![job_table_spec()]),
+            vec![
+                (
+                    r#"CREATE TABLE "exograph_jobs" (
+                 |    "id" BIGSERIAL PRIMARY KEY,
+                 |    "name" TEXT NOT NULL,
+                 |    "arguments" JSONB NOT NULL DEFAULT '{}'::jsonb,
+                 |    "unique_key" TEXT,
+                 |    "status" TEXT NOT NULL DEFAULT 'pending'::text,
+                 |    "attempts" INT NOT NULL DEFAULT 0,
+                 |    "max_attempts" INT NOT NULL,
+                 |    "run_at" TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+                 |    "locked_until" TIMESTAMP WITH TIME ZONE,
+                 |    "last_error" TEXT,
+                 |    "created_at" TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+                 |    "updated_at" TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now()
+                 |);"#,
+                    false,
+                ),
+                (
+                    r#"ALTER TABLE "exograph_jobs" ADD CONSTRAINT "exograph_jobs_unique_key_key" UNIQUE ("unique_key");"#,
+                    false,
+                ),
+                (
+                    r#"CREATE INDEX "exograph_jobs_run_at_idx" ON "exograph_jobs" ("run_at");"#,
+                    false,
+                ),
+            ],
+            "Create the job table",
+        );
+    }
+
     async fn create_postgres_system_from_str(
         model_str: &str,
         file_name: String,
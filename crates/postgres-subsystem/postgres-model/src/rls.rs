@@ -0,0 +1,664 @@
+// Copyright Exograph, Inc. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file at the root of this repository.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! Row-level security (RLS) policies that mirror the access rules of entity types.
+//!
+//! Exograph enforces access rules while generating SQL. The policies generated here let Postgres
+//! also enforce them (as a second line of defense for the statements the resolver executes). Since
+//! the database doesn't know about the request, each context value used by a rule (such as
+//! `AuthContext.id`) is read from a setting (such as `exo.ctx.AuthContext.id`) holding its JSON
+//! representation. The resolver sets these settings for each transaction (see
+//! [`RlsPolicies::context_selections`]).
+//!
+//! Any session may set these settings, so the policies don't protect against clients that can run
+//! arbitrary SQL (they can claim any context value).
+//!
+//! Only rules that can be expressed in terms of the table's row are supported. For example, rules
+//! that use `some`, `count()`, or `check(...)` can't be compiled, in which case we skip the table
+//! altogether (instead of enforcing only a part of the rules).
+
+use std::collections::BTreeMap;
+
+use core_plugin_interface::core_model::{
+    access::{
+        AccessLogicalExpression, AccessPredicateExpression, AccessRelationalOp, ArithmeticOp,
+        CommonAccessPrimitiveExpression,
+    },
+    context_type::ContextSelection,
+    primitive_type::PrimitiveType,
+    types::FieldType,
+};
+use exo_sql::{ColumnPathLink, Database, PhysicalColumnPath};
+
+use crate::{
+    access::{DatabaseAccessPrimitiveExpression, InputAccessPrimitiveExpression},
+    relation::PostgresRelation,
+    subsystem::PostgresSubsystem,
+    types::EntityType,
+};
+
+/// Prefix of the settings holding context values (for example, `exo.ctx.AuthContext.id`)
+pub const CONTEXT_SETTING_PREFIX: &str = "exo.ctx";
+
+const QUERY_POLICY: &str = "exo_query";
+const CREATE_POLICY: &str = "exo_create";
+const UPDATE_POLICY: &str = "exo_update";
+const DELETE_POLICY: &str = "exo_delete";
+
+/// Row-level security policies for all entity types
+pub struct RlsPolicies {
+    /// Statements to (re)create the policies and enable row-level security
+    pub statements: Vec<String>,
+    /// Entity types whose rules couldn't be compiled (and the reason)
+    pub issues: Vec<String>,
+    /// Context selections used by the policies (keyed by their setting name)
+    context_selections: BTreeMap<String, ContextSelection>,
+}
+
+impl RlsPolicies {
+    pub fn from_subsystem(subsystem: &PostgresSubsystem) -> Self {
+        Self::from_entities(subsystem, |_| true)
+    }
+
+    /// Policies for the entity types stored in a database (see
+    /// [`PostgresSubsystem::database_url_envs`])
+    pub fn for_database(subsystem: &PostgresSubsystem, url_env: Option<&str>) -> Self {
+        Self::from_entities(subsystem, |entity| {
+            entity.database_url_env.as_deref() == url_env
+        })
+    }
+
+    fn from_entities(subsystem: &PostgresSubsystem, filter: impl Fn(&EntityType) -> bool) -> Self {
+        let mut policies = RlsPolicies {
+            statements: vec![],
+            issues: vec![],
+            context_selections: BTreeMap::new(),
+        };
+
+        for (_, entity) in subsystem.entity_types.iter() {
+            if !filter(entity) {
+                continue;
+            }
+
+            let mut compiler = PolicyCompiler {
+                subsystem,
+                entity,
+                table_alias: quote_identifier(
+                    &subsystem.database.get_table(entity.table_id).name.name,
+                ),
+                context_selections: BTreeMap::new(),
+            };
+
+            match compiler.table_statements() {
+                Ok(statements) => {
+                    policies.statements.extend(statements);
+                    policies
+                        .context_selections
+                        .extend(compiler.context_selections);
+                }
+                Err(reason) => policies.issues.push(format!(
+                    "Skipping row-level security for `{}`: {reason}",
+                    entity.name
+                )),
+            }
+        }
+
+        policies
+    }
+
+    /// The context selections whose values the policies read (through [`context_setting_name`])
+    pub fn context_selections(&self) -> impl Iterator<Item = &ContextSelection> {
+        self.context_selections.values()
+    }
+}
+
+/// The setting that holds the (JSON-encoded) value of a context selection
+pub fn context_setting_name(selection: &ContextSelection) -> String {
+    format!(
+        "{CONTEXT_SETTING_PREFIX}.{}.{}",
+        selection.context_name, selection.path.0
+    )
+}
+
+/// An operand of a relational expression in SQL
+enum Operand {
+    Scalar(String),
+    /// An array (only context values such as `AuthContext.roles` may be arrays)
+    Array(String),
+}
+
+struct PolicyCompiler<'a> {
+    subsystem: &'a PostgresSubsystem,
+    entity: &'a EntityType,
+    /// The name to refer to the table's row in policy expressions
+    table_alias: String,
+    context_selections: BTreeMap<String, ContextSelection>,
+}
+
+impl PolicyCompiler<'_> {
+    fn table_statements(&mut self) -> Result<Vec<String>, String> {
+        let subsystem = self.subsystem;
+        let entity = self.entity;
+        let access = &entity.access;
+        let table_name = subsystem
+            .database
+            .get_table(entity.table_id)
+            .name
+            .sql_name();
+
+        let read = self.database_predicate(&subsystem.database_access_expressions[access.read])?;
+        let creation =
+            self.input_predicate(&subsystem.input_access_expressions[access.creation])?;
+        let update_database = self
+            .database_predicate(&subsystem.database_access_expressions[access.update.database])?;
+        let update_input =
+            self.input_predicate(&subsystem.input_access_expressions[access.update.input])?;
+        let delete =
+            self.database_predicate(&subsystem.database_access_expressions[access.delete])?;
+
+        let policies = [
+            (QUERY_POLICY, format!("FOR SELECT USING ({read})")),
+            (CREATE_POLICY, format!("FOR INSERT WITH CHECK ({creation})")),
+            (
+                UPDATE_POLICY,
+                format!("FOR UPDATE USING ({update_database}) WITH CHECK ({update_input})"),
+            ),
+            (DELETE_POLICY, format!("FOR DELETE USING ({delete})")),
+        ];
+
+        let mut statements: Vec<String> = policies
+            .iter()
+            .flat_map(|(name, definition)| {
+                [
+                    format!("DROP POLICY IF EXISTS {name} ON {table_name};"),
+                    format!("CREATE POLICY {name} ON {table_name} {definition};"),
+                ]
+            })
+            .collect();
+        statements.push(format!(
+            "ALTER TABLE {table_name} ENABLE ROW LEVEL SECURITY;"
+        ));
+
+        Ok(statements)
+    }
+
+    fn database_predicate(
+        &mut self,
+        expr: &AccessPredicateExpression<DatabaseAccessPrimitiveExpression>,
+    ) -> Result<String, String> {
+        self.predicate(expr, &mut |compiler, primitive| {
+            compiler.database_primitive(primitive)
+        })
+    }
+
+    fn input_predicate(
+        &mut self,
+        expr: &AccessPredicateExpression<InputAccessPrimitiveExpression>,
+    ) -> Result<String, String> {
+        self.predicate(expr, &mut |compiler, primitive| {
+            compiler.input_primitive(primitive)
+        })
+    }
+
+    fn predicate<P: Send + Sync>(
+        &mut self,
+        expr: &AccessPredicateExpression<P>,
+        primitive: &mut impl FnMut(&mut Self, &P) -> Result<Operand, String>,
+    ) -> Result<String, String> {
+        Ok(match expr {
+            AccessPredicateExpression::BooleanLiteral(value) => value.to_string(),
+            AccessPredicateExpression::LogicalOp(op) => match op {
+                AccessLogicalExpression::Not(expr) => {
+                    format!("(NOT {})", self.predicate(expr, primitive)?)
+                }
+                AccessLogicalExpression::And(left, right) => format!(
+                    "({} AND {})",
+                    self.predicate(left, primitive)?,
+                    self.predicate(right, primitive)?
+                ),
+                AccessLogicalExpression::Or(left, right) => format!(
+                    "({} OR {})",
+                    self.predicate(left, primitive)?,
+                    self.predicate(right, primitive)?
+                ),
+            },
+            AccessPredicateExpression::RelationalOp(op) => {
+                let (left, right) = op.sides();
+                let left = primitive(self, left)?;
+                let right = primitive(self, right)?;
+
+                match (op, left, right) {
+                    (AccessRelationalOp::In(..), Operand::Scalar(left), Operand::Array(right))
+                    | (
+                        AccessRelationalOp::Contains(..),
+                        Operand::Array(right),
+                        Operand::Scalar(left),
+                    ) => format!("({left} = ANY({right}))"),
+                    (
+                        AccessRelationalOp::Contains(..),
+                        Operand::Scalar(left),
+                        Operand::Scalar(right),
+                    ) => format!("(strpos({left}, {right}) > 0)"),
+                    (op, Operand::Scalar(left), Operand::Scalar(right)) => {
+                        let operator = match op {
+                            AccessRelationalOp::Eq(..) => "=",
+                            AccessRelationalOp::Neq(..) => "<>",
+                            AccessRelationalOp::Lt(..) => "<",
+                            AccessRelationalOp::Lte(..) => "<=",
+                            AccessRelationalOp::Gt(..) => ">",
+                            AccessRelationalOp::Gte(..) => ">=",
+                            AccessRelationalOp::In(..) | AccessRelationalOp::Contains(..) => {
+                                return Err("`in` requires a list on the right side".to_string())
+                            }
+                        };
+                        format!("({left} {operator} {right})")
+                    }
+                    _ => return Err("lists may only be used with `in` and `contains`".to_string()),
+                }
+            }
+        })
+    }
+
+    fn database_primitive(
+        &mut self,
+        expr: &DatabaseAccessPrimitiveExpression,
+    ) -> Result<Operand, String> {
+        match expr {
+            DatabaseAccessPrimitiveExpression::Column(path, _) => Ok(Operand::Scalar(
+                column_expression(path, &self.table_alias, 0, &self.subsystem.database)?,
+            )),
+            DatabaseAccessPrimitiveExpression::Arithmetic(left, op, right) => {
+                let left = self.database_primitive(left)?;
+                let right = self.database_primitive(right)?;
+                arithmetic(left, *op, right)
+            }
+            DatabaseAccessPrimitiveExpression::Common(common) => self.common_primitive(common),
+            DatabaseAccessPrimitiveExpression::Function(_, _)
+            | DatabaseAccessPrimitiveExpression::Count(_, _) => Err(
+                "rules using `some`, `every`, `none`, or `count()` aren't supported".to_string(),
+            ),
+            DatabaseAccessPrimitiveExpression::Check(call, _) => Err(format!(
+                "rules using `check(\"{}\", ...)` aren't supported",
+                call.function_name
+            )),
+        }
+    }
+
+    fn input_primitive(
+        &mut self,
+        expr: &InputAccessPrimitiveExpression,
+    ) -> Result<Operand, String> {
+        match expr {
+            InputAccessPrimitiveExpression::Path(path, None) => {
+                Ok(Operand::Scalar(self.input_column(path)?))
+            }
+            InputAccessPrimitiveExpression::Arithmetic(left, op, right) => {
+                let left = self.input_primitive(left)?;
+                let right = self.input_primitive(right)?;
+                arithmetic(left, *op, right)
+            }
+            InputAccessPrimitiveExpression::Common(common) => self.common_primitive(common),
+            InputAccessPrimitiveExpression::Path(_, Some(_))
+            | InputAccessPrimitiveExpression::Function(_, _)
+            | InputAccessPrimitiveExpression::Count(_, _) => Err(
+                "rules using `some`, `every`, `none`, or `count()` aren't supported".to_string(),
+            ),
+            InputAccessPrimitiveExpression::Check(call) => Err(format!(
+                "rules using `check(\"{}\", ...)` aren't supported",
+                call.function_name
+            )),
+        }
+    }
+
+    /// The column of the new row for an input path such as `self.title` or `self.owner.id`
+    fn input_column(&self, path: &[String]) -> Result<String, String> {
+        let database = &self.subsystem.database;
+        let field = |name: &str| self.entity.fields.iter().find(|field| field.name == name);
+
+        let column_id = match path {
+            [field_name] => match field(field_name).map(|field| &field.relation) {
+                Some(
+                    PostgresRelation::Pk { column_id } | PostgresRelation::Scalar { column_id },
+                ) => Some(*column_id),
+                _ => None,
+            },
+            [field_name, foreign_field_name] => {
+                match field(field_name).map(|field| &field.relation) {
+                    Some(PostgresRelation::ManyToOne(relation))
+                        if &relation
+                            .foreign_pk_field_id
+                            .resolve(&self.subsystem.entity_types)
+                            .name
+                            == foreign_field_name =>
+                    {
+                        Some(relation.relation_id.deref(database).self_column_id)
+                    }
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+        .ok_or_else(|| {
+            format!(
+                "`self.{}` can't be mapped to a column of the table",
+                path.join(".")
+            )
+        })?;
+
+        Ok(format!(
+            "{}.{}",
+            self.table_alias,
+            quote_identifier(&column_id.get_column(database).name)
+        ))
+    }
+
+    fn common_primitive(
+        &mut self,
+        expr: &CommonAccessPrimitiveExpression,
+    ) -> Result<Operand, String> {
+        Ok(match expr {
+            CommonAccessPrimitiveExpression::ContextSelection(selection) => {
+                return self.context_value(selection)
+            }
+            CommonAccessPrimitiveExpression::StringLiteral(value) => {
+                Operand::Scalar(quote_literal(value))
+            }
+            CommonAccessPrimitiveExpression::BooleanLiteral(value) => {
+                Operand::Scalar(value.to_string())
+            }
+            CommonAccessPrimitiveExpression::NumberLiteral(value) => {
+                Operand::Scalar(value.to_string())
+            }
+            CommonAccessPrimitiveExpression::Now => Operand::Scalar("now()".to_string()),
+        })
+    }
+
+    fn context_value(&mut self, selection: &ContextSelection) -> Result<Operand, String> {
+        if !selection.path.1.is_empty() {
+            return Err("nested context selections aren't supported".to_string());
+        }
+
+        let field_type = self
+            .subsystem
+            .contexts
+            .get_by_key(&selection.context_name)
+            .and_then(|context| {
+                context
+                    .fields
+                    .iter()
+                    .find(|field| field.name == selection.path.0)
+            })
+            .map(|field| &field.typ)
+            .ok_or_else(|| {
+                format!(
+                    "unknown context field `{}.{}`",
+                    selection.context_name, selection.path.0
+                )
+            })?;
+
+        let setting_name = context_setting_name(selection);
+        let setting = format!(
+            "NULLIF(current_setting({}, true), '')::jsonb",
+            quote_literal(&setting_name)
+        );
+        self.context_selections
+            .insert(setting_name, selection.clone());
+
+        fn plain_type(field_type: &FieldType<PrimitiveType>) -> &FieldType<PrimitiveType> {
+            match field_type {
+                FieldType::Optional(inner) => plain_type(inner),
+                _ => field_type,
+            }
+        }
+
+        match plain_type(field_type) {
+            FieldType::Plain(PrimitiveType::Array(element_type)) => {
+                let element_type = sql_type(element_type)?;
+                Ok(Operand::Array(format!(
+                    "ARRAY(SELECT jsonb_array_elements_text({setting}))::{element_type}[]"
+                )))
+            }
+            FieldType::List(element_type) => match plain_type(element_type) {
+                FieldType::Plain(element_type) => {
+                    let element_type = sql_type(element_type)?;
+                    Ok(Operand::Array(format!(
+                        "ARRAY(SELECT jsonb_array_elements_text({setting}))::{element_type}[]"
+                    )))
+                }
+                _ => Err("nested lists in contexts aren't supported".to_string()),
+            },
+            FieldType::Plain(typ) => {
+                let typ = sql_type(typ)?;
+                Ok(Operand::Scalar(format!("({setting} #>> '{{}}')::{typ}")))
+            }
+            FieldType::Optional(_) => unreachable!("Optional types are unwrapped above"),
+        }
+    }
+}
+
+/// The SQL expression for a column path. Many-to-one relations (such as `self.venue.name`) map to
+/// scalar subqueries.
+fn column_expression(
+    path: &PhysicalColumnPath,
+    table_alias: &str,
+    depth: usize,
+    database: &Database,
+) -> Result<String, String> {
+    let (head, tail) = path.split_head();
+    let is_one_to_many = head.is_one_to_many(database);
+
+    match (head, tail) {
+        (ColumnPathLink::Leaf(column_id), _) => Ok(format!(
+            "{table_alias}.{}",
+            quote_identifier(&column_id.get_column(database).name)
+        )),
+        (ColumnPathLink::Relation(relation), Some(tail)) if !is_one_to_many => {
+            let foreign_alias = format!("exo_rls_{depth}");
+            let foreign_table = database
+                .get_table(relation.foreign_column_id.table_id)
+                .name
+                .sql_name();
+
+            Ok(format!(
+                "(SELECT {} FROM {foreign_table} AS {foreign_alias} WHERE {foreign_alias}.{} = {table_alias}.{})",
+                column_expression(&tail, &foreign_alias, depth + 1, database)?,
+                quote_identifier(&relation.foreign_column_id.get_column(database).name),
+                quote_identifier(&relation.self_column_id.get_column(database).name),
+            ))
+        }
+        _ => Err("rules that use one-to-many relations aren't supported".to_string()),
+    }
+}
+
+fn arithmetic(left: Operand, op: ArithmeticOp, right: Operand) -> Result<Operand, String> {
+    match (left, right) {
+        (Operand::Scalar(left), Operand::Scalar(right)) => {
+            let operator = match op {
+                ArithmeticOp::Add => "+",
+                ArithmeticOp::Sub => "-",
+                ArithmeticOp::Mul => "*",
+            };
+            Ok(Operand::Scalar(format!("({left} {operator} {right})")))
+        }
+        _ => Err("arithmetic on lists isn't supported".to_string()),
+    }
+}
+
+fn sql_type(typ: &PrimitiveType) -> Result<&'static str, String> {
+    Ok(match typ {
+        PrimitiveType::Int => "bigint",
+        PrimitiveType::Float => "double precision",
+        PrimitiveType::Decimal => "numeric",
+        PrimitiveType::String => "text",
+        PrimitiveType::Boolean => "boolean",
+        PrimitiveType::LocalDate => "date",
+        PrimitiveType::LocalTime => "time",
+        PrimitiveType::LocalDateTime => "timestamp",
+        PrimitiveType::Instant => "timestamptz",
+        PrimitiveType::Uuid => "uuid",
+        PrimitiveType::Json => "jsonb",
+        _ => return Err(format!("context fields of type `{typ}` aren't supported")),
+    })
+}
+
+fn quote_identifier(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+fn quote_literal(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "''"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core_plugin_interface::{
+        error::ModelSerializationError, serializable_system::SerializableSystem,
+        system_serializer::SystemSerializer,
+    };
+
+    #[cfg_attr(not(target_family = "wasm"), tokio::test)]
+    #[cfg_attr(target_family = "wasm", wasm_bindgen_test::wasm_bindgen_test)]
+    async fn policies_for_simple_rules() {
+        let subsystem = create_postgres_system_from_str(
+            r#"
+            context AuthContext {
+                @jwt id: Int
+                @jwt roles: Array<String>
+            }
+
+            @postgres
+            module DocsDatabase {
+                @access(
+                    query = self.published || self.ownerId == AuthContext.id || "admin" in AuthContext.roles,
+                    mutation = self.ownerId == AuthContext.id
+                )
+                type Document {
+                    @pk id: Int = autoIncrement()
+                    ownerId: Int
+                    published: Boolean
+                }
+            }
+            "#,
+        )
+        .await;
+
+        let policies = RlsPolicies::from_subsystem(&subsystem);
+
+        assert!(policies.issues.is_empty(), "{:?}", policies.issues);
+
+        let owner_id = r#"("documents"."owner_id" = (NULLIF(current_setting('exo.ctx.AuthContext.id', true), '')::jsonb #>> '{}')::bigint)"#;
+        let roles = r#"('admin' = ANY(ARRAY(SELECT jsonb_array_elements_text(NULLIF(current_setting('exo.ctx.AuthContext.roles', true), '')::jsonb))::text[]))"#;
+
+        assert_eq!(
+            policies.statements,
+            vec![
+                r#"DROP POLICY IF EXISTS exo_query ON "documents";"#.to_string(),
+                format!(
+                    r#"CREATE POLICY exo_query ON "documents" FOR SELECT USING (((("documents"."published" = true) OR {owner_id}) OR {roles}));"#
+                ),
+                r#"DROP POLICY IF EXISTS exo_create ON "documents";"#.to_string(),
+                format!(
+                    r#"CREATE POLICY exo_create ON "documents" FOR INSERT WITH CHECK ({owner_id});"#
+                ),
+                r#"DROP POLICY IF EXISTS exo_update ON "documents";"#.to_string(),
+                format!(
+                    r#"CREATE POLICY exo_update ON "documents" FOR UPDATE USING ({owner_id}) WITH CHECK ({owner_id});"#
+                ),
+                r#"DROP POLICY IF EXISTS exo_delete ON "documents";"#.to_string(),
+                format!(
+                    r#"CREATE POLICY exo_delete ON "documents" FOR DELETE USING ({owner_id});"#
+                ),
+                r#"ALTER TABLE "documents" ENABLE ROW LEVEL SECURITY;"#.to_string(),
+            ]
+        );
+
+        let settings: Vec<_> = policies
+            .context_selections()
+            .map(context_setting_name)
+            .collect();
+        assert_eq!(
+            settings,
+            vec!["exo.ctx.AuthContext.id", "exo.ctx.AuthContext.roles"]
+        );
+    }
+
+    #[cfg_attr(not(target_family = "wasm"), tokio::test)]
+    #[cfg_attr(target_family = "wasm", wasm_bindgen_test::wasm_bindgen_test)]
+    async fn unsupported_rules_skip_table() {
+        let subsystem = create_postgres_system_from_str(
+            r#"
+            context AuthContext {
+                @jwt id: Int
+            }
+
+            @postgres
+            module DocsDatabase {
+                @access(self.permissions.some(p => p.userId == AuthContext.id))
+                type Document {
+                    @pk id: Int = autoIncrement()
+                    permissions: Set<Permission>?
+                }
+
+                @access(true)
+                type Permission {
+                    @pk id: Int = autoIncrement()
+                    userId: Int
+                    document: Document
+                }
+            }
+            "#,
+        )
+        .await;
+
+        let policies = RlsPolicies::from_subsystem(&subsystem);
+
+        assert_eq!(policies.issues.len(), 1);
+        assert!(policies.issues[0].starts_with("Skipping row-level security for `Document`"));
+        assert!(policies
+            .statements
+            .iter()
+            .all(|statement| !statement.contains(r#""documents""#)));
+        assert!(policies.statements.contains(
+            &r#"CREATE POLICY exo_query ON "permissions" FOR SELECT USING (true);"#.to_string()
+        ));
+    }
+
+    async fn create_postgres_system_from_str(source: &str) -> PostgresSubsystem {
+        let system = builder::build_system_from_str(
+            source,
+            "index.exo".to_string(),
+            vec![Box::new(
+                postgres_model_builder::PostgresSubsystemBuilder {},
+            )],
+        )
+        .await
+        .unwrap();
+
+        deserialize_postgres_subsystem(system).unwrap()
+    }
+
+    fn deserialize_postgres_subsystem(
+        system: SerializableSystem,
+    ) -> Result<PostgresSubsystem, ModelSerializationError> {
+        system
+            .subsystems
+            .into_iter()
+            .find_map(|subsystem| {
+                if subsystem.id == "postgres" {
+                    Some(PostgresSubsystem::deserialize(
+                        subsystem.serialized_subsystem,
+                    ))
+                } else {
+                    None
+                }
+            })
+            .unwrap()
+    }
+}
@@ -18,6 +18,7 @@ use super::{
 use crate::{
     access::{DatabaseAccessPrimitiveExpression, InputAccessPrimitiveExpression},
     aggregate::AggregateType,
+    job_queue::job_table_spec,
     query::{AggregateQuery, CollectionQuery, UniqueQuery},
     types::{EntityType, MutationType, PostgresPrimitiveType},
 };
@@ -31,7 +32,7 @@ use core_plugin_interface::{
     error::ModelSerializationError,
     system_serializer::SystemSerializer,
 };
-use exo_sql::Database;
+use exo_sql::{schema::database_spec::DatabaseSpec, Database, TableId};
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -61,9 +62,53 @@ pub struct PostgresSubsystem {
         SerializableSlab<AccessPredicateExpression<DatabaseAccessPrimitiveExpression>>,
 
     pub database: Database,
+
+    /// Whether the default database needs the job table (see [`job_table_spec`]), since a module
+    /// declares jobs or a type has a webhook
+    pub needs_job_table: bool,
 }
 
 impl PostgresSubsystem {
+    /// The databases that store the entities, identified by the env var with their URL (`None` for
+    /// the default database, which comes first). With no entities, only the default database.
+    pub fn database_url_envs(&self) -> Vec<Option<&str>> {
+        let mut url_envs: Vec<_> = self
+            .entity_types
+            .iter()
+            .map(|(_, entity)| entity.database_url_env.as_deref())
+            .collect();
+        url_envs.sort();
+        url_envs.dedup();
+
+        if url_envs.is_empty() {
+            vec![None]
+        } else {
+            url_envs
+        }
+    }
+
+    /// The env var with the URL of the database that stores a table (`None` for the default
+    /// database)
+    pub fn table_database_url_env(&self, table_id: TableId) -> Option<&str> {
+        self.entity_types
+            .iter()
+            .find(|(_, entity)| entity.table_id == table_id)
+            .and_then(|(_, entity)| entity.database_url_env.as_deref())
+    }
+
+    /// The specification of the tables stored in a database (see [`Self::database_url_envs`])
+    pub fn database_spec(&self, url_env: Option<&str>) -> DatabaseSpec {
+        let mut spec = DatabaseSpec::from_database_tables(&self.database, |table_id| {
+            self.table_database_url_env(table_id) == url_env
+        });
+
+        if self.needs_job_table && url_env.is_none() {
+            spec.tables.push(job_table_spec());
+        }
+
+        spec
+    }
+
     pub fn schema_queries(&self) -> Vec<FieldDefinition> {
         let pk_queries_defn = self
             .pk_queries
@@ -150,6 +195,8 @@ impl Default for PostgresSubsystem {
             database_access_expressions: SerializableSlab::new(),
 
             database: Database::default(),
+
+            needs_job_table: false,
         }
     }
 }
@@ -7,6 +7,8 @@
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
 
+use std::time::Duration;
+
 use super::access::Access;
 use super::relation::PostgresRelation;
 use crate::access::{DatabaseAccessPrimitiveExpression, InputAccessPrimitiveExpression};
@@ -15,12 +17,14 @@ use crate::query::{AggregateQuery, CollectionQuery, CollectionQueryParameters, P
 use crate::relation::OneToManyRelation;
 use crate::subsystem::PostgresSubsystem;
 use crate::vector_distance::VectorDistanceField;
+use crate::webhook::Webhook;
 use async_graphql_parser::types::{
     FieldDefinition, InputObjectType, ObjectType, Type, TypeDefinition, TypeKind,
 };
 use core_plugin_interface::core_model::access::AccessPredicateExpression;
 use core_plugin_interface::core_model::context_type::ContextSelection;
 use core_plugin_interface::core_model::primitive_type::vector_introspection_base_type;
+use core_plugin_interface::core_model::transaction::TransactionOptions;
 use core_plugin_interface::core_model::{
     mapped_arena::{SerializableSlab, SerializableSlabIndex},
     type_normalization::{
@@ -84,6 +88,15 @@ pub struct EntityType {
     pub collection_query: SerializableSlabIndex<CollectionQuery>,
     pub aggregate_query: SerializableSlabIndex<AggregateQuery>,
     pub access: Access,
+    pub webhook: Option<Webhook>,
+    /// The env var with the URL of the database the entity is stored in (`None` for the default
+    /// database)
+    pub database_url_env: Option<String>,
+    /// Options for the transaction the entity's mutations run in (from `@transaction`)
+    pub transaction: TransactionOptions,
+    /// How long to cache the results of queries on the entity (from `@cache`), or `None` to not
+    /// cache them
+    pub cache_ttl: Option<Duration>,
 }
 
 pub fn get_field_id(
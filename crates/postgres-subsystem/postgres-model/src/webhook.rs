@@ -0,0 +1,518 @@
+// Copyright Exograph, Inc. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file at the root of this repository.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::collections::HashMap;
+
+use exo_sql::{ColumnId, PhysicalTableName};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    job_queue::{DEFAULT_MAX_ATTEMPTS, JOB_TABLE_NAME},
+    relation::PostgresRelation,
+    subsystem::PostgresSubsystem,
+    types::EntityType,
+};
+
+/// Outbound webhook for changes to an entity (declared using `@webhook`)
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Webhook {
+    /// The environment variable holding the URL to deliver events to
+    pub url_env: String,
+    /// The environment variable holding the secret to sign events with (if not set, we use
+    /// `EXO_WEBHOOK_SECRET`)
+    pub secret_env: Option<String>,
+    pub events: Vec<WebhookEvent>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WebhookEvent {
+    Create,
+    Update,
+    Delete,
+}
+
+impl WebhookEvent {
+    pub const ALL: [WebhookEvent; 3] = [
+        WebhookEvent::Create,
+        WebhookEvent::Update,
+        WebhookEvent::Delete,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            WebhookEvent::Create => "create",
+            WebhookEvent::Update => "update",
+            WebhookEvent::Delete => "delete",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|event| event.name() == name)
+    }
+}
+
+/// Prefix of the names of the jobs that deliver webhook events (followed by the entity name)
+pub const WEBHOOK_JOB_PREFIX: &str = "webhook:";
+
+/// The trigger that records the events of an entity with a webhook (installed on its table)
+pub const TRIGGER_NAME: &str = "exograph_webhook";
+
+// `jsonb_build_object` takes at most 100 arguments, so we build objects with more fields in chunks
+const MAX_FIELDS_PER_OBJECT: usize = 50;
+
+/// The job that delivers the webhook events of an entity
+pub fn job_name(entity: &EntityType) -> String {
+    format!("{WEBHOOK_JOB_PREFIX}{}", entity.name)
+}
+
+/// A webhook trigger installed in the database
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InstalledTrigger {
+    /// The (quoted and, outside the public schema, qualified) name of the trigger function
+    pub function_name: String,
+    /// The body of the trigger function
+    pub function_source: String,
+}
+
+/// Statements that install (or update) the webhook triggers of the entities stored in a database
+/// (see [`PostgresSubsystem::database_url_envs`]) and remove the triggers of entities that no
+/// longer have a webhook. Triggers on tables that aren't part of the model are left alone.
+///
+/// `installed` holds the triggers already in the database (keyed by their table).
+pub fn trigger_statements(
+    subsystem: &PostgresSubsystem,
+    url_env: Option<&str>,
+    installed: &HashMap<PhysicalTableName, InstalledTrigger>,
+) -> Vec<String> {
+    let mut statements = vec![];
+
+    for (_, entity) in subsystem.entity_types.iter() {
+        if entity.database_url_env.as_deref() != url_env {
+            continue;
+        }
+
+        let table_name = &subsystem.database.get_table(entity.table_id).name;
+        let installed = installed.get(table_name);
+
+        match &entity.webhook {
+            Some(webhook) => {
+                let function_name = function_name(table_name);
+                let function_source = function_source(entity, webhook, subsystem);
+
+                match installed {
+                    Some(installed) if installed.function_name == function_name => {
+                        if installed.function_source != function_source {
+                            statements.push(create_function_sql(&function_name, &function_source));
+                        }
+                    }
+                    _ => {
+                        if let Some(installed) = installed {
+                            statements.extend(drop_trigger_sql(table_name, installed));
+                        }
+                        statements.push(create_function_sql(&function_name, &function_source));
+                        statements.push(create_trigger_sql(table_name, &function_name));
+                    }
+                }
+            }
+            None => {
+                if let Some(installed) = installed {
+                    statements.extend(drop_trigger_sql(table_name, installed));
+                }
+            }
+        }
+    }
+
+    statements
+}
+
+/// The name of the trigger function for a table. We create the function in the table's schema
+/// and name it after the table, so that tables with the same name in different schemas get their
+/// own function.
+fn function_name(table_name: &PhysicalTableName) -> String {
+    PhysicalTableName::new(
+        format!("{TRIGGER_NAME}_{}", table_name.name),
+        table_name.schema.as_deref(),
+    )
+    .sql_name()
+}
+
+fn create_function_sql(function_name: &str, function_source: &str) -> String {
+    format!(
+        "CREATE OR REPLACE FUNCTION {function_name}() RETURNS trigger AS $exograph${function_source}$exograph$ LANGUAGE plpgsql;"
+    )
+}
+
+fn create_trigger_sql(table_name: &PhysicalTableName, function_name: &str) -> String {
+    format!(
+        r#"CREATE TRIGGER "{TRIGGER_NAME}" AFTER INSERT OR UPDATE OR DELETE ON {} FOR EACH ROW EXECUTE FUNCTION {function_name}();"#,
+        table_name.sql_name()
+    )
+}
+
+fn drop_trigger_sql(table_name: &PhysicalTableName, installed: &InstalledTrigger) -> [String; 2] {
+    [
+        format!(
+            r#"DROP TRIGGER IF EXISTS "{TRIGGER_NAME}" ON {};"#,
+            table_name.sql_name()
+        ),
+        format!("DROP FUNCTION IF EXISTS {}();", installed.function_name),
+    ]
+}
+
+/// The body of the trigger function, which records each configured event as a job in the job
+/// table. The trigger fires for all operations (so that changing the events to deliver requires
+/// updating only the function).
+///
+/// The event id comes from `gen_random_uuid()`, which is built into Postgres 13 and later (older
+/// versions need the `pgcrypto` extension).
+fn function_source(
+    entity: &EntityType,
+    webhook: &Webhook,
+    subsystem: &PostgresSubsystem,
+) -> String {
+    let old = row_payload(entity, "OLD", subsystem);
+    let new = row_payload(entity, "NEW", subsystem);
+
+    let branches: Vec<_> = WebhookEvent::ALL
+        .into_iter()
+        .filter(|event| webhook.events.contains(event))
+        .map(|event| {
+            let (operation, data) = match event {
+                WebhookEvent::Create => ("INSERT", format!("'data', {new}")),
+                WebhookEvent::Update => ("UPDATE", format!("'data', {new}, 'previous', {old}")),
+                WebhookEvent::Delete => ("DELETE", format!("'data', {old}")),
+            };
+
+            format!(
+                "TG_OP = '{operation}' THEN\n        \"event\" := jsonb_build_object('event', '{}', {data});",
+                event.name()
+            )
+        })
+        .collect();
+
+    format!(
+        r#"
+DECLARE
+    "event" JSONB;
+BEGIN
+    IF {branches}
+    ELSE
+        RETURN NULL;
+    END IF;
+
+    INSERT INTO "{JOB_TABLE_NAME}" ("name", "arguments", "max_attempts")
+    VALUES ('{job_name}', "event" || jsonb_build_object('id', gen_random_uuid(), 'type', '{entity_name}', 'timestamp', now()), {DEFAULT_MAX_ATTEMPTS});
+
+    RETURN NULL;
+END
+"#,
+        branches = branches.join("\n    ELSIF "),
+        job_name = job_name(entity),
+        entity_name = entity.name,
+    )
+}
+
+/// A JSON object with the entity's fields (using the field names, not the column names) from the
+/// given row. A many-to-one field is represented as an object with the primary key of the
+/// referenced entity (for example, `"venue": {"id": 1}`). We omit one-to-many fields.
+fn row_payload(entity: &EntityType, row: &str, subsystem: &PostgresSubsystem) -> String {
+    let database = &subsystem.database;
+    let column_name = |column_id: &ColumnId| &column_id.get_column(database).name;
+
+    let pairs: Vec<String> = entity
+        .fields
+        .iter()
+        .filter_map(|field| {
+            let value = match &field.relation {
+                PostgresRelation::Pk { column_id } | PostgresRelation::Scalar { column_id } => {
+                    format!(r#"{row}."{}""#, column_name(column_id))
+                }
+                PostgresRelation::ManyToOne(relation) => {
+                    let self_column =
+                        column_name(&relation.relation_id.deref(database).self_column_id);
+                    let foreign_pk_field = relation
+                        .foreign_pk_field_id
+                        .resolve(&subsystem.entity_types);
+
+                    format!(
+                        r#"CASE WHEN {row}."{self_column}" IS NULL THEN NULL ELSE jsonb_build_object('{}', {row}."{self_column}") END"#,
+                        foreign_pk_field.name
+                    )
+                }
+                PostgresRelation::OneToMany(_) => return None,
+            };
+
+            Some(format!("'{}', {value}", field.name))
+        })
+        .collect();
+
+    if pairs.is_empty() {
+        return "'{}'::jsonb".to_string();
+    }
+
+    pairs
+        .chunks(MAX_FIELDS_PER_OBJECT)
+        .map(|chunk| format!("jsonb_build_object({})", chunk.join(", ")))
+        .collect::<Vec<_>>()
+        .join(" || ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core_plugin_interface::{
+        serializable_system::SerializableSystem, system_serializer::SystemSerializer,
+    };
+
+    const MODEL: &str = r#"
+        @postgres
+        module ConcertModule {
+            @webhook(url=env("CONCERT_WEBHOOK_URL"))
+            type Concert {
+                @pk id: Int = autoIncrement()
+                title: String
+                venue: Venue?
+            }
+
+            @webhook(url=env("VENUE_WEBHOOK_URL"), on=[create])
+            type Venue {
+                @pk id: Int = autoIncrement()
+                name: String
+                concerts: Set<Concert>?
+            }
+
+            type Artist {
+                @pk id: Int = autoIncrement()
+                name: String
+            }
+        }
+    "#;
+
+    const CONCERT_SOURCE: &str = r#"
+DECLARE
+    "event" JSONB;
+BEGIN
+    IF TG_OP = 'INSERT' THEN
+        "event" := jsonb_build_object('event', 'create', 'data', jsonb_build_object('id', NEW."id", 'title', NEW."title", 'venue', CASE WHEN NEW."venue_id" IS NULL THEN NULL ELSE jsonb_build_object('id', NEW."venue_id") END));
+    ELSIF TG_OP = 'UPDATE' THEN
+        "event" := jsonb_build_object('event', 'update', 'data', jsonb_build_object('id', NEW."id", 'title', NEW."title", 'venue', CASE WHEN NEW."venue_id" IS NULL THEN NULL ELSE jsonb_build_object('id', NEW."venue_id") END), 'previous', jsonb_build_object('id', OLD."id", 'title', OLD."title", 'venue', CASE WHEN OLD."venue_id" IS NULL THEN NULL ELSE jsonb_build_object('id', OLD."venue_id") END));
+    ELSIF TG_OP = 'DELETE' THEN
+        "event" := jsonb_build_object('event', 'delete', 'data', jsonb_build_object('id', OLD."id", 'title', OLD."title", 'venue', CASE WHEN OLD."venue_id" IS NULL THEN NULL ELSE jsonb_build_object('id', OLD."venue_id") END));
+    ELSE
+        RETURN NULL;
+    END IF;
+
+    INSERT INTO "exograph_jobs" ("name", "arguments", "max_attempts")
+    VALUES ('webhook:Concert', "event" || jsonb_build_object('id', gen_random_uuid(), 'type', 'Concert', 'timestamp', now()), 5);
+
+    RETURN NULL;
+END
+"#;
+
+    #[cfg_attr(not(target_family = "wasm"), tokio::test)]
+    #[cfg_attr(target_family = "wasm", wasm_bindgen_test::wasm_bindgen_test)]
+    async fn function_records_insert_update_and_delete() {
+        let subsystem = create_postgres_system_from_str(MODEL).await;
+        let (concert, webhook) = entity_with_webhook(&subsystem, "Concert");
+
+        assert_eq!(
+            row_payload(concert, "OLD", &subsystem),
+            r#"jsonb_build_object('id', OLD."id", 'title', OLD."title", 'venue', CASE WHEN OLD."venue_id" IS NULL THEN NULL ELSE jsonb_build_object('id', OLD."venue_id") END)"#
+        );
+        assert_eq!(
+            function_source(concert, webhook, &subsystem),
+            CONCERT_SOURCE
+        );
+    }
+
+    #[cfg_attr(not(target_family = "wasm"), tokio::test)]
+    #[cfg_attr(target_family = "wasm", wasm_bindgen_test::wasm_bindgen_test)]
+    async fn function_records_only_configured_events() {
+        let subsystem = create_postgres_system_from_str(MODEL).await;
+        let (venue, webhook) = entity_with_webhook(&subsystem, "Venue");
+
+        // One-to-many fields are omitted
+        assert_eq!(
+            row_payload(venue, "NEW", &subsystem),
+            r#"jsonb_build_object('id', NEW."id", 'name', NEW."name")"#
+        );
+
+        let source = function_source(venue, webhook, &subsystem);
+        assert!(source.contains(
+            r#"    IF TG_OP = 'INSERT' THEN
+        "event" := jsonb_build_object('event', 'create', 'data', jsonb_build_object('id', NEW."id", 'name', NEW."name"));
+    ELSE
+        RETURN NULL;
+    END IF;"#
+        ));
+        assert!(!source.contains("'UPDATE'"));
+        assert!(!source.contains("'DELETE'"));
+    }
+
+    #[cfg_attr(not(target_family = "wasm"), tokio::test)]
+    #[cfg_attr(target_family = "wasm", wasm_bindgen_test::wasm_bindgen_test)]
+    async fn installs_missing_triggers() {
+        let subsystem = create_postgres_system_from_str(MODEL).await;
+        let (venue, webhook) = entity_with_webhook(&subsystem, "Venue");
+
+        let statements = trigger_statements(&subsystem, None, &HashMap::new());
+
+        assert_eq!(statements.len(), 4);
+        for expected in [
+            format!(
+                r#"CREATE OR REPLACE FUNCTION "exograph_webhook_concerts"() RETURNS trigger AS $exograph${CONCERT_SOURCE}$exograph$ LANGUAGE plpgsql;"#
+            ),
+            r#"CREATE TRIGGER "exograph_webhook" AFTER INSERT OR UPDATE OR DELETE ON "concerts" FOR EACH ROW EXECUTE FUNCTION "exograph_webhook_concerts"();"#.to_string(),
+            format!(
+                r#"CREATE OR REPLACE FUNCTION "exograph_webhook_venues"() RETURNS trigger AS $exograph${}$exograph$ LANGUAGE plpgsql;"#,
+                function_source(venue, webhook, &subsystem)
+            ),
+            r#"CREATE TRIGGER "exograph_webhook" AFTER INSERT OR UPDATE OR DELETE ON "venues" FOR EACH ROW EXECUTE FUNCTION "exograph_webhook_venues"();"#.to_string(),
+        ] {
+            assert!(statements.contains(&expected), "missing statement: {expected}");
+        }
+
+        // The function must exist before the trigger that executes it
+        let position = |prefix: &str| statements.iter().position(|s| s.starts_with(prefix));
+        assert!(
+            position(r#"CREATE OR REPLACE FUNCTION "exograph_webhook_concerts""#)
+                < position(
+                    r#"CREATE TRIGGER "exograph_webhook" AFTER INSERT OR UPDATE OR DELETE ON "concerts""#
+                )
+        );
+
+        // Nothing for the entities of other databases
+        assert!(
+            trigger_statements(&subsystem, Some("OTHER_DATABASE_URL"), &HashMap::new()).is_empty()
+        );
+    }
+
+    #[cfg_attr(not(target_family = "wasm"), tokio::test)]
+    #[cfg_attr(target_family = "wasm", wasm_bindgen_test::wasm_bindgen_test)]
+    async fn updates_outdated_and_removes_stale_triggers() {
+        let subsystem = create_postgres_system_from_str(MODEL).await;
+        let (venue, webhook) = entity_with_webhook(&subsystem, "Venue");
+
+        let installed = HashMap::from([
+            // Up to date
+            (
+                PhysicalTableName::new("venues", None),
+                InstalledTrigger {
+                    function_name: r#""exograph_webhook_venues""#.to_string(),
+                    function_source: function_source(venue, webhook, &subsystem),
+                },
+            ),
+            // Outdated
+            (
+                PhysicalTableName::new("concerts", None),
+                InstalledTrigger {
+                    function_name: r#""exograph_webhook_concerts""#.to_string(),
+                    function_source: "BEGIN RETURN NULL; END".to_string(),
+                },
+            ),
+            // The entity no longer has a webhook
+            (
+                PhysicalTableName::new("artists", None),
+                InstalledTrigger {
+                    function_name: r#""exograph_webhook_artists""#.to_string(),
+                    function_source: "BEGIN RETURN NULL; END".to_string(),
+                },
+            ),
+            // Not part of the model
+            (
+                PhysicalTableName::new("legacy", None),
+                InstalledTrigger {
+                    function_name: r#""exograph_webhook_legacy""#.to_string(),
+                    function_source: "BEGIN RETURN NULL; END".to_string(),
+                },
+            ),
+        ]);
+
+        let mut statements = trigger_statements(&subsystem, None, &installed);
+        statements.sort();
+
+        assert_eq!(
+            statements,
+            vec![
+                format!(
+                    r#"CREATE OR REPLACE FUNCTION "exograph_webhook_concerts"() RETURNS trigger AS $exograph${CONCERT_SOURCE}$exograph$ LANGUAGE plpgsql;"#
+                ),
+                r#"DROP FUNCTION IF EXISTS "exograph_webhook_artists"();"#.to_string(),
+                r#"DROP TRIGGER IF EXISTS "exograph_webhook" ON "artists";"#.to_string(),
+            ]
+        );
+    }
+
+    #[cfg_attr(not(target_family = "wasm"), tokio::test)]
+    #[cfg_attr(target_family = "wasm", wasm_bindgen_test::wasm_bindgen_test)]
+    async fn qualifies_function_with_schema() {
+        let subsystem = create_postgres_system_from_str(
+            r#"
+            @postgres
+            module ConcertModule {
+                @webhook(url=env("CONCERT_WEBHOOK_URL"))
+                @table(schema="events")
+                type Concert {
+                    @pk id: Int = autoIncrement()
+                    title: String
+                }
+            }
+            "#,
+        )
+        .await;
+
+        let statements = trigger_statements(&subsystem, None, &HashMap::new());
+
+        assert!(statements[0]
+            .starts_with(r#"CREATE OR REPLACE FUNCTION "events"."exograph_webhook_concerts"()"#));
+        assert_eq!(
+            statements[1],
+            r#"CREATE TRIGGER "exograph_webhook" AFTER INSERT OR UPDATE OR DELETE ON "events"."concerts" FOR EACH ROW EXECUTE FUNCTION "events"."exograph_webhook_concerts"();"#
+        );
+    }
+
+    fn entity_with_webhook<'a>(
+        subsystem: &'a PostgresSubsystem,
+        name: &str,
+    ) -> (&'a EntityType, &'a Webhook) {
+        subsystem
+            .entity_types
+            .iter()
+            .find_map(|(_, entity)| {
+                (entity.name == name).then(|| (entity, entity.webhook.as_ref().unwrap()))
+            })
+            .unwrap()
+    }
+
+    async fn create_postgres_system_from_str(source: &str) -> PostgresSubsystem {
+        let system = builder::build_system_from_str(
+            source,
+            "index.exo".to_string(),
+            vec![Box::new(
+                postgres_model_builder::PostgresSubsystemBuilder {},
+            )],
+        )
+        .await
+        .unwrap();
+
+        deserialize_postgres_subsystem(system)
+    }
+
+    fn deserialize_postgres_subsystem(system: SerializableSystem) -> PostgresSubsystem {
+        system
+            .subsystems
+            .into_iter()
+            .find_map(|subsystem| {
+                (subsystem.id == "postgres")
+                    .then(|| PostgresSubsystem::deserialize(subsystem.serialized_subsystem))
+            })
+            .unwrap()
+            .unwrap()
+    }
+}
@@ -10,8 +10,9 @@
 use exo_sql::AbstractOperation;
 
 use core_plugin_interface::core_resolver::{
-    context::RequestContext, QueryResponse, QueryResponseBody,
+    context::RequestContext, context_extractor::ContextExtractor, QueryResponse, QueryResponseBody,
 };
+use postgres_model::rls::context_setting_name;
 use postgres_types::FromSqlOwned;
 use tokio_postgres::Row;
 
@@ -20,19 +21,42 @@ use crate::{
     postgres_execution_error::PostgresExecutionError,
 };
 
+/// Execute an operation for the given GraphQL field (`operation_name`)
 pub async fn resolve_operation<'e>(
     op: &AbstractOperation,
+    operation_name: &str,
     subsystem_resolver: &'e PostgresSubsystemResolver,
     request_context: &'e RequestContext<'e>,
 ) -> Result<QueryResponse, PostgresExecutionError> {
+    let rls_settings = rls_settings(subsystem_resolver, request_context).await?;
+
     let ctx = request_context.get_base_context();
     let mut tx = ctx.transaction_holder.try_lock().unwrap();
+    tx.set_local_settings(rls_settings);
+    tx.set_operation_name(operation_name);
+
+    let database = &subsystem_resolver.subsystem.database;
+    let executor = subsystem_resolver.table_executor(op.table_id());
+
+    let result = match subsystem_resolver.read_replica(op, &tx) {
+        Some(replica) => {
+            let result = replica.executor.execute(op, &mut tx, database).await;
 
-    let mut result = subsystem_resolver
-        .executor
-        .execute(op, &mut tx, &subsystem_resolver.subsystem.database)
-        .await
-        .map_err(PostgresExecutionError::Postgres)?;
+            if replica.fall_back(&result) {
+                if let Err(e) = &result {
+                    tracing::warn!(
+                        replica = %replica.executor.name,
+                        "Falling back to the primary database after failing to connect to a read replica: {e}"
+                    );
+                }
+                executor.execute(op, &mut tx, database).await
+            } else {
+                result
+            }
+        }
+        None => executor.execute(op, &mut tx, database).await,
+    };
+    let mut result = result.map_err(PostgresExecutionError::Postgres)?;
 
     let body = if result.len() == 1 {
         let string_result = extractor(result.swap_remove(0))?;
@@ -49,6 +73,36 @@ pub async fn resolve_operation<'e>(
     })
 }
 
+/// The settings holding context values for row-level security policies. We set a missing value
+/// to an empty string (which the policies treat as `NULL`) to override any earlier value in the
+/// transaction.
+async fn rls_settings<'e>(
+    subsystem_resolver: &'e PostgresSubsystemResolver,
+    request_context: &'e RequestContext<'e>,
+) -> Result<Vec<(String, String)>, PostgresExecutionError> {
+    let mut settings = Vec::with_capacity(subsystem_resolver.rls_context_selections.len());
+
+    for selection in subsystem_resolver.rls_context_selections.iter() {
+        let value = subsystem_resolver
+            .subsystem
+            .extract_context_selection(request_context, selection)
+            .await?;
+
+        let value = match value {
+            Some(value) => value
+                .clone()
+                .into_json()
+                .map_err(|e| PostgresExecutionError::Generic(e.to_string()))?
+                .to_string(),
+            None => String::new(),
+        };
+
+        settings.push((context_setting_name(selection), value));
+    }
+
+    Ok(settings)
+}
+
 fn extractor<T: FromSqlOwned>(row: Row) -> Result<T, PostgresExecutionError> {
     match row.try_get(0) {
         Ok(col) => Ok(col),
@@ -16,25 +16,37 @@
 //! This module differs from Deno/Wasm in that it has an additional primitive expression type,
 //! `ColumnPath`, which we process into a predicate that we can pass to the database query.
 
+use async_recursion::async_recursion;
 use async_trait::async_trait;
 use core_plugin_interface::{
-    core_model::access::AccessRelationalOp,
+    core_model::{
+        access::{
+            AccessLogicalExpression, AccessPredicateExpression, AccessRelationalOp, ArithmeticOp,
+        },
+        mapped_arena::SerializableSlabIndex,
+    },
     core_resolver::{
         access_solver::{
-            eq_values, gt_values, gte_values, in_values, lt_values, lte_values, neq_values,
-            reduce_common_primitive_expression, AccessPredicate, AccessSolver, AccessSolverError,
+            arithmetic_values, contains_values, eq_values, gt_values, gte_values, in_values,
+            lt_values, lte_values, neq_values, reduce_common_primitive_expression, AccessPredicate,
+            AccessSolver, AccessSolverError,
         },
         context::RequestContext,
         value::Val,
     },
 };
-use exo_sql::{AbstractPredicate, ColumnPath, PhysicalColumnPath, SQLParamContainer};
+use exo_sql::{
+    AbstractPredicate, ArithmeticOperator, CaseSensitivity, ColumnPath, Database,
+    PhysicalColumnPath, SQLParamContainer,
+};
+use indexmap::IndexMap;
 use postgres_model::{
     access::{DatabaseAccessPrimitiveExpression, InputAccessPrimitiveExpression},
     subsystem::PostgresSubsystem,
+    types::EntityType,
 };
 
-use crate::cast;
+use crate::{cast, predicate_mapper::compute_predicate, util::Arguments};
 
 // Only to get around the orphan rule while implementing AccessSolver
 #[derive(Debug)]
@@ -68,12 +80,21 @@ impl<'a> AccessPredicate<'a> for AbstractPredicateWrapper {
 pub enum SolvedPrimitiveExpression {
     Common(Option<Val>),
     Column(PhysicalColumnPath),
+    /// A computed expression such as `capacity - 10` or the number of related rows. Literals
+    /// combined with the expression get the type of the column (if any), otherwise (as for counts)
+    /// they are 64-bit integers.
+    Expression(ColumnPath, Option<PhysicalColumnPath>),
+    /// A filter returned by a `check(...)` function
+    Residual(AbstractPredicate),
 }
 
 #[derive(Debug)]
 pub enum SolvedJsonPrimitiveExpression {
     Common(Option<Val>),
     Path(Vec<String>),
+    /// A value computed from the input such as the number of elements in a list (`None` if the
+    /// input doesn't provide the necessary values)
+    Computed(Option<Val>),
 }
 
 #[async_trait]
@@ -86,27 +107,6 @@ impl<'a> AccessSolver<'a, DatabaseAccessPrimitiveExpression, AbstractPredicateWr
         _input_context: Option<&'a Val>,
         op: &AccessRelationalOp<DatabaseAccessPrimitiveExpression>,
     ) -> Result<Option<AbstractPredicateWrapper>, AccessSolverError> {
-        async fn reduce_primitive_expression<'a>(
-            solver: &PostgresSubsystem,
-            request_context: &'a RequestContext<'a>,
-            expr: &'a DatabaseAccessPrimitiveExpression,
-        ) -> Result<Option<SolvedPrimitiveExpression>, AccessSolverError> {
-            Ok(match expr {
-                DatabaseAccessPrimitiveExpression::Common(expr) => {
-                    let primitive_expr =
-                        reduce_common_primitive_expression(solver, request_context, expr).await?;
-                    Some(SolvedPrimitiveExpression::Common(primitive_expr))
-                }
-                DatabaseAccessPrimitiveExpression::Column(column_path, _) => {
-                    Some(SolvedPrimitiveExpression::Column(column_path.clone()))
-                }
-                DatabaseAccessPrimitiveExpression::Function(_, _) => {
-                    // TODO: Fix this through better types
-                    unreachable!("Function calls should not remain in the resolver expression")
-                }
-            })
-        }
-
         let (left, right) = op.sides();
         let left = reduce_primitive_expression(self, request_context, left).await?;
         let right = reduce_primitive_expression(self, request_context, right).await?;
@@ -116,105 +116,377 @@ impl<'a> AccessSolver<'a, DatabaseAccessPrimitiveExpression, AbstractPredicateWr
             _ => return Ok(None), // If either side is None, we can't produce a predicate
         };
 
+        let (left, right) = match (left, right) {
+            (SolvedPrimitiveExpression::Residual(predicate), other)
+            | (other, SolvedPrimitiveExpression::Residual(predicate)) => {
+                return solve_residual(op, predicate, other)
+                    .map(|predicate| predicate.map(AbstractPredicateWrapper));
+            }
+            sides => sides,
+        };
+
         type ColumnPredicateFn = fn(ColumnPath, ColumnPath) -> AbstractPredicate;
         type ValuePredicateFn = fn(Val, Val) -> AbstractPredicate;
 
-        let helper = |column_predicate: ColumnPredicateFn,
+        let database = &self.database;
+
+        let helper = |left: SolvedPrimitiveExpression,
+                      right: SolvedPrimitiveExpression,
+                      column_predicate: ColumnPredicateFn,
                       value_predicate: ValuePredicateFn|
          -> Result<Option<AbstractPredicate>, AccessSolverError> {
             match (left, right) {
                 (SolvedPrimitiveExpression::Common(None), _)
                 | (_, SolvedPrimitiveExpression::Common(None)) => Ok(None),
 
-                (
-                    SolvedPrimitiveExpression::Column(left_col),
-                    SolvedPrimitiveExpression::Column(right_col),
-                ) => Ok(Some(column_predicate(
-                    to_column_path(&left_col),
-                    to_column_path(&right_col),
-                ))),
-
                 (
                     SolvedPrimitiveExpression::Common(Some(left_value)),
                     SolvedPrimitiveExpression::Common(Some(right_value)),
                 ) => Ok(Some(value_predicate(left_value, right_value))),
 
-                // The next two need to be handled separately, since we need to pass the left side
-                // and right side to the predicate in the correct order. For example, `age > 18` is
-                // different from `18 > age`.
-                (
-                    SolvedPrimitiveExpression::Common(Some(value)),
-                    SolvedPrimitiveExpression::Column(column),
-                ) => {
-                    let physical_column = column.leaf_column().get_column(&self.database);
-                    Ok(Some(column_predicate(
-                        cast::literal_column_path(&value, physical_column)
-                            .map_err(|_| AccessSolverError::Generic("Invalid literal".into()))?,
-                        to_column_path(&column),
-                    )))
-                }
-
-                (
-                    SolvedPrimitiveExpression::Column(column),
-                    SolvedPrimitiveExpression::Common(Some(value)),
-                ) => {
-                    let physical_column = column.leaf_column().get_column(&self.database);
-
-                    Ok(Some(column_predicate(
-                        to_column_path(&column),
-                        cast::literal_column_path(&value, physical_column)
-                            .map_err(|_| AccessSolverError::Generic("Invalid literal".into()))?,
-                    )))
-                }
+                // We need to pass the left side and right side to the predicate in the correct
+                // order. For example, `age > 18` is different from `18 > age`.
+                (left, right) => Ok(Some(column_predicate(
+                    sql_operand(&left, &right, database)?,
+                    sql_operand(&right, &left, database)?,
+                ))),
             }
         };
 
         let access_predicate = match op {
-            AccessRelationalOp::Eq(..) => {
-                helper(AbstractPredicate::eq, |left_value, right_value| {
-                    eq_values(&left_value, &right_value).into()
-                })
-            }
-            AccessRelationalOp::Neq(_, _) => {
-                helper(AbstractPredicate::neq, |left_value, right_value| {
-                    neq_values(&left_value, &right_value).into()
-                })
-            }
+            AccessRelationalOp::Eq(..) => helper(
+                left,
+                right,
+                AbstractPredicate::eq,
+                |left_value, right_value| eq_values(&left_value, &right_value).into(),
+            ),
+            AccessRelationalOp::Neq(_, _) => helper(
+                left,
+                right,
+                AbstractPredicate::neq,
+                |left_value, right_value| neq_values(&left_value, &right_value).into(),
+            ),
             // For the next four, we could optimize cases where values are comparable, but
             // for now, we generate a predicate and let the database handle it
-            AccessRelationalOp::Lt(_, _) => {
-                helper(AbstractPredicate::Lt, |left_value, right_value| {
+            AccessRelationalOp::Lt(_, _) => helper(
+                left,
+                right,
+                AbstractPredicate::Lt,
+                |left_value, right_value| {
                     AbstractPredicate::Lt(literal_column(left_value), literal_column(right_value))
-                })
-            }
-            AccessRelationalOp::Lte(_, _) => {
-                helper(AbstractPredicate::Lte, |left_value, right_value| {
+                },
+            ),
+            AccessRelationalOp::Lte(_, _) => helper(
+                left,
+                right,
+                AbstractPredicate::Lte,
+                |left_value, right_value| {
                     AbstractPredicate::Lte(literal_column(left_value), literal_column(right_value))
-                })
-            }
-            AccessRelationalOp::Gt(_, _) => {
-                helper(AbstractPredicate::Gt, |left_value, right_value| {
+                },
+            ),
+            AccessRelationalOp::Gt(_, _) => helper(
+                left,
+                right,
+                AbstractPredicate::Gt,
+                |left_value, right_value| {
                     AbstractPredicate::Gt(literal_column(left_value), literal_column(right_value))
-                })
-            }
-            AccessRelationalOp::Gte(_, _) => {
-                helper(AbstractPredicate::Gte, |left_value, right_value| {
+                },
+            ),
+            AccessRelationalOp::Gte(_, _) => helper(
+                left,
+                right,
+                AbstractPredicate::Gte,
+                |left_value, right_value| {
                     AbstractPredicate::Gte(literal_column(left_value), literal_column(right_value))
-                })
-            }
+                },
+            ),
             AccessRelationalOp::In(..) => helper(
+                left,
+                right,
                 AbstractPredicate::In,
                 |left_value, right_value| match right_value {
                     Val::List(values) => values.contains(&left_value).into(),
                     _ => unreachable!("The right side operand of `in` operator must be an array"), // This never happens see relational_op::in_relation_match
                 },
             ),
+            AccessRelationalOp::Contains(..) => solve_contains(left, right, database),
         }?;
 
         Ok(access_predicate.map(AbstractPredicateWrapper))
     }
 }
 
+#[async_recursion]
+async fn reduce_primitive_expression<'a>(
+    solver: &PostgresSubsystem,
+    request_context: &'a RequestContext<'a>,
+    expr: &'a DatabaseAccessPrimitiveExpression,
+) -> Result<Option<SolvedPrimitiveExpression>, AccessSolverError> {
+    Ok(match expr {
+        DatabaseAccessPrimitiveExpression::Common(expr) => {
+            let primitive_expr =
+                reduce_common_primitive_expression(solver, request_context, expr).await?;
+            Some(SolvedPrimitiveExpression::Common(primitive_expr))
+        }
+        DatabaseAccessPrimitiveExpression::Column(column_path, _) => {
+            Some(SolvedPrimitiveExpression::Column(column_path.clone()))
+        }
+        DatabaseAccessPrimitiveExpression::Function(_, _) => {
+            // TODO: Fix this through better types
+            unreachable!("Function calls should not remain in the resolver expression")
+        }
+        DatabaseAccessPrimitiveExpression::Count(relation, filter) => {
+            let predicate = match filter {
+                Some(filter) => {
+                    let predicate: Option<AbstractPredicateWrapper> =
+                        solver.solve(request_context, None, &filter.expr).await?;
+
+                    match predicate {
+                        // `every` counts the elements that fail the filter (see
+                        // `compute_quantifier_expr` in the model builder)
+                        Some(predicate) if filter.name == "every" => {
+                            AbstractPredicate::is_not_true(predicate.0)
+                        }
+                        Some(predicate) => predicate.0,
+                        None => return Ok(None),
+                    }
+                }
+                None => AbstractPredicate::True,
+            };
+
+            Some(SolvedPrimitiveExpression::Expression(
+                ColumnPath::Count(relation.clone(), Box::new(predicate)),
+                None,
+            ))
+        }
+        DatabaseAccessPrimitiveExpression::Check(call, entity_id) => {
+            let mut args = Vec::with_capacity(call.args.len());
+            for arg in call.args.iter() {
+                // The builder ensures that the arguments don't depend on the database rows
+                match reduce_primitive_expression(solver, request_context, arg).await? {
+                    // Pass an unavailable argument (such as a missing context) as `null`, so that
+                    // the function decides how to handle it
+                    Some(SolvedPrimitiveExpression::Common(arg)) => {
+                        args.push(arg.unwrap_or(Val::Null))
+                    }
+                    _ => return Ok(None),
+                }
+            }
+
+            let result = request_context
+                .invoke_access_check(&call.function_name, &args)
+                .await?;
+
+            Some(match result {
+                Val::Object(_) => SolvedPrimitiveExpression::Residual(
+                    check_filter(solver, *entity_id, &result, request_context).await?,
+                ),
+                result => SolvedPrimitiveExpression::Common(Some(result)),
+            })
+        }
+        DatabaseAccessPrimitiveExpression::Arithmetic(left, op, right) => {
+            let left = reduce_primitive_expression(solver, request_context, left).await?;
+            let right = reduce_primitive_expression(solver, request_context, right).await?;
+
+            match (left, right) {
+                (Some(left), Some(right)) => {
+                    Some(solve_arithmetic(left, *op, right, &solver.database)?)
+                }
+                _ => None,
+            }
+        }
+    })
+}
+
+/// Map a filter returned by a `check(...)` function (such as `{id: {in: [1, 2]}}`) to a predicate
+/// in the same way as the `where` argument of the entity's collection query
+async fn check_filter<'a>(
+    solver: &'a PostgresSubsystem,
+    entity_id: SerializableSlabIndex<EntityType>,
+    filter: &'a Val,
+    request_context: &'a RequestContext<'a>,
+) -> Result<AbstractPredicate, AccessSolverError> {
+    let entity = &solver.entity_types[entity_id];
+    let predicate_param = &solver.collection_queries[entity.collection_query]
+        .parameters
+        .predicate_param;
+
+    let arguments: Arguments = IndexMap::from([(predicate_param.name.clone(), filter.clone())]);
+
+    compute_predicate(predicate_param, &arguments, solver, request_context)
+        .await
+        .map_err(|e| AccessSolverError::Generic(Box::new(e)))
+}
+
+/// Solve a comparison with a filter returned by a `check(...)` function. Since we treat
+/// `check(...)` as `check(...) == true`, the result is the filter itself (or its negation).
+fn solve_residual(
+    op: &AccessRelationalOp<DatabaseAccessPrimitiveExpression>,
+    predicate: AbstractPredicate,
+    other: SolvedPrimitiveExpression,
+) -> Result<Option<AbstractPredicate>, AccessSolverError> {
+    let matches = match (op, other) {
+        (_, SolvedPrimitiveExpression::Common(None)) => return Ok(None),
+        (AccessRelationalOp::Eq(..), SolvedPrimitiveExpression::Common(Some(Val::Bool(value)))) => {
+            value
+        }
+        (
+            AccessRelationalOp::Neq(..),
+            SolvedPrimitiveExpression::Common(Some(Val::Bool(value))),
+        ) => !value,
+        _ => {
+            return Err(AccessSolverError::Generic(
+                "A filter returned by `check` can only be compared to a boolean".into(),
+            ))
+        }
+    };
+
+    Ok(Some(if matches { predicate } else { !predicate }))
+}
+
+fn solve_arithmetic(
+    left: SolvedPrimitiveExpression,
+    op: ArithmeticOp,
+    right: SolvedPrimitiveExpression,
+    database: &Database,
+) -> Result<SolvedPrimitiveExpression, AccessSolverError> {
+    // The column that determines the type of the result (`None` for a 64-bit integer)
+    fn typing_column(expr: &SolvedPrimitiveExpression) -> Option<Option<&PhysicalColumnPath>> {
+        match expr {
+            SolvedPrimitiveExpression::Common(_) | SolvedPrimitiveExpression::Residual(_) => None,
+            SolvedPrimitiveExpression::Column(column) => Some(Some(column)),
+            SolvedPrimitiveExpression::Expression(_, column) => Some(column.as_ref()),
+        }
+    }
+
+    Ok(match (left, right) {
+        (SolvedPrimitiveExpression::Common(None), _)
+        | (_, SolvedPrimitiveExpression::Common(None)) => SolvedPrimitiveExpression::Common(None),
+
+        (
+            SolvedPrimitiveExpression::Common(Some(left_value)),
+            SolvedPrimitiveExpression::Common(Some(right_value)),
+        ) => SolvedPrimitiveExpression::Common(arithmetic_values(op, &left_value, &right_value)),
+
+        (left, right) => {
+            let column = match (typing_column(&left), typing_column(&right)) {
+                (Some(None), _) | (_, Some(None)) => None,
+                (Some(Some(column)), _) | (None, Some(Some(column))) => Some(column.clone()),
+                (None, None) => None,
+            };
+
+            SolvedPrimitiveExpression::Expression(
+                ColumnPath::Arithmetic(
+                    Box::new(sql_operand(&left, &right, database)?),
+                    arithmetic_operator(op),
+                    Box::new(sql_operand(&right, &left, database)?),
+                ),
+                column,
+            )
+        }
+    })
+}
+
+/// Solve `contains(haystack, needle)`, where either the haystack is a string field and the needle
+/// a string value, or the haystack is a list value and the needle a field.
+fn solve_contains(
+    haystack: SolvedPrimitiveExpression,
+    needle: SolvedPrimitiveExpression,
+    database: &Database,
+) -> Result<Option<AbstractPredicate>, AccessSolverError> {
+    match (haystack, needle) {
+        (SolvedPrimitiveExpression::Common(None), _)
+        | (_, SolvedPrimitiveExpression::Common(None)) => Ok(None),
+
+        (
+            SolvedPrimitiveExpression::Common(Some(haystack)),
+            SolvedPrimitiveExpression::Common(Some(needle)),
+        ) => Ok(Some(contains_values(&haystack, &needle).into())),
+
+        (haystack, SolvedPrimitiveExpression::Common(Some(Val::String(needle)))) => {
+            Ok(Some(AbstractPredicate::StringLike(
+                sql_operand(
+                    &haystack,
+                    &SolvedPrimitiveExpression::Common(None),
+                    database,
+                )?,
+                ColumnPath::Param(SQLParamContainer::new(format!(
+                    "%{}%",
+                    escape_like_pattern(&needle)
+                ))),
+                CaseSensitivity::Sensitive,
+            )))
+        }
+
+        (haystack @ SolvedPrimitiveExpression::Common(Some(Val::List(_))), needle) => {
+            Ok(Some(AbstractPredicate::In(
+                sql_operand(&needle, &haystack, database)?,
+                sql_operand(&haystack, &needle, database)?,
+            )))
+        }
+
+        _ => Err(AccessSolverError::Generic(
+            "`contains` supports a string field with a string value or a list value with a field"
+                .into(),
+        )),
+    }
+}
+
+/// Convert an operand to a column path. Literals get the type of the other operand (so that, for
+/// example, comparing a timestamp column to `now()` passes a timestamp parameter).
+fn sql_operand(
+    operand: &SolvedPrimitiveExpression,
+    other: &SolvedPrimitiveExpression,
+    database: &Database,
+) -> Result<ColumnPath, AccessSolverError> {
+    match operand {
+        SolvedPrimitiveExpression::Column(column) => Ok(to_column_path(column)),
+        SolvedPrimitiveExpression::Expression(expression, _) => Ok(expression.clone()),
+        SolvedPrimitiveExpression::Residual(_) => Err(AccessSolverError::Generic(
+            "A filter returned by `check` cannot be used as a value".into(),
+        )),
+        SolvedPrimitiveExpression::Common(None) => Ok(ColumnPath::Null),
+        SolvedPrimitiveExpression::Common(Some(value)) => match other {
+            SolvedPrimitiveExpression::Column(column)
+            | SolvedPrimitiveExpression::Expression(_, Some(column)) => {
+                let physical_column = column.leaf_column().get_column(database);
+                cast::literal_column_path(value, physical_column)
+                    .map_err(|_| AccessSolverError::Generic("Invalid literal".into()))
+            }
+            SolvedPrimitiveExpression::Expression(_, None) => match value {
+                Val::Number(number) => number
+                    .as_i64()
+                    .map(|number| ColumnPath::Param(SQLParamContainer::new(number)))
+                    .ok_or_else(|| AccessSolverError::Generic("Invalid literal".into())),
+                _ => Ok(literal_column(value.clone())),
+            },
+            SolvedPrimitiveExpression::Common(_) | SolvedPrimitiveExpression::Residual(_) => {
+                Ok(literal_column(value.clone()))
+            }
+        },
+    }
+}
+
+fn arithmetic_operator(op: ArithmeticOp) -> ArithmeticOperator {
+    match op {
+        ArithmeticOp::Add => ArithmeticOperator::Add,
+        ArithmeticOp::Sub => ArithmeticOperator::Subtract,
+        ArithmeticOp::Mul => ArithmeticOperator::Multiply,
+    }
+}
+
+/// Escape the `LIKE` wildcards (and the escape character) so that a value matches literally
+fn escape_like_pattern(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if matches!(c, '\\' | '%' | '_') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
 #[async_trait]
 impl<'a> AccessSolver<'a, InputAccessPrimitiveExpression, AbstractPredicateWrapper>
     for PostgresSubsystem
@@ -225,29 +497,11 @@ impl<'a> AccessSolver<'a, InputAccessPrimitiveExpression, AbstractPredicateWrapp
         input_context: Option<&'a Val>,
         op: &AccessRelationalOp<InputAccessPrimitiveExpression>,
     ) -> Result<Option<AbstractPredicateWrapper>, AccessSolverError> {
-        async fn reduce_primitive_expression<'a>(
-            solver: &PostgresSubsystem,
-            request_context: &'a RequestContext<'a>,
-            expr: &'a InputAccessPrimitiveExpression,
-        ) -> Result<Option<SolvedJsonPrimitiveExpression>, AccessSolverError> {
-            Ok(match expr {
-                InputAccessPrimitiveExpression::Common(expr) => {
-                    let primitive_expr =
-                        reduce_common_primitive_expression(solver, request_context, expr).await?;
-                    Some(SolvedJsonPrimitiveExpression::Common(primitive_expr))
-                }
-                InputAccessPrimitiveExpression::Path(path, _) => {
-                    Some(SolvedJsonPrimitiveExpression::Path(path.clone()))
-                }
-                InputAccessPrimitiveExpression::Function(_, _) => {
-                    unreachable!("Function calls should not remain in the resolver expression")
-                }
-            })
-        }
-
         let (left, right) = op.sides();
-        let left = reduce_primitive_expression(self, request_context, left).await?;
-        let right = reduce_primitive_expression(self, request_context, right).await?;
+        let left =
+            reduce_input_primitive_expression(self, request_context, input_context, left).await?;
+        let right =
+            reduce_input_primitive_expression(self, request_context, input_context, right).await?;
 
         let (left, right) = match (left, right) {
             (Some(left), Some(right)) => (left, right),
@@ -257,53 +511,18 @@ impl<'a> AccessSolver<'a, InputAccessPrimitiveExpression, AbstractPredicateWrapp
         type ValuePredicateFn = fn(&Val, &Val) -> bool;
 
         let helper = |value_predicate: ValuePredicateFn| -> Option<bool> {
-            match (left, right) {
-                (SolvedJsonPrimitiveExpression::Common(None), _)
-                | (_, SolvedJsonPrimitiveExpression::Common(None)) => None,
-
-                (
-                    SolvedJsonPrimitiveExpression::Path(left_path),
-                    SolvedJsonPrimitiveExpression::Path(right_path),
-                ) => Some(match_paths(
-                    &left_path,
-                    &right_path,
-                    input_context,
-                    value_predicate,
-                )),
-
-                (
-                    SolvedJsonPrimitiveExpression::Common(Some(left_value)),
-                    SolvedJsonPrimitiveExpression::Common(Some(right_value)),
-                ) => Some(value_predicate(&left_value, &right_value)),
-
-                // The next two need to be handled separately, since we need to pass the left side
-                // and right side to the predicate in the correct order. For example, `age > 18` is
-                // different from `18 > age`.
-                (
-                    SolvedJsonPrimitiveExpression::Common(Some(left_value)),
-                    SolvedJsonPrimitiveExpression::Path(right_path),
-                ) => {
-                    let right_value = resolve_value(input_context.unwrap(), &right_path);
-                    // If the user didn't provide a value, we evaluate to true. Since the purpose of
-                    // an input predicate is to enforce an invariant, if the user didn't provide a
-                    // value, the original value will remain unchanged thus keeping the invariant
-                    // intact.
-                    match right_value {
-                        Some(right_value) => Some(value_predicate(&left_value, right_value)),
-                        None => Some(true),
-                    }
-                }
-
-                (
-                    SolvedJsonPrimitiveExpression::Path(left_path),
-                    SolvedJsonPrimitiveExpression::Common(Some(right_value)),
-                ) => {
-                    let left_value = resolve_value(input_context.unwrap(), &left_path);
-                    // See above
-                    match left_value {
-                        Some(left_value) => Some(value_predicate(left_value, &right_value)),
-                        None => Some(true),
-                    }
+            match (
+                operand_value(left, input_context),
+                operand_value(right, input_context),
+            ) {
+                (OperandValue::Unknown, _) | (_, OperandValue::Unknown) => None,
+                // If the user didn't provide a value, we evaluate to true. Since the purpose of
+                // an input predicate is to enforce an invariant, if the user didn't provide a
+                // value, the original value will remain unchanged thus keeping the invariant
+                // intact.
+                (OperandValue::Missing, _) | (_, OperandValue::Missing) => Some(true),
+                (OperandValue::Value(left_value), OperandValue::Value(right_value)) => {
+                    Some(value_predicate(&left_value, &right_value))
                 }
             }
         };
@@ -316,20 +535,247 @@ impl<'a> AccessSolver<'a, InputAccessPrimitiveExpression, AbstractPredicateWrapp
             AccessRelationalOp::Gt(_, _) => helper(gt_values),
             AccessRelationalOp::Gte(_, _) => helper(gte_values),
             AccessRelationalOp::In(..) => helper(in_values),
+            AccessRelationalOp::Contains(..) => helper(contains_values),
         }
         .map(|p| AbstractPredicateWrapper(p.into())))
     }
 }
 
-fn match_paths<'a>(
-    left_path: &'a Vec<String>,
-    right_path: &'a Vec<String>,
+#[async_recursion]
+async fn reduce_input_primitive_expression<'a>(
+    solver: &PostgresSubsystem,
+    request_context: &'a RequestContext<'a>,
     input_context: Option<&'a Val>,
-    match_values: fn(&Val, &Val) -> bool,
-) -> bool {
-    let left_value = resolve_value(input_context.unwrap(), left_path).unwrap();
-    let right_value = resolve_value(input_context.unwrap(), right_path).unwrap();
-    match_values(left_value, right_value)
+    expr: &'a InputAccessPrimitiveExpression,
+) -> Result<Option<SolvedJsonPrimitiveExpression>, AccessSolverError> {
+    Ok(match expr {
+        InputAccessPrimitiveExpression::Common(expr) => {
+            let primitive_expr =
+                reduce_common_primitive_expression(solver, request_context, expr).await?;
+            Some(SolvedJsonPrimitiveExpression::Common(primitive_expr))
+        }
+        InputAccessPrimitiveExpression::Path(path, _) => {
+            Some(SolvedJsonPrimitiveExpression::Path(path.clone()))
+        }
+        InputAccessPrimitiveExpression::Function(_, _) => {
+            unreachable!("Function calls should not remain in the resolver expression")
+        }
+        InputAccessPrimitiveExpression::Count(path, filter) => {
+            let elements = match input_context.and_then(|input| resolve_value(input, path)) {
+                Some(Val::List(elements)) => elements,
+                // The input doesn't set the list (it may not include the field or update it
+                // through nested operations)
+                _ => return Ok(Some(SolvedJsonPrimitiveExpression::Computed(None))),
+            };
+
+            let count = match filter {
+                None => elements.len(),
+                Some(filter) => {
+                    let mut count = 0;
+
+                    for index in 0..elements.len() {
+                        let element_path: Vec<String> = path
+                            .iter()
+                            .cloned()
+                            .chain(std::iter::once(index.to_string()))
+                            .collect();
+                        let element_filter =
+                            element_predicate(&filter.expr, &filter.parameter_name, &element_path);
+
+                        let matched: Option<AbstractPredicateWrapper> = solver
+                            .solve(request_context, input_context, &element_filter)
+                            .await?;
+
+                        let matched = matches!(
+                            matched,
+                            Some(AbstractPredicateWrapper(AbstractPredicate::True))
+                        );
+
+                        // `every` counts the elements that fail the filter
+                        if matched != (filter.name == "every") {
+                            count += 1;
+                        }
+                    }
+
+                    count
+                }
+            };
+
+            Some(SolvedJsonPrimitiveExpression::Computed(Some(Val::Number(
+                (count as i64).into(),
+            ))))
+        }
+        InputAccessPrimitiveExpression::Check(call) => {
+            let mut args = Vec::with_capacity(call.args.len());
+            for arg in call.args.iter() {
+                let arg =
+                    reduce_input_primitive_expression(solver, request_context, input_context, arg)
+                        .await?;
+
+                // Pass a missing context value as `null` (see the database counterpart)
+                if let Some(SolvedJsonPrimitiveExpression::Common(None)) = arg {
+                    args.push(Val::Null);
+                    continue;
+                }
+
+                match arg.map(|arg| operand_value(arg, input_context)) {
+                    Some(OperandValue::Value(arg)) => args.push(arg),
+                    None | Some(OperandValue::Unknown) => {
+                        return Ok(Some(SolvedJsonPrimitiveExpression::Common(None)))
+                    }
+                    // The input doesn't set a field passed to the function, so the invariant
+                    // remains intact (see the comment in `solve_relational_op`)
+                    Some(OperandValue::Missing) => {
+                        return Ok(Some(SolvedJsonPrimitiveExpression::Computed(None)))
+                    }
+                }
+            }
+
+            let result = request_context
+                .invoke_access_check(&call.function_name, &args)
+                .await?;
+
+            if let Val::Object(_) = result {
+                return Err(AccessSolverError::Generic(
+                    format!(
+                        "`{}` returned a filter, which isn't supported in input access rules",
+                        call.function_name
+                    )
+                    .into(),
+                ));
+            }
+
+            Some(SolvedJsonPrimitiveExpression::Computed(Some(result)))
+        }
+        InputAccessPrimitiveExpression::Arithmetic(left, op, right) => {
+            let left =
+                reduce_input_primitive_expression(solver, request_context, input_context, left)
+                    .await?;
+            let right =
+                reduce_input_primitive_expression(solver, request_context, input_context, right)
+                    .await?;
+
+            match (left, right) {
+                (Some(left), Some(right)) => Some(
+                    match (
+                        operand_value(left, input_context),
+                        operand_value(right, input_context),
+                    ) {
+                        (OperandValue::Unknown, _) | (_, OperandValue::Unknown) => {
+                            SolvedJsonPrimitiveExpression::Common(None)
+                        }
+                        (OperandValue::Missing, _) | (_, OperandValue::Missing) => {
+                            SolvedJsonPrimitiveExpression::Computed(None)
+                        }
+                        (OperandValue::Value(left_value), OperandValue::Value(right_value)) => {
+                            match arithmetic_values(*op, &left_value, &right_value) {
+                                Some(value) => SolvedJsonPrimitiveExpression::Computed(Some(value)),
+                                None => SolvedJsonPrimitiveExpression::Common(None),
+                            }
+                        }
+                    },
+                ),
+                _ => None,
+            }
+        }
+    })
+}
+
+/// The value of an operand of an input predicate
+enum OperandValue {
+    /// The value is not available (for example, a context value the request doesn't have)
+    Unknown,
+    /// The input doesn't provide the value
+    Missing,
+    Value(Val),
+}
+
+fn operand_value(
+    operand: SolvedJsonPrimitiveExpression,
+    input_context: Option<&Val>,
+) -> OperandValue {
+    match operand {
+        SolvedJsonPrimitiveExpression::Common(None) => OperandValue::Unknown,
+        SolvedJsonPrimitiveExpression::Common(Some(value))
+        | SolvedJsonPrimitiveExpression::Computed(Some(value)) => OperandValue::Value(value),
+        SolvedJsonPrimitiveExpression::Computed(None) => OperandValue::Missing,
+        SolvedJsonPrimitiveExpression::Path(path) => {
+            match resolve_value(input_context.unwrap(), &path) {
+                Some(value) => OperandValue::Value(value.clone()),
+                None => OperandValue::Missing,
+            }
+        }
+    }
+}
+
+/// Instantiate the filter of a count for an element of the list by prefixing the paths that use
+/// the parameter with the element path. For example, with the `members.1` element, `m.banned`
+/// becomes `members.1.banned`.
+fn element_predicate(
+    expr: &AccessPredicateExpression<InputAccessPrimitiveExpression>,
+    parameter_name: &str,
+    element_path: &[String],
+) -> AccessPredicateExpression<InputAccessPrimitiveExpression> {
+    fn element_primitive(
+        expr: &InputAccessPrimitiveExpression,
+        parameter_name: &str,
+        element_path: &[String],
+    ) -> InputAccessPrimitiveExpression {
+        match expr {
+            InputAccessPrimitiveExpression::Path(path, expr_parameter_name) => {
+                let path = if expr_parameter_name.as_deref() == Some(parameter_name) {
+                    element_path.iter().chain(path).cloned().collect()
+                } else {
+                    path.clone()
+                };
+                InputAccessPrimitiveExpression::Path(path, expr_parameter_name.clone())
+            }
+            InputAccessPrimitiveExpression::Arithmetic(left, op, right) => {
+                InputAccessPrimitiveExpression::Arithmetic(
+                    Box::new(element_primitive(left, parameter_name, element_path)),
+                    *op,
+                    Box::new(element_primitive(right, parameter_name, element_path)),
+                )
+            }
+            InputAccessPrimitiveExpression::Common(common) => {
+                InputAccessPrimitiveExpression::Common(common.clone())
+            }
+            InputAccessPrimitiveExpression::Function(_, _)
+            | InputAccessPrimitiveExpression::Count(_, _)
+            | InputAccessPrimitiveExpression::Check(_) => {
+                unreachable!("The builder rejects function calls inside function calls")
+            }
+        }
+    }
+
+    match expr {
+        AccessPredicateExpression::LogicalOp(op) => {
+            let element = |expr: &AccessPredicateExpression<InputAccessPrimitiveExpression>| {
+                Box::new(element_predicate(expr, parameter_name, element_path))
+            };
+
+            AccessPredicateExpression::LogicalOp(match op {
+                AccessLogicalExpression::Not(p) => AccessLogicalExpression::Not(element(p)),
+                AccessLogicalExpression::And(left, right) => {
+                    AccessLogicalExpression::And(element(left), element(right))
+                }
+                AccessLogicalExpression::Or(left, right) => {
+                    AccessLogicalExpression::Or(element(left), element(right))
+                }
+            })
+        }
+        AccessPredicateExpression::RelationalOp(op) => {
+            let (left, right) = op.sides();
+
+            AccessPredicateExpression::RelationalOp(op.combiner()(
+                Box::new(element_primitive(left, parameter_name, element_path)),
+                Box::new(element_primitive(right, parameter_name, element_path)),
+            ))
+        }
+        AccessPredicateExpression::BooleanLiteral(value) => {
+            AccessPredicateExpression::BooleanLiteral(*value)
+        }
+    }
 }
 
 fn resolve_value<'a>(val: &'a Val, path: &'a Vec<String>) -> Option<&'a Val> {
@@ -339,6 +785,10 @@ fn resolve_value<'a>(val: &'a Val, path: &'a Vec<String>) -> Option<&'a Val> {
             Val::Object(map) => {
                 current = map.get(part)?;
             }
+            // Elements of a list (see `element_predicate`)
+            Val::List(values) => {
+                current = values.get(part.parse::<usize>().ok()?)?;
+            }
             _ => return None,
         }
     }
@@ -373,7 +823,8 @@ mod tests {
     use core_plugin_interface::{
         core_model::{
             access::{
-                AccessLogicalExpression, AccessPredicateExpression, CommonAccessPrimitiveExpression,
+                AccessLogicalExpression, AccessPredicateExpression,
+                CommonAccessPrimitiveExpression, FunctionCall,
             },
             context_type::ContextSelection,
         },
@@ -497,9 +948,11 @@ mod tests {
             TrustedDocuments::all(),
             Schema::new(vec![], vec![], vec![]),
             None.into(),
+            None,
             Box::new(MapEnvironment::from(HashMap::new())),
             10,
             10,
+            None,
         );
 
         TestSystem {
@@ -564,6 +1017,20 @@ mod tests {
             .unwrap_or(AbstractPredicate::False)
     }
 
+    async fn solve_input_access<'a>(
+        expr: &'a AccessPredicateExpression<InputAccessPrimitiveExpression>,
+        request_context: &'a RequestContext<'a>,
+        subsystem: &'a PostgresSubsystem,
+        input: &'a Val,
+    ) -> AbstractPredicate {
+        subsystem
+            .solve(request_context, Some(input), expr)
+            .await
+            .unwrap()
+            .map(|p| p.0)
+            .unwrap_or(AbstractPredicate::False)
+    }
+
     type CompareFn = fn(ColumnPath, ColumnPath) -> AbstractPredicate;
 
     async fn test_relational_op(
@@ -1488,6 +1955,224 @@ mod tests {
         }
     }
 
+    #[cfg_attr(not(target_family = "wasm"), tokio::test)]
+    #[cfg_attr(target_family = "wasm", wasm_bindgen_test::wasm_bindgen_test)]
+    async fn arithmetic_with_columns() {
+        // Scenario: self.owner_id - 1 < self.dept1_id
+
+        let test_system = test_system().await;
+        let TestSystem {
+            system,
+            owner_id_column_path,
+            dept1_id_column_path,
+            test_system_resolver,
+            ..
+        } = &test_system;
+
+        let test_ae = AccessPredicateExpression::RelationalOp(AccessRelationalOp::Lt(
+            Box::new(DatabaseAccessPrimitiveExpression::Arithmetic(
+                Box::new(DatabaseAccessPrimitiveExpression::Column(
+                    owner_id_column_path.clone(),
+                    None,
+                )),
+                ArithmeticOp::Sub,
+                Box::new(DatabaseAccessPrimitiveExpression::Common(
+                    CommonAccessPrimitiveExpression::NumberLiteral(1),
+                )),
+            )),
+            Box::new(DatabaseAccessPrimitiveExpression::Column(
+                dept1_id_column_path.clone(),
+                None,
+            )),
+        ));
+
+        let context = test_request_context(Value::Null, test_system_resolver);
+        let solved_predicate = solve_access(&test_ae, &context, system).await;
+        assert_eq!(
+            solved_predicate,
+            AbstractPredicate::Lt(
+                ColumnPath::Arithmetic(
+                    Box::new(test_system.owner_id_column()),
+                    ArithmeticOperator::Subtract,
+                    Box::new(ColumnPath::Param(SQLParamContainer::new(1i64))),
+                ),
+                test_system.dept1_id_column(),
+            )
+        );
+    }
+
+    #[cfg_attr(not(target_family = "wasm"), tokio::test)]
+    #[cfg_attr(target_family = "wasm", wasm_bindgen_test::wasm_bindgen_test)]
+    async fn contains_context_values() {
+        // Scenario: contains(AccessContext.token1, "admin")
+
+        let TestSystem {
+            system,
+            test_system_resolver,
+            ..
+        } = test_system().await;
+
+        let test_ae = AccessPredicateExpression::RelationalOp(AccessRelationalOp::Contains(
+            context_selection_expr("AccessContext", "token1"),
+            Box::new(DatabaseAccessPrimitiveExpression::Common(
+                CommonAccessPrimitiveExpression::StringLiteral("admin".to_owned()),
+            )),
+        ));
+
+        let context = test_request_context(json!({"token1": "site-admin"}), &test_system_resolver);
+        let solved_predicate = solve_access(&test_ae, &context, &system).await;
+        assert_eq!(solved_predicate, AbstractPredicate::True);
+
+        let context = test_request_context(json!({"token1": "site-user"}), &test_system_resolver);
+        let solved_predicate = solve_access(&test_ae, &context, &system).await;
+        assert_eq!(solved_predicate, AbstractPredicate::False);
+
+        // No token1, so we can't produce a predicate
+        let context = test_request_context(json!({}), &test_system_resolver);
+        let solved_predicate = solve_access(&test_ae, &context, &system).await;
+        assert_eq!(solved_predicate, AbstractPredicate::False);
+    }
+
+    #[test]
+    fn like_pattern_escaping() {
+        assert_eq!(escape_like_pattern("plain"), "plain");
+        assert_eq!(escape_like_pattern("50%_off\\"), "50\\%\\_off\\\\");
+    }
+
+    #[test]
+    fn check_filter_residual() {
+        // Scenario: check("visibleDocs", AuthContext.id) == <true|false>, where the function returned a filter
+
+        fn literal(value: bool) -> Box<DatabaseAccessPrimitiveExpression> {
+            Box::new(DatabaseAccessPrimitiveExpression::Common(
+                CommonAccessPrimitiveExpression::BooleanLiteral(value),
+            ))
+        }
+        let eq = AccessRelationalOp::Eq(literal(true), literal(true));
+        let neq = AccessRelationalOp::Neq(literal(true), literal(true));
+
+        let solve = |op, value: Option<Val>| {
+            solve_residual(
+                op,
+                AbstractPredicate::True,
+                SolvedPrimitiveExpression::Common(value),
+            )
+        };
+
+        assert_eq!(
+            solve(&eq, Some(Val::Bool(true))).unwrap(),
+            Some(AbstractPredicate::True)
+        );
+        assert_eq!(
+            solve(&eq, Some(Val::Bool(false))).unwrap(),
+            Some(AbstractPredicate::False)
+        );
+        assert_eq!(
+            solve(&neq, Some(Val::Bool(true))).unwrap(),
+            Some(AbstractPredicate::False)
+        );
+        assert_eq!(solve(&eq, None).unwrap(), None);
+        assert!(solve(&eq, Some(Val::String("yes".into()))).is_err());
+    }
+
+    #[cfg_attr(not(target_family = "wasm"), tokio::test)]
+    #[cfg_attr(target_family = "wasm", wasm_bindgen_test::wasm_bindgen_test)]
+    async fn input_count_with_filter() {
+        // Scenario: self.tags.count(t => t.banned) == 1 (which is how `none` and `every` are expressed)
+
+        let TestSystem {
+            system,
+            test_system_resolver,
+            ..
+        } = test_system().await;
+
+        let test_ae = AccessPredicateExpression::RelationalOp(AccessRelationalOp::Eq(
+            Box::new(InputAccessPrimitiveExpression::Count(
+                vec!["tags".to_string()],
+                Some(FunctionCall {
+                    name: "none".to_string(),
+                    parameter_name: "t".to_string(),
+                    expr: AccessPredicateExpression::RelationalOp(AccessRelationalOp::Eq(
+                        Box::new(InputAccessPrimitiveExpression::Path(
+                            vec!["banned".to_string()],
+                            Some("t".to_string()),
+                        )),
+                        Box::new(InputAccessPrimitiveExpression::Common(
+                            CommonAccessPrimitiveExpression::BooleanLiteral(true),
+                        )),
+                    )),
+                }),
+            )),
+            Box::new(InputAccessPrimitiveExpression::Common(
+                CommonAccessPrimitiveExpression::NumberLiteral(1),
+            )),
+        ));
+
+        let context = test_request_context(Value::Null, &test_system_resolver);
+
+        let input: Val = json!({"tags": [{"banned": true}, {"banned": false}]}).into();
+        let solved_predicate = solve_input_access(&test_ae, &context, &system, &input).await;
+        assert_eq!(solved_predicate, AbstractPredicate::True);
+
+        let input: Val = json!({"tags": [{"banned": true}, {"banned": true}]}).into();
+        let solved_predicate = solve_input_access(&test_ae, &context, &system, &input).await;
+        assert_eq!(solved_predicate, AbstractPredicate::False);
+
+        // The input doesn't set the tags, so the invariant remains intact
+        let input: Val = json!({}).into();
+        let solved_predicate = solve_input_access(&test_ae, &context, &system, &input).await;
+        assert_eq!(solved_predicate, AbstractPredicate::True);
+    }
+
+    #[cfg_attr(not(target_family = "wasm"), tokio::test)]
+    #[cfg_attr(target_family = "wasm", wasm_bindgen_test::wasm_bindgen_test)]
+    async fn input_every() {
+        // Scenario: self.tags.every(t => t.approved)
+
+        let TestSystem {
+            system,
+            test_system_resolver,
+            ..
+        } = test_system().await;
+
+        let test_ae = AccessPredicateExpression::RelationalOp(AccessRelationalOp::Eq(
+            Box::new(InputAccessPrimitiveExpression::Count(
+                vec!["tags".to_string()],
+                Some(FunctionCall {
+                    name: "every".to_string(),
+                    parameter_name: "t".to_string(),
+                    expr: AccessPredicateExpression::RelationalOp(AccessRelationalOp::Eq(
+                        Box::new(InputAccessPrimitiveExpression::Path(
+                            vec!["approved".to_string()],
+                            Some("t".to_string()),
+                        )),
+                        Box::new(InputAccessPrimitiveExpression::Common(
+                            CommonAccessPrimitiveExpression::BooleanLiteral(true),
+                        )),
+                    )),
+                }),
+            )),
+            Box::new(InputAccessPrimitiveExpression::Common(
+                CommonAccessPrimitiveExpression::NumberLiteral(0),
+            )),
+        ));
+
+        let context = test_request_context(Value::Null, &test_system_resolver);
+
+        let input: Val = json!({"tags": [{"approved": true}, {"approved": true}]}).into();
+        let solved_predicate = solve_input_access(&test_ae, &context, &system, &input).await;
+        assert_eq!(solved_predicate, AbstractPredicate::True);
+
+        let input: Val = json!({"tags": [{"approved": true}, {"approved": false}]}).into();
+        let solved_predicate = solve_input_access(&test_ae, &context, &system, &input).await;
+        assert_eq!(solved_predicate, AbstractPredicate::False);
+
+        // A tag without `approved` doesn't satisfy the filter
+        let input: Val = json!({"tags": [{"approved": true}, {}]}).into();
+        let solved_predicate = solve_input_access(&test_ae, &context, &system, &input).await;
+        assert_eq!(solved_predicate, AbstractPredicate::False);
+    }
+
     fn test_request_context(
         test_values: Value,
         system_resolver: &SystemResolver,
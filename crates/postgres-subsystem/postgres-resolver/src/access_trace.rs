@@ -0,0 +1,218 @@
+// Copyright Exograph, Inc. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file at the root of this repository.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use core_plugin_interface::{
+    core_model::access::AccessPredicateExpression,
+    core_resolver::{
+        access_trace::{AccessDecision, AccessRule, AccessTraceEntry},
+        context::RequestContext,
+    },
+};
+use exo_sql::{
+    AbstractPredicate, ArithmeticOperator, CaseSensitivity, ColumnId, ColumnPath, ColumnPathLink,
+    Database, PhysicalColumnPath,
+};
+use postgres_model::{subsystem::PostgresSubsystem, types::EntityType};
+
+/// The rules that [`record_access`] may record: each entity's rules and the fields' own rules
+/// (fields without a rule of their own are not recorded)
+pub(crate) fn access_rules(subsystem: &PostgresSubsystem) -> Vec<AccessRule> {
+    fn is_trivial<P: Send + Sync>(expr: &AccessPredicateExpression<P>) -> bool {
+        matches!(expr, AccessPredicateExpression::BooleanLiteral(true))
+    }
+
+    let rule = |rule: String, operation: &str| AccessRule {
+        rule,
+        operation: operation.to_string(),
+    };
+
+    subsystem
+        .entity_types
+        .iter()
+        .flat_map(|(_, entity_type)| {
+            let entity_rules = ["query", "create", "update", "delete"]
+                .into_iter()
+                .map(|operation| rule(entity_type.name.clone(), operation));
+
+            let field_rules = entity_type.fields.iter().flat_map(|field| {
+                let field_rule = || field_rule_name(entity_type, &field.name);
+
+                [
+                    (
+                        "query",
+                        is_trivial(&subsystem.database_access_expressions[field.access.read]),
+                    ),
+                    (
+                        "create",
+                        is_trivial(&subsystem.input_access_expressions[field.access.creation]),
+                    ),
+                    (
+                        "update",
+                        is_trivial(&subsystem.input_access_expressions[field.access.update.input]),
+                    ),
+                ]
+                .into_iter()
+                .filter(|(_, trivial)| !trivial)
+                .map(move |(operation, _)| rule(field_rule(), operation))
+            });
+
+            let vector_distance_rules = entity_type
+                .vector_distance_fields
+                .iter()
+                .filter(|field| {
+                    !is_trivial(&subsystem.database_access_expressions[field.access.read])
+                })
+                .map(|field| rule(field_rule_name(entity_type, &field.name), "query"));
+
+            entity_rules
+                .chain(field_rules)
+                .chain(vector_distance_rules)
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+pub(crate) fn field_rule_name(entity_type: &EntityType, field_name: &str) -> String {
+    format!("{}.{field_name}", entity_type.name)
+}
+
+/// Record the outcome of evaluating an access rule (see
+/// [`core_plugin_interface::core_resolver::access_trace`])
+pub(crate) fn record_access(
+    request_context: &RequestContext,
+    database: &Database,
+    rule: String,
+    operation: &str,
+    predicate: &AbstractPredicate,
+) {
+    let (decision, residual) = match predicate {
+        AbstractPredicate::True => (AccessDecision::Allowed, None),
+        AbstractPredicate::False => (AccessDecision::Denied, None),
+        predicate => (
+            AccessDecision::Filtered,
+            Some(describe_predicate(predicate, database)),
+        ),
+    };
+
+    request_context.record_access(AccessTraceEntry {
+        rule,
+        operation: operation.to_string(),
+        decision,
+        residual,
+    });
+}
+
+/// A human-readable form of a predicate such as `(documents.published = true OR
+/// documents.owner_id = 42)`
+fn describe_predicate(predicate: &AbstractPredicate, database: &Database) -> String {
+    let binary = |left: &ColumnPath, operator: &str, right: &ColumnPath| {
+        format!(
+            "{} {operator} {}",
+            describe_column_path(left, database),
+            describe_column_path(right, database)
+        )
+    };
+
+    match predicate {
+        AbstractPredicate::True => "true".to_string(),
+        AbstractPredicate::False => "false".to_string(),
+        AbstractPredicate::Eq(left, right) => binary(left, "=", right),
+        AbstractPredicate::Neq(left, right) => binary(left, "!=", right),
+        AbstractPredicate::Lt(left, right) => binary(left, "<", right),
+        AbstractPredicate::Lte(left, right) => binary(left, "<=", right),
+        AbstractPredicate::Gt(left, right) => binary(left, ">", right),
+        AbstractPredicate::Gte(left, right) => binary(left, ">=", right),
+        AbstractPredicate::In(left, right) => binary(left, "IN", right),
+        AbstractPredicate::StringLike(left, right, CaseSensitivity::Sensitive) => {
+            binary(left, "LIKE", right)
+        }
+        AbstractPredicate::StringLike(left, right, CaseSensitivity::Insensitive) => {
+            binary(left, "ILIKE", right)
+        }
+        AbstractPredicate::StringStartsWith(left, right) => binary(left, "STARTS WITH", right),
+        AbstractPredicate::StringEndsWith(left, right) => binary(left, "ENDS WITH", right),
+        AbstractPredicate::JsonContains(left, right) => binary(left, "@>", right),
+        AbstractPredicate::JsonContainedBy(left, right) => binary(left, "<@", right),
+        AbstractPredicate::JsonMatchKey(left, right) => binary(left, "?", right),
+        AbstractPredicate::JsonMatchAnyKey(left, right) => binary(left, "?|", right),
+        AbstractPredicate::JsonMatchAllKeys(left, right) => binary(left, "?&", right),
+        AbstractPredicate::VectorDistance(left, right, function, comparator, threshold) => {
+            format!(
+                "{function:?}({}, {}) {comparator:?} {}",
+                describe_column_path(left, database),
+                describe_column_path(right, database),
+                describe_column_path(threshold, database)
+            )
+        }
+        AbstractPredicate::And(left, right) => format!(
+            "({} AND {})",
+            describe_predicate(left, database),
+            describe_predicate(right, database)
+        ),
+        AbstractPredicate::Or(left, right) => format!(
+            "({} OR {})",
+            describe_predicate(left, database),
+            describe_predicate(right, database)
+        ),
+        AbstractPredicate::Not(predicate) => {
+            format!("NOT ({})", describe_predicate(predicate, database))
+        }
+        AbstractPredicate::IsNotTrue(predicate) => {
+            format!("({}) IS NOT TRUE", describe_predicate(predicate, database))
+        }
+    }
+}
+
+fn describe_column_path(path: &ColumnPath, database: &Database) -> String {
+    match path {
+        ColumnPath::Physical(path) => describe_physical_column_path(path, database),
+        ColumnPath::Param(param) | ColumnPath::ArrayParam(param) => param.to_string(),
+        ColumnPath::Null => "null".to_string(),
+        ColumnPath::Arithmetic(left, operator, right) => {
+            let operator = match operator {
+                ArithmeticOperator::Add => "+",
+                ArithmeticOperator::Subtract => "-",
+                ArithmeticOperator::Multiply => "*",
+            };
+            format!(
+                "({} {operator} {})",
+                describe_column_path(left, database),
+                describe_column_path(right, database)
+            )
+        }
+        ColumnPath::Count(relation, predicate) => format!(
+            "count({} WHERE {})",
+            describe_column(relation.foreign_column_id, database),
+            describe_predicate(predicate, database)
+        ),
+    }
+}
+
+/// Describe a path such as `concerts.venue_id -> venues.name`
+fn describe_physical_column_path(path: &PhysicalColumnPath, database: &Database) -> String {
+    match path.split_head() {
+        (ColumnPathLink::Relation(relation), Some(tail)) => format!(
+            "{} -> {}",
+            describe_column(relation.self_column_id, database),
+            describe_physical_column_path(&tail, database)
+        ),
+        (link, _) => describe_column(link.self_column_id(), database),
+    }
+}
+
+fn describe_column(column_id: ColumnId, database: &Database) -> String {
+    format!(
+        "{}.{}",
+        database
+            .get_table(column_id.table_id)
+            .name
+            .fully_qualified_name(),
+        column_id.get_column(database).name
+    )
+}
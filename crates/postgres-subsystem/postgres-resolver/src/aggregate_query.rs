@@ -121,6 +121,7 @@ async fn map_field<'content>(
                             SelectionElement::Function(exo_sql::Function::Named {
                                 function_name: subfield.name.to_string(),
                                 column_id: *column_id,
+                                table_alias: None,
                             })
                         };
                         (subfield.output_name(), selection_elem)
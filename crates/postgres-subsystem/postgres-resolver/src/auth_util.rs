@@ -14,7 +14,11 @@ use postgres_model::access::{
 };
 use postgres_model::types::{EntityType, PostgresField};
 
-use crate::{postgres_execution_error::PostgresExecutionError, sql_mapper::SQLOperationKind};
+use crate::{
+    access_trace::{field_rule_name, record_access},
+    postgres_execution_error::PostgresExecutionError,
+    sql_mapper::SQLOperationKind,
+};
 use core_plugin_interface::core_model::access::AccessPredicateExpression;
 use core_plugin_interface::core_resolver::{
     access_solver::AccessSolver, context::RequestContext, validation::field::ValidatedField,
@@ -41,6 +45,13 @@ pub(crate) async fn check_access<'a>(
                     input_context,
                 )
                 .await?;
+                record_access(
+                    request_context,
+                    &subsystem.database,
+                    return_type.name.clone(),
+                    "create",
+                    &access_predicate,
+                );
 
                 // For creation, the access predicate must be `True` (i.e. it must not have any residual
                 // conditions) The `False` case is already handled by the check_access function (by rejecting
@@ -54,6 +65,7 @@ pub(crate) async fn check_access<'a>(
                         subsystem,
                         request_context,
                         |field| field.access.creation,
+                        "create",
                     )
                     .await?;
 
@@ -71,6 +83,13 @@ pub(crate) async fn check_access<'a>(
                     request_context,
                 )
                 .await?;
+                record_access(
+                    request_context,
+                    &subsystem.database,
+                    return_type.name.clone(),
+                    "query",
+                    &entity_access,
+                );
 
                 if entity_access == Predicate::False {
                     // Short circuit this common case
@@ -97,6 +116,13 @@ pub(crate) async fn check_access<'a>(
                     input_context,
                 )
                 .await?;
+                record_access(
+                    request_context,
+                    &subsystem.database,
+                    return_type.name.clone(),
+                    "update",
+                    &entity_access,
+                );
 
                 if entity_access == Predicate::False {
                     // Short circuit this common case
@@ -108,6 +134,7 @@ pub(crate) async fn check_access<'a>(
                         subsystem,
                         request_context,
                         |field| field.access.update.input,
+                        "update",
                     )
                     .await?;
                     if field_access_predicate == AbstractPredicate::False {
@@ -121,12 +148,20 @@ pub(crate) async fn check_access<'a>(
                 }?
             }
             SQLOperationKind::Delete => {
-                check_delete_access(
+                let entity_access = check_delete_access(
                     &subsystem.database_access_expressions[return_type.access.delete],
                     subsystem,
                     request_context,
                 )
-                .await?
+                .await?;
+                record_access(
+                    request_context,
+                    &subsystem.database,
+                    return_type.name.clone(),
+                    "delete",
+                    &entity_access,
+                );
+                entity_access
             }
         }
     };
@@ -183,8 +218,9 @@ async fn check_update_access<'a>(
 
     // Input predicate cannot have a residue (i.e. it must fully evaluated to true or false)
     if input_predicate != AbstractPredicate::True {
-        // Hard failure, no need to proceed to restrict the predicate in SQL
-        return Err(PostgresExecutionError::Authorization);
+        // Hard failure, no need to proceed to restrict the predicate in SQL (the caller rejects the
+        // request)
+        return Ok(AbstractPredicate::False);
     }
 
     // Now compute the database access predicate (the "where" clause to the update statement)
@@ -221,32 +257,31 @@ async fn check_selection_access<'a>(
         .try_fold(
             AbstractPredicate::True,
             |access_predicate, selection_field| async {
-                let postgres_field = return_type.field_by_name(&selection_field.name);
+                let access_expr = match return_type.field_by_name(&selection_field.name) {
+                    Some(postgres_field) => Some(postgres_field.access.read),
+                    None => return_type
+                        .vector_distance_field_by_name(&selection_field.name)
+                        .map(|vector_distance_field| vector_distance_field.access.read),
+                }
+                .map(|access_expr| &subsystem.database_access_expressions[access_expr]);
 
-                let field_access_predicate = match postgres_field {
-                    Some(postgres_field) => {
-                        check_retrieve_access(
-                            &subsystem.database_access_expressions[postgres_field.access.read],
-                            subsystem,
+                let field_access_predicate = match access_expr {
+                    Some(access_expr) => {
+                        let field_access_predicate =
+                            check_retrieve_access(access_expr, subsystem, request_context).await?;
+                        record_field_access(
                             request_context,
-                        )
-                        .await
-                    }
-                    None => {
-                        match return_type.vector_distance_field_by_name(&selection_field.name) {
-                            Some(vector_distance_field) => {
-                                check_retrieve_access(
-                                    &subsystem.database_access_expressions
-                                        [vector_distance_field.access.read],
-                                    subsystem,
-                                    request_context,
-                                )
-                                .await
-                            }
-                            None => Ok(AbstractPredicate::True),
-                        }
+                            subsystem,
+                            return_type,
+                            &selection_field.name,
+                            "query",
+                            access_expr,
+                            &field_access_predicate,
+                        );
+                        field_access_predicate
                     }
-                }?;
+                    None => AbstractPredicate::True,
+                };
 
                 if field_access_predicate == AbstractPredicate::False {
                     Err(PostgresExecutionError::Authorization)
@@ -271,6 +306,7 @@ async fn check_input_access<'a>(
     ) -> SerializableSlabIndex<
         AccessPredicateExpression<InputAccessPrimitiveExpression>,
     >,
+    operation: &str,
 ) -> Result<AbstractPredicate, PostgresExecutionError> {
     match input_context {
         None => Ok(AbstractPredicate::True),
@@ -283,17 +319,28 @@ async fn check_input_access<'a>(
 
                         let field_access_predicate = match postgres_field {
                             Some(postgres_field) => {
-                                check_create_access(
-                                    &subsystem.input_access_expressions
-                                        [field_access(postgres_field)],
+                                let access_expr = &subsystem.input_access_expressions
+                                    [field_access(postgres_field)];
+                                let field_access_predicate = check_create_access(
+                                    access_expr,
                                     subsystem,
                                     request_context,
                                     Some(elem_value),
                                 )
-                                .await
+                                .await?;
+                                record_field_access(
+                                    request_context,
+                                    subsystem,
+                                    return_type,
+                                    elem_name,
+                                    operation,
+                                    access_expr,
+                                    &field_access_predicate,
+                                );
+                                field_access_predicate
                             }
-                            None => Ok(AbstractPredicate::True),
-                        }?;
+                            None => AbstractPredicate::True,
+                        };
 
                         if field_access_predicate == AbstractPredicate::False {
                             Err(PostgresExecutionError::Authorization)
@@ -310,3 +357,25 @@ async fn check_input_access<'a>(
         _ => Ok(AbstractPredicate::True),
     }
 }
+
+/// Record the decision of a field-level rule. Since most fields don't have a rule of their own
+/// (which makes it `true`), we skip such rules to keep the trace focused.
+fn record_field_access<P: Send + Sync>(
+    request_context: &RequestContext,
+    subsystem: &PostgresSubsystem,
+    entity_type: &EntityType,
+    field_name: &str,
+    operation: &str,
+    access_expr: &AccessPredicateExpression<P>,
+    predicate: &AbstractPredicate,
+) {
+    if !matches!(access_expr, AccessPredicateExpression::BooleanLiteral(true)) {
+        record_access(
+            request_context,
+            &subsystem.database,
+            field_rule_name(entity_type, field_name),
+            operation,
+            predicate,
+        );
+    }
+}
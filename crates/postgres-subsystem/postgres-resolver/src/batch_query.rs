@@ -0,0 +1,289 @@
+// Copyright Exograph, Inc. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file at the root of this repository.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! Resolution of several pk or unique queries (typically issued concurrently by module code through
+//! `executeQuery`) with a single select (see `SubsystemResolver::resolve_batch`).
+//!
+//! We select the rows whose key column (the primary key or the unique field) is in the set of the
+//! queries' keys, add the key to each row, and use it to split the rows back per query.
+
+use core_plugin_interface::core_resolver::{
+    context::RequestContext, validation::field::ValidatedField, value::Val, QueryResponse,
+    QueryResponseBody,
+};
+use exo_sql::{
+    AbstractOperation, AbstractPredicate, AliasedSelectionElement, ColumnId, ColumnPath,
+    ColumnPathLink, PhysicalColumnPath, PhysicalColumnType, Selection, SelectionCardinality,
+    SelectionElement,
+};
+use postgres_model::{
+    predicate::{PredicateParameter, PredicateParameterTypeKind},
+    subsystem::PostgresSubsystem,
+};
+use serde_json::{Map, Value};
+
+use crate::{
+    abstract_operation_resolver::resolve_operation, cast::cast_value,
+    plugin::subsystem_resolver::PostgresSubsystemResolver,
+    postgres_execution_error::PostgresExecutionError, postgres_query::compute_select,
+};
+
+/// The field added to each row to hold its key (GraphQL reserves names starting with `__`, so it
+/// can't clash with a selected field)
+const BATCH_KEY_FIELD: &str = "__exo_batch_key";
+
+/// Resolve the queries (for the same field with the same selection) with a single select. Returns
+/// `None` if they aren't pk or unique queries whose key maps directly to a column.
+pub(crate) async fn resolve_batch<'a>(
+    fields: &[&ValidatedField],
+    subsystem_resolver: &'a PostgresSubsystemResolver,
+    request_context: &'a RequestContext<'a>,
+) -> Result<Option<Vec<QueryResponse>>, PostgresExecutionError> {
+    let subsystem = &subsystem_resolver.subsystem;
+
+    let Some(first) = fields.first() else {
+        return Ok(Some(vec![]));
+    };
+    let operation_name = &first.name;
+
+    let (predicate_param, return_type) = match subsystem.pk_queries.get_by_key(operation_name) {
+        Some(query) => (&query.parameters.predicate_param, &query.return_type),
+        None => match subsystem.unique_queries.get_by_key(operation_name) {
+            Some(query) => match &query.parameters.predicate_params[..] {
+                [predicate_param] => (predicate_param, &query.return_type),
+                _ => return Ok(None),
+            },
+            None => return Ok(None),
+        },
+    };
+
+    let Some(key_column_id) = batch_key_column(predicate_param, subsystem) else {
+        return Ok(None);
+    };
+
+    let mut keys = Vec::with_capacity(fields.len());
+    for field in fields {
+        match field.arguments.get(&predicate_param.name) {
+            Some(key @ (Val::Number(_) | Val::String(_))) if field.arguments.len() == 1 => {
+                keys.push(key.clone())
+            }
+            _ => return Ok(None),
+        }
+    }
+
+    let key_column = key_column_id.get_column(&subsystem.database);
+    let Some(keys_param) = cast_value(&Val::List(keys.clone()), &key_column.typ)
+        .map_err(PostgresExecutionError::CastError)?
+    else {
+        return Ok(None);
+    };
+
+    let mut select = compute_select(
+        AbstractPredicate::In(
+            ColumnPath::Physical(PhysicalColumnPath::leaf(key_column_id)),
+            ColumnPath::ArrayParam(keys_param),
+        ),
+        None,
+        None,
+        None,
+        return_type,
+        &first.subfields,
+        subsystem,
+        request_context,
+    )
+    .await?;
+
+    let Selection::Json(mut elements, _) = select.selection else {
+        return Ok(None);
+    };
+    elements.push(AliasedSelectionElement::new(
+        BATCH_KEY_FIELD.to_string(),
+        SelectionElement::Physical(key_column_id),
+    ));
+    select.selection = Selection::Json(elements, SelectionCardinality::Many);
+
+    let response = resolve_operation(
+        &AbstractOperation::Select(select),
+        operation_name,
+        subsystem_resolver,
+        request_context,
+    )
+    .await?;
+
+    let rows: Vec<Map<String, Value>> = match response.body {
+        QueryResponseBody::Raw(Some(body)) => serde_json::from_str::<Option<Vec<_>>>(&body)
+            .map_err(|e| PostgresExecutionError::Generic(e.to_string()))?
+            .unwrap_or_default(),
+        _ => vec![],
+    };
+
+    let rows = split_rows(&keys, rows, &key_column.typ)?;
+
+    Ok(Some(
+        rows.into_iter()
+            .map(|row| QueryResponse {
+                // A missing row means no entity with the key (or one that isn't accessible)
+                body: QueryResponseBody::Raw(row),
+                headers: vec![],
+            })
+            .collect(),
+    ))
+}
+
+/// The row (without the key field) for each key. We match rows to keys by their values cast to the
+/// key column's type (as the select compared them), rather than by their JSON text.
+fn split_rows(
+    keys: &[Val],
+    rows: Vec<Map<String, Value>>,
+    key_type: &PhysicalColumnType,
+) -> Result<Vec<Option<String>>, PostgresExecutionError> {
+    let rows = rows
+        .into_iter()
+        .map(|mut row| {
+            let key = row.remove(BATCH_KEY_FIELD).unwrap_or(Value::Null);
+            let key = cast_value(&Val::from(key), key_type)?;
+            Ok((key, Value::Object(row).to_string()))
+        })
+        .collect::<Result<Vec<_>, PostgresExecutionError>>()?;
+
+    keys.iter()
+        .map(|key| {
+            let key = cast_value(key, key_type)?;
+
+            Ok(rows
+                .iter()
+                .find(|(row_key, _)| key.is_some() && row_key == &key)
+                .map(|(_, row)| row.clone()))
+        })
+        .collect()
+}
+
+/// The column that the parameter compares with its argument, if the argument maps directly to a
+/// column whose values we can match with the arguments (integers and strings)
+fn batch_key_column(
+    predicate_param: &PredicateParameter,
+    subsystem: &PostgresSubsystem,
+) -> Option<ColumnId> {
+    let parameter_type = &subsystem.predicate_types[predicate_param.typ.innermost().type_id];
+
+    match (&parameter_type.kind, &predicate_param.column_path_link) {
+        (PredicateParameterTypeKind::ImplicitEqual, Some(ColumnPathLink::Leaf(column_id))) => {
+            matches!(
+                column_id.get_column(&subsystem.database).typ,
+                PhysicalColumnType::Int { .. } | PhysicalColumnType::String { .. }
+            )
+            .then_some(*column_id)
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use exo_sql::IntBits;
+    use serde_json::json;
+
+    use crate::test_utils::create_postgres_system_from_str;
+
+    #[cfg_attr(not(target_family = "wasm"), tokio::test)]
+    #[cfg_attr(target_family = "wasm", wasm_bindgen_test::wasm_bindgen_test)]
+    async fn key_columns() {
+        let subsystem = create_postgres_system_from_str(
+            r#"
+            @postgres
+            module ConcertModule {
+                @access(true)
+                type Concert {
+                    @pk id: Int = autoIncrement()
+                    @unique title: String
+                    venue: Venue
+                }
+
+                @access(true)
+                type Venue {
+                    @pk id: Uuid = generate_uuid()
+                    name: String
+                    concerts: Set<Concert>?
+                }
+            }
+            "#,
+            "index.exo".to_string(),
+        )
+        .await
+        .unwrap();
+
+        let key_column = |query_name: &str| {
+            let predicate_param = match subsystem.pk_queries.get_by_key(query_name) {
+                Some(query) => &query.parameters.predicate_param,
+                None => {
+                    &subsystem
+                        .unique_queries
+                        .get_by_key(query_name)
+                        .unwrap()
+                        .parameters
+                        .predicate_params[0]
+                }
+            };
+
+            batch_key_column(predicate_param, &subsystem)
+                .map(|column_id| column_id.get_column(&subsystem.database).name.clone())
+        };
+
+        assert_eq!(key_column("concert"), Some("id".to_string()));
+        assert_eq!(key_column("concertByTitle"), Some("title".to_string()));
+        // A UUID may be written in several forms, so we can't match rows to arguments by value
+        assert_eq!(key_column("venue"), None);
+    }
+
+    #[test]
+    fn rows_split_by_key() {
+        let rows =
+            |rows: Value| -> Vec<Map<String, Value>> { serde_json::from_value(rows).unwrap() };
+
+        let int_key = PhysicalColumnType::Int { bits: IntBits::_32 };
+        let keys: Vec<Val> = vec![
+            json!(2).into(),
+            json!(1).into(),
+            json!(3).into(),
+            json!(2).into(),
+        ];
+        let split = split_rows(
+            &keys,
+            rows(json!([
+                {"id": 1, "title": "C1", BATCH_KEY_FIELD: 1},
+                {"id": 2, "title": "C2", BATCH_KEY_FIELD: 2}
+            ])),
+            &int_key,
+        )
+        .unwrap();
+        assert_eq!(
+            split,
+            vec![
+                Some(r#"{"id":2,"title":"C2"}"#.to_string()),
+                Some(r#"{"id":1,"title":"C1"}"#.to_string()),
+                // No row for the key
+                None,
+                // Callers with the same key each get the row
+                Some(r#"{"id":2,"title":"C2"}"#.to_string()),
+            ]
+        );
+
+        let string_key = PhysicalColumnType::String { max_length: None };
+        let keys: Vec<Val> = vec![json!("1").into(), json!("C1").into()];
+        let split = split_rows(
+            &keys,
+            rows(json!([{"id": 1, BATCH_KEY_FIELD: "C1"}])),
+            &string_key,
+        )
+        .unwrap();
+        assert_eq!(split, vec![None, Some(r#"{"id":1}"#.to_string())]);
+    }
+}
@@ -0,0 +1,287 @@
+// Copyright Exograph, Inc. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file at the root of this repository.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! Caching of query responses (see `core_resolver::response_cache`) for entity types with a TTL
+//! (from `@cache`).
+//!
+//! A query may be cached if all the entity types it selects have a TTL and their read access rules
+//! don't call module functions (through `check(...)`). Since predicates (in the arguments and the
+//! access rules) may refer to related entity types, we consider a query to depend on (and a
+//! mutation to change) all the entity types related to its return type, directly or indirectly.
+
+use std::time::Duration;
+
+use core_plugin_interface::{
+    core_model::context_type::ContextSelection,
+    core_resolver::{
+        context::RequestContext, context_extractor::ContextExtractor, response_cache::CachePolicy,
+        validation::field::ValidatedField,
+    },
+};
+use postgres_model::{
+    access::context_selections,
+    relation::PostgresRelation,
+    subsystem::PostgresSubsystem,
+    types::{EntityType, TypeIndex},
+};
+use serde_json::Value;
+
+use crate::{plugin::PostgresSubsystemResolver, postgres_execution_error::PostgresExecutionError};
+
+/// The policy for a query returning the entity type, or `None` if it must not be cached
+pub(crate) async fn cache_policy<'a>(
+    entity_type: &EntityType,
+    field: &ValidatedField,
+    subsystem_resolver: &'a PostgresSubsystemResolver,
+    request_context: &'a RequestContext<'a>,
+) -> Result<Option<CachePolicy>, PostgresExecutionError> {
+    let subsystem = &subsystem_resolver.subsystem;
+
+    let mut ttl = None;
+    // The context values set for row-level security policies also affect the response
+    let mut selections = subsystem_resolver.rls_context_selections.clone();
+
+    if collect_selection(
+        entity_type,
+        &field.subfields,
+        subsystem,
+        &mut ttl,
+        &mut selections,
+    )
+    .is_none()
+    {
+        return Ok(None);
+    }
+
+    let Some(ttl) = ttl else {
+        return Ok(None);
+    };
+
+    let mut context = Vec::with_capacity(selections.len());
+    for selection in selections.iter() {
+        let value = subsystem
+            .extract_context_selection(request_context, selection)
+            .await?;
+
+        context.push(match value {
+            Some(value) => value
+                .clone()
+                .into_json()
+                .map_err(|e| PostgresExecutionError::Generic(e.to_string()))?,
+            None => Value::Null,
+        });
+    }
+
+    Ok(Some(CachePolicy {
+        ttl,
+        entity_types: related_entity_types(entity_type, subsystem),
+        context,
+    }))
+}
+
+/// The names of the entity type and the entity types related to it (directly or indirectly)
+pub(crate) fn related_entity_types(
+    entity_type: &EntityType,
+    subsystem: &PostgresSubsystem,
+) -> Vec<String> {
+    let mut names = vec![entity_type.name.clone()];
+    let mut pending = vec![entity_type];
+
+    while let Some(entity_type) = pending.pop() {
+        for field in entity_type.fields.iter() {
+            let related_type_id = match &field.relation {
+                PostgresRelation::ManyToOne(relation) => {
+                    relation.foreign_pk_field_id.entity_type_id()
+                }
+                PostgresRelation::OneToMany(relation) => relation.foreign_field_id.entity_type_id(),
+                PostgresRelation::Pk { .. } | PostgresRelation::Scalar { .. } => continue,
+            };
+
+            let related_type = &subsystem.entity_types[related_type_id];
+            if !names.contains(&related_type.name) {
+                names.push(related_type.name.clone());
+                pending.push(related_type);
+            }
+        }
+    }
+
+    names
+}
+
+/// Collect the TTL (the shortest of the selected entity types) and the context selections used by
+/// the read access rules of the selection. Returns `None` if the selection must not be cached.
+fn collect_selection(
+    entity_type: &EntityType,
+    subfields: &[ValidatedField],
+    subsystem: &PostgresSubsystem,
+    ttl: &mut Option<Duration>,
+    selections: &mut Vec<ContextSelection>,
+) -> Option<()> {
+    let entity_ttl = entity_type.cache_ttl?;
+    *ttl = Some(ttl.map_or(entity_ttl, |ttl| ttl.min(entity_ttl)));
+
+    selections.extend(context_selections(
+        &subsystem.database_access_expressions[entity_type.access.read],
+    )?);
+
+    for subfield in subfields {
+        if let Some(field) = entity_type.fields.iter().find(|f| f.name == subfield.name) {
+            selections.extend(context_selections(
+                &subsystem.database_access_expressions[field.access.read],
+            )?);
+
+            if let TypeIndex::Composite(type_id) = &field.typ.innermost().type_id {
+                collect_selection(
+                    &subsystem.entity_types[*type_id],
+                    &subfield.subfields,
+                    subsystem,
+                    ttl,
+                    selections,
+                )?;
+            }
+        } else if let Some(field) = entity_type
+            .vector_distance_fields
+            .iter()
+            .find(|f| f.name == subfield.name)
+        {
+            selections.extend(context_selections(
+                &subsystem.database_access_expressions[field.access.read],
+            )?);
+        } else if let Some(field) = entity_type
+            .agg_fields
+            .iter()
+            .find(|f| f.name == subfield.name)
+        {
+            // An aggregate of a related collection (such as `concertsAgg { price { sum } }`),
+            // whose subfields are the fields of the related entity type
+            match &field.relation {
+                Some(PostgresRelation::OneToMany(relation)) => collect_selection(
+                    &subsystem.entity_types[relation.foreign_field_id.entity_type_id()],
+                    &subfield.subfields,
+                    subsystem,
+                    ttl,
+                    selections,
+                )?,
+                _ => return None,
+            }
+        }
+        // Any other field (`__typename`) doesn't depend on the data
+    }
+
+    Some(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::test_utils::create_postgres_system_from_str;
+
+    async fn subsystem() -> PostgresSubsystem {
+        create_postgres_system_from_str(
+            r#"
+            context AuthContext {
+                @jwt role: String
+            }
+
+            @postgres
+            module ConcertModule {
+                @cache(ttl="1min")
+                @access(query=AuthContext.role == "admin", mutation=true)
+                type Concert {
+                    @pk id: Int = autoIncrement()
+                    title: String
+                    venue: Venue
+                }
+
+                @cache(ttl="30s")
+                @access(true)
+                type Venue {
+                    @pk id: Int = autoIncrement()
+                    name: String
+                    concerts: Set<Concert>?
+                }
+
+                @access(true)
+                type Artist {
+                    @pk id: Int = autoIncrement()
+                    name: String
+                }
+            }
+            "#,
+            "index.exo".to_string(),
+        )
+        .await
+        .unwrap()
+    }
+
+    fn entity_type<'a>(subsystem: &'a PostgresSubsystem, name: &str) -> &'a EntityType {
+        subsystem
+            .entity_types
+            .iter()
+            .find(|(_, entity_type)| entity_type.name == name)
+            .unwrap()
+            .1
+    }
+
+    #[cfg_attr(not(target_family = "wasm"), tokio::test)]
+    #[cfg_attr(target_family = "wasm", wasm_bindgen_test::wasm_bindgen_test)]
+    async fn related_types() {
+        let subsystem = subsystem().await;
+
+        let mut concert_related =
+            related_entity_types(entity_type(&subsystem, "Concert"), &subsystem);
+        concert_related.sort();
+        assert_eq!(concert_related, vec!["Concert", "Venue"]);
+
+        let artist_related = related_entity_types(entity_type(&subsystem, "Artist"), &subsystem);
+        assert_eq!(artist_related, vec!["Artist"]);
+    }
+
+    #[cfg_attr(not(target_family = "wasm"), tokio::test)]
+    #[cfg_attr(target_family = "wasm", wasm_bindgen_test::wasm_bindgen_test)]
+    async fn ttl_and_context_selections() {
+        let subsystem = subsystem().await;
+
+        let mut ttl = None;
+        let mut selections = vec![];
+        assert!(collect_selection(
+            entity_type(&subsystem, "Concert"),
+            &[],
+            &subsystem,
+            &mut ttl,
+            &mut selections,
+        )
+        .is_some());
+        assert_eq!(ttl, Some(Duration::from_secs(60)));
+        assert_eq!(selections.len(), 1);
+        assert_eq!(selections[0].context_name, "AuthContext");
+
+        let mut ttl = None;
+        assert!(collect_selection(
+            entity_type(&subsystem, "Venue"),
+            &[],
+            &subsystem,
+            &mut ttl,
+            &mut vec![],
+        )
+        .is_some());
+        assert_eq!(ttl, Some(Duration::from_secs(30)));
+
+        // A type without `@cache` can't be cached
+        assert!(collect_selection(
+            entity_type(&subsystem, "Artist"),
+            &[],
+            &subsystem,
+            &mut None,
+            &mut vec![],
+        )
+        .is_none());
+    }
+}
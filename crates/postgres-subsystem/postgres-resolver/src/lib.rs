@@ -11,8 +11,11 @@ pub use plugin::PostgresSubsystemLoader;
 
 mod abstract_operation_resolver;
 mod access_solver;
+mod access_trace;
 mod aggregate_query;
 mod auth_util;
+mod batch_query;
+mod cache_policy;
 mod cast;
 mod column_path_util;
 mod create_data_param_mapper;
@@ -0,0 +1,111 @@
+// Copyright Exograph, Inc. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file at the root of this repository.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use async_trait::async_trait;
+use core_plugin_interface::core_resolver::api_key::{ApiKeyError, ApiKeyStore};
+use serde_json::{Map, Value};
+use tracing::warn;
+
+use super::PostgresSubsystemResolver;
+
+/// Validate API keys against a table (set through `EXO_API_KEYS_TABLE`). Unlike the job table, the
+/// application owns this table (it may even be part of the model to let administrators manage
+/// keys), so we don't create it. Besides the `key_hash`, `expires_at`, and `last_used_at` columns,
+/// it may have any columns (such as `owner` and `scopes`), which become `@apiKey` context fields.
+#[async_trait]
+impl ApiKeyStore for PostgresSubsystemResolver {
+    async fn lookup(&self, key_hash: &str) -> Result<Option<Map<String, Value>>, ApiKeyError> {
+        let Some(table) = &self.api_key_table else {
+            return Ok(None);
+        };
+
+        let client = self
+            .executor
+            .database_client
+            .get_client()
+            .await
+            .map_err(delegate)?;
+
+        let row = client
+            .query_opt(
+                &format!(
+                    r#"SELECT to_jsonb(k)::text AS "key" FROM {table} k WHERE k."key_hash" = $1 AND (k."expires_at" IS NULL OR k."expires_at" > now())"#
+                ),
+                &[&key_hash],
+            )
+            .await
+            .map_err(delegate)?;
+
+        let Some(row) = row else {
+            return Ok(None);
+        };
+
+        let key: String = row.get("key");
+        let key: Map<String, Value> = serde_json::from_str(&key).map_err(delegate)?;
+
+        // Record the use of the key, but at most once a minute to avoid a write for every request
+        let touch = client
+            .execute(
+                &format!(
+                    r#"UPDATE {table} SET "last_used_at" = now() WHERE "key_hash" = $1 AND ("last_used_at" IS NULL OR "last_used_at" < now() - interval '1 minute')"#
+                ),
+                &[&key_hash],
+            )
+            .await;
+
+        if let Err(e) = touch {
+            warn!("Unable to record the use of an API key: {}", e);
+        }
+
+        Ok(Some(key))
+    }
+}
+
+/// Quote a (possibly schema-qualified) table name such as `auth.api_keys`. Returns `None` if the
+/// name isn't made of valid identifiers.
+pub(super) fn quoted_table_name(name: &str) -> Option<String> {
+    let parts = name
+        .split('.')
+        .map(|part| {
+            let valid = part
+                .chars()
+                .next()
+                .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+                && part.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+
+            valid.then(|| format!("\"{part}\""))
+        })
+        .collect::<Option<Vec<_>>>()?;
+
+    (parts.len() <= 2).then(|| parts.join("."))
+}
+
+fn delegate(e: impl std::error::Error + Send + Sync + 'static) -> ApiKeyError {
+    ApiKeyError::Delegate(Box::new(e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn table_names() {
+        assert_eq!(
+            quoted_table_name("api_keys").as_deref(),
+            Some(r#""api_keys""#)
+        );
+        assert_eq!(
+            quoted_table_name("auth.api_keys").as_deref(),
+            Some(r#""auth"."api_keys""#)
+        );
+        assert_eq!(quoted_table_name("api_keys; DROP TABLE users"), None);
+        assert_eq!(quoted_table_name("a.b.c"), None);
+        assert_eq!(quoted_table_name(""), None);
+    }
+}
@@ -0,0 +1,206 @@
+// Copyright Exograph, Inc. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file at the root of this repository.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::time::Duration;
+
+use async_trait::async_trait;
+use core_plugin_interface::core_resolver::job::{ClaimedJob, EnqueueOptions, JobError, JobQueue};
+use postgres_model::job_queue::DEFAULT_MAX_ATTEMPTS;
+use serde_json::Value;
+
+use super::PostgresSubsystemResolver;
+
+// The job table is created by `exo schema migrate` (see `postgres_model::job_queue`)
+
+const ENQUEUE: &str = r#"INSERT INTO "exograph_jobs" ("name", "arguments", "unique_key", "max_attempts", "run_at")
+    VALUES ($1, $2::text::jsonb, $3, $4, now() + ($5::float8 * interval '1 second'))
+    ON CONFLICT ("unique_key") DO NOTHING
+    RETURNING "id""#;
+
+// Claim the oldest due job that is either not locked or whose lock has expired (its worker
+// presumably died) and that has attempts left. `SKIP LOCKED` lets multiple workers (even across
+// server instances) claim jobs concurrently without blocking each other.
+const CLAIM: &str = r#"UPDATE "exograph_jobs"
+    SET "attempts" = "attempts" + 1,
+        "locked_until" = now() + ($1::float8 * interval '1 second'),
+        "updated_at" = now()
+    WHERE "id" = (
+        SELECT "id" FROM "exograph_jobs"
+        WHERE "status" = 'pending'
+          AND "run_at" <= now()
+          AND ("locked_until" IS NULL OR "locked_until" < now())
+          AND "attempts" < "max_attempts"
+        ORDER BY "run_at"
+        LIMIT 1
+        FOR UPDATE SKIP LOCKED
+    )
+    RETURNING "id", "name", "arguments"::text AS "arguments", "attempts", "max_attempts""#;
+
+// Only while the job is still running (not after it completed or failed)
+const EXTEND_LOCK: &str = r#"UPDATE "exograph_jobs"
+    SET "locked_until" = now() + ($2::float8 * interval '1 second'), "updated_at" = now()
+    WHERE "id" = $1 AND "status" = 'pending' AND "locked_until" IS NOT NULL"#;
+
+// A job whose lock expired on its last attempt (its worker died every time, for example, because
+// the job makes it run out of memory) is marked as failed rather than left pending forever
+const FAIL_EXHAUSTED: &str = r#"UPDATE "exograph_jobs"
+    SET "status" = 'failed',
+        "last_error" = 'The worker running the job stopped on its last attempt',
+        "locked_until" = NULL,
+        "updated_at" = now()
+    WHERE "status" = 'pending'
+      AND "attempts" >= "max_attempts"
+      AND "locked_until" < now()"#;
+
+const COMPLETE: &str = r#"UPDATE "exograph_jobs"
+    SET "status" = 'completed', "locked_until" = NULL, "updated_at" = now()
+    WHERE "id" = $1"#;
+
+const RETRY: &str = r#"UPDATE "exograph_jobs"
+    SET "last_error" = $2,
+        "locked_until" = NULL,
+        "run_at" = now() + ($3::float8 * interval '1 second'),
+        "updated_at" = now()
+    WHERE "id" = $1"#;
+
+const FAIL: &str = r#"UPDATE "exograph_jobs"
+    SET "status" = 'failed', "last_error" = $2, "locked_until" = NULL, "updated_at" = now()
+    WHERE "id" = $1"#;
+
+#[async_trait]
+impl JobQueue for PostgresSubsystemResolver {
+    async fn enqueue(
+        &self,
+        name: &str,
+        arguments: &Value,
+        options: &EnqueueOptions,
+    ) -> Result<Option<i64>, JobError> {
+        let client = self
+            .executor
+            .database_client
+            .get_client()
+            .await
+            .map_err(delegate)?;
+
+        let max_attempts = options
+            .max_attempts
+            .map(|attempts| attempts as i32)
+            .unwrap_or(DEFAULT_MAX_ATTEMPTS);
+        let delay = options.delay.unwrap_or_default().as_secs_f64();
+
+        let row = client
+            .query_opt(
+                ENQUEUE,
+                &[
+                    &name,
+                    &arguments.to_string(),
+                    &options.unique_key,
+                    &max_attempts,
+                    &delay,
+                ],
+            )
+            .await
+            .map_err(delegate)?;
+
+        Ok(row.map(|row| row.get("id")))
+    }
+
+    async fn claim(&self, visibility_timeout: Duration) -> Result<Option<ClaimedJob>, JobError> {
+        let client = self
+            .executor
+            .database_client
+            .get_client()
+            .await
+            .map_err(delegate)?;
+
+        client
+            .execute(FAIL_EXHAUSTED, &[])
+            .await
+            .map_err(delegate)?;
+
+        let row = client
+            .query_opt(CLAIM, &[&visibility_timeout.as_secs_f64()])
+            .await
+            .map_err(delegate)?;
+
+        row.map(|row| {
+            let arguments: String = row.get("arguments");
+            let attempts: i32 = row.get("attempts");
+            let max_attempts: i32 = row.get("max_attempts");
+
+            Ok(ClaimedJob {
+                id: row.get("id"),
+                name: row.get("name"),
+                arguments: serde_json::from_str(&arguments).map_err(delegate)?,
+                attempts: attempts as u32,
+                max_attempts: max_attempts as u32,
+            })
+        })
+        .transpose()
+    }
+
+    async fn extend_lock(&self, id: i64, visibility_timeout: Duration) -> Result<(), JobError> {
+        let client = self
+            .executor
+            .database_client
+            .get_client()
+            .await
+            .map_err(delegate)?;
+
+        client
+            .execute(EXTEND_LOCK, &[&id, &visibility_timeout.as_secs_f64()])
+            .await
+            .map_err(delegate)?;
+
+        Ok(())
+    }
+
+    async fn complete(&self, id: i64) -> Result<(), JobError> {
+        let client = self
+            .executor
+            .database_client
+            .get_client()
+            .await
+            .map_err(delegate)?;
+
+        client.execute(COMPLETE, &[&id]).await.map_err(delegate)?;
+
+        Ok(())
+    }
+
+    async fn fail(
+        &self,
+        id: i64,
+        error: &str,
+        retry_after: Option<Duration>,
+    ) -> Result<(), JobError> {
+        let client = self
+            .executor
+            .database_client
+            .get_client()
+            .await
+            .map_err(delegate)?;
+
+        match retry_after {
+            Some(retry_after) => {
+                client
+                    .execute(RETRY, &[&id, &error, &retry_after.as_secs_f64()])
+                    .await
+            }
+            None => client.execute(FAIL, &[&id, &error]).await,
+        }
+        .map_err(delegate)?;
+
+        Ok(())
+    }
+}
+
+fn delegate(e: impl std::error::Error + Send + Sync + 'static) -> JobError {
+    JobError::Delegate(Box::new(e))
+}
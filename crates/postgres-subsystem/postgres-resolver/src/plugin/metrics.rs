@@ -0,0 +1,96 @@
+// Copyright Exograph, Inc. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file at the root of this repository.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use core_plugin_interface::core_resolver::metrics::{MetricKind, MetricSample};
+use exo_sql::{statement_cache_metrics, DatabaseExecutor};
+
+use super::PostgresSubsystemResolver;
+
+impl PostgresSubsystemResolver {
+    /// The usage of the connection pool of each database (labeled with the env var holding its
+    /// URL) and of the prepared statement caches
+    pub(crate) fn metric_samples(&self) -> Vec<MetricSample> {
+        let executors = std::iter::once(&self.executor)
+            .chain(self.database_executors.values())
+            .chain(self.read_replicas.executors());
+
+        let mut samples: Vec<_> = executors.flat_map(pool_samples).collect();
+
+        let statement_cache = statement_cache_metrics();
+        samples.extend([
+            MetricSample {
+                name: "exograph_postgres_statement_cache_hits_total",
+                help: "Statements executed using a cached prepared statement",
+                kind: MetricKind::Counter,
+                labels: vec![],
+                value: statement_cache.hits as f64,
+            },
+            MetricSample {
+                name: "exograph_postgres_statement_cache_misses_total",
+                help: "Statements that had to be prepared",
+                kind: MetricKind::Counter,
+                labels: vec![],
+                value: statement_cache.misses as f64,
+            },
+            MetricSample {
+                name: "exograph_postgres_statement_cache_evictions_total",
+                help: "Prepared statements evicted from a cache",
+                kind: MetricKind::Counter,
+                labels: vec![],
+                value: statement_cache.evictions as f64,
+            },
+            MetricSample {
+                name: "exograph_postgres_statement_cache_statements",
+                help: "Prepared statements currently cached",
+                kind: MetricKind::Gauge,
+                labels: vec![],
+                value: statement_cache.cached as f64,
+            },
+        ]);
+
+        samples
+    }
+}
+
+fn pool_samples(executor: &DatabaseExecutor) -> Vec<MetricSample> {
+    let Some(status) = executor.database_client.pool_status() else {
+        return vec![];
+    };
+
+    let sample = |name, help, value: usize| MetricSample {
+        name,
+        help,
+        kind: MetricKind::Gauge,
+        labels: vec![("database", executor.name.clone())],
+        value: value as f64,
+    };
+
+    vec![
+        sample(
+            "exograph_postgres_pool_max_connections",
+            "Maximum number of connections in the pool",
+            status.max_size,
+        ),
+        sample(
+            "exograph_postgres_pool_connections",
+            "Open connections in the pool",
+            status.size,
+        ),
+        sample(
+            "exograph_postgres_pool_idle_connections",
+            "Open connections in the pool not in use",
+            status.available,
+        ),
+        sample(
+            "exograph_postgres_pool_waiting_requests",
+            "Requests waiting for a connection from the pool",
+            status.waiting,
+        ),
+    ]
+}
@@ -7,8 +7,13 @@
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
 
+mod api_key_store;
+mod job_queue;
+mod metrics;
+mod read_replicas;
 pub mod subsystem_loader;
 pub mod subsystem_resolver;
+mod webhook;
 
 pub use subsystem_loader::PostgresSubsystemLoader;
 pub use subsystem_resolver::PostgresSubsystemResolver;
@@ -0,0 +1,209 @@
+// Copyright Exograph, Inc. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file at the root of this repository.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! Routing queries to read replicas of the default database (specified through
+//! `EXO_POSTGRES_READ_URL`).
+//!
+//! We pick replicas in a round-robin fashion. A replica that fails to connect is considered
+//! unhealthy and skipped for [`RETRY_INTERVAL`] (after which, the next query routed to it serves as
+//! its health check). Queries that fail to connect to a replica fall back to the primary.
+
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use exo_sql::{database_error::DatabaseError, DatabaseExecutor};
+
+/// How long to skip a replica after failing to connect to it
+const RETRY_INTERVAL: Duration = Duration::from_secs(30);
+
+pub(crate) struct ReadReplica<E = DatabaseExecutor> {
+    pub executor: E,
+    /// Until when to skip this replica (set after failing to connect to it)
+    unhealthy_until: Mutex<Option<Instant>>,
+}
+
+impl<E> ReadReplica<E> {
+    pub fn new(executor: E) -> Self {
+        Self {
+            executor,
+            unhealthy_until: Mutex::new(None),
+        }
+    }
+
+    fn is_healthy(&self, now: Instant) -> bool {
+        match *self.unhealthy_until.lock().unwrap() {
+            Some(until) => now >= until,
+            None => true,
+        }
+    }
+
+    /// Record the result of executing a query on the replica and return whether to fall back to
+    /// the primary (if we failed to connect to the replica, in which case we skip it for a while)
+    pub fn fall_back<T>(&self, result: &Result<T, DatabaseError>) -> bool {
+        match result {
+            Err(e) if e.is_connection_error() => {
+                self.mark_unhealthy();
+                true
+            }
+            _ => {
+                self.mark_healthy();
+                false
+            }
+        }
+    }
+
+    fn mark_unhealthy(&self) {
+        *self.unhealthy_until.lock().unwrap() = Some(Instant::now() + RETRY_INTERVAL);
+    }
+
+    fn mark_healthy(&self) {
+        let mut unhealthy_until = self.unhealthy_until.lock().unwrap();
+        if unhealthy_until.is_some() {
+            *unhealthy_until = None;
+        }
+    }
+}
+
+pub(crate) struct ReadReplicas<E = DatabaseExecutor> {
+    replicas: Vec<ReadReplica<E>>,
+    next: AtomicUsize,
+}
+
+impl<E> ReadReplicas<E> {
+    pub fn new(replicas: Vec<ReadReplica<E>>) -> Self {
+        Self {
+            replicas,
+            next: AtomicUsize::new(0),
+        }
+    }
+
+    /// The executors of all replicas (healthy or not)
+    pub fn executors(&self) -> impl Iterator<Item = &E> {
+        self.replicas.iter().map(|replica| &replica.executor)
+    }
+
+    /// The next healthy replica (if any)
+    pub fn select(&self) -> Option<&ReadReplica<E>> {
+        self.select_at(Instant::now())
+    }
+
+    fn select_at(&self, now: Instant) -> Option<&ReadReplica<E>> {
+        if self.replicas.is_empty() {
+            return None;
+        }
+
+        let start = self.next.fetch_add(1, Ordering::Relaxed);
+
+        (0..self.replicas.len())
+            .map(|offset| &self.replicas[(start + offset) % self.replicas.len()])
+            .find(|replica| replica.is_healthy(now))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn replicas(names: &[&'static str]) -> ReadReplicas<&'static str> {
+        ReadReplicas::new(names.iter().copied().map(ReadReplica::new).collect())
+    }
+
+    fn select_at(replicas: &ReadReplicas<&'static str>, now: Instant) -> Option<&'static str> {
+        replicas.select_at(now).map(|replica| replica.executor)
+    }
+
+    #[test]
+    fn round_robin() {
+        let replicas = replicas(&["a", "b", "c"]);
+        let now = Instant::now();
+
+        let selected: Vec<_> = (0..5).map(|_| select_at(&replicas, now)).collect();
+        assert_eq!(
+            selected,
+            [Some("a"), Some("b"), Some("c"), Some("a"), Some("b")]
+        );
+    }
+
+    #[test]
+    fn no_replicas() {
+        assert!(replicas(&[]).select().is_none());
+    }
+
+    #[test]
+    fn skip_unhealthy_replicas_for_retry_interval() {
+        let replicas = replicas(&["a", "b"]);
+
+        let marked_at = Instant::now();
+        replicas.replicas[0].mark_unhealthy();
+
+        let before_retry = marked_at + RETRY_INTERVAL - Duration::from_secs(1);
+        let selected: Vec<_> = (0..3).map(|_| select_at(&replicas, before_retry)).collect();
+        assert_eq!(selected, [Some("b"), Some("b"), Some("b")]);
+
+        // Once the interval passes, the next query routed to the replica serves as its health check
+        let after_retry = marked_at + RETRY_INTERVAL + Duration::from_secs(1);
+        let selected: Vec<_> = (0..2).map(|_| select_at(&replicas, after_retry)).collect();
+        assert!(selected.contains(&Some("a")));
+    }
+
+    #[test]
+    fn no_healthy_replicas() {
+        let replicas = replicas(&["a"]);
+
+        replicas.replicas[0].mark_unhealthy();
+        assert!(replicas.select().is_none());
+
+        // Succeeding (for example, after the retry interval) makes the replica healthy again
+        assert!(!replicas.replicas[0].fall_back(&Ok(())));
+        assert_eq!(select_at(&replicas, Instant::now()), Some("a"));
+    }
+
+    // Connecting needs the Tokio runtime support of `tokio-postgres`
+    #[cfg(feature = "network")]
+    #[test]
+    fn fall_back_to_primary_only_on_connection_errors() {
+        let replicas = replicas(&["a"]);
+        let replica = &replicas.replicas[0];
+
+        // A query that fails for other reasons (such as a constraint violation) would fail on the
+        // primary as well
+        assert!(
+            !replica.fall_back::<()>(&Err(DatabaseError::Validation("invalid input".to_string())))
+        );
+        assert!(replicas.select().is_some());
+
+        assert!(replica.fall_back::<()>(&Err(
+            connection_error().with_context("While executing a query".to_string())
+        )));
+        assert!(replicas.select().is_none());
+    }
+
+    /// The error from connecting to a port where nothing listens
+    #[cfg(feature = "network")]
+    fn connection_error() -> DatabaseError {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+
+        runtime
+            .block_on(tokio_postgres::connect(
+                "host=127.0.0.1 port=1 user=exo connect_timeout=5",
+                tokio_postgres::NoTls,
+            ))
+            .err()
+            .unwrap()
+            .into()
+    }
+}
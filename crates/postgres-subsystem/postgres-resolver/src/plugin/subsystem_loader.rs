@@ -7,17 +7,31 @@
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
 
-use super::PostgresSubsystemResolver;
+use std::collections::HashMap;
+
+use super::{
+    api_key_store::quoted_table_name,
+    read_replicas::{ReadReplica, ReadReplicas},
+    PostgresSubsystemResolver,
+};
 use async_trait::async_trait;
-use common::env_const::{DATABASE_URL, EXO_POSTGRES_URL};
+use common::env_const::{
+    DATABASE_URL, EXO_API_KEYS_TABLE, EXO_POSTGRES_READ_URL, EXO_POSTGRES_RLS,
+    EXO_POSTGRES_SQL_COMMENTS, EXO_POSTGRES_STATEMENT_CACHE_SIZE, EXO_POSTGRES_STATEMENT_TIMEOUT,
+    EXO_POSTGRES_URL, EXO_SLOW_QUERY_THRESHOLD,
+};
 use core_plugin_interface::{
+    core_model::transaction::parse_duration,
     core_resolver::plugin::SubsystemResolver,
     interface::{SubsystemLoader, SubsystemLoadingError},
     system_serializer::SystemSerializer,
 };
 use exo_env::Environment;
-use exo_sql::{DatabaseClientManager, DatabaseExecutor};
-use postgres_model::subsystem::PostgresSubsystem;
+use exo_sql::{
+    url_with_statement_timeout, DatabaseClientManager, DatabaseExecutor,
+    DEFAULT_STATEMENT_CACHE_SIZE,
+};
+use postgres_model::{rls::RlsPolicies, subsystem::PostgresSubsystem};
 
 pub struct PostgresSubsystemLoader {
     pub existing_client: Option<DatabaseClientManager>,
@@ -36,42 +50,170 @@ impl SubsystemLoader for PostgresSubsystemLoader {
     ) -> Result<Box<dyn SubsystemResolver + Send + Sync>, SubsystemLoadingError> {
         let subsystem = PostgresSubsystem::deserialize(serialized_subsystem)?;
 
+        // Log statements slower than the threshold (see `exo_sql::QueryLog`)
+        let slow_query_threshold = env
+            .get(EXO_SLOW_QUERY_THRESHOLD)
+            .map(|threshold| {
+                parse_duration(&threshold).map_err(|e| {
+                    SubsystemLoadingError::Config(format!(
+                        "Invalid {EXO_SLOW_QUERY_THRESHOLD}: {e}"
+                    ))
+                })
+            })
+            .transpose()?;
+
+        // Add the trace context to statements as a comment (see `exo_sql::QueryLog`)
+        let sql_comments = env.get(EXO_POSTGRES_SQL_COMMENTS).as_deref() == Some("true");
+
         let database_client = if let Some(existing) = self.existing_client.take() {
             existing
         } else {
-            #[cfg(feature = "network")]
-            {
-                let url = env
-                    .get(EXO_POSTGRES_URL)
-                    .or(env.get(DATABASE_URL))
-                    .ok_or_else(|| {
-                        SubsystemLoadingError::Config("Env EXO_POSTGRES_URL not set".to_string())
-                    })?;
-                let pool_size: Option<usize> = env
-                    .get("EXO_CONNECTION_POOL_SIZE")
-                    .and_then(|s| s.parse().ok());
-                let check_connection = env
-                    .get("EXO_CHECK_CONNECTION_ON_STARTUP")
-                    .map(|s| s == "true")
-                    .unwrap_or(true);
-
-                DatabaseClientManager::from_url(&url, check_connection, pool_size)
-                    .await
-                    .map_err(|e| SubsystemLoadingError::BoxedError(Box::new(e)))?
-            }
+            let url = env.get(EXO_POSTGRES_URL).or(env.get(DATABASE_URL));
+            create_database_client(url, EXO_POSTGRES_URL, None, env).await?
+        };
+        let executor = DatabaseExecutor {
+            name: EXO_POSTGRES_URL.to_string(),
+            database_client,
+            slow_query_threshold,
+            sql_comments,
+        };
+
+        // Modules with `@postgres(url=env("..."))` use their own database (and connection pool)
+        let url_envs: Vec<String> = subsystem
+            .database_url_envs()
+            .into_iter()
+            .flatten()
+            .map(str::to_string)
+            .collect();
+        let mut database_executors = HashMap::new();
+        for url_env in url_envs {
+            let database_client =
+                create_database_client(env.get(&url_env), &url_env, None, env).await?;
+            database_executors.insert(
+                url_env.clone(),
+                DatabaseExecutor {
+                    name: url_env,
+                    database_client,
+                    slow_query_threshold,
+                    sql_comments,
+                },
+            );
+        }
 
-            #[cfg(not(feature = "network"))]
+        // Queries may go to the read replicas of the default database (a comma-separated list of
+        // URLs). We don't check the connection on startup, so that an unavailable replica doesn't
+        // prevent starting the server (queries fall back to the primary instead).
+        let mut replicas = vec![];
+        if let Some(read_urls) = env.get(EXO_POSTGRES_READ_URL) {
+            for (index, read_url) in read_urls
+                .split(',')
+                .map(str::trim)
+                .filter(|url| !url.is_empty())
+                .enumerate()
             {
-                let _ = env;
-                panic!("Postgres URL feature is not enabled");
+                let database_client = create_database_client(
+                    Some(read_url.to_string()),
+                    EXO_POSTGRES_READ_URL,
+                    Some(false),
+                    env,
+                )
+                .await?;
+                replicas.push(ReadReplica::new(DatabaseExecutor {
+                    name: format!("{EXO_POSTGRES_READ_URL}[{index}]"),
+                    database_client,
+                    slow_query_threshold,
+                    sql_comments,
+                }));
             }
+        }
+        let read_replicas = ReadReplicas::new(replicas);
+
+        let api_key_table = env
+            .get(EXO_API_KEYS_TABLE)
+            .map(|table| {
+                quoted_table_name(&table).ok_or_else(|| {
+                    SubsystemLoadingError::Config(format!(
+                        "Invalid {EXO_API_KEYS_TABLE} `{table}`. Must be a table name, optionally qualified with a schema"
+                    ))
+                })
+            })
+            .transpose()?;
+
+        // Set the context values that row-level security policies (generated with `exo schema
+        // migrate --rls`) use
+        let rls_context_selections = if env.get(EXO_POSTGRES_RLS).as_deref() == Some("true") {
+            RlsPolicies::from_subsystem(&subsystem)
+                .context_selections()
+                .cloned()
+                .collect()
+        } else {
+            vec![]
         };
-        let executor = DatabaseExecutor { database_client };
 
         Ok(Box::new(PostgresSubsystemResolver {
             id: self.id(),
             subsystem,
             executor,
+            database_executors,
+            read_replicas,
+            api_key_table,
+            rls_context_selections,
         }))
     }
 }
+
+/// Create a client for a database. Unless specified, whether to check the connection on startup
+/// comes from `EXO_CHECK_CONNECTION_ON_STARTUP`. Statements on every connection are limited to
+/// `EXO_POSTGRES_STATEMENT_TIMEOUT` (if set), and each connection caches up to
+/// `EXO_POSTGRES_STATEMENT_CACHE_SIZE` prepared statements (none, if not set).
+async fn create_database_client(
+    url: Option<String>,
+    url_env: &str,
+    check_connection: Option<bool>,
+    env: &dyn Environment,
+) -> Result<DatabaseClientManager, SubsystemLoadingError> {
+    #[cfg(feature = "network")]
+    {
+        let url =
+            url.ok_or_else(|| SubsystemLoadingError::Config(format!("Env {url_env} not set")))?;
+        let pool_size: Option<usize> = env
+            .get("EXO_CONNECTION_POOL_SIZE")
+            .and_then(|s| s.parse().ok());
+        let check_connection = check_connection.unwrap_or_else(|| {
+            env.get("EXO_CHECK_CONNECTION_ON_STARTUP")
+                .map(|s| s == "true")
+                .unwrap_or(true)
+        });
+        let url = match env.get(EXO_POSTGRES_STATEMENT_TIMEOUT) {
+            Some(timeout) => {
+                let timeout = parse_duration(&timeout).map_err(|e| {
+                    SubsystemLoadingError::Config(format!(
+                        "Invalid {EXO_POSTGRES_STATEMENT_TIMEOUT}: {e}"
+                    ))
+                })?;
+                url_with_statement_timeout(&url, timeout)
+                    .map_err(|e| SubsystemLoadingError::BoxedError(Box::new(e)))?
+            }
+            None => url,
+        };
+        let statement_cache_size = match env.get(EXO_POSTGRES_STATEMENT_CACHE_SIZE) {
+            Some(size) => size.parse().map_err(|_| {
+                SubsystemLoadingError::Config(format!(
+                    "Invalid {EXO_POSTGRES_STATEMENT_CACHE_SIZE} `{size}`. Must be a non-negative integer"
+                ))
+            })?,
+            None => DEFAULT_STATEMENT_CACHE_SIZE,
+        };
+
+        DatabaseClientManager::from_url(&url, check_connection, pool_size)
+            .await
+            .map(|manager| manager.with_statement_cache_size(statement_cache_size))
+            .map_err(|e| SubsystemLoadingError::BoxedError(Box::new(e)))
+    }
+
+    #[cfg(not(feature = "network"))]
+    {
+        let _ = (url, url_env, check_connection, env);
+        panic!("Postgres URL feature is not enabled");
+    }
+}
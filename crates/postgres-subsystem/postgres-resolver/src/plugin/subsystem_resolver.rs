@@ -7,29 +7,82 @@
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
 
+use std::collections::HashMap;
+
+use super::read_replicas::{ReadReplica, ReadReplicas};
 use crate::{
-    abstract_operation_resolver::resolve_operation, operation_resolver::OperationResolver,
+    abstract_operation_resolver::resolve_operation, access_trace::access_rules,
+    batch_query::resolve_batch, cache_policy::cache_policy, operation_resolver::OperationResolver,
     postgres_execution_error::PostgresExecutionError,
 };
 use async_graphql_parser::types::{FieldDefinition, OperationType, TypeDefinition};
 use async_trait::async_trait;
 use core_plugin_interface::{
+    core_model::context_type::ContextSelection,
     core_resolver::{
+        access_trace::AccessRule,
+        api_key::ApiKeyStore,
         context::RequestContext,
+        job::{JobDefinition, JobQueue},
+        metrics::MetricSample,
         plugin::{SubsystemResolutionError, SubsystemResolver},
+        response_cache::CachePolicy,
         system_resolver::SystemResolver,
         validation::field::ValidatedField,
         InterceptedOperation, QueryResponse,
     },
     interception::InterceptorIndex,
 };
-use exo_sql::DatabaseExecutor;
+use exo_sql::{AbstractOperation, DatabaseExecutor, TableId, TransactionHolder};
 use postgres_model::subsystem::PostgresSubsystem;
+use serde_json::Value;
 
 pub struct PostgresSubsystemResolver {
     pub id: &'static str,
     pub subsystem: PostgresSubsystem,
+    /// The executor for the default database (which also holds the jobs table)
     pub executor: DatabaseExecutor,
+    /// The executors for the databases specified through `@postgres(url=env("..."))` (keyed by the
+    /// env var with their URL)
+    pub(crate) database_executors: HashMap<String, DatabaseExecutor>,
+    /// Read replicas of the default database (specified through `EXO_POSTGRES_READ_URL`)
+    pub(crate) read_replicas: ReadReplicas,
+    /// The (quoted) table to validate API keys against
+    pub(crate) api_key_table: Option<String>,
+    /// Context values to set for each transaction for row-level security policies (empty unless
+    /// `EXO_POSTGRES_RLS` is enabled)
+    pub(crate) rls_context_selections: Vec<ContextSelection>,
+}
+
+impl PostgresSubsystemResolver {
+    /// The executor for the database that stores a table
+    pub(crate) fn table_executor(&self, table_id: TableId) -> &DatabaseExecutor {
+        self.subsystem
+            .table_database_url_env(table_id)
+            .and_then(|url_env| self.database_executors.get(url_env))
+            .unwrap_or(&self.executor)
+    }
+
+    /// The read replica to execute an operation on (if any). We route only queries on the default
+    /// database, and only if the request doesn't require a transaction (so mutations, and queries
+    /// from custom code that may also mutate data, see the data in the primary).
+    pub(crate) fn read_replica(
+        &self,
+        operation: &AbstractOperation,
+        tx_holder: &TransactionHolder,
+    ) -> Option<&ReadReplica> {
+        let is_query = matches!(operation, AbstractOperation::Select(_));
+        let in_default_database = self
+            .subsystem
+            .table_database_url_env(operation.table_id())
+            .is_none();
+
+        if is_query && in_default_database && !tx_holder.transaction_required() {
+            self.read_replicas.select()
+        } else {
+            None
+        }
+    }
 }
 
 #[async_trait]
@@ -86,13 +139,22 @@ impl SubsystemResolver for PostgresSubsystemResolver {
 
         match operation {
             Some(Ok(operation)) => Ok(Some(
-                resolve_operation(&operation, self, request_context).await?,
+                resolve_operation(&operation, operation_name, self, request_context).await?,
             )),
             Some(Err(e)) => Err(e.into()),
             None => Ok(None),
         }
     }
 
+    async fn resolve_batch<'a>(
+        &'a self,
+        operations: &'a [&'a ValidatedField],
+        request_context: &'a RequestContext<'a>,
+        _system_resolver: &'a SystemResolver,
+    ) -> Result<Option<Vec<QueryResponse>>, SubsystemResolutionError> {
+        Ok(resolve_batch(operations, self, request_context).await?)
+    }
+
     async fn invoke_interceptor<'a>(
         &'a self,
         _interceptor_index: InterceptorIndex,
@@ -103,6 +165,94 @@ impl SubsystemResolver for PostgresSubsystemResolver {
         Err(SubsystemResolutionError::NoInterceptorFound)
     }
 
+    async fn cache_policy<'a>(
+        &'a self,
+        field: &'a ValidatedField,
+        operation_type: OperationType,
+        request_context: &'a RequestContext<'a>,
+    ) -> Result<Option<CachePolicy>, SubsystemResolutionError> {
+        if operation_type != OperationType::Query {
+            return Ok(None);
+        }
+
+        let operation_name = &field.name;
+        let subsystem = &self.subsystem;
+
+        let return_type = subsystem
+            .pk_queries
+            .get_by_key(operation_name)
+            .map(|query| &query.return_type)
+            .or_else(|| {
+                subsystem
+                    .collection_queries
+                    .get_by_key(operation_name)
+                    .map(|query| &query.return_type)
+            })
+            .or_else(|| {
+                subsystem
+                    .unique_queries
+                    .get_by_key(operation_name)
+                    .map(|query| &query.return_type)
+            })
+            .or_else(|| {
+                subsystem
+                    .aggregate_queries
+                    .get_by_key(operation_name)
+                    .map(|query| &query.return_type)
+            });
+
+        match return_type {
+            Some(return_type) => Ok(cache_policy(
+                return_type.typ(&subsystem.entity_types),
+                field,
+                self,
+                request_context,
+            )
+            .await?),
+            None => Ok(None),
+        }
+    }
+
+    fn jobs(&self) -> Vec<JobDefinition> {
+        self.webhook_jobs()
+    }
+
+    async fn invoke_job<'a>(
+        &'a self,
+        name: &'a str,
+        arguments: &'a Value,
+        _request_context: &'a RequestContext<'a>,
+        system_resolver: &'a SystemResolver,
+    ) -> Result<Option<()>, SubsystemResolutionError> {
+        match self
+            .deliver_webhook(name, arguments, system_resolver.env.as_ref())
+            .await
+        {
+            Some(result) => result
+                .map(Some)
+                .map_err(|e| SubsystemResolutionError::UserDisplayError(e.to_string())),
+            None => Ok(None),
+        }
+    }
+
+    fn access_rules(&self) -> Vec<AccessRule> {
+        access_rules(&self.subsystem)
+    }
+
+    async fn metrics(&self) -> Vec<MetricSample> {
+        self.metric_samples()
+    }
+
+    fn job_queue(&self) -> Option<&dyn JobQueue> {
+        Some(self)
+    }
+
+    fn api_key_store(&self) -> Option<&dyn ApiKeyStore> {
+        self.api_key_table
+            .as_ref()
+            .map(|_| self as &dyn ApiKeyStore)
+    }
+
     fn schema_queries(&self) -> Vec<FieldDefinition> {
         self.subsystem.schema_queries()
     }
@@ -0,0 +1,165 @@
+// Copyright Exograph, Inc. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file at the root of this repository.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! Outbound webhooks for entities annotated with `@webhook`.
+//!
+//! The schema migration installs a trigger on the table of each such entity (see
+//! [postgres_model::webhook]). The trigger writes each change as a job (named
+//! `webhook:<entity name>`) to the job queue (see [super::job_queue]). Since the trigger runs as
+//! part of the statement making the change, an event gets recorded if and only if the mutation's
+//! transaction commits. The job runner then picks up these jobs and calls `deliver_webhook`, which
+//! posts the event to the configured URL. A failed delivery gets retried just like any other job.
+
+use core_plugin_interface::core_resolver::job::JobDefinition;
+use exo_env::Environment;
+use hmac::{Hmac, Mac};
+use postgres_model::{
+    types::EntityType,
+    webhook::{job_name, Webhook, WEBHOOK_JOB_PREFIX},
+};
+use serde_json::Value;
+use sha2::Sha256;
+use thiserror::Error;
+
+use super::PostgresSubsystemResolver;
+
+const EXO_WEBHOOK_SECRET: &str = "EXO_WEBHOOK_SECRET";
+
+#[derive(Error, Debug)]
+pub enum WebhookError {
+    #[error("{0}")]
+    Config(String),
+
+    #[cfg(feature = "network")]
+    #[error("Webhook delivery failed: {0}")]
+    Http(#[from] reqwest::Error),
+
+    #[cfg(not(feature = "network"))]
+    #[error("Webhook delivery is not supported in this deployment")]
+    NotSupported,
+}
+
+impl PostgresSubsystemResolver {
+    fn webhooks(&self) -> impl Iterator<Item = (&EntityType, &Webhook)> {
+        self.subsystem
+            .entity_types
+            .iter()
+            .filter_map(|(_, entity)| entity.webhook.as_ref().map(|webhook| (entity, webhook)))
+    }
+
+    pub(crate) fn webhook_jobs(&self) -> Vec<JobDefinition> {
+        self.webhooks()
+            .map(|(entity, _)| JobDefinition {
+                name: job_name(entity),
+                schedule: None,
+                // Only the trigger may record events
+                enqueueable: false,
+            })
+            .collect()
+    }
+
+    /// Deliver a webhook event. Returns `None` if the job is not a webhook job of this subsystem.
+    pub(crate) async fn deliver_webhook(
+        &self,
+        job_name: &str,
+        event: &Value,
+        env: &dyn Environment,
+    ) -> Option<Result<(), WebhookError>> {
+        let entity_name = job_name.strip_prefix(WEBHOOK_JOB_PREFIX)?;
+
+        let webhook = self
+            .subsystem
+            .entity_types
+            .iter()
+            .find(|(_, entity)| entity.name == entity_name)
+            .and_then(|(_, entity)| entity.webhook.as_ref())?;
+
+        Some(deliver(webhook, event, env).await)
+    }
+}
+
+async fn deliver(
+    webhook: &Webhook,
+    event: &Value,
+    env: &dyn Environment,
+) -> Result<(), WebhookError> {
+    let url = env
+        .get(&webhook.url_env)
+        .ok_or_else(|| WebhookError::Config(format!("Env {} not set", webhook.url_env)))?;
+
+    let secret = webhook
+        .secret_env
+        .as_deref()
+        .and_then(|key| env.get(key))
+        .or_else(|| env.get(EXO_WEBHOOK_SECRET))
+        .ok_or_else(|| {
+            WebhookError::Config(format!(
+                "No secret to sign webhook events with. Set {}",
+                webhook.secret_env.as_deref().unwrap_or(EXO_WEBHOOK_SECRET)
+            ))
+        })?;
+
+    let body = event.to_string();
+    let timestamp = chrono::Utc::now().timestamp();
+    let signature = sign(&secret, timestamp, &body);
+    let event_name = event["event"].as_str().unwrap_or_default();
+
+    #[cfg(feature = "network")]
+    {
+        static CLIENT: std::sync::OnceLock<reqwest::Client> = std::sync::OnceLock::new();
+
+        let client = CLIENT.get_or_init(|| {
+            reqwest::Client::builder()
+                .timeout(std::time::Duration::from_secs(30))
+                .build()
+                .expect("Failed to create an HTTP client")
+        });
+
+        client
+            .post(url)
+            .header("content-type", "application/json")
+            .header("x-exograph-event", event_name)
+            .header("x-exograph-timestamp", timestamp.to_string())
+            .header("x-exograph-signature", format!("sha256={signature}"))
+            .body(body)
+            .send()
+            .await?
+            .error_for_status()?;
+
+        Ok(())
+    }
+
+    #[cfg(not(feature = "network"))]
+    {
+        let _ = (url, body, signature, event_name);
+        Err(WebhookError::NotSupported)
+    }
+}
+
+/// Sign the event as `HMAC-SHA256(secret, "<timestamp>.<body>")`. Including the timestamp allows
+/// receivers to reject replayed events.
+fn sign(secret: &str, timestamp: i64, body: &str) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(format!("{timestamp}.{body}").as_bytes());
+    hex::encode(mac.finalize().into_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn signs_timestamp_and_body() {
+        assert_eq!(
+            sign("secret", 1700000000, r#"{"event":"create"}"#),
+            "4fb99875e3a0fb562c3b0cd271bbe4c9c9ac46f0847f122bffa183484be87e63"
+        );
+    }
+}
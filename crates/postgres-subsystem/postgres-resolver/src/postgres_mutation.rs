@@ -14,8 +14,9 @@ use super::{
     util::{find_arg, return_type_info},
 };
 use crate::{
-    create_data_param_mapper::InsertOperation, operation_resolver::OperationResolver,
-    postgres_query::compute_select, predicate_mapper::compute_predicate, sql_mapper::SQLMapper,
+    cache_policy::related_entity_types, create_data_param_mapper::InsertOperation,
+    operation_resolver::OperationResolver, postgres_query::compute_select,
+    predicate_mapper::compute_predicate, sql_mapper::SQLMapper,
     update_data_param_mapper::UpdateOperation,
 };
 use async_trait::async_trait;
@@ -44,6 +45,19 @@ impl OperationResolver for PostgresMutation {
     ) -> Result<AbstractOperation, PostgresExecutionError> {
         let return_type = &self.return_type;
 
+        // Set the options (from the entity's `@transaction`) before executing any of the
+        // mutation's statements
+        request_context
+            .set_transaction_options(&return_type.typ(&subsystem.entity_types).transaction)
+            .await?;
+
+        // Cached responses that depend on the mutated data must be invalidated once the mutation
+        // commits
+        request_context.record_mutation(related_entity_types(
+            return_type.typ(&subsystem.entity_types),
+            subsystem,
+        ));
+
         // Compute a select without any **user-specified** predicate, order-by etc. The surrounding
         // mutation will add an appropriate predicate (for example, an update mutation will add a
         // predicate to restrict the select to only ids that had been updated). We do, however, add
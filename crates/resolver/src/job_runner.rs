@@ -0,0 +1,504 @@
+// Copyright Exograph, Inc. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file at the root of this repository.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! Runs background jobs declared in modules.
+//!
+//! The runner has two parts:
+//! - A scheduler that enqueues each `@cron` job when it is due. The enqueued job carries a unique
+//!   key derived from the scheduled time, so that when multiple server instances run the scheduler,
+//!   only one of them gets to enqueue the job.
+//! - A set of workers that claim due jobs from the queue and run them. A failed job is retried
+//!   (with exponential backoff) until it exhausts its attempts.
+
+use std::{future::Future, sync::Arc, time::Duration};
+
+use chrono::Utc;
+use common::{
+    env_const::{EXO_JOBS, EXO_JOB_CONCURRENCY, EXO_JOB_POLL_INTERVAL, EXO_JOB_VISIBILITY_TIMEOUT},
+    EnvError,
+};
+use core_resolver::{
+    context::RequestContext,
+    job::{ClaimedJob, EnqueueOptions, JobError, JobQueue, JobRequest},
+    system_resolver::{SystemResolutionError, SystemResolver},
+};
+use serde_json::Value;
+use tracing::{error, info, warn};
+
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(60 * 60);
+
+pub struct JobRunner {
+    system_resolver: Arc<SystemResolver>,
+    poll_interval: Duration,
+    visibility_timeout: Duration,
+    concurrency: usize,
+}
+
+impl JobRunner {
+    /// Create a job runner based on the environment.
+    ///
+    /// Returns `None` if running jobs is disabled (`EXO_JOBS=false`) or if the system declares no
+    /// jobs.
+    pub fn from_env(system_resolver: Arc<SystemResolver>) -> Result<Option<Self>, EnvError> {
+        let env = system_resolver.env.as_ref();
+
+        if env.get(EXO_JOBS).as_deref() == Some("false") {
+            return Ok(None);
+        }
+
+        if system_resolver.jobs().is_empty() {
+            return Ok(None);
+        }
+
+        if system_resolver.job_queue().is_none() {
+            warn!("Jobs are declared, but no subsystem offers a job queue (jobs require a Postgres database). Jobs will not run.");
+            return Ok(None);
+        }
+
+        let parse_positive = |key: &'static str, default: u64| -> Result<u64, EnvError> {
+            match env.get(key) {
+                Some(value) => match value.parse::<u64>() {
+                    Ok(value) if value > 0 => Ok(value),
+                    _ => Err(EnvError::InvalidValue {
+                        env_key: key,
+                        env_value: value,
+                        message: "Must be a positive integer".to_string(),
+                    }),
+                },
+                None => Ok(default),
+            }
+        };
+
+        let poll_interval = Duration::from_secs(parse_positive(EXO_JOB_POLL_INTERVAL, 5)?);
+        let visibility_timeout =
+            Duration::from_secs(parse_positive(EXO_JOB_VISIBILITY_TIMEOUT, 300)?);
+        let concurrency = parse_positive(EXO_JOB_CONCURRENCY, 4)? as usize;
+
+        Ok(Some(Self {
+            system_resolver,
+            poll_interval,
+            visibility_timeout,
+            concurrency,
+        }))
+    }
+
+    /// Run the scheduler and workers (until the process exits)
+    pub async fn run(self) {
+        info!(
+            "Starting job runner with {} worker(s) for {} job(s)",
+            self.concurrency,
+            self.system_resolver.jobs().len()
+        );
+
+        let workers = futures::future::join_all((0..self.concurrency).map(|_| self.work()));
+
+        futures::join!(self.schedule(), workers);
+    }
+
+    async fn schedule(&self) {
+        let schedules: Vec<(String, saffron::Cron)> = self
+            .system_resolver
+            .jobs()
+            .into_iter()
+            .filter_map(|job| {
+                let schedule = job.schedule?;
+                match schedule.parse::<saffron::Cron>() {
+                    Ok(cron) => Some((job.name, cron)),
+                    Err(_) => {
+                        // Shouldn't happen, since the builder validates cron expressions
+                        error!(
+                            "Invalid cron expression `{schedule}` for job `{}`",
+                            job.name
+                        );
+                        None
+                    }
+                }
+            })
+            .collect();
+
+        loop {
+            let now = Utc::now();
+
+            let upcoming: Vec<_> = schedules
+                .iter()
+                .filter_map(|(name, cron)| cron.next_after(now).map(|time| (name, time)))
+                .collect();
+
+            let Some(next_time) = upcoming.iter().map(|(_, time)| *time).min() else {
+                return;
+            };
+
+            tokio::time::sleep((next_time - now).to_std().unwrap_or_default()).await;
+
+            for (name, _) in upcoming.iter().filter(|(_, time)| *time == next_time) {
+                let options = EnqueueOptions {
+                    unique_key: Some(format!("cron:{name}:{}", next_time.timestamp())),
+                    ..Default::default()
+                };
+
+                if let Err(e) = self
+                    .system_resolver
+                    .enqueue_job(name, &Value::Object(Default::default()), &options)
+                    .await
+                {
+                    error!("Failed to enqueue scheduled job `{name}`: {e}");
+                }
+            }
+        }
+    }
+
+    async fn work(&self) {
+        let Some(queue) = self.system_resolver.job_queue() else {
+            return;
+        };
+
+        loop {
+            let claimed = claim_and_run(queue, self.visibility_timeout, |job| async move {
+                let result = self.invoke(&job).await;
+                record_outcome(queue, &job, result).await
+            })
+            .await;
+
+            match claimed {
+                Ok(true) => {}
+                Ok(false) => tokio::time::sleep(self.poll_interval).await,
+                Err(e) => {
+                    error!("Failed to claim a job: {e}");
+                    tokio::time::sleep(self.poll_interval).await
+                }
+            }
+        }
+    }
+
+    async fn invoke(&self, job: &ClaimedJob) -> Result<(), SystemResolutionError> {
+        let request = JobRequest;
+        let request_context = RequestContext::new(&request, vec![], &self.system_resolver)
+            .map_err(|e| SystemResolutionError::Delegate(Box::new(e)))?;
+
+        let result = self
+            .system_resolver
+            .invoke_job(&job.name, &job.arguments, &request_context)
+            .await;
+
+        let ctx = request_context.get_base_context();
+        let mut tx_holder = ctx.transaction_holder.try_lock().unwrap();
+
+        let finalize_result = tx_holder.finalize(result.is_ok()).await;
+
+        let mutated_entity_types = request_context.take_mutated_entity_types();
+        if !mutated_entity_types.is_empty() {
+            self.system_resolver
+                .invalidate_cached_responses(&mutated_entity_types)
+                .await;
+        }
+
+        finalize_result
+            .map_err(|e| {
+                SystemResolutionError::Generic(format!("Error while finalizing transaction: {e}"))
+            })
+            .and(result)
+    }
+}
+
+/// Claim the next due job (if any) and run it. Returns whether there was a job to run.
+///
+/// While the job runs, its lock is extended every half of `visibility_timeout`, so that a job that
+/// runs longer than the timeout isn't claimed (and run again) by another worker.
+async fn claim_and_run<F, Fut>(
+    queue: &dyn JobQueue,
+    visibility_timeout: Duration,
+    run: F,
+) -> Result<bool, JobError>
+where
+    F: FnOnce(ClaimedJob) -> Fut,
+    Fut: Future<Output = ()>,
+{
+    match queue.claim(visibility_timeout).await? {
+        Some(job) => {
+            let (id, name) = (job.id, job.name.clone());
+
+            let heartbeat = async {
+                loop {
+                    tokio::time::sleep(visibility_timeout / 2).await;
+                    if let Err(e) = queue.extend_lock(id, visibility_timeout).await {
+                        warn!("Failed to extend the lock of job `{name}` (id {id}): {e}");
+                    }
+                }
+            };
+
+            let run = run(job);
+            futures::pin_mut!(run, heartbeat);
+            futures::future::select(run, heartbeat).await;
+
+            Ok(true)
+        }
+        None => Ok(false),
+    }
+}
+
+/// Mark a job as completed or failed. A failed job is retried (after a backoff) until it exhausts
+/// its attempts, after which it stays failed.
+async fn record_outcome(
+    queue: &dyn JobQueue,
+    job: &ClaimedJob,
+    result: Result<(), SystemResolutionError>,
+) {
+    let update = match result {
+        Ok(()) => queue.complete(job.id).await,
+        Err(e) => {
+            let retry_after = (job.attempts < job.max_attempts).then(|| retry_delay(job.attempts));
+
+            match retry_after {
+                Some(retry_after) => warn!(
+                    "Job `{}` (id {}) failed on attempt {} of {} (retrying in {retry_after:?}): {e}",
+                    job.name, job.id, job.attempts, job.max_attempts
+                ),
+                None => error!(
+                    "Job `{}` (id {}) failed on its final attempt: {e}",
+                    job.name, job.id
+                ),
+            }
+
+            queue.fail(job.id, &e.to_string(), retry_after).await
+        }
+    };
+
+    if let Err(e) = update {
+        error!(
+            "Failed to update the status of job `{}` (id {}): {e}",
+            job.name, job.id
+        );
+    }
+}
+
+/// Exponential backoff: 2s, 4s, 8s, ... (capped at an hour)
+fn retry_delay(attempts: u32) -> Duration {
+    Duration::from_secs(2u64.saturating_pow(attempts)).min(MAX_RETRY_DELAY)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use async_trait::async_trait;
+
+    use super::*;
+
+    #[derive(Debug, PartialEq)]
+    enum Update {
+        ExtendLock(i64, Duration),
+        Complete(i64),
+        Fail {
+            id: i64,
+            error: String,
+            retry_after: Option<Duration>,
+        },
+    }
+
+    /// An in-memory queue that records claims and status updates
+    #[derive(Default)]
+    struct TestQueue {
+        pending: Mutex<Vec<ClaimedJob>>,
+        claim_timeouts: Mutex<Vec<Duration>>,
+        updates: Mutex<Vec<Update>>,
+    }
+
+    impl TestQueue {
+        fn with_jobs(jobs: Vec<ClaimedJob>) -> Self {
+            Self {
+                pending: Mutex::new(jobs),
+                ..Default::default()
+            }
+        }
+    }
+
+    #[async_trait]
+    impl JobQueue for TestQueue {
+        async fn enqueue(
+            &self,
+            _name: &str,
+            _arguments: &Value,
+            _options: &EnqueueOptions,
+        ) -> Result<Option<i64>, JobError> {
+            unimplemented!()
+        }
+
+        async fn claim(
+            &self,
+            visibility_timeout: Duration,
+        ) -> Result<Option<ClaimedJob>, JobError> {
+            self.claim_timeouts.lock().unwrap().push(visibility_timeout);
+            Ok(self.pending.lock().unwrap().pop())
+        }
+
+        async fn extend_lock(&self, id: i64, visibility_timeout: Duration) -> Result<(), JobError> {
+            self.updates
+                .lock()
+                .unwrap()
+                .push(Update::ExtendLock(id, visibility_timeout));
+            Ok(())
+        }
+
+        async fn complete(&self, id: i64) -> Result<(), JobError> {
+            self.updates.lock().unwrap().push(Update::Complete(id));
+            Ok(())
+        }
+
+        async fn fail(
+            &self,
+            id: i64,
+            error: &str,
+            retry_after: Option<Duration>,
+        ) -> Result<(), JobError> {
+            self.updates.lock().unwrap().push(Update::Fail {
+                id,
+                error: error.to_string(),
+                retry_after,
+            });
+            Ok(())
+        }
+    }
+
+    fn job(id: i64, attempts: u32, max_attempts: u32) -> ClaimedJob {
+        ClaimedJob {
+            id,
+            name: "cleanup".to_string(),
+            arguments: Value::Null,
+            attempts,
+            max_attempts,
+        }
+    }
+
+    fn failure() -> Result<(), SystemResolutionError> {
+        Err(SystemResolutionError::Generic("boom".to_string()))
+    }
+
+    #[tokio::test]
+    async fn claims_due_job() {
+        let queue = TestQueue::with_jobs(vec![job(7, 1, 5)]);
+        let timeout = Duration::from_secs(30);
+
+        let mut ran = vec![];
+        let claimed = claim_and_run(&queue, timeout, |job| {
+            ran.push(job.id);
+            async {}
+        })
+        .await
+        .unwrap();
+        assert!(claimed);
+        assert_eq!(ran, vec![7]);
+
+        // Nothing left to claim, so nothing runs
+        let claimed = claim_and_run(&queue, timeout, |_| async { panic!("no job to run") })
+            .await
+            .unwrap();
+        assert!(!claimed);
+
+        assert_eq!(
+            *queue.claim_timeouts.lock().unwrap(),
+            vec![timeout, timeout]
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn extends_lock_of_long_running_job() {
+        let queue = TestQueue::with_jobs(vec![job(7, 1, 5)]);
+        let timeout = Duration::from_secs(30);
+
+        claim_and_run(&queue, timeout, |job| {
+            let queue = &queue;
+            async move {
+                // Longer than the visibility timeout
+                tokio::time::sleep(Duration::from_secs(70)).await;
+                record_outcome(queue, &job, Ok(())).await;
+            }
+        })
+        .await
+        .unwrap();
+
+        // Extended every 15 seconds until the job completed (and not after)
+        assert_eq!(
+            *queue.updates.lock().unwrap(),
+            vec![
+                Update::ExtendLock(7, timeout),
+                Update::ExtendLock(7, timeout),
+                Update::ExtendLock(7, timeout),
+                Update::ExtendLock(7, timeout),
+                Update::Complete(7),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn completes_successful_job() {
+        let queue = TestQueue::default();
+
+        record_outcome(&queue, &job(7, 1, 5), Ok(())).await;
+
+        assert_eq!(*queue.updates.lock().unwrap(), vec![Update::Complete(7)]);
+    }
+
+    #[tokio::test]
+    async fn retries_failed_job_with_backoff() {
+        let queue = TestQueue::default();
+
+        record_outcome(&queue, &job(7, 1, 5), failure()).await;
+        record_outcome(&queue, &job(7, 4, 5), failure()).await;
+
+        assert_eq!(
+            *queue.updates.lock().unwrap(),
+            vec![
+                Update::Fail {
+                    id: 7,
+                    error: "Subsystem error: boom".to_string(),
+                    retry_after: Some(Duration::from_secs(2)),
+                },
+                Update::Fail {
+                    id: 7,
+                    error: "Subsystem error: boom".to_string(),
+                    retry_after: Some(Duration::from_secs(16)),
+                },
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn dead_letters_job_after_final_attempt() {
+        let queue = TestQueue::default();
+
+        record_outcome(&queue, &job(7, 5, 5), failure()).await;
+        // A job claimed after its last attempt (for example, after its lock expired) isn't retried either
+        record_outcome(&queue, &job(8, 6, 5), failure()).await;
+
+        assert_eq!(
+            *queue.updates.lock().unwrap(),
+            vec![
+                Update::Fail {
+                    id: 7,
+                    error: "Subsystem error: boom".to_string(),
+                    retry_after: None,
+                },
+                Update::Fail {
+                    id: 8,
+                    error: "Subsystem error: boom".to_string(),
+                    retry_after: None,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn retry_delay_backs_off_exponentially() {
+        assert_eq!(retry_delay(1), Duration::from_secs(2));
+        assert_eq!(retry_delay(2), Duration::from_secs(4));
+        assert_eq!(retry_delay(5), Duration::from_secs(32));
+        assert_eq!(retry_delay(20), MAX_RETRY_DELAY);
+        assert_eq!(retry_delay(u32::MAX), MAX_RETRY_DELAY);
+    }
+}
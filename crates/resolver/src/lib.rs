@@ -17,6 +17,11 @@ mod system_loader;
 
 #[cfg(not(target_family = "wasm"))]
 pub mod graphiql;
+#[cfg(not(target_family = "wasm"))]
+mod job_runner;
+
+#[cfg(not(target_family = "wasm"))]
+pub use job_runner::JobRunner;
 pub use root_resolver::{
     create_system_resolver, create_system_resolver_from_system, create_system_resolver_or_exit,
     get_endpoint_http_path, get_playground_http_path, resolve, resolve_in_memory, ResponseStream,
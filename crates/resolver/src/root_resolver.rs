@@ -27,7 +27,11 @@ use bytes::Bytes;
 use core_resolver::system_resolver::SystemResolutionError;
 use core_resolver::system_resolver::SystemResolver;
 pub use core_resolver::OperationsPayload;
-use core_resolver::{context::RequestContext, QueryResponseBody};
+use core_resolver::{
+    context::RequestContext,
+    metrics::{self, Timer},
+    QueryResponseBody,
+};
 use futures::Stream;
 
 use exo_env::Environment;
@@ -35,6 +39,9 @@ use exo_env::Environment;
 const EXO_PLAYGROUND_HTTP_PATH: &str = "EXO_PLAYGROUND_HTTP_PATH";
 const EXO_ENDPOINT_HTTP_PATH: &str = "EXO_ENDPOINT_HTTP_PATH";
 
+/// How many times to retry a request that failed due to a serialization failure
+const MAX_SERIALIZATION_RETRIES: usize = 3;
+
 #[instrument(
     name = "resolver::resolve_in_memory"
     skip(system_resolver, request_context)
@@ -45,24 +52,53 @@ pub async fn resolve_in_memory<'a>(
     request_context: RequestContext<'a>,
     trusted_document_enforcement: TrustedDocumentEnforcement,
 ) -> Result<Vec<(String, QueryResponse)>, SystemResolutionError> {
-    let response = system_resolver
-        .resolve_operations(
-            operations_payload,
-            &request_context,
-            trusted_document_enforcement,
-        )
-        .await;
-
-    let ctx = request_context.get_base_context();
-    let mut tx_holder = ctx.transaction_holder.try_lock().unwrap();
-
-    tx_holder
-        .finalize(response.is_ok())
-        .await
-        .map_err(|e| {
-            SystemResolutionError::Generic(format!("Error while finalizing transaction: {e}"))
-        })
-        .and(response)
+    let mut retries = 0;
+
+    loop {
+        let response = system_resolver
+            .resolve_operations(
+                operations_payload.clone(),
+                &request_context,
+                trusted_document_enforcement,
+            )
+            .await;
+
+        let ctx = request_context.get_base_context();
+        let mut tx_holder = ctx.transaction_holder.try_lock().unwrap();
+
+        let finalize_result = tx_holder.finalize(response.is_ok()).await;
+
+        // A `repeatable read` or `serializable` transaction may fail due to a concurrent
+        // transaction, but succeed if retried (with a fresh transaction)
+        if tx_holder.is_retryable() && retries < MAX_SERIALIZATION_RETRIES {
+            retries += 1;
+            tracing::warn!(
+                "Retrying the request after a serialization failure (retry {retries} of {MAX_SERIALIZATION_RETRIES})"
+            );
+            tx_holder.reset();
+
+            // The attempt's transaction rolled back, so only the retry's mutations and access
+            // decisions should invalidate cached responses and appear in the access trace
+            request_context.take_mutated_entity_types();
+            ctx.access_trace.clear();
+            continue;
+        }
+
+        // Some statements may have been committed even if the request failed, so invalidate the
+        // cached responses regardless
+        let mutated_entity_types = request_context.take_mutated_entity_types();
+        if !mutated_entity_types.is_empty() {
+            system_resolver
+                .invalidate_cached_responses(&mutated_entity_types)
+                .await;
+        }
+
+        return finalize_result
+            .map_err(|e| {
+                SystemResolutionError::Generic(format!("Error while finalizing transaction: {e}"))
+            })
+            .and(response);
+    }
 }
 
 pub type Headers = Vec<(String, String)>;
@@ -89,6 +125,33 @@ pub async fn resolve<'a, E: 'static>(
     #[cfg(target_family = "wasm")]
     let is_production = !playground_request;
 
+    // Report access decisions only outside production (they may reveal the access rules)
+    let access_trace = (operations_payload.access_trace && !is_production).then(|| {
+        let access_trace = request_context.get_base_context().access_trace.clone();
+        access_trace.enable();
+        access_trace
+    });
+
+    // Similarly, report the executed statements only outside production (they may reveal the
+    // data through the parameters)
+    let transaction_holder = operations_payload
+        .sql
+        .filter(|_| !is_production)
+        .map(|capture| {
+            let transaction_holder = request_context
+                .get_base_context()
+                .transaction_holder
+                .clone();
+            transaction_holder
+                .try_lock()
+                .unwrap()
+                .capture_statements(capture);
+            transaction_holder
+        });
+
+    let operation_name = operations_payload.operation_name.clone();
+    let timer = Timer::start();
+
     let response = resolve_in_memory(
         operations_payload,
         system_resolver,
@@ -101,6 +164,12 @@ pub async fn resolve<'a, E: 'static>(
     )
     .await;
 
+    metrics::record_request(
+        operation_name.as_deref(),
+        timer.elapsed(),
+        response.as_ref().err(),
+    );
+
     let headers = if let Ok(ref response) = response {
         response
             .iter()
@@ -110,7 +179,32 @@ pub async fn resolve<'a, E: 'static>(
         vec![]
     };
 
+    let mut extensions = serde_json::Map::new();
+    if let Some(access_trace) = access_trace.and_then(|access_trace| access_trace.take()) {
+        extensions.insert(
+            "accessTrace".to_string(),
+            serde_json::to_value(access_trace).unwrap(),
+        );
+    }
+    if let Some(statements) = transaction_holder.and_then(|transaction_holder| {
+        transaction_holder
+            .try_lock()
+            .unwrap()
+            .take_captured_statements()
+    }) {
+        extensions.insert("sql".to_string(), serde_json::to_value(statements).unwrap());
+    }
+
     let stream = try_stream! {
+        macro_rules! report_extensions {
+            () => {
+                if !extensions.is_empty() {
+                    yield Bytes::from_static(br#", "extensions": "#);
+                    yield Bytes::from(serde_json::to_string(&extensions).unwrap());
+                }
+            };
+        }
+
         macro_rules! report_position {
             ($position:expr) => {
                 let p: Pos = $position;
@@ -153,7 +247,9 @@ pub async fn resolve<'a, E: 'static>(
                         yield Bytes::from_static(b", ");
                     }
                 };
-                yield Bytes::from_static(b"}}");
+                yield Bytes::from_static(b"}");
+                report_extensions!();
+                yield Bytes::from_static(b"}");
             },
             Err(err) => {
                 yield Bytes::from_static(br#"{"errors": [{"message":""#);
@@ -169,7 +265,9 @@ pub async fn resolve<'a, E: 'static>(
                     yield Bytes::from_static(br#"]"#);
                 };
                 yield Bytes::from_static(br#"}"#);
-                yield Bytes::from_static(b"]}");
+                yield Bytes::from_static(b"]");
+                report_extensions!();
+                yield Bytes::from_static(b"}");
             },
         }
     };
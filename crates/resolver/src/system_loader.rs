@@ -9,9 +9,11 @@
 
 use std::sync::Arc;
 
-use common::env_const::EXO_INTROSPECTION;
+use common::env_const::{EXO_API_KEYS_FILE, EXO_INTROSPECTION};
 use common::EnvError;
+use core_resolver::api_key::ApiKeyFile;
 use core_resolver::context::JwtAuthenticator;
+use core_resolver::response_cache::ResponseCache;
 use introspection_resolver::IntrospectionResolver;
 use thiserror::Error;
 
@@ -119,6 +121,15 @@ impl SystemLoader {
             .await
             .map_err(|e| SystemLoadingError::Config(e.to_string()))?;
 
+        let api_key_file = env
+            .get(EXO_API_KEYS_FILE)
+            .map(|path| ApiKeyFile::load(&path))
+            .transpose()
+            .map_err(|e| SystemLoadingError::Config(e.to_string()))?;
+
+        let response_cache = ResponseCache::from_env(env.as_ref())
+            .map_err(|e| SystemLoadingError::Config(e.to_string()))?;
+
         Ok(SystemResolver::new(
             subsystem_resolvers,
             query_interception_map,
@@ -126,9 +137,11 @@ impl SystemLoader {
             trusted_documents,
             schema,
             Arc::new(authenticator),
+            api_key_file,
             env,
             normal_query_depth_limit,
             introspection_query_depth_limit,
+            response_cache,
         ))
     }
 
@@ -18,10 +18,16 @@ use actix_web::{
 };
 use url::Url;
 
-use common::env_const::{get_deployment_mode, DeploymentMode};
+use common::env_const::{
+    get_deployment_mode, DeploymentMode, EXO_METRICS_HTTP_PATH, EXO_METRICS_PORT,
+};
+use common::trace_context::{self, TRACEPARENT_HEADER, TRACESTATE_HEADER};
+use common::EnvError;
 use core_resolver::context::{ContextExtractionError, RequestContext};
+use core_resolver::metrics;
 use core_resolver::system_resolver::SystemResolver;
 use core_resolver::OperationsPayload;
+use exo_env::Environment;
 use request::ActixRequest;
 use resolver::{get_endpoint_http_path, get_playground_http_path, graphiql};
 use serde_json::Value;
@@ -65,6 +71,49 @@ pub fn configure_playground(cfg: &mut ServiceConfig) {
         .route("/", web::get().to(playground_redirect));
 }
 
+/// Where to serve metrics in the Prometheus text format (only if `EXO_METRICS_HTTP_PATH` is set,
+/// since they reveal operation names and usage)
+pub struct MetricsConfig {
+    pub path: String,
+    /// The port of a separate server for metrics (`None` to serve them along with the endpoint)
+    pub port: Option<u16>,
+}
+
+impl MetricsConfig {
+    pub fn from_env(env: &dyn Environment) -> Result<Option<Self>, EnvError> {
+        let Some(path) = env.get(EXO_METRICS_HTTP_PATH) else {
+            return Ok(None);
+        };
+
+        let port = env
+            .get(EXO_METRICS_PORT)
+            .map(|port| {
+                port.parse::<u16>().map_err(|_| EnvError::InvalidValue {
+                    env_key: EXO_METRICS_PORT,
+                    env_value: port,
+                    message: "Must be a port number".to_string(),
+                })
+            })
+            .transpose()?;
+
+        Ok(Some(Self { path, port }))
+    }
+}
+
+/// Serve metrics from the path. Requires the system resolver set up by `configure_resolver` (or
+/// added as app data when serving metrics separately).
+pub fn configure_metrics(metrics_path: String) -> impl FnOnce(&mut ServiceConfig) {
+    move |cfg| {
+        cfg.route(&metrics_path, web::get().to(serve_metrics));
+    }
+}
+
+async fn serve_metrics(system_resolver: web::Data<SystemResolver>) -> impl Responder {
+    HttpResponse::Ok()
+        .content_type("text/plain; version=0.0.4")
+        .body(metrics::render(system_resolver.metrics().await))
+}
+
 /// Resolve a GraphQL request
 ///
 /// # Arguments
@@ -101,6 +150,18 @@ async fn resolve_locally(
         .map(|value| value == "true")
         .unwrap_or(false);
 
+    // Join the caller's trace (the span is the one created by `TracingLogger` for this request)
+    let header = |name: &str| {
+        req.headers()
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+    };
+    trace_context::set_remote_parent(
+        &tracing::Span::current(),
+        header(TRACEPARENT_HEADER),
+        header(TRACESTATE_HEADER),
+    );
+
     let request = ActixRequest::from_request(req);
     let request_context = RequestContext::new(&request, vec![], system_resolver.as_ref());
 
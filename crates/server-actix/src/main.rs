@@ -12,8 +12,9 @@ use actix_web::{middleware, web, App, HttpServer};
 
 use resolver::{
     get_endpoint_http_path, get_playground_http_path, introspection_mode, IntrospectionMode,
+    JobRunner,
 };
-use server_actix::{configure_playground, configure_resolver};
+use server_actix::{configure_metrics, configure_playground, configure_resolver, MetricsConfig};
 use thiserror::Error;
 use tracing_actix_web::TracingLogger;
 
@@ -53,6 +54,14 @@ async fn main() -> Result<(), ServerError> {
 
     let system_resolver = web::Data::new(server_common::init().await);
 
+    if let Some(job_runner) = JobRunner::from_env(system_resolver.clone().into_inner())? {
+        actix_web::rt::spawn(job_runner.run());
+    }
+
+    actix_web::rt::spawn(server_common::export_subsystem_metrics(
+        system_resolver.clone().into_inner(),
+    ));
+
     let server_port = env::var(EXO_SERVER_PORT)
         .map(|port_str| {
             port_str
@@ -61,23 +70,10 @@ async fn main() -> Result<(), ServerError> {
         })
         .unwrap_or(9876);
 
-    let server = HttpServer::new(move || {
-        let cors = cors_from_env();
+    let metrics_config = MetricsConfig::from_env(&SystemEnvironment)?;
 
-        App::new()
-            .wrap(TracingLogger::default())
-            .wrap(middleware::NormalizePath::new(
-                middleware::TrailingSlash::Trim,
-            ))
-            .wrap(cors)
-            .configure(configure_resolver(system_resolver.clone()))
-            .configure(configure_playground)
-    });
-
-    let server_host = env::var(EXO_SERVER_HOST);
-
-    let server = match server_host {
-        Ok(host) => server.bind((host, server_port)),
+    let server_host = match env::var(EXO_SERVER_HOST) {
+        Ok(host) => host,
         Err(_) => {
             match get_deployment_mode()? {
                 DeploymentMode::Dev | DeploymentMode::Yolo | DeploymentMode::Playground(_) => {
@@ -87,19 +83,66 @@ async fn main() -> Result<(), ServerError> {
                     // Note that tools such as "@graphql-codegen/cli" are unable to connect to
                     // "localhost:<port>" if we only bind to "0.0.0.0" or even "127.0.0.1" (but
                     // works fine, if we bind to IPv6 loopback address "::1").
-                    server.bind(("localhost", server_port))
+                    "localhost".to_string()
                 }
                 DeploymentMode::Prod => {
                     // Bind to "0.0.0.0" (all interfaces; needed for production; see the
                     // recommendation in `HttpServer::bind` documentation). This allows the server
                     // to be accessed from outside the host machine (e.g. when the server is in a
                     // Docker container in a fly.io deployment).
-                    server.bind(("0.0.0.0", server_port))
+                    "0.0.0.0".to_string()
                 }
             }
         }
     };
-    match server {
+
+    // Serve metrics on a separate port if one is set (so that the port need not be exposed along
+    // with the endpoint), and otherwise along with the endpoint
+    let (metrics_server, endpoint_metrics_path, metrics_url) = match metrics_config {
+        Some(MetricsConfig {
+            path,
+            port: Some(metrics_port),
+        }) => {
+            let system_resolver = system_resolver.clone();
+            let metrics_path = path.clone();
+            let metrics_server = HttpServer::new(move || {
+                App::new()
+                    .app_data(system_resolver.clone())
+                    .configure(configure_metrics(metrics_path.clone()))
+            })
+            .workers(1)
+            .bind((server_host.as_str(), metrics_port))
+            .map_err(|e| bind_error(e, metrics_port))?;
+
+            let metrics_url = format!("http://{}{path}", pretty_addr(&metrics_server.addrs()));
+
+            (Some(metrics_server), None, Some(metrics_url))
+        }
+        Some(MetricsConfig { path, port: None }) => (None, Some(path), None),
+        None => (None, None, None),
+    };
+
+    let app_metrics_path = endpoint_metrics_path.clone();
+    let server = HttpServer::new(move || {
+        let cors = cors_from_env();
+
+        let app = App::new()
+            .wrap(TracingLogger::default())
+            .wrap(middleware::NormalizePath::new(
+                middleware::TrailingSlash::Trim,
+            ))
+            .wrap(cors)
+            .configure(configure_resolver(system_resolver.clone()));
+
+        let app = match &app_metrics_path {
+            Some(path) => app.configure(configure_metrics(path.clone())),
+            None => app,
+        };
+
+        app.configure(configure_playground)
+    });
+
+    match server.bind((server_host.as_str(), server_port)) {
         Ok(server) => {
             let pretty_addr = pretty_addr(&server.addrs());
 
@@ -130,13 +173,33 @@ async fn main() -> Result<(), ServerError> {
                     print_playground_info();
                 }
             }
-            Ok(server.run().await?)
+
+            let metrics_url = metrics_url.or_else(|| {
+                endpoint_metrics_path
+                    .map(|metrics_path| format!("http://{pretty_addr}{metrics_path}"))
+            });
+            if let Some(metrics_url) = metrics_url {
+                println!("- Metrics hosted at:");
+                println!("\t{metrics_url}");
+            }
+
+            match metrics_server {
+                Some(metrics_server) => {
+                    futures::try_join!(server.run(), metrics_server.run())?;
+                    Ok(())
+                }
+                None => Ok(server.run().await?),
+            }
         }
-        Err(e) => Err(if e.kind() == ErrorKind::AddrInUse {
-            ServerError::PortInUse(server_port)
-        } else {
-            ServerError::Io(e)
-        }),
+        Err(e) => Err(bind_error(e, server_port)),
+    }
+}
+
+fn bind_error(e: std::io::Error, port: u16) -> ServerError {
+    if e.kind() == ErrorKind::AddrInUse {
+        ServerError::PortInUse(port)
+    } else {
+        ServerError::Io(e)
     }
 }
 
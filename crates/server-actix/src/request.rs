@@ -16,6 +16,7 @@ pub struct ActixRequest {
     //
     // request: &'a actix_web::HttpRequest,
     headers: HeaderMap,
+    query_string: String,
     connection_info: ConnectionInfo,
 }
 
@@ -23,6 +24,7 @@ impl ActixRequest {
     pub fn from_request(req: HttpRequest) -> ActixRequest {
         ActixRequest {
             headers: req.headers().clone(),
+            query_string: req.query_string().to_owned(),
             connection_info: req.connection_info().clone(),
         }
     }
@@ -36,6 +38,12 @@ impl Request for ActixRequest {
             .collect()
     }
 
+    fn get_query_param(&self, key: &str) -> Option<String> {
+        url::form_urlencoded::parse(self.query_string.as_bytes())
+            .find(|(name, _)| name == key)
+            .map(|(_, value)| value.into_owned())
+    }
+
     fn get_ip(&self) -> Option<std::net::IpAddr> {
         self.connection_info
             .realip_remote_addr()
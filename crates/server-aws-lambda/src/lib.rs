@@ -11,8 +11,9 @@
 
 mod request;
 
+use common::trace_context::{self, TRACEPARENT_HEADER, TRACESTATE_HEADER};
 use core_resolver::{
-    context::{ContextExtractionError, RequestContext},
+    context::{ContextExtractionError, Request, RequestContext},
     system_resolver::SystemResolver,
     OperationsPayload,
 };
@@ -21,6 +22,7 @@ use lambda_runtime::{Error, LambdaEvent};
 use request::LambdaRequest;
 use serde_json::{json, Value};
 use std::sync::Arc;
+use tracing::Instrument;
 
 fn error_msg(message: &str, status_code: usize) -> Value {
     let body = format!(r#"{{ "errors": [{{"message": "{message}"}}] }}"#);
@@ -35,6 +37,27 @@ fn error_msg(message: &str, status_code: usize) -> Value {
 pub async fn resolve(
     event: LambdaEvent<Value>,
     system_resolver: Arc<SystemResolver>,
+) -> Result<Value, Error> {
+    // Resolve in a span that joins the caller's trace
+    let span = tracing::info_span!("lambda::resolve");
+    {
+        let request = LambdaRequest::new(&event);
+        let header = |name| request.get_headers(name).into_iter().next();
+        trace_context::set_remote_parent(
+            &span,
+            header(TRACEPARENT_HEADER).as_deref(),
+            header(TRACESTATE_HEADER).as_deref(),
+        );
+    }
+
+    resolve_in_span(event, system_resolver)
+        .instrument(span)
+        .await
+}
+
+async fn resolve_in_span(
+    event: LambdaEvent<Value>,
+    system_resolver: Arc<SystemResolver>,
 ) -> Result<Value, Error> {
     let request = LambdaRequest::new(&event);
     let request_context = RequestContext::new(&request, vec![], system_resolver.as_ref());
@@ -22,6 +22,7 @@ async fn main() -> Result<(), Error> {
     use std::sync::Arc;
 
     let system_resolver = Arc::new(server_common::init().await);
+    tokio::spawn(server_common::export_subsystem_metrics(system_resolver.clone()));
 
     let module = lambda_runtime::service_fn(|event: LambdaEvent<Value>| async {
         resolve(event, system_resolver.clone()).await
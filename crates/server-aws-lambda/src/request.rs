@@ -59,6 +59,12 @@ impl Request for LambdaRequest<'_> {
         headers
     }
 
+    fn get_query_param(&self, key: &str) -> Option<String> {
+        self.0.payload["queryStringParameters"][key]
+            .as_str()
+            .map(str::to_string)
+    }
+
     fn get_ip(&self) -> Option<std::net::IpAddr> {
         let event: &Value = &self.0.payload;
 
@@ -7,11 +7,11 @@
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
 
-use std::{env, process::exit};
+use std::{env, process::exit, sync::Arc, time::Duration};
 
 use common::logging_tracing;
 use core_plugin_interface::interface::SubsystemLoader;
-use core_resolver::system_resolver::SystemResolver;
+use core_resolver::{metrics, system_resolver::SystemResolver};
 
 use resolver::create_system_resolver_or_exit;
 
@@ -40,6 +40,20 @@ pub async fn init() -> SystemResolver {
     .await
 }
 
+/// Periodically report the subsystem metrics (such as the usage of connection pools) to
+/// OpenTelemetry (returns immediately if OpenTelemetry metrics aren't enabled)
+pub async fn export_subsystem_metrics(system_resolver: Arc<SystemResolver>) {
+    if !logging_tracing::otlp_metrics_enabled() {
+        return;
+    }
+
+    let mut interval = tokio::time::interval(Duration::from_secs(15));
+    loop {
+        interval.tick().await;
+        metrics::update_subsystem_metrics(system_resolver.metrics().await);
+    }
+}
+
 pub fn create_static_loaders() -> Vec<Box<dyn SubsystemLoader>> {
     vec![
         #[cfg(feature = "static-postgres-resolver")]
@@ -9,14 +9,14 @@
 
 use core_model::{
     access::{
-        AccessLogicalExpression, AccessPredicateExpression, AccessRelationalOp,
-        CommonAccessPrimitiveExpression,
+        AccessLogicalExpression, AccessPredicateExpression, AccessRelationalOp, ArithmeticOp,
+        CheckCall, CommonAccessPrimitiveExpression,
     },
     context_type::{get_context, ContextFieldType, ContextSelection},
     primitive_type::PrimitiveType,
 };
 use core_model_builder::{
-    ast::ast_types::{AstExpr, FieldSelection, LogicalOp, RelationalOp},
+    ast::ast_types::{self, AstExpr, FieldSelection, FunctionCall, LogicalOp, RelationalOp},
     error::ModelBuildingError,
     typechecker::Typed,
 };
@@ -96,6 +96,7 @@ pub fn compute_predicate_expression(
                 Box::new(compute_primitive_expr(right, resolved_env)),
             )))
         }
+        AstExpr::FunctionCall(call) => compute_function_predicate(call, resolved_env),
         AstExpr::BooleanLiteral(value, _) => Ok(AccessPredicateExpression::BooleanLiteral(*value)),
 
         _ => Err(ModelBuildingError::Generic(
@@ -124,11 +125,84 @@ fn compute_primitive_expr(
             CommonAccessPrimitiveExpression::NumberLiteral(*value),
         ),
         AstExpr::StringList(_, _) => panic!("Module access expressions do not support lists yet"),
+        AstExpr::FunctionCall(call) if call.name.0 == "now" => {
+            ModuleAccessPrimitiveExpression::Common(CommonAccessPrimitiveExpression::Now)
+        }
+        AstExpr::FunctionCall(call) => panic!(
+            "Function {} is not supported in module access expressions",
+            call.name.0
+        ),
+        AstExpr::ArithmeticOp(op) => {
+            let (left, right) = op.sides();
+            ModuleAccessPrimitiveExpression::Arithmetic(
+                Box::new(compute_primitive_expr(left, resolved_env)),
+                arithmetic_op(op),
+                Box::new(compute_primitive_expr(right, resolved_env)),
+            )
+        }
         AstExpr::LogicalOp(_) => unreachable!(), // Parser has already ensures that the two sides are primitive expressions
         AstExpr::RelationalOp(_) => unreachable!(), // Parser has already ensures that the two sides are primitive expressions
     }
 }
 
+/// Function calls that evaluate to a boolean such as `contains(AuthContext.roles, "admin")`
+fn compute_function_predicate(
+    call: &FunctionCall<Typed>,
+    resolved_env: &ResolvedTypeEnv,
+) -> Result<AccessPredicateExpression<ModuleAccessPrimitiveExpression>, ModelBuildingError> {
+    match (call.name.0.as_str(), call.args.as_slice()) {
+        ("contains", [haystack, needle]) => Ok(AccessPredicateExpression::RelationalOp(
+            AccessRelationalOp::Contains(
+                Box::new(compute_primitive_expr(haystack, resolved_env)),
+                Box::new(compute_primitive_expr(needle, resolved_env)),
+            ),
+        )),
+        ("check", [AstExpr::StringLiteral(function_name, _), args @ ..]) => {
+            let args = args
+                .iter()
+                // Contexts are supplied to the function through its injected parameters
+                .filter(|arg| !is_context_reference(arg, resolved_env))
+                .map(|arg| compute_primitive_expr(arg, resolved_env))
+                .collect();
+
+            // Treat `check(...)` the same way as `check(...) == true`
+            Ok(AccessPredicateExpression::RelationalOp(
+                AccessRelationalOp::Eq(
+                    Box::new(ModuleAccessPrimitiveExpression::Check(CheckCall {
+                        function_name: function_name.clone(),
+                        args,
+                    })),
+                    Box::new(ModuleAccessPrimitiveExpression::Common(
+                        CommonAccessPrimitiveExpression::BooleanLiteral(true),
+                    )),
+                ),
+            ))
+        }
+        (name, _) => Err(ModelBuildingError::Generic(format!(
+            "Function {name} cannot be used as a predicate in access expressions"
+        ))),
+    }
+}
+
+/// Is the expression a reference to an entire context (such as `AuthContext`)?
+fn is_context_reference(expr: &AstExpr<Typed>, resolved_env: &ResolvedTypeEnv) -> bool {
+    match expr {
+        AstExpr::FieldSelection(selection) => match selection.context_path().as_slice() {
+            [name] => resolved_env.contexts.get_by_key(name).is_some(),
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+fn arithmetic_op(op: &ast_types::ArithmeticOp<Typed>) -> ArithmeticOp {
+    match op {
+        ast_types::ArithmeticOp::Add(..) => ArithmeticOp::Add,
+        ast_types::ArithmeticOp::Sub(..) => ArithmeticOp::Sub,
+        ast_types::ArithmeticOp::Mul(..) => ArithmeticOp::Mul,
+    }
+}
+
 fn compute_selection<'a>(
     selection: &FieldSelection<Typed>,
     resolved_env: &'a ResolvedTypeEnv<'a>,
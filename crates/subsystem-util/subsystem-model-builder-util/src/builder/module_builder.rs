@@ -16,6 +16,7 @@ use subsystem_model_util::{
     access::Access,
     argument::{ArgumentParameter, ArgumentParameterType},
     interceptor::Interceptor,
+    job::Job,
     module::{Argument, ModuleMethod, ModuleMethodType, Script},
     operation::{ModuleMutation, ModuleQuery},
     types::ModuleType,
@@ -25,8 +26,8 @@ use crate::builder::resolved_builder::ResolvedFieldType;
 
 use super::{
     resolved_builder::{
-        ResolvedInterceptor, ResolvedInterceptorKind, ResolvedMethod, ResolvedMethodType,
-        ResolvedModule, ResolvedType,
+        ResolvedInterceptor, ResolvedInterceptorKind, ResolvedJob, ResolvedMethod,
+        ResolvedMethodType, ResolvedModule, ResolvedType,
     },
     system_builder::SystemContextBuilding,
 };
@@ -44,6 +45,9 @@ pub fn build_shallow(
         for interceptor in module.interceptors.iter() {
             create_shallow_interceptor(module, interceptor, building);
         }
+        for job in module.jobs.iter() {
+            create_shallow_job(module, job, building);
+        }
     }
 }
 
@@ -130,6 +134,7 @@ fn create_shallow_module(
 
                 resolved_method.return_type.wrap(plain_return_type)
             },
+            transaction: resolved_method.transaction.clone(),
         },
     );
 }
@@ -244,3 +249,31 @@ pub fn create_shallow_interceptor(
             .collect(),
     });
 }
+
+pub fn create_shallow_job(
+    resolved_module: &ResolvedModule,
+    resolved_job: &ResolvedJob,
+    building: &mut SystemContextBuilding,
+) {
+    let script = get_or_populate_script(
+        &resolved_module.script_path,
+        &resolved_module.script,
+        building,
+    );
+
+    building.jobs.insert(Job {
+        module_name: resolved_module.name.clone(),
+        method_name: resolved_job.method_name.clone(),
+        script,
+        arguments: resolved_job
+            .arguments
+            .iter()
+            .map(|arg| Argument {
+                name: arg.name.clone(),
+                type_id: arg.typ.wrap(building.get_id(arg.typ.name()).unwrap()),
+                is_injected: arg.is_injected,
+            })
+            .collect(),
+        schedule: resolved_job.schedule.clone(),
+    });
+}
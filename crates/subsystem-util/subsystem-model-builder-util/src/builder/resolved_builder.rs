@@ -13,10 +13,13 @@ use std::path::Path;
 use codemap::Span;
 use codemap_diagnostic::{Diagnostic, Level, SpanLabel, SpanStyle};
 
+use core_model::transaction::TransactionOptions;
 use core_model::types::{FieldType, Named};
 use core_model::{mapped_arena::MappedArena, primitive_type::PrimitiveType};
 use core_model_builder::ast::ast_types::AstFieldType;
-use core_model_builder::builder::resolved_builder::AnnotationMapHelper;
+use core_model_builder::builder::resolved_builder::{
+    build_transaction_options, AnnotationMapHelper,
+};
 use core_model_builder::builder::system_builder::BaseModelSystem;
 use core_model_builder::typechecker::typ::{Module, TypecheckedSystem};
 use core_model_builder::typechecker::AnnotationMap;
@@ -86,6 +89,7 @@ pub struct ResolvedModule {
     pub script_path: String,
     pub methods: Vec<ResolvedMethod>,
     pub interceptors: Vec<ResolvedInterceptor>,
+    pub jobs: Vec<ResolvedJob>,
     pub types_defined: HashSet<String>, // Typed defined in the module
 }
 
@@ -97,6 +101,8 @@ pub struct ResolvedMethod {
     pub access: ResolvedAccess,
     pub arguments: Vec<ResolvedArgument>,
     pub return_type: FieldType<ResolvedFieldType>,
+    /// Options for the transaction the method runs in (from `@transaction`)
+    pub transaction: TransactionOptions,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
@@ -137,6 +143,14 @@ impl ResolvedInterceptorKind {
     }
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ResolvedJob {
+    pub module_name: String,
+    pub method_name: String,
+    pub arguments: Vec<ResolvedArgument>,
+    pub schedule: Option<String>, // The cron expression (from `@cron`), if any
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct ResolvedModuleSystem {
     pub module_types: MappedArena<ResolvedType>,
@@ -261,9 +275,23 @@ async fn resolve_module(
 ) -> Result<(), ModelBuildingError> {
     // Extract the source path from the annotation
     // `@deno("util/auth.ts")` -> `util/auth.ts`
+    // `@deno("util/auth.ts", allowNet=["example.com"])` -> `util/auth.ts`
     let module_relative_path = match module.annotations.get(&annotation_name).unwrap() {
-        AstAnnotationParams::Single(AstExpr::StringLiteral(s, _), _) => s,
-        _ => panic!(),
+        AstAnnotationParams::Single(AstExpr::StringLiteral(s, _), _)
+        | AstAnnotationParams::Mixed(AstExpr::StringLiteral(s, _), _, _, _) => s,
+        _ => {
+            errors.push(Diagnostic {
+                level: Level::Error,
+                message: format!("The `@{annotation_name}` annotation requires the module's source path as its first parameter"),
+                code: Some("C000".to_string()),
+                spans: vec![SpanLabel {
+                    span: module.span,
+                    style: SpanStyle::Primary,
+                    label: None,
+                }],
+            });
+            return Err(ModelBuildingError::Diagnosis(errors.clone()));
+        }
     }
     .clone();
 
@@ -274,6 +302,82 @@ async fn resolve_module(
 
     let (script_path, bundled_script) = process_script(module, base_system, &source_path)?;
 
+    let jobs = module
+        .jobs
+        .iter()
+        .map(|job| {
+            let schedule = match job.annotations.get("cron") {
+                Some(AstAnnotationParams::Single(AstExpr::StringLiteral(s, _), _)) => {
+                    if s.parse::<saffron::Cron>().is_err() {
+                        errors.push(Diagnostic {
+                            level: Level::Error,
+                            message: format!(
+                                "Invalid cron expression `{s}` for job `{}`",
+                                job.name
+                            ),
+                            code: Some("C000".to_string()),
+                            spans: vec![SpanLabel {
+                                span: job.span,
+                                style: SpanStyle::Primary,
+                                label: None,
+                            }],
+                        });
+                        return Err(ModelBuildingError::Diagnosis(errors.clone()));
+                    }
+                    Some(s.clone())
+                }
+                Some(_) => {
+                    errors.push(Diagnostic {
+                        level: Level::Error,
+                        message: format!(
+                            "The `@cron` annotation of job `{}` requires a string",
+                            job.name
+                        ),
+                        code: Some("C000".to_string()),
+                        spans: vec![SpanLabel {
+                            span: job.span,
+                            style: SpanStyle::Primary,
+                            label: None,
+                        }],
+                    });
+                    return Err(ModelBuildingError::Diagnosis(errors.clone()));
+                }
+                None => None,
+            };
+
+            let arguments: Vec<_> = job
+                .arguments
+                .iter()
+                .map(|a| resolve_argument(a, types))
+                .collect();
+
+            // A scheduled job has no one to supply its arguments, so it may only take injected ones
+            if schedule.is_some() && arguments.iter().any(|a| !a.is_injected) {
+                errors.push(Diagnostic {
+                    level: Level::Error,
+                    message: format!(
+                        "Scheduled job `{}` may only take injected arguments",
+                        job.name
+                    ),
+                    code: Some("C000".to_string()),
+                    spans: vec![SpanLabel {
+                        span: job.span,
+                        style: SpanStyle::Primary,
+                        label: None,
+                    }],
+                });
+                return Err(ModelBuildingError::Diagnosis(errors.clone()));
+            }
+
+            Ok(ResolvedJob {
+                module_name: module.name.clone(),
+                method_name: job.name.clone(),
+                arguments,
+                schedule,
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
     fn extract_intercept_annot<'a>(
         annotations: &'a AnnotationMap,
         key: &str,
@@ -292,6 +396,8 @@ async fn resolve_module(
                 .iter()
                 .map(|m| {
                     let access = build_access(m.annotations.get("access"));
+                    let transaction =
+                        build_transaction_options(m.annotations.get("transaction"), errors);
                     ResolvedMethod {
                         name: m.name.clone(),
                         operation_kind: match m.typ {
@@ -306,6 +412,7 @@ async fn resolve_module(
                             .map(|a| resolve_argument(a, types))
                             .collect(),
                         return_type: resolve_field_type(&m.return_type.to_typ(types), types),
+                        transaction,
                     }
                 })
                 .collect(),
@@ -361,6 +468,7 @@ async fn resolve_module(
                     })
                 })
                 .collect(),
+                jobs,
                 types_defined: module.types.iter().map(|m| m.name.clone()).collect(),
         },
     );
@@ -399,6 +507,12 @@ fn resolve_module_input_types(
                 types_used.push((argument.typ.clone(), true))
             }
         }
+
+        for job in module.jobs.iter() {
+            for argument in job.arguments.iter() {
+                types_used.push((argument.typ.clone(), true))
+            }
+        }
     }
 
     // 2. filter out primitives
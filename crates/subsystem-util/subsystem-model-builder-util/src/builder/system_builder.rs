@@ -18,6 +18,7 @@ use core_model_builder::{
 };
 use subsystem_model_util::{
     interceptor::Interceptor,
+    job::Job,
     module::{ModuleMethod, Script},
     operation::{ModuleMutation, ModuleQuery},
     subsystem::ModuleSubsystem,
@@ -39,6 +40,7 @@ pub struct SystemContextBuilding {
     pub mutations: MappedArena<ModuleMutation>,
     pub methods: MappedArena<ModuleMethod>,
     pub interceptors: SerializableSlab<Interceptor>, // Don't use MappedArena because we use a composite key (module name + method name) here
+    pub jobs: SerializableSlab<Job>,                 // Same as interceptors
     pub scripts: MappedArena<Script>,
 }
 
@@ -50,6 +52,7 @@ impl Default for SystemContextBuilding {
             mutations: MappedArena::default(),
             methods: MappedArena::default(),
             interceptors: SerializableSlab::new(),
+            jobs: SerializableSlab::new(),
             scripts: MappedArena::default(),
         }
     }
@@ -137,6 +140,7 @@ pub async fn build_with_selection(
             scripts: building.scripts.values(),
             contexts: base_system.contexts.clone(),
             interceptors: building.interceptors,
+            jobs: building.jobs,
         },
         interceptors,
     })
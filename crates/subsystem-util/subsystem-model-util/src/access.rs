@@ -7,7 +7,9 @@
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
 
-use core_model::access::{AccessPredicateExpression, CommonAccessPrimitiveExpression};
+use core_model::access::{
+    AccessPredicateExpression, ArithmeticOp, CheckCall, CommonAccessPrimitiveExpression,
+};
 use serde::{Deserialize, Serialize};
 
 /// Access specification for a model
@@ -24,11 +26,12 @@ impl Access {
     }
 }
 
-/// Primitive expression (that doesn't contain any other expressions).
+/// Primitive expression (that doesn't contain any predicates).
 /// Used as sides of `AccessRelationalExpression` to form more complex expressions
 /// such as equal and less than.
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub enum ModuleAccessPrimitiveExpression {
-    // Even though we have only one variant here, for symmetry with other subsystems, we model it as an enum
     Common(CommonAccessPrimitiveExpression), // expression shared by all access expressions
+    Arithmetic(Box<Self>, ArithmeticOp, Box<Self>), // for example, AuthContext.quota - 1
+    Check(CheckCall<Self>),                  // for example, check("canRead", AuthContext.id)
 }
@@ -0,0 +1,26 @@
+// Copyright Exograph, Inc. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file at the root of this repository.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use serde::{Deserialize, Serialize};
+
+use super::module::{Argument, Script};
+use core_model::mapped_arena::SerializableSlabIndex;
+
+/// A module function that runs in the background.
+///
+/// A job with a `schedule` runs periodically (the schedule is a cron expression). Any job may also
+/// be enqueued (for example, from a query or mutation implementation) to run later.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Job {
+    pub module_name: String,
+    pub method_name: String,
+    pub script: SerializableSlabIndex<Script>,
+    pub arguments: Vec<Argument>,
+    pub schedule: Option<String>,
+}
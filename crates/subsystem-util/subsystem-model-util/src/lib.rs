@@ -10,6 +10,7 @@
 pub mod access;
 pub mod argument;
 pub mod interceptor;
+pub mod job;
 pub mod module;
 pub mod operation;
 pub mod subsystem;
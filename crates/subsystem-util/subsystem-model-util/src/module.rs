@@ -17,6 +17,7 @@ use super::{
 };
 use core_model::{
     mapped_arena::SerializableSlabIndex,
+    transaction::TransactionOptions,
     types::{FieldType, OperationReturnType},
 };
 
@@ -29,6 +30,7 @@ pub struct ModuleMethod {
     pub arguments: Vec<Argument>,
     pub access: Access,
     pub return_type: OperationReturnType<ModuleType>,
+    pub transaction: TransactionOptions,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
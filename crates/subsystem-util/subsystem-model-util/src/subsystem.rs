@@ -20,6 +20,7 @@ use serde::{Deserialize, Serialize};
 
 use crate::{
     interceptor::Interceptor,
+    job::Job,
     module::ModuleMethod,
     operation::{ModuleMutation, ModuleQuery},
     types::ModuleType,
@@ -42,6 +43,7 @@ pub struct ModuleSubsystem {
     pub methods: SerializableSlab<ModuleMethod>,
     pub scripts: SerializableSlab<Script>,
     pub interceptors: SerializableSlab<Interceptor>,
+    pub jobs: SerializableSlab<Job>,
 }
 
 impl ModuleSubsystem {
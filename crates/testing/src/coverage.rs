@@ -0,0 +1,525 @@
+// Copyright Exograph, Inc. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file at the root of this repository.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! Access rule coverage (`exo test --coverage`).
+//!
+//! Each testfile records the access decisions made for its operations (through the request's
+//! access trace) against the rules its model declares. Once all tests finish, we report, for each
+//! model file, how many times each rule allowed, denied, or filtered (i.e. evaluated to a residual
+//! predicate), so that rules never exercised by the tests stand out.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Write as _,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use anyhow::{Context, Result};
+use builder::parser::parse_str;
+use codemap::CodeMap;
+use colored::Colorize;
+use core_plugin_interface::core_model_builder::ast::ast_types::{AstAnnotation, Untyped};
+use core_resolver::access_trace::{AccessDecision, AccessRule, AccessTraceEntry};
+use serde::Serialize;
+
+pub struct CoverageOptions {
+    pub format: CoverageFormat,
+    /// The file to write an lcov or JSON report to (defaults to `access-coverage.lcov` or
+    /// `access-coverage.json` in the current directory)
+    pub output: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CoverageFormat {
+    Text,
+    Lcov,
+    Json,
+}
+
+impl CoverageFormat {
+    fn default_output(&self) -> Option<&'static str> {
+        match self {
+            CoverageFormat::Text => None,
+            CoverageFormat::Lcov => Some("access-coverage.lcov"),
+            CoverageFormat::Json => Some("access-coverage.json"),
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, Serialize)]
+pub(crate) struct DecisionCounts {
+    allowed: usize,
+    denied: usize,
+    filtered: usize,
+}
+
+impl DecisionCounts {
+    fn total(&self) -> usize {
+        self.allowed + self.denied + self.filtered
+    }
+
+    fn merge(&mut self, other: &DecisionCounts) {
+        self.allowed += other.allowed;
+        self.denied += other.denied;
+        self.filtered += other.filtered;
+    }
+}
+
+/// The decisions recorded for each rule of a model
+#[derive(Debug, Default)]
+pub(crate) struct AccessCoverage {
+    rules: BTreeMap<AccessRule, DecisionCounts>,
+}
+
+impl AccessCoverage {
+    pub fn new(rules: Vec<AccessRule>) -> Self {
+        Self {
+            rules: rules
+                .into_iter()
+                .map(|rule| (rule, DecisionCounts::default()))
+                .collect(),
+        }
+    }
+
+    pub fn record(&mut self, entries: Vec<AccessTraceEntry>) {
+        for entry in entries {
+            let counts = self.rules.entry(entry.access_rule()).or_default();
+            match entry.decision {
+                AccessDecision::Allowed => counts.allowed += 1,
+                AccessDecision::Denied => counts.denied += 1,
+                AccessDecision::Filtered => counts.filtered += 1,
+            }
+        }
+    }
+
+    fn merge(&mut self, other: AccessCoverage) {
+        for (rule, counts) in other.rules {
+            self.rules.entry(rule).or_default().merge(&counts);
+        }
+    }
+}
+
+/// Coverage collected across testfiles (shared by the threads running them)
+#[derive(Clone, Default)]
+pub(crate) struct CoverageCollector(Arc<Mutex<BTreeMap<PathBuf, AccessCoverage>>>);
+
+impl CoverageCollector {
+    pub fn add(&self, project_dir: &Path, coverage: AccessCoverage) {
+        self.0
+            .lock()
+            .unwrap()
+            .entry(project_dir.to_path_buf())
+            .or_default()
+            .merge(coverage);
+    }
+
+    pub fn report(
+        &self,
+        root_directory: &Path,
+        format: CoverageFormat,
+        output: Option<&Path>,
+    ) -> Result<()> {
+        let mut files: BTreeMap<PathBuf, Vec<RuleCoverage>> = BTreeMap::new();
+
+        for (project_dir, coverage) in self.0.lock().unwrap().iter() {
+            let source_index = SourceIndex::load(&project_dir.join("src"))?;
+
+            for (rule, counts) in coverage.rules.iter() {
+                let (file, line) = source_index.locate(rule);
+                let file = project_dir.join(file);
+                let file = file
+                    .strip_prefix(root_directory)
+                    .map(Path::to_path_buf)
+                    .unwrap_or(file);
+
+                files.entry(file).or_default().push(RuleCoverage {
+                    rule: rule.rule.clone(),
+                    operation: rule.operation.clone(),
+                    line,
+                    counts: *counts,
+                });
+            }
+        }
+
+        for rules in files.values_mut() {
+            rules.sort_by_key(|rule| rule.line);
+        }
+
+        print!("{}", text_report(&files));
+
+        if let Some(default_output) = format.default_output() {
+            let output = output
+                .map(Path::to_path_buf)
+                .unwrap_or_else(|| PathBuf::from(default_output));
+
+            let report = match format {
+                CoverageFormat::Lcov => lcov_report(&files),
+                _ => serde_json::to_string_pretty(&json_report(&files))?,
+            };
+
+            std::fs::write(&output, report).with_context(|| {
+                format!("While writing the coverage report to {}", output.display())
+            })?;
+
+            println!(
+                "{} {}",
+                "* Access rule coverage written to".blue().bold(),
+                output.display()
+            );
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct RuleCoverage {
+    rule: String,
+    operation: String,
+    line: usize,
+    #[serde(flatten)]
+    counts: DecisionCounts,
+}
+
+#[derive(Serialize)]
+struct FileCoverage<'a> {
+    file: &'a Path,
+    rules: &'a [RuleCoverage],
+}
+
+fn text_report(files: &BTreeMap<PathBuf, Vec<RuleCoverage>>) -> String {
+    let mut report = format!("{}\n", "* Access rule coverage".blue().bold());
+
+    for (file, rules) in files {
+        let covered = rules.iter().filter(|rule| rule.counts.total() > 0).count();
+        writeln!(
+            report,
+            "{} ({covered} of {} rules exercised)",
+            file.display().to_string().bold(),
+            rules.len(),
+        )
+        .unwrap();
+
+        for rule in rules {
+            let name = format!("{} ({})", rule.rule, rule.operation);
+            let counts = rule.counts;
+            let status = if counts.total() == 0 {
+                "not exercised".red().to_string()
+            } else {
+                format!(
+                    "allowed: {}, denied: {}, filtered: {}",
+                    counts.allowed, counts.denied, counts.filtered
+                )
+            };
+            writeln!(report, "  {:>5}: {name:<40} {status}", rule.line).unwrap();
+        }
+    }
+
+    report
+}
+
+/// An lcov report, where each rule is a function (named such as `Concert#query`) whose branches
+/// are the allowed, denied, and filtered decisions
+fn lcov_report(files: &BTreeMap<PathBuf, Vec<RuleCoverage>>) -> String {
+    let mut report = String::new();
+
+    for (file, rules) in files {
+        writeln!(report, "SF:{}", file.display()).unwrap();
+
+        for rule in rules {
+            writeln!(report, "FN:{},{}#{}", rule.line, rule.rule, rule.operation).unwrap();
+        }
+        for rule in rules {
+            writeln!(
+                report,
+                "FNDA:{},{}#{}",
+                rule.counts.total(),
+                rule.rule,
+                rule.operation
+            )
+            .unwrap();
+        }
+        let functions_hit = rules.iter().filter(|rule| rule.counts.total() > 0).count();
+        writeln!(report, "FNF:{}\nFNH:{functions_hit}", rules.len()).unwrap();
+
+        for (block, rule) in rules.iter().enumerate() {
+            let counts = rule.counts;
+            for (branch, taken) in [counts.allowed, counts.denied, counts.filtered]
+                .into_iter()
+                .enumerate()
+            {
+                let taken = if counts.total() == 0 {
+                    "-".to_string()
+                } else {
+                    taken.to_string()
+                };
+                writeln!(report, "BRDA:{},{block},{branch},{taken}", rule.line).unwrap();
+            }
+        }
+        let branches_hit = rules
+            .iter()
+            .map(|rule| {
+                [
+                    rule.counts.allowed,
+                    rule.counts.denied,
+                    rule.counts.filtered,
+                ]
+                .into_iter()
+                .filter(|taken| *taken > 0)
+                .count()
+            })
+            .sum::<usize>();
+        writeln!(report, "BRF:{}\nBRH:{branches_hit}", rules.len() * 3).unwrap();
+
+        let mut lines: BTreeMap<usize, usize> = BTreeMap::new();
+        for rule in rules {
+            *lines.entry(rule.line).or_default() += rule.counts.total();
+        }
+        for (line, count) in lines.iter() {
+            writeln!(report, "DA:{line},{count}").unwrap();
+        }
+        let lines_hit = lines.values().filter(|count| **count > 0).count();
+        writeln!(report, "LF:{}\nLH:{lines_hit}", lines.len()).unwrap();
+
+        writeln!(report, "end_of_record").unwrap();
+    }
+
+    report
+}
+
+fn json_report(files: &BTreeMap<PathBuf, Vec<RuleCoverage>>) -> Vec<FileCoverage> {
+    files
+        .iter()
+        .map(|(file, rules)| FileCoverage { file, rules })
+        .collect()
+}
+
+/// Where the rules of the types, fields, and module methods are declared in the model's source
+/// files (the line of the `@access` annotation or, without one, of the declaration)
+#[derive(Debug, Default)]
+struct SourceIndex {
+    declarations: HashMap<String, (PathBuf, usize)>,
+    default_file: PathBuf,
+}
+
+impl SourceIndex {
+    fn load(src_dir: &Path) -> Result<Self> {
+        fn collect(dir: &Path, files: &mut Vec<PathBuf>) -> Result<()> {
+            for entry in std::fs::read_dir(dir)? {
+                let path = entry?.path();
+                if path.is_dir() {
+                    collect(&path, files)?;
+                } else if path.extension().map(|ext| ext == "exo").unwrap_or(false) {
+                    files.push(path);
+                }
+            }
+            Ok(())
+        }
+
+        let mut files = vec![];
+        collect(src_dir, &mut files)
+            .with_context(|| format!("While reading model files in {}", src_dir.display()))?;
+        files.sort();
+
+        let project_dir = src_dir.parent().unwrap_or(src_dir);
+        let sources = files
+            .into_iter()
+            .map(|file| Ok((std::fs::read_to_string(&file)?, file)))
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Self::from_sources(project_dir, &sources))
+    }
+
+    /// Index the declarations in the sources (each with the path to the file, which the parser
+    /// uses to resolve imports)
+    fn from_sources(project_dir: &Path, sources: &[(String, PathBuf)]) -> Self {
+        let relative_path = |file: &Path| {
+            file.strip_prefix(project_dir)
+                .map(Path::to_path_buf)
+                .unwrap_or_else(|_| file.to_path_buf())
+        };
+
+        let mut declarations = HashMap::new();
+        let mut codemap = CodeMap::new();
+
+        for (content, file) in sources {
+            // The tests have already built the model, so a file fails to parse only if it changed
+            // since; its rules get the default location
+            let Ok(system) = parse_str(content, &mut codemap, file) else {
+                continue;
+            };
+
+            let file = relative_path(file);
+            let mut declare = |name: String, annotations: &[AstAnnotation<Untyped>], span| {
+                let span = annotations
+                    .iter()
+                    .find(|annotation| annotation.name == "access")
+                    .map(|annotation| annotation.span)
+                    .unwrap_or(span);
+                let line = codemap.look_up_span(span).begin.line + 1;
+
+                declarations
+                    .entry(name)
+                    .or_insert_with(|| (file.clone(), line));
+            };
+
+            let types = system
+                .types
+                .iter()
+                .chain(system.modules.iter().flat_map(|module| module.types.iter()));
+            for typ in types {
+                declare(typ.name.clone(), &typ.annotations, typ.span);
+
+                for field in typ.fields.iter() {
+                    declare(
+                        format!("{}.{}", typ.name, field.name),
+                        &field.annotations,
+                        field.span,
+                    );
+                }
+            }
+
+            for method in system
+                .modules
+                .iter()
+                .flat_map(|module| module.methods.iter())
+            {
+                declare(method.name.clone(), &method.annotations, method.span);
+            }
+        }
+
+        Self {
+            declarations,
+            default_file: sources
+                .first()
+                .map(|(_, file)| relative_path(file))
+                .unwrap_or_else(|| PathBuf::from("src/index.exo")),
+        }
+    }
+
+    /// The file (relative to the project directory) and the line where the rule is declared (the
+    /// first line of the first file, if we can't find it)
+    fn locate(&self, rule: &AccessRule) -> (PathBuf, usize) {
+        self.declarations
+            .get(&rule.rule)
+            .cloned()
+            .unwrap_or_else(|| (self.default_file.clone(), 1))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(rule: &str, operation: &str) -> AccessRule {
+        AccessRule {
+            rule: rule.to_string(),
+            operation: operation.to_string(),
+        }
+    }
+
+    #[test]
+    fn locate_rules() {
+        let source = r#"
+@postgres
+module ConcertModule {
+  // type Commented {
+  @access(query=true, mutation=AuthContext.role == "admin")
+  type Concert {
+    @pk id: Int = autoIncrement()
+    @access(query=AuthContext.role == "admin") internalNotes: String
+    venue: Venue
+  }
+
+  @access(true)
+  type Venue {
+    @pk id: Int = autoIncrement()
+    concerts: Set<Concert>?
+  }
+}
+
+@deno("util.ts")
+module UtilModule {
+  @access(true)
+  query concertCount(): Int
+}
+"#;
+        let index = SourceIndex::from_sources(
+            Path::new("concerts"),
+            &[(source.to_string(), PathBuf::from("concerts/src/index.exo"))],
+        );
+
+        let locate = |name: &str| index.locate(&rule(name, "query"));
+
+        assert_eq!(locate("Concert"), (PathBuf::from("src/index.exo"), 5));
+        assert_eq!(locate("Concert.internalNotes").1, 8);
+        assert_eq!(locate("Venue").1, 12);
+        assert_eq!(locate("Venue.concerts").1, 15);
+        assert_eq!(locate("concertCount").1, 21);
+        assert_eq!(locate("Unknown"), (PathBuf::from("src/index.exo"), 1));
+    }
+
+    #[test]
+    fn lcov() {
+        let mut coverage =
+            AccessCoverage::new(vec![rule("Concert", "query"), rule("Concert", "delete")]);
+        coverage.record(vec![
+            AccessTraceEntry {
+                rule: "Concert".to_string(),
+                operation: "query".to_string(),
+                decision: AccessDecision::Allowed,
+                residual: None,
+            },
+            AccessTraceEntry {
+                rule: "Concert".to_string(),
+                operation: "query".to_string(),
+                decision: AccessDecision::Filtered,
+                residual: Some("concerts.published = true".to_string()),
+            },
+        ]);
+
+        let files = BTreeMap::from([(
+            PathBuf::from("concerts/src/index.exo"),
+            coverage
+                .rules
+                .into_iter()
+                .map(|(rule, counts)| RuleCoverage {
+                    rule: rule.rule,
+                    operation: rule.operation,
+                    line: 6,
+                    counts,
+                })
+                .collect::<Vec<_>>(),
+        )]);
+
+        assert_eq!(
+            lcov_report(&files),
+            "SF:concerts/src/index.exo\n\
+             FN:6,Concert#delete\n\
+             FN:6,Concert#query\n\
+             FNDA:0,Concert#delete\n\
+             FNDA:2,Concert#query\n\
+             FNF:2\n\
+             FNH:1\n\
+             BRDA:6,0,0,-\n\
+             BRDA:6,0,1,-\n\
+             BRDA:6,0,2,-\n\
+             BRDA:6,1,0,1\n\
+             BRDA:6,1,1,0\n\
+             BRDA:6,1,2,1\n\
+             BRF:6\n\
+             BRH:2\n\
+             DA:6,2\n\
+             LF:1\n\
+             LH:1\n\
+             end_of_record\n"
+        );
+    }
+}
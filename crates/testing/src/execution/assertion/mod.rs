@@ -7,7 +7,10 @@
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
 
-use std::collections::{HashMap, HashSet};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+};
 
 use anyhow::{anyhow, Result};
 use exo_deno::{
@@ -18,6 +21,27 @@ use exo_deno::{
 
 const ASSERT_JS: &str = include_str!("./assert.js");
 
+/// A failed assertion along with the expected and actual payloads (so that reporters can show the
+/// difference)
+#[derive(Debug)]
+pub struct AssertionFailure {
+    pub message: String,
+    pub expected: String,
+    pub actual: String,
+}
+
+impl fmt::Display for AssertionFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{}\n➞ Expected: \n{}\n➞ Got: \n{}",
+            self.message, self.expected, self.actual
+        )
+    }
+}
+
+impl std::error::Error for AssertionFailure {}
+
 // Assert that a stringified 'JSON' payload is equal to a JSON object.
 //
 // `expected` is a string and not a proper JSON value because it is meant to be evaluated
@@ -64,6 +88,7 @@ pub async fn dynamic_assert_using_deno(
                 .into_iter()
                 .collect(),
                 npm_snapshot: None,
+                permissions: None,
             },
         },
         "ExographTest",
@@ -76,6 +101,7 @@ pub async fn dynamic_assert_using_deno(
         Some("ExographError"),
         None,
         None,
+        None,
     )
     .await?;
 
@@ -90,13 +116,10 @@ pub async fn dynamic_assert_using_deno(
             ],
         )
         .await
-        .map_err(|e| {
-            anyhow!(
-                "{}\n➞ Expected: \n{}\n➞ Got: \n{}\n",
-                e,
-                expected,
-                serde_json::to_string_pretty(&actual).unwrap()
-            )
+        .map_err(|e| AssertionFailure {
+            message: e.to_string(),
+            expected: expected.to_string(),
+            actual: serde_json::to_string_pretty(&actual).unwrap(),
         })?;
 
     Ok(())
@@ -131,6 +154,7 @@ pub async fn evaluate_using_deno(
                 .into_iter()
                 .collect(),
                 npm_snapshot: None,
+                permissions: None,
             },
         },
         "ExographTest",
@@ -141,6 +165,7 @@ pub async fn evaluate_using_deno(
         None,
         None,
         None,
+        None,
     )
     .await?;
 
@@ -32,15 +32,20 @@ use unescape::unescape;
 use std::net::{IpAddr, Ipv4Addr};
 use std::path::PathBuf;
 use std::sync::mpsc::Sender;
-use std::time::Duration;
-use std::{collections::HashMap, time::SystemTime};
+use std::time::{Duration, Instant};
+use std::{
+    collections::{BTreeMap, HashMap},
+    time::SystemTime,
+};
 
 use exo_env::MapEnvironment;
 
+use crate::coverage::{AccessCoverage, CoverageCollector};
 use crate::model::{resolve_testvariable, IntegrationTest, IntegrationTestOperation};
 
 use super::assertion::{dynamic_assert_using_deno, evaluate_using_deno};
-use super::{TestResult, TestResultKind};
+use super::update::{render_response, update_testfile};
+use super::{FailedOperation, StageTiming, TestResult, TestResultKind};
 
 #[derive(Serialize)]
 struct ExoPost {
@@ -55,6 +60,10 @@ struct TestfileContext {
     jwtsecret: String,
     cookies: HashMap<String, String>,
     testvariables: HashMap<String, serde_json::Value>,
+    /// The test variables bound through `@bind` (a subset of `testvariables`)
+    bound_variables: BTreeMap<String, serde_json::Value>,
+    /// The access decisions made so far (if running with coverage)
+    access_coverage: Option<AccessCoverage>,
 }
 
 impl IntegrationTest {
@@ -62,15 +71,21 @@ impl IntegrationTest {
         &self,
         project_dir: &PathBuf,
         ephemeral_database: &dyn EphemeralDatabaseServer,
+        coverage_collector: Option<&CoverageCollector>,
+        update: bool,
         tx: Sender<Result<TestResult>>,
     ) {
         let mut retries_left = self.retries;
         let mut pause = 1000;
         loop {
-            let result =
-                std::panic::AssertUnwindSafe(self.run_no_retry(project_dir, ephemeral_database))
-                    .catch_unwind()
-                    .await;
+            let result = std::panic::AssertUnwindSafe(self.run_no_retry(
+                project_dir,
+                ephemeral_database,
+                coverage_collector,
+                update,
+            ))
+            .catch_unwind()
+            .await;
 
             if result.is_err() {
                 // Don't retry after a panic
@@ -103,11 +118,24 @@ impl IntegrationTest {
         &self,
         project_dir: &PathBuf,
         ephemeral_database: &dyn EphemeralDatabaseServer,
+        coverage_collector: Option<&CoverageCollector>,
+        update: bool,
     ) -> Result<TestResult> {
         let log_prefix = format!("({})\n :: ", self.name()).purple();
 
         let db_instance_name = format!("exotest_{:x}", md5::compute(self.name()));
 
+        // track the time spent in each stage (for reporters)
+        let mut stages = vec![];
+        let mut stage_start = Instant::now();
+        let mut end_stage = |stage: &'static str| {
+            stages.push(StageTiming {
+                stage,
+                duration: stage_start.elapsed(),
+            });
+            stage_start = Instant::now();
+        };
+
         // create a database
         let db_instance = ephemeral_database.create_database(&db_instance_name)?;
 
@@ -150,6 +178,8 @@ impl IntegrationTest {
                 bail!("Could not verify schema for {}", self.name());
             }
 
+            end_stage("schema");
+
             // spawn a exo instance
             println!("{log_prefix} Initializing exo-server ...");
 
@@ -187,11 +217,18 @@ impl IntegrationTest {
                 create_system_resolver(&exo_ir_file, static_loaders, Box::new(env)).await?
             };
 
+            end_stage("server");
+
+            let access_coverage =
+                coverage_collector.map(|_| AccessCoverage::new(server.access_rules()));
+
             TestfileContext {
                 server,
                 jwtsecret,
                 cookies: HashMap::new(),
                 testvariables: HashMap::new(),
+                bound_variables: BTreeMap::new(),
+                access_coverage,
             }
         };
 
@@ -203,52 +240,103 @@ impl IntegrationTest {
             })?;
 
             match result {
-                OperationResult::Finished => {}
-                OperationResult::AssertFailed(_) | OperationResult::AssertPassed => {
+                OperationResult::Finished { .. } => {}
+                OperationResult::AssertFailed { .. } | OperationResult::AssertPassed => {
                     panic!("did not expect assertions in setup")
                 }
             }
         }
+        end_stage("init");
 
         // run test
         println!("{log_prefix} Testing ...");
 
         let mut fail = None;
-        for operation in self.test_operations.iter() {
+        let mut failed_operation = None;
+        // The new responses of the stages to update (in the update mode, failed assertions and
+        // missing responses don't fail the test, but we update the stage with the actual response)
+        let mut updated_responses = BTreeMap::new();
+        for (index, operation) in self.test_operations.iter().enumerate() {
             let result = run_operation(operation, &mut ctx)
                 .await
                 .with_context(|| anyhow!("While running tests for {}", self.name()));
 
             match result {
                 Ok(op_result) => match op_result {
-                    OperationResult::AssertPassed | OperationResult::Finished => {}
-                    OperationResult::AssertFailed(e) => {
-                        fail = Some(TestResultKind::Fail(e));
-                        break;
+                    OperationResult::AssertPassed => {}
+                    OperationResult::Finished { actual }
+                    | OperationResult::AssertFailed { actual, .. }
+                        if update =>
+                    {
+                        updated_responses.insert(
+                            index,
+                            render_response(
+                                &actual,
+                                &operation.operations_metadata.bindings,
+                                &ctx.bound_variables,
+                            ),
+                        );
+                    }
+                    OperationResult::Finished { .. } => {}
+                    OperationResult::AssertFailed { error, .. } => {
+                        fail = Some(TestResultKind::Fail(error));
                     }
                 },
 
                 Err(e) => {
                     fail = Some(TestResultKind::SetupFail(e));
-                    break;
                 }
             };
+
+            if fail.is_some() {
+                failed_operation = Some(FailedOperation {
+                    index,
+                    document: operation.document.clone(),
+                });
+                break;
+            }
+        }
+        end_stage("test");
+
+        if fail.is_none() && !updated_responses.is_empty() {
+            update_testfile(&self.testfile_path, &updated_responses)?;
+            println!(
+                "{log_prefix} Updated {} response(s) in {}",
+                updated_responses.len(),
+                self.testfile_path.display()
+            );
         }
 
         let success = fail.unwrap_or(TestResultKind::Success);
 
+        if let (Some(coverage_collector), Some(access_coverage)) =
+            (coverage_collector, ctx.access_coverage.take())
+        {
+            coverage_collector.add(project_dir, access_coverage);
+        }
+
         Ok(TestResult {
             log_prefix: log_prefix.to_string(),
             result: success,
+            project_dir: project_dir.clone(),
+            name: self.name(),
+            stages,
+            failed_operation,
         })
         // implicit ctx drop
     }
 }
 
 enum OperationResult {
-    Finished,
+    /// The operation finished (and there was no expected response to assert against)
+    Finished {
+        actual: Value,
+    },
     AssertPassed,
-    AssertFailed(anyhow::Error),
+    AssertFailed {
+        error: anyhow::Error,
+        actual: Value,
+    },
 }
 
 pub struct MemoryRequest {
@@ -372,11 +460,21 @@ async fn run_operation(
     }
 
     let request_context = RequestContext::new(&request, vec![], &ctx.server)?;
+
+    // Record access decisions (only needed for coverage)
+    let access_trace = ctx.access_coverage.as_ref().map(|_| {
+        let access_trace = request_context.get_base_context().access_trace.clone();
+        access_trace.enable();
+        access_trace
+    });
+
     let operations_payload = OperationsPayload {
         operation_name: None,
         query: Some(query),
         variables: Some(variables_map),
         query_hash: None,
+        access_trace: false,
+        sql: None,
     };
 
     // run the operation
@@ -388,6 +486,13 @@ async fn run_operation(
     )
     .await;
 
+    if let (Some(access_coverage), Some(entries)) = (
+        ctx.access_coverage.as_mut(),
+        access_trace.and_then(|access_trace| access_trace.take()),
+    ) {
+        access_coverage.record(entries);
+    }
+
     // resolve testvariables from the result of our current operation
     // and extend our collection with them
     let resolved_variables_keys = operations_metadata.bindings.keys().cloned();
@@ -400,14 +505,15 @@ async fn run_operation(
     let resolved_variables: HashMap<_, _> = resolved_variables_keys
         .zip(resolved_variables_values)
         .collect();
-    ctx.testvariables.extend(resolved_variables);
+    ctx.testvariables.extend(resolved_variables.clone());
+    ctx.bound_variables.extend(resolved_variables);
 
     match expected_payload {
         Some(expected_payload) => {
             // expected response specified - do an assertion
             match dynamic_assert_using_deno(
                 expected_payload,
-                body,
+                body.clone(),
                 &deno_prelude,
                 &ctx.testvariables,
                 &operations_metadata.unordered_paths,
@@ -415,14 +521,17 @@ async fn run_operation(
             .await
             {
                 Ok(()) => Ok(OperationResult::AssertPassed),
-                Err(e) => Ok(OperationResult::AssertFailed(e)),
+                Err(error) => Ok(OperationResult::AssertFailed {
+                    error,
+                    actual: body,
+                }),
             }
         }
 
         None => {
             // don't need to check anything
 
-            Ok(OperationResult::Finished)
+            Ok(OperationResult::Finished { actual: body })
         }
     }
 }
@@ -27,7 +27,7 @@ use common::env_const::{
     EXO_CHECK_CONNECTION_ON_STARTUP, EXO_CONNECTION_POOL_SIZE, EXO_INTROSPECTION, EXO_POSTGRES_URL,
 };
 
-use super::{TestResult, TestResultKind};
+use super::{StageTiming, TestResult, TestResultKind};
 
 use super::integration_test::{run_query, MemoryRequest};
 
@@ -37,6 +37,7 @@ const GRAPHQL_NODE_MODULE: Dir<'static> = include_dir!("$CARGO_MANIFEST_DIR/node
 pub(super) async fn run_introspection_test(model_path: &Path) -> Result<TestResult> {
     let log_prefix = format!("(introspection: {})\n :: ", model_path.display()).purple();
     println!("{log_prefix} Running introspection tests...");
+    let start_time = std::time::Instant::now();
 
     let exo_ir_file = format!("{}/target/index.exo_ir", model_path.display()).to_string();
 
@@ -55,17 +56,20 @@ pub(super) async fn run_introspection_test(model_path: &Path) -> Result<TestResu
 
     let result = check_introspection(&server).await?;
 
-    match result {
-        Ok(()) => Ok(TestResult {
-            log_prefix: log_prefix.to_string(),
-            result: TestResultKind::Success,
-        }),
-
-        Err(e) => Ok(TestResult {
-            log_prefix: log_prefix.to_string(),
-            result: TestResultKind::Fail(e),
-        }),
-    }
+    Ok(TestResult {
+        log_prefix: log_prefix.to_string(),
+        result: match result {
+            Ok(()) => TestResultKind::Success,
+            Err(e) => TestResultKind::Fail(e),
+        },
+        project_dir: model_path.to_path_buf(),
+        name: "introspection".to_string(),
+        stages: vec![StageTiming {
+            stage: "test",
+            duration: start_time.elapsed(),
+        }],
+        failed_operation: None,
+    })
 }
 
 async fn check_introspection(server: &SystemResolver) -> Result<Result<()>> {
@@ -87,6 +91,7 @@ async fn check_introspection(server: &SystemResolver) -> Result<Result<()>> {
                 .into_iter()
                 .collect(),
                 npm_snapshot: None,
+                permissions: None,
             },
         },
         "ExographTest",
@@ -117,6 +122,7 @@ async fn check_introspection(server: &SystemResolver) -> Result<Result<()>> {
                 .unwrap()
                 .replace("process.env.NODE_ENV === 'production'", "false"),
         )]),
+        None,
     )
     .await?;
 
@@ -135,6 +141,8 @@ async fn check_introspection(server: &SystemResolver) -> Result<Result<()>> {
         },
         variables: None,
         query_hash: None,
+        access_trace: false,
+        sql: None,
     };
 
     let result = run_query(
@@ -12,5 +12,7 @@ mod integration_test;
 mod introspection_tests;
 mod result;
 mod test_suite;
+mod update;
 
-pub(crate) use result::{TestResult, TestResultKind};
+pub(crate) use assertion::AssertionFailure;
+pub(crate) use result::{FailedOperation, StageTiming, TestResult, TestResultKind};
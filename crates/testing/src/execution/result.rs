@@ -11,7 +11,7 @@ use anyhow::Error;
 
 use colored::Colorize;
 
-use std::fmt;
+use std::{fmt, path::PathBuf, time::Duration};
 
 /// The result of running a testfile.
 pub enum TestResultKind {
@@ -56,11 +56,32 @@ impl PartialOrd for TestResultKind {
     }
 }
 
+/// Time spent in a stage of running a testfile (such as initializing the schema)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StageTiming {
+    pub stage: &'static str,
+    pub duration: Duration,
+}
+
+/// The operation of a testfile that failed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FailedOperation {
+    /// The (zero-based) index of the operation in the testfile
+    pub index: usize,
+    pub document: String,
+}
+
 // Represents the result of a test.
 #[derive(PartialEq, Eq)]
 pub struct TestResult {
     pub log_prefix: String,
     pub result: TestResultKind,
+    /// The directory containing the model under test
+    pub project_dir: PathBuf,
+    /// The name of the testfile (or "introspection" for introspection tests)
+    pub name: String,
+    pub stages: Vec<StageTiming>,
+    pub failed_operation: Option<FailedOperation>,
 }
 
 impl TestResult {
@@ -11,7 +11,7 @@ use std::{
 use exo_sql::testing::db::EphemeralDatabaseServer;
 
 use super::introspection_tests::run_introspection_test;
-use crate::model::TestSuite;
+use crate::{coverage::CoverageCollector, model::TestSuite};
 
 use super::TestResult;
 
@@ -20,6 +20,8 @@ impl TestSuite {
         self,
         run_introspection_tests: bool,
         ephemeral_server: Arc<Box<dyn EphemeralDatabaseServer + Send + Sync>>,
+        coverage_collector: Option<CoverageCollector>,
+        update: bool,
         tx: Sender<Result<TestResult>>,
         tasks: crossbeam_channel::Sender<Box<dyn FnOnce() + Send>>,
     ) {
@@ -58,6 +60,8 @@ impl TestSuite {
                             test.run(
                                 &project_dir,
                                 ephemeral_server.as_ref().as_ref() as &dyn EphemeralDatabaseServer,
+                                coverage_collector.as_ref(),
+                                update,
                                 tx.clone(),
                             )
                             .await;
@@ -0,0 +1,520 @@
+// Copyright Exograph, Inc. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file at the root of this repository.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! Support for `exo test --update`, which rewrites the `response` of stages with the actual
+//! responses.
+//!
+//! We edit the testfile's text (instead of re-serializing it) to preserve its formatting: only the
+//! `response` blocks of the updated stages change.
+
+use std::{collections::BTreeMap, path::Path};
+
+use anyhow::{bail, Context, Result};
+use serde_json::Value;
+
+use crate::model::TestvariableBindings;
+
+/// Render the expected response for an actual response. Values of bound test variables (through
+/// `@bind`) are rendered as references to them (such as `$.concertId` or `$.venueIds[1]`):
+/// - if the current operation binds the value (i.e. the binding's path is the value's path)
+/// - if a variable bound earlier has the same value, and the value is a string or an id (to avoid
+///   replacing incidental matches such as a count that happens to match an id)
+pub(super) fn render_response(
+    actual: &Value,
+    operation_bindings: &TestvariableBindings,
+    bound_variables: &BTreeMap<String, Value>,
+) -> String {
+    let mut operation_bindings: Vec<_> = operation_bindings.iter().collect();
+    operation_bindings.sort();
+
+    let mut bound_values = vec![];
+    for (name, value) in bound_variables {
+        flatten_bound_value(format!("$.{name}"), value, &mut bound_values);
+    }
+
+    let renderer = ResponseRenderer {
+        operation_bindings,
+        bound_values,
+    };
+
+    let mut rendered = String::new();
+    renderer.render(actual, None, &mut vec![], &mut vec![], 0, &mut rendered);
+    rendered
+}
+
+fn flatten_bound_value<'a>(
+    expr: String,
+    value: &'a Value,
+    bound_values: &mut Vec<(String, &'a Value)>,
+) {
+    match value {
+        Value::Array(elements) => {
+            for (index, element) in elements.iter().enumerate() {
+                flatten_bound_value(format!("{expr}[{index}]"), element, bound_values);
+            }
+        }
+        Value::Object(_) => {}
+        _ => bound_values.push((expr, value)),
+    }
+}
+
+struct ResponseRenderer<'a> {
+    operation_bindings: Vec<(&'a String, &'a Vec<String>)>,
+    bound_values: Vec<(String, &'a Value)>,
+}
+
+impl ResponseRenderer<'_> {
+    fn render(
+        &self,
+        value: &Value,
+        key: Option<&str>,
+        field_path: &mut Vec<String>,
+        indices: &mut Vec<usize>,
+        indent: usize,
+        out: &mut String,
+    ) {
+        let inner_indent = " ".repeat(indent + 2);
+        let closing_indent = " ".repeat(indent);
+
+        match value {
+            Value::Object(fields) if !fields.is_empty() => {
+                out.push_str("{\n");
+                for (index, (field_name, field_value)) in fields.iter().enumerate() {
+                    out.push_str(&inner_indent);
+                    out.push_str(&Value::String(field_name.clone()).to_string());
+                    out.push_str(": ");
+
+                    field_path.push(field_name.clone());
+                    self.render(
+                        field_value,
+                        Some(field_name),
+                        field_path,
+                        indices,
+                        indent + 2,
+                        out,
+                    );
+                    field_path.pop();
+
+                    if index + 1 < fields.len() {
+                        out.push(',');
+                    }
+                    out.push('\n');
+                }
+                out.push_str(&closing_indent);
+                out.push('}');
+            }
+            Value::Array(elements) if !elements.is_empty() => {
+                out.push_str("[\n");
+                for (index, element) in elements.iter().enumerate() {
+                    out.push_str(&inner_indent);
+
+                    indices.push(index);
+                    self.render(element, key, field_path, indices, indent + 2, out);
+                    indices.pop();
+
+                    if index + 1 < elements.len() {
+                        out.push(',');
+                    }
+                    out.push('\n');
+                }
+                out.push_str(&closing_indent);
+                out.push(']');
+            }
+            _ => match self.reference(value, key, field_path, indices) {
+                Some(reference) => out.push_str(&reference),
+                None => out.push_str(&value.to_string()),
+            },
+        }
+    }
+
+    fn reference(
+        &self,
+        value: &Value,
+        key: Option<&str>,
+        field_path: &[String],
+        indices: &[usize],
+    ) -> Option<String> {
+        if value.is_null() || value.is_boolean() {
+            return None;
+        }
+
+        let bound_here = self
+            .operation_bindings
+            .iter()
+            .find(|(_, path)| path.as_slice() == field_path);
+
+        if let Some((name, _)) = bound_here {
+            let indices: String = indices.iter().map(|index| format!("[{index}]")).collect();
+            return Some(format!("$.{name}{indices}"));
+        }
+
+        let is_id = key
+            .map(|key| key == "id" || key.ends_with("Id") || key.ends_with("_id"))
+            .unwrap_or(false);
+        let eligible = match value {
+            Value::String(value) => !value.is_empty(),
+            Value::Number(_) => is_id,
+            _ => false,
+        };
+
+        if eligible {
+            self.bound_values
+                .iter()
+                .find(|(_, bound_value)| *bound_value == value)
+                .map(|(expr, _)| expr.clone())
+        } else {
+            None
+        }
+    }
+}
+
+/// Replace the `response` of the given stages (by their index) in the testfile
+pub(super) fn update_testfile(
+    testfile_path: &Path,
+    responses: &BTreeMap<usize, String>,
+) -> Result<()> {
+    let contents = std::fs::read_to_string(testfile_path)
+        .with_context(|| format!("Could not read {}", testfile_path.display()))?;
+    let updated = update_responses(&contents, responses)
+        .with_context(|| format!("Could not update {}", testfile_path.display()))?;
+    std::fs::write(testfile_path, updated)
+        .with_context(|| format!("Could not write {}", testfile_path.display()))
+}
+
+/// A stage in a testfile: the range of its lines and the column of its keys
+struct StageLines {
+    start: usize,
+    end: usize,
+    key_column: usize,
+}
+
+fn update_responses(contents: &str, responses: &BTreeMap<usize, String>) -> Result<String> {
+    let mut lines: Vec<String> = contents.lines().map(str::to_string).collect();
+    let stages = stage_lines(&lines);
+
+    // Update from the last stage, so that the line ranges of the earlier stages remain valid
+    for (stage_index, response) in responses.iter().rev() {
+        let Some(stage) = stages.get(*stage_index) else {
+            bail!("Could not find stage {} in the testfile", stage_index + 1);
+        };
+
+        let key_line = (stage.start..stage.end).find(|line_index| {
+            let line = &lines[*line_index];
+            line.get(stage.key_column..)
+                .map(|key| key.starts_with("response:"))
+                .unwrap_or(false)
+                && line[..stage.key_column]
+                    .trim_start()
+                    .trim_start_matches('-')
+                    .trim()
+                    .is_empty()
+        });
+
+        match key_line {
+            Some(key_line) => {
+                // The value continues while lines are blank or indented more than the key (but
+                // trailing blank lines separate stages, so leave them alone)
+                let mut value_end = key_line + 1;
+                while value_end < stage.end
+                    && (lines[value_end].trim().is_empty()
+                        || indentation(&lines[value_end]) > stage.key_column)
+                {
+                    value_end += 1;
+                }
+                while value_end > key_line + 1 && lines[value_end - 1].trim().is_empty() {
+                    value_end -= 1;
+                }
+
+                let content_indent = lines[key_line + 1..value_end]
+                    .iter()
+                    .find(|line| !line.trim().is_empty())
+                    .map(|line| indentation(line))
+                    .unwrap_or(stage.key_column + 2);
+
+                let mut replacement = vec![format!(
+                    "{}response: |",
+                    &lines[key_line][..stage.key_column]
+                )];
+                replacement.extend(indent_block(response, content_indent));
+                lines.splice(key_line..value_end, replacement);
+            }
+            None => {
+                let insert_at = (stage.start..stage.end)
+                    .rev()
+                    .find(|line_index| !lines[*line_index].trim().is_empty())
+                    .map(|line_index| line_index + 1)
+                    .unwrap_or(stage.end);
+
+                let mut addition = vec![format!("{}response: |", " ".repeat(stage.key_column))];
+                addition.extend(indent_block(response, stage.key_column + 2));
+                lines.splice(insert_at..insert_at, addition);
+            }
+        }
+    }
+
+    let mut updated = lines.join("\n");
+    if contents.ends_with('\n') {
+        updated.push('\n');
+    }
+    Ok(updated)
+}
+
+/// The stages of a testfile: the items of `stages` or, for a single-stage testfile, the whole file
+fn stage_lines(lines: &[String]) -> Vec<StageLines> {
+    let Some(stages_line) = lines.iter().position(|line| line.trim_end() == "stages:") else {
+        return vec![StageLines {
+            start: 0,
+            end: lines.len(),
+            key_column: 0,
+        }];
+    };
+
+    let is_item =
+        |line: &str, column: usize| indentation(line) == column && line[column..].starts_with('-');
+
+    let Some(item_column) = lines[stages_line + 1..]
+        .iter()
+        .find(|line| line.trim_start().starts_with('-'))
+        .map(|line| indentation(line))
+    else {
+        return vec![];
+    };
+
+    let mut stages: Vec<StageLines> = vec![];
+    for (line_index, line) in lines.iter().enumerate().skip(stages_line + 1) {
+        let trimmed = line.trim_start();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+
+        if is_item(line, item_column) {
+            if let Some(previous) = stages.last_mut() {
+                previous.end = line_index;
+            }
+            let key_column = item_column + 1 + indentation(&line[item_column + 1..]);
+            stages.push(StageLines {
+                start: line_index,
+                end: lines.len(),
+                key_column,
+            });
+        } else if indentation(line) <= item_column {
+            // Another top-level key ends the list of stages
+            if let Some(previous) = stages.last_mut() {
+                previous.end = line_index;
+            }
+            break;
+        }
+    }
+
+    stages
+}
+
+fn indentation(line: &str) -> usize {
+    line.len() - line.trim_start_matches(' ').len()
+}
+
+fn indent_block(block: &str, indent: usize) -> Vec<String> {
+    block
+        .lines()
+        .map(|line| {
+            if line.is_empty() {
+                String::new()
+            } else {
+                format!("{}{line}", " ".repeat(indent))
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn render_with_references() {
+        let actual = json!({
+            "data": {
+                "createConcert": {
+                    "id": 3,
+                    "title": "C3",
+                    "venue": { "id": 1, "name": "V1" },
+                    "published": true,
+                    "attendees": 1,
+                    "tickets": [{ "id": 7 }, { "id": 8 }]
+                }
+            }
+        });
+
+        let operation_bindings = HashMap::from([
+            (
+                "concertId".to_string(),
+                vec!["data", "createConcert", "id"]
+                    .into_iter()
+                    .map(str::to_string)
+                    .collect(),
+            ),
+            (
+                "ticketIds".to_string(),
+                vec!["data", "createConcert", "tickets", "id"]
+                    .into_iter()
+                    .map(str::to_string)
+                    .collect(),
+            ),
+        ]);
+
+        let bound_variables = BTreeMap::from([
+            ("concertId".to_string(), json!(3)),
+            ("ticketIds".to_string(), json!([7, 8])),
+            ("venueIds".to_string(), json!([2, 1])),
+            ("venueName".to_string(), json!("V1")),
+        ]);
+
+        assert_eq!(
+            render_response(&actual, &operation_bindings, &bound_variables),
+            r#"{
+  "data": {
+    "createConcert": {
+      "id": $.concertId,
+      "title": "C3",
+      "venue": {
+        "id": $.venueIds[1],
+        "name": $.venueName
+      },
+      "published": true,
+      "attendees": 1,
+      "tickets": [
+        {
+          "id": $.ticketIds[0]
+        },
+        {
+          "id": $.ticketIds[1]
+        }
+      ]
+    }
+  }
+}"#
+        );
+    }
+
+    const RESPONSE: &str = "{\n  \"data\": {\n    \"venues\": []\n  }\n}";
+
+    #[test]
+    fn update_multi_stage() {
+        let testfile = r#"# Create and query venues
+stages:
+  - operation: |
+        mutation {
+          createVenue(data: {name: "V1"}) { id }
+        }
+    response: |
+      {
+        "data": {
+          "createVenue": {"id": 1}
+        }
+      }
+
+  - operation: |
+      query {
+        venues { id }
+      }
+    auth: |
+      {"role": "admin"}
+
+  - operation: |
+      query {
+        venues { id }
+      }
+    response: |
+      {"data": {"venues": [{"id": 1}]}}
+retries: 1
+"#;
+
+        let responses = BTreeMap::from([(1, RESPONSE.to_string()), (2, RESPONSE.to_string())]);
+
+        assert_eq!(
+            update_responses(testfile, &responses).unwrap(),
+            r#"# Create and query venues
+stages:
+  - operation: |
+        mutation {
+          createVenue(data: {name: "V1"}) { id }
+        }
+    response: |
+      {
+        "data": {
+          "createVenue": {"id": 1}
+        }
+      }
+
+  - operation: |
+      query {
+        venues { id }
+      }
+    auth: |
+      {"role": "admin"}
+    response: |
+      {
+        "data": {
+          "venues": []
+        }
+      }
+
+  - operation: |
+      query {
+        venues { id }
+      }
+    response: |
+      {
+        "data": {
+          "venues": []
+        }
+      }
+retries: 1
+"#
+        );
+    }
+
+    #[test]
+    fn update_single_stage() {
+        let testfile = r#"operation: |
+  query {
+    venues { id }
+  }
+response: |
+    {"data": {"venues": [{"id": 1}]}}
+
+envs:
+  EXO_INTROSPECTION: "true"
+"#;
+
+        let responses = BTreeMap::from([(0, RESPONSE.to_string())]);
+
+        assert_eq!(
+            update_responses(testfile, &responses).unwrap(),
+            r#"operation: |
+  query {
+    venues { id }
+  }
+response: |
+    {
+      "data": {
+        "venues": []
+      }
+    }
+
+envs:
+  EXO_INTROSPECTION: "true"
+"#
+        );
+    }
+}
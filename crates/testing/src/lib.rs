@@ -7,9 +7,11 @@
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
 
+mod coverage;
 pub(crate) mod execution;
 pub(crate) mod loader;
 mod model;
+mod report;
 
 use std::cmp::min;
 use std::path::PathBuf;
@@ -20,8 +22,12 @@ use colored::Colorize;
 
 use exo_sql::testing::db::EphemeralDatabaseLauncher;
 
+use coverage::CoverageCollector;
 use model::TestSuite;
 
+pub use coverage::{CoverageFormat, CoverageOptions};
+pub use report::{ReporterFormat, ReporterOptions};
+
 #[cfg(test)]
 use ctor::ctor;
 
@@ -37,6 +43,9 @@ pub fn run(
     root_directory: &PathBuf,
     pattern: &Option<String>,
     run_introspection_tests: bool,
+    coverage: Option<CoverageOptions>,
+    reporter: Option<ReporterOptions>,
+    update: bool,
 ) -> Result<()> {
     // Make sure deno runtime is initialized in the main thread to work around deno segfault
     // on Linux issue. The tests are run in parallel and will initialize the deno module
@@ -75,10 +84,14 @@ pub fn run(
 
     let ephemeral_server = Arc::new(EphemeralDatabaseLauncher::create_server()?);
 
+    let coverage_collector = coverage.as_ref().map(|_| CoverageCollector::default());
+
     for project_test in project_tests {
         project_test.run(
             run_introspection_tests,
             ephemeral_server.clone(),
+            coverage_collector.clone(),
+            update,
             tx.clone(),
             tasks.clone(),
         );
@@ -140,6 +153,14 @@ pub fn run(
         }
     }
 
+    if let Some(reporter) = reporter {
+        report::write_report(&test_results, root_directory, start_time.elapsed(), &reporter)?;
+    }
+
+    if let (Some(coverage), Some(coverage_collector)) = (coverage, coverage_collector) {
+        coverage_collector.report(root_directory, coverage.format, coverage.output.as_deref())?;
+    }
+
     let number_of_tests = test_results.len();
     let success = number_of_succeeded_tests == number_of_tests;
     let status = if success {
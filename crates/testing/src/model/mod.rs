@@ -12,7 +12,7 @@ use std::{collections::HashMap, path::PathBuf};
 mod operations_metadata;
 
 pub use operations_metadata::{
-    build_operations_metadata, resolve_testvariable, OperationsMetadata,
+    build_operations_metadata, resolve_testvariable, OperationsMetadata, TestvariableBindings,
 };
 
 /// Tests for a particular model
@@ -0,0 +1,512 @@
+// Copyright Exograph, Inc. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file at the root of this repository.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! Machine-readable reports of test results (`exo test --reporter junit|json`) for CI systems.
+
+use std::{
+    fmt::Write as _,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use anyhow::{Context, Error, Result};
+use colored::Colorize;
+use serde::Serialize;
+
+use crate::execution::{AssertionFailure, TestResult, TestResultKind};
+
+pub struct ReporterOptions {
+    pub format: ReporterFormat,
+    /// The file to write the report to (defaults to `test-results.xml` or `test-results.json` in
+    /// the current directory)
+    pub output: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReporterFormat {
+    Junit,
+    Json,
+}
+
+pub(crate) fn write_report(
+    test_results: &[Result<TestResult>],
+    root_directory: &Path,
+    duration: Duration,
+    options: &ReporterOptions,
+) -> Result<()> {
+    let report = TestReport::new(test_results, root_directory, duration);
+
+    let (content, default_output) = match options.format {
+        ReporterFormat::Junit => (report.to_junit(), "test-results.xml"),
+        ReporterFormat::Json => (serde_json::to_string_pretty(&report)?, "test-results.json"),
+    };
+
+    let output = options
+        .output
+        .clone()
+        .unwrap_or_else(|| PathBuf::from(default_output));
+
+    std::fs::write(&output, content)
+        .with_context(|| format!("While writing the test report to {}", output.display()))?;
+
+    println!(
+        "{} {}",
+        "* Test report written to".blue().bold(),
+        output.display()
+    );
+
+    Ok(())
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TestReport {
+    passed: usize,
+    failed: usize,
+    errored: usize,
+    /// In seconds
+    duration: f64,
+    tests: Vec<TestCaseReport>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TestCaseReport {
+    /// The model directory (relative to the root directory of the tests)
+    suite: String,
+    name: String,
+    status: TestStatus,
+    /// In seconds
+    duration: f64,
+    stages: Vec<StageReport>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    failure: Option<FailureReport>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+enum TestStatus {
+    Passed,
+    /// An assertion failed
+    Failed,
+    /// An operation couldn't run (for example, due to an invalid query)
+    SetupFailed,
+    /// The testfile couldn't run at all (for example, since the model didn't build)
+    Errored,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct StageReport {
+    stage: &'static str,
+    /// In seconds
+    duration: f64,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct FailureReport {
+    message: String,
+    /// The error along with its causes
+    details: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    operation: Option<OperationReport>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    expected: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    actual: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct OperationReport {
+    /// The (zero-based) index of the operation in the testfile
+    index: usize,
+    document: String,
+}
+
+impl TestReport {
+    fn new(test_results: &[Result<TestResult>], root_directory: &Path, duration: Duration) -> Self {
+        let tests: Vec<_> = test_results
+            .iter()
+            .enumerate()
+            .map(|(index, result)| match result {
+                Ok(result) => TestCaseReport::new(result, root_directory),
+                Err(e) => TestCaseReport {
+                    suite: "errors".to_string(),
+                    name: format!("error-{}", index + 1),
+                    status: TestStatus::Errored,
+                    duration: 0.0,
+                    stages: vec![],
+                    failure: Some(FailureReport::new(e)),
+                },
+            })
+            .collect();
+
+        let count = |status: &[TestStatus]| {
+            tests
+                .iter()
+                .filter(|test| status.contains(&test.status))
+                .count()
+        };
+
+        Self {
+            passed: count(&[TestStatus::Passed]),
+            failed: count(&[TestStatus::Failed, TestStatus::SetupFailed]),
+            errored: count(&[TestStatus::Errored]),
+            duration: duration.as_secs_f64(),
+            tests,
+        }
+    }
+
+    /// A JUnit XML report with a test suite for each model (stage timings are reported as the
+    /// properties of each test case)
+    fn to_junit(&self) -> String {
+        let mut suites: Vec<(&str, Vec<&TestCaseReport>)> = vec![];
+        for test in self.tests.iter() {
+            match suites.iter_mut().find(|(suite, _)| *suite == test.suite) {
+                Some((_, tests)) => tests.push(test),
+                None => suites.push((&test.suite, vec![test])),
+            }
+        }
+
+        let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        let (failures, errors) = junit_counts(&self.tests.iter().collect::<Vec<_>>());
+        writeln!(
+            xml,
+            r#"<testsuites name="exo test" tests="{}" failures="{failures}" errors="{errors}" time="{:.3}">"#,
+            self.tests.len(),
+            self.duration
+        )
+        .unwrap();
+
+        for (suite, tests) in suites {
+            let (failures, errors) = junit_counts(&tests);
+            writeln!(
+                xml,
+                r#"  <testsuite name="{}" tests="{}" failures="{failures}" errors="{errors}" time="{:.3}">"#,
+                escape_xml(suite),
+                tests.len(),
+                tests.iter().map(|test| test.duration).sum::<f64>()
+            )
+            .unwrap();
+
+            for test in tests {
+                test.write_junit(&mut xml);
+            }
+
+            xml.push_str("  </testsuite>\n");
+        }
+
+        xml.push_str("</testsuites>\n");
+        xml
+    }
+}
+
+impl TestCaseReport {
+    fn new(result: &TestResult, root_directory: &Path) -> Self {
+        let suite = result
+            .project_dir
+            .strip_prefix(root_directory)
+            .unwrap_or(&result.project_dir)
+            .display()
+            .to_string();
+
+        let (status, error) = match &result.result {
+            TestResultKind::Success => (TestStatus::Passed, None),
+            TestResultKind::Fail(e) => (TestStatus::Failed, Some(e)),
+            TestResultKind::SetupFail(e) => (TestStatus::SetupFailed, Some(e)),
+        };
+
+        let failure = error.map(|e| {
+            let mut failure = FailureReport::new(e);
+            failure.operation = result
+                .failed_operation
+                .as_ref()
+                .map(|operation| OperationReport {
+                    index: operation.index,
+                    document: operation.document.clone(),
+                });
+            failure
+        });
+
+        Self {
+            suite: if suite.is_empty() {
+                ".".to_string()
+            } else {
+                suite
+            },
+            name: result.name.clone(),
+            status,
+            duration: result
+                .stages
+                .iter()
+                .map(|stage| stage.duration)
+                .sum::<Duration>()
+                .as_secs_f64(),
+            stages: result
+                .stages
+                .iter()
+                .map(|stage| StageReport {
+                    stage: stage.stage,
+                    duration: stage.duration.as_secs_f64(),
+                })
+                .collect(),
+            failure,
+        }
+    }
+
+    fn write_junit(&self, xml: &mut String) {
+        write!(
+            xml,
+            r#"    <testcase classname="{}" name="{}" time="{:.3}""#,
+            escape_xml(&self.suite),
+            escape_xml(&self.name),
+            self.duration
+        )
+        .unwrap();
+
+        if self.stages.is_empty() && self.failure.is_none() {
+            xml.push_str("/>\n");
+            return;
+        }
+        xml.push_str(">\n");
+
+        if !self.stages.is_empty() {
+            xml.push_str("      <properties>\n");
+            for stage in self.stages.iter() {
+                writeln!(
+                    xml,
+                    r#"        <property name="stage.{}" value="{:.3}"/>"#,
+                    stage.stage, stage.duration
+                )
+                .unwrap();
+            }
+            xml.push_str("      </properties>\n");
+        }
+
+        if let Some(failure) = &self.failure {
+            let (element, kind) = match self.status {
+                TestStatus::Failed => ("failure", "assertion"),
+                TestStatus::SetupFailed => ("error", "setup"),
+                _ => ("error", "error"),
+            };
+
+            let mut body = String::new();
+            if let Some(operation) = &failure.operation {
+                writeln!(
+                    body,
+                    "Operation #{}:\n{}\n",
+                    operation.index + 1,
+                    operation.document.trim()
+                )
+                .unwrap();
+            }
+            match (&failure.expected, &failure.actual) {
+                (Some(expected), Some(actual)) => write!(
+                    body,
+                    "{}\n➞ Expected:\n{}\n➞ Got:\n{}",
+                    failure.message,
+                    expected.trim(),
+                    actual.trim()
+                )
+                .unwrap(),
+                _ => body.push_str(&failure.details),
+            }
+
+            writeln!(
+                xml,
+                r#"      <{element} message="{}" type="{kind}">{}</{element}>"#,
+                escape_xml(&failure.message),
+                escape_xml(&body)
+            )
+            .unwrap();
+        }
+
+        xml.push_str("    </testcase>\n");
+    }
+}
+
+impl FailureReport {
+    fn new(error: &Error) -> Self {
+        let assertion_failure = error
+            .chain()
+            .find_map(|cause| cause.downcast_ref::<AssertionFailure>());
+
+        Self {
+            message: match assertion_failure {
+                Some(assertion_failure) => assertion_failure.message.clone(),
+                None => error.to_string(),
+            },
+            details: format!("{error:#}"),
+            operation: None,
+            expected: assertion_failure.map(|failure| failure.expected.clone()),
+            actual: assertion_failure.map(|failure| failure.actual.clone()),
+        }
+    }
+}
+
+/// The number of failures and errors (assertion failures are JUnit failures, and everything else
+/// that failed is an error)
+fn junit_counts(tests: &[&TestCaseReport]) -> (usize, usize) {
+    let failures = tests
+        .iter()
+        .filter(|test| test.status == TestStatus::Failed)
+        .count();
+    let errors = tests
+        .iter()
+        .filter(|test| matches!(test.status, TestStatus::SetupFailed | TestStatus::Errored))
+        .count();
+    (failures, errors)
+}
+
+/// Escape a value for an XML attribute or text. Since XML 1.0 doesn't allow most control
+/// characters (even as character references), we drop them (for example, the ANSI color codes
+/// in an error message).
+fn escape_xml(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            '\t' | '\n' | '\r' => escaped.push(c),
+            '\u{0}'..='\u{1F}' | '\u{FFFE}' | '\u{FFFF}' => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use anyhow::anyhow;
+
+    use super::*;
+    use crate::execution::{FailedOperation, StageTiming};
+
+    fn test_result(name: &str, result: TestResultKind) -> TestResult {
+        TestResult {
+            log_prefix: name.to_string(),
+            result,
+            project_dir: PathBuf::from("integration-tests/concerts"),
+            name: name.to_string(),
+            stages: vec![
+                StageTiming {
+                    stage: "schema",
+                    duration: Duration::from_millis(500),
+                },
+                StageTiming {
+                    stage: "test",
+                    duration: Duration::from_millis(250),
+                },
+            ],
+            failed_operation: None,
+        }
+    }
+
+    fn report() -> TestReport {
+        let mut failing = test_result(
+            "query-venues",
+            TestResultKind::Fail(
+                anyhow::Error::new(AssertionFailure {
+                    message: "assertion failed at 'data.venues': expected 2, got 1".to_string(),
+                    expected: "{\"data\": {\"venues\": 2}}".to_string(),
+                    actual: "{\"data\": {\"venues\": 1}}".to_string(),
+                })
+                .context("While running tests for query-venues"),
+            ),
+        );
+        failing.failed_operation = Some(FailedOperation {
+            index: 1,
+            document: "query { venues }".to_string(),
+        });
+
+        TestReport::new(
+            &[
+                Ok(test_result("query-concerts", TestResultKind::Success)),
+                Ok(failing),
+                Err(anyhow!("Could not build the exo_ir.")),
+            ],
+            Path::new("integration-tests"),
+            Duration::from_secs(2),
+        )
+    }
+
+    #[test]
+    fn junit() {
+        assert_eq!(
+            report().to_junit(),
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<testsuites name="exo test" tests="3" failures="1" errors="1" time="2.000">
+  <testsuite name="concerts" tests="2" failures="1" errors="0" time="1.500">
+    <testcase classname="concerts" name="query-concerts" time="0.750">
+      <properties>
+        <property name="stage.schema" value="0.500"/>
+        <property name="stage.test" value="0.250"/>
+      </properties>
+    </testcase>
+    <testcase classname="concerts" name="query-venues" time="0.750">
+      <properties>
+        <property name="stage.schema" value="0.500"/>
+        <property name="stage.test" value="0.250"/>
+      </properties>
+      <failure message="assertion failed at &apos;data.venues&apos;: expected 2, got 1" type="assertion">Operation #2:
+query { venues }
+
+assertion failed at &apos;data.venues&apos;: expected 2, got 1
+➞ Expected:
+{&quot;data&quot;: {&quot;venues&quot;: 2}}
+➞ Got:
+{&quot;data&quot;: {&quot;venues&quot;: 1}}</failure>
+    </testcase>
+  </testsuite>
+  <testsuite name="errors" tests="1" failures="0" errors="1" time="0.000">
+    <testcase classname="errors" name="error-3" time="0.000">
+      <error message="Could not build the exo_ir." type="error">Could not build the exo_ir.</error>
+    </testcase>
+  </testsuite>
+</testsuites>
+"#
+        );
+    }
+
+    #[test]
+    fn json() {
+        let report = serde_json::to_value(report()).unwrap();
+
+        assert_eq!(report["passed"], 1);
+        assert_eq!(report["failed"], 1);
+        assert_eq!(report["errored"], 1);
+
+        let failing = &report["tests"][1];
+        assert_eq!(failing["status"], "failed");
+        assert_eq!(failing["stages"][0]["stage"], "schema");
+        assert_eq!(failing["failure"]["operation"]["index"], 1);
+        assert_eq!(
+            failing["failure"]["expected"],
+            "{\"data\": {\"venues\": 2}}"
+        );
+        assert_eq!(failing["failure"]["actual"], "{\"data\": {\"venues\": 1}}");
+
+        assert!(report["tests"][0].get("failure").is_none());
+    }
+
+    #[test]
+    fn escape_invalid_xml_characters() {
+        assert_eq!(
+            escape_xml("\u{1b}[31mfailed\u{1b}[0m: a < b\tc\r\n\u{0}\u{8}\u{FFFF}é"),
+            "[31mfailed[0m: a &lt; b\tc\r\né"
+        );
+    }
+}
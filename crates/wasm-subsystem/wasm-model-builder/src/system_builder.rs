@@ -48,6 +48,7 @@ pub async fn build(
     if underlying_module_system.queries.is_empty()
         && underlying_module_system.mutations.is_empty()
         && underlying_module_system.interceptors.is_empty()
+        && underlying_module_system.jobs.is_empty()
     {
         return Ok(None);
     }
@@ -71,6 +72,7 @@ pub async fn build(
             methods: underlying_module_system.methods,
             scripts: underlying_module_system.scripts,
             interceptors: underlying_module_system.interceptors,
+            jobs: underlying_module_system.jobs,
         },
         interceptors: module_system.interceptors,
     }))
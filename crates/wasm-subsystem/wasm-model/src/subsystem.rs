@@ -10,6 +10,7 @@
 use super::module::Script;
 use crate::{
     interceptor::Interceptor,
+    job::Job,
     module::ModuleMethod,
     operation::{WasmMutation, WasmQuery},
     types::ModuleType,
@@ -41,6 +42,7 @@ pub struct WasmSubsystem {
     pub methods: SerializableSlab<ModuleMethod>,
     pub scripts: SerializableSlab<Script>,
     pub interceptors: SerializableSlab<Interceptor>,
+    pub jobs: SerializableSlab<Job>,
 }
 
 impl WasmSubsystem {
@@ -14,9 +14,11 @@ use core_plugin_interface::{
     core_model::mapped_arena::SerializableSlabIndex,
     core_resolver::{
         context::RequestContext,
+        job::JobDefinition,
         plugin::{SubsystemResolutionError, SubsystemResolver},
         system_resolver::SystemResolver,
         validation::field::ValidatedField,
+        value::Val,
         InterceptedOperation, QueryResponse,
     },
     interception::InterceptorIndex,
@@ -127,6 +129,117 @@ impl SubsystemResolver for WasmSubsystemResolver {
         Err(SubsystemResolutionError::NoInterceptorFound)
     }
 
+    fn jobs(&self) -> Vec<JobDefinition> {
+        self.subsystem
+            .jobs
+            .iter()
+            .map(|(_, job)| JobDefinition {
+                name: job.method_name.clone(),
+                schedule: job.schedule.clone(),
+                enqueueable: true,
+            })
+            .collect()
+    }
+
+    async fn invoke_job<'a>(
+        &'a self,
+        name: &'a str,
+        arguments: &'a serde_json::Value,
+        request_context: &'a RequestContext<'a>,
+        _system_resolver: &'a SystemResolver,
+    ) -> Result<Option<()>, SubsystemResolutionError> {
+        let Some((_, job)) = self
+            .subsystem
+            .jobs
+            .iter()
+            .find(|(_, job)| job.method_name == name)
+        else {
+            return Ok(None);
+        };
+
+        request_context.ensure_transaction().await;
+
+        let script = &self.subsystem.scripts[job.script];
+
+        // Similar to operations, only integer arguments are supported for now
+        let args = job
+            .arguments
+            .iter()
+            .map(
+                |arg| match arguments.get(&arg.name).and_then(|value| value.as_i64()) {
+                    Some(value) => Ok(wasmtime::Val::from(value as i32)),
+                    None => Err(WasmExecutionError::InvalidArgument(arg.name.clone())),
+                },
+            )
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| SubsystemResolutionError::UserDisplayError(e.to_string()))?;
+
+        self.executor
+            .execute(&script.path, &script.script, &job.method_name, args)
+            .await
+            .map_err(|e| SubsystemResolutionError::UserDisplayError(e.to_string()))?;
+
+        Ok(Some(()))
+    }
+
+    async fn invoke_access_check<'a>(
+        &'a self,
+        function_name: &'a str,
+        arguments: &'a [Val],
+        _request_context: &'a RequestContext<'a>,
+        _system_resolver: &'a SystemResolver,
+    ) -> Result<Option<Val>, SubsystemResolutionError> {
+        // Only queries may decide access (mutations may have side effects)
+        let Some(query) = self.subsystem.queries.get_by_key(function_name) else {
+            return Ok(None);
+        };
+        let method = &self.subsystem.methods[query.method_id.unwrap()];
+
+        let script = &self.subsystem.scripts[method.script];
+
+        let parameters: Vec<_> = method
+            .arguments
+            .iter()
+            .filter(|arg| !arg.is_injected)
+            .collect();
+
+        if parameters.len() != arguments.len() {
+            return Err(SubsystemResolutionError::UserDisplayError(format!(
+                "Access check `{}` expects {} argument(s), but the access rule passes {}",
+                method.name,
+                parameters.len(),
+                arguments.len()
+            )));
+        }
+
+        // Similar to operations, only integer arguments are supported for now
+        let args = parameters
+            .into_iter()
+            .zip(arguments)
+            .map(|(arg, value)| match value {
+                Val::Number(value) => value
+                    .as_i64()
+                    .and_then(|value| i32::try_from(value).ok())
+                    .map(wasmtime::Val::from)
+                    .ok_or_else(|| WasmExecutionError::InvalidArgument(arg.name.clone())),
+                _ => Err(WasmExecutionError::InvalidArgument(arg.name.clone())),
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| SubsystemResolutionError::UserDisplayError(e.to_string()))?;
+
+        let result = self
+            .executor
+            .execute(&script.path, &script.script, &method.name, args)
+            .await
+            .map_err(|e| SubsystemResolutionError::UserDisplayError(e.to_string()))?;
+
+        // WASM functions can't return booleans, so treat a non-zero integer as `true`
+        Ok(Some(match result {
+            serde_json::Value::Number(value) => Val::Bool(value.as_i64() != Some(0)),
+            result => result.into(),
+        }))
+    }
+
     fn schema_queries(&self) -> Vec<FieldDefinition> {
         self.subsystem.schema_queries()
     }
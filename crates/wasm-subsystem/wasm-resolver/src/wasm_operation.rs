@@ -10,7 +10,10 @@
 use crate::{plugin::WasmSubsystemResolver, wasm_execution_error::WasmExecutionError};
 use core_plugin_interface::core_resolver::value::val::Val as ExoVal;
 use core_plugin_interface::core_resolver::{
-    context::RequestContext, system_resolver::SystemResolver, validation::field::ValidatedField,
+    context::RequestContext,
+    metrics::{record_wasm_execution, Timer},
+    system_resolver::SystemResolver,
+    validation::field::ValidatedField,
     QueryResponse, QueryResponseBody,
 };
 use std::collections::HashMap;
@@ -63,12 +66,16 @@ impl<'a> WasmOperation<'a> {
             })
             .collect();
 
+        let timer = Timer::start();
         let result = self
             .subsystem_resolver
             .executor
             .execute(&script.path, &script.script, &self.method.name, args)
-            .await
-            .map_err(WasmExecutionError::Wasm)?;
+            .await;
+        if let Some(duration) = timer.elapsed() {
+            record_wasm_execution(&self.method.name, duration);
+        }
+        let result = result.map_err(WasmExecutionError::Wasm)?;
 
         Ok(QueryResponse {
             body: QueryResponseBody::Json(result),
@@ -7,7 +7,7 @@
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
 
-use deno_core::Extension;
+use deno_core::{v8, Extension};
 use futures::pin_mut;
 use serde_json::Value;
 use std::fmt::Debug;
@@ -15,16 +15,16 @@ use std::{
     panic,
     sync::{
         atomic::{AtomicBool, Ordering},
-        Arc,
+        Arc, OnceLock,
     },
 };
 use tokio::sync::{
     mpsc::{Receiver, Sender},
-    oneshot, Mutex,
+    oneshot, watch, Mutex,
 };
 use tracing::instrument;
 
-use crate::deno_error::{DenoError, DenoInternalError};
+use crate::deno_error::{DenoError, DenoInternalError, DenoLimitError};
 use crate::deno_module::{Arg, DenoModule, DenoModuleSharedState, UserCode};
 
 struct DenoCall<C, R> {
@@ -54,6 +54,7 @@ struct DenoCall<C, R> {
 ///     - Waits for a request to execute call (sent by the `execute` method; see below) and forwards
 ///       it to the `DenoModule` instance.
 ///     - Sends the result to the sender of the request.
+///     - Exits if the actor has been terminated (see below).
 ///
 /// # Message flow:
 /// - A DenoActor may be asked to execute a JavaScript function by executing the `execute` method
@@ -67,6 +68,11 @@ struct DenoCall<C, R> {
 ///   callback message, it forwards that to the `callback_sender` and loops again. If it receives
 ///   the final result, it breaks the loop returning that result.
 ///
+/// # Termination:
+/// - A call that takes too long may be stopped using the `terminate` method (which terminates the
+///   V8 isolate's execution). A call that exceeds the heap limit terminates itself. In either case,
+///   the actor must not be used any further and its thread exits after the ongoing call completes.
+///
 /// # Type Parameters
 /// * `C` - The type of the call context. Call context is any value (such as the name of the current
 ///   operation) that the message processing may need
@@ -79,6 +85,11 @@ pub(crate) struct DenoActor<C, M, R> {
     // Sender to ask the actor to execute a JS/TS call. The actor will poll for messages on the corresponding receiver.
     call_sender: Sender<DenoCall<C, R>>,
     busy: Arc<std::sync::atomic::AtomicBool>,
+    terminated: Arc<AtomicBool>,
+    // Set once the DenoModule is created (used to terminate an ongoing execution)
+    isolate_handle: Arc<OnceLock<v8::IsolateHandle>>,
+    // Becomes true once the DenoModule is initialized
+    ready: watch::Receiver<bool>,
 }
 
 impl<C, M, R> DenoActor<C, M, R>
@@ -97,6 +108,7 @@ where
         explicit_error_class_name: Option<&'static str>,
         shared_state: DenoModuleSharedState,
         process_call_context: fn(&mut DenoModule, C) -> (),
+        max_heap_size: Option<usize>,
     ) -> Result<DenoActor<C, M, R>, DenoError> {
         let (callback_sender, callback_receiver) = tokio::sync::mpsc::channel(1);
 
@@ -104,7 +116,13 @@ where
         let (deno_call_sender, mut deno_call_receiver) = tokio::sync::mpsc::channel(1);
         let busy = Arc::new(AtomicBool::new(false));
 
+        let terminated = Arc::new(AtomicBool::new(false));
+        let isolate_handle = Arc::new(OnceLock::new());
+        let (ready_sender, ready) = watch::channel(false);
+
         let busy_clone = busy.clone();
+        let terminated_clone = terminated.clone();
+        let isolate_handle_clone = isolate_handle.clone();
 
         // start the DenoModule thread
         std::thread::spawn(move || {
@@ -131,6 +149,7 @@ where
                     explicit_error_class_name,
                     None,
                     None,
+                    max_heap_size,
                 )
                 .await;
 
@@ -142,11 +161,15 @@ where
                     }
                 };
 
+                let _ = isolate_handle_clone.set(deno_module.isolate_handle());
+
                 // store the request sender in Deno OpState for use by ops
                 deno_module
                     .put(callback_sender)
                     .unwrap_or_else(|_| panic!("Could not store request sender in DenoModule"));
 
+                let _ = ready_sender.send(true);
+
                 // start a receive loop
                 loop {
                     // yield and wait for a DenoCall message
@@ -160,7 +183,11 @@ where
                         None => break,
                     };
 
-                    busy_clone.store(true, Ordering::Relaxed); // mark DenoActor as busy
+                    if terminated_clone.load(Ordering::SeqCst) {
+                        break;
+                    }
+
+                    // (the actor is marked busy by `try_reserve` before sending us the call)
                     let _: Option<R> = deno_module.take().expect("take() should not have failed"); // clear any existing R from GothamStorage
 
                     process_call_context(&mut deno_module, call_context);
@@ -168,15 +195,31 @@ where
                     // execute function
                     let result = deno_module.execute_function(&method_name, arguments).await;
 
+                    let result = if deno_module.heap_limit_reached() {
+                        terminated_clone.store(true, Ordering::SeqCst);
+                        Err(DenoLimitError::HeapLimit.into())
+                    } else {
+                        result
+                    };
+
                     // take R from GothamStorage
                     let r: Option<R> = deno_module.take().expect("take() should not have failed");
 
-                    // send result of the Deno function back to call_method
-                    final_response_sender
-                        .send(result.map(|result| (result, r)))
-                        .expect("Could not send result in DenoActor thread");
+                    let terminated = terminated_clone.load(Ordering::SeqCst);
+
+                    // unmark DenoActor as busy (before sending the result, so that the actor is
+                    // available as soon as the caller releases it)
+                    if !terminated {
+                        busy_clone.store(false, Ordering::Release);
+                    }
+
+                    // send result of the Deno function back to call_method (the receiver is gone
+                    // if the caller timed out, so ignore any error)
+                    let _ = final_response_sender.send(result.map(|result| (result, r)));
 
-                    busy_clone.store(false, Ordering::Relaxed); // unmark DenoActor as busy
+                    if terminated {
+                        break;
+                    }
                 }
             });
         });
@@ -185,11 +228,47 @@ where
             callback_receiver: Arc::new(Mutex::new(callback_receiver)),
             call_sender: deno_call_sender,
             busy,
+            terminated,
+            isolate_handle,
+            ready,
         })
     }
 
     pub fn is_busy(&self) -> bool {
-        self.busy.load(Ordering::Relaxed)
+        self.busy.load(Ordering::Acquire)
+    }
+
+    pub fn is_terminated(&self) -> bool {
+        self.terminated.load(Ordering::SeqCst)
+    }
+
+    /// Mark the actor as busy if it is free (and not terminated). Returns true if successful.
+    pub fn try_reserve(&self) -> bool {
+        !self.is_terminated()
+            && self
+                .busy
+                .compare_exchange(false, true, Ordering::AcqRel, Ordering::Acquire)
+                .is_ok()
+    }
+
+    /// Wait until the underlying `DenoModule` is initialized (so that the next call doesn't incur the
+    /// cost of loading the module)
+    pub async fn wait_until_ready(&self) -> Result<(), DenoError> {
+        let mut ready = self.ready.clone();
+        ready.wait_for(|ready| *ready).await.map_err(|_| {
+            DenoInternalError::Channel(
+                "DenoActor thread exited before initializing the module".into(),
+            )
+        })?;
+        Ok(())
+    }
+
+    /// Terminate the ongoing execution (if any). The actor may not be used afterwards.
+    pub fn terminate(&self) {
+        self.terminated.store(true, Ordering::SeqCst);
+        if let Some(isolate_handle) = self.isolate_handle.get() {
+            isolate_handle.terminate_execution();
+        }
     }
 
     /// Call a deno method
@@ -263,6 +342,9 @@ impl<C, M, R> Clone for DenoActor<C, M, R> {
             callback_receiver: self.callback_receiver.clone(),
             call_sender: self.call_sender.clone(),
             busy: self.busy.clone(),
+            terminated: self.terminated.clone(),
+            isolate_handle: self.isolate_handle.clone(),
+            ready: self.ready.clone(),
         }
     }
 }
@@ -288,6 +370,7 @@ mod tests {
             EXPLICIT_ERROR_CLASS_NAME,
             DenoModuleSharedState::default(),
             |_, _| {},
+            None,
         )
         .unwrap();
 
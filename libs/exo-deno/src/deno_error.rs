@@ -11,6 +11,7 @@ use deno_core::{
     error::{AnyError, JsError},
     v8::DataError,
 };
+use std::time::Duration;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -30,11 +31,25 @@ pub enum DenoError {
     #[error("{0}")]
     Diagnostic(#[from] DenoDiagnosticError),
 
+    // A resource limit of the executor pool was exceeded
+    #[error("{0}")]
+    Limit(#[from] DenoLimitError),
+
     // An issue with this crate or a dependency
     #[error("{0}")]
     Internal(#[from] DenoInternalError),
 }
 
+#[derive(Error, Debug)]
+pub enum DenoLimitError {
+    #[error("Function execution did not complete within {0:?}")]
+    ExecutionTimeout(Duration),
+    #[error("No Deno worker became available within {0:?}")]
+    QueueTimeout(Duration),
+    #[error("Function execution exceeded the heap limit")]
+    HeapLimit,
+}
+
 #[derive(Error, Debug)]
 pub enum DenoDiagnosticError {
     #[error("Missing shim {0}")]
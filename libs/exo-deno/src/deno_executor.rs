@@ -8,13 +8,15 @@
 // by the Apache License, Version 2.0.
 
 use futures::pin_mut;
+use tokio::sync::OwnedSemaphorePermit;
 
-use crate::deno_error::DenoError;
+use crate::deno_error::{DenoError, DenoLimitError};
 
 use super::{deno_actor::DenoActor, deno_module::Arg};
 use async_trait::async_trait;
 use serde_json::Value;
 use std::fmt::Debug;
+use std::time::Duration;
 
 /// `DenoExecutor` provides a way to execute a method.
 ///
@@ -23,8 +25,13 @@ use std::fmt::Debug;
 /// Afterwards, it will kick off the execution by awaiting on the `DenoActor`'s asynchronous `execute` method.
 /// It will concurrently listen and handle requests from DenoActor sent through the channel by calling the
 /// `callback_processor` to resolve callbacks and responding with the final result.
+///
+/// If the execution doesn't complete within `timeout`, the actor is terminated.
 pub struct DenoExecutor<C, M, R> {
     pub(crate) actor: DenoActor<C, M, R>,
+    pub(crate) timeout: Option<Duration>,
+    // Released (making room for another execution of the same module) when the executor is dropped
+    pub(crate) _permit: OwnedSemaphorePermit,
 }
 
 #[async_trait]
@@ -64,6 +71,14 @@ impl<
 
         pin_mut!(on_function_result); // needs to be pinned to reuse it
 
+        let on_timeout = async {
+            match self.timeout {
+                Some(timeout) => tokio::time::sleep(timeout).await,
+                None => futures::future::pending().await,
+            }
+        };
+        pin_mut!(on_timeout);
+
         // receive loop
         loop {
             let on_recv_request = to_user_receiver.recv();
@@ -79,6 +94,14 @@ impl<
                     // function has resolved with the return value
                     break final_result;
                 },
+
+                _ = &mut on_timeout => {
+                    // the timeout is set when on_timeout completes
+                    let timeout = self.timeout.unwrap_or_default();
+                    tracing::warn!(?timeout, "Deno function execution timed out, terminating the isolate");
+                    self.actor.terminate();
+                    break Err(DenoLimitError::ExecutionTimeout(timeout).into());
+                },
             }
         }
     }
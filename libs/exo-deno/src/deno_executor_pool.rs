@@ -7,16 +7,20 @@
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
 
-use std::{collections::HashMap, marker::PhantomData, sync::Arc};
+use std::{collections::HashMap, marker::PhantomData, sync::Arc, time::Duration};
 
 use deno_core::{url::Url, Extension, ModuleType};
 use deno_npm::resolution::SerializedNpmResolutionSnapshot;
 use deno_virtual_fs::virtual_fs::VirtualDirectory;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use tokio::sync::Mutex;
+use tokio::sync::{Mutex, Semaphore};
 
-use crate::{deno_error::DenoError, Arg};
+use crate::{
+    deno_error::{DenoError, DenoLimitError},
+    deno_permissions::DenoPermissions,
+    Arg,
+};
 
 use super::{
     deno_actor::DenoActor,
@@ -27,7 +31,63 @@ use super::{
 use std::fmt::Debug;
 
 type DenoActorPoolMap<C, M, R> = HashMap<String, DenoActorPool<C, M, R>>;
-type DenoActorPool<C, M, R> = Vec<DenoActor<C, M, R>>;
+
+/// The actors for a module along with a semaphore to limit the number of concurrent executions
+struct DenoActorPool<C, M, R> {
+    actors: Vec<DenoActor<C, M, R>>,
+    semaphore: Arc<Semaphore>,
+    waiting: usize,
+    timed_out: u64,
+}
+
+impl<C, M, R> DenoActorPool<C, M, R> {
+    fn new(max_actors: usize) -> Self {
+        Self {
+            actors: vec![],
+            semaphore: Arc::new(Semaphore::new(max_actors)),
+            waiting: 0,
+            timed_out: 0,
+        }
+    }
+}
+
+/// Limits applied to the actors of a `DenoExecutorPool`
+#[derive(Clone, Debug)]
+pub struct DenoPoolConfig {
+    /// The maximum number of actors (and thus concurrent executions) per module. Any more calls
+    /// wait for an actor to become free.
+    pub max_actors_per_module: usize,
+    /// How long a call may wait for an actor before failing (`None` to wait indefinitely)
+    pub queue_timeout: Option<Duration>,
+    /// How long a call may execute before its isolate is terminated (`None` for no limit)
+    pub execution_timeout: Option<Duration>,
+    /// The maximum V8 heap size (in bytes) of each actor (`None` for V8's default)
+    pub max_heap_size: Option<usize>,
+}
+
+impl Default for DenoPoolConfig {
+    fn default() -> Self {
+        Self {
+            max_actors_per_module: 16,
+            queue_timeout: None,
+            execution_timeout: None,
+            max_heap_size: None,
+        }
+    }
+}
+
+/// A snapshot of a module's actor pool
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DenoPoolMetrics {
+    /// Number of live actors
+    pub actors: usize,
+    /// Number of actors currently executing a call
+    pub busy_actors: usize,
+    /// Number of calls waiting for an actor
+    pub waiting_calls: usize,
+    /// Number of calls that failed since they exceeded the queue or execution timeout
+    pub timed_out_calls: u64,
+}
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub enum ResolvedModule {
@@ -44,6 +104,10 @@ pub struct DenoScriptDefn {
         VirtualDirectory,
         Vec<Vec<u8>>,
     )>,
+    /// Permissions declared for the module (`None` if the module didn't declare any, in which case
+    /// the executor's default permissions apply)
+    #[serde(default)]
+    pub permissions: Option<DenoPermissions>,
 }
 
 pub struct DenoExecutorConfig<C> {
@@ -54,6 +118,8 @@ pub struct DenoExecutorConfig<C> {
     create_extensions: fn() -> Vec<Extension>,
     process_call_context: fn(&mut DenoModule, C) -> (),
     shared_state: DenoModuleSharedState,
+    default_permissions: DenoPermissions,
+    pool_config: DenoPoolConfig,
 }
 
 impl<C> DenoExecutorConfig<C> {
@@ -74,8 +140,21 @@ impl<C> DenoExecutorConfig<C> {
             create_extensions,
             process_call_context,
             shared_state,
+            default_permissions: DenoPermissions::default(),
+            pool_config: DenoPoolConfig::default(),
         }
     }
+
+    /// Set the permissions for scripts that don't declare their own (by default, everything is denied)
+    pub fn with_default_permissions(mut self, default_permissions: DenoPermissions) -> Self {
+        self.default_permissions = default_permissions;
+        self
+    }
+
+    pub fn with_pool_config(mut self, pool_config: DenoPoolConfig) -> Self {
+        self.pool_config = pool_config;
+        self
+    }
 }
 
 /// DenoExecutorPool maintains a pool of `DenoActor`s for each module to delegate work to.
@@ -83,6 +162,10 @@ impl<C> DenoExecutorConfig<C> {
 /// Calling `execute` will either select a free actor or allocate a new `DenoActor` to run the function on.
 /// It will create a `DenoExecutor` with that actor and delegate the method execution to it.
 ///
+/// The number of actors per module is bounded by `DenoPoolConfig::max_actors_per_module`. Once all
+/// of them are busy, calls wait (up to `DenoPoolConfig::queue_timeout`) for one to become free.
+/// Actors whose execution was terminated (due to a timeout or reaching the heap limit) are discarded.
+///
 /// The hierarchy of modules:
 ///
 /// DenoExecutorPool -> DenoExecutor -> DenoActor -> DenoModule
@@ -170,9 +253,78 @@ impl<
         callback_processor: impl CallbackProcessor<M>,
     ) -> Result<(Value, Option<R>), DenoError> {
         let executor = self.get_executor(script_path, script).await?;
-        executor
+        let result = executor
             .execute(method_name, arguments, call_context, callback_processor)
-            .await
+            .await;
+
+        if let Err(DenoError::Limit(DenoLimitError::ExecutionTimeout(_))) = &result {
+            if let Some(actor_pool) = self.actor_pool_map.lock().await.get_mut(script_path) {
+                actor_pool.timed_out += 1;
+            }
+        }
+
+        result
+    }
+
+    /// Metrics for the actor pool of each module (keyed by the script path)
+    pub async fn metrics(&self) -> HashMap<String, DenoPoolMetrics> {
+        let actor_pool_map = self.actor_pool_map.lock().await;
+
+        actor_pool_map
+            .iter()
+            .map(|(script_path, actor_pool)| {
+                let live_actors = actor_pool
+                    .actors
+                    .iter()
+                    .filter(|actor| !actor.is_terminated());
+
+                (
+                    script_path.clone(),
+                    DenoPoolMetrics {
+                        actors: live_actors.clone().count(),
+                        busy_actors: live_actors.filter(|actor| actor.is_busy()).count(),
+                        waiting_calls: actor_pool.waiting,
+                        timed_out_calls: actor_pool.timed_out,
+                    },
+                )
+            })
+            .collect()
+    }
+
+    /// Create actors for the module ahead of time (so that the first calls don't incur the cost of
+    /// loading the module) and wait for them to be ready. The module's pool is filled up to `count`
+    /// actors (but no more than `DenoPoolConfig::max_actors_per_module`).
+    pub async fn warm_up(
+        &self,
+        script_path: &str,
+        count: usize,
+        script: impl Fn() -> DenoScriptDefn,
+    ) -> Result<(), DenoError> {
+        let pool_config = &self.config.pool_config;
+
+        let new_actors = {
+            let mut actor_pool_map = self.actor_pool_map.lock().await;
+            let actor_pool = actor_pool_map
+                .entry(script_path.to_string())
+                .or_insert_with(|| DenoActorPool::new(pool_config.max_actors_per_module));
+
+            let new_actor_count = count
+                .min(pool_config.max_actors_per_module)
+                .saturating_sub(actor_pool.actors.len());
+
+            let new_actors = (0..new_actor_count)
+                .map(|_| self.create_actor(script_path, script()))
+                .collect::<Result<Vec<_>, _>>()?;
+
+            actor_pool.actors.extend(new_actors.iter().cloned());
+            new_actors
+        };
+
+        for actor in new_actors {
+            actor.wait_until_ready().await?;
+        }
+
+        Ok(())
     }
 
     // TODO: look at passing a fn pointer struct as an argument
@@ -181,33 +333,79 @@ impl<
         script_path: &str,
         script: DenoScriptDefn,
     ) -> Result<DenoExecutor<C, M, R>, DenoError> {
+        let pool_config = &self.config.pool_config;
+
+        let semaphore = {
+            let mut actor_pool_map = self.actor_pool_map.lock().await;
+            let actor_pool = actor_pool_map
+                .entry(script_path.to_string())
+                .or_insert_with(|| DenoActorPool::new(pool_config.max_actors_per_module));
+            actor_pool.waiting += 1;
+            actor_pool.semaphore.clone()
+        };
+
+        // wait for our turn (the semaphore is never closed, so `acquire_owned` cannot fail)
+        let permit = match pool_config.queue_timeout {
+            Some(queue_timeout) => tokio::time::timeout(queue_timeout, semaphore.acquire_owned())
+                .await
+                .ok(),
+            None => Some(semaphore.acquire_owned().await),
+        }
+        .map(|permit| permit.expect("Deno actor pool semaphore should not be closed"));
+
         // find or allocate a free actor in our pool
-        let actor = {
+        let (actor, permit) = {
             let mut actor_pool_map = self.actor_pool_map.lock().await;
-            let actor_pool = actor_pool_map.entry(script_path.to_string()).or_default();
+            let actor_pool = actor_pool_map
+                .get_mut(script_path)
+                .expect("Deno actor pool should have been created");
+            actor_pool.waiting -= 1;
+
+            let Some(permit) = permit else {
+                actor_pool.timed_out += 1;
+                return Err(DenoLimitError::QueueTimeout(
+                    pool_config.queue_timeout.unwrap_or_default(),
+                )
+                .into());
+            };
 
-            let free_actor = actor_pool.iter().find(|actor| !actor.is_busy());
+            // discard actors that can no longer be used
+            actor_pool.actors.retain(|actor| !actor.is_terminated());
 
-            if let Some(actor) = free_actor {
+            let free_actor = actor_pool.actors.iter().find(|actor| actor.try_reserve());
+
+            let actor = if let Some(actor) = free_actor {
                 // found a free actor!
                 actor.clone()
             } else {
-                // no free actors; need to allocate a new DenoActor
+                // no free actors (holding a permit guarantees that we are under the limit); need
+                // to allocate a new DenoActor
                 let new_actor = self.create_actor(script_path, script)?;
+                new_actor.try_reserve();
 
-                actor_pool.push(new_actor.clone());
+                actor_pool.actors.push(new_actor.clone());
                 new_actor
-            }
+            };
+
+            (actor, permit)
         };
 
-        Ok(DenoExecutor { actor })
+        Ok(DenoExecutor {
+            actor,
+            timeout: pool_config.execution_timeout,
+            _permit: permit,
+        })
     }
 
     fn create_actor(
         &self,
         script_path: &str,
-        script: DenoScriptDefn,
+        mut script: DenoScriptDefn,
     ) -> Result<DenoActor<C, M, R>, DenoError> {
+        script
+            .permissions
+            .get_or_insert_with(|| self.config.default_permissions.clone());
+
         DenoActor::new(
             UserCode::LoadFromMemory {
                 path: script_path.to_owned(),
@@ -220,6 +418,7 @@ impl<
             self.config.explicit_error_class_name,
             self.config.shared_state.clone(),
             self.config.process_call_context,
+            self.config.pool_config.max_heap_size,
         )
     }
 }
@@ -264,6 +463,7 @@ mod tests {
                     .into_iter()
                     .collect(),
                     npm_snapshot: None,
+                    permissions: None,
                 },
                 "addAndDouble",
                 vec![Arg::Serde(2.into()), Arg::Serde(3.into())],
@@ -316,6 +516,7 @@ mod tests {
                     .into_iter()
                     .collect(),
                     npm_snapshot: None,
+                    permissions: None,
                 },
                 method_name,
                 arguments,
@@ -348,4 +549,209 @@ mod tests {
 
         assert_eq!(result, total_futures);
     }
+
+    fn direct_js_script() -> DenoScriptDefn {
+        let module_path = "file://test_js/direct.js";
+        let module_script = include_str!("test_js/direct.js").to_string();
+
+        DenoScriptDefn {
+            modules: vec![(
+                ModuleSpecifier::parse(module_path).unwrap(),
+                ResolvedModule::Module(
+                    module_script,
+                    ModuleType::JavaScript,
+                    ModuleSpecifier::parse(module_path).unwrap(),
+                    false,
+                ),
+            )]
+            .into_iter()
+            .collect(),
+            npm_snapshot: None,
+            permissions: None,
+        }
+    }
+
+    fn pool_with_config(pool_config: DenoPoolConfig) -> DenoExecutorPool<(), (), ()> {
+        DenoExecutorPool::new_from_config(
+            DenoExecutorConfig::new(
+                "ExoDenoTest",
+                vec![],
+                vec![],
+                None,
+                Vec::new,
+                |_, _| {},
+                DenoModuleSharedState::default(),
+            )
+            .with_pool_config(pool_config),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_actor_executor_bounded() {
+        let module_path = "file://test_js/direct.js";
+
+        let executor_pool = pool_with_config(DenoPoolConfig {
+            max_actors_per_module: 2,
+            ..Default::default()
+        });
+
+        let handles = (1..=10).map(|_| {
+            executor_pool.execute(
+                module_path,
+                direct_js_script(),
+                "addAndDouble",
+                vec![Arg::Serde(4.into()), Arg::Serde(2.into())],
+                (),
+                (),
+            )
+        });
+
+        let results = join_all(handles).await;
+        assert!(results.iter().all(|res| res.as_ref().unwrap() == 12));
+
+        let metrics = executor_pool.metrics().await;
+        let module_metrics = &metrics[module_path];
+        assert!(module_metrics.actors <= 2);
+        assert_eq!(module_metrics.busy_actors, 0);
+        assert_eq!(module_metrics.waiting_calls, 0);
+    }
+
+    #[tokio::test]
+    async fn test_actor_executor_timeout() {
+        let module_path = "file://test_js/direct.js";
+
+        let executor_pool = pool_with_config(DenoPoolConfig {
+            max_actors_per_module: 1,
+            execution_timeout: Some(Duration::from_secs(1)),
+            ..Default::default()
+        });
+
+        let res = executor_pool
+            .execute(module_path, direct_js_script(), "spin", vec![], (), ())
+            .await;
+
+        assert!(matches!(
+            res,
+            Err(DenoError::Limit(DenoLimitError::ExecutionTimeout(_)))
+        ));
+
+        // the terminated actor must be replaced (and must not hold on to the only permit)
+        let res = executor_pool
+            .execute(
+                module_path,
+                direct_js_script(),
+                "addAndDouble",
+                vec![Arg::Serde(2.into()), Arg::Serde(3.into())],
+                (),
+                (),
+            )
+            .await;
+
+        assert_eq!(res.unwrap(), 10);
+
+        let metrics = executor_pool.metrics().await;
+        assert_eq!(
+            metrics[module_path],
+            DenoPoolMetrics {
+                actors: 1,
+                busy_actors: 0,
+                waiting_calls: 0,
+                timed_out_calls: 1,
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_actor_executor_warm_up() {
+        let module_path = "file://test_js/direct.js";
+
+        let executor_pool = pool_with_config(DenoPoolConfig {
+            max_actors_per_module: 2,
+            ..Default::default()
+        });
+
+        executor_pool
+            .warm_up(module_path, 3, direct_js_script)
+            .await
+            .unwrap();
+
+        assert_eq!(executor_pool.metrics().await[module_path].actors, 2);
+
+        let res = executor_pool
+            .execute(
+                module_path,
+                direct_js_script(),
+                "addAndDouble",
+                vec![Arg::Serde(2.into()), Arg::Serde(3.into())],
+                (),
+                (),
+            )
+            .await;
+
+        assert_eq!(res.unwrap(), 10);
+        assert_eq!(executor_pool.metrics().await[module_path].actors, 2);
+    }
+
+    #[tokio::test]
+    async fn test_actor_executor_permissions() {
+        // Each case uses a new pool, since a pool reuses the actors (and so the permissions) it
+        // created for a module
+        async fn read_env(
+            default_permissions: DenoPermissions,
+            permissions: Option<DenoPermissions>,
+        ) -> Result<Value, DenoError> {
+            let executor_pool = DenoExecutorPool::<(), (), ()>::new_from_config(
+                DenoExecutorConfig::new(
+                    "ExoDenoTest",
+                    vec![],
+                    vec![],
+                    None,
+                    Vec::new,
+                    |_, _| {},
+                    DenoModuleSharedState::default(),
+                )
+                .with_default_permissions(default_permissions),
+            );
+
+            executor_pool
+                .execute(
+                    "file://test_js/direct.js",
+                    DenoScriptDefn {
+                        permissions,
+                        ..direct_js_script()
+                    },
+                    "readEnv",
+                    vec![Arg::Serde("PATH".into())],
+                    (),
+                    (),
+                )
+                .await
+        }
+
+        let path = std::env::var("PATH").unwrap();
+        let allow_env = |name: &str| {
+            Some(DenoPermissions {
+                allow_env: vec![name.to_string()],
+                ..Default::default()
+            })
+        };
+
+        // Scripts that don't declare permissions get the default ones
+        assert!(read_env(DenoPermissions::default(), None).await.is_err());
+        assert_eq!(
+            read_env(DenoPermissions::allow_all(), None).await.unwrap(),
+            path
+        );
+
+        // Declared permissions grant only what they list (regardless of the default ones)
+        assert_eq!(
+            read_env(DenoPermissions::default(), allow_env("PATH"))
+                .await
+                .unwrap(),
+            path
+        );
+        assert!(read_env(DenoPermissions::allow_all(), allow_env("HOME"))
+            .await
+            .is_err());
+    }
 }
@@ -50,6 +50,8 @@ use std::collections::HashMap;
 use std::convert::TryFrom;
 use std::fs;
 use std::rc::Rc;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
 use std::sync::Arc;
 
 use crate::deno_error::DenoDiagnosticError;
@@ -78,6 +80,7 @@ pub struct DenoModule {
     shim_object_names: Vec<String>,
     user_code: UserCode,
     explicit_error_class_name: Option<&'static str>,
+    heap_limit_reached: Arc<AtomicBool>,
 }
 
 #[derive(Debug)]
@@ -198,6 +201,8 @@ impl NpmResolver for SnapshotNpmResolver {
 /// * `extra_sources` - A Vec of (URL, code) pairs to include in the source map.
 ///                     As the source map is the first thing queried during module resolution, this is useful for overriding
 ///                     scripts at certain paths with your own version.
+/// * `max_heap_size` - The maximum size (in bytes) of the V8 heap. Execution is terminated (instead of
+///                     aborting the process) upon reaching it.
 ///
 impl DenoModule {
     #[allow(clippy::too_many_arguments)]
@@ -211,6 +216,7 @@ impl DenoModule {
         explicit_error_class_name: Option<&'static str>,
         embedded_script_dirs: Option<HashMap<String, &'static Dir<'static>>>,
         extra_sources: Option<Vec<(&str, String)>>,
+        max_heap_size: Option<usize>,
     ) -> Result<Self, AnyError> {
         let shim_source_code = {
             let shims_source_codes: Vec<_> = shims
@@ -360,7 +366,8 @@ impl DenoModule {
                 argv0: None,
                 future: false,
             },
-            create_params: None,
+            create_params: max_heap_size
+                .map(|max_heap_size| v8::CreateParams::default().heap_limits(0, max_heap_size)),
             extensions,
             unsafely_ignore_certificate_errors: None,
             root_cert_store_provider: None,
@@ -389,11 +396,33 @@ impl DenoModule {
         };
 
         let main_module = deno_core::resolve_url(&main_module_specifier)?;
-        let permissions = PermissionsContainer::allow_all();
+        let permissions = match &user_code {
+            // Code loaded directly from the filesystem (tests and tooling) is trusted
+            UserCode::LoadFromFs(_) => PermissionsContainer::allow_all(),
+            UserCode::LoadFromMemory { script, .. } => script
+                .permissions
+                .clone()
+                .unwrap_or_default()
+                .to_container()?,
+        };
 
         let mut worker =
             MainWorker::bootstrap_from_options(main_module.clone(), permissions, options);
 
+        let heap_limit_reached = Arc::new(AtomicBool::new(false));
+        if max_heap_size.is_some() {
+            let isolate_handle = worker.js_runtime.v8_isolate().thread_safe_handle();
+            let heap_limit_reached = heap_limit_reached.clone();
+            worker
+                .js_runtime
+                .add_near_heap_limit_callback(move |current_limit, _initial_limit| {
+                    heap_limit_reached.store(true, Ordering::SeqCst);
+                    isolate_handle.terminate_execution();
+                    // Give V8 some room to unwind the terminated execution (otherwise, it will abort the process)
+                    current_limit * 2
+                });
+        }
+
         worker.execute_main_module(&main_module).await?;
 
         additional_code.iter().for_each(|code| {
@@ -411,6 +440,7 @@ impl DenoModule {
             shim_object_names,
             user_code,
             explicit_error_class_name,
+            heap_limit_reached,
         };
 
         Ok(deno_module)
@@ -537,6 +567,16 @@ impl DenoModule {
         }
     }
 
+    /// A handle to terminate the ongoing execution from another thread
+    pub fn isolate_handle(&mut self) -> v8::IsolateHandle {
+        self.worker.js_runtime.v8_isolate().thread_safe_handle()
+    }
+
+    /// Has the execution been terminated due to reaching the `max_heap_size`?
+    pub fn heap_limit_reached(&self) -> bool {
+        self.heap_limit_reached.load(Ordering::SeqCst)
+    }
+
     /// Put a single instance of a type into Deno's op_state
     pub fn put<T: 'static>(&mut self, val: T) -> Result<(), DenoError> {
         self.worker
@@ -629,6 +669,7 @@ mod tests {
             None,
             None,
             None,
+            None,
         )
         .await
         .unwrap();
@@ -664,6 +705,7 @@ mod tests {
             None,
             None,
             None,
+            None,
         )
         .await
         .unwrap();
@@ -707,6 +749,7 @@ mod tests {
             None,
             None,
             None,
+            None,
         )
         .await
         .unwrap();
@@ -757,6 +800,7 @@ mod tests {
             None,
             None,
             None,
+            None,
         )
         .await
         .unwrap();
@@ -833,6 +877,7 @@ mod tests {
             None,
             None,
             None,
+            None,
         )
         .await
         .unwrap();
@@ -864,6 +909,7 @@ mod tests {
             None,
             None,
             None,
+            None,
         )
         .await
         .unwrap();
@@ -0,0 +1,73 @@
+// Copyright Exograph, Inc. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file at the root of this repository.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::path::PathBuf;
+
+use deno_core::error::AnyError;
+use deno_runtime::permissions::{Permissions, PermissionsContainer, PermissionsOptions};
+use serde::{Deserialize, Serialize};
+
+/// Permissions granted to the user code running in a `DenoModule`.
+///
+/// Each list mirrors the corresponding `--allow-*` flag of the Deno CLI, except that an empty list
+/// denies access altogether (instead of granting unrestricted access as an empty `--allow-net` does).
+/// The default value, therefore, denies everything.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct DenoPermissions {
+    /// Grant every permission (equivalent to `--allow-all`). Overrides the lists below.
+    pub allow_all: bool,
+    /// Hosts (with an optional port) that may be reached (for example, "api.stripe.com").
+    pub allow_net: Vec<String>,
+    /// Environment variables that may be read.
+    pub allow_env: Vec<String>,
+    /// Paths that may be read.
+    pub allow_read: Vec<String>,
+    /// Paths that may be written to.
+    pub allow_write: Vec<String>,
+    /// Executables that may be spawned.
+    pub allow_run: Vec<String>,
+}
+
+impl DenoPermissions {
+    pub fn allow_all() -> Self {
+        Self {
+            allow_all: true,
+            ..Default::default()
+        }
+    }
+
+    pub(crate) fn to_container(&self) -> Result<PermissionsContainer, AnyError> {
+        if self.allow_all {
+            return Ok(PermissionsContainer::allow_all());
+        }
+
+        fn strings(list: &[String]) -> Option<Vec<String>> {
+            (!list.is_empty()).then(|| list.to_vec())
+        }
+
+        fn paths(list: &[String]) -> Option<Vec<PathBuf>> {
+            (!list.is_empty()).then(|| list.iter().map(PathBuf::from).collect())
+        }
+
+        let options = PermissionsOptions {
+            allow_net: strings(&self.allow_net),
+            allow_env: strings(&self.allow_env),
+            allow_read: paths(&self.allow_read),
+            allow_write: paths(&self.allow_write),
+            allow_run: strings(&self.allow_run),
+            // Never prompt (there is no one to answer); anything not granted above is denied
+            prompt: false,
+            ..Default::default()
+        };
+
+        Ok(PermissionsContainer::new(Permissions::from_options(
+            &options,
+        )?))
+    }
+}
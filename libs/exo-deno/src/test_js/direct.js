@@ -18,3 +18,11 @@ export async function getJson(id) {
 }
 
 
+
+export function readEnv(name) {
+  return Deno.env.get(name) ?? null;
+}
+
+export function spin() {
+  while (true) {}
+}
@@ -7,6 +7,8 @@
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
 
+use crate::TableId;
+
 use super::{
     delete::AbstractDelete, insert::AbstractInsert, select::AbstractSelect, update::AbstractUpdate,
 };
@@ -19,3 +21,15 @@ pub enum AbstractOperation {
     Insert(AbstractInsert),
     Update(AbstractUpdate),
 }
+
+impl AbstractOperation {
+    /// The root table of the operation
+    pub fn table_id(&self) -> TableId {
+        match self {
+            AbstractOperation::Select(select) => select.table_id,
+            AbstractOperation::Delete(delete) => delete.table_id,
+            AbstractOperation::Insert(insert) => insert.table_id,
+            AbstractOperation::Update(update) => update.table_id,
+        }
+    }
+}
@@ -13,7 +13,7 @@ use serde::{Deserialize, Serialize};
 
 use crate::{
     sql::{predicate::ParamEquality, SQLParamContainer},
-    ColumnId, Database, TableId,
+    AbstractPredicate, ArithmeticOperator, ColumnId, Database, TableId,
 };
 
 /// A link in `ColumnPath` to a column starting at a root table and ending at a leaf column. This
@@ -31,7 +31,32 @@ use crate::{
 pub enum ColumnPath {
     Physical(PhysicalColumnPath),
     Param(SQLParamContainer),
+    /// An array parameter, whose elements a column may be compared with through
+    /// [`AbstractPredicate::In`] (as in `id IN (1, 2, 3)`)
+    ArrayParam(SQLParamContainer),
     Null,
+    /// An arithmetic expression such as `capacity - 10`
+    Arithmetic(Box<ColumnPath>, ArithmeticOperator, Box<ColumnPath>),
+    /// The number of rows linked through a one-to-many relation (for example, from venues to
+    /// concerts) that satisfy the predicate. Column paths in the predicate start at the linked
+    /// table (the predicate may also refer to the root table through leaf column paths).
+    Count(RelationLink, Box<AbstractPredicate>),
+}
+
+impl ColumnPath {
+    /// Column paths that make up this path (used to determine the tables to join). A count brings
+    /// in its table through a subquery, so contributes nothing.
+    pub fn components(&self) -> Vec<&ColumnPath> {
+        match self {
+            ColumnPath::Arithmetic(left, _, right) => {
+                let mut components = left.components();
+                components.extend(right.components());
+                components
+            }
+            ColumnPath::Count(..) => vec![],
+            _ => vec![self],
+        }
+    }
 }
 
 impl ParamEquality for ColumnPath {
@@ -7,7 +7,11 @@
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
 
-use std::{ops::DerefMut, sync::atomic::AtomicBool};
+use std::{
+    ops::DerefMut,
+    sync::{atomic::AtomicBool, Arc},
+    time::Duration,
+};
 
 use crate::{
     database_error::DatabaseError,
@@ -15,17 +19,28 @@ use crate::{
         connect::{
             database_client::{DatabaseClient, TransactionWrapper},
             database_client_manager::DatabaseClientManager,
+            statement_cache::StatementCache,
         },
+        query_log::{CapturedStatement, QueryLog, StatementCapture},
         transaction::{TransactionScript, TransactionStepResult},
     },
     transform::{pg::Postgres, transformer::OperationTransformer},
     Database,
 };
 
+use tokio_postgres::error::SqlState;
+
 use super::abstract_operation::AbstractOperation;
 
 pub struct DatabaseExecutor {
+    /// Identifies the database (a [TransactionHolder] holds a transaction for each database used in
+    /// a request)
+    pub name: String,
     pub database_client: DatabaseClientManager,
+    /// Statements taking longer than this are logged (see [`QueryLog`])
+    pub slow_query_threshold: Option<Duration>,
+    /// Whether to add the trace context to statements as a comment (see [`QueryLog`])
+    pub sql_comments: bool,
 }
 
 impl DatabaseExecutor {
@@ -42,37 +57,39 @@ impl DatabaseExecutor {
         let transaction_script = database_kind.to_transaction_script(database, operation);
 
         tx_holder
-            .with_tx(database, &self.database_client, transaction_script)
+            .with_tx(
+                &self.name,
+                database,
+                &self.database_client,
+                self.slow_query_threshold,
+                self.sql_comments,
+                transaction_script,
+            )
             .await
     }
 }
 
-// TransactionHolder holds raw pointers to two objects: `client` and `transaction`.
+// DatabaseTransaction holds raw pointers to two objects: `client` and `transaction`.
 // `transaction` holds a reference to `client`, which makes initializing this struct properly difficult.
 // In addition, we must interact with async methods when using either of these objects, further complicating things
 // and preventing us from using libraries like self_cell and ouroboros.
 //
 // To simplify lifetime constraints, these are allocated and dropped manually through Box::leak
 // and a manual Drop impl. By doing so, this grants `transaction` a 'static lifetime that oversteps some lifetime
-// issues we encountered. Of course, we must manually make sure that the objects are tied to the lifetime of TransactionHolder.
+// issues we encountered. Of course, we must manually make sure that the objects are tied to the lifetime of DatabaseTransaction.
 
 #[derive(Default)]
-pub struct TransactionHolder {
+struct DatabaseTransaction {
     client: Option<*mut DatabaseClient>,
     transaction: Option<*mut TransactionWrapper<'static>>,
-    finalized: AtomicBool,
-    needs_transaction: AtomicBool,
+    /// The prepared statements of the client's connection (if caching them is enabled)
+    statement_cache: Option<Arc<StatementCache>>,
+    /// The isolation level the transaction started with
+    isolation_level: Option<IsolationLevel>,
+    local_settings_applied: bool,
 }
 
-/// # Safety
-///
-/// This is needed to mark mut pointers in TransactionHolder as Send
-/// <https://internals.rust-lang.org/t/shouldnt-pointers-be-send-sync-or/8818/4>
-///
-/// As the base types are Send, this should not be a problem.
-unsafe impl Send for TransactionHolder {}
-
-impl Drop for TransactionHolder {
+impl Drop for DatabaseTransaction {
     fn drop(&mut self) {
         if let Some(client) = self.client {
             // SAFETY: this should always be de-referenceable when it is a Some(_)
@@ -88,11 +105,69 @@ impl Drop for TransactionHolder {
     }
 }
 
+/// Transaction isolation levels (ordered from the least to the most strict)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum IsolationLevel {
+    ReadCommitted,
+    RepeatableRead,
+    Serializable,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TransactionOptions {
+    /// The isolation level (the database's default, if `None`)
+    pub isolation_level: Option<IsolationLevel>,
+    /// The maximum duration of each statement (the database's default, if `None`)
+    pub statement_timeout: Option<Duration>,
+}
+
+impl TransactionOptions {
+    /// Combine the options, keeping the stricter of each (the higher isolation level and the
+    /// shorter timeout)
+    pub fn merge(&self, other: &TransactionOptions) -> TransactionOptions {
+        TransactionOptions {
+            isolation_level: self.isolation_level.max(other.isolation_level),
+            statement_timeout: match (self.statement_timeout, other.statement_timeout) {
+                (Some(timeout), Some(other_timeout)) => Some(timeout.min(other_timeout)),
+                (timeout, other_timeout) => timeout.or(other_timeout),
+            },
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct TransactionHolder {
+    /// The transaction (or client) for each database (by the name of its executor) used so far
+    databases: Vec<(String, DatabaseTransaction)>,
+    finalized: AtomicBool,
+    needs_transaction: AtomicBool,
+    /// Settings (name, value) to set for the transaction (for example, the context values that
+    /// row-level security policies read through `current_setting`)
+    local_settings: Vec<(String, String)>,
+    options: TransactionOptions,
+    /// Did a statement (or commit) fail due to a serialization failure?
+    serialization_failure: bool,
+    /// Did we commit anything (including statements executed outside a transaction)?
+    committed: bool,
+    query_log: QueryLog,
+}
+
+/// # Safety
+///
+/// This is needed to mark mut pointers in TransactionHolder as Send
+/// <https://internals.rust-lang.org/t/shouldnt-pointers-be-send-sync-or/8818/4>
+///
+/// As the base types are Send, this should not be a problem.
+unsafe impl Send for TransactionHolder {}
+
 impl TransactionHolder {
     pub async fn with_tx(
         &mut self,
+        database_name: &str,
         database: &Database,
         client_manager: &DatabaseClientManager,
+        slow_query_threshold: Option<Duration>,
+        sql_comments: bool,
         work: TransactionScript<'_>,
     ) -> Result<TransactionStepResult, DatabaseError> {
         if self.finalized.load(std::sync::atomic::Ordering::SeqCst) {
@@ -101,82 +176,365 @@ impl TransactionHolder {
             ));
         }
 
+        let needs_transaction = work.needs_transaction()
+            || self
+                .needs_transaction
+                .load(std::sync::atomic::Ordering::SeqCst)
+            || !self.local_settings.is_empty()
+            || self.options != TransactionOptions::default();
+
+        let index = match self
+            .databases
+            .iter()
+            .position(|(name, _)| name == database_name)
+        {
+            Some(index) => index,
+            None => {
+                self.databases
+                    .push((database_name.to_string(), DatabaseTransaction::default()));
+                self.databases.len() - 1
+            }
+        };
+        let database_tx = &mut self.databases[index].1;
+        let query_log = &mut self.query_log;
+        query_log.slow_query_threshold = slow_query_threshold;
+        query_log.sql_comments = sql_comments;
+
         // SAFETY: this should be safe, we only really handle transaction in this function and it should
         // always be de-referencable when it is a Some(_)
         let tx = unsafe {
-            self.transaction
+            database_tx
+                .transaction
                 .map(|ptr| ptr.as_mut().unwrap().deref_mut())
         };
 
-        match tx {
-            Some(tx) => work.execute(database, tx).await,
+        let result = match tx {
+            Some(tx) => {
+                if !database_tx.local_settings_applied {
+                    apply_local_settings(&self.local_settings, &self.options, tx).await?;
+                    database_tx.local_settings_applied = true;
+                }
+                work.execute(
+                    database,
+                    tx,
+                    query_log,
+                    database_tx.statement_cache.as_deref(),
+                )
+                .await
+            }
 
             None => {
                 // first, grab a client if none are available
-                if self.client.is_none() {
-                    let client_owned = unsafe {
-                        let mut client_owned: Option<*mut DatabaseClient> = None;
-                        std::mem::swap(&mut self.client, &mut client_owned);
-                        client_owned.map(|ptr| Box::from_raw(ptr))
-                    };
-
-                    if client_owned.is_none() {
-                        let client = client_manager.get_client().await?;
-                        self.client = Some(Box::leak(Box::new(client)));
-                    };
+                if database_tx.client.is_none() {
+                    let client = client_manager.get_client().await?;
+                    database_tx.statement_cache = client.statement_cache().cloned();
+                    database_tx.client = Some(Box::leak(Box::new(client)));
                 }
 
                 // proceed with grabbing a transaction and execution
                 {
                     // SAFETY: this should always be de-referenceable when it is a Some(_)
-                    let client = unsafe { self.client.map(|ptr| ptr.as_mut().unwrap()) }.unwrap();
+                    let client =
+                        unsafe { database_tx.client.map(|ptr| ptr.as_mut().unwrap()) }.unwrap();
 
-                    if work.needs_transaction()
-                        || self
-                            .needs_transaction
-                            .load(std::sync::atomic::Ordering::SeqCst)
-                    {
-                        let mut tx = Box::new(client.transaction().await?);
-                        let res = work.execute(database, tx.deref_mut().deref_mut()).await;
+                    if needs_transaction {
+                        let mut tx = Box::new(
+                            client
+                                .transaction_with_isolation_level(self.options.isolation_level)
+                                .await?,
+                        );
+                        database_tx.isolation_level = self.options.isolation_level;
+                        apply_local_settings(
+                            &self.local_settings,
+                            &self.options,
+                            tx.deref_mut().deref_mut(),
+                        )
+                        .await?;
+                        database_tx.local_settings_applied = true;
+                        let res = work
+                            .execute(
+                                database,
+                                tx.deref_mut().deref_mut(),
+                                query_log,
+                                database_tx.statement_cache.as_deref(),
+                            )
+                            .await;
 
-                        self.transaction = Some(Box::leak(tx));
+                        database_tx.transaction = Some(Box::leak(tx));
 
                         res
                     } else {
-                        work.execute(database, client.deref_mut()).await
+                        self.committed = true;
+                        work.execute(
+                            database,
+                            client.deref_mut(),
+                            query_log,
+                            database_tx.statement_cache.as_deref(),
+                        )
+                        .await
                     }
                 }
             }
+        };
+
+        if result.as_ref().is_err_and(|e| e.is_serialization_failure()) {
+            self.serialization_failure = true;
         }
+
+        result
     }
 
+    /// Commit (or roll back) the transactions.
+    ///
+    /// When a request uses multiple databases, we finalize their transactions one after the other
+    /// (so, for example, a failure to commit the second transaction doesn't roll back the first).
     pub async fn finalize(&mut self, commit: bool) -> Result<(), tokio_postgres::Error> {
-        // SAFETY: this should always be de-referenceable when it is a Some(_)
-        let tx_owned = unsafe {
-            let mut tx_owned: Option<*mut TransactionWrapper> = None;
-            std::mem::swap(&mut self.transaction, &mut tx_owned);
-            tx_owned.map(|ptr| Box::from_raw(ptr))
-        };
+        let mut result = Ok(());
+
+        for (_, database_tx) in self.databases.iter_mut() {
+            // SAFETY: this should always be de-referenceable when it is a Some(_)
+            let tx_owned = unsafe {
+                let mut tx_owned: Option<*mut TransactionWrapper> = None;
+                std::mem::swap(&mut database_tx.transaction, &mut tx_owned);
+                tx_owned.map(|ptr| Box::from_raw(ptr))
+            };
 
-        match tx_owned {
-            Some(boxed) => {
-                if commit {
-                    boxed.commit().await
-                } else {
-                    boxed.rollback().await
+            // Roll back the rest if committing a transaction fails
+            let tx_result = match tx_owned {
+                Some(boxed) if commit && result.is_ok() => {
+                    let commit_result = boxed.commit().await;
+                    match &commit_result {
+                        Ok(()) => self.committed = true,
+                        Err(e) if e.code() == Some(&SqlState::T_R_SERIALIZATION_FAILURE) => {
+                            self.serialization_failure = true
+                        }
+                        Err(_) => {}
+                    }
+                    commit_result
                 }
-            }
+                Some(boxed) => boxed.rollback().await,
+                None => Ok(()),
+            };
+
+            result = result.and(tx_result);
+        }
 
-            None => Ok(()),
-        }?;
+        result?;
 
         self.finalized
             .store(true, std::sync::atomic::Ordering::SeqCst);
         Ok(())
     }
 
+    /// Did the request fail due to a serialization failure without committing anything (so
+    /// retrying it with a fresh holder is safe)?
+    pub fn is_retryable(&self) -> bool {
+        self.serialization_failure && !self.committed
+    }
+
+    /// Set the GraphQL operation that subsequent statements execute for (reported along with slow
+    /// or captured statements)
+    pub fn set_operation_name(&mut self, operation_name: &str) {
+        self.query_log.operation = Some(operation_name.to_string());
+    }
+
+    /// Capture the statements executed from now on (see [`Self::take_captured_statements`])
+    pub fn capture_statements(&mut self, capture: StatementCapture) {
+        self.query_log.capture = Some(capture);
+    }
+
+    /// The statements captured so far (`None` if capturing isn't enabled)
+    pub fn take_captured_statements(&mut self) -> Option<Vec<CapturedStatement>> {
+        self.query_log
+            .capture
+            .map(|_| std::mem::take(&mut self.query_log.captured))
+    }
+
+    /// Discard the transactions and other state to start over (for example, to retry a request
+    /// after a serialization failure), while continuing to capture statements if enabled
+    pub fn reset(&mut self) {
+        let capture = self.query_log.capture;
+        let captured = std::mem::take(&mut self.query_log.captured);
+
+        *self = Self::default();
+        self.query_log.capture = capture;
+        self.query_log.captured = captured;
+    }
+
     pub fn ensure_transaction(&self) {
         self.needs_transaction
             .store(true, std::sync::atomic::Ordering::SeqCst);
     }
+
+    /// Has the request asked for a transaction (see [`Self::ensure_transaction`]), for example,
+    /// since it has multiple operations or runs custom code that may mutate data?
+    pub fn transaction_required(&self) -> bool {
+        self.needs_transaction
+            .load(std::sync::atomic::Ordering::SeqCst)
+    }
+
+    /// Set the settings local to the transaction (through `set_config(name, value, true)`).
+    ///
+    /// Since local settings last only until the end of the transaction, having any settings forces
+    /// a transaction. If the settings change (for example, when executing with an overridden
+    /// context), the new settings apply to the rest of the transaction.
+    pub fn set_local_settings(&mut self, settings: Vec<(String, String)>) {
+        if settings != self.local_settings {
+            self.local_settings = settings;
+            for (_, database_tx) in self.databases.iter_mut() {
+                database_tx.local_settings_applied = false;
+            }
+        }
+    }
+
+    /// Set the options for the transactions, combining them with any earlier options (see
+    /// [`TransactionOptions::merge`]), since a request may execute operations with different
+    /// options in the same transaction.
+    ///
+    /// Non-default options force a transaction. Since the isolation level of a transaction can't
+    /// change once it has executed a statement, raising it after that fails.
+    pub fn set_transaction_options(
+        &mut self,
+        options: &TransactionOptions,
+    ) -> Result<(), DatabaseError> {
+        let options = self.options.merge(options);
+
+        if options == self.options {
+            return Ok(());
+        }
+
+        let isolation_raised = self.databases.iter().any(|(_, database_tx)| {
+            database_tx.transaction.is_some()
+                && database_tx.isolation_level < options.isolation_level
+        });
+        if isolation_raised {
+            return Err(DatabaseError::Transaction(
+                "Cannot raise the isolation level of a transaction that has already started".into(),
+            ));
+        }
+
+        if options.statement_timeout != self.options.statement_timeout {
+            for (_, database_tx) in self.databases.iter_mut() {
+                database_tx.local_settings_applied = false;
+            }
+        }
+
+        self.options = options;
+        Ok(())
+    }
+}
+
+async fn apply_local_settings(
+    settings: &[(String, String)],
+    options: &TransactionOptions,
+    tx: &mut tokio_postgres::Transaction<'_>,
+) -> Result<(), DatabaseError> {
+    let statement_timeout = options.statement_timeout.map(|timeout| {
+        (
+            "statement_timeout".to_string(),
+            timeout.as_millis().to_string(),
+        )
+    });
+
+    let (names, values): (Vec<&str>, Vec<&str>) = settings
+        .iter()
+        .chain(statement_timeout.iter())
+        .map(|(name, value)| (name.as_str(), value.as_str()))
+        .unzip();
+
+    if names.is_empty() {
+        return Ok(());
+    }
+
+    tx.execute(
+        "SELECT set_config(name, value, true) FROM unnest($1::text[], $2::text[]) AS settings(name, value)",
+        &[&names, &values],
+    )
+    .await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use multiplatform_test::multiplatform_test;
+
+    #[multiplatform_test]
+    fn merge_transaction_options() {
+        let serializable = TransactionOptions {
+            isolation_level: Some(IsolationLevel::Serializable),
+            statement_timeout: Some(Duration::from_secs(5)),
+        };
+        let repeatable_read = TransactionOptions {
+            isolation_level: Some(IsolationLevel::RepeatableRead),
+            statement_timeout: Some(Duration::from_secs(2)),
+        };
+
+        assert_eq!(
+            serializable.merge(&repeatable_read),
+            TransactionOptions {
+                isolation_level: Some(IsolationLevel::Serializable),
+                statement_timeout: Some(Duration::from_secs(2)),
+            }
+        );
+        assert_eq!(
+            TransactionOptions::default().merge(&serializable),
+            serializable
+        );
+        assert_eq!(
+            repeatable_read.merge(&TransactionOptions::default()),
+            repeatable_read
+        );
+    }
+
+    #[multiplatform_test]
+    fn set_transaction_options() {
+        let mut holder = TransactionHolder::default();
+        holder
+            .set_transaction_options(&TransactionOptions {
+                isolation_level: Some(IsolationLevel::RepeatableRead),
+                statement_timeout: None,
+            })
+            .unwrap();
+        holder
+            .set_transaction_options(&TransactionOptions {
+                isolation_level: None,
+                statement_timeout: Some(Duration::from_millis(500)),
+            })
+            .unwrap();
+
+        assert_eq!(
+            holder.options,
+            TransactionOptions {
+                isolation_level: Some(IsolationLevel::RepeatableRead),
+                statement_timeout: Some(Duration::from_millis(500)),
+            }
+        );
+        assert!(!holder.is_retryable());
+    }
+
+    #[multiplatform_test]
+    fn captured_statements() {
+        let mut holder = TransactionHolder::default();
+        assert!(holder.take_captured_statements().is_none());
+
+        holder.capture_statements(StatementCapture::Statements);
+        holder.set_operation_name("concerts");
+        holder.query_log.captured.push(CapturedStatement {
+            operation: holder.query_log.operation.clone(),
+            sql: "SELECT 1".to_string(),
+            params: vec![],
+            duration_ms: Some(1.0),
+            plan: None,
+        });
+
+        // Statements from an attempt that had to be retried are still reported
+        holder.reset();
+
+        let captured = holder.take_captured_statements().unwrap();
+        assert_eq!(captured.len(), 1);
+        assert_eq!(captured[0].operation.as_deref(), Some("concerts"));
+        assert_eq!(holder.take_captured_statements().unwrap().len(), 0);
+    }
 }
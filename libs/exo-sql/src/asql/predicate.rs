@@ -35,16 +35,22 @@ impl AbstractPredicate {
             | AbstractPredicate::JsonContainedBy(l, r)
             | AbstractPredicate::JsonMatchKey(l, r)
             | AbstractPredicate::JsonMatchAnyKey(l, r)
-            | AbstractPredicate::JsonMatchAllKeys(l, r) => vec![l, r],
+            | AbstractPredicate::JsonMatchAllKeys(l, r) => [l, r]
+                .into_iter()
+                .flat_map(ColumnPath::components)
+                .collect(),
 
-            AbstractPredicate::VectorDistance(c1, c2, _, _, c3) => vec![c1, c2, c3],
+            AbstractPredicate::VectorDistance(c1, c2, _, _, c3) => [c1, c2, c3]
+                .into_iter()
+                .flat_map(ColumnPath::components)
+                .collect(),
 
             AbstractPredicate::And(l, r) | AbstractPredicate::Or(l, r) => {
                 let mut result = l.column_paths();
                 result.extend(r.column_paths());
                 result
             }
-            AbstractPredicate::Not(p) => p.column_paths(),
+            AbstractPredicate::Not(p) | AbstractPredicate::IsNotTrue(p) => p.column_paths(),
         }
     }
 }
@@ -8,6 +8,7 @@
 // by the Apache License, Version 2.0.
 
 use thiserror::Error;
+use tokio_postgres::error::SqlState;
 
 #[derive(Error, Debug)]
 pub enum DatabaseError {
@@ -34,6 +35,10 @@ pub enum DatabaseError {
     #[error("Pool: {0}")]
     Pool(#[from] deadpool_postgres::PoolError),
 
+    #[cfg(feature = "sqlite")]
+    #[error("SQLite: {0}")]
+    Sqlite(#[from] rusqlite::Error),
+
     #[error("{0} {1}")]
     WithContext(String, #[source] Box<DatabaseError>),
 
@@ -48,6 +53,33 @@ impl DatabaseError {
     pub fn with_context(self, context: String) -> DatabaseError {
         DatabaseError::WithContext(context, Box::new(self))
     }
+
+    /// Is the error due to failing to connect to the database (or losing the connection), as
+    /// opposed to, for example, failing to execute a statement?
+    pub fn is_connection_error(&self) -> bool {
+        match self {
+            DatabaseError::Delegate(e) => {
+                e.is_closed()
+                    || std::error::Error::source(e)
+                        .is_some_and(|source| source.is::<std::io::Error>())
+            }
+            #[cfg(feature = "pool")]
+            DatabaseError::Pool(_) => true,
+            DatabaseError::WithContext(_, e) => e.is_connection_error(),
+            _ => false,
+        }
+    }
+
+    /// Is the error due to a serialization failure? Postgres reports one when a `repeatable read`
+    /// or `serializable` transaction conflicts with a concurrent transaction, in which case
+    /// retrying the transaction may succeed.
+    pub fn is_serialization_failure(&self) -> bool {
+        match self {
+            DatabaseError::Delegate(e) => e.code() == Some(&SqlState::T_R_SERIALIZATION_FAILURE),
+            DatabaseError::WithContext(_, e) => e.is_serialization_failure(),
+            _ => false,
+        }
+    }
 }
 
 pub trait WithContext {
@@ -59,3 +91,48 @@ impl<T> WithContext for Result<T, DatabaseError> {
         self.map_err(|e| e.with_context(context))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn other_errors_are_not_connection_errors() {
+        assert!(!DatabaseError::Validation("invalid input".to_string()).is_connection_error());
+        assert!(!DatabaseError::Generic("failed".to_string())
+            .with_context("While executing a query".to_string())
+            .is_connection_error());
+    }
+
+    #[cfg(feature = "pool")]
+    #[test]
+    fn pool_errors_are_connection_errors() {
+        let error = DatabaseError::Pool(deadpool_postgres::PoolError::Closed);
+        assert!(error.is_connection_error());
+    }
+
+    // Connecting needs the Tokio runtime support of `tokio-postgres`
+    #[cfg(feature = "postgres-url")]
+    #[test]
+    fn refused_connections_are_connection_errors() {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+
+        // Nothing listens on port 1
+        let error: DatabaseError = runtime
+            .block_on(tokio_postgres::connect(
+                "host=127.0.0.1 port=1 user=exo connect_timeout=5",
+                tokio_postgres::NoTls,
+            ))
+            .err()
+            .unwrap()
+            .into();
+
+        assert!(error.is_connection_error());
+        assert!(error
+            .with_context("While connecting".to_string())
+            .is_connection_error());
+    }
+}
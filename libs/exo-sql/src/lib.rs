@@ -13,8 +13,8 @@
 /// an [AbstractOperation] into one or more SQL operations and executing them. This
 /// separation of intention vs execution allows for simplified expression from the
 /// user of the library and leaves out the details of the database operations.
-/// Although, currently it focuses solely on Postgres support, it should be easy to
-/// extend to other databases.
+/// It primarily targets Postgres, with (behind the `sqlite` feature) an embedded SQLite
+/// alternative through [SqliteDatabase].
 ///
 /// For example, consider [AbstractSelect]. It allows expressing the intention to
 /// query data by specifying the root table, a predicate, and (potentially nested)
@@ -47,8 +47,8 @@ pub mod database_error;
 /// Public types at the root level of this crate
 pub use asql::{
     abstract_operation::AbstractOperation,
-    column_path::{ColumnPath, ColumnPathLink, PhysicalColumnPath},
-    database_executor::{DatabaseExecutor, TransactionHolder},
+    column_path::{ColumnPath, ColumnPathLink, PhysicalColumnPath, RelationLink},
+    database_executor::{DatabaseExecutor, IsolationLevel, TransactionHolder, TransactionOptions},
     delete::AbstractDelete,
     insert::{AbstractInsert, ColumnValuePair, InsertionElement, InsertionRow, NestedInsertion},
     order_by::{AbstractOrderBy, AbstractOrderByExpr},
@@ -63,9 +63,14 @@ pub use asql::{
 
 pub use sql::{
     array_util::{self, ArrayEntry},
-    column::Column,
+    column::{ArithmeticOperator, Column},
     connect::creation::Connect,
-    connect::database_client_manager::DatabaseClientManager,
+    connect::database_client_manager::{
+        url_with_statement_timeout, DatabaseClientManager, PoolStatus,
+    },
+    connect::statement_cache::{
+        statement_cache_metrics, StatementCacheMetrics, DEFAULT_STATEMENT_CACHE_SIZE,
+    },
     database::{Database, TableId},
     function::Function,
     limit::Limit,
@@ -74,7 +79,11 @@ pub use sql::{
     physical_column::{ColumnId, FloatBits, IntBits, PhysicalColumn, PhysicalColumnType},
     physical_table::{PhysicalIndex, PhysicalTable, PhysicalTableName},
     predicate::{CaseSensitivity, NumericComparator, ParamEquality, Predicate},
+    query_log::{CapturedStatement, QueryLog, StatementCapture},
     relation::{ManyToOne, ManyToOneId, OneToMany, OneToManyId, RelationId},
     vector::{VectorDistanceFunction, DEFAULT_VECTOR_SIZE},
     SQLBytes, SQLParam, SQLParamContainer,
 };
+
+#[cfg(feature = "sqlite")]
+pub use sql::connect::sqlite_database::{SqliteDatabase, SqliteRows};
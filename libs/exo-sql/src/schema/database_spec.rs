@@ -154,9 +154,19 @@ impl DatabaseSpec {
     }
 
     pub fn from_database(database: &Database) -> DatabaseSpec {
+        Self::from_database_tables(database, |_| true)
+    }
+
+    /// Creates a new schema specification from the tables (that satisfy the filter) of a database.
+    /// Useful when a model spreads its tables over multiple databases.
+    pub fn from_database_tables(
+        database: &Database,
+        filter: impl Fn(TableId) -> bool,
+    ) -> DatabaseSpec {
         let tables = database
             .tables()
             .into_iter()
+            .filter(|(table_id, _)| filter(*table_id))
             .map(|(_, table)| {
                 TableSpec::new(
                     table.name.clone(),
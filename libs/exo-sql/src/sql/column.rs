@@ -8,6 +8,7 @@
 // by the Apache License, Version 2.0.
 
 use maybe_owned::MaybeOwned;
+use serde::{Deserialize, Serialize};
 
 use crate::{ColumnId, Database, ParamEquality, PhysicalTableName};
 
@@ -56,6 +57,25 @@ pub enum Column {
     Null,
     /// A function applied to a column. For example, `count(id)` or `lower(first_name)`.
     Function(Function),
+    /// An arithmetic expression such as `capacity - 10`
+    Arithmetic(Box<Column>, ArithmeticOperator, Box<Column>),
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ArithmeticOperator {
+    Add,
+    Subtract,
+    Multiply,
+}
+
+impl ArithmeticOperator {
+    fn sql_operator(&self) -> &'static str {
+        match self {
+            ArithmeticOperator::Add => "+",
+            ArithmeticOperator::Subtract => "-",
+            ArithmeticOperator::Multiply => "*",
+        }
+    }
 }
 
 #[derive(Debug, PartialEq)]
@@ -133,6 +153,15 @@ impl ExpressionBuilder for Column {
             Column::Null => {
                 builder.push_str("NULL");
             }
+            Column::Arithmetic(left, op, right) => {
+                builder.push('(');
+                left.build(database, builder);
+                builder.push_space();
+                builder.push_str(op.sql_operator());
+                builder.push_space();
+                right.build(database, builder);
+                builder.push(')');
+            }
         }
     }
 }
@@ -31,7 +31,7 @@ impl DatabaseCreation {
                 config, connect, ..
             } => {
                 let (client, _connection) = connect.connect(config).await?;
-                Ok(DatabaseClient::Direct(client))
+                Ok(DatabaseClient::Direct(client, None))
             }
             #[cfg(feature = "postgres-url")]
             DatabaseCreation::Url { url } => Self::from_url(url).await,
@@ -63,7 +63,7 @@ impl DatabaseCreation {
                     }
                 });
 
-                Ok(DatabaseClient::Direct(client))
+                Ok(DatabaseClient::Direct(client, None))
             }
             None => {
                 let tls = tokio_postgres::NoTls;
@@ -75,7 +75,7 @@ impl DatabaseCreation {
                     }
                 });
 
-                Ok(DatabaseClient::Direct(client))
+                Ok(DatabaseClient::Direct(client, None))
             }
         }
     }
@@ -7,14 +7,24 @@
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
 
-use std::ops::{Deref, DerefMut};
+use std::{
+    ops::{Deref, DerefMut},
+    sync::Arc,
+};
 
 use tokio_postgres::ToStatement;
 
+use crate::asql::database_executor::IsolationLevel;
+
+#[cfg(feature = "pool")]
+use super::database_pool::PooledClient;
+use super::statement_cache::StatementCache;
+
+/// A connection along with its prepared statements (if caching them is enabled)
 pub enum DatabaseClient {
     #[cfg(feature = "pool")]
-    Pooled(deadpool_postgres::Client),
-    Direct(tokio_postgres::Client),
+    Pooled(PooledClient),
+    Direct(tokio_postgres::Client, Option<Arc<StatementCache>>),
 }
 
 impl Deref for DatabaseClient {
@@ -24,7 +34,7 @@ impl Deref for DatabaseClient {
         match self {
             #[cfg(feature = "pool")]
             DatabaseClient::Pooled(client) => client,
-            DatabaseClient::Direct(client) => client,
+            DatabaseClient::Direct(client, _) => client,
         }
     }
 }
@@ -34,7 +44,7 @@ impl DerefMut for DatabaseClient {
         match self {
             #[cfg(feature = "pool")]
             DatabaseClient::Pooled(client) => client,
-            DatabaseClient::Direct(client) => client,
+            DatabaseClient::Direct(client, _) => client,
         }
     }
 }
@@ -86,6 +96,14 @@ impl<'a> DerefMut for TransactionWrapper<'a> {
 }
 
 impl DatabaseClient {
+    pub fn statement_cache(&self) -> Option<&Arc<StatementCache>> {
+        match self {
+            #[cfg(feature = "pool")]
+            DatabaseClient::Pooled(client) => client.statement_cache(),
+            DatabaseClient::Direct(_, statement_cache) => statement_cache.as_ref(),
+        }
+    }
+
     pub async fn transaction(
         &mut self,
     ) -> Result<TransactionWrapper<'_>, tokio_postgres::error::Error> {
@@ -94,12 +112,44 @@ impl DatabaseClient {
             DatabaseClient::Pooled(client) => {
                 client.transaction().await.map(TransactionWrapper::Pooled)
             }
-            DatabaseClient::Direct(client) => {
+            DatabaseClient::Direct(client, _) => {
                 client.transaction().await.map(TransactionWrapper::Direct)
             }
         }
     }
 
+    /// Start a transaction with the given isolation level (the database's default, if `None`)
+    pub async fn transaction_with_isolation_level(
+        &mut self,
+        isolation_level: Option<IsolationLevel>,
+    ) -> Result<TransactionWrapper<'_>, tokio_postgres::error::Error> {
+        let isolation_level = isolation_level.map(|level| match level {
+            IsolationLevel::ReadCommitted => tokio_postgres::IsolationLevel::ReadCommitted,
+            IsolationLevel::RepeatableRead => tokio_postgres::IsolationLevel::RepeatableRead,
+            IsolationLevel::Serializable => tokio_postgres::IsolationLevel::Serializable,
+        });
+
+        match self {
+            #[cfg(feature = "pool")]
+            DatabaseClient::Pooled(client) => {
+                let builder = client.build_transaction();
+                let builder = match isolation_level {
+                    Some(level) => builder.isolation_level(level),
+                    None => builder,
+                };
+                builder.start().await.map(TransactionWrapper::Pooled)
+            }
+            DatabaseClient::Direct(client, _) => {
+                let builder = client.build_transaction();
+                let builder = match isolation_level {
+                    Some(level) => builder.isolation_level(level),
+                    None => builder,
+                };
+                builder.start().await.map(TransactionWrapper::Direct)
+            }
+        }
+    }
+
     pub async fn query<T>(
         &self,
         query: &T,
@@ -111,7 +161,7 @@ impl DatabaseClient {
         match self {
             #[cfg(feature = "pool")]
             DatabaseClient::Pooled(client) => client.query(query, params).await,
-            DatabaseClient::Direct(client) => client.query(query, params).await,
+            DatabaseClient::Direct(client, _) => client.query(query, params).await,
         }
     }
 
@@ -126,7 +176,7 @@ impl DatabaseClient {
         match self {
             #[cfg(feature = "pool")]
             DatabaseClient::Pooled(client) => client.execute(query, params).await,
-            DatabaseClient::Direct(client) => client.execute(query, params).await,
+            DatabaseClient::Direct(client, _) => client.execute(query, params).await,
         }
     }
 }
@@ -7,6 +7,8 @@
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
 
+use std::time::Duration;
+
 use crate::{database_error::DatabaseError, Connect};
 
 use super::{creation::DatabaseCreation, database_client::DatabaseClient};
@@ -20,6 +22,19 @@ pub enum DatabaseClientManager {
     Direct(DatabaseCreation),
 }
 
+/// The usage of a connection pool
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PoolStatus {
+    /// The maximum number of connections
+    pub max_size: usize,
+    /// The number of open connections
+    pub size: usize,
+    /// The number of open connections not in use
+    pub available: usize,
+    /// The number of requests waiting for a connection
+    pub waiting: usize,
+}
+
 impl DatabaseClientManager {
     pub async fn from_connect_direct(
         check_connection: bool,
@@ -74,6 +89,33 @@ impl DatabaseClientManager {
             DatabaseClientManager::Direct(creation) => creation.get_client().await,
         }
     }
+
+    /// The usage of the connection pool (`None` for direct connections)
+    pub fn pool_status(&self) -> Option<PoolStatus> {
+        match self {
+            #[cfg(feature = "pool")]
+            DatabaseClientManager::Pooled(pool) => Some(pool.status()),
+            DatabaseClientManager::Direct(_) => None,
+        }
+    }
+
+    /// Set the number of prepared statements to cache for each connection (zero to disable
+    /// caching, for example, when connecting through PgBouncer in transaction mode). Since a
+    /// direct connection lasts only for a request, we cache statements only for pooled
+    /// connections.
+    pub fn with_statement_cache_size(self, size: usize) -> Self {
+        match self {
+            #[cfg(feature = "pool")]
+            DatabaseClientManager::Pooled(mut pool) => {
+                pool.set_statement_cache_size(size);
+                DatabaseClientManager::Pooled(pool)
+            }
+            DatabaseClientManager::Direct(creation) => {
+                let _ = size;
+                DatabaseClientManager::Direct(creation)
+            }
+        }
+    }
 }
 
 #[cfg(feature = "postgres-url")]
@@ -130,3 +172,55 @@ impl DatabaseClientManager {
         res
     }
 }
+
+/// Add a `statement_timeout` to the connection options in a Postgres URL (keeping any existing
+/// options), so that the server cancels statements that run longer on every connection from the
+/// URL
+pub fn url_with_statement_timeout(url: &str, timeout: Duration) -> Result<String, DatabaseError> {
+    let mut url =
+        url::Url::parse(url).map_err(|_| DatabaseError::Config("Invalid database URL".into()))?;
+
+    let (options, other_pairs): (Vec<_>, Vec<_>) = url
+        .query_pairs()
+        .into_owned()
+        .partition(|(name, _)| name == "options");
+
+    let timeout_option = format!("-c statement_timeout={}", timeout.as_millis());
+    let options = options
+        .into_iter()
+        .map(|(_, value)| value)
+        .chain(std::iter::once(timeout_option))
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    url.query_pairs_mut()
+        .clear()
+        .extend_pairs(other_pairs)
+        .append_pair("options", &options);
+
+    Ok(url.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use multiplatform_test::multiplatform_test;
+
+    #[multiplatform_test]
+    fn statement_timeout_in_url() {
+        assert_eq!(
+            url_with_statement_timeout("postgres://user@localhost/db", Duration::from_secs(5))
+                .unwrap(),
+            "postgres://user@localhost/db?options=-c+statement_timeout%3D5000"
+        );
+
+        assert_eq!(
+            url_with_statement_timeout(
+                "postgres://user@localhost/db?sslmode=require&options=-c%20search_path%3Dapp",
+                Duration::from_millis(250)
+            )
+            .unwrap(),
+            "postgres://user@localhost/db?sslmode=require&options=-c+search_path%3Dapp+-c+statement_timeout%3D250"
+        );
+    }
+}
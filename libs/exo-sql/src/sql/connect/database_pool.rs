@@ -9,18 +9,88 @@
 
 #![cfg(feature = "pool")]
 
+use std::{
+    ops::{Deref, DerefMut},
+    sync::Arc,
+};
+
+use deadpool::managed::{Metrics, Object, Pool, RecycleResult};
 #[cfg(feature = "postgres-url")]
 use deadpool_postgres::ConfigConnectImpl;
-use deadpool_postgres::{Connect, Manager, ManagerConfig, Pool, RecyclingMethod};
+use deadpool_postgres::{ClientWrapper, Connect, Manager, ManagerConfig, RecyclingMethod};
 
 use tokio_postgres::Config;
 
 use crate::database_error::DatabaseError;
 
-use super::{creation::DatabaseCreation, database_client::DatabaseClient};
+use super::{
+    creation::DatabaseCreation,
+    database_client::DatabaseClient,
+    database_client_manager::PoolStatus,
+    statement_cache::{StatementCache, DEFAULT_STATEMENT_CACHE_SIZE},
+};
+
+/// A connection handed out by the pool
+pub type PooledClient = Object<ConnectionManager>;
 
 pub struct DatabasePool {
-    pool: Pool,
+    pool: Pool<ConnectionManager>,
+    /// The number of prepared statements to cache for each connection (zero to disable caching)
+    statement_cache_size: usize,
+}
+
+/// A pooled connection along with its prepared statements (if caching them is enabled)
+pub struct PooledConnection {
+    client: ClientWrapper,
+    statement_cache: Option<Arc<StatementCache>>,
+}
+
+impl PooledConnection {
+    pub fn statement_cache(&self) -> Option<&Arc<StatementCache>> {
+        self.statement_cache.as_ref()
+    }
+}
+
+impl Deref for PooledConnection {
+    type Target = ClientWrapper;
+
+    fn deref(&self) -> &Self::Target {
+        &self.client
+    }
+}
+
+impl DerefMut for PooledConnection {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.client
+    }
+}
+
+/// Creates and recycles connections using deadpool's manager, so that the pool can keep the
+/// prepared statements of each connection along with it
+pub struct ConnectionManager(Manager);
+
+impl deadpool::managed::Manager for ConnectionManager {
+    type Type = PooledConnection;
+    type Error = tokio_postgres::Error;
+
+    async fn create(&self) -> Result<PooledConnection, tokio_postgres::Error> {
+        Ok(PooledConnection {
+            client: self.0.create().await?,
+            statement_cache: None,
+        })
+    }
+
+    async fn recycle(
+        &self,
+        connection: &mut PooledConnection,
+        metrics: &Metrics,
+    ) -> RecycleResult<tokio_postgres::Error> {
+        self.0.recycle(&mut connection.client, metrics).await
+    }
+
+    fn detach(&self, connection: &mut PooledConnection) {
+        self.0.detach(&mut connection.client)
+    }
 }
 
 impl DatabasePool {
@@ -38,7 +108,30 @@ impl DatabasePool {
     }
 
     pub async fn get_client(&self) -> Result<DatabaseClient, DatabaseError> {
-        Ok(DatabaseClient::Pooled(self.pool.get().await?))
+        let mut client = self.pool.get().await?;
+
+        // Create the cache when first handing out the connection (instead of when creating it),
+        // since the size may be set after the pool has created a connection
+        if self.statement_cache_size > 0 && client.statement_cache.is_none() {
+            client.statement_cache = Some(Arc::new(StatementCache::new(self.statement_cache_size)));
+        }
+
+        Ok(DatabaseClient::Pooled(client))
+    }
+
+    pub fn status(&self) -> PoolStatus {
+        let status = self.pool.status();
+
+        PoolStatus {
+            max_size: status.max_size,
+            size: status.size,
+            available: status.available,
+            waiting: status.waiting,
+        }
+    }
+
+    pub fn set_statement_cache_size(&mut self, size: usize) {
+        self.statement_cache_size = size;
     }
 
     #[cfg(feature = "postgres-url")]
@@ -81,7 +174,7 @@ impl DatabasePool {
             recycling_method: RecyclingMethod::Fast,
         };
 
-        let manager = { Manager::from_connect(config, connect, manager_config) };
+        let manager = ConnectionManager(Manager::from_connect(config, connect, manager_config));
 
         let pool = Pool::builder(manager);
 
@@ -92,7 +185,10 @@ impl DatabasePool {
         .build()
         .expect("Failed to create DB pool");
 
-        let db = Self { pool };
+        let db = Self {
+            pool,
+            statement_cache_size: DEFAULT_STATEMENT_CACHE_SIZE,
+        };
 
         Ok(db)
     }
@@ -111,3 +207,48 @@ impl Connect for ConnectBridge {
         self.0.connect(pg_config)
     }
 }
+
+#[cfg(all(test, feature = "testing", feature = "postgres-url"))]
+mod tests {
+    use super::*;
+    use crate::{
+        sql::connect::statement_cache::statement_cache_metrics,
+        testing::db::EphemeralDatabaseLauncher,
+    };
+
+    #[tokio::test]
+    async fn reuses_statements_of_a_pooled_connection() {
+        let server = EphemeralDatabaseLauncher::create_server().unwrap();
+        let database = server.create_database("statement_cache").unwrap();
+
+        // With a single connection, each client reuses the same connection
+        let mut pool = DatabasePool::create(
+            DatabaseCreation::Url {
+                url: database.url(),
+            },
+            Some(1),
+        )
+        .await
+        .unwrap();
+        pool.set_statement_cache_size(10);
+
+        let before = statement_cache_metrics();
+
+        for _ in 0..2 {
+            let client = pool.get_client().await.unwrap();
+            let statement_cache = client.statement_cache().unwrap();
+
+            let statement = statement_cache
+                .prepare(&*client, "SELECT $1::INT")
+                .await
+                .unwrap();
+            let rows = client.query(&statement, &[&42]).await.unwrap();
+            assert_eq!(rows[0].get::<_, i32>(0), 42);
+        }
+
+        // Only the first execution prepares the statement
+        let after = statement_cache_metrics();
+        assert_eq!(after.misses - before.misses, 1);
+        assert_eq!(after.hits - before.hits, 1);
+    }
+}
@@ -12,3 +12,7 @@ pub mod database_client;
 pub mod database_client_manager;
 pub mod database_pool;
 pub mod ssl_config;
+pub mod statement_cache;
+
+#[cfg(feature = "sqlite")]
+pub mod sqlite_database;
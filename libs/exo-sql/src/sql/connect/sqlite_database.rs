@@ -0,0 +1,378 @@
+// Copyright Exograph, Inc. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file at the root of this repository.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! An embedded SQLite database to execute abstract operations against (for example, in small
+//! internal tools and tests that shouldn't need a Postgres server).
+
+use std::{path::Path, sync::Mutex};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use bytes::BytesMut;
+use chrono::{DateTime, FixedOffset, NaiveDate, NaiveDateTime, NaiveTime, Utc};
+use rusqlite::{functions::FunctionFlags, types::Value, Connection, Transaction};
+use tokio_postgres::types::{FromSql, ToSql, Type};
+use tracing::{error, info, instrument};
+
+use crate::{
+    database_error::DatabaseError,
+    sql::{
+        sql_operation::SQLOperation,
+        transaction::{TransactionContext, TransactionStep},
+        ExpressionBuilder, SQLBuilder, SQLDialect, SQLValue,
+    },
+    transform::{sqlite::Sqlite, transformer::OperationTransformer},
+    AbstractOperation, Database, SQLBytes, SQLParam, SQLParamContainer,
+};
+
+/// Rows returned by an operation
+pub type SqliteRows = Vec<Vec<Value>>;
+
+pub struct SqliteDatabase {
+    connection: Mutex<Connection>,
+}
+
+impl SqliteDatabase {
+    /// Open (creating, if needed) the database at the given path
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, DatabaseError> {
+        Self::new(Connection::open(path)?)
+    }
+
+    /// Open a new in-memory database
+    pub fn open_in_memory() -> Result<Self, DatabaseError> {
+        Self::new(Connection::open_in_memory()?)
+    }
+
+    fn new(connection: Connection) -> Result<Self, DatabaseError> {
+        // JSON objects encode blobs as base64 (see `JsonObjectElement`), but SQLite doesn't have a
+        // built-in function for that
+        connection.create_scalar_function(
+            "base64",
+            1,
+            FunctionFlags::SQLITE_UTF8 | FunctionFlags::SQLITE_DETERMINISTIC,
+            |ctx| {
+                let blob = ctx.get::<Option<Vec<u8>>>(0)?;
+                Ok(blob.map(|blob| STANDARD.encode(blob)))
+            },
+        )?;
+        // Match Postgres, where LIKE is case-sensitive (case-insensitive matches lower-case both
+        // sides instead of using ILIKE)
+        connection.pragma_update(None, "case_sensitive_like", true)?;
+        connection.pragma_update(None, "foreign_keys", true)?;
+
+        Ok(Self {
+            connection: Mutex::new(connection),
+        })
+    }
+
+    /// Execute SQL statements as is (for example, to create the schema)
+    pub fn execute_batch(&self, sql: &str) -> Result<(), DatabaseError> {
+        Ok(self.connection.lock().unwrap().execute_batch(sql)?)
+    }
+
+    /// Execute an operation in a transaction and return the resulting rows. For a JSON selection,
+    /// this will be a single row with a single column holding the JSON text.
+    #[instrument(name = "SqliteDatabase::execute", skip_all)]
+    pub fn execute(
+        &self,
+        operation: &AbstractOperation,
+        database: &Database,
+    ) -> Result<SqliteRows, DatabaseError> {
+        let transaction_script = Sqlite {}.to_transaction_script(database, operation);
+        let result_step_id = transaction_script.result_step_id();
+
+        let mut connection = self.connection.lock().unwrap();
+        let tx = connection.transaction()?;
+
+        let mut transaction_context = TransactionContext::new();
+        let mut result = None;
+
+        for (index, step) in transaction_script.into_steps().into_iter().enumerate() {
+            let rows = execute_step(step, database, &tx, &transaction_context)?;
+            transaction_context.push_values(
+                rows.iter()
+                    .map(|row| row.iter().map(to_sql_value).collect())
+                    .collect::<Result<_, _>>()?,
+            );
+
+            if Some(index) == result_step_id.map(|id| id.0) {
+                result = Some(rows);
+            }
+        }
+
+        tx.commit()?;
+
+        result.ok_or_else(|| DatabaseError::Transaction("".into()))
+    }
+}
+
+fn execute_step(
+    step: TransactionStep,
+    database: &Database,
+    tx: &Transaction,
+    transaction_context: &TransactionContext,
+) -> Result<SqliteRows, DatabaseError> {
+    match step {
+        TransactionStep::Concrete(step) => run_query(&step.operation, database, tx),
+        TransactionStep::Template(step) => {
+            // Same as for Postgres, the result is that of the last substep
+            let mut rows = vec![];
+            for substep in step.resolve(transaction_context) {
+                rows = run_query(&substep.operation, database, tx)?;
+            }
+            Ok(rows)
+        }
+        TransactionStep::Filter(step) => {
+            let concrete = step.resolve(transaction_context, database);
+            run_query(&concrete.operation, database, tx)
+        }
+        TransactionStep::Dynamic(step) => {
+            let concrete = step.resolve(transaction_context);
+            run_query(&concrete.operation, database, tx)
+        }
+    }
+}
+
+fn run_query(
+    operation: &SQLOperation,
+    database: &Database,
+    tx: &Transaction,
+) -> Result<SqliteRows, DatabaseError> {
+    let mut sql_builder = SQLBuilder::with_dialect(SQLDialect::Sqlite);
+    operation.build(database, &mut sql_builder);
+    let (stmt, params) = sql_builder.into_sql();
+
+    let params = params
+        .iter()
+        .map(|param| to_sqlite_value(param.as_ref()))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    info!("Executing SQL operation: {}", stmt);
+
+    let mut statement = tx.prepare(&stmt)?;
+    let column_count = statement.column_count();
+
+    let rows = statement
+        .query_map(rusqlite::params_from_iter(params), |row| {
+            (0..column_count)
+                .map(|index| row.get(index))
+                .collect::<Result<Vec<Value>, _>>()
+        })?
+        .collect::<Result<_, _>>();
+
+    rows.map_err(|e| {
+        error!("Failed to execute query: {e:?}");
+        DatabaseError::Sqlite(e).with_context("Database operation failed".into())
+    })
+}
+
+/// Convert a parameter to its SQLite counterpart. SQLite has no date/time or JSON types, so those
+/// are bound as (ISO 8601 or JSON) text. SQLite also doesn't have arrays, so an array is bound as a
+/// JSON array (which `= ANY(...)` predicates read through `json_each`).
+fn to_sqlite_value(param: &dyn SQLParam) -> Result<Value, DatabaseError> {
+    let any = param.as_any();
+
+    let value = if let Some(container) = any.downcast_ref::<SQLParamContainer>() {
+        to_sqlite_value(container.as_ref())?
+    } else if let Some(value) = any.downcast_ref::<bool>() {
+        Value::Integer(*value as i64)
+    } else if let Some(value) = any.downcast_ref::<i16>() {
+        Value::Integer(*value as i64)
+    } else if let Some(value) = any.downcast_ref::<i32>() {
+        Value::Integer(*value as i64)
+    } else if let Some(value) = any.downcast_ref::<i64>() {
+        Value::Integer(*value)
+    } else if let Some(value) = any.downcast_ref::<f32>() {
+        Value::Real(*value as f64)
+    } else if let Some(value) = any.downcast_ref::<f64>() {
+        Value::Real(*value)
+    } else if let Some(value) = any.downcast_ref::<String>() {
+        Value::Text(value.clone())
+    } else if let Some(value) = any.downcast_ref::<SQLBytes>() {
+        Value::Blob(value.0.to_vec())
+    } else if let Some(value) = any.downcast_ref::<NaiveDate>() {
+        Value::Text(value.format("%Y-%m-%d").to_string())
+    } else if let Some(value) = any.downcast_ref::<NaiveTime>() {
+        Value::Text(value.format("%H:%M:%S%.f").to_string())
+    } else if let Some(value) = any.downcast_ref::<NaiveDateTime>() {
+        Value::Text(value.format("%Y-%m-%dT%H:%M:%S%.f").to_string())
+    } else if let Some(value) = any.downcast_ref::<DateTime<Utc>>() {
+        Value::Text(value.to_rfc3339())
+    } else if let Some(value) = any.downcast_ref::<DateTime<FixedOffset>>() {
+        Value::Text(value.to_rfc3339())
+    } else if let Some(value) = any.downcast_ref::<serde_json::Value>() {
+        Value::Text(value.to_string())
+    } else if let Some(value) = any.downcast_ref::<SQLValue>() {
+        from_sql_value(value)?
+    } else if let Some(values) = any.downcast_ref::<Vec<SQLValue>>() {
+        json_array(values.iter().map(from_sql_value))?
+    } else if let Some(values) = any.downcast_ref::<Vec<SQLParamContainer>>() {
+        json_array(values.iter().map(|value| to_sqlite_value(value.as_ref())))?
+    } else {
+        return Err(DatabaseError::Validation(format!(
+            "Unsupported parameter for SQLite: {param:?}"
+        )));
+    };
+
+    Ok(value)
+}
+
+fn json_array(
+    values: impl Iterator<Item = Result<Value, DatabaseError>>,
+) -> Result<Value, DatabaseError> {
+    let elements = values
+        .map(|value| {
+            value.map(|value| match value {
+                Value::Null => serde_json::Value::Null,
+                Value::Integer(value) => value.into(),
+                Value::Real(value) => value.into(),
+                Value::Text(value) => value.into(),
+                Value::Blob(value) => STANDARD.encode(value).into(),
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(Value::Text(serde_json::Value::Array(elements).to_string()))
+}
+
+/// Convert a value returned by SQLite to an [SQLValue] (so that the subsequent steps in a
+/// transaction script can bind it as a parameter). We represent NULL as a value of the `VOID`
+/// type (which [from_sql_value] turns back into NULL).
+fn to_sql_value(value: &Value) -> Result<SQLValue, DatabaseError> {
+    fn encode<T: ToSql>(value: T, type_: Type) -> Result<SQLValue, DatabaseError> {
+        let mut buf = BytesMut::new();
+        value.to_sql(&type_, &mut buf)?;
+        Ok(SQLValue::new(buf.to_vec(), type_))
+    }
+
+    match value {
+        Value::Null => Ok(SQLValue::new(vec![], Type::VOID)),
+        Value::Integer(value) => encode(value, Type::INT8),
+        Value::Real(value) => encode(value, Type::FLOAT8),
+        Value::Text(value) => encode(value, Type::TEXT),
+        Value::Blob(value) => encode(value, Type::BYTEA),
+    }
+}
+
+/// Convert an [SQLValue] (either returned from a previous step or from Postgres) to its SQLite
+/// counterpart
+fn from_sql_value(value: &SQLValue) -> Result<Value, DatabaseError> {
+    let (type_, raw) = value.as_raw();
+
+    let value = match *type_ {
+        Type::VOID => Value::Null,
+        Type::BOOL => Value::Integer(bool::from_sql(type_, raw)? as i64),
+        Type::INT2 => Value::Integer(i16::from_sql(type_, raw)? as i64),
+        Type::INT4 => Value::Integer(i32::from_sql(type_, raw)? as i64),
+        Type::INT8 => Value::Integer(i64::from_sql(type_, raw)?),
+        Type::FLOAT4 => Value::Real(f32::from_sql(type_, raw)? as f64),
+        Type::FLOAT8 => Value::Real(f64::from_sql(type_, raw)?),
+        Type::TEXT | Type::VARCHAR | Type::BPCHAR | Type::NAME => {
+            Value::Text(String::from_sql(type_, raw)?)
+        }
+        Type::BYTEA => Value::Blob(raw.to_vec()),
+        _ => {
+            return Err(DatabaseError::Validation(format!(
+                "Unsupported value for SQLite: {value}"
+            )))
+        }
+    };
+
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sql_value_round_trip() {
+        for value in [
+            Value::Null,
+            Value::Integer(42),
+            Value::Real(4.2),
+            Value::Text("concert".to_string()),
+            Value::Blob(vec![1, 2, 3]),
+        ] {
+            let sql_value = to_sql_value(&value).unwrap();
+            assert_eq!(from_sql_value(&sql_value).unwrap(), value);
+        }
+    }
+
+    #[test]
+    fn array_param() {
+        let param = SQLParamContainer::new(vec![
+            to_sql_value(&Value::Integer(1)).unwrap(),
+            to_sql_value(&Value::Text("two".to_string())).unwrap(),
+        ]);
+
+        assert_eq!(
+            to_sqlite_value(param.as_ref()).unwrap(),
+            Value::Text(r#"[1,"two"]"#.to_string())
+        );
+    }
+
+    #[test]
+    fn execute_select() {
+        use crate::{
+            schema::{
+                database_spec::DatabaseSpec,
+                table_spec::TableSpec,
+                test_helper::{pk_column, string_column},
+            },
+            AbstractPredicate, AbstractSelect, AliasedSelectionElement, ColumnPath,
+            PhysicalColumnPath, PhysicalTableName, Selection, SelectionCardinality,
+            SelectionElement,
+        };
+
+        let database = DatabaseSpec::new(vec![TableSpec::new(
+            PhysicalTableName::new("concerts", None),
+            vec![pk_column("id"), string_column("title")],
+            vec![],
+        )])
+        .to_database();
+        let table_id = database
+            .get_table_id(&PhysicalTableName::new("concerts", None))
+            .unwrap();
+        let title_column_id = database.get_column_id(table_id, "title").unwrap();
+
+        let sqlite = SqliteDatabase::open_in_memory().unwrap();
+        sqlite
+            .execute_batch(
+                r#"CREATE TABLE "concerts" ("id" INTEGER PRIMARY KEY, "title" TEXT);
+                   INSERT INTO "concerts" ("title") VALUES ('C1'), ('C2'), ('c3');"#,
+            )
+            .unwrap();
+
+        let operation = AbstractOperation::Select(AbstractSelect {
+            table_id,
+            selection: Selection::Json(
+                vec![AliasedSelectionElement::new(
+                    "title".to_string(),
+                    SelectionElement::Physical(title_column_id),
+                )],
+                SelectionCardinality::Many,
+            ),
+            predicate: AbstractPredicate::StringStartsWith(
+                ColumnPath::Physical(PhysicalColumnPath::leaf(title_column_id)),
+                ColumnPath::Param(SQLParamContainer::new("C".to_string())),
+            ),
+            order_by: None,
+            offset: None,
+            limit: None,
+        });
+
+        let rows = sqlite.execute(&operation, &database).unwrap();
+        assert_eq!(
+            rows,
+            vec![vec![Value::Text(
+                r#"[{"title":"C1"},{"title":"C2"}]"#.to_string()
+            )]]
+        );
+    }
+}
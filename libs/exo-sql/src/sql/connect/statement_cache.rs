@@ -0,0 +1,211 @@
+// Copyright Exograph, Inc. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file at the root of this repository.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! Prepared statements of a connection, keyed by their SQL text.
+//!
+//! Without a cache, each statement is parsed and planned by Postgres every time we execute it.
+//! Since the SQL we generate for an operation (especially a trusted document) varies only in its
+//! parameters, reusing the prepared statement avoids most of that work.
+//!
+//! Prepared statements belong to a server connection, so a cached statement fails to execute when
+//! connecting through a pooler that may use a different server connection for each transaction
+//! (such as PgBouncer in transaction mode). Since we can't tell whether there is such a pooler in
+//! between, the cache is disabled unless a size is set explicitly.
+
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+};
+
+use tokio_postgres::{GenericClient, Statement};
+
+/// The default number of statements to keep for each connection (zero, so caching is opt-in)
+pub const DEFAULT_STATEMENT_CACHE_SIZE: usize = 0;
+
+static HITS: AtomicU64 = AtomicU64::new(0);
+static MISSES: AtomicU64 = AtomicU64::new(0);
+static EVICTIONS: AtomicU64 = AtomicU64::new(0);
+static CACHED: AtomicU64 = AtomicU64::new(0);
+
+/// Statement cache metrics (across all connections) since the start of the process
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StatementCacheMetrics {
+    /// Statements executed using a cached prepared statement
+    pub hits: u64,
+    /// Statements that had to be prepared
+    pub misses: u64,
+    /// Statements removed to keep a cache within its size
+    pub evictions: u64,
+    /// Statements currently cached
+    pub cached: u64,
+}
+
+pub fn statement_cache_metrics() -> StatementCacheMetrics {
+    StatementCacheMetrics {
+        hits: HITS.load(Ordering::Relaxed),
+        misses: MISSES.load(Ordering::Relaxed),
+        evictions: EVICTIONS.load(Ordering::Relaxed),
+        cached: CACHED.load(Ordering::Relaxed),
+    }
+}
+
+pub struct StatementCache {
+    statements: Mutex<LruMap<Statement>>,
+}
+
+impl StatementCache {
+    /// A cache with the given size (which must be non-zero)
+    pub fn new(size: usize) -> Self {
+        Self {
+            statements: Mutex::new(LruMap::new(size)),
+        }
+    }
+
+    /// Get the prepared statement for the SQL, preparing it if it isn't cached
+    pub async fn prepare(
+        &self,
+        client: &impl GenericClient,
+        sql: &str,
+    ) -> Result<Statement, tokio_postgres::Error> {
+        if let Some(statement) = self.statements.lock().unwrap().get(sql) {
+            HITS.fetch_add(1, Ordering::Relaxed);
+            return Ok(statement);
+        }
+
+        MISSES.fetch_add(1, Ordering::Relaxed);
+        let statement = client.prepare(sql).await?;
+
+        let (added, evicted) = self
+            .statements
+            .lock()
+            .unwrap()
+            .insert(sql.to_string(), statement.clone());
+        if added {
+            CACHED.fetch_add(1, Ordering::Relaxed);
+        }
+        if evicted {
+            EVICTIONS.fetch_add(1, Ordering::Relaxed);
+            CACHED.fetch_sub(1, Ordering::Relaxed);
+        }
+
+        Ok(statement)
+    }
+
+    /// Remove a statement (for example, after executing it failed, since a schema change may
+    /// have invalidated it)
+    pub fn remove(&self, sql: &str) {
+        if self.statements.lock().unwrap().remove(sql) {
+            CACHED.fetch_sub(1, Ordering::Relaxed);
+        }
+    }
+}
+
+impl Drop for StatementCache {
+    fn drop(&mut self) {
+        let len = self.statements.get_mut().map(|s| s.len()).unwrap_or(0);
+        CACHED.fetch_sub(len as u64, Ordering::Relaxed);
+    }
+}
+
+/// A map that keeps at most `capacity` entries by removing the least recently used entry
+struct LruMap<V> {
+    capacity: usize,
+    entries: HashMap<String, (V, u64)>,
+    /// Incremented on each access to track the last use of each entry
+    clock: u64,
+}
+
+impl<V: Clone> LruMap<V> {
+    fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            entries: HashMap::new(),
+            clock: 0,
+        }
+    }
+
+    fn get(&mut self, key: &str) -> Option<V> {
+        self.clock += 1;
+        let clock = self.clock;
+
+        self.entries.get_mut(key).map(|(value, last_used)| {
+            *last_used = clock;
+            value.clone()
+        })
+    }
+
+    /// Returns whether a new entry was added and whether an entry was evicted to make room for it
+    fn insert(&mut self, key: String, value: V) -> (bool, bool) {
+        self.clock += 1;
+
+        let added = self.entries.insert(key, (value, self.clock)).is_none();
+
+        let evicted = self.entries.len() > self.capacity;
+        if evicted {
+            let least_recently_used = self
+                .entries
+                .iter()
+                .min_by_key(|(_, (_, last_used))| *last_used)
+                .map(|(key, _)| key.clone());
+
+            if let Some(key) = least_recently_used {
+                self.entries.remove(&key);
+            }
+        }
+
+        (added, evicted)
+    }
+
+    fn remove(&mut self, key: &str) -> bool {
+        self.entries.remove(key).is_some()
+    }
+
+    fn len(&self) -> usize {
+        self.entries.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use multiplatform_test::multiplatform_test;
+
+    #[multiplatform_test]
+    fn evicts_least_recently_used() {
+        let mut map = LruMap::new(2);
+
+        assert_eq!(map.insert("a".to_string(), 1), (true, false));
+        assert_eq!(map.insert("b".to_string(), 2), (true, false));
+
+        // Using "a" makes "b" the least recently used
+        assert_eq!(map.get("a"), Some(1));
+        assert_eq!(map.insert("c".to_string(), 3), (true, true));
+
+        assert_eq!(map.get("a"), Some(1));
+        assert_eq!(map.get("b"), None);
+        assert_eq!(map.get("c"), Some(3));
+        assert_eq!(map.len(), 2);
+    }
+
+    #[multiplatform_test]
+    fn replaces_and_removes() {
+        let mut map = LruMap::new(2);
+
+        map.insert("a".to_string(), 1);
+        assert_eq!(map.insert("a".to_string(), 2), (false, false));
+        assert_eq!(map.get("a"), Some(2));
+
+        assert!(map.remove("a"));
+        assert!(!map.remove("a"));
+        assert_eq!(map.len(), 0);
+    }
+}
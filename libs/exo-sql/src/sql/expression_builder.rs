@@ -11,7 +11,7 @@ use std::sync::Arc;
 
 use maybe_owned::MaybeOwned;
 
-use super::SQLBuilder;
+use super::{SQLBuilder, SQLDialect};
 use crate::{Database, SQLParam};
 
 /// A trait for types that can build themselves into an SQL expression.
@@ -31,7 +31,19 @@ pub trait ExpressionBuilder {
     where
         Self: Sized,
     {
-        let mut builder = SQLBuilder::new();
+        self.to_sql_for_dialect(database, SQLDialect::Postgres)
+    }
+
+    /// Same as [`ExpressionBuilder::to_sql`], but renders the SQL in the given dialect
+    fn to_sql_for_dialect(
+        &self,
+        database: &Database,
+        dialect: SQLDialect,
+    ) -> (String, Vec<Arc<dyn SQLParam>>)
+    where
+        Self: Sized,
+    {
+        let mut builder = SQLBuilder::with_dialect(dialect);
         self.build(database, &mut builder);
         builder.into_sql()
     }
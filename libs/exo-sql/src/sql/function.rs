@@ -7,6 +7,8 @@ pub enum Function {
     Named {
         function_name: String,
         column_id: ColumnId,
+        /// The alias of the column's table (if the table is aliased in the query)
+        table_alias: Option<String>,
     },
     VectorDistance {
         column_id: ColumnId,
@@ -21,11 +23,17 @@ impl ExpressionBuilder for Function {
             Function::Named {
                 function_name,
                 column_id,
+                table_alias,
             } => {
                 builder.push_str(function_name);
                 builder.push('(');
                 let column = column_id.get_column(database);
-                column.build(database, builder);
+                match table_alias {
+                    Some(table_alias) => {
+                        builder.push_column_with_table_alias(&column.name, table_alias)
+                    }
+                    None => column.build(database, builder),
+                }
                 builder.push(')');
                 if matches!(column.typ, PhysicalColumnType::Vector { .. })
                     && function_name != "count"
@@ -9,18 +9,31 @@
 
 use crate::{Column, Database};
 
-use super::{ExpressionBuilder, SQLBuilder};
+use super::{ExpressionBuilder, SQLBuilder, SQLDialect};
 
-/// A JSON aggregation corresponding to the Postgres' `json_agg` function.
+/// A JSON aggregation corresponding to the Postgres' `json_agg` function (SQLite's
+/// `json_group_array`).
 #[derive(Debug, PartialEq)]
 pub struct JsonAgg(pub Box<Column>);
 
 impl ExpressionBuilder for JsonAgg {
     /// Build expression of the form `COALESCE(json_agg(<column>)), '[]'::json)`. The COALESCE
     /// wrapper ensures that return an empty array if we have no matching entities.
+    ///
+    /// For SQLite, build `json_group_array(<column>)`, which already returns an empty array if
+    /// there are no matching entities.
     fn build(&self, database: &Database, builder: &mut SQLBuilder) {
-        builder.push_str("COALESCE(json_agg(");
-        self.0.build(database, builder);
-        builder.push_str("), '[]'::json)");
+        match builder.dialect() {
+            SQLDialect::Postgres => {
+                builder.push_str("COALESCE(json_agg(");
+                self.0.build(database, builder);
+                builder.push_str("), '[]'::json)");
+            }
+            SQLDialect::Sqlite => {
+                builder.push_str("json_group_array(");
+                self.0.build(database, builder);
+                builder.push(')');
+            }
+        }
     }
 }
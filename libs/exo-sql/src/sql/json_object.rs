@@ -11,10 +11,11 @@ use crate::{Column, Database};
 
 use super::{
     physical_column::{PhysicalColumn, PhysicalColumnType},
-    ExpressionBuilder, SQLBuilder,
+    ExpressionBuilder, SQLBuilder, SQLDialect,
 };
 
-/// A JSON object corresponding to the Postgres' `json_build_object` function.
+/// A JSON object corresponding to the Postgres' `json_build_object` function (SQLite's
+/// `json_object`).
 #[derive(Debug, PartialEq)]
 pub struct JsonObject(pub Vec<JsonObjectElement>);
 
@@ -34,7 +35,10 @@ impl JsonObjectElement {
 impl ExpressionBuilder for JsonObject {
     /// Build expression of the form `json_build_object(<comma-separated-elements>)`.
     fn build(&self, database: &Database, builder: &mut SQLBuilder) {
-        builder.push_str("json_build_object(");
+        builder.push_str(match builder.dialect() {
+            SQLDialect::Postgres => "json_build_object(",
+            SQLDialect::Sqlite => "json_object(",
+        });
         builder.push_elems(database, &self.0, ", ");
         builder.push(')');
     }
@@ -50,6 +54,10 @@ impl ExpressionBuilder for JsonObjectElement {
         builder.push_str(&self.key);
         builder.push_str("', ");
 
+        if builder.dialect() == SQLDialect::Sqlite {
+            return self.build_sqlite_value(database, builder);
+        }
+
         if let Column::Physical { column_id, .. } = self.value {
             let PhysicalColumn { typ, .. } = column_id.get_column(database);
             match &typ {
@@ -80,3 +88,49 @@ impl ExpressionBuilder for JsonObjectElement {
         }
     }
 }
+
+impl JsonObjectElement {
+    /// Build the value for SQLite. SQLite stores booleans as integers and JSON as text, so we need
+    /// to convert those to their JSON counterparts. Similarly, a JSON value returned from a subquery
+    /// loses its JSON-ness (and would be embedded as a string), so we need to reinterpret it as JSON.
+    fn build_sqlite_value(&self, database: &Database, builder: &mut SQLBuilder) {
+        match &self.value {
+            Column::Physical { column_id, .. } => {
+                let PhysicalColumn { typ, .. } = column_id.get_column(database);
+                match &typ {
+                    // `base64` is registered by the SQLite connection (SQLite doesn't have a
+                    // built-in function to encode blobs as base64)
+                    PhysicalColumnType::Blob => {
+                        builder.push_str("base64(");
+                        self.value.build(database, builder);
+                        builder.push(')');
+                    }
+                    PhysicalColumnType::Numeric { .. } => {
+                        builder.push_str("CAST(");
+                        self.value.build(database, builder);
+                        builder.push_str(" AS TEXT)");
+                    }
+                    PhysicalColumnType::Boolean => {
+                        builder.push_str("json(CASE WHEN ");
+                        self.value.build(database, builder);
+                        builder.push_str(" IS NULL THEN 'null' WHEN ");
+                        self.value.build(database, builder);
+                        builder.push_str(" THEN 'true' ELSE 'false' END)");
+                    }
+                    PhysicalColumnType::Json | PhysicalColumnType::Vector { .. } => {
+                        builder.push_str("json(");
+                        self.value.build(database, builder);
+                        builder.push(')');
+                    }
+                    _ => self.value.build(database, builder),
+                }
+            }
+            Column::SubSelect(_) => {
+                builder.push_str("json(");
+                self.value.build(database, builder);
+                builder.push(')');
+            }
+            _ => self.value.build(database, builder),
+        }
+    }
+}
@@ -24,6 +24,7 @@ pub mod function;
 pub mod order;
 pub mod physical_column;
 pub mod predicate;
+pub mod query_log;
 pub mod relation;
 pub mod vector;
 
@@ -48,7 +49,7 @@ pub(crate) mod transaction;
 pub(crate) mod update;
 
 pub(crate) use expression_builder::ExpressionBuilder;
-pub(crate) use sql_builder::SQLBuilder;
+pub(crate) use sql_builder::{SQLBuilder, SQLDialect};
 pub(crate) use sql_value::SQLValue;
 
 mod expression_builder;
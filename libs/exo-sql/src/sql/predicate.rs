@@ -9,7 +9,11 @@
 
 use crate::{Database, VectorDistanceFunction};
 
-use super::{column::Column, vector::VectorDistance, ExpressionBuilder, SQLBuilder};
+use super::{
+    column::{ArrayParamWrapper, Column},
+    vector::VectorDistance,
+    ExpressionBuilder, SQLBuilder, SQLDialect, SQLParamContainer,
+};
 
 /// Case sensitivity for string predicates.
 #[derive(Debug, Clone, PartialEq, Eq, Copy)]
@@ -64,6 +68,10 @@ where
     Or(Box<Predicate<C>>, Box<Predicate<C>>),
     // Prefer Predicate::not(), which simplifies the clause
     Not(Box<Predicate<C>>),
+    // Holds unless the predicate is true, so unlike `Not`, it holds when the predicate is unknown
+    // (for example, when it compares a null column). Prefer Predicate::is_not_true(), which
+    // simplifies the clause
+    IsNotTrue(Box<Predicate<C>>),
 }
 
 pub type ConcretePredicate = Predicate<Column>;
@@ -111,6 +119,16 @@ where
             (lhs, rhs) => Predicate::Or(Box::new(lhs), Box::new(rhs)),
         }
     }
+
+    /// Negation that treats an unknown result as false (`NOT COALESCE(predicate, false)`),
+    /// reducing to a simpler predicate if possible.
+    pub fn is_not_true(predicate: Predicate<C>) -> Predicate<C> {
+        match predicate {
+            Predicate::True => Predicate::False,
+            Predicate::False => Predicate::True,
+            predicate => Predicate::IsNotTrue(Box::new(predicate)),
+        }
+    }
 }
 
 impl<C> From<bool> for Predicate<C>
@@ -177,6 +195,16 @@ impl ExpressionBuilder for ConcretePredicate {
                 if column2 == &Column::Null {
                     column1.build(database, builder);
                     builder.push_str(" IS NULL");
+                } else if let Some(param) =
+                    sqlite_array_param(column2, ArrayParamWrapper::Any, builder)
+                {
+                    // SQLite doesn't have arrays, so `= ANY(<array>)` becomes `IN` over the
+                    // elements of a JSON array (see the SQLite connection for how array
+                    // parameters are bound)
+                    column1.build(database, builder);
+                    builder.push_str(" IN (SELECT value FROM json_each(");
+                    builder.push_param(param.param());
+                    builder.push_str("))");
                 } else {
                     relational_combine(column1, column2, "=", database, builder)
                 }
@@ -185,6 +213,13 @@ impl ExpressionBuilder for ConcretePredicate {
                 if column2 == &Column::Null {
                     column1.build(database, builder);
                     builder.push_str(" IS NOT NULL");
+                } else if let Some(param) =
+                    sqlite_array_param(column2, ArrayParamWrapper::All, builder)
+                {
+                    column1.build(database, builder);
+                    builder.push_str(" NOT IN (SELECT value FROM json_each(");
+                    builder.push_param(param.param());
+                    builder.push_str("))");
                 } else {
                     relational_combine(column1, column2, "<>", database, builder)
                 }
@@ -205,6 +240,21 @@ impl ExpressionBuilder for ConcretePredicate {
                 relational_combine(column1, column2, "IN", database, builder)
             }
 
+            ConcretePredicate::StringLike(column1, column2, case_sensitivity)
+                if builder.dialect() == SQLDialect::Sqlite =>
+            {
+                // SQLite doesn't have ILIKE. The SQLite connection makes LIKE case-sensitive, so
+                // we lower-case both sides for a case-insensitive match.
+                if *case_sensitivity == CaseSensitivity::Insensitive {
+                    builder.push_str("lower(");
+                    column1.build(database, builder);
+                    builder.push_str(") LIKE lower(");
+                    column2.build(database, builder);
+                    builder.push(')');
+                } else {
+                    relational_combine(column1, column2, "LIKE", database, builder)
+                }
+            }
             ConcretePredicate::StringLike(column1, column2, case_sensitivity) => {
                 relational_combine(
                     column1,
@@ -278,7 +328,29 @@ impl ExpressionBuilder for ConcretePredicate {
                 predicate.build(database, builder);
                 builder.push(')');
             }
+            ConcretePredicate::IsNotTrue(predicate) => {
+                builder.push_str("NOT COALESCE(");
+                predicate.build(database, builder);
+                builder.push_str(", false)");
+            }
+        }
+    }
+}
+
+/// The array parameter of `column` if we are building for SQLite and `column` is an array
+/// parameter with the given wrapper.
+fn sqlite_array_param<'a>(
+    column: &'a Column,
+    expected_wrapper: ArrayParamWrapper,
+    builder: &SQLBuilder,
+) -> Option<&'a SQLParamContainer> {
+    match column {
+        Column::ArrayParam { param, wrapper }
+            if builder.dialect() == SQLDialect::Sqlite && *wrapper == expected_wrapper =>
+        {
+            Some(param)
         }
+        _ => None,
     }
 }
 
@@ -0,0 +1,268 @@
+// Copyright Exograph, Inc. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file at the root of this repository.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! Reporting of the statements executed for a request.
+//!
+//! Statements that take longer than a threshold are logged (with the `exograph::slow_query`
+//! target) along with the GraphQL operation they were executed for. Since the log may end up in
+//! places with less restricted access than the database, it doesn't include the parameter values.
+//!
+//! In development, the statements may also be captured (optionally along with their query plans)
+//! so that the response can include them. Getting the plan of a query runs it again, so capturing
+//! plans roughly doubles the time spent in the database.
+//!
+//! Statements may also carry the trace context of the request as a comment (in the
+//! [sqlcommenter](https://google.github.io/sqlcommenter/) format), so that the database's view of a
+//! statement (such as in `pg_stat_activity` or its logs) can be tied to the request's trace.
+
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use common::trace_context::TraceContext;
+use serde::Serialize;
+use tokio_postgres::{error::SqlState, types::ToSql, GenericClient, Row};
+use tracing::warn;
+
+use super::{connect::statement_cache::StatementCache, SQLParam};
+
+/// What to capture for each statement
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatementCapture {
+    /// The statement, its parameters, and how long it took
+    Statements,
+    /// Also the query plan (see [`CapturedStatement::plan`])
+    StatementsWithPlans,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CapturedStatement {
+    /// The GraphQL operation the statement was executed for
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub operation: Option<String>,
+    pub sql: String,
+    pub params: Vec<String>,
+    /// How long the statement took (not available in WebAssembly)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duration_ms: Option<f64>,
+    /// The output of `EXPLAIN (ANALYZE, FORMAT JSON)` for queries (which executes them a second
+    /// time) and `EXPLAIN (FORMAT JSON)` for other statements (see [`explain`])
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub plan: Option<serde_json::Value>,
+}
+
+/// The statements executed for a request
+#[derive(Debug, Default)]
+pub struct QueryLog {
+    pub(crate) slow_query_threshold: Option<Duration>,
+    /// Whether to add the trace context to statements as a comment
+    pub(crate) sql_comments: bool,
+    pub(crate) operation: Option<String>,
+    pub(crate) capture: Option<StatementCapture>,
+    pub(crate) captured: Vec<CapturedStatement>,
+}
+
+impl QueryLog {
+    /// Execute a statement (using the cached prepared statement, if a cache is provided), logging
+    /// it if it is slow and capturing it if enabled. `is_query` tells if the statement is a query
+    /// (which doesn't modify data, so it is safe to execute it again to get its plan).
+    pub(crate) async fn query(
+        &mut self,
+        client: &mut impl GenericClient,
+        statement_cache: Option<&StatementCache>,
+        stmt: &str,
+        params: &[Arc<dyn SQLParam>],
+        is_query: bool,
+    ) -> Result<Vec<Row>, tokio_postgres::Error> {
+        let pg_params: Vec<_> = params.iter().map(|p| p.as_pg()).collect();
+
+        let commented_stmt = if self.sql_comments {
+            TraceContext::current().map(|trace_context| with_trace_comment(stmt, &trace_context))
+        } else {
+            None
+        };
+        // A statement with a comment is unique to its request, so preparing it once per connection
+        // gains nothing (and would fill the cache)
+        let (stmt, statement_cache) = match &commented_stmt {
+            Some(commented_stmt) => (commented_stmt.as_str(), None),
+            None => (stmt, statement_cache),
+        };
+
+        let start = (self.slow_query_threshold.is_some() || self.capture.is_some())
+            .then(now)
+            .flatten();
+        let rows = match statement_cache {
+            Some(statement_cache) => {
+                let statement = statement_cache.prepare(client, stmt).await?;
+                client
+                    .query(&statement, &pg_params[..])
+                    .await
+                    .inspect_err(|e| {
+                        // The statement may no longer be valid (for example, after a schema change)
+                        if e.code() == Some(&SqlState::FEATURE_NOT_SUPPORTED)
+                            || e.code() == Some(&SqlState::INVALID_SQL_STATEMENT_NAME)
+                        {
+                            statement_cache.remove(stmt)
+                        }
+                    })?
+            }
+            None => client.query(stmt, &pg_params[..]).await?,
+        };
+        let duration = start.map(|start| start.elapsed());
+
+        if let Some(duration) = duration.filter(|duration| {
+            self.slow_query_threshold
+                .is_some_and(|threshold| *duration >= threshold)
+        }) {
+            warn!(
+                target: "exograph::slow_query",
+                operation = self.operation.as_deref(),
+                duration_ms = duration.as_millis() as u64,
+                sql = stmt,
+                params = ?vec!["<redacted>"; params.len()],
+                "Slow query"
+            );
+        }
+
+        if let Some(capture) = self.capture {
+            let plan = match capture {
+                StatementCapture::Statements => None,
+                StatementCapture::StatementsWithPlans => {
+                    match explain(client, stmt, &pg_params, is_query).await {
+                        Ok(plan) => plan,
+                        Err(e) => {
+                            warn!("Failed to explain the statement: {e}");
+                            None
+                        }
+                    }
+                }
+            };
+
+            self.captured.push(CapturedStatement {
+                operation: self.operation.clone(),
+                sql: stmt.to_string(),
+                params: params.iter().map(|p| format!("{p:?}")).collect(),
+                duration_ms: duration.map(|duration| duration.as_secs_f64() * 1000.0),
+                plan,
+            });
+        }
+
+        Ok(rows)
+    }
+}
+
+/// The query plan of a statement. Since `ANALYZE` executes the statement (so a query with a captured
+/// plan runs twice), only queries are analyzed; for other statements, we get just the estimated
+/// plan. Either way, the statement is explained in a nested transaction (a savepoint, if we are
+/// already in a transaction) that is rolled back, so that the explained statement leaves no changes
+/// behind and a failure to explain doesn't abort the enclosing transaction.
+async fn explain(
+    client: &mut impl GenericClient,
+    stmt: &str,
+    pg_params: &[&(dyn ToSql + Sync)],
+    is_query: bool,
+) -> Result<Option<serde_json::Value>, tokio_postgres::Error> {
+    let explain = if is_query {
+        "EXPLAIN (ANALYZE, FORMAT JSON)"
+    } else {
+        "EXPLAIN (FORMAT JSON)"
+    };
+
+    let transaction = client.transaction().await?;
+    let row = transaction
+        .query_one(&format!("{explain} {stmt}"), pg_params)
+        .await;
+    transaction.rollback().await?;
+
+    Ok(row?.try_get::<_, serde_json::Value>(0).ok())
+}
+
+/// The statement with the trace context (typically of the current span) as a comment
+fn with_trace_comment(stmt: &str, trace_context: &TraceContext) -> String {
+    let mut comment = format!("traceparent='{}'", trace_context.traceparent);
+    // Values are URL-encoded, which also ensures that they can't end the quote or the comment
+    if let Some(tracestate) = &trace_context.tracestate {
+        comment.push_str(&format!(
+            ",tracestate='{}'",
+            urlencoding::encode(tracestate)
+        ));
+    }
+
+    format!("{stmt} /*{comment}*/")
+}
+
+/// The current time, if available (`Instant::now` panics in WebAssembly)
+fn now() -> Option<Instant> {
+    #[cfg(not(target_family = "wasm"))]
+    {
+        Some(Instant::now())
+    }
+
+    #[cfg(target_family = "wasm")]
+    {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use multiplatform_test::multiplatform_test;
+
+    #[multiplatform_test]
+    fn serialize() {
+        let statement = CapturedStatement {
+            operation: Some("concerts".to_string()),
+            sql: "SELECT 1".to_string(),
+            params: vec!["42".to_string()],
+            duration_ms: Some(1.5),
+            plan: None,
+        };
+
+        assert_eq!(
+            serde_json::to_value(statement).unwrap(),
+            serde_json::json!({
+                "operation": "concerts",
+                "sql": "SELECT 1",
+                "params": ["42"],
+                "durationMs": 1.5
+            })
+        );
+    }
+
+    #[multiplatform_test]
+    fn trace_comment() {
+        let trace_context = |tracestate: Option<&str>| TraceContext {
+            traceparent: "00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01".to_string(),
+            tracestate: tracestate.map(|tracestate| tracestate.to_string()),
+        };
+
+        assert_eq!(
+            with_trace_comment("SELECT 1", &trace_context(None)),
+            "SELECT 1 /*traceparent='00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01'*/"
+        );
+        assert_eq!(
+            with_trace_comment(
+                "SELECT 1",
+                &trace_context(Some("congo=t61rcWkgMzE,rojo=00f067"))
+            ),
+            "SELECT 1 /*traceparent='00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01',\
+             tracestate='congo%3Dt61rcWkgMzE%2Crojo%3D00f067'*/"
+        );
+
+        // A tracestate (which comes from the caller) can't end the quote or the comment
+        assert_eq!(
+            with_trace_comment("SELECT 1", &trace_context(Some("a=b'*/; DROP TABLE t; --"))),
+            "SELECT 1 /*traceparent='00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01',\
+             tracestate='a%3Db%27%2A%2F%3B%20DROP%20TABLE%20t%3B%20--'*/"
+        );
+    }
+}
@@ -13,7 +13,7 @@ use crate::{Database, Limit, Offset};
 
 use super::{
     column::Column, group_by::GroupBy, order::OrderBy, predicate::ConcretePredicate, table::Table,
-    ExpressionBuilder, SQLBuilder,
+    ExpressionBuilder, SQLBuilder, SQLDialect,
 };
 
 /// A select statement
@@ -35,7 +35,8 @@ pub struct Select {
     pub group_by: Option<GroupBy>,
     /// Whether this is a top-level selection. This is used to put the `::text` cast on a top-level select statement
     /// This way, we can grab the JSON as a string and return it to the user as is. Specifically, we don't want to
-    /// decode into a JSON object and then re-encode it as a string.
+    /// decode into a JSON object and then re-encode it as a string. SQLite's JSON functions already
+    /// return text, so no cast is needed there.
     pub top_level_selection: bool,
 }
 
@@ -59,6 +60,7 @@ impl ExpressionBuilder for Select {
                 col.build(database, builder);
 
                 if self.top_level_selection
+                    && builder.dialect() == SQLDialect::Postgres
                     && matches!(col, Column::JsonObject(_) | Column::JsonAgg(_))
                 {
                     // See the comment on `top_level_selection` for why we do this
@@ -13,6 +13,17 @@ use crate::{Database, SQLParam};
 
 use super::{physical_table::PhysicalTableName, ExpressionBuilder};
 
+/// The SQL dialect to render expressions in. The abstract-to-concrete transformation produces the
+/// same SQL tree for both dialects, and only the rendering of a few constructs (parameter
+/// placeholders, JSON functions, casts, etc.) differs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SQLDialect {
+    #[default]
+    Postgres,
+    #[cfg_attr(not(feature = "sqlite"), allow(dead_code))]
+    Sqlite,
+}
+
 pub struct SQLBuilder {
     /// The SQL being built with placeholders for each parameter
     sql: String,
@@ -26,18 +37,29 @@ pub struct SQLBuilder {
     /// (...table...)"). Similarly, for a sub-select, the alias would be the name of the sub-select.
     /// This is used to render alias in lieu of table names for the related expressions.
     table_alias_map: HashMap<PhysicalTableName, String>,
+    /// The dialect to render the SQL in
+    dialect: SQLDialect,
 }
 
 impl SQLBuilder {
     pub fn new() -> Self {
+        Self::with_dialect(SQLDialect::Postgres)
+    }
+
+    pub fn with_dialect(dialect: SQLDialect) -> Self {
         Self {
             sql: String::new(),
             params: Vec::new(),
             fully_qualify_column_names: true,
             table_alias_map: HashMap::new(),
+            dialect,
         }
     }
 
+    pub fn dialect(&self) -> SQLDialect {
+        self.dialect
+    }
+
     /// Push a string
     pub fn push_str<T: AsRef<str>>(&mut self, s: T) {
         self.sql.push_str(s.as_ref());
@@ -98,10 +120,14 @@ impl SQLBuilder {
     }
 
     /// Push a parameter, which will be replaced with a placeholder in the SQL string
-    /// and the parameter will be added to the list of parameters.
+    /// and the parameter will be added to the list of parameters. The placeholder is `$<n>` for
+    /// Postgres and `?<n>` for SQLite.
     pub fn push_param(&mut self, param: Arc<dyn SQLParam>) {
         self.params.push(param);
-        self.push('$');
+        self.push(match self.dialect {
+            SQLDialect::Postgres => '$',
+            SQLDialect::Sqlite => '?',
+        });
         self.push_str(&self.params.len().to_string());
     }
 
@@ -20,6 +20,21 @@ pub struct SQLValue {
     type_: Type,
 }
 
+#[cfg(any(feature = "sqlite", test))]
+impl SQLValue {
+    pub(crate) fn new(value: Vec<u8>, type_: Type) -> Self {
+        Self { value, type_ }
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl SQLValue {
+    /// The type and the raw (Postgres binary format) value
+    pub(crate) fn as_raw(&self) -> (&Type, &[u8]) {
+        (&self.type_, &self.value)
+    }
+}
+
 impl Display for SQLValue {
     fn fmt(&self, fmt: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
         write!(fmt, "<SQLValue containing {}>", self.type_)
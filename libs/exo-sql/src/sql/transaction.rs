@@ -20,7 +20,9 @@ use crate::{
 
 use super::{
     column::ArrayParamWrapper,
+    connect::statement_cache::StatementCache,
     predicate::ConcretePredicate,
+    query_log::QueryLog,
     sql_operation::{SQLOperation, TemplateSQLOperation},
     ExpressionBuilder, SQLValue,
 };
@@ -32,11 +34,22 @@ pub type TransactionStepResult = Vec<Row>;
 #[derive(Default, Debug)]
 pub struct TransactionScript<'a> {
     steps: Vec<TransactionStep<'a>>,
+    /// The step whose result is the result of the script (the last step, if not set)
+    result_step_id: Option<TransactionStepId>,
 }
 
 /// Collection of results from steps in a transaction
 pub struct TransactionContext {
-    results: Vec<TransactionStepResult>,
+    results: Vec<StepRows>,
+}
+
+/// The rows returned by a step
+enum StepRows {
+    Postgres(TransactionStepResult),
+    /// Rows from a database other than Postgres, with each value converted to its Postgres
+    /// counterpart (so that the subsequent steps can bind them as usual)
+    #[cfg(any(feature = "sqlite", test))]
+    Values(Vec<Vec<SQLValue>>),
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -45,17 +58,35 @@ pub struct TransactionStepId(pub usize);
 impl TransactionContext {
     /// Returns the value of a column in a row from the given step id
     pub fn resolve_value(&self, step_id: TransactionStepId, row: usize, col: usize) -> SQLValue {
-        self.results[step_id.0][row].get::<usize, SQLValue>(col)
+        match &self.results[step_id.0] {
+            StepRows::Postgres(rows) => rows[row].get::<usize, SQLValue>(col),
+            #[cfg(any(feature = "sqlite", test))]
+            StepRows::Values(rows) => rows[row][col].clone(),
+        }
     }
 
     /// Returns the number of rows in the result of the given step id
     pub fn row_count(&self, step_id: TransactionStepId) -> usize {
-        self.results[step_id.0].len()
+        match &self.results[step_id.0] {
+            StepRows::Postgres(rows) => rows.len(),
+            #[cfg(any(feature = "sqlite", test))]
+            StepRows::Values(rows) => rows.len(),
+        }
+    }
+
+    #[cfg(any(feature = "sqlite", test))]
+    pub(crate) fn new() -> Self {
+        Self { results: vec![] }
+    }
+
+    #[cfg(any(feature = "sqlite", test))]
+    pub(crate) fn push_values(&mut self, rows: Vec<Vec<SQLValue>>) {
+        self.results.push(StepRows::Values(rows))
     }
 }
 
 impl<'a> TransactionScript<'a> {
-    /// Returns the result of the last step
+    /// Returns the result of the result step (see [`TransactionScript::set_result_step`])
     #[instrument(
         name = "TransactionScript::execute"
         skip_all
@@ -64,21 +95,31 @@ impl<'a> TransactionScript<'a> {
         self,
         database: &Database,
         tx: &mut T,
+        query_log: &mut QueryLog,
+        statement_cache: Option<&StatementCache>,
     ) -> Result<TransactionStepResult, DatabaseError> {
         let mut transaction_context = TransactionContext { results: vec![] };
+        let result_step_id = self.result_step_id();
 
         // Execute each step in the transaction and store the result in the transaction_context
         for step in self.steps.into_iter() {
-            let result = step.execute(database, tx, &transaction_context).await?;
-            transaction_context.results.push(result)
+            let result = step
+                .execute(
+                    database,
+                    tx,
+                    &transaction_context,
+                    query_log,
+                    statement_cache,
+                )
+                .await?;
+            transaction_context.results.push(StepRows::Postgres(result))
         }
 
-        // Return the result of the last step (usually the "select")
-        transaction_context
-            .results
-            .into_iter()
-            .last()
-            .ok_or_else(|| DatabaseError::Transaction("".into()))
+        // Return the result of the result step (usually the last "select")
+        match result_step_id.map(|id| transaction_context.results.swap_remove(id.0)) {
+            Some(StepRows::Postgres(rows)) => Ok(rows),
+            _ => Err(DatabaseError::Transaction("".into())),
+        }
     }
 
     /// Adds a step to the transaction script and return the step id (which is just the index of the step in the script)
@@ -88,6 +129,25 @@ impl<'a> TransactionScript<'a> {
         TransactionStepId(id)
     }
 
+    /// Make the result of the given step the result of the script. Needed when the steps after
+    /// the one producing the result don't return the same rows (for example, a select followed by
+    /// a delete).
+    #[cfg(any(feature = "sqlite", test))]
+    pub fn set_result_step(&mut self, step_id: TransactionStepId) {
+        self.result_step_id = Some(step_id);
+    }
+
+    /// The step whose result is the result of the script (`None` if the script is empty)
+    pub(crate) fn result_step_id(&self) -> Option<TransactionStepId> {
+        self.result_step_id
+            .or_else(|| self.steps.len().checked_sub(1).map(TransactionStepId))
+    }
+
+    #[cfg(any(feature = "sqlite", test))]
+    pub(crate) fn into_steps(self) -> Vec<TransactionStep<'a>> {
+        self.steps
+    }
+
     pub fn needs_transaction(&self) -> bool {
         self.steps.len() > 1
     }
@@ -112,9 +172,14 @@ impl<'a> TransactionStep<'a> {
         database: &Database,
         client: &mut impl GenericClient,
         transaction_context: &TransactionContext,
+        query_log: &mut QueryLog,
+        statement_cache: Option<&StatementCache>,
     ) -> Result<TransactionStepResult, DatabaseError> {
         match self {
-            Self::Concrete(step) => step.execute(database, client).await,
+            Self::Concrete(step) => {
+                step.execute(database, client, query_log, statement_cache)
+                    .await
+            }
             Self::Template(step) => {
                 let concrete = step.resolve(transaction_context);
 
@@ -125,10 +190,14 @@ impl<'a> TransactionStep<'a> {
                 for (index, substep) in concrete.into_iter().enumerate() {
                     if index == substep_count - 1 {
                         // Execute the last step and return the result
-                        res = substep.execute(database, client).await;
+                        res = substep
+                            .execute(database, client, query_log, statement_cache)
+                            .await;
                     } else {
                         // Execute all but the last step
-                        substep.execute(database, client).await?;
+                        substep
+                            .execute(database, client, query_log, statement_cache)
+                            .await?;
                     }
                 }
 
@@ -136,11 +205,13 @@ impl<'a> TransactionStep<'a> {
             }
             Self::Filter(step) => {
                 let concrete = step.resolve(transaction_context, database);
-                concrete.execute(database, client).await
+                concrete
+                    .execute(database, client, query_log, statement_cache)
+                    .await
             }
             Self::Dynamic(step) => {
                 step.resolve(transaction_context)
-                    .execute(database, client)
+                    .execute(database, client, query_log, statement_cache)
                     .await
             }
         }
@@ -169,27 +240,35 @@ impl<'a> ConcreteTransactionStep<'a> {
         self,
         database: &Database,
         client: &mut impl GenericClient,
+        query_log: &mut QueryLog,
+        statement_cache: Option<&StatementCache>,
     ) -> Result<TransactionStepResult, DatabaseError> {
-        self.run_query(database, client).await
+        self.run_query(database, client, query_log, statement_cache)
+            .await
     }
 
     async fn run_query(
         &'a self,
         database: &Database,
         client: &mut impl GenericClient,
+        query_log: &mut QueryLog,
+        statement_cache: Option<&StatementCache>,
     ) -> Result<TransactionStepResult, DatabaseError> {
         let mut sql_builder = SQLBuilder::new();
         self.operation.build(database, &mut sql_builder);
         let (stmt, params) = sql_builder.into_sql();
 
-        let params: Vec<_> = params.iter().map(|p| p.as_pg()).collect();
-
         info!("Executing SQL operation: {}", stmt);
 
-        client.query(&stmt, &params[..]).await.map_err(|e| {
-            error!("Failed to execute query: {e:?}");
-            DatabaseError::Delegate(e).with_context("Database operation failed".into())
-        })
+        let is_query = matches!(self.operation, SQLOperation::Select(_));
+
+        query_log
+            .query(client, statement_cache, &stmt, &params, is_query)
+            .await
+            .map_err(|e| {
+                error!("Failed to execute query: {e:?}");
+                DatabaseError::Delegate(e).with_context("Database operation failed".into())
+            })
     }
 }
 
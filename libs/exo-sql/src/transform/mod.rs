@@ -7,10 +7,12 @@
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
 
-//! Transform abstract operations into concrete operations for a specific database with an implementation for
-//! Postgres.
+//! Transform abstract operations into concrete operations for a specific database with
+//! implementations for Postgres and SQLite.
 
 pub(crate) mod pg;
+#[cfg(any(feature = "sqlite", test))]
+pub(crate) mod sqlite;
 pub(crate) mod transformer;
 
 mod join_util;
@@ -10,7 +10,8 @@
 mod delete;
 mod insert;
 mod select;
-mod update;
+
+pub(crate) mod update;
 
 mod order_by_transformer;
 mod predicate_transformer;
@@ -9,10 +9,14 @@
 
 use crate::{
     asql::column_path::{ColumnPathLink, RelationLink},
-    sql::predicate::ConcretePredicate,
-    transform::{pg::selection_level::SelectionLevel, transformer::PredicateTransformer},
+    sql::{column::ArrayParamWrapper, predicate::ConcretePredicate, select::Select, table::Table},
+    transform::{
+        pg::selection_level::{SelectionLevel, ALIAS_SEPARATOR},
+        transformer::PredicateTransformer,
+    },
     AbstractPredicate, AbstractSelect, AliasedSelectionElement, Column, ColumnPath, Database,
-    NumericComparator, Selection, SelectionElement, VectorDistanceFunction,
+    Function, NumericComparator, PhysicalColumnPath, Selection, SelectionElement,
+    VectorDistanceFunction,
 };
 
 use super::Postgres;
@@ -26,7 +30,7 @@ impl PredicateTransformer for Postgres {
         database: &Database,
     ) -> ConcretePredicate {
         if tables_supplied {
-            to_join_predicate(predicate, selection_level, database)
+            to_join_predicate(self, predicate, selection_level, database)
         } else {
             to_subselect_predicate(self, predicate, selection_level, database)
         }
@@ -38,12 +42,13 @@ impl PredicateTransformer for Postgres {
 /// The predicate generated will look like "concerts.price = $1 AND venues.name = $2". It assumes
 /// that the join would have brought in "concerts" and "venues" through a join.
 fn to_join_predicate(
+    transformer: &Postgres,
     predicate: &AbstractPredicate,
     selection_level: &SelectionLevel,
     database: &Database,
 ) -> ConcretePredicate {
     let compute_leaf_column =
-        |column_path: &ColumnPath| leaf_column(column_path, selection_level, database);
+        |column_path: &ColumnPath| leaf_column(transformer, column_path, selection_level, database);
 
     match predicate {
         AbstractPredicate::True => ConcretePredicate::True,
@@ -67,6 +72,13 @@ fn to_join_predicate(
         AbstractPredicate::Gte(l, r) => {
             ConcretePredicate::Gte(compute_leaf_column(l), compute_leaf_column(r))
         }
+        AbstractPredicate::In(l, ColumnPath::ArrayParam(param)) => ConcretePredicate::Eq(
+            compute_leaf_column(l),
+            Column::ArrayParam {
+                param: param.clone(),
+                wrapper: ArrayParamWrapper::Any,
+            },
+        ),
         AbstractPredicate::In(l, r) => {
             ConcretePredicate::In(compute_leaf_column(l), compute_leaf_column(r))
         }
@@ -112,16 +124,22 @@ fn to_join_predicate(
         ),
 
         AbstractPredicate::And(l, r) => ConcretePredicate::and(
-            to_join_predicate(l, selection_level, database),
-            to_join_predicate(r, selection_level, database),
+            to_join_predicate(transformer, l, selection_level, database),
+            to_join_predicate(transformer, r, selection_level, database),
         ),
         AbstractPredicate::Or(l, r) => ConcretePredicate::or(
-            to_join_predicate(l, selection_level, database),
-            to_join_predicate(r, selection_level, database),
+            to_join_predicate(transformer, l, selection_level, database),
+            to_join_predicate(transformer, r, selection_level, database),
         ),
-        AbstractPredicate::Not(p) => {
-            ConcretePredicate::Not(Box::new(to_join_predicate(p, selection_level, database)))
-        }
+        AbstractPredicate::Not(p) => ConcretePredicate::Not(Box::new(to_join_predicate(
+            transformer,
+            p,
+            selection_level,
+            database,
+        ))),
+        AbstractPredicate::IsNotTrue(p) => ConcretePredicate::IsNotTrue(Box::new(
+            to_join_predicate(transformer, p, selection_level, database),
+        )),
     }
 }
 
@@ -169,7 +187,7 @@ fn to_subselect_predicate(
                 to_subselect_predicate(transformer, p1, selection_level, database),
                 to_subselect_predicate(transformer, p2, selection_level, database),
             ),
-            _ => to_join_predicate(predicate, selection_level, database),
+            _ => to_join_predicate(transformer, predicate, selection_level, database),
         },
     }
 }
@@ -212,6 +230,7 @@ fn form_subselect(
 }
 
 fn leaf_column(
+    transformer: &Postgres,
     column_path: &ColumnPath,
     selection_level: &SelectionLevel,
     database: &Database,
@@ -224,10 +243,108 @@ fn leaf_column(
             Column::physical(links.leaf_column(), alias)
         }
         ColumnPath::Param(l) => Column::Param(l.clone()),
+        ColumnPath::ArrayParam(l) => Column::ArrayParam {
+            param: l.clone(),
+            wrapper: ArrayParamWrapper::None,
+        },
         ColumnPath::Null => Column::Null,
+        ColumnPath::Arithmetic(left, op, right) => Column::Arithmetic(
+            Box::new(leaf_column(transformer, left, selection_level, database)),
+            *op,
+            Box::new(leaf_column(transformer, right, selection_level, database)),
+        ),
+        ColumnPath::Count(link, predicate) => {
+            count_subselect(transformer, link, predicate, selection_level, database)
+        }
     }
 }
 
+/// A correlated subquery to count the linked rows that satisfy the predicate. For example, the
+/// number of concerts in a venue with price above 100 would be:
+///
+/// ```sql
+/// (SELECT count("concerts$count"."venue_id") FROM "concerts" AS "concerts$count" WHERE "concerts$count"."venue_id" = "venues"."id" AND "concerts$count"."id" IN (SELECT "concerts"."id" FROM "concerts" WHERE "concerts"."price" > $1))
+/// ```
+///
+/// We alias the counted table, so that the correlation refers to the outer row even when the
+/// relation links a table to itself (such as the reports of an employee). Since the predicate
+/// refers to the table by its name, we apply it through a subselect on the primary key.
+fn count_subselect(
+    transformer: &Postgres,
+    link: &RelationLink,
+    predicate: &AbstractPredicate,
+    selection_level: &SelectionLevel,
+    database: &Database,
+) -> Column {
+    let RelationLink {
+        self_column_id,
+        foreign_column_id,
+        ..
+    } = link;
+
+    let foreign_table_id = foreign_column_id.table_id;
+    let alias = format!(
+        "{}{ALIAS_SEPARATOR}count",
+        database
+            .get_table(foreign_table_id)
+            .name
+            .fully_qualified_name_with_sep(ALIAS_SEPARATOR)
+    );
+
+    let correlation = ConcretePredicate::Eq(
+        Column::physical(*foreign_column_id, Some(alias.clone())),
+        leaf_column(
+            transformer,
+            &ColumnPath::Physical(PhysicalColumnPath::leaf(*self_column_id)),
+            selection_level,
+            database,
+        ),
+    );
+
+    let filter = match predicate {
+        AbstractPredicate::True => ConcretePredicate::True,
+        _ => {
+            let pk_column_id = database
+                .get_pk_column_id(foreign_table_id)
+                .expect("Counted table must have a primary key");
+
+            ConcretePredicate::In(
+                Column::physical(pk_column_id, Some(alias.clone())),
+                Column::SubSelect(Box::new(Select {
+                    table: Table::physical(foreign_table_id, None),
+                    columns: vec![Column::physical(pk_column_id, None)],
+                    predicate: to_subselect_predicate(
+                        transformer,
+                        predicate,
+                        &SelectionLevel::TopLevel,
+                        database,
+                    ),
+                    order_by: None,
+                    offset: None,
+                    limit: None,
+                    group_by: None,
+                    top_level_selection: false,
+                })),
+            )
+        }
+    };
+
+    Column::SubSelect(Box::new(Select {
+        table: Table::physical(foreign_table_id, Some(alias.clone())),
+        columns: vec![Column::Function(Function::Named {
+            function_name: "count".to_string(),
+            column_id: *foreign_column_id,
+            table_alias: Some(alias),
+        })],
+        predicate: ConcretePredicate::and(correlation, filter),
+        order_by: None,
+        offset: None,
+        limit: None,
+        group_by: None,
+        top_level_selection: false,
+    }))
+}
+
 fn attempt_subselect_predicate(
     predicate: &AbstractPredicate,
 ) -> Option<(RelationLink, AbstractPredicate)> {
@@ -244,7 +361,40 @@ fn attempt_subselect_predicate(
                     _ => None,
                 }
             }
-            ColumnPath::Param(_) | ColumnPath::Null => None,
+            ColumnPath::Arithmetic(l, op, r) => {
+                // Both sides must go through the same link (or not refer to any table)
+                match (split(l), split(r)) {
+                    (Some((l_link, l_tail)), Some((r_link, r_tail))) => {
+                        (l_link == r_link).then(|| {
+                            (
+                                l_link,
+                                ColumnPath::Arithmetic(Box::new(l_tail), *op, Box::new(r_tail)),
+                            )
+                        })
+                    }
+                    (Some((l_link, l_tail)), None) if is_constant(r) => Some((
+                        l_link,
+                        ColumnPath::Arithmetic(Box::new(l_tail), *op, r.clone()),
+                    )),
+                    (None, Some((r_link, r_tail))) if is_constant(l) => Some((
+                        r_link,
+                        ColumnPath::Arithmetic(l.clone(), *op, Box::new(r_tail)),
+                    )),
+                    _ => None,
+                }
+            }
+            ColumnPath::Param(_)
+            | ColumnPath::ArrayParam(_)
+            | ColumnPath::Null
+            | ColumnPath::Count(..) => None,
+        }
+    }
+
+    fn is_constant(cp: &ColumnPath) -> bool {
+        match cp {
+            ColumnPath::Param(_) | ColumnPath::ArrayParam(_) | ColumnPath::Null => true,
+            ColumnPath::Arithmetic(l, _, r) => is_constant(l) && is_constant(r),
+            ColumnPath::Physical(_) | ColumnPath::Count(..) => false,
         }
     }
 
@@ -417,15 +567,22 @@ fn attempt_subselect_predicate(
         AbstractPredicate::Or(l, r) => logical_binary_op(l, r, AbstractPredicate::Or),
         AbstractPredicate::Not(p) => attempt_subselect_predicate(p)
             .map(|(p_link, p_path)| (p_link, AbstractPredicate::Not(Box::new(p_path)))),
+        AbstractPredicate::IsNotTrue(p) => attempt_subselect_predicate(p)
+            .map(|(p_link, p_path)| (p_link, AbstractPredicate::IsNotTrue(Box::new(p_path)))),
     }
 }
 
 #[cfg(test)]
 mod tests {
     use crate::{
+        schema::{
+            database_spec::DatabaseSpec,
+            table_spec::TableSpec,
+            test_helper::{pk_column, pk_reference_column, string_column},
+        },
         sql::{predicate::CaseSensitivity, ExpressionBuilder, SQLParamContainer},
         transform::{pg::Postgres, test_util::TestSetup},
-        AbstractPredicate, ColumnPath, PhysicalColumnPath,
+        AbstractPredicate, ArithmeticOperator, ColumnPath, PhysicalColumnPath, PhysicalTableName,
     };
 
     use multiplatform_test::multiplatform_test;
@@ -576,6 +733,224 @@ mod tests {
         );
     }
 
+    #[multiplatform_test]
+    fn count_predicate() {
+        TestSetup::with_setup(
+            move |TestSetup {
+                      database,
+                      venues_id_column,
+                      concerts_venue_id_column,
+                      concerts_name_column,
+                      ..
+                  }| {
+                // Venues with fewer than 10 concerts named "v1"
+                let count = ColumnPath::Count(
+                    RelationLink {
+                        self_column_id: venues_id_column,
+                        foreign_column_id: concerts_venue_id_column,
+                        linked_table_alias: None,
+                    },
+                    Box::new(AbstractPredicate::Eq(
+                        ColumnPath::Physical(PhysicalColumnPath::leaf(concerts_name_column)),
+                        ColumnPath::Param(SQLParamContainer::new("v1".to_string())),
+                    )),
+                );
+                let abstract_predicate =
+                    AbstractPredicate::Lt(count, ColumnPath::Param(SQLParamContainer::new(10)));
+
+                for tables_supplied in [true, false] {
+                    let predicate = Postgres {}.to_predicate(
+                        &abstract_predicate,
+                        &SelectionLevel::TopLevel,
+                        tables_supplied,
+                        &database,
+                    );
+
+                    assert_binding!(
+                        predicate.to_sql(&database),
+                        r#"(SELECT count("concerts$count"."venue_id") FROM "concerts" AS "concerts$count" WHERE ("concerts$count"."venue_id" = "venues"."id" AND "concerts$count"."id" IN (SELECT "concerts"."id" FROM "concerts" WHERE "concerts"."name" = $1))) < $2"#,
+                        "v1".to_string(),
+                        10
+                    );
+                }
+            },
+        );
+    }
+
+    #[multiplatform_test]
+    fn every_count_predicate() {
+        TestSetup::with_setup(
+            move |TestSetup {
+                      database,
+                      venues_id_column,
+                      concerts_venue_id_column,
+                      concerts_name_column,
+                      ..
+                  }| {
+                // Venues where every concert is named "v1" (a concert without a name doesn't
+                // qualify, so we count it)
+                let count = ColumnPath::Count(
+                    RelationLink {
+                        self_column_id: venues_id_column,
+                        foreign_column_id: concerts_venue_id_column,
+                        linked_table_alias: None,
+                    },
+                    Box::new(AbstractPredicate::is_not_true(AbstractPredicate::Eq(
+                        ColumnPath::Physical(PhysicalColumnPath::leaf(concerts_name_column)),
+                        ColumnPath::Param(SQLParamContainer::new("v1".to_string())),
+                    ))),
+                );
+                let abstract_predicate =
+                    AbstractPredicate::Eq(count, ColumnPath::Param(SQLParamContainer::new(0)));
+
+                let predicate = Postgres {}.to_predicate(
+                    &abstract_predicate,
+                    &SelectionLevel::TopLevel,
+                    true,
+                    &database,
+                );
+
+                assert_binding!(
+                    predicate.to_sql(&database),
+                    r#"(SELECT count("concerts$count"."venue_id") FROM "concerts" AS "concerts$count" WHERE ("concerts$count"."venue_id" = "venues"."id" AND "concerts$count"."id" IN (SELECT "concerts"."id" FROM "concerts" WHERE NOT COALESCE("concerts"."name" = $1, false)))) = $2"#,
+                    "v1".to_string(),
+                    0
+                );
+            },
+        );
+    }
+
+    #[multiplatform_test]
+    fn self_relation_count_predicate() {
+        let database = DatabaseSpec::new(vec![TableSpec::new(
+            PhysicalTableName::new("employees", None),
+            vec![
+                pk_column("id"),
+                pk_reference_column("manager_id", "employees", None),
+                string_column("name"),
+            ],
+            vec![],
+        )])
+        .to_database();
+
+        let employees_table_id = database
+            .get_table_id(&PhysicalTableName::new("employees", None))
+            .unwrap();
+        let column = |name| database.get_column_id(employees_table_id, name).unwrap();
+
+        let reports = RelationLink {
+            self_column_id: column("id"),
+            foreign_column_id: column("manager_id"),
+            linked_table_alias: None,
+        };
+
+        // Employees with at least two reports
+        let abstract_predicate = AbstractPredicate::Gte(
+            ColumnPath::Count(reports.clone(), Box::new(AbstractPredicate::True)),
+            ColumnPath::Param(SQLParamContainer::new(2)),
+        );
+        let predicate = Postgres {}.to_predicate(
+            &abstract_predicate,
+            &SelectionLevel::TopLevel,
+            true,
+            &database,
+        );
+        assert_binding!(
+            predicate.to_sql(&database),
+            r#"(SELECT count("employees$count"."manager_id") FROM "employees" AS "employees$count" WHERE "employees$count"."manager_id" = "employees"."id") >= $1"#,
+            2
+        );
+
+        // Employees with no reports named "v1"
+        let abstract_predicate = AbstractPredicate::Eq(
+            ColumnPath::Count(
+                reports,
+                Box::new(AbstractPredicate::Eq(
+                    ColumnPath::Physical(PhysicalColumnPath::leaf(column("name"))),
+                    ColumnPath::Param(SQLParamContainer::new("v1".to_string())),
+                )),
+            ),
+            ColumnPath::Param(SQLParamContainer::new(0)),
+        );
+        let predicate = Postgres {}.to_predicate(
+            &abstract_predicate,
+            &SelectionLevel::TopLevel,
+            true,
+            &database,
+        );
+        assert_binding!(
+            predicate.to_sql(&database),
+            r#"(SELECT count("employees$count"."manager_id") FROM "employees" AS "employees$count" WHERE ("employees$count"."manager_id" = "employees"."id" AND "employees$count"."id" IN (SELECT "employees"."id" FROM "employees" WHERE "employees"."name" = $1))) = $2"#,
+            "v1".to_string(),
+            0
+        );
+    }
+
+    #[multiplatform_test]
+    fn in_array_param_predicate() {
+        TestSetup::with_setup(
+            move |TestSetup {
+                      database,
+                      concerts_id_column,
+                      ..
+                  }| {
+                let abstract_predicate = AbstractPredicate::In(
+                    ColumnPath::Physical(PhysicalColumnPath::leaf(concerts_id_column)),
+                    ColumnPath::ArrayParam(SQLParamContainer::new(vec![1, 2])),
+                );
+
+                let predicate = Postgres {}.to_predicate(
+                    &abstract_predicate,
+                    &SelectionLevel::TopLevel,
+                    true,
+                    &database,
+                );
+
+                assert_binding!(
+                    predicate.to_sql(&database),
+                    r#""concerts"."id" = ANY($1)"#,
+                    vec![1, 2]
+                );
+            },
+        );
+    }
+
+    #[multiplatform_test]
+    fn arithmetic_predicate() {
+        TestSetup::with_setup(
+            move |TestSetup {
+                      database,
+                      concerts_id_column,
+                      concerts_venue_id_column,
+                      ..
+                  }| {
+                let abstract_predicate = AbstractPredicate::Gt(
+                    ColumnPath::Arithmetic(
+                        Box::new(ColumnPath::Physical(PhysicalColumnPath::leaf(
+                            concerts_id_column,
+                        ))),
+                        ArithmeticOperator::Subtract,
+                        Box::new(ColumnPath::Param(SQLParamContainer::new(1))),
+                    ),
+                    ColumnPath::Physical(PhysicalColumnPath::leaf(concerts_venue_id_column)),
+                );
+
+                let predicate = Postgres {}.to_predicate(
+                    &abstract_predicate,
+                    &SelectionLevel::TopLevel,
+                    false,
+                    &database,
+                );
+
+                assert_binding!(
+                    predicate.to_sql(&database),
+                    r#"("concerts"."id" - $1) > "concerts"."venue_id""#,
+                    1
+                );
+            },
+        );
+    }
+
     fn test_nested_op_predicate<OP>(op: OP, op_combinator: fn(&str, &str) -> String)
     where
         OP: Clone + Fn(ColumnPath, ColumnPath) -> AbstractPredicate,
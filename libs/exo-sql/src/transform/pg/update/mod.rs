@@ -9,7 +9,9 @@
 
 pub(crate) mod update_transformer;
 
+// Used by the SQLite transformer
+pub(crate) mod multi_statement_strategy;
+pub(crate) mod update_strategy;
+pub(crate) mod update_strategy_chain;
+
 mod cte_strategy;
-mod multi_statement_strategy;
-mod update_strategy;
-mod update_strategy_chain;
@@ -0,0 +1,194 @@
+// Copyright Exograph, Inc. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file at the root of this repository.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use crate::{
+    asql::delete::AbstractDelete,
+    sql::{
+        delete::Delete,
+        predicate::Predicate,
+        select::Select,
+        sql_operation::SQLOperation,
+        transaction::{ConcreteTransactionStep, TransactionScript, TransactionStep},
+    },
+    transform::{
+        pg::{selection_level::SelectionLevel, Postgres},
+        transformer::{DeleteTransformer, PredicateTransformer, SelectTransformer},
+    },
+    Database,
+};
+
+use super::Sqlite;
+
+impl DeleteTransformer for Sqlite {
+    /// Since SQLite doesn't support `WITH ... AS (DELETE ... RETURNING *)` (which is how we get the
+    /// deleted rows for Postgres), we select the rows to be deleted first and then delete them. The
+    /// result of the select is the result of the script.
+    fn update_transaction_script<'a>(
+        &self,
+        abstract_delete: &'a AbstractDelete,
+        database: &'a Database,
+        transaction_script: &mut TransactionScript<'a>,
+    ) {
+        let (select, delete) = to_select_and_delete(abstract_delete, database);
+
+        let select_step_id = transaction_script.add_step(TransactionStep::Concrete(
+            ConcreteTransactionStep::new(SQLOperation::Select(select)),
+        ));
+        let _ = transaction_script.add_step(TransactionStep::Concrete(
+            ConcreteTransactionStep::new(SQLOperation::Delete(delete)),
+        ));
+
+        transaction_script.set_result_step(select_step_id);
+    }
+}
+
+fn to_select_and_delete<'a>(
+    abstract_delete: &'a AbstractDelete,
+    database: &'a Database,
+) -> (Select, Delete<'a>) {
+    let transformer = Postgres {};
+
+    // The predicate is needed for both the select and the delete (and concrete predicates aren't
+    // cloneable), so we form it twice
+    let to_predicate = || {
+        transformer.to_predicate(
+            &abstract_delete.predicate,
+            &SelectionLevel::TopLevel,
+            false,
+            database,
+        )
+    };
+
+    // Same as the insertion, restrict the select to the affected rows by adding the predicate to
+    // the top-level select
+    let select = transformer.to_select(&abstract_delete.selection, database);
+    let select = Select {
+        predicate: Predicate::and(to_predicate(), select.predicate),
+        ..select
+    };
+
+    let delete = database
+        .get_table(abstract_delete.table_id)
+        .delete(to_predicate(), vec![]);
+
+    (select, delete)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        asql::selection::{
+            AliasedSelectionElement, Selection, SelectionCardinality, SelectionElement,
+        },
+        sql::{transaction::TransactionStepId, ExpressionBuilder, SQLDialect, SQLParamContainer},
+        transform::{sqlite::test_util::script_statements, test_util::TestSetup},
+        AbstractPredicate, AbstractSelect, ColumnPath, PhysicalColumnPath,
+    };
+
+    use multiplatform_test::multiplatform_test;
+
+    use super::*;
+
+    #[multiplatform_test]
+    fn non_nested_predicate() {
+        TestSetup::with_setup(
+            |TestSetup {
+                 database,
+                 concerts_table,
+                 concerts_id_column,
+                 concerts_name_column,
+                 ..
+             }| {
+                let predicate = AbstractPredicate::Eq(
+                    ColumnPath::Physical(PhysicalColumnPath::leaf(concerts_name_column)),
+                    ColumnPath::Param(SQLParamContainer::new("v1".to_string())),
+                );
+
+                let adelete = AbstractDelete {
+                    table_id: concerts_table,
+                    selection: AbstractSelect {
+                        table_id: concerts_table,
+                        selection: Selection::Json(
+                            vec![AliasedSelectionElement::new(
+                                "id".to_string(),
+                                SelectionElement::Physical(concerts_id_column),
+                            )],
+                            SelectionCardinality::Many,
+                        ),
+                        predicate: Predicate::True,
+                        order_by: None,
+                        offset: None,
+                        limit: None,
+                    },
+                    predicate,
+                };
+
+                let (select, delete) = to_select_and_delete(&adelete, &database);
+
+                assert_binding!(
+                    select.to_sql_for_dialect(&database, SQLDialect::Sqlite),
+                    r#"SELECT json_group_array(json_object('id', "concerts"."id")) FROM "concerts" WHERE "concerts"."name" = ?1"#,
+                    "v1".to_string()
+                );
+                assert_binding!(
+                    delete.to_sql_for_dialect(&database, SQLDialect::Sqlite),
+                    r#"DELETE FROM "concerts" WHERE "concerts"."name" = ?1"#,
+                    "v1".to_string()
+                );
+            },
+        );
+    }
+
+    #[multiplatform_test]
+    fn select_before_delete() {
+        TestSetup::with_setup(
+            |TestSetup {
+                 database,
+                 concerts_table,
+                 concerts_id_column,
+                 ..
+             }| {
+                let adelete = AbstractDelete {
+                    table_id: concerts_table,
+                    selection: AbstractSelect {
+                        table_id: concerts_table,
+                        selection: Selection::Json(
+                            vec![AliasedSelectionElement::new(
+                                "id".to_string(),
+                                SelectionElement::Physical(concerts_id_column),
+                            )],
+                            SelectionCardinality::Many,
+                        ),
+                        predicate: Predicate::True,
+                        order_by: None,
+                        offset: None,
+                        limit: None,
+                    },
+                    predicate: Predicate::True,
+                };
+
+                let script =
+                    DeleteTransformer::to_transaction_script(&Sqlite {}, &adelete, &database);
+
+                // The rows must be selected before they are deleted, and the script returns the
+                // selected rows (not the empty result of the delete)
+                assert_eq!(script.result_step_id(), Some(TransactionStepId(0)));
+
+                let mut statements = script_statements(script, &database, vec![]).into_iter();
+
+                assert_binding!(
+                    statements.next().unwrap(),
+                    r#"SELECT json_group_array(json_object('id', "concerts"."id")) FROM "concerts""#
+                );
+                assert_binding!(statements.next().unwrap(), r#"DELETE FROM "concerts""#);
+                assert!(statements.next().is_none());
+            },
+        );
+    }
+}
@@ -0,0 +1,147 @@
+// Copyright Exograph, Inc. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file at the root of this repository.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use crate::{
+    sql::transaction::{TransactionScript, TransactionStepId},
+    transform::{pg::Postgres, transformer::InsertTransformer},
+    AbstractInsert, ColumnId, Database,
+};
+
+use super::Sqlite;
+
+impl InsertTransformer for Sqlite {
+    /// The Postgres insertion uses only plain `INSERT ... RETURNING` statements, so we can use it as is.
+    fn update_transaction_script<'a>(
+        &self,
+        abstract_insert: &'a AbstractInsert,
+        parent_step: Option<(TransactionStepId, ColumnId)>,
+        database: &'a Database,
+        transaction_script: &mut TransactionScript<'a>,
+    ) {
+        InsertTransformer::update_transaction_script(
+            &Postgres {},
+            abstract_insert,
+            parent_step,
+            database,
+            transaction_script,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        asql::selection::{
+            AliasedSelectionElement, Selection, SelectionCardinality, SelectionElement,
+        },
+        transform::{sqlite::test_util::*, test_util::TestSetup},
+        AbstractSelect, Column, ColumnValuePair, InsertionElement, InsertionRow, NestedInsertion,
+        Predicate, SQLParamContainer,
+    };
+
+    use multiplatform_test::multiplatform_test;
+
+    use super::*;
+
+    #[multiplatform_test]
+    fn nested_insert() {
+        TestSetup::with_setup(
+            |TestSetup {
+                 database,
+                 venues_table,
+                 venues_id_column,
+                 venues_name_column,
+                 concerts_name_column,
+                 concerts_venue_id_column,
+                 ..
+             }| {
+                let name = |column_id, name: &str| {
+                    InsertionElement::SelfInsert(ColumnValuePair::new(
+                        column_id,
+                        Column::Param(SQLParamContainer::new(name.to_string())),
+                    ))
+                };
+
+                let ainsert = AbstractInsert {
+                    table_id: venues_table,
+                    rows: vec![
+                        InsertionRow {
+                            elems: vec![
+                                name(venues_name_column, "v1"),
+                                InsertionElement::NestedInsert(NestedInsertion {
+                                    relation_id: concerts_venue_id_column
+                                        .get_otm_relation(&database)
+                                        .unwrap(),
+                                    insertions: vec![InsertionRow {
+                                        elems: vec![name(concerts_name_column, "c1")],
+                                    }],
+                                }),
+                            ],
+                        },
+                        InsertionRow {
+                            elems: vec![name(venues_name_column, "v2")],
+                        },
+                    ],
+                    selection: AbstractSelect {
+                        table_id: venues_table,
+                        selection: Selection::Json(
+                            vec![AliasedSelectionElement::new(
+                                "id".to_string(),
+                                SelectionElement::Physical(venues_id_column),
+                            )],
+                            SelectionCardinality::Many,
+                        ),
+                        predicate: Predicate::True,
+                        order_by: None,
+                        offset: None,
+                        limit: None,
+                    },
+                };
+
+                let script =
+                    InsertTransformer::to_transaction_script(&Sqlite {}, &ainsert, None, &database);
+
+                // The ids returned by the two venue inserts and the concert insert
+                let mut statements = script_statements(
+                    script,
+                    &database,
+                    vec![
+                        vec![vec![int_value(1)]],
+                        vec![vec![int_value(10)]],
+                        vec![vec![int_value(2)]],
+                    ],
+                )
+                .into_iter();
+
+                assert_binding!(
+                    statements.next().unwrap(),
+                    r#"INSERT INTO "venues" ("name") VALUES (?1) RETURNING "venues"."id""#,
+                    "v1".to_string()
+                );
+                assert_binding!(
+                    statements.next().unwrap(),
+                    r#"INSERT INTO "concerts" ("name", "venue_id") VALUES (?1, ?2) RETURNING "concerts"."id""#,
+                    "c1".to_string(),
+                    int_value(1)
+                );
+                assert_binding!(
+                    statements.next().unwrap(),
+                    r#"INSERT INTO "venues" ("name") VALUES (?1) RETURNING "venues"."id""#,
+                    "v2".to_string()
+                );
+                assert_binding!(
+                    statements.next().unwrap(),
+                    r#"SELECT json_group_array(json_object('id', "venues"."id")) FROM "venues" WHERE "venues"."id" IN (SELECT value FROM json_each(?1))"#,
+                    vec![int_value(1), int_value(2)]
+                );
+                assert!(statements.next().is_none());
+            },
+        );
+    }
+}
@@ -0,0 +1,56 @@
+// Copyright Exograph, Inc. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file at the root of this repository.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! Transformation for SQLite.
+//!
+//! The SQL tree formed for selections is the same as for Postgres (the SQLite-specific syntax, such
+//! as `json_object` in place of `json_build_object`, is handled when building the SQL with
+//! [`SQLDialect::Sqlite`](crate::sql::SQLDialect::Sqlite)), so we delegate to the Postgres
+//! transformer wherever possible. The key difference is that SQLite doesn't support
+//! data-modifying statements in CTEs, so mutations always use the multi-statement strategies.
+
+mod delete_transformer;
+mod insert_transformer;
+mod select_transformer;
+mod test_util;
+mod update_transformer;
+
+use crate::{
+    asql::abstract_operation::AbstractOperation, sql::transaction::TransactionScript, Database,
+};
+
+use super::transformer::{
+    DeleteTransformer, InsertTransformer, OperationTransformer, SelectTransformer,
+    UpdateTransformer,
+};
+
+pub struct Sqlite {}
+
+impl OperationTransformer for Sqlite {
+    fn to_transaction_script<'a>(
+        &self,
+        database: &'a Database,
+        abstract_operation: &'a AbstractOperation,
+    ) -> TransactionScript<'a> {
+        match abstract_operation {
+            AbstractOperation::Select(select) => {
+                SelectTransformer::to_transaction_script(self, select, database)
+            }
+            AbstractOperation::Delete(delete) => {
+                DeleteTransformer::to_transaction_script(self, delete, database)
+            }
+            AbstractOperation::Insert(insert) => {
+                InsertTransformer::to_transaction_script(self, insert, None, database)
+            }
+            AbstractOperation::Update(update) => {
+                UpdateTransformer::to_transaction_script(self, update, database)
+            }
+        }
+    }
+}
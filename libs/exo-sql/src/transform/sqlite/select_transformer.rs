@@ -0,0 +1,149 @@
+// Copyright Exograph, Inc. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file at the root of this repository.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use crate::{
+    asql::select::AbstractSelect,
+    sql::{select::Select, transaction::TransactionScript},
+    transform::{pg::Postgres, transformer::SelectTransformer},
+    Database,
+};
+
+use super::Sqlite;
+
+impl SelectTransformer for Sqlite {
+    fn to_select(&self, abstract_select: &AbstractSelect, database: &Database) -> Select {
+        Postgres {}.to_select(abstract_select, database)
+    }
+
+    fn to_transaction_script<'a>(
+        &self,
+        abstract_select: &'a AbstractSelect,
+        database: &'a Database,
+    ) -> TransactionScript<'a> {
+        SelectTransformer::to_transaction_script(&Postgres {}, abstract_select, database)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        asql::{
+            column_path::{ColumnPath, PhysicalColumnPath},
+            predicate::AbstractPredicate,
+            selection::{
+                AliasedSelectionElement, Selection, SelectionCardinality, SelectionElement,
+            },
+        },
+        sql::{predicate::Predicate, ExpressionBuilder, SQLDialect, SQLParamContainer},
+        transform::test_util::TestSetup,
+        CaseSensitivity, RelationId,
+    };
+
+    use super::*;
+
+    use multiplatform_test::multiplatform_test;
+
+    #[multiplatform_test]
+    fn nested_many_to_one_json() {
+        TestSetup::with_setup(
+            |TestSetup {
+                 database,
+                 concerts_table,
+                 venues_table,
+                 concerts_id_column,
+                 venues_id_column,
+                 concerts_venue_id_column,
+                 ..
+             }| {
+                let aselect = AbstractSelect {
+                    table_id: concerts_table,
+                    selection: Selection::Json(
+                        vec![
+                            AliasedSelectionElement::new(
+                                "id".to_string(),
+                                SelectionElement::Physical(concerts_id_column),
+                            ),
+                            AliasedSelectionElement::new(
+                                "venue".to_string(),
+                                SelectionElement::SubSelect(
+                                    RelationId::OneToMany(
+                                        concerts_venue_id_column
+                                            .get_otm_relation(&database)
+                                            .unwrap(),
+                                    ),
+                                    AbstractSelect {
+                                        table_id: venues_table,
+                                        selection: Selection::Json(
+                                            vec![AliasedSelectionElement::new(
+                                                "id".to_string(),
+                                                SelectionElement::Physical(venues_id_column),
+                                            )],
+                                            SelectionCardinality::One,
+                                        ),
+                                        predicate: Predicate::True,
+                                        order_by: None,
+                                        offset: None,
+                                        limit: None,
+                                    },
+                                ),
+                            ),
+                        ],
+                        SelectionCardinality::Many,
+                    ),
+                    predicate: Predicate::True,
+                    order_by: None,
+                    offset: None,
+                    limit: None,
+                };
+
+                let select = Sqlite {}.to_select(&aselect, &database);
+                assert_binding!(
+                    select.to_sql_for_dialect(&database, SQLDialect::Sqlite),
+                    r#"SELECT json_group_array(json_object('id', "concerts"."id", 'venue', json((SELECT json_object('id', "venues"."id") FROM "venues" WHERE "venues"."id" = "concerts"."venue_id")))) FROM "concerts""#
+                );
+            },
+        );
+    }
+
+    #[multiplatform_test]
+    fn case_insensitive_like() {
+        TestSetup::with_setup(
+            |TestSetup {
+                 database,
+                 concerts_table,
+                 concerts_id_column,
+                 concerts_name_column,
+                 ..
+             }| {
+                let aselect = AbstractSelect {
+                    table_id: concerts_table,
+                    selection: Selection::Seq(vec![AliasedSelectionElement::new(
+                        "id".to_string(),
+                        SelectionElement::Physical(concerts_id_column),
+                    )]),
+                    predicate: AbstractPredicate::StringLike(
+                        ColumnPath::Physical(PhysicalColumnPath::leaf(concerts_name_column)),
+                        ColumnPath::Param(SQLParamContainer::new("%v1%".to_string())),
+                        CaseSensitivity::Insensitive,
+                    ),
+                    order_by: None,
+                    offset: None,
+                    limit: None,
+                };
+
+                let select = Sqlite {}.to_select(&aselect, &database);
+                assert_binding!(
+                    select.to_sql_for_dialect(&database, SQLDialect::Sqlite),
+                    r#"SELECT "concerts"."id" FROM "concerts" WHERE lower("concerts"."name") LIKE lower(?1)"#,
+                    "%v1%".to_string()
+                );
+            },
+        );
+    }
+}
@@ -0,0 +1,67 @@
+// Copyright Exograph, Inc. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file at the root of this repository.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+#![cfg(test)]
+
+use std::sync::Arc;
+
+use tokio_postgres::types::Type;
+
+use crate::{
+    sql::{
+        sql_operation::SQLOperation,
+        transaction::{TransactionContext, TransactionScript, TransactionStep},
+        ExpressionBuilder, SQLDialect, SQLParam, SQLValue,
+    },
+    Database,
+};
+
+/// The SQLite statements (along with their parameters) of a transaction script. The steps that
+/// depend on earlier steps are resolved as if each step returned the corresponding entry in
+/// `step_rows`.
+pub fn script_statements(
+    script: TransactionScript,
+    database: &Database,
+    step_rows: Vec<Vec<Vec<SQLValue>>>,
+) -> Vec<(String, Vec<Arc<dyn SQLParam>>)> {
+    let mut transaction_context = TransactionContext::new();
+    for rows in step_rows {
+        transaction_context.push_values(rows);
+    }
+
+    let to_sql =
+        |operation: &SQLOperation| operation.to_sql_for_dialect(database, SQLDialect::Sqlite);
+
+    script
+        .into_steps()
+        .into_iter()
+        .flat_map(|step| match step {
+            TransactionStep::Concrete(step) => vec![to_sql(&step.operation)],
+            TransactionStep::Template(step) => step
+                .resolve(&transaction_context)
+                .iter()
+                .map(|step| to_sql(&step.operation))
+                .collect(),
+            TransactionStep::Filter(step) => {
+                vec![to_sql(
+                    &step.resolve(&transaction_context, database).operation,
+                )]
+            }
+            TransactionStep::Dynamic(step) => {
+                vec![to_sql(&step.resolve(&transaction_context).operation)]
+            }
+        })
+        .collect()
+}
+
+/// The value of an integer primary key as returned by a step (see the SQLite connection for how
+/// the returned values are converted)
+pub fn int_value(value: i64) -> SQLValue {
+    SQLValue::new(value.to_be_bytes().to_vec(), Type::INT8)
+}
@@ -0,0 +1,155 @@
+// Copyright Exograph, Inc. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file at the root of this repository.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use crate::{
+    sql::transaction::TransactionScript,
+    transform::{
+        pg::{
+            update::{
+                multi_statement_strategy::MultiStatementStrategy,
+                update_strategy_chain::UpdateStrategyChain,
+            },
+            Postgres,
+        },
+        transformer::UpdateTransformer,
+    },
+    AbstractUpdate, Database,
+};
+
+use super::Sqlite;
+
+impl UpdateTransformer for Sqlite {
+    /// Use only the multi-statement strategy (the CTE strategy relies on `WITH ... AS (UPDATE
+    /// ...)`, which SQLite doesn't support).
+    fn update_transaction_script<'a>(
+        &self,
+        abstract_update: &'a AbstractUpdate,
+        database: &'a Database,
+        transaction_script: &mut TransactionScript<'a>,
+    ) {
+        let chain = UpdateStrategyChain::new(vec![&MultiStatementStrategy {}]);
+
+        chain.update_transaction_script(
+            abstract_update,
+            database,
+            &Postgres {},
+            transaction_script,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        asql::selection::{
+            AliasedSelectionElement, Selection, SelectionCardinality, SelectionElement,
+        },
+        transform::{sqlite::test_util::*, test_util::TestSetup},
+        AbstractDelete, AbstractPredicate, AbstractSelect, Column, ColumnPath,
+        NestedAbstractDelete, PhysicalColumnPath, Predicate, SQLParamContainer,
+    };
+
+    use multiplatform_test::multiplatform_test;
+
+    use super::*;
+
+    #[multiplatform_test]
+    fn nested_delete() {
+        TestSetup::with_setup(
+            |TestSetup {
+                 database,
+                 venues_table,
+                 venues_id_column,
+                 venues_name_column,
+                 concerts_table,
+                 concerts_name_column,
+                 concerts_venue_id_column,
+                 ..
+             }| {
+                let nested_delete = NestedAbstractDelete {
+                    nesting_relation: concerts_venue_id_column
+                        .get_otm_relation(&database)
+                        .unwrap()
+                        .deref(&database),
+                    delete: AbstractDelete {
+                        table_id: concerts_table,
+                        predicate: AbstractPredicate::eq(
+                            ColumnPath::Physical(PhysicalColumnPath::leaf(concerts_name_column)),
+                            ColumnPath::Param(SQLParamContainer::new("c1".to_string())),
+                        ),
+                        selection: AbstractSelect {
+                            table_id: concerts_table,
+                            selection: Selection::Seq(vec![]),
+                            predicate: Predicate::True,
+                            order_by: None,
+                            offset: None,
+                            limit: None,
+                        },
+                    },
+                };
+
+                let aupdate = AbstractUpdate {
+                    table_id: venues_table,
+                    predicate: AbstractPredicate::eq(
+                        ColumnPath::Physical(PhysicalColumnPath::leaf(venues_id_column)),
+                        ColumnPath::Param(SQLParamContainer::new(5)),
+                    ),
+                    column_values: vec![(
+                        venues_name_column,
+                        Column::Param(SQLParamContainer::new("new_name".to_string())),
+                    )],
+                    nested_updates: vec![],
+                    nested_inserts: vec![],
+                    nested_deletes: vec![nested_delete],
+                    selection: AbstractSelect {
+                        table_id: venues_table,
+                        selection: Selection::Json(
+                            vec![AliasedSelectionElement::new(
+                                "id".to_string(),
+                                SelectionElement::Physical(venues_id_column),
+                            )],
+                            SelectionCardinality::Many,
+                        ),
+                        predicate: Predicate::True,
+                        order_by: None,
+                        offset: None,
+                        limit: None,
+                    },
+                };
+
+                let script =
+                    UpdateTransformer::to_transaction_script(&Sqlite {}, &aupdate, &database);
+
+                // The id returned by the root update (the nested delete returns no rows)
+                let mut statements =
+                    script_statements(script, &database, vec![vec![vec![int_value(5)]], vec![]])
+                        .into_iter();
+
+                assert_binding!(
+                    statements.next().unwrap(),
+                    r#"UPDATE "venues" SET "name" = ?1 WHERE "venues"."id" = ?2 RETURNING "venues"."id""#,
+                    "new_name".to_string(),
+                    5
+                );
+                assert_binding!(
+                    statements.next().unwrap(),
+                    r#"DELETE FROM "concerts" WHERE "concerts"."name" = ?1 AND "concerts"."venue_id" = ?2"#,
+                    "c1".to_string(),
+                    int_value(5)
+                );
+                assert_binding!(
+                    statements.next().unwrap(),
+                    r#"SELECT json_group_array(json_object('id', "venues"."id")) FROM "venues" WHERE "venues"."id" IN (SELECT value FROM json_each(?1))"#,
+                    vec![int_value(5)]
+                );
+                assert!(statements.next().is_none());
+            },
+        );
+    }
+}